@@ -0,0 +1,623 @@
+//! Core filesystem operations of rapfiles, without PyO3.
+//!
+//! The `rapfiles` Python extension runs its operations on the runtime and
+//! backends defined here. Rust code linked into the same binary (e.g. another
+//! PyO3 extension built together with rapfiles, or a Rust application that
+//! embeds Python) can use them directly instead of starting its own runtime:
+//!
+//! - [`runtime`] returns the shared multi-threaded Tokio runtime, sized with
+//!   [`configure_runtime`] before its first use
+//! - [`set_default_backend`] and [`with_backend`] select the I/O backend, the
+//!   same setting `rapfiles.set_backend()` and `rapfiles.use_backend()` change
+//! - the operations ([`read`], [`write`], [`list_dir`], ...) are plain `async`
+//!   functions returning `std::io::Result`
+//!
+//! The operations only need a Tokio runtime with the blocking pool enabled.
+//! They run on whatever runtime polls them, so callers already inside their
+//! own runtime can await them there; nothing creates a nested runtime.
+//!
+//! ```
+//! let dir = std::env::temp_dir().join("rapfiles-core-doc");
+//! let file = dir.join("hello.txt");
+//! rapfiles_core::runtime().block_on(async {
+//!     rapfiles_core::create_dir_all(&dir).await?;
+//!     rapfiles_core::write(&file, b"hello").await?;
+//!     assert_eq!(rapfiles_core::read(&file).await?, b"hello");
+//!     rapfiles_core::remove_dir_all(&dir).await
+//! })?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicPtr, AtomicU8, Ordering};
+use std::sync::Mutex;
+use tokio::io::AsyncWriteExt;
+use tokio::runtime::Runtime;
+
+// Runtime
+
+/// Thread pool sizes of the shared runtime.
+///
+/// `None` keeps Tokio's defaults: one worker thread per CPU and up to 512
+/// blocking threads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    pub worker_threads: Option<usize>,
+    pub max_blocking_threads: Option<usize>,
+}
+
+/// A runtime and the ID of the process that built it.
+struct BuiltRuntime {
+    pid: u32,
+    runtime: Runtime,
+}
+
+/// The runtime last built, null until the first `runtime()` call. Read
+/// without locking; the runtimes it points to are leaked, never freed.
+static RUNTIME: AtomicPtr<BuiltRuntime> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Configuration of the runtime, and whether it has been built with it.
+/// Also held while the runtime is built, so only one thread builds it.
+static RUNTIME_CONFIG: Mutex<(RuntimeConfig, bool)> = Mutex::new((
+    RuntimeConfig {
+        worker_threads: None,
+        max_blocking_threads: None,
+    },
+    false,
+));
+
+/// Return the shared Tokio runtime, building it on first use.
+///
+/// The runtime is multi-threaded with all drivers enabled. The `rapfiles`
+/// extension drives its Python coroutines on it, so work spawned here shares
+/// the worker and blocking threads of rapfiles' own operations.
+///
+/// A child process created with `fork()` inherits the runtime but none of
+/// its threads, so the first call in the child builds a new runtime with
+/// the same configuration. The parent's runtime is leaked there: dropping it
+/// would wait for threads that do not exist. Tasks and handles belonging to
+/// the parent's runtime never complete in the child.
+///
+/// # Panics
+///
+/// Panics if the runtime cannot be built (e.g. the OS refuses to create
+/// threads).
+pub fn runtime() -> &'static Runtime {
+    let pid = std::process::id();
+    if let Some(runtime) = built_runtime(pid) {
+        return runtime;
+    }
+    let mut state = RUNTIME_CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+    // Built by another thread while this one waited for the lock
+    if let Some(runtime) = built_runtime(pid) {
+        return runtime;
+    }
+    let (config, started) = &mut *state;
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    if let Some(threads) = config.worker_threads {
+        builder.worker_threads(threads);
+    }
+    if let Some(threads) = config.max_blocking_threads {
+        builder.max_blocking_threads(threads);
+    }
+    *started = true;
+    let built: &'static BuiltRuntime = Box::leak(Box::new(BuiltRuntime {
+        pid,
+        runtime: builder.build().expect("Unable to build Tokio runtime"),
+    }));
+    RUNTIME.store(std::ptr::from_ref(built).cast_mut(), Ordering::Release);
+    &built.runtime
+}
+
+/// Return the runtime if it was built by process `pid`.
+fn built_runtime(pid: u32) -> Option<&'static Runtime> {
+    // SAFETY: `RUNTIME` only holds null or pointers leaked by `runtime()`
+    let built = unsafe { RUNTIME.load(Ordering::Acquire).as_ref() }?;
+    (built.pid == pid).then_some(&built.runtime)
+}
+
+/// Set the thread pool sizes the runtime is built with.
+///
+/// # Returns
+///
+/// `false` (leaving the configuration unchanged) if the runtime has already
+/// been built.
+///
+/// # Panics
+///
+/// `runtime()` panics later if a size is 0.
+pub fn configure_runtime(config: RuntimeConfig) -> bool {
+    let mut state = RUNTIME_CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+    if state.1 {
+        return false;
+    }
+    state.0 = config;
+    true
+}
+
+/// Return the configuration the runtime is (or will be) built with.
+pub fn runtime_config() -> RuntimeConfig {
+    RUNTIME_CONFIG.lock().unwrap_or_else(|e| e.into_inner()).0
+}
+
+/// Return whether the runtime has been built.
+pub fn runtime_started() -> bool {
+    RUNTIME_CONFIG.lock().unwrap_or_else(|e| e.into_inner()).1
+}
+
+/// Run a blocking closure on the blocking thread pool of the current runtime.
+///
+/// # Errors
+///
+/// Returns the closure's error, or an error if the blocking task panicked or
+/// was cancelled.
+pub async fn run_blocking<T, F>(f: F) -> io::Result<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(io::Error::other)?
+}
+
+// I/O backends
+
+/// Strategy used to perform blocking filesystem calls.
+///
+/// `Tokio` uses `tokio::fs`, whose file handles buffer writes and complete
+/// them in the background. `ThreadPool` runs each operation as plain
+/// `std::fs` calls inside a single `spawn_blocking` job, which behaves
+/// better on filesystems that dislike Tokio's access pattern (e.g. some
+/// FUSE mounts).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Tokio,
+    ThreadPool,
+}
+
+impl Backend {
+    /// All backends, in the order of their numeric values.
+    pub const ALL: [Backend; 2] = [Backend::Tokio, Backend::ThreadPool];
+
+    /// Name of the backend as used by the Python API ("tokio", "threadpool").
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Tokio => "tokio",
+            Backend::ThreadPool => "threadpool",
+        }
+    }
+
+    /// Look up a backend by name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|backend| backend.name() == name)
+    }
+
+    /// Look up a backend by its numeric value, falling back to `Tokio`.
+    pub fn from_index(index: u8) -> Self {
+        Self::ALL
+            .get(index as usize)
+            .copied()
+            .unwrap_or(Backend::Tokio)
+    }
+}
+
+/// Backend used outside `with_backend`, as an index into `Backend::ALL`.
+static DEFAULT_BACKEND: AtomicU8 = AtomicU8::new(0);
+
+tokio::task_local! {
+    /// Backend selected for the current task by `with_backend`.
+    static TASK_BACKEND: Backend;
+}
+
+/// Set the backend used by operations outside `with_backend`.
+pub fn set_default_backend(backend: Backend) {
+    DEFAULT_BACKEND.store(backend as u8, Ordering::Relaxed);
+}
+
+/// Return the backend used by operations outside `with_backend`.
+pub fn default_backend() -> Backend {
+    Backend::from_index(DEFAULT_BACKEND.load(Ordering::Relaxed))
+}
+
+/// Return the backend of the current task (the default outside `with_backend`).
+pub fn current_backend() -> Backend {
+    TASK_BACKEND
+        .try_with(|backend| *backend)
+        .unwrap_or_else(|_| default_backend())
+}
+
+/// Run `future` with `backend` selected for the operations it awaits.
+pub async fn with_backend<F: std::future::Future>(backend: Backend, future: F) -> F::Output {
+    TASK_BACKEND.scope(backend, future).await
+}
+
+// File operations
+
+/// Read the entire contents of a file.
+///
+/// # Errors
+///
+/// Returns the error of opening or reading the file.
+pub async fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let path = path.as_ref().to_owned();
+    match current_backend() {
+        Backend::Tokio => tokio::fs::read(path).await,
+        Backend::ThreadPool => run_blocking(move || std::fs::read(path)).await,
+    }
+}
+
+/// Read the entire contents of a file as UTF-8 text.
+///
+/// # Errors
+///
+/// Returns the error of opening or reading the file, or an `InvalidData`
+/// error if it is not valid UTF-8.
+pub async fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref().to_owned();
+    match current_backend() {
+        Backend::Tokio => tokio::fs::read_to_string(path).await,
+        Backend::ThreadPool => run_blocking(move || std::fs::read_to_string(path)).await,
+    }
+}
+
+/// Write `contents` to a file, creating or truncating it.
+///
+/// # Errors
+///
+/// Returns the error of creating or writing the file.
+pub async fn write(path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> io::Result<()> {
+    let (path, contents) = (path.as_ref().to_owned(), contents.into());
+    match current_backend() {
+        Backend::Tokio => tokio::fs::write(path, contents).await,
+        Backend::ThreadPool => run_blocking(move || std::fs::write(path, contents)).await,
+    }
+}
+
+/// Append `contents` to a file, creating it if it doesn't exist.
+///
+/// # Errors
+///
+/// Returns the error of opening or writing the file.
+pub async fn append(path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> io::Result<()> {
+    let (path, contents) = (path.as_ref().to_owned(), contents.into());
+    match current_backend() {
+        Backend::Tokio => {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            file.write_all(&contents).await?;
+            file.flush().await
+        }
+        Backend::ThreadPool => {
+            run_blocking(move || {
+                use std::io::Write;
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?
+                    .write_all(&contents)
+            })
+            .await
+        }
+    }
+}
+
+/// Copy a file, returning the number of bytes copied.
+///
+/// # Errors
+///
+/// Returns the error of reading the source or writing the destination.
+pub async fn copy(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<u64> {
+    let (src, dst) = (src.as_ref().to_owned(), dst.as_ref().to_owned());
+    match current_backend() {
+        Backend::Tokio => tokio::fs::copy(src, dst).await,
+        Backend::ThreadPool => run_blocking(move || std::fs::copy(src, dst)).await,
+    }
+}
+
+/// Rename a file or directory, replacing `dst` if it is a file.
+///
+/// # Errors
+///
+/// Returns the error of the rename (e.g. across filesystems).
+pub async fn rename(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<()> {
+    let (src, dst) = (src.as_ref().to_owned(), dst.as_ref().to_owned());
+    match current_backend() {
+        Backend::Tokio => tokio::fs::rename(src, dst).await,
+        Backend::ThreadPool => run_blocking(move || std::fs::rename(src, dst)).await,
+    }
+}
+
+/// Remove a file.
+///
+/// # Errors
+///
+/// Returns the error of the removal.
+pub async fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref().to_owned();
+    match current_backend() {
+        Backend::Tokio => tokio::fs::remove_file(path).await,
+        Backend::ThreadPool => run_blocking(move || std::fs::remove_file(path)).await,
+    }
+}
+
+/// Return the metadata of a path, following symlinks.
+///
+/// # Errors
+///
+/// Returns the error of the `stat` call.
+pub async fn metadata(path: impl AsRef<Path>) -> io::Result<std::fs::Metadata> {
+    let path = path.as_ref().to_owned();
+    match current_backend() {
+        Backend::Tokio => tokio::fs::metadata(path).await,
+        Backend::ThreadPool => run_blocking(move || std::fs::metadata(path)).await,
+    }
+}
+
+// Directory operations
+
+/// Create a directory and any missing parents.
+///
+/// # Errors
+///
+/// Returns the error of creating a directory.
+pub async fn create_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref().to_owned();
+    match current_backend() {
+        Backend::Tokio => tokio::fs::create_dir_all(path).await,
+        Backend::ThreadPool => run_blocking(move || std::fs::create_dir_all(path)).await,
+    }
+}
+
+/// Remove a directory and all of its contents.
+///
+/// # Errors
+///
+/// Returns the first error of removing an entry.
+pub async fn remove_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref().to_owned();
+    match current_backend() {
+        Backend::Tokio => tokio::fs::remove_dir_all(path).await,
+        Backend::ThreadPool => run_blocking(move || std::fs::remove_dir_all(path)).await,
+    }
+}
+
+/// List the names of the entries of a directory, in filesystem order.
+///
+/// The listing runs as a single blocking job. Names that aren't valid UTF-8
+/// are skipped.
+///
+/// # Errors
+///
+/// Returns the error of reading the directory, with the path in the message.
+pub async fn list_dir(path: impl AsRef<Path>) -> io::Result<Vec<String>> {
+    let path = path.as_ref().to_owned();
+    run_blocking(move || {
+        let display = path.display();
+        let entries = std::fs::read_dir(&path).map_err(|e| {
+            io::Error::new(e.kind(), format!("Failed to read directory {display}: {e}"))
+        })?;
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Failed to read directory entry in {display}: {e}"),
+                )
+            })?;
+            // Reuse the name's buffer; names that aren't valid UTF-8 are skipped
+            if let Ok(name) = entry.file_name().into_string() {
+                names.push(name);
+            }
+        }
+        Ok(names)
+    })
+    .await
+}
+
+/// Recursively list a directory tree as `(path, is_file)` pairs.
+///
+/// The walk runs as a single blocking job, in no particular order. Entries
+/// are classified without following symlinks; directories that cannot be
+/// read are skipped.
+///
+/// # Errors
+///
+/// Returns the error of reading an entry of a directory that was opened.
+pub async fn walk_dir(path: impl AsRef<Path>) -> io::Result<Vec<(PathBuf, bool)>> {
+    let root = path.as_ref().to_owned();
+    run_blocking(move || {
+        let mut results = Vec::new();
+
+        // Use a stack to traverse directories
+        let mut stack = vec![root];
+
+        while let Some(current) = stack.pop() {
+            let entries = match std::fs::read_dir(&current) {
+                Ok(entries) => entries,
+                Err(_e) => {
+                    // Skip directories we can't read
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                let entry = entry.map_err(|e| {
+                    io::Error::new(
+                        e.kind(),
+                        format!(
+                            "Failed to read directory entry in {}: {e}",
+                            current.display()
+                        ),
+                    )
+                })?;
+
+                // Like lstat, the entry type doesn't follow symlinks, and most
+                // filesystems report it without an extra syscall
+                let file_type = match entry.file_type() {
+                    Ok(t) => t,
+                    Err(_) => continue, // Skip entries we can't get the type of
+                };
+
+                let path = entry.path();
+                // Add subdirectories to the stack for traversal
+                if file_type.is_dir() {
+                    stack.push(path.clone());
+                }
+                results.push((path, file_type.is_file()));
+            }
+        }
+        Ok(results)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `future` on a fresh current-thread runtime, leaving the shared one
+    /// alone.
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// Return an empty scratch directory unique to this process and test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rapfiles-core-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn with_backend_selects_backend_for_task() {
+        block_on(async {
+            let outside = current_backend();
+            assert_eq!(outside, default_backend());
+            for backend in Backend::ALL {
+                let (selected, nested) = with_backend(backend, async {
+                    let nested =
+                        with_backend(Backend::ThreadPool, async { current_backend() }).await;
+                    (current_backend(), nested)
+                })
+                .await;
+                assert_eq!(selected, backend);
+                assert_eq!(nested, Backend::ThreadPool);
+            }
+            assert_eq!(current_backend(), outside);
+        });
+    }
+
+    #[test]
+    fn operations_run_on_each_backend() {
+        let dir = scratch_dir("backends");
+        block_on(async {
+            for backend in Backend::ALL {
+                let file = dir.join(backend.name());
+                with_backend(backend, async {
+                    write(&file, b"one").await?;
+                    append(&file, b" two").await?;
+                    assert_eq!(read_to_string(&file).await?, "one two");
+                    remove_file(&file).await
+                })
+                .await
+                .unwrap();
+            }
+        });
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn backend_names_round_trip() {
+        for backend in Backend::ALL {
+            assert_eq!(Backend::from_name(backend.name()), Some(backend));
+            assert_eq!(Backend::from_index(backend as u8), backend);
+        }
+        assert_eq!(Backend::from_name("io_uring"), None);
+        assert_eq!(Backend::from_index(u8::MAX), Backend::Tokio);
+    }
+
+    #[test]
+    fn runtime_is_built_once() {
+        let threads: Vec<_> = (0..8)
+            .map(|_| std::thread::spawn(|| std::ptr::from_ref(runtime()) as usize))
+            .collect();
+        let runtimes: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert!(runtimes
+            .iter()
+            .all(|&built| built == std::ptr::from_ref(runtime()) as usize));
+    }
+
+    #[test]
+    fn configure_runtime_fails_once_started() {
+        runtime();
+        assert!(runtime_started());
+        let config = runtime_config();
+        let changed = RuntimeConfig {
+            worker_threads: Some(config.worker_threads.unwrap_or(1) + 1),
+            max_blocking_threads: Some(1),
+        };
+        assert!(!configure_runtime(changed));
+        assert_eq!(runtime_config(), config);
+    }
+
+    #[test]
+    fn list_dir_reports_missing_path() {
+        let dir = scratch_dir("list-missing");
+        let missing = dir.join("missing");
+        let err = block_on(list_dir(&missing)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().contains(&missing.display().to_string()));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn list_dir_reports_file() {
+        let dir = scratch_dir("list-file");
+        let file = dir.join("file.txt");
+        std::fs::write(&file, b"data").unwrap();
+        let err = block_on(list_dir(&file)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotADirectory);
+        assert!(err.to_string().starts_with("Failed to read directory"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn walk_dir_skips_unreadable_roots() {
+        let dir = scratch_dir("walk-errors");
+        let file = dir.join("file.txt");
+        std::fs::write(&file, b"data").unwrap();
+        assert!(block_on(walk_dir(dir.join("missing"))).unwrap().is_empty());
+        assert!(block_on(walk_dir(&file)).unwrap().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn walk_dir_lists_tree() {
+        let dir = scratch_dir("walk-tree");
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("file.txt"), b"data").unwrap();
+        let mut entries = block_on(walk_dir(&dir)).unwrap();
+        entries.sort();
+        assert_eq!(
+            entries,
+            [
+                (dir.join("sub"), false),
+                (dir.join("sub").join("file.txt"), true),
+            ]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -5,16 +5,28 @@ from typing import (
     Optional,
     Union,
     Any,
-    Coroutine,
+    Awaitable,
     TypeVar,
+    Generic,
     TYPE_CHECKING,
     Tuple,
     Type,
     Dict,
+    Iterator,
+    Iterable,
+    Callable,
 )
 from types import TracebackType
+import atexit
+import contextlib
+import os
+
+# Other modules are imported where they are used, so that importing
+# rapfiles stays cheap for short-lived scripts that barely use it
 
 if TYPE_CHECKING:
+    import asyncio
+    import socket
     from typing import Protocol
 
 # Import ospath module for aiofiles compatibility
@@ -25,15 +37,25 @@ try:
         read_file_async,
         write_file_async,
         read_file_bytes_async,
+        read_with_stat_async,
+        read_exact_async,
         write_file_bytes_async,
         append_file_async,
         open_file,
         AsyncFile,
+        OpenFileInfo,
+        open_files as _open_files,
+        send_file_async,
+        recv_file_async,
+        capture_open_stacks as _capture_open_stacks,
+        set_small_file_threshold as _set_small_file_threshold,
+        get_small_file_threshold as _get_small_file_threshold,
         create_dir_async,
         create_dir_all_async,
         remove_dir_async,
         remove_dir_all_async,
         list_dir_async,
+        list_dir_stat_async,
         exists_async,
         is_file_async,
         is_dir_async,
@@ -41,20 +63,105 @@ try:
         metadata_async,
         FileMetadata,
         walk_dir_async,
+        audit_permissions_async,
+        PermissionFinding,
+        check_portability_async,
+        PortabilityIssue,
+        dircmp_async,
+        DirDiff,
+        DirCompare,
         copy_file_async,
+        copytree_async,
         move_file_async,
+        move_async,
+        rename_async,
+        replace_async,
         remove_file_async,
+        unlink_async,
+        touch_async,
+        truncate_async,
+        chmod_async,
+        chown_async,
         hard_link_async,
+        link_async,
         symlink_async,
         canonicalize_async,
+        readlink_async,
+        realpath_async,
+        dedupe_tree_async,
+        DedupeReport,
         atomic_write_file_async,
         atomic_write_file_bytes_async,
+        write_if_unchanged_async,
+        FileChangedError,
         atomic_move_file_async,
+        read_toml_async,
+        toml_loads as _toml_loads,
+        toml_dumps as _toml_dumps,
+        read_yaml_async,
+        save_config_async,
         lock_file_async,
+        lease_file_async,
+        register_cleanup as _register_cleanup,
+        unregister_cleanup as _unregister_cleanup,
+        registered_cleanup as _registered_cleanup,
+        run_cleanup as _run_cleanup,
+        run_cleanup_async,
+        best_scratch_dir_async,
+        runtime_info as _runtime_info,
+        set_backend as _set_backend,
+        get_backend as _get_backend,
+        available_backends as _available_backends,
+        override_backend as _override_backend,
+        reset_backend_override as _reset_backend_override,
+        add_route as _add_route,
+        remove_route as _remove_route,
+        clear_routes as _clear_routes,
+        routes as _routes,
+        Route,
+        set_read_only as _set_read_only,
+        is_read_only as _is_read_only,
+        writable_roots as _writable_roots,
+        ReadOnlyError,
+        override_options as _override_options,
+        reset_options_override as _reset_options_override,
+        get_options as _get_options,
+        configure as _configure,
+        get_config as _get_config,
+        preload as _preload,
+        reinit_after_fork as _reinit_after_fork,
         FileLock,
+        FileLease,
+        open_log_async,
+        LogReader,
+        watch_async,
+        DirWatcher,
+        WatchEvent,
         read_files_async,
         write_files_async,
         copy_files_async,
+        rename_many_async,
+        rename_pattern_async,
+        generate_tree_async,
+        Transaction,
+        transaction as _transaction,
+        recover_transaction_async,
+        Cache,
+        read_ranges_async,
+        write_segments_async,
+        list_extents_async,
+        create_filled_async,
+        inject_fault as _inject_fault,
+        remove_fault as _remove_fault,
+        clear_faults as _clear_faults,
+        inject_latency as _inject_latency,
+        remove_latency as _remove_latency,
+        clear_latency as _clear_latency,
+        start_recording as _start_recording,
+        stop_recording as _stop_recording,
+        OpRecord,
+        start_audit as _start_audit,
+        stop_audit as _stop_audit,
     )
 except ImportError:
     # Try alternative import path
@@ -63,15 +170,25 @@ except ImportError:
             read_file_async,
             write_file_async,
             read_file_bytes_async,
+            read_with_stat_async,
+            read_exact_async,
             write_file_bytes_async,
             append_file_async,
             open_file,
             AsyncFile,
+            OpenFileInfo,
+            open_files as _open_files,
+            send_file_async,
+            recv_file_async,
+            capture_open_stacks as _capture_open_stacks,
+            set_small_file_threshold as _set_small_file_threshold,
+            get_small_file_threshold as _get_small_file_threshold,
             create_dir_async,
             create_dir_all_async,
             remove_dir_async,
             remove_dir_all_async,
             list_dir_async,
+            list_dir_stat_async,
             exists_async,
             is_file_async,
             is_dir_async,
@@ -79,20 +196,105 @@ except ImportError:
             metadata_async,
             FileMetadata,
             walk_dir_async,
+            audit_permissions_async,
+            PermissionFinding,
+            check_portability_async,
+            PortabilityIssue,
+            dircmp_async,
+            DirDiff,
+            DirCompare,
             copy_file_async,
+            copytree_async,
             move_file_async,
+            move_async,
+            rename_async,
+            replace_async,
             remove_file_async,
+            unlink_async,
+            touch_async,
+            truncate_async,
+            chmod_async,
+            chown_async,
             hard_link_async,
+            link_async,
             symlink_async,
             canonicalize_async,
+            readlink_async,
+            realpath_async,
+            dedupe_tree_async,
+            DedupeReport,
             atomic_write_file_async,
             atomic_write_file_bytes_async,
+            write_if_unchanged_async,
+            FileChangedError,
             atomic_move_file_async,
+            read_toml_async,
+            toml_loads as _toml_loads,
+            toml_dumps as _toml_dumps,
+            read_yaml_async,
+            save_config_async,
             lock_file_async,
+            lease_file_async,
+            register_cleanup as _register_cleanup,
+            unregister_cleanup as _unregister_cleanup,
+            registered_cleanup as _registered_cleanup,
+            run_cleanup as _run_cleanup,
+            run_cleanup_async,
+            best_scratch_dir_async,
+            runtime_info as _runtime_info,
+            set_backend as _set_backend,
+            get_backend as _get_backend,
+            available_backends as _available_backends,
+            override_backend as _override_backend,
+            reset_backend_override as _reset_backend_override,
+            add_route as _add_route,
+            remove_route as _remove_route,
+            clear_routes as _clear_routes,
+            routes as _routes,
+            Route,
+            set_read_only as _set_read_only,
+            is_read_only as _is_read_only,
+            writable_roots as _writable_roots,
+            ReadOnlyError,
+            override_options as _override_options,
+            reset_options_override as _reset_options_override,
+            get_options as _get_options,
+            configure as _configure,
+            get_config as _get_config,
+            preload as _preload,
+            reinit_after_fork as _reinit_after_fork,
             FileLock,
+            FileLease,
+            open_log_async,
+            LogReader,
+            watch_async,
+            DirWatcher,
+            WatchEvent,
             read_files_async,
             write_files_async,
             copy_files_async,
+            rename_many_async,
+            rename_pattern_async,
+            generate_tree_async,
+            Transaction,
+            transaction as _transaction,
+            recover_transaction_async,
+            Cache,
+            read_ranges_async,
+            write_segments_async,
+            list_extents_async,
+            create_filled_async,
+            inject_fault as _inject_fault,
+            remove_fault as _remove_fault,
+            clear_faults as _clear_faults,
+            inject_latency as _inject_latency,
+            remove_latency as _remove_latency,
+            clear_latency as _clear_latency,
+            start_recording as _start_recording,
+            stop_recording as _stop_recording,
+            OpRecord,
+            start_audit as _start_audit,
+            stop_audit as _stop_audit,
         )
     except ImportError:
         raise ImportError(
@@ -112,16 +314,26 @@ __all__: List[str] = [
     "read_file_bytes",
     "write_file_bytes",
     "append_file",
+    "read_with_stat",
+    "read_exact",
     # File handles
     "open",
     "open_file",
     "AsyncFile",
+    "open_files",
+    "capture_open_stacks",
+    "send_file",
+    "recv_file",
+    "set_small_file_threshold",
+    "get_small_file_threshold",
+    "OpenFileInfo",
     # Directory operations
     "create_dir",
     "create_dir_all",
     "remove_dir",
     "remove_dir_all",
     "list_dir",
+    "list_dir_stat",
     "exists",
     "is_file",
     "is_dir",
@@ -131,28 +343,119 @@ __all__: List[str] = [
     "FileMetadata",
     # Directory traversal
     "walk_dir",
+    # Permission auditing
+    "audit_permissions",
+    "PermissionFinding",
+    # Portability checks
+    "check_portability",
+    "PortabilityIssue",
+    # Directory comparison
+    "dircmp",
+    "DirDiff",
+    "DirCompare",
     # File manipulation
     "copy_file",
+    "copytree",
     "move_file",
+    "move",
     "rename",
+    "replace",
     "remove_file",
+    "unlink",
+    "touch",
+    "truncate",
+    "chmod",
+    "chown",
+    "lchown",
     "hard_link",
+    "link",
     "symlink",
     "canonicalize",
+    "realpath",
+    "readlink",
+    # Deduplication
+    "dedupe_tree",
+    "DedupeReport",
     # Atomic operations
     "atomic_write_file",
     "atomic_write_file_bytes",
+    "write_if_unchanged",
+    "FileChangedError",
     "atomic_move_file",
     # File locking
     "lock_file",
     "lock_file_shared",
     "FileLock",
+    "lease_file",
+    "FileLease",
     "_LockContextManager",
+    # Log reading
+    "open_log",
+    "LogReader",
+    # Directory watching
+    "watch",
+    "DirWatcher",
+    "WatchEvent",
+    # Config file reads
+    "read_toml",
+    "read_yaml",
+    # Config file updates
+    "update_json",
+    "update_toml",
+    "save_config",
+    # Cleanup registry
+    "register_cleanup",
+    "unregister_cleanup",
+    "registered_cleanup",
+    "run_cleanup",
+    # Scratch space
+    "best_scratch_dir",
+    # Runtime introspection
+    "runtime_info",
+    # I/O backends
+    "set_backend",
+    "get_backend",
+    "use_backend",
+    "available_backends",
+    "add_route",
+    "remove_route",
+    "clear_routes",
+    "routes",
+    "Route",
+    # Operation options
+    "options",
+    "get_options",
+    # Read-only mode
+    "set_read_only",
+    "is_read_only",
+    "writable_roots",
+    "ReadOnlyError",
+    # Configuration
+    "configure",
+    "get_config",
+    "preload",
+    "reinit_after_fork",
+    # Task groups
+    "TaskGroup",
+    # Positional I/O
+    "read_ranges",
+    "write_segments",
+    "list_extents",
+    "create_filled",
     # Batch operations
     "read_files",
     "read_files_dict",
     "write_files",
     "copy_files",
+    "rename_many",
+    "rename_pattern",
+    "generate_tree",
+    # Transactions
+    "Transaction",
+    "transaction",
+    "recover_transaction",
+    # Disk cache
+    "Cache",
 ]
 
 
@@ -164,7 +467,8 @@ async def read_file(path: str) -> str:
     This function reads the entire file and returns its contents as a UTF-8
     decoded string. All I/O operations execute outside the Python GIL using
     native Rust/Tokio, ensuring true async behavior and preventing event loop
-    stalls.
+    stalls. UTF-8 validation and decoding also happen in Rust without the GIL,
+    so reading large text files does not stall other tasks.
 
     The file is read completely into memory. For large files, consider using
     `open()` with a file handle for streaming reads.
@@ -177,6 +481,7 @@ async def read_file(path: str) -> str:
 
     Raises:
         FileNotFoundError: If the file does not exist.
+        UnicodeDecodeError: If the file is not valid UTF-8.
         IOError: If the file cannot be read (e.g., permission denied).
         ValueError: If the path is invalid (empty string or contains null bytes).
 
@@ -195,7 +500,7 @@ async def read_file(path: str) -> str:
     return await read_file_async(path)
 
 
-async def write_file(path: str, contents: str) -> None:
+async def write_file(path: str, contents: str, *, durability: str = "flush") -> None:
     """
     Write a file asynchronously using true async I/O.
 
@@ -210,11 +515,17 @@ async def write_file(path: str, contents: str) -> None:
     Args:
         path: Path to the file to write. Can be a relative or absolute path.
         contents: Content to write to the file. Will be encoded as UTF-8.
+        durability: How far the data is persisted before this returns:
+            "none", "flush" (default; handed to the OS, so it survives a
+            crash of the process), "fsync" (synced to disk, so it survives
+            a power loss) or "fsync+dir" (the directory is synced too, so a
+            newly created file doesn't vanish either).
 
     Raises:
         IOError: If the file cannot be written (e.g., disk full, I/O error).
         PermissionError: If write permission is denied.
-        ValueError: If the path is invalid (empty string or contains null bytes).
+        ValueError: If the path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
 
     Example:
         ```python
@@ -228,7 +539,7 @@ async def write_file(path: str, contents: str) -> None:
         - `append_file()`: Append content to an existing file.
         - `write_files()`: Write multiple files concurrently.
     """
-    await write_file_async(path, contents)
+    await write_file_async(path, contents, durability)
 
 
 async def read_file_bytes(path: str) -> bytes:
@@ -268,7 +579,95 @@ async def read_file_bytes(path: str) -> bytes:
     return await read_file_bytes_async(path)
 
 
-async def write_file_bytes(path: str, contents: bytes) -> None:
+async def read_with_stat(path: str) -> Tuple[FileMetadata, bytes]:
+    """
+    Read a file and its metadata from a single open.
+
+    The metadata is taken with `fstat` on the descriptor the contents are
+    read from, so both describe the same file even if the path is replaced
+    in between, unlike separate `stat()` and `read_file_bytes()` calls. It
+    also saves a round trip to the blocking pool.
+
+    Args:
+        path: Path to the file to read.
+
+    Returns:
+        Tuple[FileMetadata, bytes]: Metadata of the opened file and its
+            contents. If the file is appended to while being read, the
+            contents may be longer than `metadata.size`.
+
+    Raises:
+        FileNotFoundError: If the file does not exist.
+        IOError: If the file cannot be read (e.g., it's a directory).
+        ValueError: If the path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        # Reload a config file only when it changed
+        metadata, data = await read_with_stat("config.json")
+        if metadata.modified != last_modified:
+            config = json.loads(data)
+        ```
+
+    See Also:
+        - `read_file_bytes()`: Read a file as bytes.
+        - `stat()`: Get file metadata.
+    """
+    return await read_with_stat_async(path)
+
+
+async def read_exact(
+    source: Union[str, AsyncFile], size: int, *, timeout: Optional[float] = None
+) -> bytes:
+    """
+    Read exactly `size` bytes from a path or an open file.
+
+    Pipes, FIFOs, sockets and character devices return short reads whenever
+    less data is available; this repeats reads until `size` bytes have
+    arrived, like `asyncio.StreamReader.readexactly()`. Useful for
+    length-prefixed protocols.
+
+    Args:
+        source: Path to open and read from the start, or an open file
+            (`AsyncFile`, also in text mode) to read from its position.
+        size: Number of bytes to read.
+        timeout: Seconds to wait for all `size` bytes, or None (default) to
+            wait as long as it takes. On timeout, the bytes already read
+            from an open file stay buffered and are returned by its next
+            read, so nothing is lost.
+
+    Returns:
+        bytes: Exactly `size` bytes.
+
+    Raises:
+        asyncio.IncompleteReadError: If the file ends first; `partial` holds
+            the bytes read.
+        TimeoutError: If `timeout` expires first.
+        TypeError: If `source` is neither a path nor an open file.
+        FileNotFoundError: If the path does not exist.
+        ValueError: If the path is invalid or `timeout` is negative.
+
+    Example:
+        ```python
+        read_fd, write_fd = os.pipe()
+        async with rapfiles.AsyncFile.from_fd(read_fd, "rb") as pipe:
+            header = await read_exact(pipe, 4, timeout=5)
+            body = await read_exact(pipe, int.from_bytes(header, "big"))
+        ```
+
+    See Also:
+        - `AsyncFile.read_exact()`: The same, as a method.
+        - `AsyncFile.read1()`: Read whatever is available with one read.
+    """
+    if not isinstance(source, (str, AsyncFile)) and hasattr(source, "read_exact"):
+        # Text mode wrapper; read the bytes from the file underneath
+        return await source.read_exact(size, timeout)
+    return await read_exact_async(source, size, timeout)
+
+
+async def write_file_bytes(
+    path: str, contents: bytes, *, durability: str = "flush"
+) -> None:
     """
     Write raw bytes to a file asynchronously.
 
@@ -285,11 +684,14 @@ async def write_file_bytes(path: str, contents: bytes) -> None:
     Args:
         path: Path to the file to write. Can be a relative or absolute path.
         contents: Raw bytes to write to the file.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir"; see
+            `write_file()`.
 
     Raises:
         IOError: If the file cannot be written (e.g., disk full, I/O error).
         PermissionError: If write permission is denied.
-        ValueError: If the path is invalid (empty string or contains null bytes).
+        ValueError: If the path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
 
     Example:
         ```python
@@ -302,10 +704,10 @@ async def write_file_bytes(path: str, contents: bytes) -> None:
         - `atomic_write_file_bytes()`: Write bytes atomically (prevents partial writes).
         - `write_files()`: Write multiple files concurrently.
     """
-    await write_file_bytes_async(path, contents)
+    await write_file_bytes_async(path, contents, durability)
 
 
-async def append_file(path: str, contents: str) -> None:
+async def append_file(path: str, contents: str, *, durability: str = "flush") -> None:
     """
     Append content to a file asynchronously.
 
@@ -320,11 +722,17 @@ async def append_file(path: str, contents: str) -> None:
     Args:
         path: Path to the file to append to. Can be a relative or absolute path.
         contents: Content to append to the file. Will be encoded as UTF-8.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir"; see
+            `write_file()`. With "none" the call may return while the data is
+            still on its way to the OS, and a failure of that write is not
+            reported; appending to a log with "fsync" makes each entry
+            survive a power loss.
 
     Raises:
         IOError: If the file cannot be written (e.g., disk full, I/O error).
         PermissionError: If write permission is denied.
-        ValueError: If the path is invalid (empty string or contains null bytes).
+        ValueError: If the path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
 
     Example:
         ```python
@@ -336,7 +744,7 @@ async def append_file(path: str, contents: str) -> None:
         - `write_file()`: Overwrite file with new content.
         - `write_file_bytes()`: Append raw bytes to a file (use with 'a' mode via `open()`).
     """
-    await append_file_async(path, contents)
+    await append_file_async(path, contents, durability)
 
 
 # Directory operations
@@ -435,7 +843,9 @@ async def remove_dir(path: str) -> None:
     await remove_dir_async(path)
 
 
-async def remove_dir_all(path: str) -> None:
+async def remove_dir_all(
+    path: str, *, rate_limit_ops: Optional[float] = None, nice: bool = False
+) -> None:
     """
     Remove a directory and all its contents asynchronously.
 
@@ -444,32 +854,51 @@ async def remove_dir_all(path: str) -> None:
     undone. All I/O operations execute outside the Python GIL using native
     Rust/Tokio, ensuring true async behavior and preventing event loop stalls.
 
+    On Linux, macOS and FreeBSD the tree is removed by several worker threads
+    that unlink entries relative to open directory descriptors, so even
+    directories with millions of files are deleted quickly. Symlinks inside
+    the tree are removed, never followed.
+
+    A bulk cleanup at full speed can saturate a disk shared with latency
+    sensitive workloads such as a database. With `rate_limit_ops` or `nice`
+    the tree is instead removed one entry at a time, paced and/or at the
+    lowest priority.
+
     Warning: This function will delete all files and subdirectories within the
     specified directory. Use with caution.
 
     Args:
         path: Path to the directory to remove. Can be a relative or absolute path.
+        rate_limit_ops: Maximum number of entries (files and directories)
+            removed per second. No limit if None (default).
+        nice: If True, remove from a thread with the lowest CPU priority and,
+            on Linux, the idle I/O scheduling class (like `ionice -c 3`), so
+            other I/O goes first. Defaults to False.
 
     Raises:
         FileNotFoundError: If the directory does not exist.
         IOError: If the directory cannot be removed (e.g., permission denied,
             files in use).
-        ValueError: If the path is invalid (empty string or contains null bytes).
+        ValueError: If the path is invalid (empty string or contains null
+            bytes) or `rate_limit_ops` is not a positive number.
 
     Example:
         ```python
         await remove_dir_all("directory_to_remove")
         # Directory and all its contents are permanently deleted
+
+        # Background cleanup next to a busy database
+        await remove_dir_all("old_segments", rate_limit_ops=500, nice=True)
         ```
 
     See Also:
         - `remove_dir()`: Remove an empty directory.
         - `remove_file()`: Remove a file.
     """
-    await remove_dir_all_async(path)
+    await remove_dir_all_async(path, rate_limit_ops=rate_limit_ops, nice=nice)
 
 
-async def list_dir(path: str) -> List[str]:
+async def list_dir(path: str, sorted: Union[bool, str] = False) -> List[str]:
     """
     List directory contents asynchronously.
 
@@ -482,10 +911,15 @@ async def list_dir(path: str) -> List[str]:
 
     Args:
         path: Path to the directory to list. Can be a relative or absolute path.
+        sorted: Sort order applied in Rust before the list reaches Python.
+            False (default) keeps filesystem order; True or "bytes" sorts
+            names by their raw bytes for deterministic output; "natural"
+            compares digit runs numerically so "file2" sorts before "file10".
 
     Returns:
-        List[str]: List of file and directory names (strings). The order is
-            not guaranteed and may vary between calls.
+        List[str]: List of file and directory names (strings). Unless
+            `sorted` is set, the order is not guaranteed and may vary
+            between calls.
 
     Raises:
         FileNotFoundError: If the directory does not exist.
@@ -506,7 +940,43 @@ async def list_dir(path: str) -> List[str]:
         - `is_file()`: Check if a path is a file.
         - `is_dir()`: Check if a path is a directory.
     """
-    return await list_dir_async(path)
+    return await list_dir_async(path, sorted)
+
+
+async def list_dir_stat(
+    path: str, sorted: Union[bool, str] = False
+) -> List[Tuple[str, FileMetadata]]:
+    """
+    List a directory together with the metadata of every entry.
+
+    Equivalent to `list_dir()` followed by `stat()` on each entry, but every
+    entry is stat'ed relative to the open directory in the same job as the
+    listing, which is far cheaper for directory-heavy indexing. As with
+    `os.scandir()`, symlinks are followed (a broken link reports the link
+    itself). Entries removed while listing are skipped.
+
+    Args:
+        path: Path to the directory to list.
+        sorted: Sort order of the names, as for `list_dir()`.
+
+    Returns:
+        List[Tuple[str, FileMetadata]]: `(name, metadata)` for each entry.
+
+    Raises:
+        FileNotFoundError: If the directory does not exist.
+        IOError: If the directory or an entry cannot be read.
+        ValueError: If the path or sort order is invalid.
+
+    Example:
+        ```python
+        sizes = {name: meta.size for name, meta in await list_dir_stat("data")}
+        ```
+
+    See Also:
+        - `list_dir()`: List entry names only.
+        - `stat()`: Get the metadata of a single path.
+    """
+    return await list_dir_stat_async(path, sorted)
 
 
 async def exists(path: str) -> bool:
@@ -703,7 +1173,9 @@ async def metadata(path: str) -> "FileMetadata":
 
 
 # Directory traversal
-async def walk_dir(path: str) -> List[Tuple[str, bool]]:
+async def walk_dir(
+    path: str, sorted: Union[bool, str] = False
+) -> List[Tuple[str, bool]]:
     """
     Recursively walk a directory tree asynchronously.
 
@@ -714,13 +1186,19 @@ async def walk_dir(path: str) -> List[Tuple[str, bool]]:
 
     Args:
         path: Directory path to walk. Can be a relative or absolute path.
+        sorted: Sort order applied in Rust. False (default) keeps traversal
+            order; True or "bytes" returns a deterministic pre-order listing
+            where every directory is followed by its contents and siblings
+            are ordered by their raw name bytes. "natural" produces the same
+            traversal with numeric-aware ordering ("file2" before "file10").
 
     Returns:
         List[Tuple[str, bool]]: List of (path, is_file) tuples where:
             - path (str): Full path to the file or directory
             - is_file (bool): True if the path is a file, False if it's a directory
 
-        The order of items is not guaranteed and may vary between calls.
+        Unless `sorted` is set, the order of items is not guaranteed and may
+        vary between calls.
 
     Raises:
         FileNotFoundError: If the directory does not exist.
@@ -743,36 +1221,208 @@ async def walk_dir(path: str) -> List[Tuple[str, bool]]:
     See Also:
         - `list_dir()`: List contents of a single directory (non-recursive).
     """
-    return await walk_dir_async(path)
+    return await walk_dir_async(path, sorted)
+
+
+# Permission auditing
+_AUDIT_POLICY_KEYS = ("world_writable", "setuid", "owner", "group")
+
+
+async def audit_permissions(
+    root: str, policy: Optional[Dict[str, Any]] = None
+) -> List["PermissionFinding"]:
+    """
+    Audit the permissions of a directory tree asynchronously.
+
+    Walks the tree rooted at `root` (including `root` itself) and reports
+    every entry that violates the given policy. Symbolic links are not
+    followed. All I/O operations execute outside the Python GIL using native
+    Rust/Tokio, ensuring true async behavior and preventing event loop stalls.
+
+    Args:
+        root: Root directory (or single file) to audit.
+        policy: Optional dictionary selecting the checks to run:
+            - world_writable (bool): Report entries writable by others.
+              Sticky directories such as `/tmp` are exempt. Defaults to True.
+            - setuid (bool): Report setuid entries and setgid files.
+              Defaults to True.
+            - owner (int): Report entries not owned by this user ID.
+            - group (int): Report entries not owned by this group ID.
+
+    Returns:
+        List[PermissionFinding]: One finding per violated rule, each with
+            `path`, `issue`, `mode`, `uid` and `gid` properties. `issue` is
+            one of "world_writable", "setuid", "setgid", "owner" or "group".
+
+    Raises:
+        FileNotFoundError: If `root` does not exist.
+        NotImplementedError: On platforms without Unix permission bits.
+        ValueError: If the path is invalid or the policy has unknown keys.
+
+    Example:
+        ```python
+        findings = await audit_permissions("/srv/app", {"owner": 1000})
+        for finding in findings:
+            print(f"{finding.issue}: {finding.path} ({finding.mode:o})")
+        # Output example:
+        # world_writable: /srv/app/uploads (777)
+        # owner: /srv/app/config.toml (644)
+        ```
+
+    See Also:
+        - `walk_dir()`: Recursively list a directory tree.
+        - `stat()`: Get metadata for a single path.
+    """
+    policy = dict(policy or {})
+    unknown = sorted(set(policy) - set(_AUDIT_POLICY_KEYS))
+    if unknown:
+        raise ValueError(f"Unknown audit policy keys: {', '.join(unknown)}")
+    return await audit_permissions_async(
+        root,
+        bool(policy.get("world_writable", True)),
+        bool(policy.get("setuid", True)),
+        policy.get("owner"),
+        policy.get("group"),
+    )
+
+
+# Portability checks
+async def check_portability(
+    root: str, max_path_length: int = 260
+) -> List["PortabilityIssue"]:
+    """
+    Check a directory tree for names that break on other platforms.
+
+    Walks the tree below `root` (without following symbolic links) and
+    reports names that collide case-insensitively within a directory, as
+    they would on Windows and macOS, Windows reserved device names such as
+    `CON` or `nul.txt`, characters Windows rejects, trailing dots and
+    spaces, and names or paths over the Windows length limits. Run it on
+    build output before archiving it to catch trees that cannot be
+    extracted on every platform.
+
+    Args:
+        root: Root directory to check. Its own name is not checked.
+        max_path_length: Longest allowed path relative to `root`. Defaults
+            to Windows' MAX_PATH of 260; lower it to leave room for the
+            directory the tree will be extracted into.
+
+    Returns:
+        List[PortabilityIssue]: Issues sorted by path, each with `path`,
+            `issue` and `detail` properties. `issue` is one of
+            "case_conflict", "reserved_name", "invalid_character",
+            "trailing_dot_or_space", "name_too_long" or "path_too_long".
+
+    Raises:
+        FileNotFoundError: If `root` does not exist.
+        ValueError: If the path or `max_path_length` is invalid.
+
+    Example:
+        ```python
+        for issue in await check_portability("dist/package"):
+            print(f"{issue.issue}: {issue.path} ({issue.detail})")
+        # Output example:
+        # case_conflict: dist/package/readme.md (conflicts with .../README.md)
+        # reserved_name: dist/package/aux.py (AUX is a reserved device name ...)
+        ```
+
+    See Also:
+        - `audit_permissions()`: Report permission problems in a tree.
+        - `walk_dir()`: Recursively list a directory tree.
+    """
+    return await check_portability_async(root, max_path_length)
+
+
+# Directory comparison
+def dircmp(a: str, b: str, compare_content: bool = False) -> "DirCompare":
+    """
+    Compare two directory trees as an async stream of differences.
+
+    Walks both trees in lockstep (without following symbolic links) and yields
+    a `DirDiff` for every entry that exists on only one side or differs
+    between them. Differences are produced while you iterate, directory by
+    directory in byte order, so neither tree is snapshotted into memory first.
+    All I/O operations execute outside the Python GIL using native Rust/Tokio.
+
+    Args:
+        a: First directory tree (e.g. the original).
+        b: Second directory tree (e.g. the restored copy).
+        compare_content: If True, files of equal size are compared byte by
+            byte. If False (default), they are compared by modification time.
+
+    Returns:
+        DirCompare: An async iterator of `DirDiff` objects with `path`
+            (relative to both roots), `kind` ("only_in_a", "only_in_b" or
+            "different") and `reason` ("type", "size", "mtime", "content" or
+            "target" for "different" entries, otherwise None).
+
+    Raises:
+        ValueError: If either path is invalid.
+        FileNotFoundError: Raised while iterating if a root does not exist.
+        IOError: Raised while iterating if a directory cannot be read.
+
+    Example:
+        ```python
+        async for diff in dircmp("backup", "restored", compare_content=True):
+            print(diff.kind, diff.path, diff.reason)
+        # Output example:
+        # only_in_a logs/app.log None
+        # different data/db.sqlite content
+        ```
+
+    See Also:
+        - `walk_dir()`: Recursively list a single directory tree.
+    """
+    return dircmp_async(a, b, compare_content)
 
 
 # File manipulation operations
-async def copy_file(src: str, dst: str) -> None:
+async def copy_file(
+    src: str, dst: str, preserve_owner: bool = False, *, durability: str = "flush"
+) -> int:
     """
     Copy a file asynchronously.
 
     Copies a file from source to destination. If the destination file exists,
     it will be overwritten. The source file remains unchanged. All I/O
     operations execute outside the Python GIL using native Rust/Tokio,
-    ensuring true async behavior and preventing event loop stalls.
+    ensuring true async behavior and preventing event loop stalls. The data
+    is streamed in Rust and never read into Python memory, so files of any
+    size can be copied.
 
     This operation preserves file permissions and metadata where possible.
-    For atomic file operations, see `atomic_write_file()`.
+    Ownership is only preserved when `preserve_owner` is True, since the copy
+    is otherwise owned by the calling user. For atomic file operations, see
+    `atomic_write_file()`.
 
     Args:
         src: Path to the source file. Can be a relative or absolute path.
         dst: Path to the destination file. Can be a relative or absolute path.
             Parent directories will not be created automatically.
+        preserve_owner: If True, give the destination the source's owning
+            user and group. Changing ownership to another user usually
+            requires running as root. Ignored on non-Unix platforms.
+            Defaults to False.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir"; see
+            `write_file()`. "fsync" syncs the copy to disk before returning.
+
+    Returns:
+        int: Number of bytes copied.
 
     Raises:
         FileNotFoundError: If the source file does not exist.
+        PermissionError: If ownership cannot be preserved.
         IOError: If the file cannot be copied (e.g., disk full, permission denied).
-        ValueError: If any path is invalid (empty string or contains null bytes).
+        ValueError: If any path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
 
     Example:
         ```python
-        await copy_file("source.txt", "destination.txt")
-        # destination.txt now contains a copy of source.txt
+        copied = await copy_file("source.txt", "destination.txt")
+        # destination.txt now contains a copy of source.txt (`copied` bytes)
+
+        # Running as root: keep the service user's ownership
+        await copy_file("/srv/app/data.db", "/backup/data.db", preserve_owner=True)
         ```
 
     See Also:
@@ -780,7 +1430,111 @@ async def copy_file(src: str, dst: str) -> None:
         - `copy_files()`: Copy multiple files concurrently.
         - `atomic_write_file()`: Write file atomically.
     """
-    await copy_file_async(src, dst)
+    return await copy_file_async(src, dst, preserve_owner, durability)
+
+
+async def copytree(
+    src: str,
+    dst: str,
+    mode: str = "copy",
+    preserve_owner: bool = False,
+    checkpoint: Optional[str] = None,
+    verify: str = "stat",
+    sanitize: Optional[str] = None,
+) -> Optional[List[Tuple[str, str]]]:
+    """
+    Copy a directory tree asynchronously.
+
+    Recreates the directory structure of `src` at `dst`, then copies many
+    files concurrently. With `mode="hardlink"`, files are hard linked instead
+    of copied, which makes snapshot-style deployments of large trees nearly
+    instant (files on another device than `dst` are copied instead). Symlinks
+    are recreated as symlinks on Unix rather than followed; FIFOs, sockets
+    and devices cannot be copied. All I/O operations execute outside the
+    Python GIL using native Rust/Tokio.
+
+    Note that hard linked files share their content with the source: writing
+    to one in place changes the other. Replace files (e.g. with
+    `atomic_write_file()`) instead of editing them.
+
+    For long copies, pass a `checkpoint` file. Every completed file is
+    recorded in it, so if the copy is interrupted (a crash, a killed job),
+    calling `copytree()` again with the same arguments resumes it: recorded
+    files are verified and skipped, anything else is copied again. The
+    checkpoint is removed once the copy succeeds. Keep it outside `src`.
+
+    Trees built on Linux may contain names that cannot exist on Windows,
+    such as `aux.py`, `a:b` or `notes.`. With `sanitize`, such names are
+    escaped in the copy: the offending characters (bytes that are not valid
+    Unicode, characters Windows rejects, trailing dots and spaces, and the
+    last letter of a reserved device name) become "_" with "replace", or
+    "%XX" with "percent-encode", which also encodes "%" so names can be
+    decoded again. "error" fails on the first such name instead. Symlink
+    targets are copied unchanged.
+
+    Args:
+        src: Path to the directory to copy.
+        dst: Path of the new tree. Must not exist unless resuming from
+            `checkpoint`; parent directories are not created automatically.
+        mode: "copy" (default) to copy file contents, or "hardlink" to hard
+            link files.
+        preserve_owner: If True, give copied files and created directories
+            the owning user and group of their source. Usually requires root;
+            ignored on non-Unix platforms. Defaults to False.
+        checkpoint: Path of a file to record progress in and resume from.
+            Defaults to None (no checkpoint).
+        verify: How files recorded in the checkpoint are verified before
+            being skipped: "stat" (default) checks that the source size and
+            mtime are unchanged and the copy has the same size; "content"
+            also compares the contents of source and copy.
+        sanitize: "replace", "percent-encode" or "error" to handle names
+            that are not valid on Windows. Defaults to None (names are
+            copied unchanged).
+
+    Returns:
+        Optional[List[Tuple[str, str]]]: With `sanitize`, the source and
+            destination paths of every renamed entry, sorted; otherwise
+            None.
+
+    Raises:
+        FileNotFoundError: If `src` does not exist.
+        NotADirectoryError: If `src` is not a directory.
+        FileExistsError: If `dst` already exists and there is no checkpoint
+            to resume from, or two entries are sanitized to the same name.
+        shutil.SpecialFileError: If an entry is a FIFO, socket or device.
+        IOError: If an entry cannot be copied (e.g., disk full, permission
+            denied).
+        ValueError: If a path is invalid, `dst` is inside `src`, `mode` is
+            not "copy" or "hardlink", `verify` is not "stat" or "content",
+            `sanitize` is not a known strategy, the checkpoint is corrupt or
+            belongs to a copy of different directories, or a name is invalid
+            with `sanitize="error"` (names are checked before anything is
+            created).
+
+    Example:
+        ```python
+        # Deploy a new release as a hard link farm of the build output
+        await copytree("build/output", "/srv/releases/v42", mode="hardlink")
+
+        # Copy a large dataset; rerun the same call to resume after a crash
+        await copytree(
+            "/mnt/raw", "/mnt/backup/raw", checkpoint="/var/tmp/raw.ckpt"
+        )
+
+        # Stage an artifact for Windows users
+        renamed = await copytree("build", "dist/win", sanitize="replace")
+        # Output example: [("build/aux.py", "dist/win/au_.py")]
+        ```
+
+    See Also:
+        - `copy_file()`: Copy a single file.
+        - `hard_link()`: Hard link a single file.
+        - `dircmp()`: Compare the copy with its source.
+        - `check_portability()`: Report non-portable names without copying.
+    """
+    return await copytree_async(
+        src, dst, mode, preserve_owner, checkpoint, verify, sanitize
+    )
 
 
 async def move_file(src: str, dst: str) -> None:
@@ -814,30 +1568,82 @@ async def move_file(src: str, dst: str) -> None:
         ```
 
     See Also:
-        - `rename()`: Alias for `move_file()`.
+        - `move()`: Move files or directories like `shutil.move()`.
+        - `rename()`: Rename without the cross-device fallback (`os.rename`).
         - `atomic_move_file()`: Move file atomically with additional safety.
         - `copy_file()`: Copy a file without removing the source.
     """
     await move_file_async(src, dst)
 
 
+async def move(src: str, dst: str, *, durability: str = "flush") -> str:
+    """
+    Move a file or directory asynchronously, like `shutil.move()`.
+
+    If `dst` is an existing directory, `src` is moved inside it. Within a
+    filesystem the move is a single rename. Across filesystems (e.g. from a
+    tmpfs scratch directory to persistent storage), `src` is copied with its
+    permissions and timestamps, synced to disk, renamed into place under its
+    final name and only then removed, so the destination never holds a
+    partial copy. Directories are copied recursively, keeping symlinks as
+    links on Unix.
+
+    Args:
+        src: Path of the file or directory to move.
+        dst: Destination path, or an existing directory to move `src` into.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir"; see
+            `write_file()`. A rename is synced with "fsync" (the moved entry)
+            and "fsync+dir" (also the directories of `src` and `dst`, so the
+            rename itself survives a power loss). A cross-filesystem copy is
+            always synced, as the source is removed after it.
+
+    Returns:
+        str: The path `src` was moved to.
+
+    Raises:
+        FileNotFoundError: If the source does not exist.
+        FileExistsError: If `dst` is a directory that already contains an
+            entry named like `src`.
+        IOError: If the move fails (e.g., disk full, permission denied). A
+            failed cross-filesystem copy leaves the source untouched.
+        ValueError: If any path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
+
+    Example:
+        ```python
+        # Publish a file built in /dev/shm to a data directory
+        path = await move("/dev/shm/report.parquet", "/data/reports/")
+        # path == "/data/reports/report.parquet"
+        ```
+
+    See Also:
+        - `move_file()`: Move a single file to an exact destination path.
+        - `rename()`: Rename without the cross-device fallback (`os.rename`).
+    """
+    return await move_async(src, dst, durability)
+
+
 async def rename(src: str, dst: str) -> None:
     """
-    Rename a file asynchronously (alias for move_file).
+    Rename a file or directory asynchronously, like `os.rename()`.
 
-    This is an alias for `move_file()`. Renames a file from source to destination.
-    The source file is removed after a successful rename. All I/O operations
-    execute outside the Python GIL using native Rust/Tokio, ensuring true async
-    behavior and preventing event loop stalls.
+    Performs a single rename system call, so it is atomic but cannot move
+    across filesystems (use `move_file()` for that). On POSIX an existing
+    destination file is replaced; on Windows an existing destination raises
+    `FileExistsError`, as with `os.rename()`. Use `replace()` to overwrite
+    the destination on every platform. All I/O operations execute outside
+    the Python GIL using native Rust/Tokio, ensuring true async behavior and
+    preventing event loop stalls.
 
     Args:
-        src: Path to the source file. Can be a relative or absolute path.
-        dst: Path to the destination file. Can be a relative or absolute path.
-            Parent directories will not be created automatically.
+        src: Path to the file or directory to rename.
+        dst: New path. Parent directories will not be created automatically.
 
     Raises:
-        FileNotFoundError: If the source file does not exist.
-        IOError: If the file cannot be renamed (e.g., disk full, permission denied).
+        FileNotFoundError: If the source does not exist.
+        FileExistsError: If the destination exists (Windows only).
+        IOError: If the rename fails (e.g., across filesystems, permission
+            denied).
         ValueError: If any path is invalid (empty string or contains null bytes).
 
     Example:
@@ -847,11 +1653,46 @@ async def rename(src: str, dst: str) -> None:
         ```
 
     See Also:
-        - `move_file()`: Primary function for moving/renaming files.
+        - `replace()`: Rename, overwriting the destination on every platform.
+        - `move_file()`: Move, copying across filesystems when needed.
         - `atomic_move_file()`: Atomic file move operation.
-        - `copy_file()`: Copy a file without removing the source.
     """
-    await move_file_async(src, dst)
+    await rename_async(src, dst)
+
+
+async def replace(src: str, dst: str) -> None:
+    """
+    Rename a file or directory, replacing the destination, like `os.replace()`.
+
+    An existing destination file is replaced atomically: other processes see
+    either the old or the new file at `dst`, never a missing one. This makes
+    it the final step of the write-to-temp-then-rename pattern. On POSIX this
+    is `rename(2)`, which can also replace an empty directory with a
+    directory; on Windows it moves with `MOVEFILE_REPLACE_EXISTING`. Moving
+    across filesystems fails (use `move_file()` for that). All I/O
+    operations execute outside the Python GIL.
+
+    Args:
+        src: Path to the file or directory to rename.
+        dst: New path, replaced if it exists.
+
+    Raises:
+        FileNotFoundError: If the source does not exist.
+        IOError: If the rename fails (e.g., across filesystems, or the
+            destination is a non-empty directory).
+        ValueError: If any path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        await write_file("config.json.tmp", new_config)
+        await replace("config.json.tmp", "config.json")
+        ```
+
+    See Also:
+        - `rename()`: Rename with `os.rename()` semantics.
+        - `atomic_write_file()`: Write a file atomically in one call.
+    """
+    await replace_async(src, dst)
 
 
 async def remove_file(path: str) -> None:
@@ -871,8 +1712,9 @@ async def remove_file(path: str) -> None:
 
     Raises:
         FileNotFoundError: If the file does not exist.
-        IOError: If the file cannot be removed (e.g., it's a directory,
-            permission denied, or file is in use).
+        IsADirectoryError: If the path is a directory.
+        IOError: If the file cannot be removed (e.g., permission denied, or
+            file is in use).
         ValueError: If the path is invalid (empty string or contains null bytes).
 
     Example:
@@ -882,125 +1724,468 @@ async def remove_file(path: str) -> None:
         ```
 
     See Also:
+        - `unlink()`: Remove a file, optionally ignoring a missing one.
         - `remove_dir()`: Remove an empty directory.
         - `remove_dir_all()`: Remove a directory and all its contents.
     """
     await remove_file_async(path)
 
 
-async def hard_link(src: str, dst: str) -> None:
+async def unlink(path: str, missing_ok: bool = False) -> None:
     """
-    Create a hard link asynchronously.
-
-    Creates a hard link from source to destination. Both files will refer
-    to the same underlying file data. Changes to one file will be reflected
-    in the other. Hard links only work within the same filesystem. All I/O
-    operations execute outside the Python GIL using native Rust/Tokio,
-    ensuring true async behavior and preventing event loop stalls.
+    Remove a file asynchronously, like `pathlib.Path.unlink()`.
 
-    Note: Hard links cannot be created for directories on most filesystems.
+    Same as `remove_file()`, except that with `missing_ok=True` a path that
+    does not exist is silently ignored. Symlinks are removed, not their
+    targets.
 
     Args:
-        src: Path to the source file. Can be a relative or absolute path.
-        dst: Path to the destination link. Can be a relative or absolute path.
+        path: Path to the file to remove.
+        missing_ok: Whether a missing path is not an error. Defaults to False.
 
     Raises:
-        FileNotFoundError: If the source file does not exist.
-        IOError: If the link cannot be created (e.g., cross-filesystem link,
-            permission denied, or destination already exists).
-        ValueError: If any path is invalid (empty string or contains null bytes).
+        FileNotFoundError: If the file does not exist and `missing_ok` is False.
+        IsADirectoryError: If the path is a directory.
+        IOError: If the file cannot be removed (e.g., permission denied).
+        ValueError: If the path is invalid (empty string or contains null bytes).
 
     Example:
         ```python
-        await hard_link("original.txt", "link.txt")
-        # Both files now refer to the same data
+        # Clean up a lock file that may or may not be there
+        await unlink("service.lock", missing_ok=True)
         ```
-
-    See Also:
-        - `symlink()`: Create a symbolic link (works across filesystems).
-        - `copy_file()`: Create a copy of a file (independent data).
     """
-    await hard_link_async(src, dst)
+    await unlink_async(path, missing_ok)
 
 
-async def symlink(src: str, dst: str) -> None:
+async def touch(path: str, exist_ok: bool = True) -> None:
     """
-    Create a symbolic link asynchronously.
-
-    Creates a symbolic link from source to destination. The destination will
-    point to the source path. Unlike hard links, symbolic links can point
-    to files on different filesystems and can point to directories. All I/O
-    operations execute outside the Python GIL using native Rust/Tokio,
-    ensuring true async behavior and preventing event loop stalls.
+    Create a file or update its timestamps, like `pathlib.Path.touch()`.
 
-    The source path can be relative or absolute. If relative, it's resolved
-    relative to the symlink's directory.
+    A missing file is created empty; an existing one keeps its contents and
+    gets its access and modification times set to the current time.
+    Symlinks are followed.
 
     Args:
-        src: Path that the symlink will point to. Can be a relative or absolute
-            path. The path doesn't need to exist at creation time.
-        dst: Path to the symbolic link to create. Can be a relative or absolute
-            path. Parent directories will not be created automatically.
+        path: Path to the file.
+        exist_ok: Whether an existing file is touched rather than an error.
+            Defaults to True.
 
     Raises:
-        IOError: If the symlink cannot be created (e.g., destination already
-            exists, permission denied).
-        ValueError: If any path is invalid (empty string or contains null bytes).
+        FileExistsError: If the file exists and `exist_ok` is False.
+        FileNotFoundError: If the parent directory does not exist.
+        IOError: If the file cannot be created or updated (e.g., permission
+            denied).
+        ValueError: If the path is invalid (empty string or contains null bytes).
 
     Example:
         ```python
-        await symlink("/path/to/original", "/path/to/link")
-        # link now points to original
+        # Mark a job as done for tools that watch modification times
+        await touch("build/.stamp")
         ```
-
-    See Also:
-        - `hard_link()`: Create a hard link (same filesystem only).
-        - `canonicalize()`: Resolve a symlink to its canonical path.
     """
-    await symlink_async(src, dst)
+    await touch_async(path, exist_ok)
 
 
-async def canonicalize(path: str) -> str:
+async def truncate(path: str, size: int) -> None:
     """
-    Canonicalize a path asynchronously.
+    Truncate or extend a file to a given length, like `os.truncate()`.
 
-    Resolves all symbolic links and returns the absolute path. This function
-    resolves all intermediate symlinks in the path and normalizes relative
-    components (e.g., '..' and '.'). All I/O operations execute outside the
-    Python GIL using native Rust/Tokio, ensuring true async behavior and
-    preventing event loop stalls.
-
-    The path must exist for canonicalization to succeed. This is useful for
-    getting the "real" path of a file, especially when dealing with symlinks.
+    Bytes past `size` are discarded; a shorter file is extended with zero
+    bytes (sparsely, where the filesystem supports it). The file is resized
+    in Rust without opening a handle in Python, e.g. to preallocate a file
+    or reset a log.
 
     Args:
-        path: Path to canonicalize. Can be a relative or absolute path.
-
-    Returns:
-        str: Canonical absolute path as a string. All symlinks are resolved
-            and the path is normalized.
+        path: Path to an existing file.
+        size: New size in bytes.
 
     Raises:
-        FileNotFoundError: If the path does not exist.
-        IOError: If the path cannot be canonicalized (e.g., broken symlink,
-            permission denied).
-        ValueError: If the path is invalid (empty string or contains null bytes).
+        FileNotFoundError: If the file does not exist.
+        IOError: If the file cannot be resized (e.g., permission denied, or
+            the path is a directory).
+        ValueError: If size is negative or the path is invalid (empty string
+            or contains null bytes).
 
     Example:
         ```python
-        canonical_path = await canonicalize("./relative/path/../file.txt")
-        print(canonical_path)  # /absolute/path/to/file.txt
+        # Start a fresh log without recreating the file
+        await truncate("app.log", 0)
         ```
-
-    See Also:
-        - `symlink()`: Create a symbolic link.
-        - `rapfiles.ospath.abspath()`: Get absolute path without resolving symlinks.
+    """
+    await truncate_async(path, size)
+
+
+async def chmod(path: str, mode: int) -> None:
+    """
+    Change the permission bits of a file or directory, like `os.chmod()`.
+
+    On Unix, `mode` (e.g. `0o644`) replaces the permission bits, including
+    the setuid, setgid and sticky bits. On Windows only the owner write bit
+    is used: without `0o200` the file is made read-only, with it the
+    read-only attribute is removed. Symlinks are followed.
+
+    Args:
+        path: Path to the file or directory.
+        mode: Permission bits, at most `0o7777`.
+
+    Raises:
+        FileNotFoundError: If the path does not exist.
+        PermissionError: If permission is denied (e.g., the file belongs to
+            another user).
+        IOError: If the permissions cannot be changed for another reason.
+        ValueError: If mode has bits above `0o7777` or the path is invalid
+            (empty string or contains null bytes).
+
+    Example:
+        ```python
+        await chmod("deploy.sh", 0o755)
+        ```
+    """
+    await chmod_async(path, mode)
+
+
+async def chown(
+    path: str, uid: int, gid: int, *, follow_symlinks: bool = True
+) -> None:
+    """
+    Change the owning user and group of a file or directory, like `os.chown()`.
+
+    Only the superuser may give files away; other users can change the group
+    to one they belong to. Unix only.
+
+    Args:
+        path: Path to the file or directory.
+        uid: New owning user ID, or -1 to leave it unchanged.
+        gid: New owning group ID, or -1 to leave it unchanged.
+        follow_symlinks: If False, change a symlink itself instead of the
+            file it points to (see `lchown()`). Defaults to True.
+
+    Raises:
+        FileNotFoundError: If the path does not exist.
+        PermissionError: If the process may not change the ownership.
+        IOError: If the ownership cannot be changed for another reason.
+        ValueError: If an ID is not -1 or a valid ID, or the path is invalid
+            (empty string or contains null bytes).
+        NotImplementedError: On platforms other than Unix.
+
+    Example:
+        ```python
+        # Hand a deployed release to the service account
+        await chown("/srv/app/current", pwd.getpwnam("app").pw_uid, -1)
+        ```
+    """
+    await chown_async(path, uid, gid, follow_symlinks)
+
+
+async def lchown(path: str, uid: int, gid: int) -> None:
+    """
+    Change the owning user and group of a path without following symlinks.
+
+    Same as `chown(path, uid, gid, follow_symlinks=False)`, like
+    `os.lchown()`.
+
+    Args:
+        path: Path to the file, directory or symlink.
+        uid: New owning user ID, or -1 to leave it unchanged.
+        gid: New owning group ID, or -1 to leave it unchanged.
+
+    Raises:
+        FileNotFoundError: If the path does not exist.
+        PermissionError: If the process may not change the ownership.
+        IOError: If the ownership cannot be changed for another reason.
+        ValueError: If an ID is not -1 or a valid ID, or the path is invalid.
+        NotImplementedError: On platforms other than Unix.
+    """
+    await chown_async(path, uid, gid, False)
+
+
+async def hard_link(src: str, dst: str) -> None:
+    """
+    Create a hard link asynchronously.
+
+    Creates a hard link from source to destination. Both files will refer
+    to the same underlying file data. Changes to one file will be reflected
+    in the other. Hard links only work within the same filesystem. All I/O
+    operations execute outside the Python GIL using native Rust/Tokio,
+    ensuring true async behavior and preventing event loop stalls.
+
+    Note: Hard links cannot be created for directories on most filesystems.
+
+    Args:
+        src: Path to the source file. Can be a relative or absolute path.
+        dst: Path to the destination link. Can be a relative or absolute path.
+
+    Raises:
+        FileNotFoundError: If the source file does not exist.
+        FileExistsError: If the destination already exists.
+        IOError: If the link cannot be created (e.g., permission denied, or
+            source and destination are on different filesystems).
+        ValueError: If any path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        await hard_link("original.txt", "link.txt")
+        # Both files now refer to the same data
+        ```
+
+    See Also:
+        - `link()`: The same, named after `os.link()`.
+        - `symlink()`: Create a symbolic link (works across filesystems).
+        - `copy_file()`: Create a copy of a file (independent data).
+    """
+    await hard_link_async(src, dst)
+
+
+async def link(src: str, dst: str) -> None:
+    """
+    Create a hard link asynchronously, like `os.link()`.
+
+    Same as `hard_link()`: `dst` becomes another name for the file at
+    `src`, so deduplicated copies share their data on disk.
+
+    Args:
+        src: Path to the existing file.
+        dst: Path of the new link.
+
+    Raises:
+        FileNotFoundError: If the source file does not exist.
+        FileExistsError: If the destination already exists.
+        IOError: If the link cannot be created, e.g. because source and
+            destination are on different filesystems.
+        ValueError: If any path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        try:
+            await link(cached, target)
+        except FileExistsError:
+            pass
+        ```
+    """
+    await link_async(src, dst)
+
+
+async def symlink(src: str, dst: str, target_is_directory: bool = False) -> None:
+    """
+    Create a symbolic link asynchronously.
+
+    Creates a symbolic link from source to destination. The destination will
+    point to the source path. Unlike hard links, symbolic links can point
+    to files on different filesystems and can point to directories. All I/O
+    operations execute outside the Python GIL using native Rust/Tokio,
+    ensuring true async behavior and preventing event loop stalls.
+
+    The source path can be relative or absolute. If relative, it's resolved
+    relative to the symlink's directory.
+
+    Windows has separate file and directory symlinks. A directory symlink is
+    created when `target_is_directory` is True or the target already exists
+    as a directory; set the flag for a directory that doesn't exist yet. On
+    other platforms the flag is ignored, as with `os.symlink()`.
+
+    Args:
+        src: Path that the symlink will point to. Can be a relative or absolute
+            path. The path doesn't need to exist at creation time.
+        dst: Path to the symbolic link to create. Can be a relative or absolute
+            path. Parent directories will not be created automatically.
+        target_is_directory: Create a directory symlink on Windows. Defaults
+            to False.
+
+    Raises:
+        FileExistsError: If the destination already exists.
+        IOError: If the symlink cannot be created (e.g., permission denied, or
+            symlinks need Developer Mode or admin rights on Windows).
+        ValueError: If any path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        await symlink("/path/to/original", "/path/to/link")
+        # link now points to original
+        ```
+
+    See Also:
+        - `hard_link()`: Create a hard link (same filesystem only).
+        - `canonicalize()`: Resolve a symlink to its canonical path.
+    """
+    await symlink_async(src, dst, target_is_directory)
+
+
+async def canonicalize(path: str) -> str:
+    """
+    Canonicalize a path asynchronously.
+
+    Resolves all symbolic links and returns the absolute path. This function
+    resolves all intermediate symlinks in the path and normalizes relative
+    components (e.g., '..' and '.'). All I/O operations execute outside the
+    Python GIL using native Rust/Tokio, ensuring true async behavior and
+    preventing event loop stalls.
+
+    The path must exist for canonicalization to succeed. This is useful for
+    getting the "real" path of a file, especially when dealing with symlinks.
+
+    Args:
+        path: Path to canonicalize. Can be a relative or absolute path.
+
+    Returns:
+        str: Canonical absolute path as a string. All symlinks are resolved
+            and the path is normalized.
+
+    Raises:
+        FileNotFoundError: If the path does not exist.
+        IOError: If the path cannot be canonicalized (e.g., broken symlink,
+            permission denied).
+        ValueError: If the path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        canonical_path = await canonicalize("./relative/path/../file.txt")
+        print(canonical_path)  # /absolute/path/to/file.txt
+        ```
+
+    See Also:
+        - `symlink()`: Create a symbolic link.
+        - `rapfiles.ospath.abspath()`: Get absolute path without resolving symlinks.
     """
     return await canonicalize_async(path)
 
 
+async def realpath(path: str, *, strict: bool = False) -> str:
+    """
+    Resolve a path like `os.path.realpath()`, without blocking the loop.
+
+    Makes the path absolute and resolves symbolic links, '.' and '..'
+    components one at a time, so '..' after a symlink applies to the
+    directory the link led to. Unlike `canonicalize()`, the path does not
+    have to exist by default: it is resolved as far as possible and the
+    rest is kept, which suits paths of files about to be created.
+
+    Args:
+        path: Path to resolve. Can be a relative or absolute path.
+        strict: If True, raise if a component does not exist or a symlink
+            loop is found, like `os.path.realpath(path, strict=True)`.
+            Defaults to False.
+
+    Returns:
+        str: The resolved absolute path.
+
+    Raises:
+        FileNotFoundError: With `strict`, if a component does not exist.
+        IOError: With `strict`, for a symlink loop or another error while
+            examining the path.
+        ValueError: If the path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        # "current" is a symlink to "releases/v2"
+        print(await realpath("current/new.log"))
+        # /srv/app/releases/v2/new.log, even if new.log doesn't exist yet
+        ```
+
+    See Also:
+        - `canonicalize()`: Resolve a path that must exist.
+        - `readlink()`: Read a single symlink without resolving it.
+    """
+    return await realpath_async(path, strict)
+
+
+async def readlink(path: str) -> str:
+    """
+    Return the target a symbolic link points to, like `os.readlink()`.
+
+    The target is returned exactly as stored in the link, without resolving
+    it: a relative target stays relative to the link's directory, and it
+    need not exist. Use `canonicalize()` to resolve a path completely.
+    Targets that are not valid UTF-8 are decoded like `os.fsdecode()`, so
+    `os.fsencode()` gives back the original bytes.
+
+    Args:
+        path: Path of the symbolic link.
+
+    Returns:
+        str: The link target.
+
+    Raises:
+        FileNotFoundError: If the path does not exist.
+        IOError: If the path is not a symbolic link or cannot be read.
+        ValueError: If the path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        await symlink("releases/v2", "current")
+        print(await readlink("current"))  # releases/v2
+        ```
+
+    See Also:
+        - `symlink()`: Create a symbolic link.
+        - `canonicalize()`: Resolve all symlinks to an absolute path.
+    """
+    return await readlink_async(path)
+
+
+# Deduplication
+async def dedupe_tree(
+    root: str, *, method: str = "reflink", dry_run: bool = True
+) -> "DedupeReport":
+    """
+    Find identical files in a tree and replace duplicates with clones or links.
+
+    Non-empty regular files of equal size are hashed, and files with equal
+    hashes are compared byte by byte. In every set of identical files the
+    first path (in sorted order) is kept and the others are replaced by a
+    reflink, a copy-on-write clone sharing its data blocks (Btrfs, XFS,
+    APFS), or a hard link to it. Replacements are renamed into place, so no
+    path is ever missing. Symbolic links are not followed, and hard links
+    of one file are handled together. By default this is a dry run that
+    only reports what would be replaced.
+
+    Only run it on trees nothing is writing to: a file changed after it was
+    compared loses the change.
+
+    Args:
+        root: Root directory to deduplicate.
+        method: "reflink" (default) or "hardlink". Reflinked files stay
+            independent, keeping their own permissions; hard linked files
+            share their content and metadata, so writing to one in place
+            changes all of them.
+        dry_run: If True (default), leave the files unchanged and only
+            report what would be replaced.
+
+    Returns:
+        DedupeReport: With `groups` (sets of identical paths, the kept path
+            first), `duplicates` (number of replaced paths), `reclaimed`
+            (bytes freed) and `dry_run` properties.
+
+    Raises:
+        FileNotFoundError: If `root` does not exist.
+        IOError: If a file cannot be read or replaced, e.g. on a file
+            system without reflink support. Files replaced before the error
+            stay replaced.
+        ValueError: If the path or `method` is invalid.
+
+    Example:
+        ```python
+        report = await dedupe_tree("/srv/builds")
+        print(f"{report.duplicates} duplicates, {report.reclaimed} bytes")
+        if report.reclaimed > 1 << 30:
+            await dedupe_tree("/srv/builds", dry_run=False)
+        ```
+
+    See Also:
+        - `copytree()`: Copy a tree, optionally as a hard link farm.
+        - `hard_link()`: Hard link a single file.
+    """
+    return await dedupe_tree_async(root, method=method, dry_run=dry_run)
+
+
 # Atomic file operations
-async def atomic_write_file(path: str, contents: str) -> None:
+async def atomic_write_file(
+    path: str,
+    contents: str,
+    finalize_read_only: bool = False,
+    *,
+    durability: str = "flush",
+) -> None:
     """
     Write a file atomically using a temporary file.
 
@@ -1017,11 +2202,21 @@ async def atomic_write_file(path: str, contents: str) -> None:
     Args:
         path: Path to the file to write. Can be a relative or absolute path.
         contents: Content to write to the file. Will be encoded as UTF-8.
+        finalize_read_only: If True, drop all write permission bits from the
+            file before it is moved into place, so it never appears writable
+            at `path`. Useful for artifact stores that must not be edited in
+            place. Defaults to False.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir"; see
+            `write_file()`. Without "fsync", a power loss shortly after the
+            rename can leave `path` empty on some filesystems even though
+            the rename itself is atomic; "fsync" syncs the temporary file
+            before the rename, "fsync+dir" also syncs the rename.
 
     Raises:
         IOError: If the file cannot be written (e.g., disk full, I/O error).
         PermissionError: If write permission is denied.
-        ValueError: If the path is invalid (empty string or contains null bytes).
+        ValueError: If the path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
 
     Example:
         ```python
@@ -1034,10 +2229,16 @@ async def atomic_write_file(path: str, contents: str) -> None:
         - `atomic_write_file_bytes()`: Atomic write for binary data.
         - `atomic_move_file()`: Atomic file move operation.
     """
-    await atomic_write_file_async(path, contents)
+    await atomic_write_file_async(path, contents, finalize_read_only, durability)
 
 
-async def atomic_write_file_bytes(path: str, contents: bytes) -> None:
+async def atomic_write_file_bytes(
+    path: str,
+    contents: bytes,
+    finalize_read_only: bool = False,
+    *,
+    durability: str = "flush",
+) -> None:
     """
     Write bytes to a file atomically using a temporary file.
 
@@ -1053,11 +2254,18 @@ async def atomic_write_file_bytes(path: str, contents: bytes) -> None:
     Args:
         path: Path to the file to write. Can be a relative or absolute path.
         contents: Raw bytes to write to the file.
+        finalize_read_only: If True, drop all write permission bits from the
+            file before it is moved into place, so it never appears writable
+            at `path`. Useful for artifact stores that must not be edited in
+            place. Defaults to False.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir"; see
+            `atomic_write_file()`.
 
     Raises:
         IOError: If the file cannot be written (e.g., disk full, I/O error).
         PermissionError: If write permission is denied.
-        ValueError: If the path is invalid (empty string or contains null bytes).
+        ValueError: If the path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
 
     Example:
         ```python
@@ -1070,7 +2278,69 @@ async def atomic_write_file_bytes(path: str, contents: bytes) -> None:
         - `atomic_write_file()`: Atomic write for text data.
         - `atomic_move_file()`: Atomic file move operation.
     """
-    await atomic_write_file_bytes_async(path, contents)
+    await atomic_write_file_bytes_async(path, contents, finalize_read_only, durability)
+
+
+async def write_if_unchanged(
+    path: str,
+    data: Union[str, bytes],
+    expected: Union[None, float, str],
+    *,
+    durability: str = "flush",
+) -> None:
+    """
+    Replace a file only if it has not changed since it was read.
+
+    A compare-and-swap for files that prevents lost updates when several
+    tools edit the same file: read the file, remember its modification time
+    or content digest, and pass it as `expected` when writing the result
+    back. The new contents are written to a temporary file and renamed over
+    `path` only if the file still matches, so it is never left partially
+    written.
+
+    With `expected=None` the file must not exist yet, and the new file is
+    hard-linked into place, so a file created concurrently is never
+    replaced; on filesystems without hard links this raises IOError.
+    Otherwise the check happens right before the rename; writers that must
+    never race should additionally hold `lock_file()`.
+
+    Args:
+        path: Path to the file to replace.
+        data: New contents; str is encoded as UTF-8.
+        expected: What the file must still look like: None if it must not
+            exist, the modification time the caller saw
+            (`(await stat(path)).modified`), or the hex SHA-256 digest of
+            the contents the caller read. Digests also catch edits within
+            the filesystem's timestamp resolution, at the cost of reading
+            the file again.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir"; see
+            `atomic_write_file()`.
+
+    Raises:
+        FileChangedError: If the file no longer matches `expected`; the file
+            is left untouched.
+        IOError: If the file cannot be written, or with `expected=None`
+            cannot be linked into place.
+        TypeError: If `data` or `expected` has an unsupported type.
+        ValueError: If the path, the digest or `durability` is invalid.
+
+    Example:
+        ```python
+        import hashlib
+
+        data = await read_file_bytes("notes.md")
+        digest = hashlib.sha256(data).hexdigest()
+        try:
+            await write_if_unchanged("notes.md", edit(data), digest)
+        except FileChangedError:
+            ...  # Someone else saved first: reload and merge
+        ```
+
+    See Also:
+        - `atomic_write_file_bytes()`: Atomic write without the check.
+        - `lock_file()`: Exclusive lock for writers that must never race.
+    """
+    await write_if_unchanged_async(path, data, expected, durability)
 
 
 async def atomic_move_file(src: str, dst: str) -> None:
@@ -1110,15 +2380,20 @@ async def atomic_move_file(src: str, dst: str) -> None:
 
 
 # File locking operations
-class _LockContextManager:
-    """Internal async context manager wrapper for lock_file."""
+_L = TypeVar("_L", FileLock, FileLease)
+
 
-    def __init__(self, coro: Coroutine[Any, Any, "FileLock"]) -> None:
-        self._coro: Coroutine[Any, Any, "FileLock"] = coro
-        self._lock: Optional["FileLock"] = None
+class _LockContextManager(Generic[_L]):
+    """Internal async context manager wrapper for lock_file and lease_file."""
 
-    async def __aenter__(self) -> "FileLock":
-        self._lock = await self._coro
+    def __init__(self, acquire: Callable[[], Awaitable[_L]]) -> None:
+        # The lock future is created on entry, so it belongs to the loop that
+        # is running then rather than the one (if any) running at creation
+        self._acquire: Callable[[], Awaitable[_L]] = acquire
+        self._lock: Optional[_L] = None
+
+    async def __aenter__(self) -> _L:
+        self._lock = await self._acquire()
         return self._lock.__aenter__()
 
     async def __aexit__(
@@ -1132,7 +2407,7 @@ class _LockContextManager:
         return None
 
 
-def lock_file(path: str, exclusive: bool = True) -> _LockContextManager:
+def lock_file(path: str, exclusive: bool = True) -> _LockContextManager[FileLock]:
     """
     Lock a file asynchronously with advisory file locking.
 
@@ -1184,11 +2459,10 @@ def lock_file(path: str, exclusive: bool = True) -> _LockContextManager:
         - `lock_file_shared()`: Convenience function for shared locks.
         - `atomic_write_file()`: Atomic writes for data integrity.
     """
-    coro = lock_file_async(path, exclusive)
-    return _LockContextManager(coro)
+    return _LockContextManager(lambda: lock_file_async(path, exclusive))
 
 
-def lock_file_shared(path: str) -> _LockContextManager:
+def lock_file_shared(path: str) -> _LockContextManager[FileLock]:
     """
     Lock a file with shared (read) lock asynchronously.
 
@@ -1224,194 +2498,1836 @@ def lock_file_shared(path: str) -> _LockContextManager:
         - `lock_file()`: Lock a file with exclusive or shared lock.
         - `atomic_write_file()`: Atomic writes for data integrity.
     """
-    coro = lock_file_async(path, exclusive=False)
-    return _LockContextManager(coro)
+    return _LockContextManager(lambda: lock_file_async(path, exclusive=False))
 
 
-# Batch operations
-async def read_files(paths: List[str]) -> List[Tuple[str, bytes]]:
+def lease_file(
+    path: str,
+    ttl: float = 30.0,
+    owner: Optional[str] = None,
+    timeout: Optional[float] = None,
+    poll_interval: float = 1.0,
+) -> _LockContextManager[FileLease]:
     """
-    Read multiple files concurrently.
+    Acquire an exclusive lease on a file that expires unless renewed.
 
-    Reads all specified files concurrently and returns their contents as a
-    list of tuples. All files are read in parallel, significantly improving
-    performance when processing many files. All I/O operations execute outside
-    the Python GIL using native Rust/Tokio, ensuring true async behavior and
-    preventing event loop stalls.
+    A lease is recorded in the sidecar file `path + ".lease"` together with
+    its owner and expiry time, and renewed in the background every third of
+    `ttl` while it is held. If the holder crashes, renewal stops and other
+    processes can take the lease over once it has expired, so a crashed
+    cron job cannot block the next run forever. Unlike `lock_file()`, leases
+    do not depend on OS locks and therefore also coordinate processes on
+    different hosts sharing a network filesystem, as long as their clocks
+    roughly agree.
+
+    Like `lock_file()`, leases are advisory: they only coordinate processes
+    that acquire them.
 
     Args:
-        paths: List of file paths to read. Can contain relative or absolute paths.
+        path: Path the lease protects. The file itself is not created or
+            modified.
+        ttl: Seconds the lease lasts after each renewal. Defaults to 30.
+        owner: Name recorded in the sidecar file and reported to processes
+            waiting for the lease. Defaults to `"<hostname>:<pid>"`.
+        timeout: Seconds to wait for a lease held by someone else; None
+            waits indefinitely and 0 tries once. Defaults to None.
+        poll_interval: Seconds between attempts while waiting. Defaults to 1.
 
     Returns:
-        List[Tuple[str, bytes]]: List of (path, bytes) tuples where:
-            - path (str): The file path (same as input)
-            - bytes (bytes): The file contents as raw bytes
-
-        The order of results matches the order of input paths.
+        _LockContextManager: An async context manager that yields a
+            `FileLease` and releases it (removing the sidecar file) on exit.
 
     Raises:
-        FileNotFoundError: If any file does not exist.
-        IOError: If any file cannot be read (e.g., permission denied).
-        ValueError: If any path is invalid (empty string or contains null bytes).
+        TimeoutError: If the lease is still held when `timeout` expires.
+        IOError: If the sidecar file cannot be written.
+        ValueError: If the path is invalid or `ttl`, `timeout` or
+            `poll_interval` is not a valid number of seconds.
 
     Example:
         ```python
-        files = await read_files(["file1.txt", "file2.txt", "file3.txt"])
-        for path, content in files:
-            print(f"{path}: {len(content)} bytes")
+        async with lease_file("/shared/jobs/nightly", ttl=60, timeout=0) as lease:
+            for batch in batches:
+                if not lease.held:
+                    raise RuntimeError("lease lost, stopping")
+                await process(batch)
+        ```
+
+    See Also:
+        - `lock_file()`: OS advisory lock, released when the process exits.
+    """
+    return _LockContextManager(
+        lambda: lease_file_async(path, ttl, owner, timeout, poll_interval)
+    )
+
+
+# Log reading
+async def open_log(
+    path: str, from_end: bool = False, poll_interval: float = 0.1
+) -> LogReader:
+    """
+    Start following a log file that other processes append to.
+
+    The returned `LogReader` remembers how far it has read: `read_new()`
+    returns only the bytes appended since the previous call and
+    `read_lines()` only complete lines, holding back a partial last line
+    until the writer finishes it. Reads use positional I/O, so they never
+    disturb the writer. A file truncated in place is read again from the
+    start; after a rotation (a new file created at the path) the rest of
+    the old file is read before the reader switches to the new one.
+
+    Args:
+        path: Path of the log file. It may not exist yet; the reader picks
+            it up once it is created.
+        from_end: If True, skip the data already in the file and only return
+            what is appended from now on. Defaults to False.
+        poll_interval: Seconds between checks for new data in `wait()` and
+            `async for`. Defaults to 0.1.
+
+    Returns:
+        LogReader: The reader, also usable with `async for` to follow the
+            file line by line until `close()` is called.
+
+    Raises:
+        IOError: If the file exists but cannot be opened.
+        ValueError: If the path is invalid or `poll_interval` is not positive.
+
+    Example:
+        ```python
+        reader = await open_log("/var/log/app.log", from_end=True)
+        async for line in reader:
+            print(line.decode(), end="")
+        ```
+    """
+    return await open_log_async(path, from_end, poll_interval)
+
+
+# Directory watching
+async def watch(
+    path: str,
+    emit_existing: bool = True,
+    recursive: bool = True,
+    poll_interval: float = 1.0,
+    journal: Optional[str] = None,
+    resume_token: Optional[str] = None,
+    emit_when_stable: Optional[float] = None,
+) -> DirWatcher:
+    """
+    Watch a directory tree for created, modified and deleted entries.
+
+    With `emit_existing`, iteration starts with a "created" event (with
+    `existing=True`) for every entry already in the tree, directories before
+    their contents, followed by the live events. Consumers can therefore
+    build their state from the event stream alone, without a separate scan
+    that could miss changes made between the scan and the start of watching.
+
+    The tree is polled: it is scanned every `poll_interval` seconds while the
+    watcher waits for events, and compared with the previous scan. This
+    works on every platform and on network filesystems, but a change that
+    keeps both the size and the modification time of a file goes unnoticed.
+    Symbolic links are reported as entries and not followed.
+
+    With a `journal`, every event is appended to the journal file before it
+    is returned and carries a `token`. A daemon that stores the token of the
+    last event it processed can pass it as `resume_token` after a restart:
+    the watcher then replays the recorded events that followed it and
+    reports the changes made to the tree while nothing was watching, as
+    found by comparing it with the journal. Each resume rewrites the
+    journal without the events before `resume_token`, so it stays small.
+
+    Args:
+        path: Directory to watch. Its initial contents are recorded before
+            this coroutine completes, so later changes are reported even if
+            iteration starts afterwards.
+        emit_existing: If True (default), first emit "created" events for the
+            current contents.
+        recursive: If True (default), watch subdirectories too; otherwise
+            only the direct entries of `path`.
+        poll_interval: Seconds between scans. Defaults to 1. Each scan reads
+            the metadata of every entry, so lower it for small trees only.
+        journal: Path of a file to record events in and resume from. A
+            journal belongs to one watched path and should be kept outside
+            the watched tree; `emit_existing` only applies when the journal
+            does not exist yet.
+        resume_token: `token` of the last event processed. If None, every
+            event recorded in the journal since it was last resumed is
+            replayed. Requires `journal`.
+        emit_when_stable: Seconds a file's size and modification time must
+            stay unchanged before it is reported as created or modified.
+            Use this for hot folders, so that files still being copied in
+            are not picked up half-written; a file removed before it settles
+            is not reported. Directories and deletions are reported at once.
+
+    Returns:
+        DirWatcher: An async iterator of `WatchEvent` objects with `kind`
+            ("created", "modified" or "deleted"), `path`, `is_dir`,
+            `existing` and `token`. Iteration continues until `close()` is
+            called.
+
+    Raises:
+        FileNotFoundError: If the directory does not exist.
+        IOError: If the directory or the journal cannot be read or written.
+        ValueError: If the path is invalid, `poll_interval` is not positive,
+            the journal belongs to another path, `resume_token` is not in
+            the journal or `emit_when_stable` is not positive.
+
+    Example:
+        ```python
+        files = set()
+        watcher = await watch("inbox")
+        async for event in watcher:
+            if event.kind == "deleted":
+                files.discard(event.path)
+            elif not event.is_dir:
+                files.add(event.path)
+
+        # Resume where the previous run stopped
+        watcher = await watch("inbox", journal="inbox.journal",
+                              resume_token=load_token())
+        async for event in watcher:
+            await ingest(event)
+            save_token(event.token)
+        ```
+    """
+    return await watch_async(
+        path,
+        emit_existing,
+        recursive,
+        poll_interval,
+        journal,
+        resume_token,
+        emit_when_stable,
+    )
+
+
+# Config file reads
+async def read_toml(path: str) -> Dict[str, Any]:
+    """
+    Read and parse a TOML file.
+
+    The file is read and parsed in Rust without the GIL, so loading large
+    config files does not stall the event loop. The result has the same
+    types as `tomllib.load()`: tables are dicts (in document order), arrays
+    are lists, and dates and times are `datetime.datetime`, `datetime.date`
+    or `datetime.time` objects (offset date-times are timezone-aware).
+
+    Args:
+        path: Path to the TOML file.
+
+    Returns:
+        Dict[str, Any]: The parsed document.
+
+    Raises:
+        FileNotFoundError: If the file does not exist.
+        ValueError: If the file is not valid UTF-8 or not valid TOML, or the
+            path is invalid.
+        IOError: If the file cannot be read.
+
+    Example:
+        ```python
+        project = await read_toml("pyproject.toml")
+        print(project["project"]["name"])
+        ```
+
+    See Also:
+        - `read_yaml()`: Read and parse a YAML file.
+        - `update_toml()`: Locked read-modify-write of a TOML file.
+    """
+    return await read_toml_async(path)
+
+
+async def read_yaml(path: str) -> Any:
+    """
+    Read and parse a YAML file.
+
+    The file is read and parsed in Rust without the GIL. Values are plain
+    Python objects like those of `yaml.safe_load()`: mappings are dicts,
+    sequences are lists and scalars are str, int, float, bool or None. Merge
+    keys (`<<`) are applied and custom tags are ignored. Timestamps are
+    returned as strings, and only a single document is accepted.
+
+    YAML support is compiled in by default; builds without the `yaml` cargo
+    feature raise NotImplementedError.
+
+    Args:
+        path: Path to the YAML file.
+
+    Returns:
+        Any: The parsed document (None for an empty file).
+
+    Raises:
+        FileNotFoundError: If the file does not exist.
+        ValueError: If the file is not valid UTF-8 or not valid YAML, or the
+            path is invalid.
+        IOError: If the file cannot be read.
+        NotImplementedError: If rapfiles was built without YAML support.
+
+    Example:
+        ```python
+        config = await read_yaml("config.yaml")
+        workers = config["server"]["workers"]
+        ```
+
+    See Also:
+        - `read_toml()`: Read and parse a TOML file.
+    """
+    return await read_yaml_async(path)
+
+
+# Config file updates
+async def _update_config(
+    path: str,
+    mutate: Callable[[Any], Any],
+    default: Any,
+    loads: Callable[[str], Any],
+    dumps: Callable[[Any], str],
+    durability: str,
+) -> Any:
+    import copy
+    import inspect
+
+    # Lock a sidecar file: the config itself is replaced on every update, so a
+    # lock on it would not be seen by writers that open the new file
+    async with lock_file(path + ".lock"):
+        try:
+            data = loads(await read_file(path))
+        except FileNotFoundError:
+            if default is None:
+                raise
+            data = copy.deepcopy(default)
+        result = mutate(data)
+        if inspect.isawaitable(result):
+            result = await result
+        if result is not None:
+            data = result
+        await atomic_write_file(path, dumps(data), durability=durability)
+    return data
+
+
+async def update_json(
+    path: str,
+    mutate: Callable[[Any], Any],
+    default: Any = None,
+    indent: Optional[int] = 2,
+    *,
+    durability: str = "flush",
+) -> Any:
+    """
+    Read a JSON file, apply a change and write it back atomically.
+
+    The read-modify-write cycle holds an exclusive lock on `path + ".lock"`
+    (created next to the file and left in place), so concurrent updates from
+    other tasks or processes using this function are never lost. The new
+    content replaces the file atomically, so readers see either the old or
+    the new version.
+
+    Args:
+        path: Path to the JSON file.
+        mutate: Callback receiving the parsed data. It may modify the data
+            in place and return None, or return the new data. Coroutine
+            functions are awaited.
+        default: Data to start from if the file doesn't exist (a deep copy
+            is passed to `mutate`). If None, a missing file raises.
+        indent: Indentation of the written JSON, or None for a single line.
+            Defaults to 2.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir" for
+            the atomic write; see `atomic_write_file()`.
+
+    Returns:
+        Any: The data that was written.
+
+    Raises:
+        FileNotFoundError: If the file doesn't exist and no `default` is given.
+        ValueError: If the file is not valid JSON (`json.JSONDecodeError`) or
+            `durability` is unknown.
+        IOError: If the file cannot be read, locked or written.
+
+    Example:
+        ```python
+        def enable_feature(config):
+            config.setdefault("features", []).append("beta")
+
+        config = await update_json("config.json", enable_feature, default={})
+        ```
+
+    See Also:
+        - `update_toml()`: The same for TOML files.
+        - `atomic_write_file()`: Atomic writes for data integrity.
+    """
+    import json
+
+    return await _update_config(
+        path,
+        mutate,
+        default,
+        json.loads,
+        lambda data: json.dumps(data, indent=indent) + "\n",
+        durability,
+    )
+
+
+async def update_toml(
+    path: str,
+    mutate: Callable[[Any], Any],
+    default: Optional[Dict[str, Any]] = None,
+    *,
+    durability: str = "flush",
+) -> Dict[str, Any]:
+    """
+    Read a TOML file, apply a change and write it back atomically.
+
+    Works like `update_json()`, including the lock on `path + ".lock"`.
+    The table has the same value types as `read_toml()`, and is written
+    back with the same types (`None` has no TOML equivalent). Comments and
+    formatting of the original file are not preserved.
+
+    Args:
+        path: Path to the TOML file.
+        mutate: Callback receiving the parsed table. It may modify it in place
+            and return None, or return a new table. Coroutine functions are
+            awaited.
+        default: Table to start from if the file doesn't exist. If None, a
+            missing file raises.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir" for
+            the atomic write; see `atomic_write_file()`.
+
+    Returns:
+        Dict[str, Any]: The table that was written.
+
+    Raises:
+        FileNotFoundError: If the file doesn't exist and no `default` is given.
+        ValueError: If the file is not valid TOML or `durability` is unknown.
+        TypeError: If the table holds a value TOML can't represent; the file
+            is left unchanged.
+        IOError: If the file cannot be read, locked or written.
+
+    Example:
+        ```python
+        def bump(config):
+            config["tool"]["app"]["version"] = "1.2.0"
+
+        await update_toml("pyproject.toml", bump)
+        ```
+    """
+    return await _update_config(
+        path, mutate, default, _toml_loads, _toml_dumps, durability
+    )
+
+
+async def save_config(
+    path: str,
+    data: Any,
+    *,
+    format: str = "json",
+    backup: bool = True,
+    mode: Optional[int] = 0o600,
+    durability: str = "fsync+dir",
+) -> None:
+    """
+    Save a config file the safe way.
+
+    Serializes `data`, keeps the previous file as `path + ".bak"`, writes
+    the new contents to a temporary file created with `mode`, syncs it to
+    disk and atomically renames it over `path`, then syncs the directory.
+    Readers see the old or the new file, never a partial one; a power loss
+    can't leave an empty file behind; and secrets are never readable with
+    looser permissions, not even briefly.
+
+    Args:
+        path: Path of the config file.
+        data: Data to save; a dict for TOML.
+        format: "json" (default; indented by 2 spaces) or "toml".
+        backup: Keep the previous version as `path + ".bak"`, replacing an
+            older backup. Defaults to True.
+        mode: Permission bits of the new file, applied regardless of the
+            umask (Unix only). Defaults to 0o600, readable by the owner
+            only; None uses the default permissions.
+        durability: "none", "flush", "fsync" or "fsync+dir" (default); see
+            `write_file()`. Lower presets skip syncing the directory, or the
+            file too, for configs that are cheap to regenerate.
+
+    Raises:
+        ValueError: If `format` or `durability` is unknown or the path is
+            invalid.
+        TypeError: If `data` can't be serialized; nothing is written.
+        IOError: If the file cannot be written.
+
+    Example:
+        ```python
+        await save_config("credentials.json", {"token": token})
+        await save_config("app.toml", settings, format="toml", mode=0o644)
+        ```
+
+    See Also:
+        - `update_json()`: Locked read-modify-write of a JSON file.
+        - `atomic_write_file()`: Atomic write of arbitrary text.
+    """
+    if format == "json":
+        import json
+
+        text = json.dumps(data, indent=2) + "\n"
+    elif format == "toml":
+        text = _toml_dumps(data)
+    else:
+        raise ValueError(f"Invalid format: {format}. Must be 'json' or 'toml'")
+    await save_config_async(path, text.encode(), backup, mode, durability)
+
+
+# Positional I/O
+async def read_ranges(
+    path: str,
+    ranges: List[Tuple[int, int]],
+    coalesce_gap: Optional[int] = None,
+) -> List[bytes]:
+    """
+    Read several byte ranges of a file in one call.
+
+    Each range is read with a positional read, so the ranges do not need to be
+    sorted and the file position is never moved. With `coalesce_gap`, ranges
+    that overlap or lie at most `coalesce_gap` bytes apart are merged into a
+    single larger read in Rust and then sliced, which turns access patterns
+    such as a Parquet footer plus its column pages, or a zip central directory
+    plus local headers, into a handful of reads. All I/O operations execute
+    outside the Python GIL.
+
+    Args:
+        path: Path to the file to read.
+        ranges: List of (offset, length) tuples.
+        coalesce_gap: Largest gap in bytes between two ranges that still
+            merges them into one read. 0 merges only overlapping and adjacent
+            ranges. If None (default), every range is read separately.
+
+    Returns:
+        List[bytes]: The data of each range, in the order of `ranges`. A range
+            that extends past the end of the file is truncated (empty if it
+            starts past the end).
+
+    Raises:
+        FileNotFoundError: If the file does not exist.
+        IOError: If the file cannot be read (e.g., permission denied).
+        ValueError: If the path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        size = (await rapfiles.stat("data.parquet")).size
+        footer, magic = await read_ranges(
+            "data.parquet", [(size - 8, 4), (size - 4, 4)], coalesce_gap=0
+        )
+        # Both ranges are served by a single 8-byte read
+        ```
+
+    See Also:
+        - `read_file_bytes()`: Read a whole file as bytes.
+        - `open()`: Open a file handle for sequential reads with `seek()`.
+    """
+    return await read_ranges_async(path, ranges, coalesce_gap)
+
+
+async def write_segments(
+    path: str, segments: List[Tuple[int, bytes]], *, durability: str = "fsync"
+) -> None:
+    """
+    Write several byte segments into a file at known offsets.
+
+    Segments are written with positional writes in the given order and, by
+    default, the file is synced to disk once at the end, so patching many fields of a
+    binary file costs a single `fsync`. Consecutive segments that continue
+    exactly where the previous one ended are merged into one write. The file
+    is created if it does not exist and is never truncated; writing past its
+    end extends it. All I/O operations execute outside the Python GIL.
+
+    Args:
+        path: Path to the file to patch.
+        segments: List of (offset, data) tuples. Where segments overlap, the
+            later one wins.
+        durability: "none", "flush", "fsync" (default) or "fsync+dir"; see
+            `write_file()`. "none" and "flush" skip the `fsync`.
+
+    Raises:
+        IOError: If the file cannot be written (e.g., permission denied, disk
+            full).
+        ValueError: If the path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
+
+    Example:
+        ```python
+        # Patch the record count in a header and append a trailer
+        await write_segments(
+            "archive.bin",
+            [(8, count.to_bytes(4, "little")), (end, b"TRAILER")],
+        )
+        ```
+
+    See Also:
+        - `read_ranges()`: Read several byte ranges of a file.
+        - `atomic_write_file_bytes()`: Replace a whole file atomically.
+    """
+    await write_segments_async(path, segments, durability)
+
+
+async def list_extents(path: str) -> List[Tuple[int, int]]:
+    """
+    List the data extents of a (possibly sparse) file.
+
+    Returns the regions of the file that hold data, so backup and copy tools
+    can skip holes and transfer only allocated bytes (e.g. with
+    `read_ranges()`). Extents are found with `SEEK_DATA`/`SEEK_HOLE` where
+    the platform supports them; on other platforms, and on filesystems that
+    do not track holes, the whole file is reported as a single extent. All
+    I/O operations execute outside the Python GIL.
+
+    Args:
+        path: Path to the file to inspect.
+
+    Returns:
+        List[Tuple[int, int]]: (offset, length) tuples in ascending offset
+            order. Gaps between extents are holes that read as zero bytes. An
+            empty file yields an empty list.
+
+    Raises:
+        FileNotFoundError: If the file does not exist.
+        IOError: If the file cannot be inspected (e.g., permission denied).
+        ValueError: If the path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        extents = await list_extents("disk.img")
+        chunks = await read_ranges("disk.img", extents)
+        # Later, restore only the data regions into a sparse copy
+        offsets = [offset for offset, _ in extents]
+        await write_segments("restored.img", list(zip(offsets, chunks)))
+        ```
+
+    See Also:
+        - `read_ranges()`: Read the extents' data.
+        - `write_segments()`: Write data back at the same offsets.
+    """
+    return await list_extents_async(path)
+
+
+async def create_filled(
+    path: str,
+    size: int,
+    fill: str = "zero",
+    *,
+    seed: Optional[int] = None,
+    durability: str = "fsync",
+) -> None:
+    """
+    Create a file of a given size filled with zeros or random data.
+
+    Meant for generating test data and for disk burn-in tools. Zero fill
+    reserves the space with `fallocate` where available, so the blocks are
+    allocated without writing them; elsewhere the file is extended and may
+    be sparse. Random fill streams pseudo-random bytes generated in Rust. An
+    existing file is replaced, and by default the file is synced to disk
+    before the call returns. All I/O operations execute outside the Python
+    GIL.
+
+    Args:
+        path: Path to the file to create.
+        size: Size of the file in bytes.
+        fill: "zero" (default) or "random".
+        seed: Seed for random fill, so the same seed gives the same contents.
+            Random if None.
+        durability: "none", "flush", "fsync" (default) or "fsync+dir"; see
+            `write_file()`. Test fixtures that are thrown away can skip the
+            `fsync` with "flush".
+
+    Raises:
+        IOError: If the file cannot be created or written (e.g., disk full).
+        ValueError: If `fill` or `durability` is unknown or the path is
+            invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        # 1 GiB of allocated zeros
+        await create_filled("disk.img", 1 << 30)
+        # Reproducible incompressible test data
+        await create_filled("payload.bin", 10_000_000, "random", seed=42)
+        ```
+    """
+    await create_filled_async(path, size, fill, seed=seed, durability=durability)
+
+
+# Batch operations
+async def read_files(paths: List[str]) -> List[Tuple[str, bytes]]:
+    """
+    Read multiple files concurrently.
+
+    Reads all specified files concurrently and returns their contents as a
+    list of tuples. All files are read in parallel, significantly improving
+    performance when processing many files. All I/O operations execute outside
+    the Python GIL using native Rust/Tokio, ensuring true async behavior and
+    preventing event loop stalls.
+
+    Args:
+        paths: List of file paths to read. Can contain relative or absolute paths.
+
+    Returns:
+        List[Tuple[str, bytes]]: List of (path, bytes) tuples where:
+            - path (str): The file path (same as input)
+            - bytes (bytes): The file contents as raw bytes
+
+        The order of results matches the order of input paths.
+
+    Raises:
+        FileNotFoundError: If any file does not exist.
+        IOError: If any file cannot be read (e.g., permission denied).
+        ValueError: If any path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        files = await read_files(["file1.txt", "file2.txt", "file3.txt"])
+        for path, content in files:
+            print(f"{path}: {len(content)} bytes")
         # Output example:
         # file1.txt: 12 bytes
         # file2.txt: 15 bytes
         # file3.txt: 8 bytes
         ```
 
-    See Also:
-        - `read_files_dict()`: Read files and return as dictionary.
-        - `read_file()`: Read a single file.
-        - `read_file_bytes()`: Read a single file as bytes.
+    See Also:
+        - `read_files_dict()`: Read files and return as dictionary.
+        - `read_file()`: Read a single file.
+        - `read_file_bytes()`: Read a single file as bytes.
+    """
+    results = await read_files_async(paths)
+    # Convert results to list of (path, bytes) tuples, raising on error
+    output = []
+    for path, result in results:
+        if isinstance(result, bytes):
+            output.append((path, result))
+        elif isinstance(result, str):
+            raise IOError(result)
+        elif isinstance(result, Exception):
+            raise result
+        else:
+            output.append((path, result))
+    return output
+
+
+async def read_files_dict(paths: List[str]) -> Dict[str, bytes]:
+    """
+    Read multiple files concurrently and return as dictionary.
+
+    Reads all specified files concurrently and returns their contents as a
+    dictionary mapping paths to contents. This is a convenience wrapper around
+    `read_files()` that returns a dictionary instead of a list of tuples.
+    All I/O operations execute outside the Python GIL using native Rust/Tokio,
+    ensuring true async behavior and preventing event loop stalls.
+
+    Args:
+        paths: List of file paths to read. Can contain relative or absolute paths.
+
+    Returns:
+        Dict[str, bytes]: Dictionary mapping file paths (str) to their contents
+            (bytes). Keys are the same as the input paths.
+
+    Raises:
+        FileNotFoundError: If any file does not exist.
+        IOError: If any file cannot be read (e.g., permission denied).
+        ValueError: If any path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        files = await read_files_dict(["file1.txt", "file2.txt"])
+        print(files["file1.txt"])  # b"content"
+        # Access files by path: files["file1.txt"]
+        ```
+
+    See Also:
+        - `read_files()`: Read files and return as list of tuples.
+        - `read_file()`: Read a single file.
+    """
+    results = await read_files(paths)
+    return dict(results)
+
+
+async def write_files(files: Dict[str, bytes], *, durability: str = "flush") -> None:
+    """
+    Write multiple files concurrently.
+
+    Writes contents to all specified files concurrently. All files are written
+    in parallel, significantly improving performance when processing many files.
+    If a destination file exists, it will be overwritten. All I/O operations
+    execute outside the Python GIL using native Rust/Tokio, ensuring true async
+    behavior and preventing event loop stalls.
+
+    Args:
+        files: Dictionary mapping file paths (str) to their contents (bytes).
+            Keys are file paths, values are the bytes to write.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir",
+            applied to each file; see `write_file()`.
+
+    Raises:
+        IOError: If any file cannot be written (e.g., disk full, I/O error).
+        PermissionError: If write permission is denied for any file.
+        ValueError: If any path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
+
+    Example:
+        ```python
+        files = {
+            "file1.txt": b"content1",
+            "file2.txt": b"content2",
+        }
+        await write_files(files)
+        # All files are written concurrently
+        ```
+
+    See Also:
+        - `write_file()`: Write a single file.
+        - `write_file_bytes()`: Write a single file as bytes.
+        - `read_files_dict()`: Read multiple files as dictionary.
+    """
+    # Convert dict to list of (path, bytes) tuples for Rust function
+    # PyO3 will automatically convert Python bytes to Vec<u8>
+    files_list = [(path, contents) for path, contents in files.items()]
+    results = await write_files_async(files_list, durability)
+
+    # Check for errors
+    for path, result in results:
+        if isinstance(result, str):
+            raise IOError(result)
+
+
+async def copy_files(
+    files: List[Tuple[str, str]],
+    preserve_owner: bool = False,
+    *,
+    durability: str = "flush",
+) -> None:
+    """
+    Copy multiple files concurrently.
+
+    Copies all specified files concurrently. All files are copied in parallel,
+    significantly improving performance when processing many files. If a
+    destination file exists, it will be overwritten. All I/O operations execute
+    outside the Python GIL using native Rust/Tokio, ensuring true async behavior
+    and preventing event loop stalls.
+
+    Args:
+        files: List of (src, dst) tuples where:
+            - src (str): Path to the source file
+            - dst (str): Path to the destination file
+        preserve_owner: If True, give each destination its source's owning
+            user and group (usually requires root; ignored on non-Unix
+            platforms). Defaults to False.
+        durability: "none", "flush" (default), "fsync" or "fsync+dir",
+            applied to each copy; see `write_file()`.
+
+    Raises:
+        FileNotFoundError: If any source file does not exist.
+        IOError: If any file cannot be copied (e.g., disk full, permission denied).
+        ValueError: If any path is invalid (empty string or contains null
+            bytes) or `durability` is unknown.
+
+    Example:
+        ```python
+        files = [
+            ("source1.txt", "dest1.txt"),
+            ("source2.txt", "dest2.txt"),
+        ]
+        await copy_files(files)
+        # All files are copied concurrently
+        ```
+
+    See Also:
+        - `copy_file()`: Copy a single file.
+        - `move_file()`: Move a file (removes source).
+    """
+    results = await copy_files_async(files, preserve_owner, durability)
+
+    # Check for errors
+    for src, dst, result in results:
+        if isinstance(result, str):
+            raise IOError(result)
+
+
+async def rename_many(pairs: List[Tuple[str, str]]) -> None:
+    """
+    Rename many files concurrently, all or nothing.
+
+    The whole batch is checked before anything is renamed: every source must
+    exist, no two pairs may share a source or a destination, and no
+    destination may exist unless the batch renames it away, so chains
+    (a -> b, b -> c) and swaps (a -> b, b -> a) work. If a rename fails, the
+    renames already done are undone and the error is raised. Renames are
+    not atomic as a group: other processes can observe the intermediate
+    state, and a destination created by another process after the checks
+    may be overwritten.
+
+    Args:
+        pairs: List of (src, dst) tuples. Each source must be on the same
+            filesystem as its destination.
+
+    Raises:
+        FileNotFoundError: If a source does not exist.
+        FileExistsError: If a destination already exists.
+        IOError: If a rename fails; the message also lists any renames that
+            could not be undone.
+        ValueError: If a path is invalid or two pairs share a source or a
+            destination.
+
+    Example:
+        ```python
+        await rename_many([
+            ("raw/IMG_001.JPG", "raw/img_001.jpg"),
+            ("raw/IMG_002.JPG", "raw/img_002.jpg"),
+        ])
+        ```
+
+    See Also:
+        - `rename_pattern()`: Build the pairs from a name pattern.
+        - `rename()`: Rename a single file.
+    """
+    await rename_many_async(pairs)
+
+
+async def rename_pattern(
+    path: str, pattern: str, template: str
+) -> List[Tuple[str, str]]:
+    """
+    Rename the entries of a directory whose names match a pattern.
+
+    Each entry of `path` whose name matches `pattern` (fnmatch-style: `*`,
+    `?` and `[...]`) is renamed to `template` filled in from the match. The
+    renames run as one batch with the checks and rollback of
+    `rename_many()`, which makes this suitable for normalizing the file
+    names of a dataset in one step.
+
+    Template fields:
+        - `{0}`: the whole old name
+        - `{1}`, `{2}`, ...: the text matched by each wildcard of the pattern
+        - `{n}`: position of the entry among the matches in name order,
+          from 1; `{n:04}` pads it with zeros
+        - `{{` and `}}`: literal braces
+
+    Args:
+        path: Directory whose entries are renamed.
+        pattern: Pattern the whole name must match.
+        template: New name, relative to `path`.
+
+    Returns:
+        List[Tuple[str, str]]: The (src, dst) pairs renamed, in name order.
+
+    Raises:
+        FileNotFoundError: If the directory does not exist.
+        FileExistsError: If a new name is already taken.
+        IOError: If a rename fails (the batch is rolled back).
+        ValueError: If the path or template is invalid, the template uses
+            more wildcards than the pattern has, or two entries get the
+            same new name.
+
+    Example:
+        ```python
+        # IMG_0001.JPG, IMG_0002.JPG, ... -> photo_1.jpg, photo_2.jpg, ...
+        await rename_pattern("raw", "IMG_*.JPG", "photo_{n}.jpg")
+        # scan-2024-01-05.tif -> 2024/01/05.tif (the directories must exist)
+        await rename_pattern("scans", "scan-*-*-*.tif", "{1}/{2}/{3}.tif")
+        ```
+    """
+    return await rename_pattern_async(path, pattern, template)
+
+
+async def generate_tree(
+    root: str,
+    n_dirs: int,
+    n_files: int,
+    size_distribution: Union[int, Tuple[int, int]] = (0, 4096),
+    name_seed: int = 0,
+) -> List[str]:
+    """
+    Generate a reproducible tree of synthetic directories and files.
+
+    Builds fixtures for benchmarks and stress tests of file tooling. Each
+    directory is placed in `root` or in a directory generated before it, and
+    each file in one of these, so the tree has a random shape. Names, sizes
+    and contents are derived from `name_seed`, so the same arguments always
+    produce the same tree. The files are written concurrently.
+
+    Args:
+        root: Directory to generate into, created if missing. Files that
+            already exist at generated paths are replaced.
+        n_dirs: Number of directories to create below `root`.
+        n_files: Number of files to create.
+        size_distribution: File size in bytes, either fixed or a
+            (min, max) range (inclusive) that sizes are drawn from uniformly.
+            Defaults to (0, 4096).
+        name_seed: Seed for the names, layout and contents. Defaults to 0.
+
+    Returns:
+        List[str]: Paths of the created files, in creation order.
+
+    Raises:
+        IOError: If a directory or file cannot be created (e.g., disk full).
+        ValueError: If the path is invalid or the minimum size exceeds the
+            maximum.
+
+    Example:
+        ```python
+        # 10,000 files of 1-64 KiB spread over 200 directories
+        files = await generate_tree("fixtures", 200, 10_000, (1024, 65536))
+        ```
+    """
+    if isinstance(size_distribution, int):
+        size_distribution = (size_distribution, size_distribution)
+    return await generate_tree_async(
+        root, n_dirs, n_files, size_distribution, name_seed
+    )
+
+
+# Transactions
+def transaction(journal: Optional[str] = None) -> Transaction:
+    """
+    Start a transaction that applies several file changes together.
+
+    Writes, renames and removals are staged on the returned `Transaction`
+    and applied in order on commit. Staged writes go to temporary files next
+    to their targets right away, so committing only renames files: files
+    that are replaced or removed are renamed aside and deleted once every
+    change has been applied. If a change fails, the renames already done are
+    undone, so the files are either all changed or all left as they were.
+    Used with `async with`, the transaction commits when the block exits
+    normally and rolls back if it raises.
+
+    The commit is atomic for concurrent readers only per file, not for the
+    set of files: a reader may see some changes applied and others not yet.
+    If the process crashes during a commit, the files are left half
+    changed; with a `journal`, `recover_transaction()` completes or undoes
+    such a commit. A transaction that is garbage collected before it was
+    committed or rolled back deletes its staged temporary files and emits a
+    `ResourceWarning`.
+
+    Args:
+        journal: Path of a journal file recording the commit while it runs
+            (removed once it has finished). Defaults to None (no journal).
+
+    Returns:
+        Transaction: The transaction, usable as an async context manager.
+
+    Raises:
+        ValueError: If the journal path is invalid.
+
+    Example:
+        ```python
+        async with transaction(journal="state/.commit-journal") as txn:
+            await txn.write("state/index.json", index_json)
+            await txn.write("state/data.bin", data)
+            txn.rename("state/current", "state/previous")
+            txn.remove("state/stale.lock")
+        # All changes applied, or none if one failed
+        ```
+
+    See Also:
+        - `recover_transaction()`: Finish a commit interrupted by a crash.
+        - `rename_many()`: Rename many files as one batch.
+        - `atomic_write_file()`: Replace a single file atomically.
+    """
+    return _transaction(journal)
+
+
+async def recover_transaction(journal: str) -> Optional[bool]:
+    """
+    Complete or roll back a commit that was interrupted by a crash.
+
+    Call this with the journal path given to `transaction()` before
+    starting the next transaction, e.g. when a service starts. If the
+    journal records that every change was applied, the leftover backups of
+    replaced and removed files are deleted; otherwise the changes applied
+    are undone and the staged temporary files are deleted. The journal is
+    removed afterwards.
+
+    Args:
+        journal: Journal path given to `transaction()`.
+
+    Returns:
+        Optional[bool]: True if the commit was completed, False if it was
+            rolled back, or None if there is no journal (nothing to recover).
+
+    Raises:
+        ValueError: If the path is invalid or the file is not a transaction
+            journal.
+        IOError: If a file cannot be restored or removed. The journal is
+            kept, so recovery can be retried.
+
+    Example:
+        ```python
+        if await recover_transaction("state/.commit-journal") is False:
+            logger.warning("Rolled back an interrupted commit")
+        ```
+    """
+    return await recover_transaction_async(journal)
+
+
+# Cleanup registry
+# Event loops with paths registered for their shutdown: loop -> (scope, guard),
+# a WeakKeyDictionary created on first use
+_loop_cleanup: Any = None
+# The same for loop implementations that don't support weak references; an
+# entry is dropped when its loop shuts down
+_strong_loop_cleanup: Dict[Any, Tuple[int, Any]] = {}
+_last_cleanup_scope = 0
+
+
+async def _loop_shutdown_guard(scope: int):
+    # Closed by the loop's shutdown_asyncgens() (e.g. at the end of
+    # asyncio.run()), after the remaining tasks have been cancelled
+    try:
+        yield
+    finally:
+        for loop, (loop_scope, _) in list(_strong_loop_cleanup.items()):
+            if loop_scope == scope:
+                del _strong_loop_cleanup[loop]
+        _run_cleanup(scope)
+
+
+def _loop_scope(loop: "asyncio.AbstractEventLoop") -> int:
+    global _loop_cleanup, _last_cleanup_scope
+    if _loop_cleanup is None:
+        import weakref
+
+        _loop_cleanup = weakref.WeakKeyDictionary()
+    try:
+        entry = _loop_cleanup.get(loop)
+        registry = _loop_cleanup
+    except TypeError:
+        entry = _strong_loop_cleanup.get(loop)
+        registry = _strong_loop_cleanup
+    if entry is None:
+        _last_cleanup_scope += 1
+        scope = _last_cleanup_scope
+        guard = _loop_shutdown_guard(scope)
+        # Start the generator synchronously so the loop tracks it right away
+        try:
+            guard.__anext__().send(None)
+        except StopIteration:
+            pass
+        entry = registry[loop] = (scope, guard)
+    return entry[0]
+
+
+def register_cleanup(path: str, on_loop_shutdown: bool = True) -> str:
+    """
+    Register a temporary file or directory for guaranteed removal.
+
+    Registered paths are tracked in Rust and removed when the interpreter
+    exits, even if the tasks that created them were cancelled or crashed.
+    When called while an event loop is running (and `on_loop_shutdown` is
+    True), the path is also removed when that loop shuts down through
+    `loop.shutdown_asyncgens()`, which `asyncio.run()` calls after cancelling
+    the remaining tasks. Directories are removed with all their contents.
+
+    Args:
+        path: File or directory to remove. Relative paths are resolved
+            against the current working directory at registration time.
+        on_loop_shutdown: If True (default), also remove the path when the
+            running event loop shuts down. If False, or if no loop is
+            running, the path is only removed at interpreter exit or by
+            `run_cleanup()`.
+
+    Returns:
+        str: The absolute path that was registered.
+
+    Raises:
+        ValueError: If the path is invalid (empty string or contains null bytes).
+
+    Example:
+        ```python
+        async def convert(data: bytes) -> bytes:
+            scratch = register_cleanup(tempfile.mkdtemp())
+            await write_file_bytes(os.path.join(scratch, "input"), data)
+            ...  # Cancelled here? scratch is still removed at shutdown
+
+        # Remove it early once the work is done
+        await run_cleanup()
+        ```
+
+    See Also:
+        - `unregister_cleanup()`: Keep a registered path after all.
+        - `run_cleanup()`: Remove the registered paths now.
+    """
+    import asyncio
+
+    scope = 0
+    if on_loop_shutdown:
+        try:
+            scope = _loop_scope(asyncio.get_running_loop())
+        except RuntimeError:
+            pass
+    return _register_cleanup(path, scope)
+
+
+def unregister_cleanup(path: str) -> bool:
+    """
+    Stop tracking a path registered with `register_cleanup()`.
+
+    Use this when a temporary path becomes permanent, e.g. after it has been
+    renamed into place.
+
+    Args:
+        path: Path as passed to (or returned by) `register_cleanup()`.
+
+    Returns:
+        bool: True if the path was registered, False otherwise.
+    """
+    return _unregister_cleanup(path)
+
+
+def registered_cleanup() -> List[str]:
+    """
+    List the paths currently registered for cleanup.
+
+    Returns:
+        List[str]: Absolute paths in registration order.
+    """
+    return _registered_cleanup()
+
+
+async def run_cleanup() -> List[str]:
+    """
+    Remove every registered cleanup path now.
+
+    Paths are unregistered as they are processed, so each one is removed (or
+    reported) once. Paths that no longer exist are skipped silently.
+
+    Returns:
+        List[str]: An error message for each path that could not be removed.
+
+    Raises:
+        ReadOnlyError: If read-only mode does not allow removing a registered
+            path; nothing is removed. At interpreter exit such paths are
+            reported and kept instead.
+
+    Example:
+        ```python
+        errors = await run_cleanup()
+        for error in errors:
+            logger.warning(error)
+        ```
+    """
+    return await run_cleanup_async()
+
+
+# Remove whatever is still registered when the interpreter exits
+atexit.register(_run_cleanup)
+
+
+# Scratch space
+async def best_scratch_dir(
+    min_free: int = 0, candidates: Optional[List[str]] = None
+) -> Optional[str]:
+    """
+    Pick the fastest suitable directory for temporary files.
+
+    Inspects each candidate for free space and filesystem type, so
+    temp-heavy pipelines can use a RAM-backed directory when one has room
+    and fall back to disk otherwise. A candidate must be an existing
+    directory the current user can write to, with at least `min_free` bytes
+    available. Memory-backed filesystems (tmpfs, ramfs, such as `/dev/shm`)
+    are preferred over disks, and among equally fast candidates the earlier
+    one wins. Filesystem types are only detected on Linux; elsewhere the
+    first suitable candidate is returned.
+
+    Args:
+        min_free: Bytes that must be available to the current user.
+            Defaults to 0.
+        candidates: Directories to consider, in order of preference. If None
+            (default), the system temporary directory (`TMPDIR`), then
+            `/dev/shm`, `/tmp` and `/var/tmp` on Unix.
+
+    Returns:
+        Optional[str]: The chosen directory, or None if no candidate is
+            suitable.
+
+    Raises:
+        ValueError: If a candidate path is invalid.
+
+    Example:
+        ```python
+        scratch = await best_scratch_dir(min_free=2 * 1024**3)
+        workdir = tempfile.mkdtemp(dir=scratch)
+        rapfiles.register_cleanup(workdir)
+        ```
+    """
+    return await best_scratch_dir_async(min_free, candidates)
+
+
+# Runtime introspection
+def runtime_info() -> Dict[str, Any]:
+    """
+    Describe the current state of the rapfiles runtime.
+
+    Returns a snapshot suitable for a service's debug or health endpoint.
+    Collecting it is cheap and never waits for in-flight I/O.
+
+    Returns:
+        Dict[str, Any]: A dictionary with:
+            - runtime (str): Async runtime, currently "tokio" (see
+              `get_backend()` for the I/O backend)
+            - worker_threads (int): Tokio worker threads
+            - alive_tasks (int): Tasks currently alive in the runtime
+            - queued_tasks (int): Tasks waiting in the runtime's global queue
+            - open_files (int): `AsyncFile` handles not yet closed
+            - caches (Dict[str, int]): Sizes of the in-memory tables kept by
+              rapfiles: `faults` and `slowdowns` (testing hooks),
+              `cleanup_paths` (see `register_cleanup()`) and `recorded_ops`
+              (operations buffered by an active recording)
+
+    Example:
+        ```python
+        @app.get("/debug/rapfiles")
+        async def debug_rapfiles():
+            return rapfiles.runtime_info()
+        # {'runtime': 'tokio', 'worker_threads': 8, 'alive_tasks': 3,
+        #  'queued_tasks': 0, 'open_files': 2, 'caches': {...}}
+        ```
+    """
+    return _runtime_info()
+
+
+# I/O backends
+def set_backend(name: str) -> None:
+    """
+    Select the I/O backend used by operations started from now on.
+
+    "tokio" (default) uses `tokio::fs`. "threadpool" runs each operation as
+    plain blocking calls on a worker thread, a fallback for filesystems where
+    Tokio's file handles behave poorly (e.g. some FUSE mounts). The Python API
+    is identical with either backend. Whole-file operations such as
+    `read_file()` already run as a single blocking call with both backends;
+    the choice matters most for handles returned by `open()` and for
+    `append_file()`. A handle keeps the backend it was opened with.
+
+    Args:
+        name: "tokio" or "threadpool" (see `available_backends()`).
+
+    Raises:
+        ValueError: If the backend name is unknown.
+
+    Example:
+        ```python
+        rapfiles.set_backend("threadpool")
+        async with rapfiles.open("/mnt/fuse/log.txt", "a") as f:
+            await f.write("entry\n")
+        ```
+    """
+    _set_backend(name)
+
+
+def get_backend(path: Optional[str] = None) -> str:
+    """
+    Return the backend used by operations started in the current context.
+
+    Args:
+        path: If given, resolve the backend for operations on this path,
+            taking routes registered with `add_route()` into account.
+
+    Returns:
+        str: The `use_backend()` override active in the current task, else
+            the backend of the route matching `path`, else the backend
+            selected with `set_backend()`.
+    """
+    return _get_backend(path)
+
+
+def available_backends() -> List[str]:
+    """
+    Return the names of the available I/O backends.
+
+    Returns:
+        List[str]: Backend names accepted by `set_backend()` and `use_backend()`.
+    """
+    return _available_backends()
+
+
+@contextlib.contextmanager
+def use_backend(name: str) -> Iterator[None]:
+    """
+    Use a backend for the operations started inside the block.
+
+    The override is stored in a context variable, so it applies to the
+    current task (and tasks it creates) without affecting concurrent tasks.
+
+    Args:
+        name: "tokio" or "threadpool" (see `available_backends()`).
+
+    Raises:
+        ValueError: If the backend name is unknown.
+
+    Example:
+        ```python
+        with rapfiles.use_backend("threadpool"):
+            data = await rapfiles.read_file_bytes("/mnt/fuse/blob.bin")
+        ```
+    """
+    token = _override_backend(name)
+    try:
+        yield
+    finally:
+        _reset_backend_override(token)
+
+
+def add_route(
+    prefix: str,
+    backend: Optional[str] = None,
+    concurrency: Optional[int] = None,
+) -> None:
+    """
+    Apply a backend and tuning to every operation on paths below a prefix.
+
+    Routes let one process treat mounts differently, e.g. the thread-pool
+    backend for a FUSE mount and a low concurrency for an NFS share, without
+    changing the code that performs the I/O. An operation is routed by its
+    first path; prefixes match whole path components (`/data` matches
+    `/data/x` but not `/database`) and the longest matching prefix wins. A
+    `use_backend()` block still takes precedence over a route's backend.
+
+    Args:
+        prefix: Directory prefix. Relative prefixes are resolved against the
+            current working directory. Adding a route for an existing prefix
+            replaces it.
+        backend: Backend for matching operations ("tokio" or "threadpool").
+            The global backend applies if None.
+        concurrency: Maximum number of files processed at once by batch and
+            tree operations (`read_files()`, `write_files()`, `copy_files()`,
+            `copytree()`, `remove_dir_all()`). Built-in limits apply if None.
+
+    Raises:
+        ValueError: If the prefix or backend is invalid, `concurrency` is
+            less than 1, or neither `backend` nor `concurrency` is given.
+
+    Example:
+        ```python
+        rapfiles.add_route("/mnt/fuse", backend="threadpool")
+        rapfiles.add_route("/mnt/nfs", concurrency=4)
+        await rapfiles.copytree("/srv/build", "/mnt/nfs/releases/42")
+        ```
+    """
+    _add_route(prefix, backend, concurrency)
+
+
+def remove_route(prefix: str) -> bool:
     """
-    results = await read_files_async(paths)
-    # Convert results to list of (path, bytes) tuples, raising on error
-    output = []
-    for path, result in results:
-        if isinstance(result, bytes):
-            output.append((path, result))
-        elif isinstance(result, str):
-            raise IOError(result)
-        elif isinstance(result, Exception):
-            raise result
-        else:
-            output.append((path, result))
-    return output
+    Remove the route registered for `prefix`.
 
+    Args:
+        prefix: Prefix passed to `add_route()`.
 
-async def read_files_dict(paths: List[str]) -> Dict[str, bytes]:
+    Returns:
+        bool: True if a route was removed, False if none was registered.
     """
-    Read multiple files concurrently and return as dictionary.
+    return _remove_route(prefix)
 
-    Reads all specified files concurrently and returns their contents as a
-    dictionary mapping paths to contents. This is a convenience wrapper around
-    `read_files()` that returns a dictionary instead of a list of tuples.
-    All I/O operations execute outside the Python GIL using native Rust/Tokio,
-    ensuring true async behavior and preventing event loop stalls.
+
+def clear_routes() -> None:
+    """Remove every route registered with `add_route()`."""
+    _clear_routes()
+
+
+def routes() -> List[Route]:
+    """
+    List the registered routes.
+
+    Returns:
+        List[Route]: Routes with their absolute `prefix`, `backend` and
+            `concurrency`, most specific prefix first.
+    """
+    return _routes()
+
+
+# Operation options
+@contextlib.contextmanager
+def options(
+    timeout: Optional[float] = None,
+    retries: Optional[int] = None,
+    rate_limit: Optional[float] = None,
+    priority: Optional[str] = None,
+    job: Optional[str] = None,
+    weight: Optional[float] = None,
+) -> Iterator[None]:
+    """
+    Apply I/O policies to the operations started inside the block.
+
+    The options are stored in a context variable, like `use_backend()`, so a
+    request handler can scope them to its own task without passing arguments
+    down to every call. Nested blocks inherit the options they don't set.
 
     Args:
-        paths: List of file paths to read. Can contain relative or absolute paths.
+        timeout: Seconds each operation may take, including its retries.
+            An operation exceeding it raises `TimeoutError`; blocking work
+            it already started may still complete in the background.
+        retries: How often reads (`read_file()`, `read_file_bytes()`,
+            `read_toml()`, `read_yaml()`) are retried after a transient
+            `OSError`, with exponential backoff starting at 50 ms. Errors
+            such as `FileNotFoundError` or `PermissionError` are raised at
+            once. Other operations are never retried.
+        rate_limit: Bytes per second shared by all operations of the block.
+            Operations wait after transferring data until the budget allows
+            it, so concurrent tasks in the block together stay below it.
+        priority: "high", "normal" or "low". While high-priority operations
+            are in flight, low-priority ones run one at a time and the rest
+            wait, so background scans don't starve latency-critical reads.
+            Normal-priority operations are never held back.
+        job: Name of the job the operations belong to. Jobs sharing a
+            `rate_limit` split it in proportion to their `weight` instead of
+            first-come-first-served, counting only jobs with operations in
+            flight, so a job running alone still gets the whole budget.
+            Without a `rate_limit`, the job has no effect.
+        weight: Share of `rate_limit` the job gets relative to the other
+            jobs (1 if not given).
+
+    Raises:
+        ValueError: If `timeout`, `rate_limit` or `weight` is not positive,
+            `retries` is negative, `priority` is unknown, or `weight` is
+            given outside a named job.
+
+    Example:
+        ```python
+        async def handler(request):
+            with rapfiles.options(timeout=5, retries=2, rate_limit=10_000_000):
+                return await rapfiles.read_file_bytes(request.path)
+
+        # An urgent restore gets 4/5 of the budget while the mirror runs
+        with rapfiles.options(rate_limit=100_000_000):
+            async with rapfiles.TaskGroup() as group:
+                with rapfiles.options(job="mirror"):
+                    group.submit(rapfiles.copy_files(mirror_pairs))
+                with rapfiles.options(job="restore", weight=4):
+                    group.submit(rapfiles.copy_files(restore_pairs))
+        ```
+    """
+    token = _override_options(timeout, retries, rate_limit, priority, job, weight)
+    try:
+        yield
+    finally:
+        _reset_options_override(token)
+
+
+def get_options() -> Dict[str, Any]:
+    """
+    Return the options applied to operations started in the current context.
 
     Returns:
-        Dict[str, bytes]: Dictionary mapping file paths (str) to their contents
-            (bytes). Keys are the same as the input paths.
+        Dict[str, Any]: `timeout` (seconds or None), `retries`,
+            `rate_limit` (bytes per second or None), `priority`, `job`
+            (name or None) and `weight`, as set by the enclosing `options()`
+            blocks.
+    """
+    return _get_options()
+
+
+# Read-only mode
+def set_read_only(
+    enabled: bool = True, writable_roots: Optional[Iterable[str]] = None
+) -> None:
+    """
+    Block every operation that would modify the filesystem.
+
+    Meant for exploring production data safely, e.g. from a notebook: while
+    enabled, writes, appends, removals, renames, copies, directory creation,
+    `lock_file()` and opening files in a writable mode raise `ReadOnlyError`
+    before touching the filesystem. Paths below `writable_roots` stay
+    writable, and reads are never affected. Paths and roots are compared
+    after resolving symlinks, `.` and `..` (like `os.path.realpath()`), so
+    a symlink inside a writable root can't lead out of it; a link pointing
+    outside is protected like its target. The check is process-wide and
+    happens right before each operation, so it is a safety net rather than
+    a security boundary against other processes changing links.
+
+    Args:
+        enabled: True to enable read-only mode, False to disable it.
+        writable_roots: Directories that stay writable, e.g. a scratch
+            directory. Relative paths are resolved against the current
+            working directory.
 
     Raises:
-        FileNotFoundError: If any file does not exist.
-        IOError: If any file cannot be read (e.g., permission denied).
-        ValueError: If any path is invalid (empty string or contains null bytes).
+        ValueError: If a root is invalid.
 
     Example:
         ```python
-        files = await read_files_dict(["file1.txt", "file2.txt"])
-        print(files["file1.txt"])  # b"content"
-        # Access files by path: files["file1.txt"]
+        rapfiles.set_read_only(writable_roots=["/tmp/scratch"])
+        data = await rapfiles.read_file("/mnt/prod/table.csv")
+        await rapfiles.write_file("/tmp/scratch/copy.csv", data)
+        await rapfiles.remove_file("/mnt/prod/table.csv")  # ReadOnlyError
         ```
+    """
+    _set_read_only(enabled, None if writable_roots is None else list(writable_roots))
 
-    See Also:
-        - `read_files()`: Read files and return as list of tuples.
-        - `read_file()`: Read a single file.
+
+def is_read_only() -> bool:
     """
-    results = await read_files(paths)
-    return dict(results)
+    Return whether read-only mode is enabled.
+
+    Returns:
+        bool: True between `set_read_only()` and `set_read_only(False)`.
+    """
+    return _is_read_only()
 
 
-async def write_files(files: Dict[str, bytes]) -> None:
+def writable_roots() -> List[str]:
     """
-    Write multiple files concurrently.
+    List the directories that stay writable in read-only mode.
 
-    Writes contents to all specified files concurrently. All files are written
-    in parallel, significantly improving performance when processing many files.
-    If a destination file exists, it will be overwritten. All I/O operations
-    execute outside the Python GIL using native Rust/Tokio, ensuring true async
-    behavior and preventing event loop stalls.
+    Returns:
+        List[str]: Absolute roots passed to `set_read_only()`; empty when
+            read-only mode is disabled.
+    """
+    return _writable_roots()
+
+
+# Configuration
+def configure(**options: Any) -> None:
+    """
+    Set global tuning options in one call.
+
+    Options not given keep their current value; all of them are validated
+    before any is applied. Each option can also be set with a `RAPFILES_*`
+    environment variable read at import (e.g. `RAPFILES_WORKER_THREADS=4`,
+    `RAPFILES_CAPTURE_OPEN_STACKS=1`); invalid variables are ignored with a
+    `RuntimeWarning`. Routes (`add_route()`) and `use_backend()` still take
+    precedence over the global backend and concurrency.
 
     Args:
-        files: Dictionary mapping file paths (str) to their contents (bytes).
-            Keys are file paths, values are the bytes to write.
+        **options: Any of:
+
+            - backend: "tokio" or "threadpool", as `set_backend()`.
+            - worker_threads: Tokio worker threads, None for one per CPU.
+            - max_blocking_threads: Upper bound of the blocking thread pool
+              that runs file I/O, None for Tokio's default (512).
+            - small_file_threshold: Bytes up to which read-only `open()`
+              loads the file whole, as `set_small_file_threshold()`.
+            - capture_open_stacks: Record the stack of each `open()`, as
+              `capture_open_stacks()`.
+            - concurrency: Maximum number of files processed at once by batch
+              and tree operations, None for the built-in limits.
 
     Raises:
-        IOError: If any file cannot be written (e.g., disk full, I/O error).
-        PermissionError: If write permission is denied for any file.
-        ValueError: If any path is invalid (empty string or contains null bytes).
+        TypeError: If an option is unknown or has the wrong type.
+        ValueError: If a value is invalid (e.g. a count below 1).
+        RuntimeError: If `worker_threads` or `max_blocking_threads` is changed
+            after the first operation started the runtime.
 
     Example:
         ```python
-        files = {
-            "file1.txt": b"content1",
-            "file2.txt": b"content2",
-        }
-        await write_files(files)
-        # All files are written concurrently
+        rapfiles.configure(worker_threads=2, concurrency=8)
         ```
+    """
+    _configure(**options)
 
-    See Also:
-        - `write_file()`: Write a single file.
-        - `write_file_bytes()`: Write a single file as bytes.
-        - `read_files_dict()`: Read multiple files as dictionary.
+
+def get_config() -> Dict[str, Any]:
     """
-    # Convert dict to list of (path, bytes) tuples for Rust function
-    # PyO3 will automatically convert Python bytes to Vec<u8>
-    files_list = [(path, contents) for path, contents in files.items()]
-    results = await write_files_async(files_list)
+    Return the global configuration.
 
-    # Check for errors
-    for path, result in results:
-        if isinstance(result, str):
-            raise IOError(result)
+    Returns:
+        Dict[str, Any]: The options accepted by `configure()` with their
+            current values, so `configure(**get_config())` is a no-op.
+    """
+    return _get_config()
 
 
-async def copy_files(files: List[Tuple[str, str]]) -> None:
+def preload() -> None:
     """
-    Copy multiple files concurrently.
+    Start the runtime now instead of on the first operation.
 
-    Copies all specified files concurrently. All files are copied in parallel,
-    significantly improving performance when processing many files. If a
-    destination file exists, it will be overwritten. All I/O operations execute
-    outside the Python GIL using native Rust/Tokio, ensuring true async behavior
-    and preventing event loop stalls.
+    Importing rapfiles starts no threads: the Tokio runtime and its thread
+    pools are created by the first operation, so short-lived scripts that
+    import rapfiles without using it pay nothing for them. Long-running
+    services can call `preload()` at startup instead, so the first request
+    does not wait for the threads. Call `configure()` first to size the
+    pools; they cannot be resized afterwards. Calling it again does nothing.
+
+    Raises:
+        RuntimeError: If the runtime cannot be started.
+
+    Example:
+        ```python
+        rapfiles.configure(worker_threads=4)
+        rapfiles.preload()
+        ```
+    """
+    _preload()
 
-    Args:
-        files: List of (src, dst) tuples where:
-            - src (str): Path to the source file
-            - dst (str): Path to the destination file
+
+def reinit_after_fork() -> None:
+    """
+    Prepare rapfiles for use in a child process created with `fork()`.
+
+    A forked child inherits rapfiles' state but none of its runtime threads.
+    rapfiles detects this by itself: the first operation in the child
+    starts a new runtime, paths the parent registered with
+    `register_cleanup()` are forgotten (the parent removes them), and using
+    an `AsyncFile` opened by the parent raises `RuntimeError` instead of
+    hanging. Calling this from a post-fork hook does the first two right
+    away, so the first request in the worker does not start the runtime.
+    Open files again in the child rather than sharing handles.
 
     Raises:
-        FileNotFoundError: If any source file does not exist.
-        IOError: If any file cannot be copied (e.g., disk full, permission denied).
-        ValueError: If any path is invalid (empty string or contains null bytes).
+        RuntimeError: If the new runtime cannot be started.
 
     Example:
         ```python
-        files = [
-            ("source1.txt", "dest1.txt"),
-            ("source2.txt", "dest2.txt"),
-        ]
-        await copy_files(files)
-        # All files are copied concurrently
+        # gunicorn.conf.py
+        def post_fork(server, worker):
+            rapfiles.reinit_after_fork()
         ```
+    """
+    _reinit_after_fork()
 
-    See Also:
-        - `copy_file()`: Copy a single file.
-        - `move_file()`: Move a file (removes source).
+
+# Task groups
+# Result type of an awaitable submitted to a TaskGroup
+_R = TypeVar("_R")
+
+
+class TaskGroup:
     """
-    results = await copy_files_async(files)
+    Run operations concurrently with a shared limit, failing them together.
 
-    # Check for errors
-    for src, dst, result in results:
-        if isinstance(result, str):
-            raise IOError(result)
+    Awaitables submitted to the group run as tasks, at most
+    `max_concurrency` at a time. When one of them raises, the others are
+    cancelled and the error is raised when the `async with` block exits; if
+    the block itself raises, every task is cancelled and the block's error
+    propagates. Leaving the block waits for all tasks, including tasks
+    submitted by other tasks of the group.
+
+    Args:
+        max_concurrency: Maximum number of submitted operations running at
+            once, None for no limit.
+
+    Raises:
+        ValueError: If `max_concurrency` is below 1.
+
+    Example:
+        ```python
+        async with rapfiles.TaskGroup(max_concurrency=16) as group:
+            tasks = [group.submit(rapfiles.read_file_bytes(p)) for p in paths]
+        contents = [task.result() for task in tasks]
+        ```
+    """
+
+    def __init__(self, max_concurrency: Optional[int] = None) -> None:
+        if max_concurrency is not None and max_concurrency < 1:
+            raise ValueError(
+                f"Invalid max_concurrency: {max_concurrency}. Must be at least 1"
+            )
+        self.max_concurrency = max_concurrency
+        self._semaphore: Optional[asyncio.Semaphore] = None
+        self._tasks: List["asyncio.Task[Any]"] = []
+        self._error: Optional[BaseException] = None
+        self._state = "new"
+
+    def submit(self, awaitable: Awaitable[_R]) -> "asyncio.Task[_R]":
+        """
+        Start running an awaitable as part of the group.
+
+        Args:
+            awaitable: Coroutine or other awaitable, typically a rapfiles
+                operation such as `rapfiles.read_file(path)`.
+
+        Returns:
+            asyncio.Task: The task running the awaitable; its `result()`
+                is available once the group's block has exited.
+
+        Raises:
+            RuntimeError: If the group is not active or is cancelling its
+                tasks after a failure.
+        """
+        import asyncio
+        import inspect
+
+        if self._state != "active" or self._error is not None:
+            if inspect.iscoroutine(awaitable):
+                awaitable.close()
+            raise RuntimeError(
+                "TaskGroup is not active"
+                if self._error is None
+                else "TaskGroup is shutting down after a failed task"
+            )
+        task = asyncio.ensure_future(self._run(awaitable))
+        task.add_done_callback(lambda task: self._task_done(task, awaitable))
+        self._tasks.append(task)
+        return task
+
+    async def _run(self, awaitable: Awaitable[_R]) -> _R:
+        if self._semaphore is None:
+            return await awaitable
+        async with self._semaphore:
+            return await awaitable
+
+    def _task_done(self, task: "asyncio.Task[Any]", awaitable: Awaitable[Any]) -> None:
+        import inspect
+
+        # A task cancelled before it got a slot never awaited its coroutine
+        if inspect.iscoroutine(awaitable):
+            awaitable.close()
+        if task.cancelled():
+            return
+        error = task.exception()
+        if error is not None and self._error is None:
+            self._error = error
+            self._cancel_all()
+
+    def _cancel_all(self) -> None:
+        for task in self._tasks:
+            task.cancel()
+
+    async def _wait_all(self) -> None:
+        import asyncio
+
+        # Tasks may submit more tasks while the group waits for them
+        while True:
+            pending = [task for task in self._tasks if not task.done()]
+            if not pending:
+                return
+            await asyncio.wait(pending)
+
+    async def __aenter__(self) -> "TaskGroup":
+        if self._state != "new":
+            raise RuntimeError("TaskGroup has already been entered")
+        self._state = "active"
+        if self.max_concurrency is not None:
+            import asyncio
+
+            self._semaphore = asyncio.Semaphore(self.max_concurrency)
+        return self
+
+    async def __aexit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        import asyncio
+
+        if exc_val is not None:
+            self._cancel_all()
+        try:
+            await self._wait_all()
+        except asyncio.CancelledError:
+            self._cancel_all()
+            await self._wait_all()
+            raise
+        finally:
+            self._state = "closed"
+        if exc_val is None and self._error is not None:
+            raise self._error
 
 
 # Type variable for the return type of open()
 _T = TypeVar("_T", bound="AsyncFile")
 
+# Bytes decoded at a time when text mode searches for line ends in the text
+_TEXT_CHUNK_SIZE = 8192
+
+
+class _DetachedFile:
+    """Stands in for the file of a text-mode handle after `detach()`."""
+
+    def __getattr__(self, name: str) -> Any:
+        raise ValueError("underlying buffer has been detached")
+
 # Forward declarations for type checking
 if TYPE_CHECKING:
 
@@ -1429,11 +4345,13 @@ if TYPE_CHECKING:
         async def read(self, size: int = -1) -> str: ...
         async def readline(self, size: int = -1) -> str: ...
         async def readlines(self, hint: int = -1) -> List[str]: ...
+        def __aiter__(self) -> "_TextModeWrapperProtocol": ...
+        async def __anext__(self) -> str: ...
 
 
 # aiofiles.open() compatible function
 def open(
-    file: Union[str, bytes],
+    file: Union[str, bytes, int],
     mode: str = "r",
     buffering: int = -1,
     encoding: Optional[str] = None,
@@ -1441,6 +4359,7 @@ def open(
     newline: Optional[str] = None,
     closefd: bool = True,
     opener: Optional[Any] = None,
+    access: str = "serialized",
 ) -> Any:  # Returns _OpenContextManager (internal type)
     """
     Open a file asynchronously (aiofiles.open() compatible).
@@ -1452,7 +4371,8 @@ def open(
     preventing event loop stalls.
 
     Args:
-        file: Path to the file. Can be a string or bytes (bytes are decoded to UTF-8).
+        file: Path to the file. Can be a string or bytes (bytes are decoded to UTF-8),
+            or an open file descriptor to wrap (Unix only).
         mode: File mode string. Supported modes:
             - 'r', 'r+': Read mode (text)
             - 'w', 'w+': Write mode (text, truncates existing file)
@@ -1460,29 +4380,63 @@ def open(
             - 'rb', 'rb+': Read mode (binary)
             - 'wb', 'wb+': Write mode (binary, truncates existing file)
             - 'ab', 'ab+': Append mode (binary)
-        buffering: Buffer size. Currently not implemented, accepted for API
-            compatibility. Defaults to -1.
-        encoding: Text encoding. Currently not implemented (always UTF-8),
-            accepted for API compatibility.
-        errors: Error handling. Currently not implemented, accepted for API
-            compatibility.
-        newline: Newline handling. Currently not implemented, accepted for
-            API compatibility.
-        closefd: Close file descriptor. Currently not implemented, accepted
-            for API compatibility. Defaults to True.
-        opener: Custom opener. Currently not implemented, accepted for API
-            compatibility.
+            - 'x', 'x+': Exclusive creation (text, fails if the file exists)
+            - 'xb', 'xb+': Exclusive creation (binary, fails if the file exists)
+        buffering: 1 selects line buffering in text mode: writes containing a
+            newline are flushed immediately, as log writers and interactive
+            tools expect (in binary mode it warns like the built-in
+            `open()`). A size above 1 sets the read-ahead buffer used by
+            `readline()`, `readlines()` and line iteration (64 KiB by
+            default); 0 reads lines without one. Defaults to -1.
+        encoding: Text encoding, any codec known to Python (e.g. 'latin-1',
+            'cp1252' or 'utf-16'). Defaults to UTF-8. Text mode only.
+        errors: How encoding and decoding errors are handled, as for
+            `str.encode()`: 'strict' (default, raising `UnicodeDecodeError`
+            or `UnicodeEncodeError`), 'replace', 'ignore',
+            'backslashreplace', or 'surrogateescape' (which writes back the
+            undecodable bytes it read). Text mode only.
+        newline: Line endings in text mode, as for the built-in `open()`:
+            - None (default): '\\r\\n' and '\\r' are read as '\\n', and '\\n'
+              is written as `os.linesep`
+            - '': lines end at '\\n', '\\r' or '\\r\\n', read unchanged
+            - '\\n', '\\r' or '\\r\\n': lines end only there, and '\\n' is
+              written as that string
+        closefd: Whether closing the handle closes the file descriptor given
+            as `file`. False keeps it open for the caller (the handle then
+            works on a duplicate of it); must be True for a path. Defaults
+            to True.
+        opener: Custom opener, as for the built-in `open()`: called as
+            `opener(file, flags)` (with `os.O_*` flags for the mode) when
+            the file is opened, on the event loop's thread, and must return an
+            open file descriptor, which the handle then owns. `reopen()` and
+            `refresh()` call it again. Unix only.
+        access: How the handle treats operations awaited concurrently (e.g.
+            from `asyncio.gather`):
+            - 'serialized' (default): operations wait for each other and run
+              in turn on the shared file cursor
+            - 'exclusive': an operation started while another is in progress
+              raises RuntimeError, surfacing accidental concurrent use
+            - 'positional': like 'serialized', and the handle also offers
+              `pread(size, offset)` / `pwrite(data, offset)`, which use no
+              cursor and never wait for other operations
 
     Returns:
         _OpenContextManager: An async context manager that yields an `AsyncFile`
             instance (or `_TextModeWrapper` for text mode). Use with `async with`
-            syntax.
+            syntax, or await it for the same handle and close it yourself.
 
     Raises:
         FileNotFoundError: If the file does not exist (read modes).
+        FileExistsError: If the file already exists ('x' modes).
         IOError: If the file cannot be opened (e.g., permission denied).
-        ValueError: If the path is invalid (empty string or contains null bytes)
-            or if the mode is invalid.
+        ValueError: If the path is invalid (empty string or contains null bytes),
+            if `closefd` is False for a path, if the file descriptor is negative,
+            if the mode or access mode is invalid, if `encoding` or `errors`
+            or `newline` is given in binary mode, if `newline` is not one of
+            the values above, or if `opener` returns a negative descriptor.
+        LookupError: If the encoding or error handler is unknown.
+        NotImplementedError: If `opener` or a file descriptor is given on a
+            platform other than Unix.
 
     Example:
         ```python
@@ -1498,6 +4452,10 @@ def open(
         # Write mode
         async with open("output.txt", "w") as f:
             await f.write("Hello, world!")
+
+        # Concurrent positional reads
+        async with open("data.bin", "rb", access="positional") as f:
+            header, footer = await asyncio.gather(f.pread(16, 0), f.pread(16, 4096))
         ```
 
     See Also:
@@ -1505,9 +4463,38 @@ def open(
         - `read_file_bytes()`: Read entire file as bytes.
         - `write_file()`: Write entire file as string.
     """
+    import codecs
+
     if isinstance(file, bytes):
         file = file.decode("utf-8")
 
+    # Checked before opening, like the built-in open()
+    text_encoding = "utf-8" if encoding is None else encoding
+    text_errors = "strict" if errors is None else errors
+    if "b" in mode:
+        if encoding is not None:
+            raise ValueError("binary mode doesn't take an encoding argument")
+        if errors is not None:
+            raise ValueError("binary mode doesn't take an errors argument")
+        if newline is not None:
+            raise ValueError("binary mode doesn't take a newline argument")
+    else:
+        codecs.lookup(text_encoding)
+        codecs.lookup_error(text_errors)
+    if newline not in (None, "", "\n", "\r", "\r\n"):
+        raise ValueError(f"illegal newline value: {newline!r}")
+    # None and "" end lines at "\n", "\r" and "\r\n"; None also reads them
+    # as "\n"
+    universal_newlines = newline is None or newline == ""
+    # "\n" written by the caller is written as this
+    write_newline = os.linesep if newline is None else newline or "\n"
+    # With encodings such as UTF-16 a newline byte can be part of another
+    # character, so lines must be found in the decoded text; the same goes
+    # for lines ending in "\r" alone
+    newline_is_byte = "\n".encode(text_encoding, text_errors) == b"\n"
+    split_natively = newline_is_byte and newline != "\r" and newline != "\r\n"
+    writes_bom = codecs.getincrementalencoder(text_encoding)().encode("") != b""
+
     # Create an awaitable wrapper that implements async context manager protocol
     # and handles text/binary mode conversion
     class _OpenContextManager:
@@ -1515,23 +4502,41 @@ def open(
 
         def __init__(
             self,
-            coro: Coroutine[Any, Any, AsyncFile],
+            open_: Callable[[], Awaitable[AsyncFile]],
             file_mode: str,
         ) -> None:
-            self._coro: Coroutine[Any, Any, AsyncFile] = coro
+            # The file is opened when awaited or entered, so the future belongs
+            # to the loop running then, whichever loop (if any) ran at creation
+            self._open: Callable[[], Awaitable[AsyncFile]] = open_
             self._file: Optional[AsyncFile] = None
             self._is_binary: bool = "b" in file_mode
+            self._started: bool = False
+
+        def _start(self) -> Awaitable[AsyncFile]:
+            # Like a coroutine, each open() call opens one file
+            if self._started:
+                raise RuntimeError(
+                    "open() result was already awaited or entered; "
+                    "call open() again for another handle"
+                )
+            self._started = True
+            return self._open()
 
         def __await__(self) -> Any:
-            return self._coro.__await__()
+            return self._open_wrapped().__await__()
+
+        async def _open_wrapped(self) -> Union["_TextModeWrapper", AsyncFile]:
+            # Awaiting gives the same handle as `async with`, minus the block
+            file_obj = await self._start()
+            if not self._is_binary:
+                return _TextModeWrapper(file_obj)
+            return file_obj
 
         async def __aenter__(self) -> Union["_TextModeWrapper", AsyncFile]:
-            self._file = await self._coro
-            # __aenter__ returns self directly, no need to await
-            file_obj = self._file.__aenter__()
+            self._file = await self._start()
+            file_obj = await self._file.__aenter__()
 
             # Wrap the file object to handle text/binary mode
-            # Note: file_obj is already the AsyncFile instance (__aenter__ returns self)
             if not self._is_binary:
                 return _TextModeWrapper(file_obj)
             return file_obj
@@ -1543,7 +4548,8 @@ def open(
             exc_tb: Optional[TracebackType],
         ) -> Optional[bool]:
             if self._file is None:
-                self._file = await self._coro
+                # Not entered, so nothing was opened
+                return None
             # __aexit__ returns a coroutine/future, need to await it
             result = self._file.__aexit__(exc_type, exc_val, exc_tb)
             if hasattr(result, "__await__"):
@@ -1556,34 +4562,390 @@ def open(
 
         def __init__(self, file_obj: AsyncFile) -> None:
             self._file: AsyncFile = file_obj
+            # Holds the bytes of a character split by a size-limited read
+            self._decoder: codecs.IncrementalDecoder = codecs.getincrementaldecoder(
+                text_encoding
+            )(text_errors)
+            # Created by the first write, which checks the position for BOMs
+            self._encoder: Optional[codecs.IncrementalEncoder] = None
+            # Set when a "\r" was returned as "\n", so a "\n" read next is
+            # the rest of the same line end
+            self._skip_lf: bool = False
 
         async def read(self, size: int = -1) -> str:
-            """Read and decode bytes to string for text mode."""
-            result: Union[str, bytes] = await self._file.read(size)
-            if isinstance(result, bytes):
-                return result.decode("utf-8")
-            return result  # type: ignore[return-value]
+            """Read and decode up to `size` characters (all if negative)."""
+            if size < 0:
+                data = await self._file.read(-1)
+                return self._translate(self._decoder.decode(data, final=True))
+            text = ""
+            while len(text) < size:
+                # Every character takes at least one byte
+                data = await self._file.read(size - len(text))
+                text += self._translate(self._decoder.decode(data, final=not data))
+                if not data:
+                    break
+            return text
 
         async def readline(self, size: int = -1) -> str:
             """Read a line and decode bytes to string for text mode."""
-            result: Union[str, bytes] = await self._file.readline(size)
-            if isinstance(result, bytes):
-                return result.decode("utf-8")
-            return result  # type: ignore[return-value]
+            if split_natively:
+                line: bytes = await self._file.readline(size)
+                if not self._splits_further(line):
+                    return self._decode_line(line)
+                await self._file.seek(-len(line), 1)
+            return await self._read_decoded_line(size)
 
         async def readlines(self, hint: int = -1) -> List[str]:
             """Read all lines and decode bytes to strings for text mode."""
-            result: Union[List[str], List[bytes]] = await self._file.readlines(hint)
-            if isinstance(result, list) and result and isinstance(result[0], bytes):
-                return [
-                    line.decode("utf-8") if isinstance(line, bytes) else line
-                    for line in result
-                ]  # type: ignore[misc,return-value]
-            return result  # type: ignore[return-value]
+            lines: List[str] = []
+            if split_natively:
+                result: List[bytes] = await self._file.readlines(hint)
+                for index, line in enumerate(result):
+                    if self._splits_further(line):
+                        await self._file.seek(-sum(map(len, result[index:])), 1)
+                        break
+                    lines.append(self._decode_line(line))
+                else:
+                    return lines
+            total = sum(map(len, lines))
+            while hint <= 0 or total < hint:
+                line = await self._read_decoded_line(-1)
+                if not line:
+                    break
+                lines.append(line)
+                total += len(line)
+            return lines
+
+        def __aiter__(self) -> "_TextModeWrapper":
+            return self
+
+        async def __anext__(self) -> str:
+            """Read the next line and decode it for text mode."""
+            line = await self.readline()
+            if not line:
+                raise StopAsyncIteration
+            return line
+
+        def _splits_further(self, line: bytes) -> bool:
+            # Whether a line split at "\n" holds another line end, ends in a
+            # "\r" that may be followed by "\n", or starts with the "\n" of
+            # a line end already returned
+            if not universal_newlines:
+                return False
+            body = line[:-2] if line.endswith(b"\r\n") else line
+            return b"\r" in body or (self._skip_lf and line.startswith(b"\n"))
+
+        def _line_end(self, text: str, at_eof: bool) -> int:
+            # Index after the first line end in the text, or 0 if none is known
+            if not universal_newlines:
+                index = text.find(newline or "\n")
+                return index + len(newline or "\n") if index >= 0 else 0
+            lf = text.find("\n")
+            cr = text.find("\r")
+            if cr < 0 or 0 <= lf < cr:
+                return lf + 1
+            if cr + 1 < len(text):
+                return cr + 2 if text[cr + 1] == "\n" else cr + 1
+            # A "\r" at the end of the text may be followed by "\n"
+            return cr + 1 if at_eof else 0
+
+        def _translate(self, text: str) -> str:
+            # With newline=None, "\r\n" and "\r" are read as "\n"
+            if newline is not None or not text:
+                return text
+            skip = self._skip_lf and text.startswith("\n")
+            self._skip_lf = text.endswith("\r")
+            return text[skip:].replace("\r\n", "\n").replace("\r", "\n")
+
+        def _decode_line(self, line: bytes) -> str:
+            # A line cut short by a size limit may end inside a character
+            complete = not line or line.endswith(b"\n")
+            return self._translate(self._decoder.decode(line, final=complete))
+
+        async def _read_decoded_line(self, size: int) -> str:
+            # Decode chunks until the text holds a line, then seek back over
+            # the bytes decoded past its end
+            text = ""
+            data = b""
+            while True:
+                end = self._line_end(text, at_eof=False)
+                if 0 <= size < (end or len(text) + 1):
+                    # The size limit is reached before the end of the line
+                    end = size
+                    break
+                if end:
+                    break
+                state = self._decoder.getstate()
+                start = len(text)
+                data = await self._file.read(_TEXT_CHUNK_SIZE)
+                if not data:
+                    text += self._decoder.decode(b"", final=True)
+                    return self._translate(text)
+                text += self._decoder.decode(data)
+                if self._skip_lf and text:
+                    # Drop the "\n" of a line end already returned
+                    if text.startswith("\n"):
+                        text = text[1:]
+                        start -= 1
+                    self._skip_lf = False
+            if data and (len(text) > end or self._decoder.getstate()[0]):
+                # Error handlers may change the length of the text, so find
+                # the bytes of the line by decoding prefixes of the chunk
+                needed = end - start
+                low, high = 0, len(data)
+                while low < high:
+                    middle = (low + high) // 2
+                    self._decoder.setstate(state)
+                    if len(self._decoder.decode(data[:middle])) >= needed:
+                        high = middle
+                    else:
+                        low = middle + 1
+                self._decoder.setstate(state)
+                self._decoder.decode(data[:low])
+                await self._file.seek(low - len(data), 1)
+            return self._translate(text[:end])
+
+        async def _encode(self, text: str) -> bytes:
+            if self._encoder is None:
+                self._encoder = codecs.getincrementalencoder(text_encoding)(
+                    text_errors
+                )
+                if writes_bom:
+                    # Like the built-in open(), only write a byte order mark
+                    # at the start of the file
+                    if "a" in mode:
+                        position = await self._file.seek(0, 2)
+                    else:
+                        position = await self._file.tell()
+                    if position != 0:
+                        self._encoder.setstate(0)
+            if write_newline != "\n":
+                text = text.replace("\n", write_newline)
+            return self._encoder.encode(text)
+
+        async def write(self, data: Union[str, bytes]) -> int:
+            """Encode and write a string, returning the number of characters."""
+            if isinstance(data, str):
+                await self._file.write(await self._encode(data))
+                return len(data)
+            return await self._file.write(data)
+
+        async def writelines(self, lines: Iterable[Union[str, bytes]]) -> None:
+            """Encode and write a sequence of strings."""
+            await self._file.writelines(
+                [
+                    await self._encode(line) if isinstance(line, str) else line
+                    for line in lines
+                ]
+            )
+
+        async def seek(self, offset: int, whence: int = 0) -> int:
+            """Seek, dropping any partly decoded character."""
+            pending, flag = self._decoder.getstate()
+            if whence == 1:
+                offset -= len(pending)
+            position: int = await self._file.seek(offset, whence)
+            self._skip_lf = False
+            if position == 0:
+                self._decoder.reset()
+            else:
+                # Keep the byte order found in a byte order mark
+                self._decoder.setstate((b"", flag))
+            self._encoder = None
+            return position
+
+        async def tell(self) -> int:
+            """Return the position of the next character to read."""
+            pending, _ = self._decoder.getstate()
+            return await self._file.tell() - len(pending)
+
+        async def dup(self) -> "_TextModeWrapper":
+            """Duplicate the handle, keeping text mode decoding."""
+            return _TextModeWrapper(await self._file.dup())
+
+        async def detach(self) -> AsyncFile:
+            """Return the binary handle at the text position, invalidating this one."""
+            file = self._file
+            if isinstance(file, _DetachedFile):
+                raise ValueError("underlying buffer has been detached")
+            pending, _ = self._decoder.getstate()
+            if pending:
+                # Give back the start of a character split by a size limit
+                await file.seek(-len(pending), 1)
+            elif self._skip_lf and (await file.peek(1)).startswith(b"\n"):
+                # The rest of a "\r\n" already returned as "\n"
+                await file.read1(1)
+            self._file = _DetachedFile()  # type: ignore[assignment]
+            return file
+
+        async def __aenter__(self) -> "_TextModeWrapper":
+            return self
+
+        async def __aexit__(
+            self,
+            exc_type: Optional[Type[BaseException]],
+            exc_val: Optional[BaseException],
+            exc_tb: Optional[TracebackType],
+        ) -> Optional[bool]:
+            return await self._file.__aexit__(exc_type, exc_val, exc_tb)
 
         def __getattr__(self, name: str) -> Any:
             # Delegate all other attributes to the underlying file
             return getattr(self._file, name)
 
-    coro = open_file(file, mode, buffering, encoding, errors, newline, closefd, opener)
-    return _OpenContextManager(coro, mode)
+    return _OpenContextManager(
+        lambda: open_file(file, mode, buffering, closefd, opener, access),
+        mode,
+    )
+
+
+# Open handle tracking
+def open_files() -> List[OpenFileInfo]:
+    """
+    List the file handles opened with `open()` that have not been closed.
+
+    A handle counts as open until `close()` is awaited or its `async with`
+    block exits. Handles that are garbage collected while still open are
+    flushed and closed, removed from the list and reported with a
+    `ResourceWarning`, the same
+    warning Python emits for unclosed built-in files (shown by `python -X dev`
+    and by pytest).
+
+    Returns:
+        List[OpenFileInfo]: Open handles, oldest first, with `path`, `mode`,
+            `opened_at` (Unix timestamp) and `stack` (the formatted Python
+            stack of the `open()` call, or None unless
+            `capture_open_stacks()` was enabled at the time).
+
+    Example:
+        ```python
+        rapfiles.capture_open_stacks()
+        ...
+        for handle in rapfiles.open_files():
+            if time.time() - handle.opened_at > 300:
+                logger.warning("Long-lived handle %s\n%s", handle.path, handle.stack)
+        ```
+
+    See Also:
+        - `runtime_info()`: Includes the number of open handles.
+    """
+    return _open_files()
+
+
+def capture_open_stacks(enabled: bool = True) -> None:
+    """
+    Record the Python stack of every subsequent `open()` call.
+
+    The stacks are reported by `open_files()` and appended to the
+    `ResourceWarning` of a handle collected while open, which makes it easy
+    to find the code that leaks a handle. Capturing costs one traceback per open, so it
+    is disabled by default.
+
+    Args:
+        enabled: True to capture stacks, False to stop. Defaults to True.
+    """
+    _capture_open_stacks(enabled)
+
+
+# Descriptor passing
+async def send_file(sock: Union["socket.socket", int], file: AsyncFile) -> None:
+    """
+    Send an open file handle to another process over a Unix socket.
+
+    The descriptor travels as `SCM_RIGHTS` ancillary data, so the receiving
+    process gets the open file itself: it does not need permission to open
+    the path, and the file keeps working if it was renamed or removed. This
+    suits a dispatcher that opens files and hands them to worker processes.
+    Both handles share the file position; the sending handle stays open.
+
+    Args:
+        sock: Connected Unix stream socket (e.g. from `socket.socketpair()`),
+            or its descriptor. Blocking and non-blocking sockets both work.
+        file: Binary-mode handle to send. Call `flush()` first so the
+            receiver sees pending writes.
+
+    Raises:
+        TypeError: If the handle is in text mode.
+        ValueError: If the handle is closed.
+        OSError: If the descriptor cannot be sent.
+        NotImplementedError: On platforms other than Unix.
+
+    Example:
+        ```python
+        parent_sock, child_sock = socket.socketpair()
+        async with rapfiles.open("upload.bin", "rb") as f:
+            await rapfiles.send_file(parent_sock, f)
+
+        # In the worker
+        async with await rapfiles.recv_file(child_sock) as f:
+            data = await f.read()
+        ```
+
+    See Also:
+        - `AsyncFile.detach_fd()`: Give up a handle's descriptor.
+        - Pickling: `AsyncFile` can also be sent through `multiprocessing`
+          queues and pipes.
+    """
+    await send_file_async(sock if isinstance(sock, int) else sock.fileno(), file)
+
+
+async def recv_file(sock: Union["socket.socket", int]) -> AsyncFile:
+    """
+    Receive a file handle sent with `send_file()`.
+
+    Args:
+        sock: Connected Unix stream socket, or its descriptor.
+
+    Returns:
+        AsyncFile: A handle with the sender's path, mode and access mode,
+            owning the received descriptor. Close it when done.
+
+    Raises:
+        OSError: If receiving fails, the socket was closed, or the message
+            was not sent by `send_file()`.
+        NotImplementedError: On platforms other than Unix.
+    """
+    return await recv_file_async(sock if isinstance(sock, int) else sock.fileno())
+
+
+def _rebuild_file(dup_fd: Any, *state: Any) -> AsyncFile:
+    """Unpickle an `AsyncFile` from its transferred descriptor and state."""
+    return AsyncFile._adopt_fd(dup_fd.detach(), *state)
+
+
+# Small-file fast path
+def set_small_file_threshold(size: int) -> None:
+    """
+    Set the size up to which read-only `open()` calls load the file whole.
+
+    Opening a file of at most this many bytes in "r" or "rb" mode opens and
+    reads it in a single blocking-pool job; the handle's `read()`,
+    `readline()`, `seek()` and `tell()` are then served from memory. This
+    removes most of the per-operation overhead of reading small files. Once
+    the loaded contents are consumed, reads continue on the file itself, so
+    data appended after it was opened is still read; changes made in place
+    to the part already loaded are not seen.
+
+    Args:
+        size: Threshold in bytes (default 65536, the read-ahead of a regular
+            handle). 0 disables the fast path.
+
+    Example:
+        ```python
+        rapfiles.set_small_file_threshold(16 * 1024)
+        async with rapfiles.open("config.json") as f:
+            config = json.loads(await f.read())
+        ```
+    """
+    _set_small_file_threshold(size)
+
+
+def get_small_file_threshold() -> int:
+    """
+    Return the size up to which read-only `open()` calls load the file whole.
+
+    Returns:
+        int: Threshold in bytes (65536 by default); 0 if the fast path is
+        disabled.
+    """
+    return _get_small_file_threshold()
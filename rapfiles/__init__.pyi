@@ -1,15 +1,35 @@
 """Type stubs for rapfiles public API."""
 
+import asyncio
+import socket
+from types import TracebackType
 from typing import (
     Any,
+    Awaitable,
+    Type,
+    TypeVar,
     Optional,
     Union,
     List,
     Tuple,
     Dict,
+    Callable,
+    ContextManager,
+    Iterable,
 )
 
-from ._rapfiles import FileMetadata
+from ._rapfiles import (
+    DedupeReport,
+    DirCompare,
+    DirDiff,
+    FileChangedError,
+    FileMetadata,
+    OpenFileInfo,
+    PermissionFinding,
+    PortabilityIssue,
+    ReadOnlyError,
+    Route,
+)
 
 __version__: str
 
@@ -18,17 +38,28 @@ __all__: List[str]
 
 # Convenience async functions
 async def read_file(path: str) -> str: ...
-async def write_file(path: str, contents: str) -> None: ...
+async def write_file(path: str, contents: str, *, durability: str = ...) -> None: ...
 async def read_file_bytes(path: str) -> bytes: ...
-async def write_file_bytes(path: str, contents: bytes) -> None: ...
-async def append_file(path: str, contents: str) -> None: ...
+async def write_file_bytes(
+    path: str, contents: bytes, *, durability: str = ...
+) -> None: ...
+async def append_file(path: str, contents: str, *, durability: str = ...) -> None: ...
+async def read_with_stat(path: str) -> Tuple[FileMetadata, bytes]: ...
+async def read_exact(
+    source: Union[str, AsyncFile], size: int, *, timeout: Optional[float] = None
+) -> bytes: ...
 
 # Directory operations
 async def create_dir(path: str) -> None: ...
 async def create_dir_all(path: str) -> None: ...
 async def remove_dir(path: str) -> None: ...
-async def remove_dir_all(path: str) -> None: ...
-async def list_dir(path: str) -> List[str]: ...
+async def remove_dir_all(
+    path: str, *, rate_limit_ops: Optional[float] = ..., nice: bool = ...
+) -> None: ...
+async def list_dir(path: str, sorted: Union[bool, str] = ...) -> List[str]: ...
+async def list_dir_stat(
+    path: str, sorted: Union[bool, str] = ...
+) -> List[Tuple[str, FileMetadata]]: ...
 async def exists(path: str) -> bool: ...
 async def is_file(path: str) -> bool: ...
 async def is_dir(path: str) -> bool: ...
@@ -38,20 +69,83 @@ async def stat(path: str) -> FileMetadata: ...
 async def metadata(path: str) -> FileMetadata: ...
 
 # Directory traversal
-async def walk_dir(path: str) -> List[Tuple[str, bool]]: ...
+async def walk_dir(
+    path: str, sorted: Union[bool, str] = ...
+) -> List[Tuple[str, bool]]: ...
+
+# Permission auditing
+async def audit_permissions(
+    root: str, policy: Optional[Dict[str, Any]] = ...
+) -> List[PermissionFinding]: ...
+
+# Portability checks
+async def check_portability(
+    root: str, max_path_length: int = ...
+) -> List[PortabilityIssue]: ...
+
+# Directory comparison
+def dircmp(a: str, b: str, compare_content: bool = ...) -> DirCompare: ...
 
 # File manipulation operations
-async def copy_file(src: str, dst: str) -> None: ...
+async def copy_file(
+    src: str, dst: str, preserve_owner: bool = ..., *, durability: str = ...
+) -> int: ...
+async def copytree(
+    src: str,
+    dst: str,
+    mode: str = ...,
+    preserve_owner: bool = ...,
+    checkpoint: Optional[str] = ...,
+    verify: str = ...,
+    sanitize: Optional[str] = ...,
+) -> Optional[List[Tuple[str, str]]]: ...
 async def move_file(src: str, dst: str) -> None: ...
+async def move(src: str, dst: str, *, durability: str = ...) -> str: ...
 async def rename(src: str, dst: str) -> None: ...
+async def replace(src: str, dst: str) -> None: ...
 async def remove_file(path: str) -> None: ...
+async def unlink(path: str, missing_ok: bool = ...) -> None: ...
+async def touch(path: str, exist_ok: bool = ...) -> None: ...
+async def truncate(path: str, size: int) -> None: ...
+async def chmod(path: str, mode: int) -> None: ...
+async def chown(
+    path: str, uid: int, gid: int, *, follow_symlinks: bool = ...
+) -> None: ...
+async def lchown(path: str, uid: int, gid: int) -> None: ...
 async def hard_link(src: str, dst: str) -> None: ...
-async def symlink(src: str, dst: str) -> None: ...
+async def link(src: str, dst: str) -> None: ...
+async def symlink(src: str, dst: str, target_is_directory: bool = ...) -> None: ...
 async def canonicalize(path: str) -> str: ...
+async def realpath(path: str, *, strict: bool = ...) -> str: ...
+async def readlink(path: str) -> str: ...
+
+# Deduplication
+async def dedupe_tree(
+    root: str, *, method: str = ..., dry_run: bool = ...
+) -> DedupeReport: ...
 
 # Atomic operations
-async def atomic_write_file(path: str, contents: str) -> None: ...
-async def atomic_write_file_bytes(path: str, contents: bytes) -> None: ...
+async def atomic_write_file(
+    path: str,
+    contents: str,
+    finalize_read_only: bool = ...,
+    *,
+    durability: str = ...,
+) -> None: ...
+async def atomic_write_file_bytes(
+    path: str,
+    contents: bytes,
+    finalize_read_only: bool = ...,
+    *,
+    durability: str = ...,
+) -> None: ...
+async def write_if_unchanged(
+    path: str,
+    data: Union[str, bytes],
+    expected: Union[None, float, str],
+    *,
+    durability: str = ...,
+) -> None: ...
 async def atomic_move_file(src: str, dst: str) -> None: ...
 
 # File locking
@@ -59,16 +153,252 @@ def lock_file(
     path: str, exclusive: bool = ...
 ) -> Any: ...  # Returns _LockContextManager
 def lock_file_shared(path: str) -> Any: ...  # Returns _LockContextManager
+def lease_file(
+    path: str,
+    ttl: float = ...,
+    owner: Optional[str] = ...,
+    timeout: Optional[float] = ...,
+    poll_interval: float = ...,
+) -> Any: ...  # Returns _LockContextManager yielding FileLease
+
+class FileLease:
+    @property
+    def path(self) -> str: ...
+    @property
+    def owner(self) -> str: ...
+    @property
+    def ttl(self) -> float: ...
+    @property
+    def expires(self) -> float: ...
+    @property
+    def held(self) -> bool: ...
+    async def release(self) -> None: ...
+
+# Log reading
+class LogReader:
+    @property
+    def path(self) -> str: ...
+    @property
+    def offset(self) -> int: ...
+    async def read_new(self) -> bytes: ...
+    async def read_lines(self) -> List[bytes]: ...
+    async def wait(self, timeout: Optional[float] = ...) -> bool: ...
+    def close(self) -> None: ...
+    def __aiter__(self) -> "LogReader": ...
+    async def __anext__(self) -> bytes: ...
+
+async def open_log(
+    path: str, from_end: bool = ..., poll_interval: float = ...
+) -> LogReader: ...
+
+# Directory watching
+class WatchEvent:
+    @property
+    def kind(self) -> str: ...
+    @property
+    def path(self) -> str: ...
+    @property
+    def is_dir(self) -> bool: ...
+    @property
+    def existing(self) -> bool: ...
+    @property
+    def token(self) -> Optional[str]: ...
+
+class DirWatcher:
+    @property
+    def path(self) -> str: ...
+    def close(self) -> None: ...
+    def __aiter__(self) -> "DirWatcher": ...
+    async def __anext__(self) -> WatchEvent: ...
+
+async def watch(
+    path: str,
+    emit_existing: bool = ...,
+    recursive: bool = ...,
+    poll_interval: float = ...,
+    journal: Optional[str] = ...,
+    resume_token: Optional[str] = ...,
+    emit_when_stable: Optional[float] = ...,
+) -> DirWatcher: ...
+
+# Config file reads
+async def read_toml(path: str) -> Dict[str, Any]: ...
+async def read_yaml(path: str) -> Any: ...
+
+# Config file updates
+async def update_json(
+    path: str,
+    mutate: Callable[[Any], Any],
+    default: Any = ...,
+    indent: Optional[int] = ...,
+    *,
+    durability: str = ...,
+) -> Any: ...
+async def update_toml(
+    path: str,
+    mutate: Callable[[Any], Any],
+    default: Optional[Dict[str, Any]] = ...,
+    *,
+    durability: str = ...,
+) -> Dict[str, Any]: ...
+async def save_config(
+    path: str,
+    data: Any,
+    *,
+    format: str = ...,
+    backup: bool = ...,
+    mode: Optional[int] = ...,
+    durability: str = ...,
+) -> None: ...
+
+# Cleanup registry
+def register_cleanup(path: str, on_loop_shutdown: bool = ...) -> str: ...
+def unregister_cleanup(path: str) -> bool: ...
+def registered_cleanup() -> List[str]: ...
+async def run_cleanup() -> List[str]: ...
+
+# Scratch space
+async def best_scratch_dir(
+    min_free: int = ..., candidates: Optional[List[str]] = ...
+) -> Optional[str]: ...
+
+# Runtime introspection
+def runtime_info() -> Dict[str, Any]: ...
+
+# I/O backends
+def set_backend(name: str) -> None: ...
+def get_backend(path: Optional[str] = None) -> str: ...
+def available_backends() -> List[str]: ...
+def use_backend(name: str) -> ContextManager[None]: ...
+def add_route(
+    prefix: str, backend: Optional[str] = None, concurrency: Optional[int] = None
+) -> None: ...
+def remove_route(prefix: str) -> bool: ...
+def clear_routes() -> None: ...
+def routes() -> List[Route]: ...
+
+# Operation options
+def options(
+    timeout: Optional[float] = None,
+    retries: Optional[int] = None,
+    rate_limit: Optional[float] = None,
+    priority: Optional[str] = None,
+    job: Optional[str] = None,
+    weight: Optional[float] = None,
+) -> ContextManager[None]: ...
+def get_options() -> Dict[str, Any]: ...
+
+# Read-only mode
+def set_read_only(
+    enabled: bool = True, writable_roots: Optional[Iterable[str]] = None
+) -> None: ...
+def is_read_only() -> bool: ...
+def writable_roots() -> List[str]: ...
+
+# Configuration
+def configure(**options: Any) -> None: ...
+def get_config() -> Dict[str, Any]: ...
+def preload() -> None: ...
+def reinit_after_fork() -> None: ...
+
+# Task groups
+_R = TypeVar("_R")
+
+class TaskGroup:
+    max_concurrency: Optional[int]
+    def __init__(self, max_concurrency: Optional[int] = ...) -> None: ...
+    def submit(self, awaitable: Awaitable[_R]) -> "asyncio.Task[_R]": ...
+    async def __aenter__(self) -> "TaskGroup": ...
+    async def __aexit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None: ...
+
+# Positional I/O
+async def read_ranges(
+    path: str, ranges: List[Tuple[int, int]], coalesce_gap: Optional[int] = ...
+) -> List[bytes]: ...
+async def write_segments(
+    path: str, segments: List[Tuple[int, bytes]], *, durability: str = ...
+) -> None: ...
+async def list_extents(path: str) -> List[Tuple[int, int]]: ...
+async def create_filled(
+    path: str,
+    size: int,
+    fill: str = ...,
+    *,
+    seed: Optional[int] = ...,
+    durability: str = ...,
+) -> None: ...
 
 # Batch operations
 async def read_files(paths: List[str]) -> List[Tuple[str, bytes]]: ...
 async def read_files_dict(paths: List[str]) -> Dict[str, bytes]: ...
-async def write_files(files: Dict[str, bytes]) -> None: ...
-async def copy_files(files: List[Tuple[str, str]]) -> None: ...
+async def write_files(files: Dict[str, bytes], *, durability: str = ...) -> None: ...
+async def copy_files(
+    files: List[Tuple[str, str]], preserve_owner: bool = ..., *, durability: str = ...
+) -> None: ...
+async def rename_many(pairs: List[Tuple[str, str]]) -> None: ...
+async def rename_pattern(
+    path: str, pattern: str, template: str
+) -> List[Tuple[str, str]]: ...
+async def generate_tree(
+    root: str,
+    n_dirs: int,
+    n_files: int,
+    size_distribution: Union[int, Tuple[int, int]] = ...,
+    name_seed: int = ...,
+) -> List[str]: ...
+
+# Transactions
+class Transaction:
+    @property
+    def journal(self) -> Optional[str]: ...
+    @property
+    def status(self) -> str: ...
+    @property
+    def pending(self) -> int: ...
+    async def write(self, path: str, data: Union[str, bytes]) -> None: ...
+    def rename(self, src: str, dst: str) -> None: ...
+    def remove(self, path: str) -> None: ...
+    async def commit(self) -> None: ...
+    async def rollback(self) -> None: ...
+    async def __aenter__(self) -> "Transaction": ...
+    async def __aexit__(self, *args: Any) -> None: ...
+
+def transaction(journal: Optional[str] = None) -> Transaction: ...
+async def recover_transaction(journal: str) -> Optional[bool]: ...
+
+# Disk cache
+class Cache:
+    def __init__(
+        self,
+        root: str,
+        max_bytes: int = ...,
+        default_ttl: Optional[float] = ...,
+    ) -> None: ...
+    @property
+    def root(self) -> str: ...
+    @property
+    def max_bytes(self) -> int: ...
+    @property
+    def default_ttl(self) -> Optional[float]: ...
+    async def get(self, key: Union[str, bytes]) -> Optional[bytes]: ...
+    async def set(
+        self,
+        key: Union[str, bytes],
+        value: Union[str, bytes],
+        ttl: Optional[float] = ...,
+    ) -> None: ...
+    async def delete(self, key: Union[str, bytes]) -> bool: ...
+    async def clear(self) -> None: ...
+    async def evict(self) -> int: ...
 
 # File opening - returns an async context manager
 def open(
-    file: Union[str, bytes],
+    file: Union[str, bytes, int],
     mode: str = ...,
     buffering: int = ...,
     encoding: Optional[str] = ...,
@@ -76,16 +406,60 @@ def open(
     newline: Optional[str] = ...,
     closefd: bool = ...,
     opener: Optional[Any] = ...,
+    access: str = ...,
 ) -> Any: ...  # Returns _OpenContextManager which is internal
 
+# Open handle tracking
+def open_files() -> List[OpenFileInfo]: ...
+def capture_open_stacks(enabled: bool = ...) -> None: ...
+async def send_file(sock: Union[socket.socket, int], file: AsyncFile) -> None: ...
+async def recv_file(sock: Union[socket.socket, int]) -> AsyncFile: ...
+def set_small_file_threshold(size: int) -> None: ...
+def get_small_file_threshold() -> int: ...
+
 # File handle class
 class AsyncFile:
     async def read(self, size: int = ...) -> Union[str, bytes]: ...
     async def write(self, data: Union[str, bytes]) -> int: ...
+    async def writelines(self, lines: Iterable[Union[str, bytes]]) -> None: ...
+    async def readinto(self, buffer: Any) -> int: ...
+    async def readinto1(self, buffer: Any) -> int: ...
+    async def peek(self, size: int = ...) -> bytes: ...
+    async def read1(self, size: int = ...) -> bytes: ...
+    async def read_exact(self, size: int, timeout: Optional[float] = None) -> bytes: ...
     async def readline(self, size: int = ...) -> Union[str, bytes]: ...
     async def readlines(self, hint: int = ...) -> Union[List[str], List[bytes]]: ...
+    def __aiter__(self) -> "AsyncFile": ...
+    async def __anext__(self) -> Union[str, bytes]: ...
     def seek(self, offset: int, whence: int = ...) -> int: ...
     def tell(self) -> int: ...
+    async def truncate(self, size: Optional[int] = ...) -> int: ...
+    async def flush(self) -> None: ...
+    async def fsync(self) -> None: ...
+    async def fdatasync(self) -> None: ...
+    @property
+    def access(self) -> str: ...
+    @property
+    def line_buffering(self) -> bool: ...
+    @property
+    def name(self) -> Union[str, int]: ...
+    @property
+    def mode(self) -> str: ...
+    @property
+    def closed(self) -> bool: ...
+    def readable(self) -> bool: ...
+    def writable(self) -> bool: ...
+    def seekable(self) -> bool: ...
+    def fileno(self) -> int: ...
+    async def detach_fd(self) -> int: ...
+    @staticmethod
+    def from_fd(fd: int, mode: str = ..., closefd: bool = ...) -> Any: ...
+    async def pread(self, size: int, offset: int) -> bytes: ...
+    async def pwrite(self, data: Union[str, bytes], offset: int) -> int: ...
+    async def dup(self) -> "AsyncFile": ...
+    async def detach(self) -> "AsyncFile": ...  # Text mode only
+    async def reopen(self) -> None: ...
+    async def refresh(self) -> bool: ...
     async def close(self) -> None: ...
     async def __aenter__(self) -> "AsyncFile": ...
     async def __aexit__(
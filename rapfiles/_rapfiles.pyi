@@ -1,32 +1,66 @@
 """Type stubs for _rapfiles Rust extension module."""
 
-from typing import Coroutine, Any, Optional, Union, List, Tuple, Type
+from typing import (
+    Callable,
+    Coroutine,
+    Any,
+    Dict,
+    Iterable,
+    Optional,
+    Union,
+    List,
+    Tuple,
+    Type,
+)
 
 # File operations
 def read_file_async(path: str) -> Coroutine[Any, Any, str]: ...
-def write_file_async(path: str, contents: str) -> Coroutine[Any, Any, None]: ...
+def write_file_async(
+    path: str, contents: str, durability: str = "flush"
+) -> Coroutine[Any, Any, None]: ...
 def read_file_bytes_async(path: str) -> Coroutine[Any, Any, bytes]: ...
-def write_file_bytes_async(path: str, contents: bytes) -> Coroutine[Any, Any, None]: ...
-def append_file_async(path: str, contents: str) -> Coroutine[Any, Any, None]: ...
+def read_with_stat_async(
+    path: str,
+) -> Coroutine[Any, Any, Tuple["FileMetadata", bytes]]: ...
+def read_exact_async(
+    source: Union[str, "AsyncFile"], size: int, timeout: Optional[float] = None
+) -> Coroutine[Any, Any, bytes]: ...
+def write_file_bytes_async(
+    path: str, contents: bytes, durability: str = "flush"
+) -> Coroutine[Any, Any, None]: ...
+def append_file_async(
+    path: str, contents: str, durability: str = "flush"
+) -> Coroutine[Any, Any, None]: ...
 
 # File handles
 def open_file(
-    path: str,
+    path: Union[str, int],
     mode: str = "r",
     buffering: int = -1,
-    encoding: Optional[str] = None,
-    errors: Optional[str] = None,
-    newline: Optional[str] = None,
     closefd: bool = True,
     opener: Optional[Any] = None,
+    access: str = "serialized",
 ) -> Coroutine[Any, Any, "AsyncFile"]: ...
+def open_files() -> List["OpenFileInfo"]: ...
+def capture_open_stacks(enabled: bool = True) -> None: ...
+def send_file_async(sock: int, file: "AsyncFile") -> Coroutine[Any, Any, None]: ...
+def recv_file_async(sock: int) -> Coroutine[Any, Any, "AsyncFile"]: ...
+def set_small_file_threshold(size: int) -> None: ...
+def get_small_file_threshold() -> int: ...
 
 # Directory operations
 def create_dir_async(path: str) -> Coroutine[Any, Any, None]: ...
 def create_dir_all_async(path: str) -> Coroutine[Any, Any, None]: ...
 def remove_dir_async(path: str) -> Coroutine[Any, Any, None]: ...
-def remove_dir_all_async(path: str) -> Coroutine[Any, Any, None]: ...
-def list_dir_async(path: str) -> Coroutine[Any, Any, List[str]]: ...
+def remove_dir_all_async(
+    path: str, *, rate_limit_ops: Optional[float] = ..., nice: bool = ...
+) -> Coroutine[Any, Any, None]: ...
+def list_dir_async(
+    path: str, sorted: Union[bool, str, None] = None
+) -> Coroutine[Any, Any, List[str]]: ...
+def list_dir_stat_async(
+    path: str, sorted: Union[bool, str, None] = None
+) -> Coroutine[Any, Any, List[Tuple[str, "FileMetadata"]]]: ...
 def exists_async(path: str) -> Coroutine[Any, Any, bool]: ...
 def is_file_async(path: str) -> Coroutine[Any, Any, bool]: ...
 def is_dir_async(path: str) -> Coroutine[Any, Any, bool]: ...
@@ -36,7 +70,327 @@ def stat_async(path: str) -> Coroutine[Any, Any, "FileMetadata"]: ...
 def metadata_async(path: str) -> Coroutine[Any, Any, "FileMetadata"]: ...
 
 # Directory traversal
-def walk_dir_async(path: str) -> Coroutine[Any, Any, List[Tuple[str, bool]]]: ...
+def walk_dir_async(
+    path: str, sorted: Union[bool, str, None] = None
+) -> Coroutine[Any, Any, List[Tuple[str, bool]]]: ...
+
+# Permission auditing
+def audit_permissions_async(
+    path: str,
+    world_writable: bool = True,
+    setuid: bool = True,
+    owner: Optional[int] = None,
+    group: Optional[int] = None,
+) -> Coroutine[Any, Any, List["PermissionFinding"]]: ...
+
+# Portability checks
+def check_portability_async(
+    root: str, max_path_length: int = 260
+) -> Coroutine[Any, Any, List["PortabilityIssue"]]: ...
+
+# Directory comparison
+def dircmp_async(a: str, b: str, compare_content: bool = False) -> "DirCompare": ...
+
+# File manipulation
+def copytree_async(
+    src: str,
+    dst: str,
+    mode: str = "copy",
+    preserve_owner: bool = False,
+    checkpoint: Optional[str] = None,
+    verify: str = "stat",
+    sanitize: Optional[str] = None,
+) -> Coroutine[Any, Any, Optional[List[Tuple[str, str]]]]: ...
+def move_async(
+    src: str, dst: str, durability: str = "flush"
+) -> Coroutine[Any, Any, str]: ...
+def rename_async(src: str, dst: str) -> Coroutine[Any, Any, None]: ...
+def replace_async(src: str, dst: str) -> Coroutine[Any, Any, None]: ...
+def unlink_async(path: str, missing_ok: bool = False) -> Coroutine[Any, Any, None]: ...
+def touch_async(path: str, exist_ok: bool = True) -> Coroutine[Any, Any, None]: ...
+def truncate_async(path: str, size: int) -> Coroutine[Any, Any, None]: ...
+def chmod_async(path: str, mode: int) -> Coroutine[Any, Any, None]: ...
+def chown_async(
+    path: str, uid: int, gid: int, follow_symlinks: bool = True
+) -> Coroutine[Any, Any, None]: ...
+def hard_link_async(src: str, dst: str) -> Coroutine[Any, Any, None]: ...
+def link_async(src: str, dst: str) -> Coroutine[Any, Any, None]: ...
+def symlink_async(
+    src: str, dst: str, target_is_directory: bool = False
+) -> Coroutine[Any, Any, None]: ...
+def readlink_async(path: str) -> Coroutine[Any, Any, str]: ...
+def realpath_async(path: str, strict: bool = False) -> Coroutine[Any, Any, str]: ...
+
+# Deduplication
+def dedupe_tree_async(
+    root: str, *, method: str = "reflink", dry_run: bool = True
+) -> Coroutine[Any, Any, "DedupeReport"]: ...
+
+# Atomic operations
+def write_if_unchanged_async(
+    path: str,
+    data: Union[str, bytes],
+    expected: Union[None, float, str],
+    durability: str = "flush",
+) -> Coroutine[Any, Any, None]: ...
+
+class FileChangedError(Exception):
+    """Raised when write_if_unchanged() finds the file changed since it was read."""
+
+# Config file parsing
+def read_toml_async(path: str) -> Coroutine[Any, Any, Dict[str, Any]]: ...
+def toml_loads(text: str) -> Dict[str, Any]: ...
+def toml_dumps(data: Dict[str, Any]) -> str: ...
+def read_yaml_async(path: str) -> Coroutine[Any, Any, Any]: ...
+def save_config_async(
+    path: str,
+    contents: bytes,
+    backup: bool = True,
+    mode: Optional[int] = 0o600,
+    durability: str = "fsync+dir",
+) -> Coroutine[Any, Any, None]: ...
+
+# Batch operations
+def rename_many_async(
+    pairs: List[Tuple[str, str]],
+) -> Coroutine[Any, Any, None]: ...
+def rename_pattern_async(
+    path: str, pattern: str, template: str
+) -> Coroutine[Any, Any, List[Tuple[str, str]]]: ...
+def generate_tree_async(
+    root: str,
+    n_dirs: int,
+    n_files: int,
+    size_distribution: Tuple[int, int] = ...,
+    name_seed: int = ...,
+) -> Coroutine[Any, Any, List[str]]: ...
+
+# Transactions
+class Transaction:
+    """Writes, renames and removals applied together on commit."""
+
+    @property
+    def journal(self) -> Optional[str]: ...
+    @property
+    def status(self) -> str: ...
+    @property
+    def pending(self) -> int: ...
+    def write(
+        self, path: str, data: Union[str, bytes]
+    ) -> Coroutine[Any, Any, None]: ...
+    def rename(self, src: str, dst: str) -> None: ...
+    def remove(self, path: str) -> None: ...
+    def commit(self) -> Coroutine[Any, Any, None]: ...
+    def rollback(self) -> Coroutine[Any, Any, None]: ...
+    def __aenter__(self) -> "Transaction": ...
+    def __aexit__(self, *args: Any) -> Coroutine[Any, Any, None]: ...
+
+def transaction(journal: Optional[str] = None) -> Transaction: ...
+def recover_transaction_async(journal: str) -> Coroutine[Any, Any, Optional[bool]]: ...
+
+# Disk cache
+class Cache:
+    """Disk cache of byte values with expiry and a size limit."""
+
+    def __init__(
+        self,
+        root: str,
+        max_bytes: int = 1 << 30,
+        default_ttl: Optional[float] = None,
+    ) -> None: ...
+    @property
+    def root(self) -> str: ...
+    @property
+    def max_bytes(self) -> int: ...
+    @property
+    def default_ttl(self) -> Optional[float]: ...
+    def get(self, key: Union[str, bytes]) -> Coroutine[Any, Any, Optional[bytes]]: ...
+    def set(
+        self,
+        key: Union[str, bytes],
+        value: Union[str, bytes],
+        ttl: Optional[float] = None,
+    ) -> Coroutine[Any, Any, None]: ...
+    def delete(self, key: Union[str, bytes]) -> Coroutine[Any, Any, bool]: ...
+    def clear(self) -> Coroutine[Any, Any, None]: ...
+    def evict(self) -> Coroutine[Any, Any, int]: ...
+
+# Cleanup registry
+def register_cleanup(path: str, scope: int = 0) -> str: ...
+def unregister_cleanup(path: str) -> bool: ...
+def registered_cleanup() -> List[str]: ...
+def run_cleanup(scope: Optional[int] = None) -> List[str]: ...
+def run_cleanup_async(
+    scope: Optional[int] = None,
+) -> Coroutine[Any, Any, List[str]]: ...
+
+# Scratch space
+def best_scratch_dir_async(
+    min_free: int = ..., candidates: Optional[List[str]] = ...
+) -> Coroutine[Any, Any, Optional[str]]: ...
+
+# Runtime introspection
+def runtime_info() -> Dict[str, Any]: ...
+
+# Positional I/O
+def read_ranges_async(
+    path: str, ranges: List[Tuple[int, int]], coalesce_gap: Optional[int] = None
+) -> Coroutine[Any, Any, List[bytes]]: ...
+def write_segments_async(
+    path: str, segments: List[Tuple[int, bytes]], durability: str = "fsync"
+) -> Coroutine[Any, Any, None]: ...
+def list_extents_async(path: str) -> Coroutine[Any, Any, List[Tuple[int, int]]]: ...
+def create_filled_async(
+    path: str,
+    size: int,
+    fill: str = ...,
+    *,
+    seed: Optional[int] = ...,
+    durability: str = ...,
+) -> Coroutine[Any, Any, None]: ...
+
+# I/O backends
+def set_backend(name: str) -> None: ...
+def get_backend(path: Optional[str] = None) -> str: ...
+def available_backends() -> List[str]: ...
+def override_backend(name: str) -> Any: ...
+def reset_backend_override(token: Any) -> None: ...
+def add_route(
+    prefix: str, backend: Optional[str] = None, concurrency: Optional[int] = None
+) -> None: ...
+def remove_route(prefix: str) -> bool: ...
+def clear_routes() -> None: ...
+def routes() -> List[Route]: ...
+
+# Operation options
+def override_options(
+    timeout: Optional[float] = None,
+    retries: Optional[int] = None,
+    rate_limit: Optional[float] = None,
+    priority: Optional[str] = None,
+    job: Optional[str] = None,
+    weight: Optional[float] = None,
+) -> Any: ...
+def reset_options_override(token: Any) -> None: ...
+def get_options() -> Dict[str, Any]: ...
+
+# Read-only mode
+def set_read_only(
+    enabled: bool = True, writable_roots: Optional[List[str]] = None
+) -> None: ...
+def is_read_only() -> bool: ...
+def writable_roots() -> List[str]: ...
+
+class ReadOnlyError(PermissionError):
+    """Raised when read-only mode blocks a mutating operation."""
+
+# Configuration
+def configure(**options: Any) -> None: ...
+def get_config() -> Dict[str, Any]: ...
+def preload() -> None: ...
+def reinit_after_fork() -> None: ...
+
+# Leases
+def lease_file_async(
+    path: str,
+    ttl: float = 30.0,
+    owner: Optional[str] = None,
+    timeout: Optional[float] = None,
+    poll_interval: float = 1.0,
+) -> Coroutine[Any, Any, "FileLease"]: ...
+
+class FileLease:
+    """Advisory lease on a file, renewed in the background while held."""
+
+    @property
+    def path(self) -> str: ...
+    @property
+    def owner(self) -> str: ...
+    @property
+    def ttl(self) -> float: ...
+    @property
+    def expires(self) -> float: ...
+    @property
+    def held(self) -> bool: ...
+    def release(self) -> Coroutine[Any, Any, None]: ...
+    def __aenter__(self) -> "FileLease": ...
+    def __aexit__(self, *args: Any) -> Coroutine[Any, Any, None]: ...
+
+# Log reading
+def open_log_async(
+    path: str, from_end: bool = False, poll_interval: float = 0.1
+) -> Coroutine[Any, Any, "LogReader"]: ...
+
+class LogReader:
+    """Reader following a log file while other processes append to it."""
+
+    @property
+    def path(self) -> str: ...
+    @property
+    def offset(self) -> int: ...
+    def read_new(self) -> Coroutine[Any, Any, bytes]: ...
+    def read_lines(self) -> Coroutine[Any, Any, List[bytes]]: ...
+    def wait(self, timeout: Optional[float] = None) -> Coroutine[Any, Any, bool]: ...
+    def close(self) -> None: ...
+    def __aiter__(self) -> "LogReader": ...
+    def __anext__(self) -> Coroutine[Any, Any, bytes]: ...
+
+# Directory watching
+def watch_async(
+    path: str,
+    emit_existing: bool = True,
+    recursive: bool = True,
+    poll_interval: float = 1.0,
+    journal: Optional[str] = None,
+    resume_token: Optional[str] = None,
+    emit_when_stable: Optional[float] = None,
+) -> Coroutine[Any, Any, "DirWatcher"]: ...
+
+class WatchEvent:
+    """Change to an entry of a watched directory."""
+
+    @property
+    def kind(self) -> str: ...
+    @property
+    def path(self) -> str: ...
+    @property
+    def is_dir(self) -> bool: ...
+    @property
+    def existing(self) -> bool: ...
+    @property
+    def token(self) -> Optional[str]: ...
+
+class DirWatcher:
+    """Async iterator over changes to a directory tree."""
+
+    @property
+    def path(self) -> str: ...
+    def close(self) -> None: ...
+    def __aiter__(self) -> "DirWatcher": ...
+    def __anext__(self) -> Coroutine[Any, Any, WatchEvent]: ...
+
+# Testing hooks
+def inject_fault(
+    pattern: str,
+    errno: int,
+    probability: float = 1.0,
+    ops: Optional[List[str]] = None,
+    seed: Optional[int] = None,
+) -> int: ...
+def remove_fault(id: int) -> bool: ...
+def clear_faults() -> None: ...
+def inject_latency(
+    pattern: str,
+    latency: float = 0.0,
+    bytes_per_second: Optional[float] = None,
+    ops: Optional[List[str]] = None,
+) -> int: ...
+def remove_latency(id: int) -> bool: ...
+def clear_latency() -> None: ...
+def start_recording() -> None: ...
+def stop_recording() -> List["OpRecord"]: ...
+def start_audit(sink: Callable[[Dict[str, Any]], Any], actor_var: Any) -> None: ...
+def stop_audit() -> bool: ...
 
 class AsyncFile:
     """Async file handle for true async I/O operations."""
@@ -54,12 +408,52 @@ class AsyncFile:
     ) -> None: ...
     def read(self, size: int = -1) -> Coroutine[Any, Any, bytes]: ...
     def write(self, data: Union[str, bytes]) -> Coroutine[Any, Any, int]: ...
+    def writelines(
+        self, lines: Iterable[Union[str, bytes]]
+    ) -> Coroutine[Any, Any, None]: ...
+    def readinto(self, buffer: Any) -> Coroutine[Any, Any, int]: ...
+    def readinto1(self, buffer: Any) -> Coroutine[Any, Any, int]: ...
+    def peek(self, size: int = ...) -> Coroutine[Any, Any, bytes]: ...
+    def read1(self, size: int = ...) -> Coroutine[Any, Any, bytes]: ...
+    def read_exact(
+        self, size: int, timeout: Optional[float] = None
+    ) -> Coroutine[Any, Any, bytes]: ...
     def readline(self, size: int = -1) -> Coroutine[Any, Any, bytes]: ...
     def readlines(self, hint: int = -1) -> Coroutine[Any, Any, List[bytes]]: ...
+    def __aiter__(self) -> "AsyncFile": ...
+    def __anext__(self) -> Coroutine[Any, Any, bytes]: ...
     def seek(self, offset: int, whence: int = 0) -> Coroutine[Any, Any, int]: ...
     def tell(self) -> Coroutine[Any, Any, int]: ...
+    def truncate(self, size: Optional[int] = None) -> Coroutine[Any, Any, int]: ...
+    def flush(self) -> Coroutine[Any, Any, None]: ...
+    def fsync(self) -> Coroutine[Any, Any, None]: ...
+    def fdatasync(self) -> Coroutine[Any, Any, None]: ...
+    @property
+    def access(self) -> str: ...
+    @property
+    def line_buffering(self) -> bool: ...
+    @property
+    def name(self) -> Union[str, int]: ...
+    @property
+    def mode(self) -> str: ...
+    @property
+    def closed(self) -> bool: ...
+    def readable(self) -> bool: ...
+    def writable(self) -> bool: ...
+    def seekable(self) -> bool: ...
+    def fileno(self) -> int: ...
+    def detach_fd(self) -> Coroutine[Any, Any, int]: ...
+    @staticmethod
+    def from_fd(fd: int, mode: str = "r", closefd: bool = True) -> Any: ...
+    def pread(self, size: int, offset: int) -> Coroutine[Any, Any, bytes]: ...
+    def pwrite(
+        self, data: Union[str, bytes], offset: int
+    ) -> Coroutine[Any, Any, int]: ...
+    def dup(self) -> Coroutine[Any, Any, "AsyncFile"]: ...
+    def reopen(self) -> Coroutine[Any, Any, None]: ...
+    def refresh(self) -> Coroutine[Any, Any, bool]: ...
     def close(self) -> Coroutine[Any, Any, None]: ...
-    def __aenter__(self) -> "AsyncFile": ...
+    def __aenter__(self) -> Coroutine[Any, Any, "AsyncFile"]: ...
     def __aexit__(
         self,
         exc_type: Optional[Type[BaseException]],
@@ -91,3 +485,95 @@ class FileMetadata:
     def accessed(self) -> float: ...
     @property
     def created(self) -> float: ...
+
+class PermissionFinding:
+    """Permission policy violation reported by audit_permissions_async."""
+
+    @property
+    def path(self) -> str: ...
+    @property
+    def issue(self) -> str: ...
+    @property
+    def mode(self) -> int: ...
+    @property
+    def uid(self) -> int: ...
+    @property
+    def gid(self) -> int: ...
+
+class PortabilityIssue:
+    """Non-portable name reported by check_portability_async."""
+
+    @property
+    def path(self) -> str: ...
+    @property
+    def issue(self) -> str: ...
+    @property
+    def detail(self) -> str: ...
+
+class DedupeReport:
+    """Result of dedupe_tree_async."""
+
+    @property
+    def groups(self) -> List[List[str]]: ...
+    @property
+    def duplicates(self) -> int: ...
+    @property
+    def reclaimed(self) -> int: ...
+    @property
+    def dry_run(self) -> bool: ...
+
+class OpenFileInfo:
+    """An `AsyncFile` handle that has not been closed."""
+
+    @property
+    def path(self) -> str: ...
+    @property
+    def mode(self) -> str: ...
+    @property
+    def opened_at(self) -> float: ...
+    @property
+    def stack(self) -> Optional[str]: ...
+
+class Route:
+    """Backend and tuning applied to operations below a path prefix."""
+
+    @property
+    def prefix(self) -> str: ...
+    @property
+    def backend(self) -> Optional[str]: ...
+    @property
+    def concurrency(self) -> Optional[int]: ...
+
+class DirDiff:
+    """Difference between two trees reported by dircmp_async."""
+
+    @property
+    def path(self) -> str: ...
+    @property
+    def kind(self) -> str: ...
+    @property
+    def reason(self) -> Optional[str]: ...
+
+class OpRecord:
+    """Filesystem operation captured by start_recording()."""
+
+    @property
+    def op(self) -> str: ...
+    @property
+    def paths(self) -> List[str]: ...
+    @property
+    def bytes(self) -> int: ...
+    @property
+    def error(self) -> Optional[str]: ...
+    @property
+    def ok(self) -> bool: ...
+    @property
+    def start(self) -> float: ...
+    @property
+    def duration(self) -> float: ...
+
+class DirCompare:
+    """Async iterator of DirDiff objects produced by dircmp_async."""
+
+    def __aiter__(self) -> "DirCompare": ...
+    def __anext__(self) -> Coroutine[Any, Any, DirDiff]: ...
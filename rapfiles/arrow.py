@@ -0,0 +1,255 @@
+"""Random access adapter for pyarrow.
+
+pyarrow reads Parquet and Arrow IPC files through synchronous random access
+calls (`read_at(nbytes, offset)` and `size()`), often from its own worker
+threads. `RandomAccessFile` serves those calls with rapfiles' positional
+reads (`AsyncFile.pread()`), which run on a background event loop, so
+concurrent range reads neither share a cursor nor hold the GIL while the
+data is read.
+
+Example:
+    ```python
+    import pyarrow.parquet as pq
+    from rapfiles.arrow import open_input_file
+
+    with open_input_file("data.parquet") as source:
+        table = pq.read_table(source)
+    ```
+"""
+
+import asyncio
+import atexit
+import threading
+from types import TracebackType
+from typing import Any, Awaitable, Callable, Optional, Type, TypeVar
+
+from rapfiles import AsyncFile, open, stat
+
+__all__ = ["RandomAccessFile", "open_input_file"]
+
+T = TypeVar("T")
+
+_loop: Optional[asyncio.AbstractEventLoop] = None
+_loop_thread: Optional[threading.Thread] = None
+_loop_lock = threading.Lock()
+
+
+@atexit.register
+def _stop_loop() -> None:
+    # Stop the loop thread before the interpreter finalizes
+    with _loop_lock:
+        if _loop is not None and _loop_thread is not None:
+            _loop.call_soon_threadsafe(_loop.stop)
+            _loop_thread.join()
+
+
+def _run(function: Callable[..., Awaitable[T]], *args: Any) -> T:
+    # Calls arrive from pyarrow threads and from threads that already run an
+    # event loop, so run every operation on a dedicated loop thread. The
+    # awaitable is created there too, as rapfiles binds it to the running loop
+    async def call() -> T:
+        return await function(*args)
+
+    global _loop, _loop_thread
+    with _loop_lock:
+        if _loop is None:
+            _loop = asyncio.new_event_loop()
+            _loop_thread = threading.Thread(
+                target=_loop.run_forever, name="rapfiles-arrow", daemon=True
+            )
+            _loop_thread.start()
+    return asyncio.run_coroutine_threadsafe(call(), _loop).result()
+
+
+class RandomAccessFile:
+    """
+    Read-only file object with the random access interface pyarrow expects.
+
+    `read_at()` is thread-safe and does not move the file position. The
+    sequential `read()`/`seek()`/`tell()` methods share one position, like a
+    regular binary file. The size is captured when the file is opened.
+
+    Args:
+        path: Path to the file to read.
+
+    Raises:
+        FileNotFoundError: If the file does not exist.
+        IOError: If the file cannot be opened.
+
+    Example:
+        ```python
+        with RandomAccessFile("data.arrow") as f:
+            footer = f.read_at(10, f.size() - 10)
+        ```
+    """
+
+    def __init__(self, path: str) -> None:
+        self.path = path
+        self._file: Optional[AsyncFile] = _run(
+            lambda: open(path, "rb", access="positional")
+        )
+        self._size: int = _run(stat, path).size
+        self._position = 0
+        self._lock = threading.Lock()
+
+    def _handle(self) -> AsyncFile:
+        if self._file is None:
+            raise ValueError("I/O operation on closed file.")
+        return self._file
+
+    @property
+    def closed(self) -> bool:
+        """True if the file has been closed."""
+        return self._file is None
+
+    @property
+    def mode(self) -> str:
+        """The file mode ("rb")."""
+        return "rb"
+
+    def readable(self) -> bool:
+        return True
+
+    def seekable(self) -> bool:
+        return True
+
+    def writable(self) -> bool:
+        return False
+
+    def size(self) -> int:
+        """
+        Return the size of the file in bytes.
+
+        Returns:
+            int: Size of the file when it was opened.
+        """
+        self._handle()
+        return self._size
+
+    def read_at(self, nbytes: int, offset: int) -> bytes:
+        """
+        Read up to `nbytes` bytes starting at `offset`.
+
+        The file position is not used or changed, so calls from several
+        threads can run concurrently.
+
+        Args:
+            nbytes: Maximum number of bytes to read.
+            offset: Byte offset to read from.
+
+        Returns:
+            bytes: The data read; shorter than `nbytes` only at end of file.
+
+        Raises:
+            ValueError: If the file is closed or the arguments are negative.
+            IOError: If the read fails.
+        """
+        if nbytes < 0 or offset < 0:
+            raise ValueError("nbytes and offset must be non-negative")
+        handle = self._handle()
+        if nbytes == 0 or offset >= self._size:
+            return b""
+        return _run(handle.pread, min(nbytes, self._size - offset), offset)
+
+    def read(self, nbytes: Optional[int] = None) -> bytes:
+        """
+        Read up to `nbytes` bytes from the current position.
+
+        Args:
+            nbytes: Maximum number of bytes to read. None or a negative value
+                reads to the end of the file.
+
+        Returns:
+            bytes: The data read.
+        """
+        with self._lock:
+            if nbytes is None or nbytes < 0:
+                nbytes = max(self._size - self._position, 0)
+            data = self.read_at(nbytes, self._position)
+            self._position += len(data)
+        return data
+
+    def seek(self, offset: int, whence: int = 0) -> int:
+        """
+        Move the position used by `read()`.
+
+        Args:
+            offset: Offset relative to `whence`.
+            whence: 0 for the start of the file, 1 for the current position
+                and 2 for the end of the file.
+
+        Returns:
+            int: The new absolute position.
+
+        Raises:
+            ValueError: If the file is closed, `whence` is invalid or the new
+                position would be negative.
+        """
+        self._handle()
+        with self._lock:
+            if whence not in (0, 1, 2):
+                raise ValueError(f"Invalid whence ({whence}, should be 0, 1 or 2)")
+            base = (0, self._position, self._size)[whence]
+            if base + offset < 0:
+                raise ValueError(f"Negative seek position {base + offset}")
+            self._position = base + offset
+            return self._position
+
+    def tell(self) -> int:
+        """Return the position used by `read()`."""
+        self._handle()
+        return self._position
+
+    def close(self) -> None:
+        """Close the file. Closing an already closed file has no effect."""
+        file, self._file = self._file, None
+        if file is not None:
+            _run(file.close)
+
+    def __enter__(self) -> "RandomAccessFile":
+        return self
+
+    def __exit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        self.close()
+
+
+def open_input_file(path: str) -> Any:
+    """
+    Open a file as a pyarrow input file backed by rapfiles.
+
+    Wraps a `RandomAccessFile` in `pyarrow.PythonFile`, which can be passed
+    anywhere pyarrow accepts a file, e.g. `pyarrow.parquet.read_table()`,
+    `pyarrow.parquet.ParquetFile` or `pyarrow.ipc.open_file()`.
+
+    Args:
+        path: Path to the file to read.
+
+    Returns:
+        pyarrow.PythonFile: Read-only pyarrow file object.
+
+    Raises:
+        ImportError: If pyarrow is not installed.
+        FileNotFoundError: If the file does not exist.
+        IOError: If the file cannot be opened.
+
+    Example:
+        ```python
+        import pyarrow.parquet as pq
+
+        with open_input_file("data.parquet") as source:
+            metadata = pq.ParquetFile(source).metadata
+        ```
+    """
+    try:
+        import pyarrow  # type: ignore[import-not-found]
+    except ImportError:
+        raise ImportError(
+            "open_input_file() requires pyarrow; install it with: "
+            "pip install rapfiles[arrow]"
+        ) from None
+    return pyarrow.PythonFile(RandomAccessFile(path), mode="r")
@@ -0,0 +1,220 @@
+"""File access audit log.
+
+This module reports every filesystem operation performed through rapfiles
+(who, when, which paths and the outcome) to a sink, for deployments that must
+keep an access log. The sink is a JSON Lines file or a callable; a redaction
+hook can rewrite or drop each event before it reaches the sink.
+
+Example:
+    ```python
+    from rapfiles.audit import AuditLog, actor
+
+    with AuditLog("/var/log/app/file-access.jsonl"):
+        with actor("alice"):
+            await rapfiles.read_file("/srv/records/42.json")
+    ```
+"""
+
+import contextlib
+import contextvars
+import getpass
+import json
+import os
+import threading
+from types import TracebackType
+from typing import IO, Any, Callable, Dict, Iterator, Optional, Type, Union
+
+from rapfiles import _start_audit, _stop_audit
+
+__all__ = ["AuditLog", "actor", "redact_paths"]
+
+# An event as passed to redaction hooks and sinks
+AuditEvent = Dict[str, Any]
+
+_actor: contextvars.ContextVar[Optional[str]] = contextvars.ContextVar(
+    "rapfiles_audit_actor", default=None
+)
+
+
+@contextlib.contextmanager
+def actor(name: str) -> Iterator[None]:
+    """
+    Name the actor reported for operations started inside the block.
+
+    The name is stored in a context variable, so a request handler can set
+    the authenticated user for its own task only.
+
+    Args:
+        name: Actor reported in the `actor` field of audit events.
+
+    Example:
+        ```python
+        async def handler(request):
+            with actor(request.user):
+                return await rapfiles.read_file_bytes(request.path)
+        ```
+    """
+    token = _actor.set(name)
+    try:
+        yield
+    finally:
+        _actor.reset(token)
+
+
+def redact_paths(
+    replace: Callable[[str], str],
+) -> Callable[[AuditEvent], AuditEvent]:
+    """
+    Build a redaction hook that rewrites every path of an event.
+
+    The paths are also replaced inside the error message, which quotes them.
+
+    Args:
+        replace: Function mapping a path to the text to log instead.
+
+    Returns:
+        Callable: Hook to pass as `AuditLog(redact=...)`.
+
+    Example:
+        ```python
+        import hashlib
+
+        def digest(path):
+            return hashlib.sha256(path.encode()).hexdigest()[:16]
+
+        AuditLog("audit.jsonl", redact=redact_paths(digest))
+        ```
+    """
+
+    def redact(event: AuditEvent) -> AuditEvent:
+        paths = event["paths"]
+        replaced = [replace(path) for path in paths]
+        error = event["error"]
+        if error is not None:
+            # Longest first, so a path is not broken up by one of its prefixes
+            for path, new in sorted(
+                zip(paths, replaced), key=lambda pair: -len(pair[0])
+            ):
+                error = error.replace(path, new)
+        return {**event, "paths": replaced, "error": error}
+
+    return redact
+
+
+class AuditLog:
+    """
+    Report every filesystem operation performed through rapfiles.
+
+    Each finished operation produces an event with these keys:
+
+    - `timestamp`: Unix time at which the operation started
+    - `actor`: Name set with `actor()`, or None
+    - `user`, `pid`, `uid`: Login name, process ID and user ID of the process
+      (`uid` is None where unavailable)
+    - `op`: Operation name, e.g. "read_file" or "write" for `AsyncFile.write`
+    - `paths`: Paths the operation acted on
+    - `bytes`: Bytes read or written
+    - `ok`, `error`: Outcome and error message (None on success)
+    - `duration`: Seconds the operation took
+
+    Events are produced on the thread that completed the operation, so the
+    sink should be quick; a failing sink or hook is reported through
+    `sys.unraisablehook` and does not affect the operation. Only one audit
+    log can be active at a time.
+
+    Args:
+        sink: Path of a JSON Lines file to append one line per event to, or
+            a callable receiving each event dict.
+        redact: Hook receiving each event and returning the event to log,
+            e.g. with paths masked (see `redact_paths()`), or None to drop it.
+
+    Example:
+        ```python
+        events = []
+        with AuditLog(events.append):
+            await rapfiles.write_file("out.txt", "data")
+        print(events[0]["op"], events[0]["ok"])
+        # Output: write_file True
+        ```
+    """
+
+    def __init__(
+        self,
+        sink: Union[str, "os.PathLike[str]", Callable[[AuditEvent], Any]],
+        redact: Optional[Callable[[AuditEvent], Optional[AuditEvent]]] = None,
+    ) -> None:
+        self.sink = sink
+        self.redact = redact
+        self._file: Optional[IO[str]] = None
+        self._lock = threading.Lock()
+        self._active = False
+        self._process = {
+            "user": _user(),
+            "pid": os.getpid(),
+            "uid": os.getuid() if hasattr(os, "getuid") else None,
+        }
+
+    def start(self) -> None:
+        """
+        Start reporting operations.
+
+        Raises:
+            RuntimeError: If an audit log is already active.
+            OSError: If the log file cannot be opened.
+        """
+        if not callable(self.sink):
+            self._file = open(self.sink, "a", encoding="utf-8")
+        try:
+            _start_audit(self._emit, _actor)
+        except BaseException:
+            self._close_file()
+            raise
+        self._active = True
+
+    def stop(self) -> None:
+        """Stop reporting operations and close the log file."""
+        if self._active:
+            self._active = False
+            _stop_audit()
+            self._close_file()
+
+    def _close_file(self) -> None:
+        with self._lock:
+            if self._file is not None:
+                self._file.close()
+                self._file = None
+
+    def _emit(self, event: AuditEvent) -> None:
+        event = {**event, **self._process}
+        if self.redact is not None:
+            redacted = self.redact(event)
+            if redacted is None:
+                return
+            event = redacted
+        if callable(self.sink):
+            self.sink(event)
+            return
+        line = json.dumps(event) + "\n"
+        with self._lock:
+            if self._file is not None:
+                self._file.write(line)
+                self._file.flush()
+
+    def __enter__(self) -> "AuditLog":
+        self.start()
+        return self
+
+    def __exit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        self.stop()
+
+
+def _user() -> Optional[str]:
+    try:
+        return getpass.getuser()
+    except Exception:
+        return None
@@ -0,0 +1,247 @@
+"""Benchmark harness for rapfiles.
+
+Runs standardized per-file (write/read/stat/copy) and high-entry-count
+(list/walk/batch-read) workloads against a scratch directory and returns
+structured results, so the same numbers can be compared across
+machines, filesystems and rapfiles versions.
+
+Example:
+    ```python
+    import asyncio
+    from rapfiles import bench
+
+    results = asyncio.run(bench.run("small-files"))
+    for result in results:
+        print(f"{result.workload}: {result.ops_per_second:,.0f} ops/s")
+    ```
+"""
+
+import asyncio
+import os
+import shutil
+import tempfile
+import time
+from dataclasses import asdict, dataclass
+from typing import Any, Awaitable, Callable, Dict, List, Optional, Union
+
+from rapfiles import (
+    copy_file,
+    create_dir_all,
+    get_backend,
+    list_dir,
+    read_file_bytes,
+    read_files,
+    stat,
+    walk_dir,
+    write_file_bytes,
+)
+
+__all__ = ["PROFILES", "SCAN_ROUNDS", "WORKLOADS", "WorkloadResult", "run"]
+
+# Workloads in the order they run; later workloads use the files written first
+WORKLOADS = ("write", "read", "stat", "list", "walk", "batch-read", "copy")
+
+# Number of times each whole-directory workload ("list", "walk", "batch-read")
+# repeats its single call over all files
+SCAN_ROUNDS = 10
+
+# Built-in profiles; pass a dict to `run()` to override individual settings
+PROFILES: Dict[str, Dict[str, Any]] = {
+    "default": {"file_size": 64 * 1024, "file_count": 256, "concurrency": 16},
+    "small-files": {"file_size": 4 * 1024, "file_count": 2000, "concurrency": 64},
+    "large-files": {"file_size": 16 * 1024 * 1024, "file_count": 16, "concurrency": 4},
+}
+
+
+@dataclass
+class WorkloadResult:
+    """
+    Measurements for a single workload.
+
+    Attributes:
+        workload: Workload name (one of `WORKLOADS`).
+        backend: I/O backend that executed the workload.
+        file_size: Size of each file in bytes.
+        file_count: Number of files the workload covers.
+        concurrency: Maximum number of calls in flight.
+        seconds: Wall-clock time for the whole workload.
+        ops_per_second: Files (or directory entries) processed per second.
+        bytes_per_second: Data throughput (0 for metadata workloads).
+        latency_p50: Median call latency in seconds. Per-file workloads make
+            one call per file; whole-directory workloads make `SCAN_ROUNDS`
+            calls that each cover every file.
+        latency_p95: 95th percentile call latency in seconds.
+        latency_p99: 99th percentile call latency in seconds.
+        latency_max: Slowest call latency in seconds.
+    """
+
+    workload: str
+    backend: str
+    file_size: int
+    file_count: int
+    concurrency: int
+    seconds: float
+    ops_per_second: float
+    bytes_per_second: float
+    latency_p50: float
+    latency_p95: float
+    latency_p99: float
+    latency_max: float
+
+    def as_dict(self) -> Dict[str, Any]:
+        """Return the result as a plain dictionary (e.g. for JSON output)."""
+        return asdict(self)
+
+
+def _resolve_profile(profile: Union[str, Dict[str, Any], None]) -> Dict[str, Any]:
+    if profile is None:
+        profile = "default"
+    if isinstance(profile, str):
+        if profile not in PROFILES:
+            raise ValueError(
+                f"Unknown benchmark profile: {profile}. "
+                f"Must be one of: {', '.join(PROFILES)}"
+            )
+        settings = dict(PROFILES[profile])
+    else:
+        settings = dict(PROFILES[profile.get("base", "default")])
+        settings.update({k: v for k, v in profile.items() if k != "base"})
+    settings.setdefault("workloads", list(WORKLOADS))
+
+    unknown = [w for w in settings["workloads"] if w not in WORKLOADS]
+    if unknown:
+        raise ValueError(f"Unknown benchmark workloads: {', '.join(unknown)}")
+    for key, minimum in (("file_size", 0), ("file_count", 1), ("concurrency", 1)):
+        value = settings[key]
+        if not isinstance(value, int) or value < minimum:
+            raise ValueError(
+                f"Invalid {key}: {value!r}. Must be an integer >= {minimum}"
+            )
+    return settings
+
+
+def _percentile(sorted_values: List[float], fraction: float) -> float:
+    index = min(len(sorted_values) - 1, int(round(fraction * (len(sorted_values) - 1))))
+    return sorted_values[index]
+
+
+async def _measure(
+    name: str,
+    settings: Dict[str, Any],
+    operation: Callable[[int], Awaitable[Any]],
+    calls: int,
+    entries_per_call: int,
+    bytes_per_call: int,
+) -> WorkloadResult:
+    semaphore = asyncio.Semaphore(settings["concurrency"])
+    latencies: List[float] = []
+
+    async def timed(index: int) -> None:
+        async with semaphore:
+            start = time.perf_counter()
+            await operation(index)
+            latencies.append(time.perf_counter() - start)
+
+    start = time.perf_counter()
+    await asyncio.gather(*(timed(i) for i in range(calls)))
+    seconds = max(time.perf_counter() - start, 1e-9)
+
+    latencies.sort()
+    return WorkloadResult(
+        workload=name,
+        backend=get_backend(),
+        file_size=settings["file_size"],
+        file_count=settings["file_count"],
+        concurrency=settings["concurrency"],
+        seconds=seconds,
+        ops_per_second=calls * entries_per_call / seconds,
+        bytes_per_second=calls * bytes_per_call / seconds,
+        latency_p50=_percentile(latencies, 0.50),
+        latency_p95=_percentile(latencies, 0.95),
+        latency_p99=_percentile(latencies, 0.99),
+        latency_max=latencies[-1],
+    )
+
+
+async def run(
+    profile: Union[str, Dict[str, Any], None] = None,
+    root: Optional[str] = None,
+) -> List[WorkloadResult]:
+    """
+    Run the benchmark workloads and return their measurements.
+
+    Per-file workloads perform `file_count` operations with at most
+    `concurrency` in flight: "write" creates the files, "read" reads them
+    back, "stat" queries their metadata and "copy" duplicates them.
+    Whole-directory workloads cover all files in each call: "list" lists
+    their directory, "walk" walks it and "batch-read" reads every file with
+    one `read_files()` call. Workloads that need existing files create them
+    first (untimed) if "write" is not selected.
+
+    Args:
+        profile: Name of a built-in profile ("default", "small-files",
+            "large-files") or a dict of settings: `file_size` (bytes),
+            `file_count`, `concurrency`, `workloads` (subset of `WORKLOADS`)
+            and optionally `base` (profile to start from, default
+            "default").
+        root: Directory to run in, e.g. on the filesystem you want to
+            measure. A temporary directory is used (and removed) if None.
+            When given, a `rapfiles-bench` subdirectory is created and
+            removed afterwards.
+
+    Returns:
+        List[WorkloadResult]: One result per workload, in execution order.
+
+    Raises:
+        ValueError: If the profile name, a workload or a setting is invalid.
+
+    Example:
+        ```python
+        results = await bench.run({"base": "small-files", "concurrency": 128})
+        print([r.as_dict() for r in results])
+        ```
+    """
+    settings = _resolve_profile(profile)
+    workdir = (
+        tempfile.mkdtemp(prefix="rapfiles-bench-")
+        if root is None
+        else os.path.join(root, "rapfiles-bench")
+    )
+    src_dir = os.path.join(workdir, "src")
+    dst_dir = os.path.join(workdir, "dst")
+    payload = os.urandom(settings["file_size"])
+
+    def src(index: int) -> str:
+        return os.path.join(src_dir, f"file{index:06d}.bin")
+
+    def dst(index: int) -> str:
+        return os.path.join(dst_dir, f"file{index:06d}.bin")
+
+    count = settings["file_count"]
+    size = settings["file_size"]
+    sources = [src(i) for i in range(count)]
+    # name -> (operation, calls, entries per call, bytes per call)
+    operations = {
+        "write": (lambda i: write_file_bytes(src(i), payload), count, 1, size),
+        "read": (lambda i: read_file_bytes(src(i)), count, 1, size),
+        "stat": (lambda i: stat(src(i)), count, 1, 0),
+        "list": (lambda i: list_dir(src_dir), SCAN_ROUNDS, count, 0),
+        "walk": (lambda i: walk_dir(src_dir), SCAN_ROUNDS, count, 0),
+        "batch-read": (lambda i: read_files(sources), SCAN_ROUNDS, count, count * size),
+        "copy": (lambda i: copy_file(src(i), dst(i)), count, 1, size),
+    }
+
+    results = []
+    try:
+        await create_dir_all(src_dir)
+        await create_dir_all(dst_dir)
+        selected = [w for w in WORKLOADS if w in settings["workloads"]]
+        if "write" not in selected and selected:
+            await asyncio.gather(
+                *(write_file_bytes(src(i), payload) for i in range(count))
+            )
+        for name in selected:
+            results.append(await _measure(name, settings, *operations[name]))
+    finally:
+        shutil.rmtree(workdir, ignore_errors=True)
+    return results
@@ -0,0 +1,285 @@
+"""Pool of open file handles.
+
+Services that read ranges of the same large files over and over (indexes,
+Parquet row groups, database pages) spend much of their time opening and
+closing them. A `HandlePool` keeps a bounded number of handles open and
+shares them: the handles are opened with `access="positional"`, so any
+number of tasks can `pread()` (or, with mode "rb+", `pwrite()`) through the
+same handle at once. When more than `max_open` files are open, the least
+recently used handles that no task is using are closed.
+
+A pooled handle keeps reading the file it opened; after replacing a file on
+disk, call `invalidate()` so later borrowers open the new one.
+
+Example:
+    ```python
+    from rapfiles import pool
+
+    async with pool.open("index.bin") as f:
+        header = await f.pread(64, 0)
+    ```
+"""
+
+import asyncio
+import os
+from collections import OrderedDict
+from types import TracebackType
+from typing import Dict, Optional, Tuple, Type
+
+from rapfiles import AsyncFile
+from rapfiles import open as _open
+
+__all__ = ["HandlePool", "PooledFile", "open", "invalidate", "close"]
+
+_MODES = ("rb", "rb+")
+
+_Key = Tuple[str, str]
+
+
+class _Entry:
+    """A pooled handle and the number of tasks borrowing it."""
+
+    def __init__(self, key: _Key, opened: "asyncio.Future[AsyncFile]") -> None:
+        self.key = key
+        self.opened = opened
+        self.file: Optional[AsyncFile] = None
+        self.users = 0
+        # Removed from the pool; closed once the last borrower is done
+        self.stale = False
+
+
+class PooledFile:
+    """
+    Borrowed handle from a `HandlePool`, used with `async with`.
+
+    Entering the block yields the shared `AsyncFile`; leaving it returns the
+    handle to the pool instead of closing it. Use `pread()`/`pwrite()` on the
+    handle: other tasks share it, so its cursor is not yours.
+    """
+
+    def __init__(self, pool: "HandlePool", path: str, mode: str) -> None:
+        self._pool = pool
+        self._path = path
+        self._mode = mode
+        self._entry: Optional[_Entry] = None
+
+    async def __aenter__(self) -> AsyncFile:
+        if self._entry is not None:
+            raise RuntimeError("PooledFile is already entered")
+        self._entry, file = await self._pool._acquire(self._path, self._mode)
+        return file
+
+    async def __aexit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        entry, self._entry = self._entry, None
+        if entry is not None:
+            await self._pool._release(entry)
+
+
+class HandlePool:
+    """
+    Bounded pool of open file handles with LRU eviction.
+
+    Handles are keyed by absolute path and mode and shared by every task
+    that borrows the same file. A pool belongs to one event loop.
+
+    Args:
+        max_open: Number of handles kept open. Handles in use are never
+            closed, so the pool can exceed this while more files are
+            borrowed at once; it shrinks back as they are returned.
+            Defaults to 128.
+
+    Raises:
+        ValueError: If `max_open` is less than 1.
+
+    Example:
+        ```python
+        pool = HandlePool(max_open=32)
+        async with pool.open("data.bin") as f:
+            page = await f.pread(4096, 7 * 4096)
+        await pool.close()
+        ```
+    """
+
+    def __init__(self, max_open: int = 128) -> None:
+        if max_open < 1:
+            raise ValueError(f"Invalid max_open: {max_open}. Must be at least 1")
+        self.max_open = max_open
+        # Least recently used first
+        self._entries: "OrderedDict[_Key, _Entry]" = OrderedDict()
+        self._hits = 0
+        self._misses = 0
+        self._evictions = 0
+
+    def open(self, path: str, mode: str = "rb") -> PooledFile:
+        """
+        Borrow a handle for `path` for the duration of an `async with` block.
+
+        Args:
+            path: Path to the file.
+            mode: "rb" (default) or "rb+".
+
+        Returns:
+            PooledFile: Async context manager yielding the shared handle.
+
+        Raises:
+            ValueError: If `mode` is not "rb" or "rb+".
+            FileNotFoundError: If the file does not exist (on entering).
+        """
+        if mode == "r+b":
+            mode = "rb+"
+        if mode not in _MODES:
+            raise ValueError(f"Invalid pool mode: {mode}. Must be 'rb' or 'rb+'")
+        return PooledFile(self, path, mode)
+
+    async def invalidate(self, path: str) -> int:
+        """
+        Drop the pooled handles of `path`, e.g. after the file was replaced.
+
+        Idle handles are closed now and borrowed ones when they are
+        returned; later borrowers open the file again.
+
+        Args:
+            path: Path whose handles are dropped.
+
+        Returns:
+            int: Number of handles dropped.
+        """
+        path = os.path.abspath(path)
+        entries = [e for key, e in self._entries.items() if key[0] == path]
+        for entry in entries:
+            await self._drop(entry)
+        return len(entries)
+
+    async def close(self) -> None:
+        """Close every idle handle and the borrowed ones once returned."""
+        for entry in list(self._entries.values()):
+            await self._drop(entry)
+
+    def stats(self) -> Dict[str, int]:
+        """
+        Return pool statistics.
+
+        Returns:
+            Dict[str, int]: `open` (pooled handles), `in_use` (handles
+                borrowed now), `hits` and `misses` (borrows that reused or
+                opened a handle) and `evictions` (handles closed to stay
+                within `max_open`).
+        """
+        return {
+            "open": len(self._entries),
+            "in_use": sum(1 for e in self._entries.values() if e.users),
+            "hits": self._hits,
+            "misses": self._misses,
+            "evictions": self._evictions,
+        }
+
+    async def __aenter__(self) -> "HandlePool":
+        return self
+
+    async def __aexit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        await self.close()
+
+    async def _acquire(self, path: str, mode: str) -> Tuple[_Entry, AsyncFile]:
+        key = (os.path.abspath(path), mode)
+        entry = self._entries.get(key)
+        if entry is not None and entry.file is not None and entry.file.closed:
+            # Closed by a borrower; open a fresh one
+            await self._drop(entry)
+            entry = None
+        if entry is not None:
+            self._hits += 1
+            self._entries.move_to_end(key)
+            entry.users += 1
+            try:
+                # Another task may still be opening it
+                return entry, await asyncio.shield(entry.opened)
+            except BaseException:
+                await self._release(entry)
+                raise
+
+        self._misses += 1
+        entry = _Entry(key, asyncio.get_running_loop().create_future())
+        entry.users = 1
+        self._entries[key] = entry
+        try:
+            file = await _open(path, mode, access="positional")
+        except BaseException as e:
+            if self._entries.get(key) is entry:
+                del self._entries[key]
+            entry.opened.set_exception(e)
+            # Mark it retrieved, as no other task may be waiting for it
+            entry.opened.exception()
+            raise
+        entry.file = file
+        entry.opened.set_result(file)
+        await self._evict()
+        return entry, file
+
+    async def _release(self, entry: _Entry) -> None:
+        entry.users -= 1
+        if entry.users == 0 and entry.stale:
+            await self._close_entry(entry)
+        else:
+            await self._evict()
+
+    async def _evict(self) -> None:
+        while len(self._entries) > self.max_open:
+            idle = next(
+                (e for e in self._entries.values() if not e.users and e.file),
+                None,
+            )
+            if idle is None:
+                return
+            self._evictions += 1
+            await self._drop(idle)
+
+    async def _drop(self, entry: _Entry) -> None:
+        if self._entries.get(entry.key) is entry:
+            del self._entries[entry.key]
+        entry.stale = True
+        if not entry.users:
+            await self._close_entry(entry)
+
+    @staticmethod
+    async def _close_entry(entry: _Entry) -> None:
+        file, entry.file = entry.file, None
+        if file is not None and not file.closed:
+            await file.close()
+
+
+_default = HandlePool()
+
+
+def open(path: str, mode: str = "rb") -> PooledFile:
+    """
+    Borrow a handle from the default pool (128 handles).
+
+    See `HandlePool.open()`.
+
+    Example:
+        ```python
+        async with rapfiles.pool.open("index.bin") as f:
+            header = await f.pread(64, 0)
+        ```
+    """
+    return _default.open(path, mode)
+
+
+async def invalidate(path: str) -> int:
+    """Drop the default pool's handles of `path`; see `HandlePool.invalidate()`."""
+    return await _default.invalidate(path)
+
+
+async def close() -> None:
+    """Close the default pool's handles, e.g. at service shutdown."""
+    await _default.close()
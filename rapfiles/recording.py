@@ -0,0 +1,300 @@
+"""Operation recording and replay.
+
+This module captures every filesystem operation performed through rapfiles
+(operation name, paths, bytes transferred, outcome and timing) and replays a
+captured trace against another directory tree. Use it to debug incidents from
+a production trace or to turn real workloads into benchmarks.
+
+Example:
+    ```python
+    from rapfiles.recording import Recorder, replay
+
+    with Recorder() as recorder:
+        await run_job("/srv/data")
+    await recorder.save("job-trace.jsonl")
+
+    summary = await replay(recorder.records, "/srv/data", "/tmp/replay")
+    ```
+"""
+
+import asyncio
+import json
+import os
+import time
+from types import TracebackType
+from typing import (
+    Any,
+    Awaitable,
+    Callable,
+    Dict,
+    Iterable,
+    List,
+    Optional,
+    Type,
+    Union,
+)
+
+from rapfiles import (
+    OpRecord,
+    _start_recording,
+    _stop_recording,
+    append_file,
+    atomic_move_file,
+    atomic_write_file,
+    atomic_write_file_bytes,
+    canonicalize,
+    copy_file,
+    copy_files,
+    create_dir,
+    create_dir_all,
+    exists,
+    hard_link,
+    is_dir,
+    is_file,
+    list_dir,
+    move_file,
+    read_file,
+    read_file_bytes,
+    read_files,
+    remove_dir,
+    remove_dir_all,
+    remove_file,
+    stat,
+    symlink,
+    walk_dir,
+    write_file,
+    write_file_bytes,
+    write_files,
+)
+
+__all__ = ["OpRecord", "Recorder", "load_records", "replay"]
+
+# A record as stored in a trace file
+RecordDict = Dict[str, Any]
+
+
+def _record_to_dict(record: Union[OpRecord, RecordDict]) -> RecordDict:
+    if isinstance(record, dict):
+        return record
+    return {
+        "op": record.op,
+        "paths": record.paths,
+        "bytes": record.bytes,
+        "error": record.error,
+        "start": record.start,
+        "duration": record.duration,
+    }
+
+
+class Recorder:
+    """
+    Capture the filesystem operations performed through rapfiles.
+
+    Every coroutine returned by rapfiles is recorded when it finishes,
+    whichever task or module started it. Only one recording can be active at
+    a time.
+
+    Example:
+        ```python
+        with Recorder() as recorder:
+            await rapfiles.write_file("out.txt", "data")
+        print(recorder.records[0].op, recorder.records[0].bytes)
+        # Output: write_file 4
+        ```
+    """
+
+    def __init__(self) -> None:
+        self.records: List[OpRecord] = []
+        self._active = False
+
+    def start(self) -> None:
+        """
+        Start recording.
+
+        Raises:
+            RuntimeError: If a recording is already active.
+        """
+        _start_recording()
+        self._active = True
+
+    def stop(self) -> List[OpRecord]:
+        """
+        Stop recording and collect the captured operations.
+
+        Returns:
+            List[OpRecord]: Operations recorded since `start()`, in completion
+                order. They are also appended to `records`.
+        """
+        if self._active:
+            self._active = False
+            self.records.extend(_stop_recording())
+        return self.records
+
+    async def save(self, path: str) -> None:
+        """
+        Write the captured operations to a JSON Lines trace file.
+
+        Args:
+            path: Trace file to write; one JSON object per operation.
+        """
+        lines = [json.dumps(_record_to_dict(record)) for record in self.records]
+        await write_file(path, "".join(line + "\n" for line in lines))
+
+    def __enter__(self) -> "Recorder":
+        self.start()
+        return self
+
+    def __exit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        self.stop()
+
+
+async def load_records(path: str) -> List[RecordDict]:
+    """
+    Load operations from a trace file written by `Recorder.save()`.
+
+    Args:
+        path: JSON Lines trace file.
+
+    Returns:
+        List[Dict[str, Any]]: One dictionary per operation with `op`, `paths`,
+            `bytes`, `error`, `start` and `duration` keys.
+    """
+    content = await read_file(path)
+    return [json.loads(line) for line in content.splitlines() if line.strip()]
+
+
+def _text(size: int) -> str:
+    return "x" * size
+
+
+def _data(size: int) -> bytes:
+    return b"\0" * size
+
+
+def _split(total: int, parts: int) -> List[int]:
+    base, extra = divmod(total, max(parts, 1))
+    return [base + (1 if i < extra else 0) for i in range(parts)]
+
+
+# Replay actions for whole-path operations. Written data is synthetic: text
+# writes use "x" and binary writes use zero bytes of the recorded size.
+_REPLAY: Dict[str, Callable[[List[str], int], Awaitable[Any]]] = {
+    "read_file": lambda p, n: read_file(p[0]),
+    "read_file_bytes": lambda p, n: read_file_bytes(p[0]),
+    "write_file": lambda p, n: write_file(p[0], _text(n)),
+    "write_file_bytes": lambda p, n: write_file_bytes(p[0], _data(n)),
+    "append_file": lambda p, n: append_file(p[0], _text(n)),
+    "create_dir": lambda p, n: create_dir(p[0]),
+    "create_dir_all": lambda p, n: create_dir_all(p[0]),
+    "remove_dir": lambda p, n: remove_dir(p[0]),
+    "remove_dir_all": lambda p, n: remove_dir_all(p[0]),
+    "list_dir": lambda p, n: list_dir(p[0]),
+    "exists": lambda p, n: exists(p[0]),
+    "is_file": lambda p, n: is_file(p[0]),
+    "is_dir": lambda p, n: is_dir(p[0]),
+    "stat": lambda p, n: stat(p[0]),
+    "walk_dir": lambda p, n: walk_dir(p[0]),
+    "copy_file": lambda p, n: copy_file(p[0], p[1]),
+    "move_file": lambda p, n: move_file(p[0], p[1]),
+    "remove_file": lambda p, n: remove_file(p[0]),
+    "hard_link": lambda p, n: hard_link(p[0], p[1]),
+    "symlink": lambda p, n: symlink(p[0], p[1]),
+    "canonicalize": lambda p, n: canonicalize(p[0]),
+    "atomic_write_file": lambda p, n: atomic_write_file(p[0], _text(n)),
+    "atomic_write_file_bytes": lambda p, n: atomic_write_file_bytes(p[0], _data(n)),
+    "atomic_move_file": lambda p, n: atomic_move_file(p[0], p[1]),
+    "read_files": lambda p, n: read_files(p),
+    "write_files": lambda p, n: write_files(
+        {path: _data(size) for path, size in zip(p, _split(n, len(p)))}
+    ),
+    "copy_files": lambda p, n: copy_files(list(zip(p[::2], p[1::2]))),
+}
+
+
+def _rebase(path: str, source_root: str, target_root: str) -> Optional[str]:
+    rel = os.path.relpath(path, source_root)
+    if rel == os.pardir or rel.startswith(os.pardir + os.sep) or os.path.isabs(rel):
+        return None
+    return os.path.normpath(os.path.join(target_root, rel))
+
+
+async def replay(
+    records: Iterable[Union[OpRecord, RecordDict]],
+    source_root: str,
+    target_root: str,
+    preserve_timing: bool = False,
+) -> Dict[str, int]:
+    """
+    Re-execute recorded operations against another directory tree.
+
+    Paths below `source_root` are rewritten to the same relative location
+    below `target_root`. Operations touching any path outside `source_root`
+    are skipped so a replay never modifies files outside the target tree.
+    Operations on open file handles (`open`, `AsyncFile` methods, locks) are
+    skipped too; all whole-path operations are replayed. Written content is
+    synthetic data of the recorded size.
+
+    Args:
+        records: `OpRecord` objects from a `Recorder` or dictionaries from
+            `load_records()`.
+        source_root: Root the operations were recorded against.
+        target_root: Root to replay them against.
+        preserve_timing: If True, each operation starts at its recorded
+            offset, reproducing the original concurrency and pacing. If False
+            (default), operations run one after another as fast as possible.
+
+    Returns:
+        Dict[str, int]: Counts of `replayed` and `skipped` operations,
+            `errors` raised during the replay and `mismatches` where the
+            outcome (success or failure) differs from the recording.
+
+    Example:
+        ```python
+        records = await load_records("incident.jsonl")
+        summary = await replay(records, "/srv/data", "/tmp/repro")
+        # {'replayed': 120, 'skipped': 8, 'errors': 1, 'mismatches': 0}
+        ```
+    """
+    summary = {"replayed": 0, "skipped": 0, "errors": 0, "mismatches": 0}
+    planned = []
+    for record in map(_record_to_dict, records):
+        action = _REPLAY.get(record["op"])
+        paths = [_rebase(p, source_root, target_root) for p in record["paths"]]
+        if action is None or not paths or None in paths:
+            summary["skipped"] += 1
+            continue
+        planned.append((record, action, paths))
+    planned.sort(key=lambda item: item[0]["start"])
+
+    async def run(record: RecordDict, action: Callable, paths: List[str]) -> None:
+        try:
+            await action(paths, record["bytes"])
+            succeeded = True
+        except Exception:
+            summary["errors"] += 1
+            succeeded = False
+        summary["replayed"] += 1
+        if succeeded != (record["error"] is None):
+            summary["mismatches"] += 1
+
+    if not preserve_timing:
+        for item in planned:
+            await run(*item)
+        return summary
+
+    origin = time.monotonic()
+    first = planned[0][0]["start"] if planned else 0.0
+
+    async def run_at(record: RecordDict, action: Callable, paths: List[str]) -> None:
+        delay = record["start"] - first - (time.monotonic() - origin)
+        if delay > 0:
+            await asyncio.sleep(delay)
+        await run(record, action, paths)
+
+    await asyncio.gather(*(run_at(*item) for item in planned))
+    return summary
@@ -0,0 +1,390 @@
+"""Test helpers for code that uses rapfiles.
+
+This module provides `TestFs`, a scratch filesystem rooted in a temporary
+directory with async builders for files and trees, a `rapfiles_fs` pytest
+fixture that hands each test a fresh `TestFs` and removes it afterwards, and
+hooks that make rapfiles operations fail (`inject_fault()`) or run slowly
+(`inject_latency()`) on purpose.
+
+Enable the fixture with `pytest_plugins = ["rapfiles.testing"]` in a
+`conftest.py` (or `pytest -p rapfiles.testing`); it is not registered
+automatically, so other projects' test sessions don't import rapfiles.
+Test suites then only need to request it:
+
+    ```python
+    async def test_loader(rapfiles_fs):
+        config = await rapfiles_fs.make_file("config.toml", "debug = true")
+        assert await load(config)
+    ```
+"""
+
+import errno as _errno
+import os
+import shutil
+import tempfile
+from types import TracebackType
+from typing import Dict, Iterable, Optional, Type, Union
+
+from rapfiles import (
+    _clear_faults,
+    _clear_latency,
+    _inject_fault,
+    _inject_latency,
+    _remove_fault,
+    _remove_latency,
+    create_dir_all,
+    remove_dir_all,
+    write_file,
+    write_file_bytes,
+)
+
+# A tree spec maps names to file contents (str or bytes) or nested specs
+TreeSpec = Dict[str, Union[str, bytes, "TreeSpec"]]
+
+
+class TestFs:
+    """
+    Scratch filesystem for tests, backed by a temporary directory.
+
+    All builders take paths relative to `root` and create missing parent
+    directories. Use it as an async context manager, call `cleanup()`
+    explicitly, or request the `rapfiles_fs` pytest fixture to have it removed
+    automatically.
+
+    Example:
+        ```python
+        async with TestFs() as fs:
+            await fs.make_tree({"data": {"a.csv": "1,2", "b.csv": "3,4"}})
+            assert await rapfiles.list_dir(fs.path("data"), sorted=True) == [
+                "a.csv",
+                "b.csv",
+            ]
+        ```
+    """
+
+    # Not a test class, even though the name starts with "Test"
+    __test__ = False
+
+    def __init__(self, root: Optional[str] = None) -> None:
+        """
+        Create a scratch filesystem.
+
+        Args:
+            root: Existing directory to build in. If None (default), a new
+                temporary directory is created and owned by this instance.
+        """
+        self._owns_root = root is None
+        self.root = tempfile.mkdtemp(prefix="rapfiles-") if root is None else root
+
+    def path(self, *parts: str) -> str:
+        """
+        Return the absolute path of `parts` joined below the root.
+
+        Args:
+            *parts: Path components relative to the root.
+
+        Returns:
+            str: Absolute path inside the scratch filesystem.
+        """
+        return os.path.join(self.root, *parts)
+
+    async def make_dir(self, rel_path: str) -> str:
+        """
+        Create a directory (and its parents) below the root.
+
+        Args:
+            rel_path: Directory path relative to the root.
+
+        Returns:
+            str: Absolute path of the created directory.
+        """
+        path = self.path(rel_path)
+        await create_dir_all(path)
+        return path
+
+    async def make_file(self, rel_path: str, content: Union[str, bytes] = "") -> str:
+        """
+        Create a file below the root, creating parent directories as needed.
+
+        Args:
+            rel_path: File path relative to the root.
+            content: Text or bytes to write. Defaults to an empty file.
+
+        Returns:
+            str: Absolute path of the created file.
+        """
+        path = self.path(rel_path)
+        parent = os.path.dirname(path)
+        if parent:
+            await create_dir_all(parent)
+        if isinstance(content, bytes):
+            await write_file_bytes(path, content)
+        else:
+            await write_file(path, content)
+        return path
+
+    async def make_tree(self, spec: TreeSpec, rel_path: str = "") -> str:
+        """
+        Create a directory tree from a nested dictionary.
+
+        Args:
+            spec: Mapping of names to file contents (str or bytes) or to
+                nested mappings for subdirectories. An empty mapping creates
+                an empty directory.
+            rel_path: Directory (relative to the root) to build the tree in.
+                Defaults to the root itself.
+
+        Returns:
+            str: Absolute path of the directory the tree was built in.
+
+        Example:
+            ```python
+            await fs.make_tree({
+                "README.md": "# Demo",
+                "src": {"main.py": "print('hi')", "empty": {}},
+            })
+            ```
+        """
+        base = await self.make_dir(rel_path) if rel_path else self.root
+        for name, entry in spec.items():
+            child = os.path.join(rel_path, name) if rel_path else name
+            if isinstance(entry, dict):
+                await self.make_tree(entry, child)
+            else:
+                await self.make_file(child, entry)
+        return base
+
+    def cleanup(self) -> None:
+        """
+        Remove the scratch filesystem if this instance created it.
+
+        Directories passed in as `root` are left in place.
+        """
+        if self._owns_root:
+            shutil.rmtree(self.root, ignore_errors=True)
+
+    async def __aenter__(self) -> "TestFs":
+        """Enter async context manager."""
+        return self
+
+    async def __aexit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        """Exit async context manager and remove the scratch filesystem."""
+        if self._owns_root and os.path.exists(self.root):
+            await remove_dir_all(self.root)
+
+    def __repr__(self) -> str:
+        return f"TestFs(root={self.root!r})"
+
+
+class _Hook:
+    """
+    Handle for a hook registered in the Rust operation layer.
+
+    The hook stays active until `remove()` is called or, when used as a
+    context manager, until the block exits.
+    """
+
+    def __init__(self, hook_id: int, path_glob: str) -> None:
+        self.id = hook_id
+        self.path_glob = path_glob
+
+    def _remove(self, hook_id: int) -> bool:
+        raise NotImplementedError
+
+    def remove(self) -> bool:
+        """
+        Deactivate the hook.
+
+        Returns:
+            bool: True if the hook was still active, False otherwise.
+        """
+        return self._remove(self.id)
+
+    def __enter__(self):
+        return self
+
+    def __exit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        self.remove()
+
+
+class Fault(_Hook):
+    """Handle for a fault registered with `inject_fault()`."""
+
+    def __init__(self, fault_id: int, path_glob: str, errno: int) -> None:
+        super().__init__(fault_id, path_glob)
+        self.errno = errno
+
+    def _remove(self, hook_id: int) -> bool:
+        return _remove_fault(hook_id)
+
+    def __repr__(self) -> str:
+        return f"Fault(path_glob={self.path_glob!r}, errno={self.errno})"
+
+
+class Slowdown(_Hook):
+    """Handle for a slowdown registered with `inject_latency()`."""
+
+    def __init__(
+        self,
+        slowdown_id: int,
+        path_glob: str,
+        latency: float,
+        bytes_per_second: Optional[float],
+    ) -> None:
+        super().__init__(slowdown_id, path_glob)
+        self.latency = latency
+        self.bytes_per_second = bytes_per_second
+
+    def _remove(self, hook_id: int) -> bool:
+        return _remove_latency(hook_id)
+
+    def __repr__(self) -> str:
+        return (
+            f"Slowdown(path_glob={self.path_glob!r}, latency={self.latency}, "
+            f"bytes_per_second={self.bytes_per_second})"
+        )
+
+
+def inject_fault(
+    path_glob: str,
+    errno: int = _errno.EIO,
+    probability: float = 1.0,
+    ops: Optional[Iterable[str]] = None,
+    seed: Optional[int] = None,
+) -> Fault:
+    """
+    Make matching rapfiles operations fail with an OS error.
+
+    The check happens in Rust when an operation starts, so application code
+    exercises its real error handling without mocking rapfiles. An operation
+    matches if any of its paths (e.g. either side of `copy_file`) matches
+    `path_glob`. Failures raise `OSError(errno, ...)`, which Python resolves
+    to the matching subclass such as `FileNotFoundError` or `PermissionError`.
+
+    Args:
+        path_glob: fnmatch-style pattern (`*`, `?`, `[...]`) matched against
+            the full path string passed to the operation. `*` also matches
+            path separators.
+        errno: OS error number to fail with. Defaults to `errno.EIO`.
+        probability: Chance in [0, 1] that a matching operation fails.
+            Defaults to 1.0 (always fail).
+        ops: Operation names to restrict the fault to, e.g.
+            `["write_file", "write"]`. Names are the rapfiles function names
+            (`read_file`, `copy_file`, `list_dir`, ...) and the `AsyncFile`
+            method names (`read`, `write`, `seek`, ...). All operations match
+            if None (default).
+        seed: Seed for the fault's random generator so probabilistic
+            failures repeat across runs. Random if None (default).
+
+    Returns:
+        Fault: Handle used to remove the fault; also a context manager.
+
+    Raises:
+        ValueError: If `errno` is not positive or `probability` is outside
+            [0, 1].
+
+    Example:
+        ```python
+        import errno
+        from rapfiles.testing import inject_fault
+
+        with inject_fault("*/uploads/*", errno.ENOSPC, ops=["write_file"]):
+            with pytest.raises(OSError) as exc_info:
+                await save_upload("uploads/a.bin", data)
+            assert exc_info.value.errno == errno.ENOSPC
+        ```
+    """
+    fault_id = _inject_fault(
+        path_glob,
+        errno,
+        probability,
+        None if ops is None else list(ops),
+        seed,
+    )
+    return Fault(fault_id, path_glob, errno)
+
+
+def clear_faults() -> None:
+    """Remove every fault registered with `inject_fault()`."""
+    _clear_faults()
+
+
+def inject_latency(
+    path_glob: str = "*",
+    latency: float = 0.0,
+    bytes_per_second: Optional[float] = None,
+    ops: Optional[Iterable[str]] = None,
+) -> Slowdown:
+    """
+    Slow down matching rapfiles operations to simulate a slow disk.
+
+    Matching operations wait `latency` seconds before they start. With
+    `bytes_per_second`, they additionally wait as long as their data transfer
+    (bytes read or written, e.g. by `read_file`, `AsyncFile.write` or
+    `copy_file`) would take at that rate. Delays are Tokio timers, so the
+    event loop keeps running while operations are held back. When several
+    slowdowns match, the largest latency and the lowest throughput apply.
+
+    Args:
+        path_glob: fnmatch-style pattern matched against the full path string
+            passed to the operation. Defaults to "*" (every path).
+        latency: Seconds to wait before each matching operation.
+        bytes_per_second: Throughput cap for data-transferring operations.
+            No cap if None (default).
+        ops: Operation names to restrict the slowdown to (see
+            `inject_fault()`). All operations match if None (default).
+
+    Returns:
+        Slowdown: Handle used to remove the slowdown; also a context manager.
+
+    Raises:
+        ValueError: If `latency` is negative or `bytes_per_second` is not
+            positive.
+
+    Example:
+        ```python
+        from rapfiles.testing import inject_latency
+
+        # Reproduce "slow NFS": 200ms per call, 1 MiB/s for reads
+        with inject_latency("/mnt/nfs/*", latency=0.2):
+            with inject_latency("/mnt/nfs/*", bytes_per_second=1 << 20, ops=["read"]):
+                with pytest.raises(asyncio.TimeoutError):
+                    await asyncio.wait_for(load_dataset("/mnt/nfs/data"), 1.0)
+        ```
+    """
+    slowdown_id = _inject_latency(
+        path_glob,
+        latency,
+        bytes_per_second,
+        None if ops is None else list(ops),
+    )
+    return Slowdown(slowdown_id, path_glob, latency, bytes_per_second)
+
+
+def clear_latency() -> None:
+    """Remove every slowdown registered with `inject_latency()`."""
+    _clear_latency()
+
+
+try:
+    import pytest
+except ImportError:  # pragma: no cover - pytest is optional
+    pass
+else:
+
+    @pytest.fixture
+    def rapfiles_fs():
+        """Provide a fresh `TestFs` that is removed after the test."""
+        fs = TestFs()
+        yield fs
+        fs.cleanup()
@@ -2,7 +2,8 @@
 
 use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyString};
-use pyo3_async_runtimes::tokio::future_into_py;
+use rapfiles_core::{current_backend, run_blocking, runtime, Backend};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::fs::File;
@@ -70,1177 +71,2639 @@ fn map_io_error(e: std::io::Error, path: &str, operation: &str) -> PyErr {
     }
 }
 
-/// Python bindings for rapfiles - True async filesystem I/O.
-///
-/// This module provides true async filesystem I/O operations backed by Rust and Tokio.
-/// All I/O operations execute outside the Python GIL, ensuring event loops never stall.
-/// Compatible with `aiofiles` API for drop-in replacement scenarios.
-///
-/// # Features
+// Operation hooks
+
+/// Filesystem operation as seen by the testing hooks.
 ///
-/// - File operations: read, write, append (text and binary)
-/// - File handles: AsyncFile class with async context manager support
-/// - Directory operations: create, remove, list, walk
-/// - File metadata: stat, size, timestamps
-/// - Path operations: ospath module compatibility
-#[pymodule]
-fn _rapfiles(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
-    // File operations
-    m.add_function(wrap_pyfunction!(read_file_async, m)?)?;
-    m.add_function(wrap_pyfunction!(write_file_async, m)?)?;
-    m.add_function(wrap_pyfunction!(read_file_bytes_async, m)?)?;
-    m.add_function(wrap_pyfunction!(write_file_bytes_async, m)?)?;
-    m.add_function(wrap_pyfunction!(append_file_async, m)?)?;
-    m.add_function(wrap_pyfunction!(open_file, m)?)?;
-    m.add_class::<AsyncFile>()?;
+/// Every coroutine handed back to Python is started through `run_op`, which
+/// consults the hooks registered by `rapfiles.testing` before the operation
+/// touches the filesystem.
+struct OpContext {
+    /// Operation name, e.g. "read_file" or "write" for `AsyncFile.write`
+    name: &'static str,
+    /// Paths the operation acts on
+    paths: Vec<Arc<str>>,
+}
 
-    // Directory operations
-    m.add_function(wrap_pyfunction!(create_dir_async, m)?)?;
-    m.add_function(wrap_pyfunction!(create_dir_all_async, m)?)?;
-    m.add_function(wrap_pyfunction!(remove_dir_async, m)?)?;
-    m.add_function(wrap_pyfunction!(remove_dir_all_async, m)?)?;
-    m.add_function(wrap_pyfunction!(list_dir_async, m)?)?;
-    m.add_function(wrap_pyfunction!(exists_async, m)?)?;
-    m.add_function(wrap_pyfunction!(is_file_async, m)?)?;
-    m.add_function(wrap_pyfunction!(is_dir_async, m)?)?;
+impl OpContext {
+    fn new<'a>(name: &'static str, paths: impl IntoIterator<Item = &'a str>) -> Self {
+        OpContext {
+            name,
+            paths: paths.into_iter().map(Arc::from).collect(),
+        }
+    }
 
-    // Metadata operations
-    m.add_function(wrap_pyfunction!(stat_async, m)?)?;
-    m.add_function(wrap_pyfunction!(metadata_async, m)?)?;
-    m.add_class::<FileMetadata>()?;
+    /// Create a context for paths the operation itself keeps, without copying them.
+    fn shared(name: &'static str, paths: &[Arc<str>]) -> Self {
+        OpContext {
+            name,
+            paths: paths.to_vec(),
+        }
+    }
 
-    // Directory traversal
-    m.add_function(wrap_pyfunction!(walk_dir_async, m)?)?;
+    /// Paths the operation creates, modifies or removes.
+    ///
+    /// Opening a file is checked separately, as it depends on the mode.
+    fn written_paths(&self) -> Vec<&str> {
+        let paths = self.paths.iter().map(|path| &**path);
+        match self.name {
+            // Sources and destinations alternate
+            "copy_files" => paths.skip(1).step_by(2).collect(),
+            "copy_file" | "copytree" | "hard_link" | "link" | "symlink" => paths.skip(1).collect(),
+            "write_file"
+            | "write_file_bytes"
+            | "append_file"
+            | "write_files"
+            | "atomic_write_file"
+            | "atomic_write_file_bytes"
+            | "write_if_unchanged"
+            | "save_config"
+            | "touch"
+            | "chmod"
+            | "chown"
+            | "atomic_move_file"
+            | "move_file"
+            | "move"
+            | "rename"
+            | "replace"
+            | "create_dir"
+            | "create_dir_all"
+            | "remove_file"
+            | "unlink"
+            | "remove_dir"
+            | "remove_dir_all"
+            | "write"
+            | "writelines"
+            | "pwrite"
+            | "truncate"
+            | "write_segments"
+            | "create_filled"
+            | "generate_tree"
+            // Creates the file if it does not exist
+            | "lock_file"
+            // Create and remove the lease sidecar file
+            | "lease_file"
+            | "release_lease"
+            | "rename_many"
+            | "rename_pattern"
+            | "transaction_write"
+            | "commit_transaction"
+            | "recover_transaction"
+            | "dedupe_tree"
+            | "run_cleanup"
+            | "cache_set"
+            | "cache_delete"
+            | "cache_clear"
+            | "cache_evict" => paths.collect(),
+            // The journal follows the watched directory
+            "watch" => paths.skip(1).collect(),
+            _ => Vec::new(),
+        }
+    }
 
-    // File manipulation operations
-    m.add_function(wrap_pyfunction!(copy_file_async, m)?)?;
-    m.add_function(wrap_pyfunction!(move_file_async, m)?)?;
-    m.add_function(wrap_pyfunction!(remove_file_async, m)?)?;
-    m.add_function(wrap_pyfunction!(hard_link_async, m)?)?;
-    m.add_function(wrap_pyfunction!(symlink_async, m)?)?;
-    m.add_function(wrap_pyfunction!(canonicalize_async, m)?)?;
+    /// Return the first path matched by a hook's glob and operation filter.
+    fn matching_path(&self, pattern: &str, ops: &Option<Vec<String>>) -> Option<&str> {
+        if let Some(ops) = ops {
+            if !ops.iter().any(|op| op == self.name) {
+                return None;
+            }
+        }
+        self.paths
+            .iter()
+            .find(|path| glob_match(pattern.as_bytes(), path.as_bytes()))
+            .map(|path| &**path)
+    }
 
-    // Atomic operations
-    m.add_function(wrap_pyfunction!(atomic_write_file_async, m)?)?;
-    m.add_function(wrap_pyfunction!(atomic_write_file_bytes_async, m)?)?;
-    m.add_function(wrap_pyfunction!(atomic_move_file_async, m)?)?;
+    /// Return the error of the first injected fault that fires for this operation.
+    fn injected_fault(&self) -> Option<PyErr> {
+        let mut faults = FAULTS.lock().unwrap_or_else(|e| e.into_inner());
+        for fault in faults.iter_mut() {
+            let Some(path) = self.matching_path(&fault.pattern, &fault.ops) else {
+                continue;
+            };
+            if fault.probability < 1.0 && next_random(&mut fault.rng_state) >= fault.probability {
+                continue;
+            }
+            let message = format!(
+                "Failed to {} {path}: {} (injected fault)",
+                self.name,
+                std::io::Error::from_raw_os_error(fault.errno)
+            );
+            // OSError(errno, msg) resolves to the matching subclass, e.g. FileNotFoundError
+            return Some(PyErr::new::<pyo3::exceptions::PyOSError, _>((
+                fault.errno,
+                message,
+            )));
+        }
+        None
+    }
 
-    // File locking
-    m.add_function(wrap_pyfunction!(lock_file_async, m)?)?;
-    m.add_class::<FileLock>()?;
+    /// Combine the injected slowdowns matching this operation.
+    ///
+    /// When several rules match, the largest latency and the lowest
+    /// throughput apply.
+    fn slowdown(&self) -> (std::time::Duration, Option<f64>) {
+        let slowdowns = SLOWDOWNS.lock().unwrap_or_else(|e| e.into_inner());
+        let mut latency = std::time::Duration::ZERO;
+        let mut bytes_per_second: Option<f64> = None;
+        for slowdown in slowdowns.iter() {
+            if self
+                .matching_path(&slowdown.pattern, &slowdown.ops)
+                .is_none()
+            {
+                continue;
+            }
+            latency = latency.max(slowdown.latency);
+            if let Some(rate) = slowdown.bytes_per_second {
+                bytes_per_second = Some(bytes_per_second.map_or(rate, |current| current.min(rate)));
+            }
+        }
+        (latency, bytes_per_second)
+    }
+}
 
-    // Batch operations
-    m.add_function(wrap_pyfunction!(read_files_async, m)?)?;
-    m.add_function(wrap_pyfunction!(write_files_async, m)?)?;
-    m.add_function(wrap_pyfunction!(copy_files_async, m)?)?;
+tokio::task_local! {
+    /// Bytes read or written by the operation running in the current task.
+    static OP_BYTES: std::cell::Cell<usize>;
+}
 
-    Ok(())
+/// Record bytes read or written by the running operation.
+///
+/// Feeds the throughput caps of `rapfiles.testing.inject_latency`. Calls
+/// outside `run_op` (e.g. inside `spawn_blocking`) are ignored.
+fn record_transfer(bytes: usize) {
+    let _ = OP_BYTES.try_with(|total| total.set(total.get() + bytes));
 }
 
-/// Async file read using Tokio (GIL-independent).
+/// Convert an operation future into a Python awaitable, applying the hooks.
+///
+/// The operation runs under the `rapfiles.options()` timeout and rate limit
+/// of the calling context, but is never retried.
+fn run_op<F, T>(py: Python<'_>, op: OpContext, future: F) -> PyResult<Bound<'_, PyAny>>
+where
+    F: std::future::Future<Output = PyResult<T>> + Send + 'static,
+    T: for<'py> IntoPyObject<'py> + Send + 'static,
+{
+    let mut future = Some(future);
+    spawn_op(py, op, false, move || {
+        future.take().expect("operation future started twice")
+    })
+}
+
+/// Like `run_op`, for operations without a result.
 ///
-/// Reads the entire file and returns its contents as a UTF-8 decoded string.
-/// All I/O operations execute outside the Python GIL using native Tokio,
-/// ensuring true async behavior and preventing event loop stalls.
+/// The awaitable yields `None`, like a Python function without a return
+/// value; `()` itself would convert to an empty tuple.
+fn run_unit_op<F>(py: Python<'_>, op: OpContext, future: F) -> PyResult<Bound<'_, PyAny>>
+where
+    F: std::future::Future<Output = PyResult<()>> + Send + 'static,
+{
+    run_op(py, op, async move { future.await.map(|()| None::<()>) })
+}
+
+/// Like `run_op`, for operations that are safe to repeat.
 ///
-/// # Arguments
+/// `make_future` creates the future of one attempt; it is called again for
+/// each retry allowed by `rapfiles.options(retries=...)`.
+fn run_retryable_op<F, T, M>(
+    py: Python<'_>,
+    op: OpContext,
+    make_future: M,
+) -> PyResult<Bound<'_, PyAny>>
+where
+    F: std::future::Future<Output = PyResult<T>> + Send + 'static,
+    T: for<'py> IntoPyObject<'py> + Send + 'static,
+    M: FnMut() -> F + Send + 'static,
+{
+    spawn_op(py, op, true, make_future)
+}
+
+fn spawn_op<F, T, M>(
+    py: Python<'_>,
+    op: OpContext,
+    retryable: bool,
+    make_future: M,
+) -> PyResult<Bound<'_, PyAny>>
+where
+    F: std::future::Future<Output = PyResult<T>> + Send + 'static,
+    T: for<'py> IntoPyObject<'py> + Send + 'static,
+    M: FnMut() -> F + Send + 'static,
+{
+    let future = prepare_op(py, op, retryable, make_future)?;
+    future_into_py(py, future)
+}
+
+/// Wrap an operation in the policies of `spawn_op` without converting it to
+/// a Python awaitable, for callers that post-process its result.
+fn prepare_op<F, T, M>(
+    py: Python<'_>,
+    op: OpContext,
+    retryable: bool,
+    make_future: M,
+) -> PyResult<impl std::future::Future<Output = PyResult<T>> + Send + 'static>
+where
+    F: std::future::Future<Output = PyResult<T>> + Send + 'static,
+    T: Send + 'static,
+    M: FnMut() -> F + Send + 'static,
+{
+    if READ_ONLY_ACTIVE.load(Ordering::Relaxed) {
+        for path in op.written_paths() {
+            check_writable(op.name, path)?;
+        }
+    }
+    let settings = select_settings(py, op.paths.first().map(|path| &**path))?;
+    let options = current_options(py)?;
+    let audit = AUDIT_ACTIVE
+        .load(Ordering::Relaxed)
+        .then(|| AuditStart::capture(py))
+        .transpose()?;
+    let future = OP_SETTINGS.scope(settings, async move {
+        let started = RECORDING_ACTIVE
+            .load(Ordering::Relaxed)
+            .then(std::time::Instant::now);
+        let (result, bytes) = run_with_options(&op, &options, retryable, make_future).await;
+        if let Some(audit) = audit {
+            audit_op(&op, audit, bytes, result.as_ref().err());
+        }
+        if let Some(started) = started {
+            record_op(op, bytes, result.as_ref().err(), started);
+        }
+        result
+    });
+    Ok(rapfiles_core::with_backend(settings.backend, future))
+}
+
+/// Run an operation future under the injected faults and slowdowns.
 ///
-/// * `py` - Python GIL token
-/// * `path` - Path to the file to read
+/// Returns the operation result and the number of bytes it transferred.
+async fn run_hooked<F, T>(op: &OpContext, future: F) -> (PyResult<T>, usize)
+where
+    F: std::future::Future<Output = PyResult<T>>,
+{
+    if let Some(err) = op.injected_fault() {
+        return (Err(err), 0);
+    }
+    let (latency, bytes_per_second) = op.slowdown();
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+    let (result, bytes) = OP_BYTES
+        .scope(std::cell::Cell::new(0), async {
+            let result = future.await;
+            (result, OP_BYTES.with(std::cell::Cell::get))
+        })
+        .await;
+    if let Some(rate) = bytes_per_second {
+        tokio::time::sleep(std::time::Duration::from_secs_f64(bytes as f64 / rate)).await;
+    }
+    (result, bytes)
+}
+
+/// Failure registered through `rapfiles.testing.inject_fault`.
+struct Fault {
+    id: u64,
+    pattern: String,
+    errno: i32,
+    probability: f64,
+    ops: Option<Vec<String>>,
+    rng_state: u64,
+}
+
+/// Slowdown registered through `rapfiles.testing.inject_latency`.
+struct Slowdown {
+    id: u64,
+    pattern: String,
+    latency: std::time::Duration,
+    bytes_per_second: Option<f64>,
+    ops: Option<Vec<String>>,
+}
+
+static FAULTS: std::sync::Mutex<Vec<Fault>> = std::sync::Mutex::new(Vec::new());
+static SLOWDOWNS: std::sync::Mutex<Vec<Slowdown>> = std::sync::Mutex::new(Vec::new());
+static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Advance a SplitMix64 state and return its next output.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Fill `buffer` with bytes from a SplitMix64 stream.
+fn fill_random(state: &mut u64, buffer: &mut [u8]) {
+    for word in buffer.chunks_mut(8) {
+        let bytes = splitmix64(state).to_le_bytes();
+        word.copy_from_slice(&bytes[..word.len()]);
+    }
+}
+
+/// Advance a SplitMix64 state and return a uniform value in `[0, 1)`.
+fn next_random(state: &mut u64) -> f64 {
+    (splitmix64(state) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Match `text` against an fnmatch-style pattern.
+///
+/// Supports `*` (any run of characters, including separators), `?` (any
+/// single character) and `[...]`/`[!...]` character classes with ranges.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it is matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+                continue;
+            }
+            Some(b'?') => Some(p + 1),
+            Some(b'[') => match_class(pattern, p, text[t]),
+            Some(&c) => (c == text[t]).then_some(p + 1),
+            None => None,
+        };
+        match (step, backtrack) {
+            (Some(next), _) => {
+                p = next;
+                t += 1;
+            }
+            (None, Some((star_p, star_t))) => {
+                p = star_p;
+                t = star_t + 1;
+                backtrack = Some((star_p, star_t + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Match `c` against the character class starting at `pattern[start]`.
 ///
-/// # Returns
+/// Returns the pattern position after the class if it matches. An unclosed
+/// `[` is treated as a literal.
+fn match_class(pattern: &[u8], start: usize, c: u8) -> Option<usize> {
+    let mut i = start + 1;
+    let negate = matches!(pattern.get(i), Some(b'!') | Some(b'^'));
+    if negate {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() && (first || pattern[i] != b']') {
+        first = false;
+        if i + 2 < pattern.len() && pattern[i + 1] == b'-' && pattern[i + 2] != b']' {
+            matched |= pattern[i] <= c && c <= pattern[i + 2];
+            i += 3;
+        } else {
+            matched |= pattern[i] == c;
+            i += 1;
+        }
+    }
+    if i >= pattern.len() {
+        return (c == b'[').then_some(start + 1);
+    }
+    (matched != negate).then_some(i + 1)
+}
+
+/// Match `text` against an fnmatch-style pattern like `glob_match`,
+/// returning the text matched by each wildcard in order.
+///
+/// A `*` matches as much as it can, like the `.*` it stands for in Python's
+/// `fnmatch`.
+fn glob_captures<'t>(pattern: &[u8], text: &'t [u8]) -> Option<Vec<&'t [u8]>> {
+    fn matches<'t>(
+        pattern: &[u8],
+        p: usize,
+        text: &'t [u8],
+        t: usize,
+        captures: &mut Vec<&'t [u8]>,
+    ) -> bool {
+        let Some(&c) = pattern.get(p) else {
+            return t == text.len();
+        };
+        if c == b'*' {
+            for end in (t..=text.len()).rev() {
+                captures.push(&text[t..end]);
+                if matches(pattern, p + 1, text, end, captures) {
+                    return true;
+                }
+                captures.pop();
+            }
+            return false;
+        }
+        let Some(&ch) = text.get(t) else {
+            return false;
+        };
+        let next = match c {
+            b'?' => Some(p + 1),
+            b'[' => match_class(pattern, p, ch),
+            _ => (c == ch).then_some(p + 1),
+        };
+        let Some(next) = next else {
+            return false;
+        };
+        // An unclosed `[` is a literal, a class spans at least three bytes
+        let wildcard = c == b'?' || next > p + 1;
+        if wildcard {
+            captures.push(&text[t..t + 1]);
+        }
+        if matches(pattern, next, text, t + 1, captures) {
+            return true;
+        }
+        if wildcard {
+            captures.pop();
+        }
+        false
+    }
+
+    let mut captures = Vec::new();
+    matches(pattern, 0, text, 0, &mut captures).then_some(captures)
+}
+
+/// Filesystem operation captured by `rapfiles.recording.Recorder`.
 ///
-/// A coroutine that yields the file contents as a string.
+/// # Properties
+///
+/// * `op` - Operation name, e.g. "read_file" or "write" for `AsyncFile.write`
+/// * `paths` - Paths the operation acted on
+/// * `bytes` - Bytes read or written
+/// * `error` - Error message if the operation failed, `None` otherwise
+/// * `ok` - Whether the operation succeeded
+/// * `start` - Seconds between the start of the recording and the operation
+/// * `duration` - Seconds the operation took
+#[pyclass]
+#[derive(Clone)]
+struct OpRecord {
+    op: String,
+    paths: Vec<String>,
+    bytes: usize,
+    error: Option<String>,
+    start: f64,
+    duration: f64,
+}
+
+#[pymethods]
+impl OpRecord {
+    #[getter]
+    fn op(&self) -> String {
+        self.op.clone()
+    }
+
+    #[getter]
+    fn paths(&self) -> Vec<String> {
+        self.paths.clone()
+    }
+
+    #[getter]
+    fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    #[getter]
+    fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+
+    #[getter]
+    fn ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    #[getter]
+    fn start(&self) -> f64 {
+        self.start
+    }
+
+    #[getter]
+    fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "OpRecord(op={:?}, paths={:?}, bytes={}, ok={})",
+            self.op,
+            self.paths,
+            self.bytes,
+            self.error.is_none()
+        )
+    }
+}
+
+/// Operations captured since `start_recording`.
+struct Recording {
+    started: std::time::Instant,
+    records: Vec<OpRecord>,
+}
+
+static RECORDING: std::sync::Mutex<Option<Recording>> = std::sync::Mutex::new(None);
+static RECORDING_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Append a finished operation to the active recording, if any.
+fn record_op(op: OpContext, bytes: usize, error: Option<&PyErr>, started: std::time::Instant) {
+    let duration = started.elapsed().as_secs_f64();
+    let error = error.map(|e| e.to_string());
+    let mut recording = RECORDING.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(recording) = recording.as_mut() {
+        recording.records.push(OpRecord {
+            op: op.name.to_string(),
+            paths: op.paths.iter().map(|path| path.to_string()).collect(),
+            bytes,
+            error,
+            start: started
+                .saturating_duration_since(recording.started)
+                .as_secs_f64(),
+            duration,
+        });
+    }
+}
+
+/// Start capturing every filesystem operation.
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the file does not exist,
-/// `PyIOError` if the file cannot be read, or `PyValueError` if the path is invalid.
+/// Returns `PyRuntimeError` if a recording is already active.
 #[pyfunction]
-fn read_file_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        tokio::fs::read_to_string(&path)
-            .await
-            .map_err(|e| map_io_error(e, &path_clone, "read file"))
-    };
-    future_into_py(py, future)
+fn start_recording() -> PyResult<()> {
+    let mut recording = RECORDING.lock().unwrap_or_else(|e| e.into_inner());
+    if recording.is_some() {
+        return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+            "A recording is already active",
+        ));
+    }
+    *recording = Some(Recording {
+        started: std::time::Instant::now(),
+        records: Vec::new(),
+    });
+    RECORDING_ACTIVE.store(true, Ordering::Relaxed);
+    Ok(())
 }
 
-/// Async file write using Tokio (GIL-independent).
+/// Stop capturing and return the recorded operations in completion order.
 ///
-/// Writes the entire contents to a file. If the file exists, it will be overwritten.
-/// All I/O operations execute outside the Python GIL using native Tokio,
-/// ensuring true async behavior and preventing event loop stalls.
+/// Returns an empty list if no recording is active.
+#[pyfunction]
+fn stop_recording() -> Vec<OpRecord> {
+    let mut recording = RECORDING.lock().unwrap_or_else(|e| e.into_inner());
+    RECORDING_ACTIVE.store(false, Ordering::Relaxed);
+    recording
+        .take()
+        .map(|recording| recording.records)
+        .unwrap_or_default()
+}
+
+/// Sink of the active audit log and the context variable naming the actor.
+struct AuditLog {
+    sink: Py<PyAny>,
+    actor_var: Py<PyAny>,
+}
+
+static AUDIT_LOG: std::sync::Mutex<Option<AuditLog>> = std::sync::Mutex::new(None);
+static AUDIT_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Who started an audited operation, and when.
+struct AuditStart {
+    actor: Py<PyAny>,
+    timestamp: f64,
+    started: std::time::Instant,
+}
+
+impl AuditStart {
+    /// Capture the actor of the current Python context.
+    fn capture(py: Python<'_>) -> PyResult<Self> {
+        let actor_var = AUDIT_LOG
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|log| log.actor_var.clone_ref(py));
+        let actor = match actor_var {
+            Some(var) => var.bind(py).call_method1("get", (py.None(),))?,
+            None => py.None().into_bound(py),
+        };
+        Ok(AuditStart {
+            actor: actor.unbind(),
+            timestamp: system_time_to_timestamp(SystemTime::now()),
+            started: std::time::Instant::now(),
+        })
+    }
+}
+
+/// Pass a finished operation to the sink of the active audit log, if any.
+///
+/// The sink is called on the thread that completed the operation; its
+/// exceptions are reported with `sys.unraisablehook`.
+fn audit_op(op: &OpContext, start: AuditStart, bytes: usize, error: Option<&PyErr>) {
+    let duration = start.started.elapsed().as_secs_f64();
+    Python::attach(|py| {
+        let Some(sink) = AUDIT_LOG
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|log| log.sink.clone_ref(py))
+        else {
+            return;
+        };
+        let event = || -> PyResult<Bound<'_, pyo3::types::PyDict>> {
+            let event = pyo3::types::PyDict::new(py);
+            event.set_item("timestamp", start.timestamp)?;
+            event.set_item("actor", start.actor.bind(py))?;
+            event.set_item("op", op.name)?;
+            event.set_item(
+                "paths",
+                op.paths.iter().map(|path| &**path).collect::<Vec<_>>(),
+            )?;
+            event.set_item("bytes", bytes)?;
+            event.set_item("ok", error.is_none())?;
+            event.set_item("error", error.map(|e| e.to_string()))?;
+            event.set_item("duration", duration)?;
+            Ok(event)
+        };
+        if let Err(err) = event().and_then(|event| sink.bind(py).call1((event,))) {
+            err.write_unraisable(py, Some(sink.bind(py)));
+        }
+    });
+}
+
+/// Start passing every finished operation to `sink`.
 ///
 /// # Arguments
 ///
-/// * `py` - Python GIL token
-/// * `path` - Path to the file to write
-/// * `contents` - Content to write to the file (UTF-8 string)
-///
-/// # Returns
-///
-/// A coroutine that yields `None` on success.
+/// * `sink` - Callable receiving one event dict per operation
+/// * `actor_var` - `contextvars.ContextVar` read when an operation starts,
+///   reported as the event's `actor`
 ///
 /// # Errors
 ///
-/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
-/// if write permission is denied, or `PyValueError` if the path is invalid.
+/// Returns `PyRuntimeError` if an audit log is already active.
 #[pyfunction]
-fn write_file_async(py: Python<'_>, path: String, contents: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        tokio::fs::write(&path, contents)
-            .await
-            .map_err(|e| map_io_error(e, &path_clone, "write file"))
-    };
-    future_into_py(py, future)
+fn start_audit(sink: Py<PyAny>, actor_var: Py<PyAny>) -> PyResult<()> {
+    let mut log = AUDIT_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    if log.is_some() {
+        return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+            "An audit log is already active",
+        ));
+    }
+    *log = Some(AuditLog { sink, actor_var });
+    AUDIT_ACTIVE.store(true, Ordering::Relaxed);
+    Ok(())
 }
 
-/// Async binary file read using Tokio (GIL-independent).
+/// Stop the active audit log, returning whether one was active.
 ///
-/// Reads the entire file and returns its contents as raw bytes.
-/// All I/O operations execute outside the Python GIL using native Tokio,
-/// ensuring true async behavior and preventing event loop stalls.
+/// Operations still running when the log stops are not reported.
+#[pyfunction]
+fn stop_audit() -> bool {
+    let mut log = AUDIT_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    AUDIT_ACTIVE.store(false, Ordering::Relaxed);
+    log.take().is_some()
+}
+
+/// Register an injected fault.
+///
+/// Operations touching a path that matches `pattern` fail with `errno`
+/// (with the given probability) until the fault is removed.
 ///
 /// # Arguments
 ///
-/// * `py` - Python GIL token
-/// * `path` - Path to the file to read
+/// * `pattern` - fnmatch-style glob matched against every path of an operation
+/// * `errno` - OS error number to fail with (e.g. `errno.EIO`)
+/// * `probability` - Chance in `[0, 1]` that a matching operation fails
+/// * `ops` - Operation names to restrict the fault to (all operations if `None`)
+/// * `seed` - Seed for the fault's random generator, for reproducible runs
 ///
 /// # Returns
 ///
-/// A coroutine that yields the file contents as bytes.
+/// The fault ID, used with `remove_fault`.
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the file does not exist,
-/// `PyIOError` if the file cannot be read, or `PyValueError` if the path is invalid.
+/// Returns `PyValueError` if `errno` is not positive or `probability` is
+/// outside `[0, 1]`.
 #[pyfunction]
-fn read_file_bytes_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        tokio::fs::read(&path)
-            .await
-            .map_err(|e| map_io_error(e, &path_clone, "read file"))
-    };
-    future_into_py(py, future)
+#[pyo3(signature = (pattern, errno, probability = 1.0, ops = None, seed = None))]
+fn inject_fault(
+    pattern: String,
+    errno: i32,
+    probability: f64,
+    ops: Option<Vec<String>>,
+    seed: Option<u64>,
+) -> PyResult<u64> {
+    if errno <= 0 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid errno: {errno}. Must be a positive OS error number"
+        )));
+    }
+    if !(0.0..=1.0).contains(&probability) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid probability: {probability}. Must be between 0 and 1"
+        )));
+    }
+    let id = NEXT_HOOK_ID.fetch_add(1, Ordering::Relaxed);
+    let rng_state = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+            ^ id
+    });
+    FAULTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Fault {
+            id,
+            pattern,
+            errno,
+            probability,
+            ops,
+            rng_state,
+        });
+    Ok(id)
 }
 
-/// Async binary file write using Tokio (GIL-independent).
+/// Remove an injected fault, returning whether it was registered.
+#[pyfunction]
+fn remove_fault(id: u64) -> bool {
+    let mut faults = FAULTS.lock().unwrap_or_else(|e| e.into_inner());
+    let before = faults.len();
+    faults.retain(|fault| fault.id != id);
+    faults.len() != before
+}
+
+/// Remove all injected faults.
+#[pyfunction]
+fn clear_faults() {
+    FAULTS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Register an injected slowdown.
 ///
-/// Writes raw bytes to a file. If the file exists, it will be overwritten.
-/// All I/O operations execute outside the Python GIL using native Tokio,
-/// ensuring true async behavior and preventing event loop stalls.
+/// Operations touching a path that matches `pattern` are delayed by
+/// `latency` before they start and, if `bytes_per_second` is set, by the
+/// time their data transfer would take at that rate once they finish.
 ///
 /// # Arguments
 ///
-/// * `py` - Python GIL token
-/// * `path` - Path to the file to write
-/// * `contents` - Bytes to write to the file
+/// * `pattern` - fnmatch-style glob matched against every path of an operation
+/// * `latency` - Delay in seconds added before each matching operation
+/// * `bytes_per_second` - Throughput cap for data read or written
+/// * `ops` - Operation names to restrict the slowdown to (all operations if `None`)
 ///
 /// # Returns
 ///
-/// A coroutine that yields `None` on success.
+/// The slowdown ID, used with `remove_latency`.
 ///
 /// # Errors
 ///
-/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
-/// if write permission is denied, or `PyValueError` if the path is invalid.
+/// Returns `PyValueError` if `latency` is negative or `bytes_per_second` is
+/// not positive.
 #[pyfunction]
-fn write_file_bytes_async<'a>(
-    py: Python<'a>,
-    path: String,
-    contents: &'a Bound<'a, PyBytes>,
-) -> PyResult<Bound<'a, PyAny>> {
-    validate_path(&path)?;
-    let bytes = contents.as_bytes().to_vec();
-    let future = async move {
-        let path_clone = path.clone();
-        tokio::fs::write(&path, bytes)
-            .await
-            .map_err(|e| map_io_error(e, &path_clone, "write file"))
-    };
-    future_into_py(py, future)
+#[pyo3(signature = (pattern, latency = 0.0, bytes_per_second = None, ops = None))]
+fn inject_latency(
+    pattern: String,
+    latency: f64,
+    bytes_per_second: Option<f64>,
+    ops: Option<Vec<String>>,
+) -> PyResult<u64> {
+    let latency = std::time::Duration::try_from_secs_f64(latency).map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid latency: {latency}. Must be a non-negative number of seconds"
+        ))
+    })?;
+    if let Some(rate) = bytes_per_second {
+        if !(rate > 0.0 && rate.is_finite()) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid bytes_per_second: {rate}. Must be positive"
+            )));
+        }
+    }
+    let id = NEXT_HOOK_ID.fetch_add(1, Ordering::Relaxed);
+    SLOWDOWNS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Slowdown {
+            id,
+            pattern,
+            latency,
+            bytes_per_second,
+            ops,
+        });
+    Ok(id)
 }
 
-/// Async file append using Tokio (GIL-independent).
-///
-/// Appends content to the end of a file. If the file does not exist, it will be created.
-/// All I/O operations execute outside the Python GIL using native Tokio,
-/// ensuring true async behavior and preventing event loop stalls.
-///
-/// # Arguments
-///
-/// * `py` - Python GIL token
-/// * `path` - Path to the file to append to
-/// * `contents` - Content to append to the file (UTF-8 string)
+/// Remove an injected slowdown, returning whether it was registered.
+#[pyfunction]
+fn remove_latency(id: u64) -> bool {
+    let mut slowdowns = SLOWDOWNS.lock().unwrap_or_else(|e| e.into_inner());
+    let before = slowdowns.len();
+    slowdowns.retain(|slowdown| slowdown.id != id);
+    slowdowns.len() != before
+}
+
+/// Remove all injected slowdowns.
+#[pyfunction]
+fn clear_latency() {
+    SLOWDOWNS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+// I/O backends
+//
+// The backends, their global default and the runtime live in rapfiles-core,
+// so Rust callers share them; this section adds the Python-facing selection
+// (contextvar overrides and per-path routes).
+
+/// Look up a backend by name.
 ///
-/// # Returns
+/// # Errors
 ///
-/// A coroutine that yields `None` on success.
+/// Returns `PyValueError` if the backend name is unknown.
+fn parse_backend(name: &str) -> PyResult<Backend> {
+    Backend::from_name(name).ok_or_else(|| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid backend: {name}. Must be 'tokio' or 'threadpool'"
+        ))
+    })
+}
+
+/// `contextvars.ContextVar` holding the per-context backend override.
+static BACKEND_OVERRIDE: pyo3::sync::PyOnceLock<Py<PyAny>> = pyo3::sync::PyOnceLock::new();
+
+/// Backend and tuning applied to operations on paths below a prefix.
 ///
-/// # Errors
+/// # Properties
 ///
-/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
-/// if write permission is denied, or `PyValueError` if the path is invalid.
-#[pyfunction]
-fn append_file_async(py: Python<'_>, path: String, contents: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        let mut file = tokio::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&path)
-            .await
-            .map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                    "Failed to open file {path_clone} for appending: {e}"
-                ))
-            })?;
+/// * `prefix` - Absolute path prefix, matched component by component
+/// * `backend` - Backend for matching operations, `None` to keep the default
+/// * `concurrency` - Files processed at once by batch and tree operations,
+///   `None` for the built-in limits
+#[pyclass]
+#[derive(Clone)]
+struct Route {
+    prefix: std::path::PathBuf,
+    backend: Option<Backend>,
+    concurrency: Option<usize>,
+}
 
-        use tokio::io::AsyncWriteExt;
-        file.write_all(contents.as_bytes()).await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to append to file {path_clone}: {e}"
-            ))
+#[pymethods]
+impl Route {
+    #[getter]
+    fn prefix(&self) -> String {
+        self.prefix.to_string_lossy().into_owned()
+    }
+
+    #[getter]
+    fn backend(&self) -> Option<&'static str> {
+        self.backend.map(Backend::name)
+    }
+
+    #[getter]
+    fn concurrency(&self) -> Option<usize> {
+        self.concurrency
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Route(prefix={:?}, backend={:?}, concurrency={:?})",
+            self.prefix(),
+            self.backend(),
+            self.concurrency
+        )
+    }
+}
+
+/// Registered routes, longest prefix first so the first match is the most specific.
+static ROUTES: std::sync::Mutex<Vec<Route>> = std::sync::Mutex::new(Vec::new());
+
+/// Settings resolved for one operation when it starts.
+#[derive(Clone, Copy)]
+struct OpSettings {
+    backend: Backend,
+    concurrency: Option<usize>,
+}
+
+tokio::task_local! {
+    /// Settings of the operation running in the current task.
+    static OP_SETTINGS: OpSettings;
+}
+
+fn backend_override_var(py: Python<'_>) -> PyResult<&Bound<'_, PyAny>> {
+    BACKEND_OVERRIDE
+        .get_or_try_init(py, || {
+            let var = py
+                .import("contextvars")?
+                .getattr("ContextVar")?
+                .call1(("rapfiles_backend",))?;
+            Ok::<_, PyErr>(var.unbind())
         })
+        .map(|var| var.bind(py))
+}
+
+/// Return the most specific route whose prefix contains `path`.
+fn matching_route(path: &str) -> Option<Route> {
+    let routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    if routes.is_empty() {
+        return None;
+    }
+    let path = std::path::absolute(path).ok()?;
+    routes
+        .iter()
+        .find(|route| path.starts_with(&route.prefix))
+        .cloned()
+}
+
+/// Resolve the settings of an operation on `path` started in the current Python context.
+///
+/// The backend comes from a `use_backend` override, then the matching route,
+/// then the global default.
+fn select_settings(py: Python<'_>, path: Option<&str>) -> PyResult<OpSettings> {
+    let route = path.and_then(matching_route);
+    let overridden = backend_override_var(py)?.call_method1("get", (py.None(),))?;
+    let backend = if !overridden.is_none() {
+        Backend::from_index(overridden.extract()?)
+    } else if let Some(backend) = route.as_ref().and_then(|route| route.backend) {
+        backend
+    } else {
+        rapfiles_core::default_backend()
     };
-    future_into_py(py, future)
+    Ok(OpSettings {
+        backend,
+        concurrency: route
+            .and_then(|route| route.concurrency)
+            .or_else(default_concurrency),
+    })
 }
 
-// Directory operations
+/// Concurrency limit of the running operation's route or the global
+/// configuration, or `default`.
+fn op_concurrency(default: usize) -> usize {
+    OP_SETTINGS
+        .try_with(|settings| settings.concurrency)
+        .ok()
+        .flatten()
+        .unwrap_or(default)
+}
 
-/// Create a directory asynchronously.
+/// Await futures concurrently and return their outputs in order.
 ///
-/// Creates a single directory. Parent directories must already exist.
-/// All I/O operations execute outside the Python GIL using native Tokio.
+/// At most the route's `concurrency` futures run at once; without a limit
+/// all of them run together.
+async fn join_limited<F: std::future::Future>(futures: Vec<F>) -> Vec<F::Output> {
+    use futures::stream::StreamExt;
+
+    let limit = op_concurrency(futures.len().max(1));
+    futures::stream::iter(futures)
+        .buffered(limit)
+        .collect()
+        .await
+}
+
+/// Set the backend used by operations started from now on.
 ///
 /// # Arguments
 ///
-/// * `py` - Python GIL token
-/// * `path` - Path to the directory to create
-///
-/// # Returns
-///
-/// A coroutine that yields `None` on success.
+/// * `name` - "tokio" (default) or "threadpool"
 ///
 /// # Errors
 ///
-/// Returns `PyFileExistsError` if the directory already exists,
-/// `PyIOError` if the directory cannot be created, or `PyValueError` if the path is invalid.
+/// Returns `PyValueError` if the backend name is unknown.
 #[pyfunction]
-fn create_dir_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        tokio::fs::create_dir(&path).await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to create directory {path_clone}: {e}"
-            ))
-        })
-    };
-    future_into_py(py, future)
+fn set_backend(name: &str) -> PyResult<()> {
+    rapfiles_core::set_default_backend(parse_backend(name)?);
+    Ok(())
 }
 
-/// Create a directory and all parent directories asynchronously.
-///
-/// Creates a directory and any necessary parent directories (equivalent to `mkdir -p`).
-/// All I/O operations execute outside the Python GIL using native Tokio.
+/// Return the name of the backend used by operations started in the current context.
 ///
 /// # Arguments
 ///
-/// * `py` - Python GIL token
-/// * `path` - Path to the directory to create (with parents)
+/// * `path` - Resolve the backend for operations on this path, applying routes
+#[pyfunction]
+#[pyo3(signature = (path = None))]
+fn get_backend(py: Python<'_>, path: Option<&str>) -> PyResult<&'static str> {
+    Ok(select_settings(py, path)?.backend.name())
+}
+
+/// Route operations on paths below `prefix` to a backend and tuning.
 ///
-/// # Returns
+/// Replaces an existing route for the same prefix. Routes apply to every
+/// operation, matched on its first path; the longest matching prefix wins.
 ///
-/// A coroutine that yields `None` on success.
+/// # Arguments
+///
+/// * `prefix` - Directory prefix; relative prefixes are made absolute
+/// * `backend` - Backend for matching operations ("tokio" or "threadpool")
+/// * `concurrency` - Files processed at once by batch and tree operations
 ///
 /// # Errors
 ///
-/// Returns `PyIOError` if the directory cannot be created,
-/// or `PyValueError` if the path is invalid.
+/// Returns `PyValueError` if the prefix is invalid, the backend is unknown,
+/// `concurrency` is 0, or neither `backend` nor `concurrency` is given.
 #[pyfunction]
-fn create_dir_all_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        tokio::fs::create_dir_all(&path).await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to create directory {path_clone}: {e}"
-            ))
-        })
-    };
-    future_into_py(py, future)
+#[pyo3(signature = (prefix, backend = None, concurrency = None))]
+fn add_route(prefix: &str, backend: Option<&str>, concurrency: Option<usize>) -> PyResult<()> {
+    validate_path(prefix)?;
+    let backend = backend.map(parse_backend).transpose()?;
+    if concurrency == Some(0) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Invalid concurrency: 0. Must be at least 1",
+        ));
+    }
+    if backend.is_none() && concurrency.is_none() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "A route needs a backend or a concurrency setting",
+        ));
+    }
+    let prefix = std::path::absolute(prefix).map_err(|e| map_io_error(e, prefix, "resolve"))?;
+    let mut routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    routes.retain(|route| route.prefix != prefix);
+    routes.push(Route {
+        prefix,
+        backend,
+        concurrency,
+    });
+    routes.sort_by_key(|route| std::cmp::Reverse(route.prefix.components().count()));
+    Ok(())
 }
 
-/// Remove an empty directory asynchronously.
+/// Remove the route for `prefix`, returning whether one was registered.
 #[pyfunction]
-fn remove_dir_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        tokio::fs::remove_dir(&path).await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to remove directory {path_clone}: {e}"
-            ))
-        })
+fn remove_route(prefix: &str) -> bool {
+    let Ok(prefix) = std::path::absolute(prefix) else {
+        return false;
     };
-    future_into_py(py, future)
+    let mut routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    let before = routes.len();
+    routes.retain(|route| route.prefix != prefix);
+    routes.len() != before
 }
 
-/// Remove a directory and all its contents asynchronously.
+/// Remove all routes.
 #[pyfunction]
-fn remove_dir_all_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        tokio::fs::remove_dir_all(&path).await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to remove directory {path_clone}: {e}"
-            ))
-        })
-    };
-    future_into_py(py, future)
+fn clear_routes() {
+    ROUTES.lock().unwrap_or_else(|e| e.into_inner()).clear();
 }
 
-/// List directory contents asynchronously.
-///
-/// Returns a list of file and directory names in the specified directory.
-/// All I/O operations execute outside the Python GIL using native Tokio.
-///
-/// # Arguments
-///
-/// * `py` - Python GIL token
-/// * `path` - Path to the directory to list
+/// List the registered routes, most specific first.
+#[pyfunction]
+fn routes() -> Vec<Route> {
+    ROUTES.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Return the names of the available backends.
+#[pyfunction]
+fn available_backends() -> Vec<&'static str> {
+    Backend::ALL.into_iter().map(Backend::name).collect()
+}
+
+/// Override the backend for the current context (task or thread).
 ///
 /// # Returns
 ///
-/// A coroutine that yields a list of file and directory names (strings).
+/// A `contextvars.Token` to pass to `reset_backend_override`.
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the directory does not exist,
-/// `PyIOError` if the directory cannot be read, or `PyValueError` if the path is invalid.
+/// Returns `PyValueError` if the backend name is unknown.
 #[pyfunction]
-fn list_dir_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        let mut entries = tokio::fs::read_dir(&path).await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to read directory {path_clone}: {e}"
-            ))
-        })?;
-
-        let mut names = Vec::new();
-        while let Some(entry) = entries.next_entry().await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to read directory entry in {path_clone}: {e}"
-            ))
-        })? {
-            if let Some(name) = entry.file_name().to_str() {
-                names.push(name.to_string());
-            }
-        }
-        Ok(names)
-    };
-    future_into_py(py, future)
+fn override_backend<'py>(py: Python<'py>, name: &str) -> PyResult<Bound<'py, PyAny>> {
+    let backend = parse_backend(name)?;
+    backend_override_var(py)?.call_method1("set", (backend as u8,))
 }
 
-/// Check if a path exists asynchronously.
+/// Undo an `override_backend` call.
 #[pyfunction]
-fn exists_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move { Ok(tokio::fs::metadata(&path).await.is_ok()) };
-    future_into_py(py, future)
+fn reset_backend_override(py: Python<'_>, token: &Bound<'_, PyAny>) -> PyResult<()> {
+    backend_override_var(py)?.call_method1("reset", (token,))?;
+    Ok(())
 }
 
-/// Check if a path is a file asynchronously.
-#[pyfunction]
-fn is_file_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        let metadata = tokio::fs::metadata(&path).await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to get metadata for {path_clone}: {e}"
-            ))
-        })?;
-        Ok(metadata.is_file())
-    };
-    future_into_py(py, future)
+// Operation options
+//
+// `rapfiles.options()` stores an `OpOptions` in a context variable; every
+// operation started in that context picks it up in `spawn_op`.
+
+/// Delay before the first retry, doubled for each further attempt.
+const RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Shared byte budget of a `rapfiles.options(rate_limit=...)` block.
+///
+/// The budget is split between the jobs using it in proportion to their
+/// weights. Only active jobs (with operations in flight or bytes still
+/// being paid for) count, so a job running alone gets the whole budget.
+struct RateLimiter {
+    bytes_per_second: f64,
+    /// Budget of each job, keyed by name; operations outside a named job
+    /// share the one named "".
+    jobs: std::sync::Mutex<std::collections::HashMap<Arc<str>, JobBudget>>,
 }
 
-/// Check if a path is a directory asynchronously.
-#[pyfunction]
-fn is_dir_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        let metadata = tokio::fs::metadata(&path).await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to get metadata for {path_clone}: {e}"
-            ))
-        })?;
-        Ok(metadata.is_dir())
+/// A job's use of a `RateLimiter`.
+struct JobBudget {
+    weight: f64,
+    in_flight: usize,
+    /// When the bytes the job transferred so far have been paid for.
+    next_free: tokio::time::Instant,
+}
+
+/// Name and weight of the job an operation belongs to, set with
+/// `rapfiles.options(job=..., weight=...)`.
+#[derive(Clone)]
+struct JobShare {
+    name: Arc<str>,
+    weight: f64,
+}
+
+impl JobShare {
+    /// The job of operations that were not given one.
+    fn unnamed() -> Self {
+        JobShare {
+            name: Arc::from(""),
+            weight: 1.0,
+        }
+    }
+}
+
+impl RateLimiter {
+    fn new(bytes_per_second: f64) -> Self {
+        RateLimiter {
+            bytes_per_second,
+            jobs: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    /// Mark an operation of `job` as in flight until the guard is dropped.
+    fn start(self: &Arc<Self>, job: &JobShare) -> JobGuard {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let budget = jobs.entry(Arc::clone(&job.name)).or_insert(JobBudget {
+            weight: job.weight,
+            in_flight: 0,
+            next_free: tokio::time::Instant::now(),
+        });
+        budget.weight = job.weight;
+        budget.in_flight += 1;
+        JobGuard {
+            limiter: Arc::clone(self),
+            name: Arc::clone(&job.name),
+        }
+    }
+
+    /// Charge `bytes` to the budget of `job` and wait until they are paid for.
+    ///
+    /// Operations of the same job queue behind each other at the job's share
+    /// of the limit, so together all jobs stay below it.
+    async fn throttle(&self, job: &JobShare, bytes: usize) {
+        if bytes == 0 {
+            return;
+        }
+        let until = {
+            let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+            let now = tokio::time::Instant::now();
+            // Forget idle jobs, so the map only holds the active ones
+            jobs.retain(|name, budget| {
+                budget.in_flight > 0 || budget.next_free > now || *name == job.name
+            });
+            let total_weight: f64 = jobs.values().map(|budget| budget.weight).sum();
+            let Some(budget) = jobs.get_mut(&job.name) else {
+                return;
+            };
+            let rate = self.bytes_per_second * budget.weight / total_weight;
+            let start = budget.next_free.max(now);
+            budget.next_free = start + std::time::Duration::from_secs_f64(bytes as f64 / rate);
+            budget.next_free
+        };
+        tokio::time::sleep_until(until).await;
+    }
+}
+
+/// Keeps a job active in its `RateLimiter` while one of its operations runs.
+struct JobGuard {
+    limiter: Arc<RateLimiter>,
+    name: Arc<str>,
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        let mut jobs = self.limiter.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(budget) = jobs.get_mut(&self.name) {
+            budget.in_flight -= 1;
+        }
+    }
+}
+
+/// Scheduling class of an operation, set with `rapfiles.options(priority=...)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "high" => Ok(Priority::High),
+            "normal" => Ok(Priority::Normal),
+            "low" => Ok(Priority::Low),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid priority: {name}. Must be 'high', 'normal' or 'low'"
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Normal => "normal",
+            Priority::Low => "low",
+        }
+    }
+}
+
+/// Low-priority operations allowed to run while high-priority ones are in
+/// flight; the others queue until a slot frees up or the high-priority
+/// operations finish.
+const LOW_PRIORITY_SLOTS: usize = 1;
+
+static HIGH_PRIORITY_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+/// Notified when the last high-priority operation finishes.
+static HIGH_PRIORITY_IDLE: tokio::sync::Notify = tokio::sync::Notify::const_new();
+static LOW_PRIORITY_PERMITS: tokio::sync::Semaphore =
+    tokio::sync::Semaphore::const_new(LOW_PRIORITY_SLOTS);
+
+/// Admission of an operation by its priority, held while it runs.
+enum PriorityGuard {
+    Unrestricted,
+    High,
+    Low {
+        _permit: tokio::sync::SemaphorePermit<'static>,
+    },
+}
+
+impl PriorityGuard {
+    /// Wait until an operation of the given priority may run.
+    ///
+    /// High- and normal-priority operations run at once. Low-priority ones run
+    /// at once while no high-priority operation is in flight, and otherwise
+    /// share `LOW_PRIORITY_SLOTS`, so background work cannot flood the
+    /// blocking thread pool ahead of latency-critical operations.
+    async fn admit(priority: Priority) -> Self {
+        match priority {
+            Priority::Normal => PriorityGuard::Unrestricted,
+            Priority::High => {
+                HIGH_PRIORITY_IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+                PriorityGuard::High
+            }
+            Priority::Low => loop {
+                // Register for the notification before checking, so a high-
+                // priority operation finishing in between is not missed
+                let idle = HIGH_PRIORITY_IDLE.notified();
+                tokio::pin!(idle);
+                idle.as_mut().enable();
+                if HIGH_PRIORITY_IN_FLIGHT.load(Ordering::SeqCst) == 0 {
+                    return PriorityGuard::Unrestricted;
+                }
+                tokio::select! {
+                    permit = LOW_PRIORITY_PERMITS.acquire() => {
+                        let permit = permit.expect("low-priority semaphore is never closed");
+                        return PriorityGuard::Low { _permit: permit };
+                    }
+                    () = &mut idle => {}
+                }
+            },
+        }
+    }
+}
+
+impl Drop for PriorityGuard {
+    fn drop(&mut self) {
+        if let PriorityGuard::High = self {
+            if HIGH_PRIORITY_IN_FLIGHT.fetch_sub(1, Ordering::SeqCst) == 1 {
+                HIGH_PRIORITY_IDLE.notify_waiters();
+            }
+        }
+    }
+}
+
+/// Policies applied to the operations started in a `rapfiles.options()` block.
+#[pyclass(frozen)]
+#[derive(Clone, Default)]
+struct OpOptions {
+    timeout: Option<std::time::Duration>,
+    retries: u32,
+    rate_limit: Option<Arc<RateLimiter>>,
+    priority: Priority,
+    job: Option<JobShare>,
+}
+
+/// `contextvars.ContextVar` holding the `OpOptions` of the current context.
+static OPTIONS_OVERRIDE: pyo3::sync::PyOnceLock<Py<PyAny>> = pyo3::sync::PyOnceLock::new();
+
+fn options_override_var(py: Python<'_>) -> PyResult<&Bound<'_, PyAny>> {
+    OPTIONS_OVERRIDE
+        .get_or_try_init(py, || {
+            let var = py
+                .import("contextvars")?
+                .getattr("ContextVar")?
+                .call1(("rapfiles_options",))?;
+            Ok::<_, PyErr>(var.unbind())
+        })
+        .map(|var| var.bind(py))
+}
+
+/// Return the options of the current Python context.
+fn current_options(py: Python<'_>) -> PyResult<OpOptions> {
+    let options = options_override_var(py)?.call_method1("get", (py.None(),))?;
+    if options.is_none() {
+        return Ok(OpOptions::default());
+    }
+    Ok(options.cast::<OpOptions>()?.get().clone())
+}
+
+/// Return whether a failed attempt may succeed when repeated.
+///
+/// Errors naming a permanent condition (missing file, denied permission,
+/// invalid argument or data, ...) are not retried.
+fn is_transient(error: &PyErr) -> bool {
+    use pyo3::exceptions::{
+        PyFileExistsError, PyFileNotFoundError, PyIsADirectoryError, PyNotADirectoryError,
+        PyOSError, PyPermissionError,
     };
-    future_into_py(py, future)
+
+    Python::attach(|py| {
+        error.is_instance_of::<PyOSError>(py)
+            && !error.is_instance_of::<PyFileNotFoundError>(py)
+            && !error.is_instance_of::<PyPermissionError>(py)
+            && !error.is_instance_of::<PyFileExistsError>(py)
+            && !error.is_instance_of::<PyIsADirectoryError>(py)
+            && !error.is_instance_of::<PyNotADirectoryError>(py)
+    })
 }
 
-/// Parse file mode string to determine open options.
+/// Run an operation under its hooks and the options of its context.
+///
+/// Returns the result of the last attempt and the bytes it transferred.
+async fn run_with_options<F, T, M>(
+    op: &OpContext,
+    options: &OpOptions,
+    retryable: bool,
+    mut make_future: M,
+) -> (PyResult<T>, usize)
+where
+    F: std::future::Future<Output = PyResult<T>>,
+    M: FnMut() -> F,
+{
+    let job = options.job.clone().unwrap_or_else(JobShare::unnamed);
+    let attempts = async {
+        let _active = options
+            .rate_limit
+            .as_ref()
+            .map(|limiter| limiter.start(&job));
+        let _admission = PriorityGuard::admit(options.priority).await;
+        let mut attempt = 0;
+        loop {
+            let (result, bytes) = run_hooked(op, make_future()).await;
+            if let Some(limiter) = &options.rate_limit {
+                limiter.throttle(&job, bytes).await;
+            }
+            match result {
+                Err(e) if retryable && attempt < options.retries && is_transient(&e) => {
+                    tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(attempt.min(5))).await;
+                    attempt += 1;
+                }
+                result => return (result, bytes),
+            }
+        }
+    };
+    let Some(timeout) = options.timeout else {
+        return attempts.await;
+    };
+    match tokio::time::timeout(timeout, attempts).await {
+        Ok(outcome) => outcome,
+        Err(_) => {
+            let path = op.paths.first().map_or("", |path| &**path);
+            let message = format!(
+                "Failed to {} {path}: timed out after {}s",
+                op.name,
+                timeout.as_secs_f64()
+            );
+            (
+                Err(PyErr::new::<pyo3::exceptions::PyTimeoutError, _>(message)),
+                0,
+            )
+        }
+    }
+}
+
+/// Set options for the operations started in the current context.
 ///
-/// Parses Python file mode strings (e.g., "r", "w+", "rb") and converts them
-/// to flags for Tokio's OpenOptions. Supports both text and binary modes.
+/// Options not given are inherited from the enclosing `rapfiles.options()`
+/// block, if any.
 ///
 /// # Arguments
 ///
-/// * `mode` - File mode string (r, r+, w, w+, a, a+, rb, rb+, wb, wb+, ab, ab+)
+/// * `timeout` - Seconds an operation (including its retries) may take
+/// * `retries` - Retries of reads failing with a transient error
+/// * `rate_limit` - Bytes per second shared by the operations of the block
+/// * `priority` - "high", "normal" or "low"
+/// * `job` - Name of the job sharing `rate_limit` with other jobs
+/// * `weight` - Share of `rate_limit` the job gets relative to other jobs
 ///
 /// # Returns
 ///
-/// Tuple of (read, write, append) boolean flags
+/// A `contextvars.Token` to pass to `reset_options_override`.
 ///
 /// # Errors
 ///
-/// Returns `PyValueError` if the mode string is invalid.
-fn parse_mode(mode: &str) -> PyResult<(bool, bool, bool)> {
-    // Returns (read, write, append)
-    match mode {
-        "r" => Ok((true, false, false)),
-        "r+" => Ok((true, true, false)),
-        "w" => Ok((false, true, false)),
-        "w+" => Ok((true, true, false)),
-        "a" => Ok((false, true, true)),
-        "a+" => Ok((true, true, true)),
-        "rb" => Ok((true, false, false)),
-        "rb+" => Ok((true, true, false)),
-        "wb" => Ok((false, true, false)),
-        "wb+" => Ok((true, true, false)),
-        "ab" => Ok((false, true, true)),
-        "ab+" => Ok((true, true, true)),
-        _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-            "Invalid mode: {mode}. Must be one of: r, r+, w, w+, a, a+, rb, rb+, wb, wb+, ab, ab+"
-        ))),
+/// Returns `PyValueError` if `timeout`, `rate_limit` or `weight` is not a
+/// positive number, `retries` is negative, `priority` is unknown, or
+/// `weight` is given outside a named job.
+#[pyfunction]
+#[pyo3(signature = (timeout = None, retries = None, rate_limit = None, priority = None, job = None, weight = None))]
+fn override_options<'py>(
+    py: Python<'py>,
+    timeout: Option<f64>,
+    retries: Option<i64>,
+    rate_limit: Option<f64>,
+    priority: Option<&str>,
+    job: Option<&str>,
+    weight: Option<f64>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let invalid = |message: String| PyErr::new::<pyo3::exceptions::PyValueError, _>(message);
+    let mut options = current_options(py)?;
+    if let Some(timeout) = timeout {
+        if !(timeout.is_finite() && timeout > 0.0) {
+            return Err(invalid(format!(
+                "Invalid timeout: {timeout}. Must be a positive number of seconds"
+            )));
+        }
+        options.timeout = Some(std::time::Duration::from_secs_f64(timeout));
+    }
+    if let Some(retries) = retries {
+        options.retries = u32::try_from(retries)
+            .map_err(|_| invalid(format!("Invalid retries: {retries}. Must be at least 0")))?;
+    }
+    if let Some(rate_limit) = rate_limit {
+        if !(rate_limit.is_finite() && rate_limit > 0.0) {
+            return Err(invalid(format!(
+                "Invalid rate_limit: {rate_limit}. Must be a positive number of bytes per second"
+            )));
+        }
+        options.rate_limit = Some(Arc::new(RateLimiter::new(rate_limit)));
+    }
+    if let Some(priority) = priority {
+        options.priority = Priority::parse(priority)?;
+    }
+    if let Some(job) = job {
+        options.job = Some(JobShare {
+            name: Arc::from(job),
+            weight: 1.0,
+        });
+    }
+    if let Some(weight) = weight {
+        if !(weight.is_finite() && weight > 0.0) {
+            return Err(invalid(format!(
+                "Invalid weight: {weight}. Must be a positive number"
+            )));
+        }
+        let Some(job) = options.job.as_mut() else {
+            return Err(invalid(format!(
+                "Invalid weight: {weight}. Only named jobs can be weighted"
+            )));
+        };
+        job.weight = weight;
     }
+    options_override_var(py)?.call_method1("set", (Py::new(py, options)?,))
 }
 
-/// Async file handle for true async I/O operations.
+/// Undo an `override_options` call.
+#[pyfunction]
+fn reset_options_override(py: Python<'_>, token: &Bound<'_, PyAny>) -> PyResult<()> {
+    options_override_var(py)?.call_method1("reset", (token,))?;
+    Ok(())
+}
+
+/// Return the options of the current context.
 ///
-/// Provides file handle operations with true async I/O backed by Tokio.
-/// All operations execute outside the Python GIL, ensuring event loops
-/// never stall. Supports both text and binary modes, and can be used
-/// as an async context manager.
+/// # Returns
 ///
-/// # Example
+/// A dict with `timeout` (seconds or `None`), `retries`, `rate_limit`
+/// (bytes per second or `None`), `priority`, `job` (name or `None`) and
+/// `weight`.
+#[pyfunction]
+fn get_options(py: Python<'_>) -> PyResult<Bound<'_, pyo3::types::PyDict>> {
+    let options = current_options(py)?;
+    let dict = pyo3::types::PyDict::new(py);
+    dict.set_item("timeout", options.timeout.map(|t| t.as_secs_f64()))?;
+    dict.set_item("retries", options.retries)?;
+    dict.set_item(
+        "rate_limit",
+        options.rate_limit.map(|limiter| limiter.bytes_per_second),
+    )?;
+    dict.set_item("priority", options.priority.name())?;
+    let job = options.job.unwrap_or_else(JobShare::unnamed);
+    dict.set_item("job", (!job.name.is_empty()).then_some(&*job.name))?;
+    dict.set_item("weight", job.weight)?;
+    Ok(dict)
+}
+
+// Read-only mode
+
+pyo3::create_exception!(
+    _rapfiles,
+    ReadOnlyError,
+    pyo3::exceptions::PyPermissionError,
+    "Raised when read-only mode blocks an operation that would modify the filesystem."
+);
+
+/// Roots that stay writable while read-only mode is enabled, `None` when disabled.
+static READ_ONLY: std::sync::Mutex<Option<Vec<std::path::PathBuf>>> = std::sync::Mutex::new(None);
+static READ_ONLY_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Check that read-only mode allows `op_name` to modify `path`.
 ///
-/// ```python
-/// async with rapfiles.open("file.txt", "r") as f:
-///     content = await f.read()
-/// ```
-#[pyclass]
-struct AsyncFile {
-    file: Arc<Mutex<File>>,
-    path: String,
-    mode: String,
+/// # Errors
+///
+/// Returns `ReadOnlyError` if read-only mode is enabled and `path` is not
+/// below one of the writable roots.
+fn check_writable(op_name: &str, path: &str) -> PyResult<()> {
+    if is_writable(path) {
+        return Ok(());
+    }
+    Err(ReadOnlyError::new_err(format!(
+        "Failed to {op_name} {path}: rapfiles is in read-only mode"
+    )))
 }
 
-#[pymethods]
-impl AsyncFile {
-    /// Default constructor - use open_file() or rapfiles.open() instead.
-    #[new]
-    fn new() -> PyResult<Self> {
-        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-            "AsyncFile cannot be instantiated directly. Use rapfiles.open() or open_file() instead."
-        ))
+/// Whether read-only mode allows modifying `path`; usable without the GIL.
+fn is_writable(path: &str) -> bool {
+    if !READ_ONLY_ACTIVE.load(Ordering::Relaxed) {
+        return true;
+    }
+    let roots = READ_ONLY.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(roots) = roots.as_ref() else {
+        return true;
+    };
+    // Symlinks are followed, so a link inside a root can't lead out of it
+    real_path(path.as_ref(), false)
+        .is_ok_and(|path| roots.iter().any(|root| path.starts_with(root)))
+}
+
+/// Enable or disable read-only mode.
+///
+/// While enabled, operations that would create, modify or remove anything
+/// outside `writable_roots` raise `ReadOnlyError` before touching the
+/// filesystem, as does opening such a file for writing.
+///
+/// # Arguments
+///
+/// * `enabled` - Whether to block mutating operations
+/// * `writable_roots` - Directories that stay writable; relative paths are made
+///   absolute and symlinks are resolved, as they are for checked paths
+///
+/// # Errors
+///
+/// Returns `PyValueError` if a root is invalid.
+#[pyfunction]
+#[pyo3(signature = (enabled = true, writable_roots = None))]
+fn set_read_only(enabled: bool, writable_roots: Option<Vec<String>>) -> PyResult<()> {
+    let roots = writable_roots
+        .unwrap_or_default()
+        .iter()
+        .map(|root| {
+            validate_path(root)?;
+            real_path(root.as_ref(), false).map_err(|e| map_io_error(e, root, "resolve"))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let mut read_only = READ_ONLY.lock().unwrap_or_else(|e| e.into_inner());
+    *read_only = enabled.then_some(roots);
+    READ_ONLY_ACTIVE.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Return whether read-only mode is enabled.
+#[pyfunction]
+fn is_read_only() -> bool {
+    READ_ONLY_ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Return the roots that stay writable in read-only mode (empty when disabled).
+#[pyfunction]
+fn writable_roots() -> Vec<String> {
+    READ_ONLY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .flatten()
+        .map(|root| root.to_string_lossy().into_owned())
+        .collect()
+}
+
+// Configuration
+//
+// `configure()` and the `RAPFILES_*` environment variables set the global
+// tuning knobs in one place. They store into the same statics as the
+// individual setters (`set_backend`, `set_small_file_threshold`, ...).
+
+/// Names accepted by `configure()`, each also read from `RAPFILES_<NAME>`.
+const CONFIG_KEYS: [&str; 6] = [
+    "backend",
+    "worker_threads",
+    "max_blocking_threads",
+    "small_file_threshold",
+    "capture_open_stacks",
+    "concurrency",
+];
+
+/// Global concurrency limit of batch and tree operations, 0 for the built-in limits.
+static DEFAULT_CONCURRENCY: AtomicUsize = AtomicUsize::new(0);
+
+fn default_concurrency() -> Option<usize> {
+    match DEFAULT_CONCURRENCY.load(Ordering::Relaxed) {
+        0 => None,
+        limit => Some(limit),
     }
+}
 
-    /// Read from file.
-    ///
-    /// Reads data from the file. In binary mode, returns bytes. In text mode,
-    /// returns bytes that are decoded to strings by the Python wrapper.
-    ///
-    /// # Arguments
-    ///
-    /// * `size` - Number of bytes to read. If -1 (default), reads the entire file.
-    ///
-    /// # Returns
-    ///
-    /// A coroutine that yields bytes (or str in text mode via wrapper).
-    ///
-    /// # Errors
-    ///
-    /// Returns `PyIOError` if the file cannot be read.
-    #[pyo3(signature = (size = -1))]
-    fn read<'a>(&self, py: Python<'a>, size: i64) -> PyResult<Bound<'a, PyAny>> {
-        let file = Arc::clone(&self.file);
-        let path = self.path.clone();
-        let _mode = self.mode.clone();
+/// `pyo3_async_runtimes` runtime spawning on `rapfiles_core::runtime()`.
+///
+/// `pyo3_async_runtimes::tokio` keeps the first runtime it is given for good,
+/// which in a child process forked after an operation is the parent's, whose
+/// threads do not exist there. This adapter looks the runtime up for every
+/// coroutine, so the child gets a runtime of its own. The runtime itself is
+/// built by the first operation, so `configure()` can still size the thread
+/// pools after import.
+struct SharedRuntime;
 
-        let future = async move {
-            let mut file_guard = file.lock().await;
+tokio::task_local! {
+    static TASK_LOCALS: std::cell::OnceCell<pyo3_async_runtimes::TaskLocals>;
+}
 
-            let buffer = if size < 0 {
-                // Read all
-                let mut buffer = Vec::new();
-                file_guard.read_to_end(&mut buffer).await.map_err(|e| {
-                    PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                        "Failed to read file {path}: {e}"
-                    ))
-                })?;
-                buffer
-            } else {
-                let mut buffer = vec![0u8; size as usize];
-                let n = file_guard.read(&mut buffer).await.map_err(|e| {
-                    PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                        "Failed to read file {path}: {e}"
-                    ))
-                })?;
-                buffer.truncate(n);
-                buffer
-            };
+impl pyo3_async_runtimes::generic::Runtime for SharedRuntime {
+    type JoinError = tokio::task::JoinError;
+    type JoinHandle = tokio::task::JoinHandle<()>;
 
-            // Return bytes - Python wrapper will decode for text mode
-            Ok(buffer)
-        };
+    fn spawn<F>(future: F) -> Self::JoinHandle
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        runtime().spawn(future)
+    }
 
-        future_into_py(py, future)
+    fn spawn_blocking<F>(f: F) -> Self::JoinHandle
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        runtime().spawn_blocking(f)
     }
+}
 
-    /// Write to file.
-    ///
-    /// Writes data to the file. Accepts both strings and bytes.
-    ///
-    /// # Arguments
+impl pyo3_async_runtimes::generic::ContextExt for SharedRuntime {
+    fn scope<F, R>(
+        locals: pyo3_async_runtimes::TaskLocals,
+        future: F,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = R> + Send>>
+    where
+        F: std::future::Future<Output = R> + Send + 'static,
+    {
+        let cell = std::cell::OnceCell::new();
+        let _ = cell.set(locals);
+        Box::pin(TASK_LOCALS.scope(cell, future))
+    }
+
+    fn get_task_locals() -> Option<pyo3_async_runtimes::TaskLocals> {
+        TASK_LOCALS
+            .try_with(|cell| cell.get().cloned())
+            .unwrap_or_default()
+    }
+}
+
+/// Convert a future into a Python awaitable driven by the shared runtime.
+fn future_into_py<F, T>(py: Python<'_>, future: F) -> PyResult<Bound<'_, PyAny>>
+where
+    F: std::future::Future<Output = PyResult<T>> + Send + 'static,
+    T: for<'py> IntoPyObject<'py> + Send + 'static,
+{
+    pyo3_async_runtimes::generic::future_into_py::<SharedRuntime, _, T>(py, future)
+}
+
+/// Snapshot of the global configuration.
+#[derive(Clone, Copy, PartialEq)]
+struct Config {
+    backend: Backend,
+    worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+    small_file_threshold: u64,
+    capture_open_stacks: bool,
+    concurrency: Option<usize>,
+}
+
+impl Config {
+    fn current() -> Self {
+        let runtime = rapfiles_core::runtime_config();
+        Config {
+            backend: rapfiles_core::default_backend(),
+            worker_threads: runtime.worker_threads,
+            max_blocking_threads: runtime.max_blocking_threads,
+            small_file_threshold: SMALL_FILE_THRESHOLD.load(Ordering::Relaxed),
+            capture_open_stacks: CAPTURE_OPEN_STACKS.load(Ordering::Relaxed),
+            concurrency: default_concurrency(),
+        }
+    }
+
+    /// Set `key` from a Python value.
     ///
-    /// * `data` - Data to write (str or bytes)
+    /// # Errors
     ///
-    /// # Returns
+    /// Returns `PyTypeError` for an unknown key or a value of the wrong type,
+    /// and `PyValueError` for an out-of-range value.
+    fn set(&mut self, key: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        match key {
+            "backend" => self.backend = parse_backend(&value.extract::<String>()?)?,
+            "worker_threads" => self.worker_threads = positive(key, value.extract()?)?,
+            "max_blocking_threads" => self.max_blocking_threads = positive(key, value.extract()?)?,
+            "small_file_threshold" => {
+                let size: i64 = value.extract()?;
+                self.small_file_threshold = u64::try_from(size).map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid small_file_threshold: {size}. Must be at least 0"
+                    ))
+                })?;
+            }
+            "capture_open_stacks" => self.capture_open_stacks = value.extract()?,
+            "concurrency" => self.concurrency = positive(key, value.extract()?)?,
+            _ => {
+                return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                    "Unknown configuration option: {key}. Expected one of: {}",
+                    CONFIG_KEYS.join(", ")
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Set `key` from the text of its environment variable.
     ///
-    /// A coroutine that yields the number of bytes written.
+    /// Counts accept "none" (or an empty value) for the default; flags accept
+    /// 1/true/yes/on and 0/false/no/off.
     ///
     /// # Errors
     ///
-    /// Returns `PyTypeError` if data is not str or bytes,
-    /// or `PyIOError` if the file cannot be written.
-    fn write<'a>(&self, py: Python<'a>, data: &Bound<'a, PyAny>) -> PyResult<Bound<'a, PyAny>> {
-        let file = Arc::clone(&self.file);
-        let path = self.path.clone();
-
-        // Convert Python bytes/string to Vec<u8>
-        let bytes: Vec<u8> = if let Ok(py_bytes) = data.cast::<PyBytes>() {
-            py_bytes.as_bytes().to_vec()
-        } else if let Ok(py_str) = data.cast::<PyString>() {
-            py_str.to_string().into_bytes()
-        } else {
-            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
-                "write() argument must be bytes or str",
-            ));
+    /// Returns `PyValueError` if the text cannot be parsed.
+    fn set_from_env(&mut self, key: &str, text: &str) -> PyResult<()> {
+        let text = text.trim();
+        let invalid =
+            || PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid {key}: {text:?}"));
+        let count = || -> PyResult<Option<usize>> {
+            if text.is_empty() || text.eq_ignore_ascii_case("none") {
+                return Ok(None);
+            }
+            positive(key, Some(text.parse().map_err(|_| invalid())?))
         };
+        match key {
+            "backend" => self.backend = parse_backend(&text.to_ascii_lowercase())?,
+            "worker_threads" => self.worker_threads = count()?,
+            "max_blocking_threads" => self.max_blocking_threads = count()?,
+            "small_file_threshold" => {
+                self.small_file_threshold = text.parse().map_err(|_| invalid())?
+            }
+            "capture_open_stacks" => {
+                self.capture_open_stacks = match text.to_ascii_lowercase().as_str() {
+                    "1" | "true" | "yes" | "on" => true,
+                    "0" | "false" | "no" | "off" => false,
+                    _ => return Err(invalid()),
+                }
+            }
+            "concurrency" => self.concurrency = count()?,
+            _ => unreachable!("not a configuration key: {key}"),
+        }
+        Ok(())
+    }
 
-        let future = async move {
-            let mut file_guard = file.lock().await;
-            file_guard.write_all(&bytes).await.map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                    "Failed to write file {path}: {e}"
-                ))
-            })?;
-            Ok(bytes.len() as i64)
+    /// Make this configuration the global one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyRuntimeError`, changing nothing, if the thread pool sizes
+    /// differ from those of an already started runtime.
+    fn apply(self) -> PyResult<()> {
+        let runtime = rapfiles_core::RuntimeConfig {
+            worker_threads: self.worker_threads,
+            max_blocking_threads: self.max_blocking_threads,
         };
+        if runtime != rapfiles_core::runtime_config() && !rapfiles_core::configure_runtime(runtime)
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "worker_threads and max_blocking_threads cannot be changed after \
+                 the first operation started the runtime",
+            ));
+        }
+        rapfiles_core::set_default_backend(self.backend);
+        SMALL_FILE_THRESHOLD.store(self.small_file_threshold, Ordering::Relaxed);
+        CAPTURE_OPEN_STACKS.store(self.capture_open_stacks, Ordering::Relaxed);
+        DEFAULT_CONCURRENCY.store(self.concurrency.unwrap_or(0), Ordering::Relaxed);
+        Ok(())
+    }
 
-        future_into_py(py, future)
+    fn into_dict(self, py: Python<'_>) -> PyResult<Bound<'_, pyo3::types::PyDict>> {
+        let config = pyo3::types::PyDict::new(py);
+        config.set_item("backend", self.backend.name())?;
+        config.set_item("worker_threads", self.worker_threads)?;
+        config.set_item("max_blocking_threads", self.max_blocking_threads)?;
+        config.set_item("small_file_threshold", self.small_file_threshold)?;
+        config.set_item("capture_open_stacks", self.capture_open_stacks)?;
+        config.set_item("concurrency", self.concurrency)?;
+        Ok(config)
     }
+}
 
-    /// Read a line from file.
-    ///
-    /// Reads a single line from the file, up to and including the newline character.
-    ///
-    /// # Arguments
-    ///
-    /// * `size` - Maximum number of bytes to read. If -1 (default), reads until newline.
-    ///
-    /// # Returns
-    ///
-    /// A coroutine that yields bytes (or str in text mode via wrapper).
-    ///
-    /// # Errors
-    ///
-    /// Returns `PyIOError` if the file cannot be read.
-    #[pyo3(signature = (size = -1))]
-    fn readline<'a>(&self, py: Python<'a>, size: i64) -> PyResult<Bound<'a, PyAny>> {
-        let file = Arc::clone(&self.file);
-        let path = self.path.clone();
-        let _mode = self.mode.clone();
+/// Check that an optional count is at least 1.
+fn positive(key: &str, value: Option<i64>) -> PyResult<Option<usize>> {
+    match value {
+        Some(n) if n < 1 => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid {key}: {n}. Must be at least 1"
+        ))),
+        n => Ok(n.map(|n| n as usize)),
+    }
+}
 
-        let future = async move {
-            let mut file_guard = file.lock().await;
-            let mut buffer = Vec::new();
-            let mut single_byte = [0u8; 1];
+/// Apply the `RAPFILES_*` environment variables to the global configuration.
+///
+/// Invalid values are reported as a `RuntimeWarning` and ignored, so a bad
+/// variable never prevents importing rapfiles.
+fn configure_from_env(py: Python<'_>) -> PyResult<()> {
+    let mut config = Config::current();
+    for key in CONFIG_KEYS {
+        let var = format!("RAPFILES_{}", key.to_ascii_uppercase());
+        let Some(text) = std::env::var_os(&var) else {
+            continue;
+        };
+        let text = text.to_string_lossy();
+        if let Err(e) = config.set_from_env(key, &text) {
+            let message = format!("Ignoring {var}: {}", e.value(py));
+            PyErr::warn(
+                py,
+                &py.get_type::<pyo3::exceptions::PyRuntimeWarning>(),
+                &std::ffi::CString::new(message)?,
+                1,
+            )?;
+        }
+    }
+    if let Err(e) = config.apply() {
+        let message = format!("Ignoring RAPFILES_* thread settings: {}", e.value(py));
+        PyErr::warn(
+            py,
+            &py.get_type::<pyo3::exceptions::PyRuntimeWarning>(),
+            &std::ffi::CString::new(message)?,
+            1,
+        )?;
+    }
+    Ok(())
+}
 
-            loop {
-                let n = file_guard
-                    .read(&mut single_byte)
-                    .await
-                    .map_err(|e| map_io_error(e, &path, "read file"))?;
+/// Set global tuning options.
+///
+/// Options not given keep their current value. All options are validated
+/// before any is applied.
+///
+/// # Arguments
+///
+/// * `options` - Any of `backend`, `worker_threads`, `max_blocking_threads`,
+///   `small_file_threshold`, `capture_open_stacks` and `concurrency`
+///
+/// # Errors
+///
+/// Returns `PyTypeError` for an unknown option or a value of the wrong type,
+/// `PyValueError` for an invalid value, and `PyRuntimeError` when changing
+/// the thread pool sizes after the runtime has started.
+#[pyfunction]
+#[pyo3(signature = (**options))]
+fn configure(options: Option<&Bound<'_, pyo3::types::PyDict>>) -> PyResult<()> {
+    let mut config = Config::current();
+    if let Some(options) = options {
+        for (key, value) in options.iter() {
+            config.set(&key.extract::<String>()?, &value)?;
+        }
+    }
+    config.apply()
+}
 
-                if n == 0 {
-                    break; // EOF
-                }
+/// Return the global configuration as a dict of the `configure()` options.
+#[pyfunction]
+fn get_config(py: Python<'_>) -> PyResult<Bound<'_, pyo3::types::PyDict>> {
+    Config::current().into_dict(py)
+}
 
-                buffer.push(single_byte[0]);
+/// Start the runtime now rather than on the first operation.
+///
+/// Importing rapfiles starts no threads; the runtime and its thread pools
+/// are built by the first operation. This builds them eagerly, with the
+/// configured thread pool sizes, and runs an empty blocking job so the
+/// first blocking thread exists too. Calling it again does nothing.
+///
+/// # Errors
+///
+/// Returns `PyRuntimeError` if the warm-up job fails.
+#[pyfunction]
+fn preload(py: Python<'_>) -> PyResult<()> {
+    py.detach(|| runtime().block_on(run_blocking(|| Ok(()))))
+        .map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "Failed to start the runtime: {e}"
+            ))
+        })
+}
 
-                if single_byte[0] == b'\n' {
-                    break; // End of line
-                }
+/// Prepare rapfiles for use in a child process created with `fork()`.
+///
+/// The threads of the parent's runtime do not exist in the child. rapfiles
+/// notices by itself: the first operation in the child starts a new
+/// runtime, the cleanup registry starts empty, and handles opened by the
+/// parent raise instead of hanging. This does the first two right away,
+/// e.g. from a pre-fork server's post-fork hook. In a process that was not
+/// forked it only starts the runtime.
+///
+/// # Errors
+///
+/// Returns `PyRuntimeError` if the new runtime cannot be started.
+#[pyfunction]
+fn reinit_after_fork(py: Python<'_>) -> PyResult<()> {
+    drop(cleanup_registry());
+    preload(py)
+}
 
-                if size > 0 && buffer.len() >= size as usize {
-                    break; // Reached size limit
-                }
-            }
+/// Python bindings for rapfiles - True async filesystem I/O.
+///
+/// This module provides true async filesystem I/O operations backed by Rust and Tokio.
+/// All I/O operations execute outside the Python GIL, ensuring event loops never stall.
+/// Compatible with `aiofiles` API for drop-in replacement scenarios.
+///
+/// # Features
+///
+/// - File operations: read, write, append (text and binary)
+/// - File handles: AsyncFile class with async context manager support
+/// - Directory operations: create, remove, list, walk
+/// - File metadata: stat, size, timestamps
+/// - Path operations: ospath module compatibility
+#[pymodule]
+fn _rapfiles(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    configure_from_env(py)?;
 
-            // For now, always return bytes - Python will handle text decoding
-            Ok(buffer)
-        };
+    // File operations
+    m.add_function(wrap_pyfunction!(read_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(write_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(read_file_bytes_async, m)?)?;
+    m.add_function(wrap_pyfunction!(read_with_stat_async, m)?)?;
+    m.add_function(wrap_pyfunction!(read_exact_async, m)?)?;
+    m.add_function(wrap_pyfunction!(write_file_bytes_async, m)?)?;
+    m.add_function(wrap_pyfunction!(append_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(open_file, m)?)?;
+    m.add_class::<AsyncFile>()?;
+    m.add_function(wrap_pyfunction!(open_files, m)?)?;
+    m.add_function(wrap_pyfunction!(send_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(recv_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(capture_open_stacks, m)?)?;
+    m.add_function(wrap_pyfunction!(set_small_file_threshold, m)?)?;
+    m.add_function(wrap_pyfunction!(get_small_file_threshold, m)?)?;
+    m.add_class::<OpenFileInfo>()?;
 
-        future_into_py(py, future)
-    }
+    // Directory operations
+    m.add_function(wrap_pyfunction!(create_dir_async, m)?)?;
+    m.add_function(wrap_pyfunction!(create_dir_all_async, m)?)?;
+    m.add_function(wrap_pyfunction!(remove_dir_async, m)?)?;
+    m.add_function(wrap_pyfunction!(remove_dir_all_async, m)?)?;
+    m.add_function(wrap_pyfunction!(list_dir_async, m)?)?;
+    m.add_function(wrap_pyfunction!(list_dir_stat_async, m)?)?;
+    m.add_function(wrap_pyfunction!(exists_async, m)?)?;
+    m.add_function(wrap_pyfunction!(is_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(is_dir_async, m)?)?;
 
-    /// Read all lines from file.
-    ///
-    /// Reads all lines from the file and returns them as a list.
-    ///
-    /// # Arguments
-    ///
-    /// * `hint` - Approximate number of lines to read. If -1 (default), reads all lines.
-    ///
-    /// # Returns
-    ///
-    /// A coroutine that yields a list of bytes (or list of str in text mode via wrapper).
-    ///
-    /// # Errors
-    ///
-    /// Returns `PyIOError` if the file cannot be read.
-    #[pyo3(signature = (hint = -1))]
-    fn readlines<'a>(&self, py: Python<'a>, hint: i64) -> PyResult<Bound<'a, PyAny>> {
-        let file = Arc::clone(&self.file);
-        let path = self.path.clone();
-        let _mode = self.mode.clone();
+    // Metadata operations
+    m.add_function(wrap_pyfunction!(stat_async, m)?)?;
+    m.add_function(wrap_pyfunction!(metadata_async, m)?)?;
+    m.add_class::<FileMetadata>()?;
 
-        let future = async move {
-            let mut file_guard = file.lock().await;
-            let mut lines = Vec::new();
-            let mut current_line = Vec::new();
-            let mut single_byte = [0u8; 1];
+    // Directory traversal
+    m.add_function(wrap_pyfunction!(walk_dir_async, m)?)?;
 
-            loop {
-                let n = file_guard
-                    .read(&mut single_byte)
-                    .await
-                    .map_err(|e| map_io_error(e, &path, "read file"))?;
+    // Permission auditing
+    m.add_function(wrap_pyfunction!(audit_permissions_async, m)?)?;
+    m.add_class::<PermissionFinding>()?;
 
-                if n == 0 {
-                    if !current_line.is_empty() {
-                        lines.push(current_line);
-                    }
-                    break; // EOF
-                }
+    // Portability checks
+    m.add_function(wrap_pyfunction!(check_portability_async, m)?)?;
+    m.add_class::<PortabilityIssue>()?;
 
-                current_line.push(single_byte[0]);
+    // Directory comparison
+    m.add_function(wrap_pyfunction!(dircmp_async, m)?)?;
+    m.add_class::<DirDiff>()?;
+    m.add_class::<DirCompare>()?;
 
-                if single_byte[0] == b'\n' {
-                    lines.push(current_line);
-                    current_line = Vec::new();
+    // File manipulation operations
+    m.add_function(wrap_pyfunction!(copy_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(copytree_async, m)?)?;
+    m.add_function(wrap_pyfunction!(move_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(move_async, m)?)?;
+    m.add_function(wrap_pyfunction!(rename_async, m)?)?;
+    m.add_function(wrap_pyfunction!(replace_async, m)?)?;
+    m.add_function(wrap_pyfunction!(remove_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(unlink_async, m)?)?;
+    m.add_function(wrap_pyfunction!(hard_link_async, m)?)?;
+    m.add_function(wrap_pyfunction!(link_async, m)?)?;
+    m.add_function(wrap_pyfunction!(symlink_async, m)?)?;
+    m.add_function(wrap_pyfunction!(canonicalize_async, m)?)?;
+    m.add_function(wrap_pyfunction!(readlink_async, m)?)?;
+    m.add_function(wrap_pyfunction!(realpath_async, m)?)?;
+    m.add_function(wrap_pyfunction!(touch_async, m)?)?;
+    m.add_function(wrap_pyfunction!(truncate_async, m)?)?;
+    m.add_function(wrap_pyfunction!(chmod_async, m)?)?;
+    m.add_function(wrap_pyfunction!(chown_async, m)?)?;
 
-                    if hint > 0 && lines.len() >= hint as usize {
-                        break;
-                    }
-                }
-            }
+    // Deduplication
+    m.add_function(wrap_pyfunction!(dedupe_tree_async, m)?)?;
+    m.add_class::<DedupeReport>()?;
 
-            // For now, always return list of bytes - Python will handle text decoding
-            Ok(lines)
-        };
+    // Atomic operations
+    m.add_function(wrap_pyfunction!(atomic_write_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(atomic_write_file_bytes_async, m)?)?;
+    m.add_function(wrap_pyfunction!(write_if_unchanged_async, m)?)?;
+    m.add("FileChangedError", m.py().get_type::<FileChangedError>())?;
+    m.add_function(wrap_pyfunction!(atomic_move_file_async, m)?)?;
 
-        future_into_py(py, future)
-    }
+    // Config file parsing
+    m.add_function(wrap_pyfunction!(read_toml_async, m)?)?;
+    m.add_function(wrap_pyfunction!(toml_loads, m)?)?;
+    m.add_function(wrap_pyfunction!(toml_dumps, m)?)?;
+    m.add_function(wrap_pyfunction!(read_yaml_async, m)?)?;
+    m.add_function(wrap_pyfunction!(save_config_async, m)?)?;
 
-    /// Seek to a position in the file.
-    ///
-    /// Changes the file position to the given offset.
-    ///
-    /// # Arguments
-    ///
-    /// * `offset` - Byte offset
-    /// * `whence` - Reference point: 0=start (SEEK_SET), 1=current (SEEK_CUR), 2=end (SEEK_END)
-    ///
-    /// # Returns
-    ///
-    /// A coroutine that yields the new absolute position.
-    ///
-    /// # Errors
-    ///
-    /// Returns `PyValueError` if whence is invalid, or `PyIOError` if seek fails.
-    #[pyo3(signature = (offset, whence = 0))]
-    fn seek<'a>(&self, py: Python<'a>, offset: i64, whence: i32) -> PyResult<Bound<'a, PyAny>> {
-        let file = Arc::clone(&self.file);
-        let path = self.path.clone();
+    // File locking
+    m.add_function(wrap_pyfunction!(lock_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(lease_file_async, m)?)?;
+    m.add_class::<FileLock>()?;
+    m.add_class::<FileLease>()?;
 
-        let future = async move {
-            let mut file_guard = file.lock().await;
+    // Log reading
+    m.add_function(wrap_pyfunction!(open_log_async, m)?)?;
+    m.add_class::<LogReader>()?;
 
-            let pos = match whence {
-                0 => std::io::SeekFrom::Start(offset as u64),
-                1 => std::io::SeekFrom::Current(offset),
-                2 => std::io::SeekFrom::End(offset),
-                _ => {
-                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                        format!("Invalid whence value: {whence}. Must be 0 (SEEK_SET), 1 (SEEK_CUR), or 2 (SEEK_END)"),
-                    ));
-                }
-            };
+    // Directory watching
+    m.add_function(wrap_pyfunction!(watch_async, m)?)?;
+    m.add_class::<DirWatcher>()?;
+    m.add_class::<WatchEvent>()?;
 
-            let new_pos = file_guard.seek(pos).await.map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                    "Failed to seek in file {path}: {e}"
-                ))
-            })?;
+    // Cleanup registry
+    m.add_function(wrap_pyfunction!(register_cleanup, m)?)?;
+    m.add_function(wrap_pyfunction!(unregister_cleanup, m)?)?;
+    m.add_function(wrap_pyfunction!(registered_cleanup, m)?)?;
+    m.add_function(wrap_pyfunction!(run_cleanup, m)?)?;
+    m.add_function(wrap_pyfunction!(run_cleanup_async, m)?)?;
 
-            Ok(new_pos as i64)
-        };
+    // Scratch space
+    m.add_function(wrap_pyfunction!(best_scratch_dir_async, m)?)?;
 
-        future_into_py(py, future)
-    }
+    // Runtime introspection
+    m.add_function(wrap_pyfunction!(runtime_info, m)?)?;
 
-    /// Get current position in file.
-    ///
-    /// Returns the current file position (byte offset from start).
-    ///
-    /// # Returns
-    ///
-    /// A coroutine that yields the current position as an integer.
-    ///
-    /// # Errors
-    ///
-    /// Returns `PyIOError` if the position cannot be determined.
-    fn tell<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
-        let file = Arc::clone(&self.file);
-        let path = self.path.clone();
+    // Positional I/O
+    m.add_function(wrap_pyfunction!(read_ranges_async, m)?)?;
+    m.add_function(wrap_pyfunction!(write_segments_async, m)?)?;
+    m.add_function(wrap_pyfunction!(list_extents_async, m)?)?;
+    m.add_function(wrap_pyfunction!(create_filled_async, m)?)?;
 
-        let future = async move {
-            let mut file_guard = file.lock().await;
-            let pos = file_guard.stream_position().await.map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                    "Failed to get position in file {path}: {e}"
-                ))
-            })?;
-            Ok(pos as i64)
-        };
+    // Batch operations
+    m.add_function(wrap_pyfunction!(read_files_async, m)?)?;
+    m.add_function(wrap_pyfunction!(write_files_async, m)?)?;
+    m.add_function(wrap_pyfunction!(copy_files_async, m)?)?;
+    m.add_function(wrap_pyfunction!(rename_many_async, m)?)?;
+    m.add_function(wrap_pyfunction!(rename_pattern_async, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_tree_async, m)?)?;
+
+    // Transactions
+    m.add_class::<Transaction>()?;
+    m.add_function(wrap_pyfunction!(transaction, m)?)?;
+    m.add_function(wrap_pyfunction!(recover_transaction_async, m)?)?;
+
+    // Disk cache
+    m.add_class::<Cache>()?;
+
+    // I/O backends
+    m.add_function(wrap_pyfunction!(set_backend, m)?)?;
+    m.add_function(wrap_pyfunction!(get_backend, m)?)?;
+    m.add_function(wrap_pyfunction!(available_backends, m)?)?;
+    m.add_function(wrap_pyfunction!(override_backend, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_backend_override, m)?)?;
+    m.add_function(wrap_pyfunction!(add_route, m)?)?;
+    m.add_function(wrap_pyfunction!(remove_route, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_routes, m)?)?;
+    m.add_function(wrap_pyfunction!(routes, m)?)?;
+    m.add_class::<Route>()?;
+
+    // Operation options
+    m.add_function(wrap_pyfunction!(override_options, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_options_override, m)?)?;
+    m.add_function(wrap_pyfunction!(get_options, m)?)?;
+
+    // Read-only mode
+    m.add_function(wrap_pyfunction!(set_read_only, m)?)?;
+    m.add_function(wrap_pyfunction!(is_read_only, m)?)?;
+    m.add_function(wrap_pyfunction!(writable_roots, m)?)?;
+    m.add("ReadOnlyError", m.py().get_type::<ReadOnlyError>())?;
+
+    // Configuration
+    m.add_function(wrap_pyfunction!(configure, m)?)?;
+    m.add_function(wrap_pyfunction!(get_config, m)?)?;
+    m.add_function(wrap_pyfunction!(preload, m)?)?;
+    m.add_function(wrap_pyfunction!(reinit_after_fork, m)?)?;
+
+    // Operation hooks
+    m.add_function(wrap_pyfunction!(inject_fault, m)?)?;
+    m.add_function(wrap_pyfunction!(remove_fault, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_faults, m)?)?;
+    m.add_function(wrap_pyfunction!(inject_latency, m)?)?;
+    m.add_function(wrap_pyfunction!(remove_latency, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_latency, m)?)?;
+    m.add_function(wrap_pyfunction!(start_recording, m)?)?;
+    m.add_function(wrap_pyfunction!(stop_recording, m)?)?;
+    m.add_class::<OpRecord>()?;
+    m.add_function(wrap_pyfunction!(start_audit, m)?)?;
+    m.add_function(wrap_pyfunction!(stop_audit, m)?)?;
 
-        future_into_py(py, future)
-    }
+    Ok(())
+}
 
-    /// Close the file.
-    ///
-    /// Closes the file handle. The file is automatically closed when the
-    /// object is dropped, but this method is provided for API compatibility
-    /// with standard file interfaces.
+/// Code points of a UTF-8 text in the layout of a Python `str` (PEP 393).
+enum TextData {
+    Ucs1(Vec<u8>),
+    Ucs2(Vec<u16>),
+    Ucs4(Vec<u32>),
+}
+
+/// UTF-8 text validated and decoded without holding the GIL.
+///
+/// Converting a Rust `String` to `str` makes CPython decode the UTF-8 again
+/// while the GIL is held, which stalls every other task for large files.
+/// `DecodedText` is decoded into the final code point width up front, so
+/// building the `str` only allocates it and copies the data, and copies of
+/// large texts run with the GIL released.
+struct DecodedText {
+    data: TextData,
+    max_char: u32,
+}
+
+/// Texts of at least this many bytes are copied into their `str` without the GIL.
+const DETACHED_COPY_BYTES: usize = 1 << 20;
+
+impl DecodedText {
+    /// Validate `bytes` as UTF-8 and decode them to the narrowest code point width.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// A coroutine that yields `None` on success.
-    fn close<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
-        // File is automatically closed when dropped, but we provide this for API compatibility
-        let future = async move {
-            // The file will be closed when the Arc is dropped
-            Ok(())
+    /// Returns `PyUnicodeDecodeError` (with the offending position, like
+    /// `bytes.decode`) if the bytes are not valid UTF-8.
+    fn from_utf8(bytes: Vec<u8>) -> PyResult<Self> {
+        let text = std::str::from_utf8(&bytes).map_err(|e| {
+            Python::attach(|py| {
+                match pyo3::exceptions::PyUnicodeDecodeError::new_utf8(py, &bytes, e) {
+                    Ok(err) => PyErr::from_value(err.into_any()),
+                    Err(err) => err,
+                }
+            })
+        })?;
+        if text.is_ascii() {
+            return Ok(DecodedText {
+                data: TextData::Ucs1(bytes),
+                max_char: 0x7F,
+            });
+        }
+        let max_char = text.chars().map(u32::from).max().unwrap_or(0);
+        let data = if max_char < 0x100 {
+            TextData::Ucs1(text.chars().map(|c| c as u8).collect())
+        } else if max_char < 0x10000 {
+            TextData::Ucs2(text.chars().map(|c| c as u16).collect())
+        } else {
+            TextData::Ucs4(text.chars().map(u32::from).collect())
         };
-        future_into_py(py, future)
+        Ok(DecodedText { data, max_char })
     }
 
-    /// Async context manager entry.
-    fn __aenter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
-        // Return self directly - Python's async context manager will handle it
-        slf
+    /// Return the number of code points and a pointer to their data.
+    fn code_points(&self) -> (usize, *const u8, usize) {
+        match &self.data {
+            TextData::Ucs1(data) => (data.len(), data.as_ptr(), data.len()),
+            TextData::Ucs2(data) => (data.len(), data.as_ptr().cast(), data.len() * 2),
+            TextData::Ucs4(data) => (data.len(), data.as_ptr().cast(), data.len() * 4),
+        }
     }
+}
 
-    /// Async context manager exit.
-    fn __aexit__(
-        &self,
-        _exc_type: Option<&Bound<'_, PyAny>>,
-        _exc_val: Option<&Bound<'_, PyAny>>,
-        _exc_tb: Option<&Bound<'_, PyAny>>,
-    ) -> PyResult<Py<PyAny>> {
-        // Flush and sync file on exit to ensure all writes are persisted
-        // Only flush/sync for write-enabled modes to avoid Windows permission errors
-        let file = Arc::clone(&self.file);
-        let path = self.path.clone();
-        let mode = self.mode.clone();
-        Python::attach(|py| {
-            let future = async move {
-                // Check if mode allows writing (w, w+, r+, a, a+ or binary equivalents)
-                let is_write_mode =
-                    mode.starts_with('w') || mode.contains('+') || mode.starts_with('a');
-
-                if is_write_mode {
-                    use tokio::io::AsyncWriteExt;
-                    let mut file_guard = file.lock().await;
-                    // Flush any buffered data
-                    file_guard.flush().await.map_err(|e| {
-                        PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                            "Failed to flush file {path}: {e}"
-                        ))
-                    })?;
-                    // Sync to ensure data is written to disk
-                    file_guard.sync_all().await.map_err(|e| {
-                        PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                            "Failed to sync file {path}: {e}"
-                        ))
-                    })?;
-                }
-                Ok(false) // Return False to not suppress exceptions
-            };
-            future_into_py(py, future).map(|bound| bound.unbind())
-        })
+impl<'py> IntoPyObject<'py> for DecodedText {
+    type Target = PyString;
+    type Output = Bound<'py, PyString>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Bound<'py, PyString>> {
+        let (len, src, byte_len) = self.code_points();
+        // SAFETY: `PyUnicode_New` returns a new compact string of `len` code
+        // points whose width matches `max_char`, i.e. the width of `data`.
+        // Nothing else references it until it is returned, so its buffer can
+        // be filled without holding the GIL.
+        unsafe {
+            let string = pyo3::ffi::PyUnicode_New(len as pyo3::ffi::Py_ssize_t, self.max_char);
+            if string.is_null() {
+                return Err(PyErr::fetch(py));
+            }
+            let dst = pyo3::ffi::PyUnicode_DATA(string).cast::<u8>();
+            if byte_len >= DETACHED_COPY_BYTES {
+                let (src, dst) = (src as usize, dst as usize);
+                py.detach(|| {
+                    std::ptr::copy_nonoverlapping(src as *const u8, dst as *mut u8, byte_len)
+                });
+            } else {
+                std::ptr::copy_nonoverlapping(src, dst, byte_len);
+            }
+            Ok(Bound::from_owned_ptr(py, string).cast_into_unchecked())
+        }
     }
 }
 
-/// File metadata structure (aiofiles.stat_result compatible).
-///
-/// Provides file metadata including size, timestamps, and type information.
-/// Compatible with `aiofiles.stat_result` for drop-in replacement scenarios.
-///
-/// # Properties
-///
-/// * `size` - File size in bytes
-/// * `is_file` - True if path is a file
-/// * `is_dir` - True if path is a directory
-/// * `modified` - Modification time as Unix timestamp (float)
-/// * `accessed` - Access time as Unix timestamp (float)
-/// * `created` - Creation time as Unix timestamp (float)
-#[pyclass]
-#[derive(Clone)]
-struct FileMetadata {
-    size: u64,
-    is_file: bool,
-    is_dir: bool,
-    modified: f64, // Unix timestamp
-    accessed: f64, // Unix timestamp
-    created: f64,  // Unix timestamp (creation time on Windows, birth time on Unix)
+/// How far a write operation persists data before it returns, chosen with
+/// its `durability` argument.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum Durability {
+    /// No waiting beyond what the operation needs anyway
+    None,
+    /// The data is handed to the OS, so it survives a crash of the process
+    #[default]
+    Flush,
+    /// The file is also synced to disk, so it survives a power loss
+    Fsync,
+    /// The parent directory is synced too, so a new or renamed entry does
+    FsyncDir,
 }
 
-#[pymethods]
-impl FileMetadata {
-    #[new]
-    fn new(
-        size: u64,
-        is_file: bool,
-        is_dir: bool,
-        modified: f64,
-        accessed: f64,
-        created: f64,
-    ) -> Self {
-        FileMetadata {
-            size,
-            is_file,
-            is_dir,
-            modified,
-            accessed,
-            created,
+impl Durability {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "none" => Ok(Durability::None),
+            "flush" => Ok(Durability::Flush),
+            "fsync" => Ok(Durability::Fsync),
+            "fsync+dir" => Ok(Durability::FsyncDir),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid durability: {name}. Must be 'none', 'flush', 'fsync' or 'fsync+dir'"
+            ))),
         }
     }
 
-    #[getter]
-    fn size(&self) -> u64 {
-        self.size
+    /// Write `contents` to a new or truncated file at `path`.
+    ///
+    /// Below `fsync` this is `write`; otherwise the file is written and
+    /// synced through the same descriptor in one blocking job.
+    async fn write_file<W>(
+        self,
+        path: &std::path::Path,
+        contents: Vec<u8>,
+        write: impl FnOnce(std::path::PathBuf, Vec<u8>) -> W,
+    ) -> std::io::Result<()>
+    where
+        W: std::future::Future<Output = std::io::Result<()>>,
+    {
+        let path = path.to_path_buf();
+        if self < Durability::Fsync {
+            return write(path, contents).await;
+        }
+        run_blocking(move || {
+            use std::io::Write;
+
+            let mut file = std::fs::File::create(&path)?;
+            file.write_all(&contents)?;
+            file.sync_all()
+        })
+        .await
     }
 
-    #[getter]
-    fn is_file(&self) -> bool {
-        self.is_file
+    /// Bring a written handle to this durability: flush it, then sync it.
+    async fn finish(self, file: &mut HandleFile) -> std::io::Result<()> {
+        if self >= Durability::Flush {
+            file.flush().await?;
+        }
+        if self >= Durability::Fsync {
+            file.sync_all().await?;
+        }
+        Ok(())
     }
 
-    #[getter]
-    fn is_dir(&self) -> bool {
-        self.is_dir
+    /// Sync the file at `path` for `fsync` and above, for operations that
+    /// wrote it without keeping a handle (copies and moves).
+    async fn sync_file(self, path: &std::path::Path) -> std::io::Result<()> {
+        if self < Durability::Fsync {
+            return Ok(());
+        }
+        let path = path.to_path_buf();
+        run_blocking(move || sync_path(&path)).await
     }
 
-    #[getter]
-    fn modified(&self) -> f64 {
-        self.modified
+    /// Sync the directory holding `path` for `fsync+dir`, so that creating
+    /// or renaming `path` survives a power loss.
+    ///
+    /// Only Unix can sync directories; elsewhere this does nothing.
+    async fn sync_parent(self, path: &std::path::Path) -> std::io::Result<()> {
+        if self < Durability::FsyncDir {
+            return Ok(());
+        }
+        let path = path.to_path_buf();
+        run_blocking(move || sync_parent_dir(&path)).await
     }
+}
 
-    #[getter]
-    fn accessed(&self) -> f64 {
-        self.accessed
+/// Sync the file or directory at `path` to disk.
+///
+/// Directories can only be synced on Unix; elsewhere they are skipped.
+fn sync_path(path: &std::path::Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    std::fs::File::open(path)?.sync_all()?;
+    #[cfg(not(unix))]
+    if !std::fs::metadata(path)?.is_dir() {
+        // FlushFileBuffers needs a handle with write access
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)?
+            .sync_all()?;
     }
+    Ok(())
+}
 
-    #[getter]
-    fn created(&self) -> f64 {
-        self.created
+/// Sync the directory holding `path`, persisting its entries (Unix only).
+fn sync_parent_dir(path: &std::path::Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => std::path::Path::new("."),
+        };
+        std::fs::File::open(dir)?.sync_all()?;
     }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
 }
 
-/// Convert SystemTime to Unix timestamp.
+/// Async file read using Tokio (GIL-independent).
 ///
-/// Converts a Rust SystemTime to a Unix timestamp (seconds since epoch as float).
-/// Used for file metadata timestamps (modified, accessed, created).
+/// Reads the entire file and returns its contents as a UTF-8 decoded string.
+/// All I/O operations execute outside the Python GIL using native Tokio,
+/// ensuring true async behavior and preventing event loop stalls. The text
+/// is validated and decoded on the blocking thread that read it (see
+/// `DecodedText`), so building the `str` barely holds the GIL.
 ///
 /// # Arguments
 ///
-/// * `time` - SystemTime to convert
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to read
 ///
 /// # Returns
 ///
-/// Unix timestamp as f64 (seconds since epoch)
-fn system_time_to_timestamp(time: SystemTime) -> f64 {
-    time.duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs_f64()
+/// A coroutine that yields the file contents as a string.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the file does not exist,
+/// `PyUnicodeDecodeError` if the file is not valid UTF-8,
+/// `PyIOError` if the file cannot be read, or `PyValueError` if the path is invalid.
+#[pyfunction]
+fn read_file_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("read_file", [path.as_str()]);
+    run_retryable_op(py, op, move || {
+        let path = path.clone();
+        async move {
+            let path_clone = path.clone();
+            let (contents, len) = run_blocking(move || {
+                let bytes = std::fs::read(&path)?;
+                let len = bytes.len();
+                Ok((DecodedText::from_utf8(bytes), len))
+            })
+            .await
+            .map_err(|e| map_io_error(e, &path_clone, "read file"))?;
+            record_transfer(len);
+            contents
+        }
+    })
 }
 
-/// Get file statistics asynchronously.
+/// Async file write using Tokio (GIL-independent).
 ///
-/// Returns file metadata including size, timestamps, and type information.
-/// All I/O operations execute outside the Python GIL using native Tokio.
+/// Writes the entire contents to a file. If the file exists, it will be overwritten.
+/// All I/O operations execute outside the Python GIL using native Tokio,
+/// ensuring true async behavior and preventing event loop stalls.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `path` - Path to the file or directory
+/// * `path` - Path to the file to write
+/// * `contents` - Content to write to the file (UTF-8 string)
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` or
+///   `"fsync+dir"`; see `Durability`
 ///
 /// # Returns
 ///
-/// A coroutine that yields a `FileMetadata` object.
+/// A coroutine that yields `None` on success.
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the path does not exist,
-/// `PyIOError` if metadata cannot be retrieved, or `PyValueError` if the path is invalid.
+/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
+/// if write permission is denied, or `PyValueError` if the path or
+/// durability is invalid.
 #[pyfunction]
-fn stat_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+#[pyo3(signature = (path, contents, durability = "flush"))]
+fn write_file_async<'py>(
+    py: Python<'py>,
+    path: String,
+    contents: String,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
     validate_path(&path)?;
+    let durability = Durability::parse(durability)?;
+    let op = OpContext::new("write_file", [path.as_str()]);
     let future = async move {
-        let path_clone = path.clone();
-        let metadata = tokio::fs::metadata(&path).await.map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to get metadata for {path_clone}: {e}"
-            ))
-        })?;
-
-        let size = metadata.len();
-        let is_file = metadata.is_file();
-        let is_dir = metadata.is_dir();
-
-        let modified = metadata
-            .modified()
-            .map(system_time_to_timestamp)
-            .unwrap_or(0.0);
-        let accessed = metadata
-            .accessed()
-            .map(system_time_to_timestamp)
-            .unwrap_or(0.0);
-
-        // Creation time (available on Windows, birth time on Unix requires platform-specific code)
-        let created = metadata
-            .created()
-            .map(system_time_to_timestamp)
-            .unwrap_or(modified); // Fallback to modified time if creation time not available
-
-        Ok(FileMetadata {
-            size,
-            is_file,
-            is_dir,
-            modified,
-            accessed,
-            created,
-        })
+        let file_path = std::path::Path::new(&path);
+        record_transfer(contents.len());
+        durability
+            .write_file(file_path, contents.into_bytes(), |path, contents| {
+                tokio::fs::write(path, contents)
+            })
+            .await
+            .map_err(|e| map_io_error(e, &path, "write file"))?;
+        durability
+            .sync_parent(file_path)
+            .await
+            .map_err(|e| map_io_error(e, &path, "sync directory of"))
     };
-    future_into_py(py, future)
-}
-
-/// Get file metadata asynchronously (alias for stat).
-#[pyfunction]
-fn metadata_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
-    stat_async(py, path)
+    run_op(py, op, future)
 }
 
-// Directory traversal
-
-/// Recursively walk a directory asynchronously.
+/// Async binary file read using Tokio (GIL-independent).
 ///
-/// Traverses a directory tree recursively and returns a list of all files
-/// and directories found. All I/O operations execute outside the Python GIL
-/// using native Tokio, ensuring true async behavior.
+/// Reads the entire file and returns its contents as raw bytes.
+/// All I/O operations execute outside the Python GIL using native Tokio,
+/// ensuring true async behavior and preventing event loop stalls.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `path` - Directory path to walk
+/// * `path` - Path to the file to read
 ///
 /// # Returns
 ///
-/// A coroutine that yields a list of (path, is_file) tuples where:
-/// - `path`: Full path to the file or directory
-/// - `is_file`: True if the path is a file, False if it's a directory
+/// A coroutine that yields the file contents as bytes.
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the directory does not exist,
-/// `PyIOError` if the directory cannot be read, or `PyValueError` if the path is invalid.
+/// Returns `PyFileNotFoundError` if the file does not exist,
+/// `PyIOError` if the file cannot be read, or `PyValueError` if the path is invalid.
 #[pyfunction]
-fn walk_dir_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+fn read_file_bytes_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
     validate_path(&path)?;
-    let future = async move {
-        let path_clone = path.clone();
-        let mut results = Vec::new();
-
-        // Use a stack to traverse directories
-        let mut stack = vec![path_clone.clone()];
-
-        while let Some(current_path) = stack.pop() {
-            let mut entries = match tokio::fs::read_dir(&current_path).await {
-                Ok(entries) => entries,
-                Err(_e) => {
-                    // Skip directories we can't read
-                    continue;
-                }
-            };
-
-            while let Some(entry) = entries.next_entry().await.map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                    "Failed to read directory entry in {current_path}: {e}"
-                ))
-            })? {
-                let entry_path = entry.path();
-                let path_str = entry_path.to_string_lossy().to_string();
-
-                let metadata = match entry.metadata().await {
-                    Ok(m) => m,
-                    Err(_) => continue, // Skip entries we can't get metadata for
-                };
-
-                let is_file = metadata.is_file();
-                let is_dir = metadata.is_dir();
-
-                results.push((path_str.clone(), is_file));
-
-                // Add subdirectories to the stack for traversal
-                if is_dir {
-                    stack.push(path_str);
-                }
-            }
+    let op = OpContext::new("read_file_bytes", [path.as_str()]);
+    run_retryable_op(py, op, move || {
+        let path = path.clone();
+        async move {
+            let bytes = rapfiles_core::read(&path)
+                .await
+                .map_err(|e| map_io_error(e, &path, "read file"))?;
+            record_transfer(bytes.len());
+            Ok(bytes)
         }
-
-        Ok(results)
-    };
-    future_into_py(py, future)
+    })
 }
 
-// File manipulation operations
-
-/// Copy a file asynchronously.
+/// Read a file and its metadata from a single open.
 ///
-/// Copies a file from source to destination. If the destination file exists,
-/// it will be overwritten. All I/O operations execute outside the Python GIL
-/// using native Tokio, ensuring true async behavior.
+/// The metadata comes from `fstat` on the descriptor the contents are read
+/// from, so both describe the same file even if the path is replaced
+/// concurrently, and the open and stat cost one job on the blocking pool
+/// instead of two round trips. The buffer is sized from the stat result.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `src` - Path to the source file
-/// * `dst` - Path to the destination file
+/// * `path` - Path to the file to read
 ///
 /// # Returns
 ///
-/// A coroutine that yields `None` on success.
+/// A coroutine that yields a `(FileMetadata, bytes)` tuple.
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the source file does not exist,
-/// `PyIOError` if the file cannot be copied, or `PyValueError` if the path is invalid.
+/// Returns `PyFileNotFoundError` if the file does not exist, `PyIOError` if
+/// it cannot be read (e.g. it is a directory), or `PyValueError` if the
+/// path is invalid.
 #[pyfunction]
-fn copy_file_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&src)?;
-    validate_path(&dst)?;
-    let future = async move {
-        let src_clone = src.clone();
-        let dst_clone = dst.clone();
-        tokio::fs::copy(&src, &dst)
+fn read_with_stat_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("read_with_stat", [path.as_str()]);
+    run_retryable_op(py, op, move || {
+        let path = path.clone();
+        async move {
+            let path_clone = path.clone();
+            let (metadata, bytes) = run_blocking(move || {
+                use std::io::Read;
+
+                let mut file = std::fs::File::open(&path)?;
+                let metadata = file.metadata()?;
+                let mut bytes = Vec::with_capacity(metadata.len() as usize);
+                file.read_to_end(&mut bytes)?;
+                Ok((FileMetadata::from_std(&metadata), bytes))
+            })
             .await
-            .map_err(|e| map_io_error(e, &format!("{src_clone} -> {dst_clone}"), "copy file"))?;
-        Ok(())
-    };
-    future_into_py(py, future)
+            .map_err(|e| map_io_error(e, &path_clone, "read file"))?;
+            record_transfer(bytes.len());
+            Ok((metadata, bytes))
+        }
+    })
 }
 
-/// Move or rename a file asynchronously.
+/// Read exactly `size` bytes from a path or an open `AsyncFile`.
 ///
-/// Moves a file from source to destination. This is an atomic operation when
-/// moving within the same filesystem. For cross-device moves, it will copy
-/// and then remove the source file. All I/O operations execute outside the
-/// Python GIL using native Tokio, ensuring true async behavior.
+/// Repeats partial reads until `size` bytes have arrived, so protocol
+/// readers on pipes, FIFOs and character devices don't have to loop over
+/// short reads themselves. A path is opened for reading and closed again;
+/// an `AsyncFile` is read from its current position (see
+/// `AsyncFile.read_exact()`).
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `src` - Path to the source file
-/// * `dst` - Path to the destination file
+/// * `source` - Path to read from, or an open `AsyncFile`
+/// * `size` - Number of bytes to read
+/// * `timeout` - Seconds to wait for all of them, or `None` to wait forever
 ///
 /// # Returns
 ///
-/// A coroutine that yields `None` on success.
+/// A coroutine that yields exactly `size` bytes.
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the source file does not exist,
-/// `PyIOError` if the file cannot be moved, or `PyValueError` if the path is invalid.
+/// Raises `asyncio.IncompleteReadError` (with the bytes read in `partial`)
+/// if the file ends first, `TimeoutError` on timeout, `PyTypeError` if
+/// `source` is neither a path nor an `AsyncFile`, `PyFileNotFoundError` if
+/// the path does not exist, or `PyValueError` if the path or timeout is
+/// invalid.
 #[pyfunction]
-fn move_file_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&src)?;
-    validate_path(&dst)?;
-    let future = async move {
-        let src_clone = src.clone();
-        let dst_clone = dst.clone();
+#[pyo3(signature = (source, size, timeout = None))]
+fn read_exact_async<'py>(
+    py: Python<'py>,
+    source: &Bound<'py, PyAny>,
+    size: usize,
+    timeout: Option<f64>,
+) -> PyResult<Bound<'py, PyAny>> {
+    if let Ok(file) = source.extract::<PyRef<'_, AsyncFile>>() {
+        return file.read_exact(py, size, timeout);
+    }
+    let path: String = source.extract().map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "read_exact() source must be a path or an AsyncFile, not {}",
+            source
+                .get_type()
+                .name()
+                .map_or_else(|_| "?".into(), |n| n.to_string())
+        ))
+    })?;
+    validate_path(&path)?;
+    let deadline = read_exact_deadline(timeout)?;
 
-        // Try rename first (atomic on same filesystem)
-        match tokio::fs::rename(&src, &dst).await {
-            Ok(_) => Ok(()),
-            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
-                // Cross-device move: copy then remove
-                tokio::fs::copy(&src, &dst).await.map_err(|e| {
-                    map_io_error(e, &format!("{src_clone} -> {dst_clone}"), "copy file")
-                })?;
-                tokio::fs::remove_file(&src)
-                    .await
-                    .map_err(|e| map_io_error(e, &src_clone, "remove file"))?;
-                Ok(())
-            }
-            Err(e) => Err(map_io_error(
-                e,
-                &format!("{src_clone} -> {dst_clone}"),
-                "move file",
-            )),
-        }
+    let op = OpContext::new("read_exact", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let file = run_blocking(move || std::fs::File::open(&path_clone))
+            .await
+            .map_err(|e| map_io_error(e, &path, "open file"))?;
+        let mut handle = OpenHandle::new(HandleFile::Blocking(Arc::new(file)), 0);
+        let buffer = read_exact_from(&mut handle, size, deadline)
+            .await
+            .map_err(|e| e.into_py_err(&path, size))?;
+        record_transfer(buffer.len());
+        Ok(buffer)
     };
-    future_into_py(py, future)
+
+    run_op(py, op, future)
 }
 
-/// Remove a file asynchronously.
+/// Async binary file write using Tokio (GIL-independent).
 ///
-/// Deletes a file from the filesystem. This will not remove directories.
+/// Writes raw bytes to a file. If the file exists, it will be overwritten.
 /// All I/O operations execute outside the Python GIL using native Tokio,
-/// ensuring true async behavior.
+/// ensuring true async behavior and preventing event loop stalls.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `path` - Path to the file to remove
+/// * `path` - Path to the file to write
+/// * `contents` - Bytes to write to the file
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` or
+///   `"fsync+dir"`; see `Durability`
 ///
 /// # Returns
 ///
@@ -1248,43 +2711,49 @@ fn move_file_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the file does not exist,
-/// `PyIOError` if the file cannot be removed (e.g., if it's a directory),
-/// or `PyValueError` if the path is invalid.
+/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
+/// if write permission is denied, or `PyValueError` if the path or
+/// durability is invalid.
 #[pyfunction]
-fn remove_file_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+#[pyo3(signature = (path, contents, durability = "flush"))]
+fn write_file_bytes_async<'a>(
+    py: Python<'a>,
+    path: String,
+    contents: &'a Bound<'a, PyBytes>,
+    durability: &str,
+) -> PyResult<Bound<'a, PyAny>> {
     validate_path(&path)?;
+    let durability = Durability::parse(durability)?;
+    let bytes = contents.as_bytes().to_vec();
+    let op = OpContext::new("write_file_bytes", [path.as_str()]);
     let future = async move {
-        let path_clone = path.clone();
-
-        // Check if it's a directory first to provide a better error message
-        let metadata = tokio::fs::metadata(&path).await;
-        if let Ok(md) = metadata {
-            if md.is_dir() {
-                return Err(PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                    "Failed to remove file {path_clone}: path is a directory. Use remove_dir() instead."
-                )));
-            }
-        }
-
-        tokio::fs::remove_file(&path)
+        let file_path = std::path::Path::new(&path);
+        record_transfer(bytes.len());
+        durability
+            .write_file(file_path, bytes, rapfiles_core::write)
+            .await
+            .map_err(|e| map_io_error(e, &path, "write file"))?;
+        durability
+            .sync_parent(file_path)
             .await
-            .map_err(|e| map_io_error(e, &path_clone, "remove file"))
+            .map_err(|e| map_io_error(e, &path, "sync directory of"))
     };
-    future_into_py(py, future)
+    run_op(py, op, future)
 }
 
-/// Create a hard link asynchronously.
+/// Async file append using Tokio (GIL-independent).
 ///
-/// Creates a hard link from source to destination. Both files will refer
-/// to the same underlying file data. All I/O operations execute outside
-/// the Python GIL using native Tokio, ensuring true async behavior.
+/// Appends content to the end of a file. If the file does not exist, it will be created.
+/// All I/O operations execute outside the Python GIL using native Tokio,
+/// ensuring true async behavior and preventing event loop stalls.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `src` - Path to the source file
-/// * `dst` - Path to the destination link
+/// * `path` - Path to the file to append to
+/// * `contents` - Content to append to the file (UTF-8 string)
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` or
+///   `"fsync+dir"`; see `Durability`
 ///
 /// # Returns
 ///
@@ -1292,45 +2761,59 @@ fn remove_file_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>>
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the source file does not exist,
-/// `PyIOError` if the link cannot be created, or `PyValueError` if the path is invalid.
+/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
+/// if write permission is denied, or `PyValueError` if the path or
+/// durability is invalid.
 #[pyfunction]
-fn hard_link_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&src)?;
-    validate_path(&dst)?;
+#[pyo3(signature = (path, contents, durability = "flush"))]
+fn append_file_async<'py>(
+    py: Python<'py>,
+    path: String,
+    contents: String,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&path)?;
+    let durability = Durability::parse(durability)?;
+    let op = OpContext::new("append_file", [path.as_str()]);
     let future = async move {
-        let src_clone = src.clone();
-        let dst_clone = dst.clone();
+        let path_clone = path.clone();
+        let mut open_options = std::fs::OpenOptions::new();
+        open_options.create(true).append(true);
+        let mut file = open_handle(open_options, &path).await.map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to open file {path_clone} for appending: {e}"
+            ))
+        })?;
 
-        // tokio::fs::hard_link is not available, use std::fs::hard_link in blocking mode
-        tokio::task::spawn_blocking(move || {
-            std::fs::hard_link(&src, &dst).map_err(|e| {
-                map_io_error(
-                    e,
-                    &format!("{src_clone} -> {dst_clone}"),
-                    "create hard link",
-                )
-            })
-        })
-        .await
-        .map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to create hard link: {e}"))
-        })?
+        record_transfer(contents.len());
+        let appended = match file.write_all(contents.into_bytes()).await {
+            Ok(()) => durability.finish(&mut file).await,
+            Err(e) => Err(e),
+        };
+        appended.map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to append to file {path_clone}: {e}"
+            ))
+        })?;
+        durability
+            .sync_parent(std::path::Path::new(&path))
+            .await
+            .map_err(|e| map_io_error(e, &path, "sync directory of"))
     };
-    future_into_py(py, future)
+    run_op(py, op, future)
 }
 
-/// Create a symbolic link asynchronously.
+// Directory operations
+
+/// Create a directory asynchronously.
 ///
-/// Creates a symbolic link from source to destination. The destination
-/// will point to the source path. All I/O operations execute outside
-/// the Python GIL using native Tokio, ensuring true async behavior.
+/// Creates a single directory. Parent directories must already exist.
+/// All I/O operations execute outside the Python GIL using native Tokio.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `src` - Path that the symlink will point to
-/// * `dst` - Path to the symbolic link to create
+/// * `path` - Path to the directory to create
 ///
 /// # Returns
 ///
@@ -1338,740 +2821,13143 @@ fn hard_link_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'
 ///
 /// # Errors
 ///
-/// Returns `PyIOError` if the symlink cannot be created, or `PyValueError` if the path is invalid.
+/// Returns `PyFileExistsError` if the directory already exists,
+/// `PyIOError` if the directory cannot be created, or `PyValueError` if the path is invalid.
 #[pyfunction]
-fn symlink_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&src)?;
-    validate_path(&dst)?;
+fn create_dir_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("create_dir", [path.as_str()]);
     let future = async move {
-        let src_clone = src.clone();
-        let dst_clone = dst.clone();
-
-        // tokio::fs::symlink has different behavior on Windows vs Unix
-        #[cfg(unix)]
-        {
-            use tokio::fs::symlink;
-            symlink(&src, &dst).await.map_err(|e| {
-                map_io_error(e, &format!("{src_clone} -> {dst_clone}"), "create symlink")
-            })
-        }
-
-        #[cfg(windows)]
-        {
-            // On Windows, symlink requires checking if src is a file or directory
-            use tokio::fs;
-            let metadata = fs::symlink_metadata(&src).await;
-            match metadata {
-                Ok(md) if md.is_dir() => fs::symlink_dir(&src, &dst).await.map_err(|e| {
-                    map_io_error(
-                        e,
-                        &format!("{} -> {}", src_clone, dst_clone),
-                        "create symlink",
-                    )
-                }),
-                Ok(_) => fs::symlink_file(&src, &dst).await.map_err(|e| {
-                    map_io_error(
-                        e,
-                        &format!("{} -> {}", src_clone, dst_clone),
-                        "create symlink",
-                    )
-                }),
-                Err(_) => {
-                    // If source doesn't exist, default to file symlink on Windows
-                    fs::symlink_file(&src, &dst).await.map_err(|e| {
-                        map_io_error(
-                            e,
-                            &format!("{} -> {}", src_clone, dst_clone),
-                            "create symlink",
-                        )
-                    })
-                }
-            }
-        }
+        let path_clone = path.clone();
+        tokio::fs::create_dir(&path).await.map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to create directory {path_clone}: {e}"
+            ))
+        })
     };
-    future_into_py(py, future)
+    run_op(py, op, future)
 }
 
-/// Canonicalize a path asynchronously.
+/// Create a directory and all parent directories asynchronously.
 ///
-/// Resolves all symbolic links and returns the absolute path. All I/O
-/// operations execute outside the Python GIL using native Tokio, ensuring
-/// true async behavior.
+/// Creates a directory and any necessary parent directories (equivalent to `mkdir -p`).
+/// All I/O operations execute outside the Python GIL using native Tokio.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `path` - Path to canonicalize
+/// * `path` - Path to the directory to create (with parents)
 ///
 /// # Returns
 ///
-/// A coroutine that yields the canonical path as a string.
+/// A coroutine that yields `None` on success.
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the path does not exist,
-/// `PyIOError` if the path cannot be canonicalized, or `PyValueError` if the path is invalid.
+/// Returns `PyIOError` if the directory cannot be created,
+/// or `PyValueError` if the path is invalid.
 #[pyfunction]
-fn canonicalize_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+fn create_dir_all_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
     validate_path(&path)?;
+    let op = OpContext::new("create_dir_all", [path.as_str()]);
     let future = async move {
         let path_clone = path.clone();
-        let canonical = tokio::fs::canonicalize(&path)
-            .await
-            .map_err(|e| map_io_error(e, &path_clone, "canonicalize path"))?;
-
-        canonical
-            .to_str()
-            .ok_or_else(|| {
-                PyErr::new::<pyo3::exceptions::PyUnicodeDecodeError, _>(
-                    "Canonicalized path contains invalid UTF-8",
-                )
-            })
-            .map(|s| s.to_string())
+        tokio::fs::create_dir_all(&path).await.map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to create directory {path_clone}: {e}"
+            ))
+        })
     };
-    future_into_py(py, future)
+    run_op(py, op, future)
 }
 
-// Atomic file operations
-
-/// Write a file atomically using a temporary file.
-///
-/// Writes content to a temporary file first, then atomically replaces
-/// the target file by renaming. This ensures the target file is never
-/// in a partially-written state. All I/O operations execute outside
-/// the Python GIL using native Tokio, ensuring true async behavior.
-///
-/// # Arguments
-///
-/// * `py` - Python GIL token
-/// * `path` - Path to the file to write
-/// * `contents` - Content to write to the file (UTF-8 string)
-///
-/// # Returns
-///
-/// A coroutine that yields `None` on success.
-///
-/// # Errors
-///
-/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
-/// if write permission is denied, or `PyValueError` if the path is invalid.
+/// Remove an empty directory asynchronously.
 #[pyfunction]
-fn atomic_write_file_async(
-    py: Python<'_>,
-    path: String,
-    contents: String,
-) -> PyResult<Bound<'_, PyAny>> {
+fn remove_dir_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
     validate_path(&path)?;
+    let op = OpContext::new("remove_dir", [path.as_str()]);
     let future = async move {
-        use std::path::Path;
         let path_clone = path.clone();
+        tokio::fs::remove_dir(&path).await.map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to remove directory {path_clone}: {e}"
+            ))
+        })
+    };
+    run_op(py, op, future)
+}
 
-        let file_path = Path::new(&path);
-        let dir = file_path.parent().ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>("Path has no parent directory")
-        })?;
+/// Default upper bound on the worker threads used to remove one tree.
+const REMOVE_TREE_MAX_WORKERS: usize = 16;
+
+/// Remove a directory tree with several threads working on descriptors.
+///
+/// Every directory is opened relative to its parent's descriptor
+/// (`openat` with `O_NOFOLLOW`), its entries are unlinked with `unlinkat`
+/// on that descriptor, and its subdirectories are queued in one batch for
+/// whichever worker is free. A directory is removed as soon as its last
+/// subdirectory is gone, so the tree shrinks from the leaves up while the
+/// traversal continues elsewhere. Paths are never resolved below the root,
+/// so symlinks swapped in during the removal are unlinked, not followed.
+/// Like `std::fs::remove_dir_all`, entries that disappear while the tree is
+/// being removed (e.g. another process deleting it too) are skipped.
+/// At most `max_workers` threads (and no more than the CPUs) are used.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "freebsd"
+))]
+fn remove_tree_parallel(root: &std::path::Path, max_workers: usize) -> std::io::Result<()> {
+    use std::ffi::{CStr, CString};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::{Condvar, Mutex};
+
+    /// Directory being emptied
+    struct Dir {
+        /// Descriptor the entries are opened and unlinked through
+        fd: OwnedFd,
+        /// Parent and the name of this directory in it (None for the root)
+        parent: Option<(Arc<Dir>, CString)>,
+        /// Subdirectories not yet removed, plus one until the listing is done
+        pending: AtomicUsize,
+    }
 
-        // Create temporary file in same directory
-        let file_name = file_path.file_name().ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>("Path has no file name")
-        })?;
+    /// Subdirectories waiting for a worker, as (parent, name)
+    struct Work {
+        tasks: Vec<(Arc<Dir>, CString)>,
+        /// Workers currently emptying a directory
+        busy: usize,
+    }
 
-        let temp_path = dir.join(format!(".{}.tmp", file_name.to_string_lossy()));
-        let temp_path_str = temp_path.to_string_lossy().to_string();
+    struct Queue {
+        state: Mutex<Work>,
+        changed: Condvar,
+        error: Mutex<Option<std::io::Error>>,
+        failed: AtomicBool,
+    }
 
-        // Write to temporary file
-        tokio::fs::write(&temp_path, contents)
-            .await
-            .map_err(|e| map_io_error(e, &temp_path_str, "write temporary file"))?;
+    fn check(ret: libc::c_int) -> std::io::Result<libc::c_int> {
+        if ret < 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(ret)
+        }
+    }
 
-        // Atomically replace target file
-        tokio::fs::rename(&temp_path, &path).await.map_err(|e| {
-            // Clean up temp file on error (spawn cleanup task)
-            let temp_cleanup = temp_path.clone();
-            tokio::spawn(async move {
-                let _ = tokio::fs::remove_file(&temp_cleanup).await;
-            });
-            map_io_error(e, &path_clone, "atomically write file")
-        })
-    };
-    future_into_py(py, future)
-}
+    /// Treat an entry that is already gone as removed.
+    fn ignore_not_found(result: std::io::Result<libc::c_int>) -> std::io::Result<()> {
+        match result {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
 
-/// Write bytes to a file atomically using a temporary file.
-///
-/// Writes bytes to a temporary file first, then atomically replaces
-/// the target file by renaming. This ensures the target file is never
-/// in a partially-written state. All I/O operations execute outside
-/// the Python GIL using native Tokio, ensuring true async behavior.
-///
-/// # Arguments
-///
-/// * `py` - Python GIL token
-/// * `path` - Path to the file to write
-/// * `contents` - Bytes to write to the file
+    /// Reset `errno`, which `readdir` only sets on failure.
+    fn clear_errno() {
+        #[cfg(target_os = "linux")]
+        unsafe {
+            *libc::__errno_location() = 0
+        };
+        #[cfg(target_os = "android")]
+        unsafe {
+            *libc::__errno() = 0
+        };
+        #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+        unsafe {
+            *libc::__error() = 0
+        };
+    }
+
+    /// Read the names of a directory's entries and whether each is a directory.
+    fn read_entries(dir: &Dir) -> std::io::Result<Vec<(CString, bool)>> {
+        // fdopendir takes ownership, so hand it a duplicate
+        let dup = check(unsafe { libc::dup(dir.fd.as_raw_fd()) })?;
+        let stream = unsafe { libc::fdopendir(dup) };
+        if stream.is_null() {
+            let err = std::io::Error::last_os_error();
+            unsafe { libc::close(dup) };
+            return Err(err);
+        }
+        let mut entries = Vec::new();
+        let mut result = Ok(());
+        loop {
+            // The end of the stream and a read error both return null
+            clear_errno();
+            let entry = unsafe { libc::readdir(stream) };
+            if entry.is_null() {
+                let err = std::io::Error::last_os_error();
+                if err.raw_os_error() != Some(0) {
+                    result = Err(err);
+                }
+                break;
+            }
+            let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) };
+            if name.to_bytes() == b"." || name.to_bytes() == b".." {
+                continue;
+            }
+            let is_dir = match unsafe { (*entry).d_type } {
+                libc::DT_DIR => true,
+                libc::DT_UNKNOWN => {
+                    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+                    let ret = unsafe {
+                        libc::fstatat(
+                            dir.fd.as_raw_fd(),
+                            name.as_ptr(),
+                            &mut st,
+                            libc::AT_SYMLINK_NOFOLLOW,
+                        )
+                    };
+                    match check(ret) {
+                        Ok(_) => st.st_mode & libc::S_IFMT == libc::S_IFDIR,
+                        // Removed since it was listed
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                        Err(e) => {
+                            result = Err(e);
+                            break;
+                        }
+                    }
+                }
+                _ => false,
+            };
+            entries.push((name.to_owned(), is_dir));
+        }
+        unsafe { libc::closedir(stream) };
+        result.map(|()| entries)
+    }
+
+    /// Unlink a directory's files and queue its subdirectories.
+    fn empty_dir(dir: Arc<Dir>, queue: &Queue) -> std::io::Result<()> {
+        let mut subdirs = Vec::new();
+        for (name, is_dir) in read_entries(&dir)? {
+            if is_dir {
+                subdirs.push((dir.clone(), name));
+            } else {
+                ignore_not_found(check(unsafe {
+                    libc::unlinkat(dir.fd.as_raw_fd(), name.as_ptr(), 0)
+                }))?;
+            }
+        }
+        if !subdirs.is_empty() {
+            dir.pending.fetch_add(subdirs.len(), Ordering::AcqRel);
+            queue
+                .state
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .tasks
+                .extend(subdirs);
+            queue.changed.notify_all();
+        }
+        release(dir)
+    }
+
+    /// Drop one pending reference, removing directories that became empty.
+    fn release(mut dir: Arc<Dir>) -> std::io::Result<()> {
+        while dir.pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let Some((parent, name)) = &dir.parent else {
+                break;
+            };
+            let ret =
+                unsafe { libc::unlinkat(parent.fd.as_raw_fd(), name.as_ptr(), libc::AT_REMOVEDIR) };
+            ignore_not_found(check(ret))?;
+            dir = parent.clone();
+        }
+        Ok(())
+    }
+
+    fn open_subdir(parent: RawFd, name: &CStr) -> std::io::Result<OwnedFd> {
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+        let fd = check(unsafe { libc::openat(parent, name.as_ptr(), flags) })?;
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    fn work(queue: &Queue) {
+        loop {
+            let (parent, name) = {
+                let mut state = queue.state.lock().unwrap_or_else(|e| e.into_inner());
+                loop {
+                    if queue.failed.load(Ordering::Acquire) {
+                        return;
+                    }
+                    if let Some(task) = state.tasks.pop() {
+                        state.busy += 1;
+                        break task;
+                    }
+                    if state.busy == 0 {
+                        return;
+                    }
+                    state = queue.changed.wait(state).unwrap_or_else(|e| e.into_inner());
+                }
+            };
+            let result = match open_subdir(parent.fd.as_raw_fd(), &name) {
+                Ok(fd) => {
+                    let pending = AtomicUsize::new(1);
+                    let dir = Dir {
+                        fd,
+                        parent: Some((parent, name)),
+                        pending,
+                    };
+                    empty_dir(Arc::new(dir), queue)
+                }
+                // Already removed
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => release(parent),
+                // Replaced by a file or symlink since it was listed
+                Err(e) if matches!(e.raw_os_error(), Some(libc::ENOTDIR | libc::ELOOP)) => {
+                    ignore_not_found(check(unsafe {
+                        libc::unlinkat(parent.fd.as_raw_fd(), name.as_ptr(), 0)
+                    }))
+                    .and_then(|()| release(parent))
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                queue
+                    .error
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .get_or_insert(e);
+                queue.failed.store(true, Ordering::Release);
+            }
+            queue.state.lock().unwrap_or_else(|e| e.into_inner()).busy -= 1;
+            queue.changed.notify_all();
+        }
+    }
+
+    // Like std::fs::remove_dir_all, a symlink root is removed, not followed
+    if std::fs::symlink_metadata(root)?.file_type().is_symlink() {
+        return std::fs::remove_file(root);
+    }
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECTORY | libc::O_NOFOLLOW)
+        .open(root)?;
+    let queue = Queue {
+        state: Mutex::new(Work {
+            tasks: Vec::new(),
+            busy: 0,
+        }),
+        changed: Condvar::new(),
+        error: Mutex::new(None),
+        failed: AtomicBool::new(false),
+    };
+    let top = Arc::new(Dir {
+        fd: file.into(),
+        parent: None,
+        pending: AtomicUsize::new(1),
+    });
+    empty_dir(top, &queue)?;
+
+    let queued = queue
+        .state
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .tasks
+        .len();
+    if queued > 0 {
+        let workers = std::thread::available_parallelism()
+            .map_or(4, |n| n.get())
+            .clamp(1, max_workers.max(1))
+            .min(queued.max(2));
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| work(&queue));
+            }
+        });
+        if let Some(e) = queue.error.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            return Err(e);
+        }
+    }
+    match std::fs::remove_dir(root) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Remove a directory tree (sequential fallback for other platforms).
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "freebsd"
+)))]
+fn remove_tree_parallel(root: &std::path::Path, _max_workers: usize) -> std::io::Result<()> {
+    std::fs::remove_dir_all(root)
+}
+
+/// Spaces out the removals of a throttled `remove_dir_all_async`.
+struct RemovalPacer {
+    /// Minimum time between two removals; `None` removes at full speed
+    interval: Option<std::time::Duration>,
+    next: std::time::Instant,
+}
+
+impl RemovalPacer {
+    fn new(ops_per_second: Option<f64>) -> Self {
+        RemovalPacer {
+            interval: ops_per_second.map(|rate| std::time::Duration::from_secs_f64(1.0 / rate)),
+            next: std::time::Instant::now(),
+        }
+    }
+
+    /// Block until the next removal may start.
+    fn wait(&mut self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        if self.next > now {
+            std::thread::sleep(self.next - now);
+        }
+        self.next = self.next.max(now) + interval;
+    }
+}
+
+/// Remove a directory tree one entry at a time, pacing every removal.
 ///
-/// # Returns
+/// Used by the throttled mode of `remove_dir_all_async`, which trades speed
+/// for a steady, low I/O load.
+fn remove_tree_paced(path: &std::path::Path, pacer: &mut RemovalPacer) -> std::io::Result<()> {
+    // Symlinks are removed, never followed
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            remove_tree_paced(&entry?.path(), pacer)?;
+        }
+        pacer.wait();
+        std::fs::remove_dir(path)
+    } else {
+        pacer.wait();
+        std::fs::remove_file(path)
+    }
+}
+
+/// Give the calling thread the lowest CPU priority and the idle I/O class.
+///
+/// Best effort: the I/O class only takes effect with I/O schedulers that
+/// honour it (BFQ, CFQ), and failures are ignored. Does nothing on platforms
+/// other than Linux.
+fn lower_thread_priority() {
+    #[cfg(target_os = "linux")]
+    // SAFETY: both calls only change the scheduling of the calling thread
+    unsafe {
+        let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+        libc::setpriority(libc::PRIO_PROCESS, tid, 19);
+        // IOPRIO_WHO_PROCESS (1) of the calling thread, IOPRIO_CLASS_IDLE (3),
+        // as set by `ionice -c 3`
+        libc::syscall(libc::SYS_ioprio_set, 1, 0, 3 << 13);
+    }
+}
+
+/// Remove a directory and all its contents asynchronously.
 ///
-/// A coroutine that yields `None` on success.
+/// On Linux, macOS and FreeBSD the tree is removed by several threads that
+/// unlink entries relative to open directory descriptors (see
+/// `remove_tree_parallel`), which is much faster than a sequential walk for
+/// directories with millions of files. Other platforms use
+/// `std::fs::remove_dir_all`.
+///
+/// With `rate_limit_ops` or `nice` the tree is instead removed one entry at
+/// a time, so a bulk cleanup does not saturate the disk shared with other
+/// workloads.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path of the directory to remove
+/// * `rate_limit_ops` - Maximum number of entries (files and directories)
+///   removed per second
+/// * `nice` - Remove from a thread with the lowest CPU priority and, on
+///   Linux, the idle I/O scheduling class
 ///
 /// # Errors
 ///
-/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
-/// if write permission is denied, or `PyValueError` if the path is invalid.
+/// Returns `PyIOError` if the tree cannot be removed, or `PyValueError` if
+/// the path is invalid or `rate_limit_ops` is not a positive number.
 #[pyfunction]
-fn atomic_write_file_bytes_async<'a>(
-    py: Python<'a>,
+#[pyo3(signature = (path, *, rate_limit_ops = None, nice = false))]
+fn remove_dir_all_async(
+    py: Python<'_>,
     path: String,
-    contents: &'a Bound<'a, PyBytes>,
-) -> PyResult<Bound<'a, PyAny>> {
+    rate_limit_ops: Option<f64>,
+    nice: bool,
+) -> PyResult<Bound<'_, PyAny>> {
     validate_path(&path)?;
-    let bytes = contents.as_bytes().to_vec();
+    if let Some(rate) = rate_limit_ops {
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid rate_limit_ops: {rate}. Must be a positive number of entries per second"
+            )));
+        }
+    }
+    let op = OpContext::new("remove_dir_all", [path.as_str()]);
     let future = async move {
-        use std::path::Path;
         let path_clone = path.clone();
+        let max_workers = op_concurrency(REMOVE_TREE_MAX_WORKERS);
+        tokio::task::spawn_blocking(move || {
+            let root = std::path::Path::new(&path);
+            if !nice && rate_limit_ops.is_none() {
+                return remove_tree_parallel(root, max_workers);
+            }
+            let metadata = std::fs::symlink_metadata(root)?;
+            if !metadata.is_dir() && !metadata.is_symlink() {
+                return Err(std::io::ErrorKind::NotADirectory.into());
+            }
+            let mut pacer = RemovalPacer::new(rate_limit_ops);
+            if !nice {
+                return remove_tree_paced(root, &mut pacer);
+            }
+            // A thread of its own, so no pool thread keeps the low priority
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| {
+                        lower_thread_priority();
+                        remove_tree_paced(root, &mut pacer)
+                    })
+                    .join()
+            })
+            .unwrap_or_else(|_| Err(std::io::Error::other("removal thread panicked")))
+        })
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))
+        .and_then(|result| result)
+        .map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to remove directory {path_clone}: {e}"
+            ))
+        })
+    };
+    run_op(py, op, future)
+}
 
-        let file_path = Path::new(&path);
-        let dir = file_path.parent().ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>("Path has no parent directory")
-        })?;
+/// Ordering applied to directory listings.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortOrder {
+    /// Filesystem order (fastest, not stable across filesystems)
+    Unsorted,
+    /// Lexicographic order of the raw name bytes
+    Bytes,
+    /// Human order where digit runs compare numerically (`file2` < `file10`)
+    Natural,
+}
 
-        // Create temporary file in same directory
-        let file_name = file_path.file_name().ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>("Path has no file name")
-        })?;
+impl SortOrder {
+    /// Parse the `sorted` argument accepted by listing functions.
+    ///
+    /// Accepts `None`/`False` (unsorted), `True` or `"bytes"` (byte order),
+    /// and `"natural"` (numeric-aware order).
+    fn from_py(sorted: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(sorted) = sorted else {
+            return Ok(SortOrder::Unsorted);
+        };
+        if sorted.is_none() {
+            return Ok(SortOrder::Unsorted);
+        }
+        if let Ok(flag) = sorted.cast::<pyo3::types::PyBool>() {
+            return Ok(if flag.is_true() {
+                SortOrder::Bytes
+            } else {
+                SortOrder::Unsorted
+            });
+        }
+        match sorted.extract::<String>()?.as_str() {
+            "bytes" => Ok(SortOrder::Bytes),
+            "natural" => Ok(SortOrder::Natural),
+            other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid sort order: {other}. Must be True, False, 'bytes', or 'natural'"
+            ))),
+        }
+    }
 
-        let temp_path = dir.join(format!(".{}.tmp", file_name.to_string_lossy()));
-        let temp_path_str = temp_path.to_string_lossy().to_string();
+    /// Compare two entry names.
+    fn compare(self, a: &std::ffi::OsStr, b: &std::ffi::OsStr) -> std::cmp::Ordering {
+        match self {
+            SortOrder::Unsorted => std::cmp::Ordering::Equal,
+            SortOrder::Bytes => a.as_encoded_bytes().cmp(b.as_encoded_bytes()),
+            SortOrder::Natural => natural_compare(a.as_encoded_bytes(), b.as_encoded_bytes()),
+        }
+    }
 
-        // Write to temporary file
-        tokio::fs::write(&temp_path, bytes)
-            .await
-            .map_err(|e| map_io_error(e, &temp_path_str, "write temporary file"))?;
+    /// Compare two paths component by component.
+    ///
+    /// Sorting a recursive listing this way yields a stable pre-order
+    /// traversal where each directory is followed by its contents.
+    fn compare_paths(self, a: &str, b: &str) -> std::cmp::Ordering {
+        let mut a = std::path::Path::new(a).components();
+        let mut b = std::path::Path::new(b).components();
+        loop {
+            let ordering = match (a.next(), b.next()) {
+                (Some(a), Some(b)) => self.compare(a.as_os_str(), b.as_os_str()),
+                (None, Some(_)) => std::cmp::Ordering::Less,
+                (Some(_), None) => std::cmp::Ordering::Greater,
+                (None, None) => return std::cmp::Ordering::Equal,
+            };
+            if ordering != std::cmp::Ordering::Equal {
+                return ordering;
+            }
+        }
+    }
+}
 
-        // Atomically replace target file
-        tokio::fs::rename(&temp_path, &path).await.map_err(|e| {
-            // Clean up temp file on error (spawn cleanup task)
-            let temp_cleanup = temp_path.clone();
-            tokio::spawn(async move {
-                let _ = tokio::fs::remove_file(&temp_cleanup).await;
-            });
-            map_io_error(e, &path_clone, "atomically write file")
-        })
-    };
-    future_into_py(py, future)
+/// Compare two names in natural order.
+///
+/// Runs of ASCII digits are compared by numeric value (ignoring leading
+/// zeros) and everything else byte by byte. Names that are numerically equal
+/// (`a01` and `a1`) fall back to byte order so the result stays total.
+fn natural_compare(a: &[u8], b: &[u8]) -> std::cmp::Ordering {
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let start_a = i;
+            let start_b = j;
+            while i < a.len() && a[i].is_ascii_digit() {
+                i += 1;
+            }
+            while j < b.len() && b[j].is_ascii_digit() {
+                j += 1;
+            }
+            let digits_a = trim_leading_zeros(&a[start_a..i]);
+            let digits_b = trim_leading_zeros(&b[start_b..j]);
+            let ordering = digits_a
+                .len()
+                .cmp(&digits_b.len())
+                .then_with(|| digits_a.cmp(digits_b));
+            if ordering != std::cmp::Ordering::Equal {
+                return ordering;
+            }
+        } else {
+            if a[i] != b[j] {
+                return a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j)).then_with(|| a.cmp(b))
 }
 
-/// Move a file atomically.
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&d| d == b'0').count();
+    &digits[zeros..]
+}
+
+/// List directory contents asynchronously.
 ///
-/// Moves a file from source to destination atomically. For cross-device
-/// moves, it will copy atomically and then remove the source. All I/O
-/// operations execute outside the Python GIL using native Tokio, ensuring
-/// true async behavior.
+/// Returns a list of file and directory names in the specified directory.
+/// The listing runs as a single job on Tokio's blocking pool, outside the
+/// Python GIL.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `src` - Path to the source file
-/// * `dst` - Path to the destination file
+/// * `path` - Path to the directory to list
+/// * `sorted` - `None`/`False` for filesystem order, `True` or `"bytes"` to
+///   sort names by their raw bytes, `"natural"` for numeric-aware order
+///   (sorting happens in Rust, off the GIL)
 ///
 /// # Returns
 ///
-/// A coroutine that yields `None` on success.
+/// A coroutine that yields a list of file and directory names (strings).
 ///
 /// # Errors
 ///
-/// Returns `PyFileNotFoundError` if the source file does not exist,
-/// `PyIOError` if the file cannot be moved, or `PyValueError` if the path is invalid.
+/// Returns `PyFileNotFoundError` if the directory does not exist,
+/// `PyIOError` if the directory cannot be read, or `PyValueError` if the path
+/// or sort order is invalid.
 #[pyfunction]
-fn atomic_move_file_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&src)?;
-    validate_path(&dst)?;
+#[pyo3(signature = (path, sorted = None))]
+fn list_dir_async<'a>(
+    py: Python<'a>,
+    path: String,
+    sorted: Option<&Bound<'a, PyAny>>,
+) -> PyResult<Bound<'a, PyAny>> {
+    validate_path(&path)?;
+    let order = SortOrder::from_py(sorted)?;
+    let op = OpContext::new("list_dir", [path.as_str()]);
     let future = async move {
-        let src_clone = src.clone();
-        let dst_clone = dst.clone();
-
-        // Try rename first (atomic on same filesystem)
-        match tokio::fs::rename(&src, &dst).await {
-            Ok(_) => Ok(()),
-            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
-                // Cross-device move: copy atomically then remove
-                use std::path::Path;
-                let dst_path = Path::new(&dst);
-                let dir = dst_path.parent().ok_or_else(|| {
-                    PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                        "Destination path has no parent directory",
-                    )
-                })?;
-
-                let file_name = dst_path.file_name().ok_or_else(|| {
-                    PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                        "Destination path has no file name",
-                    )
-                })?;
-
-                let temp_path = dir.join(format!(".{}.tmp", file_name.to_string_lossy()));
-
-                // Copy to temp file
-                tokio::fs::copy(&src, &temp_path).await.map_err(|e| {
-                    map_io_error(e, &format!("{src_clone} -> {dst_clone}"), "copy file")
-                })?;
-
-                // Atomically replace destination
-                tokio::fs::rename(&temp_path, &dst).await.map_err(|e| {
-                    // Clean up temp file on error (spawn cleanup task)
-                    let temp_cleanup = temp_path.clone();
-                    tokio::spawn(async move {
-                        let _ = tokio::fs::remove_file(&temp_cleanup).await;
-                    });
-                    map_io_error(
-                        e,
-                        &format!("{src_clone} -> {dst_clone}"),
-                        "atomically move file",
-                    )
-                })?;
-
-                // Remove source file (best effort - move already succeeded)
-                if let Err(e) = tokio::fs::remove_file(&src).await {
-                    // Log warning but don't fail - the move was successful
-                    // The source file removal failure is logged but doesn't affect the operation
-                    eprintln!("Warning: Failed to remove source file after atomic move {src_clone} -> {dst_clone}: {e}");
-                }
-                Ok(())
-            }
-            Err(e) => Err(map_io_error(
-                e,
-                &format!("{src_clone} -> {dst_clone}"),
-                "atomically move file",
-            )),
+        let mut names = rapfiles_core::list_dir(&path)
+            .await
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        if order != SortOrder::Unsorted {
+            names = run_blocking(move || {
+                names.sort_by(|a, b| order.compare(a.as_ref(), b.as_ref()));
+                Ok(names)
+            })
+            .await
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
         }
+        Ok(names)
     };
-    future_into_py(py, future)
+    run_op(py, op, future)
 }
 
-// File locking operations
-
-use std::fs::File as StdFile;
-
-/// File lock for advisory file locking.
+/// List a directory together with the metadata of each entry.
 ///
-/// Provides advisory file locks for coordinating access to files across
-/// processes. Supports both shared (read) and exclusive (write) locks.
-/// The lock is automatically released when the object is dropped or when
-/// `release()` is called.
+/// Each entry is stat'ed relative to the open directory (`fstatat` on Unix)
+/// in the same blocking job as the listing, instead of a separate lookup of
+/// every full path. Like `os.scandir().stat()`, only entries whose directory
+/// type is a symlink are stat'ed again to follow the link; a broken link
+/// reports the link itself. Entries removed while listing, and names that
+/// aren't valid UTF-8, are skipped.
 ///
-/// # Example
+/// # Arguments
 ///
-/// ```python
-/// async with rapfiles.lock_file("file.txt", exclusive=True) as lock:
-///     # File is locked here
-///     await rapfiles.write_file("file.txt", "content")
+/// * `py` - Python GIL token
+/// * `path` - Path to the directory to list
+/// * `sorted` - Sort order of the names, as for `list_dir_async`
+///
+/// # Returns
+///
+/// A coroutine that yields a list of `(name, FileMetadata)` tuples.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the directory does not exist,
+/// `PyIOError` if it or an entry cannot be read, or `PyValueError` if the
+/// path or sort order is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, sorted = None))]
+fn list_dir_stat_async<'a>(
+    py: Python<'a>,
+    path: String,
+    sorted: Option<&Bound<'a, PyAny>>,
+) -> PyResult<Bound<'a, PyAny>> {
+    validate_path(&path)?;
+    let order = SortOrder::from_py(sorted)?;
+    let op = OpContext::new("list_dir_stat", [path.as_str()]);
+    let future = async move {
+        let dir = path.clone();
+        run_blocking(move || {
+            let mut entries = Vec::new();
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let Ok(name) = entry.file_name().into_string() else {
+                    continue;
+                };
+                let metadata = match entry.metadata() {
+                    Ok(metadata) if metadata.file_type().is_symlink() => {
+                        std::fs::metadata(entry.path()).unwrap_or(metadata)
+                    }
+                    Ok(metadata) => metadata,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e),
+                };
+                entries.push((name, FileMetadata::from_std(&metadata)));
+            }
+            if order != SortOrder::Unsorted {
+                entries.sort_by(|(a, _), (b, _)| order.compare(a.as_ref(), b.as_ref()));
+            }
+            Ok(entries)
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path, "list directory"))
+    };
+    run_op(py, op, future)
+}
+
+/// Check if a path exists asynchronously.
+#[pyfunction]
+fn exists_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("exists", [path.as_str()]);
+    let future = async move { Ok(tokio::fs::metadata(&path).await.is_ok()) };
+    run_op(py, op, future)
+}
+
+/// Check if a path is a file asynchronously.
+#[pyfunction]
+fn is_file_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("is_file", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let metadata = tokio::fs::metadata(&path).await.map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to get metadata for {path_clone}: {e}"
+            ))
+        })?;
+        Ok(metadata.is_file())
+    };
+    run_op(py, op, future)
+}
+
+/// Check if a path is a directory asynchronously.
+#[pyfunction]
+fn is_dir_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("is_dir", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let metadata = tokio::fs::metadata(&path).await.map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to get metadata for {path_clone}: {e}"
+            ))
+        })?;
+        Ok(metadata.is_dir())
+    };
+    run_op(py, op, future)
+}
+
+/// Parse file mode string to determine open options.
+///
+/// Parses Python file mode strings (e.g., "r", "w+", "rb") and converts them
+/// to flags for Tokio's OpenOptions. Supports both text and binary modes.
+///
+/// # Arguments
+///
+/// * `mode` - File mode string (r, r+, w, w+, a, a+, x, x+, rb, rb+, wb, wb+,
+///   ab, ab+, xb, xb+)
+///
+/// # Returns
+///
+/// The `OpenMode` flags of the mode.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if the mode string is invalid.
+fn parse_mode(mode: &str) -> PyResult<OpenMode> {
+    let (kind, plus) = match mode {
+        "r" | "rb" => ('r', false),
+        "r+" | "rb+" => ('r', true),
+        "w" | "wb" => ('w', false),
+        "w+" | "wb+" => ('w', true),
+        "a" | "ab" => ('a', false),
+        "a+" | "ab+" => ('a', true),
+        "x" | "xb" => ('x', false),
+        "x+" | "xb+" => ('x', true),
+        _ => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid mode: {mode}. Must be one of: r, r+, w, w+, a, a+, x, x+, rb, rb+, wb, wb+, ab, ab+, xb, xb+"
+            )))
+        }
+    };
+    Ok(OpenMode {
+        read: kind == 'r' || plus,
+        write: kind != 'r' || plus,
+        append: kind == 'a',
+        create_new: kind == 'x',
+        truncate: kind == 'w',
+    })
+}
+
+/// Flags of a file mode string, as parsed by `parse_mode`.
+///
+/// "r+" modes write without creating or truncating the file.
+#[derive(Clone, Copy)]
+struct OpenMode {
+    read: bool,
+    /// Set for every mode but "r", including append modes
+    write: bool,
+    /// Writes go to the end of the file ("a" modes)
+    append: bool,
+    /// Fail if the file already exists ("x" modes)
+    create_new: bool,
+    /// Empty the file, or create it if missing ("w" modes)
+    truncate: bool,
+}
+
+/// How an `AsyncFile` handles operations awaited concurrently.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AccessMode {
+    /// Raise instead of waiting when another operation is in progress
+    Exclusive,
+    /// Queue operations behind each other on the shared cursor
+    Serialized,
+    /// Like `Serialized`, plus `pread`/`pwrite` calls that never wait
+    Positional,
+}
+
+impl AccessMode {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "exclusive" => Ok(AccessMode::Exclusive),
+            "serialized" => Ok(AccessMode::Serialized),
+            "positional" => Ok(AccessMode::Positional),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid access mode: {name}. Must be 'exclusive', 'serialized' or 'positional'"
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            AccessMode::Exclusive => "exclusive",
+            AccessMode::Serialized => "serialized",
+            AccessMode::Positional => "positional",
+        }
+    }
+}
+
+/// Replace a handle's file with a new open of `path`.
+///
+/// Pending writes are flushed to the old file first, and positional I/O
+/// switches to the new file. Handles created with an `opener` call it again.
+async fn reopen_handle(
+    file: &mut HandleFile,
+    positional: &std::sync::Mutex<Option<Arc<std::fs::File>>>,
+    path: &str,
+    flags: OpenMode,
+    access: AccessMode,
+    opener: Option<&Py<PyAny>>,
+) -> PyResult<()> {
+    file.flush()
+        .await
+        .map_err(|e| map_io_error(e, path, "flush file"))?;
+    let reopened = match opener {
+        Some(opener) => {
+            let opened = Python::attach(|py| open_with_opener(opener.bind(py), path, flags))?;
+            handle_from_std(opened, access)
+        }
+        None => open_for_mode(path, flags, access)
+            .await
+            .map_err(|e| map_io_error(e, path, "reopen file"))?,
+    };
+    if let HandleFile::Blocking(new_file) = &reopened {
+        if access == AccessMode::Positional {
+            *positional.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::clone(new_file));
+        }
+    }
+    *file = reopened;
+    Ok(())
+}
+
+/// Read-ahead buffer size of handles opened with the default `buffering`.
+const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Open file of an `AsyncFile` with the data line reads fetched past the
+/// end of the last line.
+///
+/// The file position is ahead of the handle's position by the unconsumed
+/// part of the buffer; `lock_handle` seeks back over it before any other
+/// operation uses the file.
+struct OpenHandle {
+    file: HandleFile,
+    read_ahead: Vec<u8>,
+    /// Start of the unconsumed part of `read_ahead`
+    consumed: usize,
+    /// Bytes fetched per read; 0 reads lines without a buffer
+    buffer_size: usize,
+}
+
+impl OpenHandle {
+    fn new(file: HandleFile, buffer_size: usize) -> Self {
+        OpenHandle {
+            file,
+            read_ahead: Vec::new(),
+            consumed: 0,
+            buffer_size,
+        }
+    }
+
+    /// Give the unconsumed read-ahead back to the file by seeking backwards.
+    async fn discard_read_ahead(&mut self) -> std::io::Result<()> {
+        let pending = self.read_ahead.len() - self.consumed;
+        self.read_ahead.clear();
+        self.consumed = 0;
+        if pending > 0 {
+            self.file
+                .seek(std::io::SeekFrom::Current(-(pending as i64)))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Return the unconsumed read-ahead without consuming it, first filling
+    /// it with a single read of at least `size` bytes if it is empty.
+    async fn peek(&mut self, size: usize) -> std::io::Result<&[u8]> {
+        if self.consumed == self.read_ahead.len() {
+            let fetch = self.buffer_size.max(size).max(1);
+            self.read_ahead = self.file.read_up_to(fetch).await?;
+            self.consumed = 0;
+        }
+        Ok(&self.read_ahead[self.consumed..])
+    }
+
+    /// Read at most `size` bytes, or everything buffered if `None`: from the
+    /// read-ahead if it holds any data, otherwise with a single read.
+    async fn read1(&mut self, size: Option<usize>) -> std::io::Result<Vec<u8>> {
+        let available = self.read_ahead.len() - self.consumed;
+        if available == 0 {
+            let fetch = size.unwrap_or(match self.buffer_size {
+                0 => DEFAULT_BUFFER_SIZE,
+                buffer_size => buffer_size,
+            });
+            return self.file.read_up_to(fetch).await;
+        }
+        let taken = size.map_or(available, |size| size.min(available));
+        let data = self.read_ahead[self.consumed..self.consumed + taken].to_vec();
+        self.consumed += taken;
+        Ok(data)
+    }
+
+    /// Read up to and including the next newline, or at most `limit` bytes,
+    /// fetching `buffer_size` bytes at a time.
+    async fn read_line(&mut self, limit: Option<usize>) -> std::io::Result<Vec<u8>> {
+        if self.buffer_size == 0 || matches!(self.file, HandleFile::Preloaded { .. }) {
+            // Data fetched by `peek` is given back first
+            self.discard_read_ahead().await?;
+            return self.file.read_line(limit).await;
+        }
+        let mut line = Vec::new();
+        loop {
+            let available = &self.read_ahead[self.consumed..];
+            let wanted = limit.map_or(available.len(), |limit| {
+                available.len().min(limit - line.len())
+            });
+            let (taken, complete) = match available[..wanted].iter().position(|&b| b == b'\n') {
+                Some(end) => (end + 1, true),
+                None => (
+                    wanted,
+                    limit.is_some_and(|limit| line.len() + wanted >= limit),
+                ),
+            };
+            line.extend_from_slice(&available[..taken]);
+            self.consumed += taken;
+            if complete {
+                return Ok(line);
+            }
+            self.read_ahead = self.file.read_up_to(self.buffer_size).await?;
+            self.consumed = 0;
+            if self.read_ahead.is_empty() {
+                return Ok(line); // EOF
+            }
+        }
+    }
+
+    /// Read lines until EOF, or until `hint` lines have been read.
+    async fn read_lines(&mut self, hint: Option<usize>) -> std::io::Result<Vec<Vec<u8>>> {
+        if self.buffer_size == 0 {
+            self.discard_read_ahead().await?;
+            return self.file.read_lines(hint).await;
+        }
+        let mut lines = Vec::new();
+        loop {
+            let line = self.read_line(None).await?;
+            if line.is_empty() {
+                break; // EOF
+            }
+            lines.push(line);
+            if hint.is_some_and(|hint| lines.len() >= hint) {
+                break;
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Put `data` back in front of the unconsumed read-ahead, so the next
+    /// read returns it first.
+    fn unread(&mut self, mut data: Vec<u8>) {
+        data.extend_from_slice(&self.read_ahead[self.consumed..]);
+        self.read_ahead = data;
+        self.consumed = 0;
+    }
+
+    /// Wait up to `timeout` until a read would not block; false on timeout.
+    async fn wait_readable(&self, timeout: std::time::Duration) -> std::io::Result<bool> {
+        if self.consumed < self.read_ahead.len() {
+            return Ok(true);
+        }
+        self.file.wait_readable(timeout).await
+    }
+}
+
+/// How `read_exact_from` fell short of the requested size.
+enum ReadExactError {
+    Io(std::io::Error),
+    /// End of file after these bytes
+    Eof(Vec<u8>),
+    /// The deadline passed; the bytes read so far were given back to the handle
+    Timeout(usize),
+}
+
+/// Read exactly `size` bytes with as many partial reads as needed.
+///
+/// With a deadline, each read first waits for the file to become readable
+/// (with `poll` on Unix), so no read is left in flight when time runs out.
+async fn read_exact_from(
+    handle: &mut OpenHandle,
+    size: usize,
+    deadline: Option<tokio::time::Instant>,
+) -> Result<Vec<u8>, ReadExactError> {
+    let mut data = Vec::with_capacity(size.min(DEFAULT_BUFFER_SIZE));
+    while data.len() < size {
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            match handle.wait_readable(remaining).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    let read = data.len();
+                    handle.unread(data);
+                    return Err(ReadExactError::Timeout(read));
+                }
+                Err(e) => return Err(ReadExactError::Io(e)),
+            }
+        }
+        match handle.read1(Some(size - data.len())).await {
+            Ok(chunk) if chunk.is_empty() => return Err(ReadExactError::Eof(data)),
+            Ok(chunk) => data.extend_from_slice(&chunk),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(ReadExactError::Io(e)),
+        }
+    }
+    Ok(data)
+}
+
+impl ReadExactError {
+    /// Convert to the Python error: `asyncio.IncompleteReadError` at end of
+    /// file, like `StreamReader.readexactly()`, or `TimeoutError`.
+    fn into_py_err(self, path: &str, size: usize) -> PyErr {
+        match self {
+            ReadExactError::Io(e) => map_io_error(e, path, "read file"),
+            ReadExactError::Eof(partial) => Python::attach(|py| {
+                let error = py
+                    .import("asyncio")
+                    .and_then(|asyncio| asyncio.getattr("IncompleteReadError"))
+                    .and_then(|cls| cls.call1((PyBytes::new(py, &partial), size)));
+                match error {
+                    Ok(error) => PyErr::from_value(error),
+                    Err(e) => e,
+                }
+            }),
+            ReadExactError::Timeout(read) => PyErr::new::<pyo3::exceptions::PyTimeoutError, _>(
+                format!("Timed out reading {size} bytes from {path} after {read} bytes"),
+            ),
+        }
+    }
+}
+
+/// Parse the `timeout` argument of `read_exact` into a deadline.
+fn read_exact_deadline(timeout: Option<f64>) -> PyResult<Option<tokio::time::Instant>> {
+    timeout
+        .map(|timeout| {
+            std::time::Duration::try_from_secs_f64(timeout)
+                .map(|timeout| tokio::time::Instant::now() + timeout)
+                .map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid timeout: {timeout}. Must be a number of seconds of at least 0"
+                    ))
+                })
+        })
+        .transpose()
+}
+
+/// Place of an operation in the queue of an `AsyncFile`, taken when the
+/// operation is called.
+///
+/// The tasks of operations may start in any order, so each one waits until
+/// the operation called before it holds the file (or was dropped) before
+/// asking for the file's lock, which hands it out in request order.
+struct HandleTurn {
+    file: Arc<Mutex<Option<OpenHandle>>>,
+    /// Completes once the previous operation holds the file or is dropped
+    previous: Option<tokio::sync::oneshot::Receiver<()>>,
+    /// Dropped once this operation holds the file, letting the next one queue
+    next: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl HandleTurn {
+    /// Wait for the operations called before this one to take the file.
+    async fn wait(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            // An error only means the previous operation was dropped
+            let _ = previous.await;
+        }
+    }
+}
+
+/// Acquire a handle for a line read, keeping its read-ahead buffer.
+///
+/// # Errors
+///
+/// Same as `lock_handle`.
+async fn lock_open_handle<'a>(
+    turn: &'a mut HandleTurn,
+    access: AccessMode,
+    path: &str,
+) -> PyResult<tokio::sync::MappedMutexGuard<'a, OpenHandle>> {
+    turn.wait().await;
+    let guard = if access == AccessMode::Exclusive {
+        turn.file.try_lock()
+    } else {
+        Ok(turn.file.lock().await)
+    };
+    // Let the next operation queue for the file
+    turn.next.take();
+    let guard = guard.map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+            "Concurrent operation on file {path} opened with access='exclusive'"
+        ))
+    })?;
+    tokio::sync::MutexGuard::try_map(guard, Option::as_mut).map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "I/O operation on closed file {path}"
+        ))
+    })
+}
+
+/// Acquire a handle's file for a cursor-based operation.
+///
+/// Data read ahead by line reads is given back first, so the file position
+/// is the handle's position.
+///
+/// # Errors
+///
+/// Returns `PyRuntimeError` if the handle was opened with
+/// `access="exclusive"` and another operation on it is still in progress,
+/// `PyValueError` if the handle was closed, including while the
+/// operation waited for its turn, or `PyIOError` if the read-ahead cannot
+/// be given back.
+async fn lock_handle<'a>(
+    turn: &'a mut HandleTurn,
+    access: AccessMode,
+    path: &str,
+) -> PyResult<tokio::sync::MappedMutexGuard<'a, HandleFile>> {
+    let mut handle = lock_open_handle(turn, access, path).await?;
+    handle
+        .discard_read_ahead()
+        .await
+        .map_err(|e| map_io_error(e, path, "seek file"))?;
+    Ok(tokio::sync::MappedMutexGuard::map(handle, |handle| {
+        &mut handle.file
+    }))
+}
+
+/// Underlying file of an `AsyncFile`, bound to the backend it was opened with.
+enum HandleFile {
+    Tokio(File),
+    /// Shared so each call can move a reference into its blocking job
+    Blocking(Arc<std::fs::File>),
+    /// Contents of a small read-only file, loaded when it was opened; reads
+    /// past their end continue on `file` (see `HandleFile::unload_consumed`)
+    Preloaded {
+        contents: std::io::Cursor<Vec<u8>>,
+        /// Identity of the file when it was loaded, changed by modifications
+        id: Option<FileId>,
+        file: Arc<std::fs::File>,
+    },
+    /// Descriptor shared with another handle, accessed with positional I/O at
+    /// this handle's own cursor
+    Duplicate {
+        file: Arc<std::fs::File>,
+        pos: u64,
+        append: bool,
+    },
+}
+
+impl HandleFile {
+    async fn read_to_end(&mut self) -> std::io::Result<Vec<u8>> {
+        self.unload_consumed();
+        match self {
+            HandleFile::Tokio(file) => {
+                let mut buffer = Vec::new();
+                file.read_to_end(&mut buffer).await?;
+                Ok(buffer)
+            }
+            HandleFile::Blocking(file) => {
+                let file = Arc::clone(file);
+                run_blocking(move || {
+                    let mut buffer = Vec::new();
+                    std::io::Read::read_to_end(&mut &*file, &mut buffer)?;
+                    Ok(buffer)
+                })
+                .await
+            }
+            HandleFile::Preloaded { contents, .. } => {
+                let mut buffer = Vec::new();
+                std::io::Read::read_to_end(contents, &mut buffer)?;
+                Ok(buffer)
+            }
+            HandleFile::Duplicate { file, pos, .. } => {
+                let (file, start) = (Arc::clone(file), *pos);
+                let buffer = run_blocking(move || {
+                    let mut buffer = Vec::new();
+                    let mut chunk = vec![0u8; 64 * 1024];
+                    loop {
+                        let n = read_full_at(&file, &mut chunk, start + buffer.len() as u64)?;
+                        buffer.extend_from_slice(&chunk[..n]);
+                        if n < chunk.len() {
+                            return Ok(buffer); // EOF
+                        }
+                    }
+                })
+                .await?;
+                *pos += buffer.len() as u64;
+                Ok(buffer)
+            }
+        }
+    }
+
+    /// Perform a single read of at most `size` bytes.
+    async fn read_up_to(&mut self, size: usize) -> std::io::Result<Vec<u8>> {
+        self.unload_consumed();
+        let mut buffer = vec![0u8; size];
+        match self {
+            HandleFile::Tokio(file) => {
+                let n = file.read(&mut buffer).await?;
+                buffer.truncate(n);
+                Ok(buffer)
+            }
+            HandleFile::Blocking(file) => {
+                let file = Arc::clone(file);
+                run_blocking(move || {
+                    let n = std::io::Read::read(&mut &*file, &mut buffer)?;
+                    buffer.truncate(n);
+                    Ok(buffer)
+                })
+                .await
+            }
+            HandleFile::Preloaded { contents, .. } => {
+                let n = std::io::Read::read(contents, &mut buffer)?;
+                buffer.truncate(n);
+                Ok(buffer)
+            }
+            HandleFile::Duplicate { file, pos, .. } => {
+                let (file, offset) = (Arc::clone(file), *pos);
+                let buffer = run_blocking(move || {
+                    let n = read_full_at(&file, &mut buffer, offset)?;
+                    buffer.truncate(n);
+                    Ok(buffer)
+                })
+                .await?;
+                *pos += buffer.len() as u64;
+                Ok(buffer)
+            }
+        }
+    }
+
+    /// Read into the caller's buffer; with `fill`, keep reading until it is
+    /// full or the end of the file is reached, otherwise perform one read.
+    async fn read_into(&mut self, target: Arc<ReadTarget>, fill: bool) -> std::io::Result<usize> {
+        self.unload_consumed();
+        match self {
+            HandleFile::Tokio(file) => {
+                // SAFETY: this is the only slice of the target
+                let buf = unsafe { target.slice() };
+                let mut filled = 0;
+                while filled < buf.len() {
+                    let n = file.read(&mut buf[filled..]).await?;
+                    filled += n;
+                    if n == 0 || !fill {
+                        break;
+                    }
+                }
+                Ok(filled)
+            }
+            HandleFile::Blocking(file) => {
+                let file = Arc::clone(file);
+                // SAFETY: this is the only slice of the target
+                run_blocking(move || read_into_slice(&mut &*file, unsafe { target.slice() }, fill))
+                    .await
+            }
+            HandleFile::Preloaded { contents, .. } => {
+                // SAFETY: this is the only slice of the target
+                read_into_slice(contents, unsafe { target.slice() }, fill)
+            }
+            HandleFile::Duplicate { file, pos, .. } => {
+                let (file, offset) = (Arc::clone(file), *pos);
+                // SAFETY: this is the only slice of the target
+                let n =
+                    run_blocking(move || read_full_at(&file, unsafe { target.slice() }, offset))
+                        .await?;
+                *pos += n as u64;
+                Ok(n)
+            }
+        }
+    }
+
+    /// Read up to and including the next newline, or at most `limit` bytes.
+    async fn read_line(&mut self, limit: Option<usize>) -> std::io::Result<Vec<u8>> {
+        self.unload_consumed();
+        match self {
+            HandleFile::Tokio(file) => {
+                // Read in chunks like `read_line_blocking`, giving back the
+                // bytes past the end of the line by seeking backwards
+                let mut line = Vec::new();
+                let mut chunk = vec![0u8; 8192];
+                loop {
+                    let wanted =
+                        limit.map_or(chunk.len(), |limit| chunk.len().min(limit - line.len()));
+                    let n = file.read(&mut chunk[..wanted]).await?;
+                    if n == 0 {
+                        return Ok(line); // EOF
+                    }
+                    if let Some(end) = chunk[..n].iter().position(|&b| b == b'\n') {
+                        line.extend_from_slice(&chunk[..=end]);
+                        let excess = (n - end - 1) as i64;
+                        if excess > 0 {
+                            file.seek(std::io::SeekFrom::Current(-excess)).await?;
+                        }
+                        return Ok(line);
+                    }
+                    line.extend_from_slice(&chunk[..n]);
+                    if limit.is_some_and(|limit| line.len() >= limit) {
+                        return Ok(line);
+                    }
+                }
+            }
+            HandleFile::Blocking(file) => {
+                let file = Arc::clone(file);
+                run_blocking(move || read_line_blocking(&file, limit)).await
+            }
+            HandleFile::Preloaded { contents, .. } => {
+                use std::io::BufRead;
+
+                let mut line = Vec::new();
+                match limit {
+                    Some(limit) => {
+                        std::io::Read::take(contents, limit as u64).read_until(b'\n', &mut line)?
+                    }
+                    None => contents.read_until(b'\n', &mut line)?,
+                };
+                Ok(line)
+            }
+            HandleFile::Duplicate { file, pos, .. } => {
+                let (file, offset) = (Arc::clone(file), *pos);
+                let line = run_blocking(move || read_line_at(&file, offset, limit)).await?;
+                *pos += line.len() as u64;
+                Ok(line)
+            }
+        }
+    }
+
+    /// Read lines until EOF, or until `hint` lines have been read.
+    async fn read_lines(&mut self, hint: Option<usize>) -> std::io::Result<Vec<Vec<u8>>> {
+        let file = match self {
+            HandleFile::Blocking(file) => Arc::clone(file),
+            _ => {
+                let mut lines = Vec::new();
+                loop {
+                    let line = self.read_line(None).await?;
+                    if line.is_empty() {
+                        break; // EOF
+                    }
+                    lines.push(line);
+                    if hint.is_some_and(|hint| lines.len() >= hint) {
+                        break;
+                    }
+                }
+                return Ok(lines);
+            }
+        };
+        run_blocking(move || {
+            let mut lines = Vec::new();
+            loop {
+                let line = read_line_blocking(&file, None)?;
+                if line.is_empty() {
+                    break; // EOF
+                }
+                lines.push(line);
+                if hint.is_some_and(|hint| lines.len() >= hint) {
+                    break;
+                }
+            }
+            Ok(lines)
+        })
+        .await
+    }
+
+    async fn write_all(&mut self, bytes: Vec<u8>) -> std::io::Result<()> {
+        match self {
+            HandleFile::Tokio(file) => file.write_all(&bytes).await,
+            HandleFile::Blocking(file) => {
+                let file = Arc::clone(file);
+                run_blocking(move || std::io::Write::write_all(&mut &*file, &bytes)).await
+            }
+            HandleFile::Preloaded { .. } => Err(std::io::Error::other("file not open for writing")),
+            HandleFile::Duplicate { file, pos, append } => {
+                let (file, offset, append) = (Arc::clone(file), *pos, *append);
+                *pos = run_blocking(move || {
+                    if append {
+                        // Append mode writes always go to the end of the file
+                        std::io::Write::write_all(&mut &*file, &bytes)?;
+                        Ok(file.metadata()?.len())
+                    } else {
+                        write_full_at(&file, &bytes, offset)?;
+                        Ok(offset + bytes.len() as u64)
+                    }
+                })
+                .await?;
+                Ok(())
+            }
+        }
+    }
+
+    async fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self {
+            HandleFile::Tokio(file) => file.seek(pos).await,
+            HandleFile::Blocking(file) => {
+                let file = Arc::clone(file);
+                run_blocking(move || std::io::Seek::seek(&mut &*file, pos)).await
+            }
+            HandleFile::Preloaded { contents, .. } => std::io::Seek::seek(contents, pos),
+            HandleFile::Duplicate {
+                file, pos: cursor, ..
+            } => {
+                let target = match pos {
+                    std::io::SeekFrom::Start(offset) => Some(offset),
+                    std::io::SeekFrom::Current(delta) => cursor.checked_add_signed(delta),
+                    std::io::SeekFrom::End(delta) => {
+                        let file = Arc::clone(file);
+                        let len = run_blocking(move || Ok(file.metadata()?.len())).await?;
+                        len.checked_add_signed(delta)
+                    }
+                };
+                *cursor = target.ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "invalid seek to a negative position",
+                    )
+                })?;
+                Ok(*cursor)
+            }
+        }
+    }
+
+    async fn stream_position(&mut self) -> std::io::Result<u64> {
+        self.seek(std::io::SeekFrom::Current(0)).await
+    }
+
+    /// Resize the file to `size` bytes, or to the current position if `None`.
+    ///
+    /// The position is left unchanged. Returns the new size.
+    async fn set_len(&mut self, size: Option<u64>) -> std::io::Result<u64> {
+        match self {
+            HandleFile::Tokio(file) => {
+                file.flush().await?;
+                let size = match size {
+                    Some(size) => size,
+                    None => file.stream_position().await?,
+                };
+                file.set_len(size).await?;
+                Ok(size)
+            }
+            HandleFile::Blocking(file) => {
+                let file = Arc::clone(file);
+                run_blocking(move || {
+                    let size = match size {
+                        Some(size) => size,
+                        None => std::io::Seek::stream_position(&mut &*file)?,
+                    };
+                    file.set_len(size)?;
+                    Ok(size)
+                })
+                .await
+            }
+            HandleFile::Preloaded { .. } => Err(std::io::Error::other("file not open for writing")),
+            HandleFile::Duplicate { file, pos, .. } => {
+                let (file, size) = (Arc::clone(file), size.unwrap_or(*pos));
+                run_blocking(move || file.set_len(size)).await?;
+                Ok(size)
+            }
+        }
+    }
+
+    /// Flush buffered writes (a no-op for the handles that do not buffer them).
+    async fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            HandleFile::Tokio(file) => file.flush().await,
+            HandleFile::Blocking(_)
+            | HandleFile::Preloaded { .. }
+            | HandleFile::Duplicate { .. } => Ok(()),
+        }
+    }
+
+    async fn sync_all(&mut self) -> std::io::Result<()> {
+        match self {
+            HandleFile::Tokio(file) => file.sync_all().await,
+            HandleFile::Blocking(file) | HandleFile::Duplicate { file, .. } => {
+                let file = Arc::clone(file);
+                run_blocking(move || file.sync_all()).await
+            }
+            HandleFile::Preloaded { .. } => Ok(()),
+        }
+    }
+
+    async fn sync_data(&mut self) -> std::io::Result<()> {
+        match self {
+            HandleFile::Tokio(file) => file.sync_data().await,
+            HandleFile::Blocking(file) | HandleFile::Duplicate { file, .. } => {
+                let file = Arc::clone(file);
+                run_blocking(move || file.sync_data()).await
+            }
+            HandleFile::Preloaded { .. } => Ok(()),
+        }
+    }
+
+    /// Identity of the open file, to tell whether its path now names another file.
+    async fn identity(&self) -> std::io::Result<Option<FileId>> {
+        match self {
+            HandleFile::Tokio(file) => Ok(file_id(&file.metadata().await?, true)),
+            HandleFile::Blocking(file) | HandleFile::Duplicate { file, .. } => {
+                let file = Arc::clone(file);
+                run_blocking(move || Ok(file_id(&file.metadata()?, true))).await
+            }
+            HandleFile::Preloaded { id, .. } => Ok(*id),
+        }
+    }
+
+    /// Wait up to `timeout` until a read would not block; false on timeout.
+    ///
+    /// Regular files are always readable. Platforms other than Unix can't
+    /// wait and always report the file readable.
+    async fn wait_readable(&self, timeout: std::time::Duration) -> std::io::Result<bool> {
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+
+            let fd = match self {
+                HandleFile::Tokio(file) => file.as_raw_fd(),
+                HandleFile::Blocking(file) | HandleFile::Duplicate { file, .. } => file.as_raw_fd(),
+                HandleFile::Preloaded { .. } => return Ok(true),
+            };
+            let millis = i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX);
+            // The caller holds the handle, so `fd` stays open while polling
+            run_blocking(move || {
+                let mut pollfd = libc::pollfd {
+                    fd,
+                    events: libc::POLLIN,
+                    revents: 0,
+                };
+                loop {
+                    // SAFETY: `pollfd` is a valid array of one entry
+                    match unsafe { libc::poll(&mut pollfd, 1, millis) } {
+                        -1 if std::io::Error::last_os_error().kind()
+                            == std::io::ErrorKind::Interrupted => {}
+                        -1 => return Err(std::io::Error::last_os_error()),
+                        // Ready to read, at EOF (POLLHUP) or failed (POLLERR)
+                        ready => return Ok(ready > 0),
+                    }
+                }
+            })
+            .await
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = timeout;
+            Ok(true)
+        }
+    }
+
+    /// Whether the open file supports seeking; false for pipes, sockets and
+    /// terminals.
+    fn is_seekable(&self) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+
+            let fd = match self {
+                HandleFile::Tokio(file) => file.as_raw_fd(),
+                HandleFile::Blocking(file) | HandleFile::Duplicate { file, .. } => file.as_raw_fd(),
+                HandleFile::Preloaded { .. } => return true,
+            };
+            // SAFETY: `fd` stays open while `self` is borrowed, and querying
+            // the offset does not move it
+            unsafe { libc::lseek(fd, 0, libc::SEEK_CUR) != -1 }
+        }
+        #[cfg(not(unix))]
+        {
+            true
+        }
+    }
+
+    /// Descriptor of the open file. Preloaded contents are dropped and the
+    /// file they were loaded from is positioned where they were read up to.
+    #[cfg(unix)]
+    fn raw_fd(&mut self) -> std::io::Result<i32> {
+        use std::os::fd::AsRawFd;
+
+        if let HandleFile::Preloaded { contents, file, .. } = self {
+            let pos = std::io::SeekFrom::Start(contents.position());
+            std::io::Seek::seek(&mut &**file, pos)?;
+            *self = HandleFile::Blocking(Arc::clone(file));
+        }
+        Ok(match self {
+            HandleFile::Tokio(file) => file.as_raw_fd(),
+            HandleFile::Blocking(file) | HandleFile::Duplicate { file, .. } => file.as_raw_fd(),
+            HandleFile::Preloaded { .. } => unreachable!("preloaded contents were replaced"),
+        })
+    }
+
+    /// Once the contents of a preloaded file are consumed, continue reading
+    /// the file itself at the same position, so data appended after it was
+    /// opened is read like on any other handle.
+    ///
+    /// Handles from `dup()` share the descriptor, so reads are positional.
+    fn unload_consumed(&mut self) {
+        if let HandleFile::Preloaded { contents, file, .. } = self {
+            if contents.position() >= contents.get_ref().len() as u64 {
+                *self = HandleFile::Duplicate {
+                    file: Arc::clone(file),
+                    pos: contents.position(),
+                    append: false,
+                };
+            }
+        }
+    }
+
+    /// Create a handle on the same open file with its own cursor, starting
+    /// at this handle's position.
+    async fn duplicate(&mut self, append: bool) -> std::io::Result<HandleFile> {
+        match self {
+            HandleFile::Tokio(file) => {
+                // Make pending writes visible through the duplicate
+                file.flush().await?;
+                let pos = file.stream_position().await?;
+                let file = file.try_clone().await?.into_std().await;
+                Ok(HandleFile::Duplicate {
+                    file: Arc::new(file),
+                    pos,
+                    append,
+                })
+            }
+            HandleFile::Blocking(file) => {
+                let shared = Arc::clone(file);
+                let pos =
+                    run_blocking(move || std::io::Seek::stream_position(&mut &*shared)).await?;
+                Ok(HandleFile::Duplicate {
+                    file: Arc::clone(file),
+                    pos,
+                    append,
+                })
+            }
+            HandleFile::Preloaded { contents, id, file } => Ok(HandleFile::Preloaded {
+                contents: contents.clone(),
+                id: *id,
+                file: Arc::clone(file),
+            }),
+            HandleFile::Duplicate { file, pos, append } => Ok(HandleFile::Duplicate {
+                file: Arc::clone(file),
+                pos: *pos,
+                append: *append,
+            }),
+        }
+    }
+}
+
+/// Identity of the file behind a handle.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct FileId {
+    dev: u64,
+    ino: u64,
+    /// Status change time (seconds, nanoseconds), for files no longer held
+    /// open, whose inode number may be reused by a new file
+    changed: Option<(i64, i64)>,
+}
+
+/// Identify the file described by `metadata`; `None` where the platform
+/// offers no stable identity.
+fn file_id(metadata: &std::fs::Metadata, held_open: bool) -> Option<FileId> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(FileId {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+            changed: (!held_open).then(|| (metadata.ctime(), metadata.ctime_nsec())),
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (metadata, held_open);
+        None
+    }
+}
+
+/// Whether `metadata` describes the file identified by `id`.
+fn is_same_file(id: Option<FileId>, metadata: &std::fs::Metadata) -> bool {
+    id.is_some_and(|id| file_id(metadata, id.changed.is_none()) == Some(id))
+}
+
+/// Open a file with the backend of the running operation.
+async fn open_handle(options: std::fs::OpenOptions, path: &str) -> std::io::Result<HandleFile> {
+    match current_backend() {
+        Backend::Tokio => tokio::fs::OpenOptions::from(options)
+            .open(path)
+            .await
+            .map(HandleFile::Tokio),
+        Backend::ThreadPool => {
+            let path = path.to_string();
+            run_blocking(move || options.open(path))
+                .await
+                .map(|file| HandleFile::Blocking(Arc::new(file)))
+        }
+    }
+}
+
+/// Open a file for reading, loading it whole if it is at most `threshold` bytes.
+///
+/// Small files are opened and read in a single blocking job, so reading
+/// them through the returned handle needs no further I/O until their
+/// contents are consumed. Larger files (and non-regular files) get a normal
+/// handle for the running operation's backend.
+async fn open_read_handle(
+    options: std::fs::OpenOptions,
+    path: &str,
+    threshold: u64,
+) -> std::io::Result<HandleFile> {
+    let backend = current_backend();
+    let path = path.to_string();
+    run_blocking(move || {
+        use std::io::{Read, Seek};
+
+        let mut file = options.open(path)?;
+        let metadata = file.metadata()?;
+        if metadata.is_file() && metadata.len() <= threshold {
+            let mut contents = Vec::with_capacity(metadata.len() as usize);
+            (&mut file).take(threshold + 1).read_to_end(&mut contents)?;
+            if contents.len() as u64 <= threshold {
+                return Ok(HandleFile::Preloaded {
+                    contents: std::io::Cursor::new(contents),
+                    id: file_id(&metadata, false),
+                    file: Arc::new(file),
+                });
+            }
+            // Grew past the threshold while being read
+            file.rewind()?;
+        }
+        Ok(match backend {
+            Backend::Tokio => HandleFile::Tokio(File::from_std(file)),
+            Backend::ThreadPool => HandleFile::Blocking(Arc::new(file)),
+        })
+    })
+    .await
+}
+
+/// Open `path` for an `AsyncFile` with the flags of its mode.
+async fn open_for_mode(
+    path: &str,
+    OpenMode {
+        read,
+        write,
+        append,
+        create_new,
+        truncate,
+    }: OpenMode,
+    access: AccessMode,
+) -> std::io::Result<HandleFile> {
+    let mut open_options = std::fs::OpenOptions::new();
+    open_options.read(read);
+    open_options.write(write || append);
+    open_options.create(truncate || append);
+    open_options.truncate(truncate);
+    open_options.append(append);
+    open_options.create_new(create_new);
+
+    let threshold = SMALL_FILE_THRESHOLD.load(Ordering::Relaxed);
+    if access == AccessMode::Positional {
+        // Positional I/O needs a plain descriptor shared outside the lock
+        let path = path.to_string();
+        run_blocking(move || open_options.open(path))
+            .await
+            .map(|file| HandleFile::Blocking(Arc::new(file)))
+    } else if read && !write && !append && threshold > 0 {
+        open_read_handle(open_options, path, threshold).await
+    } else {
+        open_handle(open_options, path).await
+    }
+}
+
+/// Wrap a file opened outside `open_for_mode` for the running operation's backend.
+fn handle_from_std(file: std::fs::File, access: AccessMode) -> HandleFile {
+    match (access, current_backend()) {
+        (AccessMode::Positional, _) | (_, Backend::ThreadPool) => {
+            HandleFile::Blocking(Arc::new(file))
+        }
+        (_, Backend::Tokio) => HandleFile::Tokio(File::from_std(file)),
+    }
+}
+
+/// Call a Python `opener(path, flags)` and take ownership of the descriptor it returns.
+///
+/// `flags` are the `os.O_*` flags Python's built-in `open()` would pass for
+/// the same mode.
+///
+/// # Errors
+///
+/// Propagates exceptions raised by the opener, returns `PyValueError` if it
+/// returns a negative descriptor, or `PyNotImplementedError` on platforms
+/// other than Unix.
+fn open_with_opener(
+    opener: &Bound<'_, PyAny>,
+    path: &str,
+    OpenMode {
+        read,
+        write,
+        append,
+        create_new,
+        truncate,
+    }: OpenMode,
+) -> PyResult<std::fs::File> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::FromRawFd;
+
+        let mut flags = match (read, write || append) {
+            (true, true) => libc::O_RDWR,
+            (false, true) => libc::O_WRONLY,
+            _ => libc::O_RDONLY,
+        };
+        if truncate || append || create_new {
+            flags |= libc::O_CREAT;
+        }
+        if create_new {
+            flags |= libc::O_EXCL;
+        } else if truncate {
+            flags |= libc::O_TRUNC;
+        }
+        if append {
+            flags |= libc::O_APPEND;
+        }
+        flags |= libc::O_CLOEXEC;
+
+        let fd: i32 = opener.call1((path, flags))?.extract()?;
+        if fd < 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "opener returned {fd}"
+            )));
+        }
+        // SAFETY: the opener contract hands ownership of the returned descriptor to the file object
+        Ok(unsafe { std::fs::File::from_raw_fd(fd) })
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (opener, path, read, write, append, create_new, truncate);
+        Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "opener is only supported on Unix",
+        ))
+    }
+}
+
+/// Flush an `AsyncFile` and duplicate its descriptor, positioned where the
+/// handle's cursor is, for `detach_fd()`.
+///
+/// # Errors
+///
+/// Returns the errors of `lock_handle`, or `PyIOError` if the file cannot be
+/// flushed, positioned or duplicated.
+async fn detach_handle_fd(turn: &mut HandleTurn, access: AccessMode, path: &str) -> PyResult<i32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::{BorrowedFd, IntoRawFd};
+
+        let mut file_guard = lock_handle(turn, access, path).await?;
+        file_guard
+            .flush()
+            .await
+            .map_err(|e| map_io_error(e, path, "flush file"))?;
+        let pos = if file_guard.is_seekable() {
+            let pos = file_guard
+                .stream_position()
+                .await
+                .map_err(|e| map_io_error(e, path, "get position in file"))?;
+            Some(pos)
+        } else {
+            None
+        };
+        let fd = file_guard
+            .raw_fd()
+            .map_err(|e| map_io_error(e, path, "get the descriptor of"))?;
+        // SAFETY: the descriptor belongs to the locked handle and stays open
+        // while it is duplicated
+        let owned = unsafe { BorrowedFd::borrow_raw(fd) }
+            .try_clone_to_owned()
+            .map_err(|e| map_io_error(e, path, "duplicate"))?;
+        let mut detached = std::fs::File::from(owned);
+        if let Some(pos) = pos {
+            // Duplicates share their position, so this also moves the
+            // descriptor of a handle created by `dup()`
+            std::io::Seek::seek(&mut detached, std::io::SeekFrom::Start(pos))
+                .map_err(|e| map_io_error(e, path, "seek file"))?;
+        }
+        Ok(detached.into_raw_fd())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (file, access, path);
+        Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "file descriptors are only supported on Unix",
+        ))
+    }
+}
+
+/// Wrap a file descriptor passed to `open()` in place of a path.
+///
+/// With `closefd` the handle takes ownership of `fd`; otherwise it uses a
+/// duplicate sharing the same open file, so closing the handle leaves `fd`
+/// open. Append modes start at the end of the file, like the built-in
+/// `open()`.
+///
+/// # Errors
+///
+/// Returns `PyOSError` if `fd` is not an open descriptor, or
+/// `PyNotImplementedError` on platforms other than Unix.
+fn file_from_fd(fd: i32, closefd: bool, append: bool, label: &str) -> PyResult<std::fs::File> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::{BorrowedFd, FromRawFd};
+
+        // SAFETY: only queries the descriptor flags
+        if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+            return Err(map_io_error(
+                std::io::Error::last_os_error(),
+                label,
+                "open file",
+            ));
+        }
+        let mut file = if closefd {
+            // SAFETY: `fd` is open, and closefd=True hands its ownership to the handle
+            unsafe { std::fs::File::from_raw_fd(fd) }
+        } else {
+            // SAFETY: `fd` is open and stays open while it is duplicated
+            let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+            let owned = borrowed
+                .try_clone_to_owned()
+                .map_err(|e| map_io_error(e, label, "duplicate"))?;
+            std::fs::File::from(owned)
+        };
+        if append {
+            std::io::Seek::seek(&mut file, std::io::SeekFrom::End(0))
+                .map_err(|e| map_io_error(e, label, "seek"))?;
+        }
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (fd, closefd, append, label);
+        Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "opening a file descriptor is only supported on Unix",
+        ))
+    }
+}
+
+/// File argument of `open_file`: a path, or a descriptor to wrap.
+#[derive(FromPyObject)]
+enum FileArg {
+    Fd(i32),
+    Path(String),
+}
+
+/// Largest file, in bytes, that `open()` loads whole when opened read-only;
+/// 0 disables the fast path. Defaults to the read-ahead of a regular
+/// handle, so loading a file costs no more than its first buffered read.
+static SMALL_FILE_THRESHOLD: AtomicU64 = AtomicU64::new(DEFAULT_BUFFER_SIZE as u64);
+
+/// Set the size up to which read-only `open()` calls load the file whole.
+///
+/// Opening such a file costs a single blocking job and its reads are
+/// served from memory, removing most of the per-operation overhead for
+/// small files. Once the loaded contents are consumed, reads continue on
+/// the file, so data appended after it was opened is still read.
+///
+/// # Arguments
+///
+/// * `size` - Threshold in bytes (64 KiB by default); 0 disables the fast path
+#[pyfunction]
+fn set_small_file_threshold(size: u64) {
+    SMALL_FILE_THRESHOLD.store(size, Ordering::Relaxed);
+}
+
+/// Return the size up to which read-only `open()` calls load the file whole.
+#[pyfunction]
+fn get_small_file_threshold() -> u64 {
+    SMALL_FILE_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Read a line from a blocking file in chunks.
+///
+/// Bytes read past the end of the line are given back by seeking backwards,
+/// so the file position ends up right after the returned line.
+fn read_line_blocking(file: &std::fs::File, limit: Option<usize>) -> std::io::Result<Vec<u8>> {
+    let mut reader = file;
+    let mut line = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let wanted = limit.map_or(chunk.len(), |limit| chunk.len().min(limit - line.len()));
+        let n = std::io::Read::read(&mut reader, &mut chunk[..wanted])?;
+        if n == 0 {
+            return Ok(line); // EOF
+        }
+        if let Some(end) = chunk[..n].iter().position(|&b| b == b'\n') {
+            line.extend_from_slice(&chunk[..=end]);
+            let excess = (n - end - 1) as i64;
+            if excess > 0 {
+                std::io::Seek::seek(&mut reader, std::io::SeekFrom::Current(-excess))?;
+            }
+            return Ok(line);
+        }
+        line.extend_from_slice(&chunk[..n]);
+        if limit.is_some_and(|limit| line.len() >= limit) {
+            return Ok(line);
+        }
+    }
+}
+
+/// Read a line at `offset` with positional reads, leaving no file position behind.
+fn read_line_at(
+    file: &std::fs::File,
+    offset: u64,
+    limit: Option<usize>,
+) -> std::io::Result<Vec<u8>> {
+    let mut line = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let wanted = limit.map_or(chunk.len(), |limit| chunk.len().min(limit - line.len()));
+        let n = read_full_at(file, &mut chunk[..wanted], offset + line.len() as u64)?;
+        if let Some(end) = chunk[..n].iter().position(|&b| b == b'\n') {
+            line.extend_from_slice(&chunk[..=end]);
+            return Ok(line);
+        }
+        line.extend_from_slice(&chunk[..n]);
+        if n < wanted || limit.is_some_and(|limit| line.len() >= limit) {
+            return Ok(line); // EOF or size limit
+        }
+    }
+}
+
+/// Writable, C-contiguous memory of a Python buffer, filled by `readinto()`
+/// without holding the GIL. Holding the buffer keeps its exporter (e.g. a
+/// `bytearray`) from resizing or freeing the memory.
+struct ReadTarget(pyo3::buffer::PyBuffer<u8>);
+
+impl ReadTarget {
+    /// Export the buffer of `data` for `method`; buffers of other item types
+    /// (e.g. a numpy float array) are filled as raw bytes.
+    fn new(data: &Bound<'_, PyAny>, method: &str) -> PyResult<Self> {
+        let type_error = || {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                "{method}() argument must be read-write bytes-like object, not {}",
+                data.get_type()
+                    .name()
+                    .map_or_else(|_| "object".to_string(), |name| name.to_string())
+            ))
+        };
+        let buffer = match pyo3::buffer::PyBuffer::<u8>::get(data) {
+            Ok(buffer) if buffer.is_c_contiguous() => buffer,
+            _ => {
+                let view = pyo3::types::PyMemoryView::from(data).map_err(|_| type_error())?;
+                pyo3::buffer::PyBuffer::<u8>::get(&view.call_method1("cast", ("B",))?)?
+            }
+        };
+        if buffer.readonly() {
+            return Err(type_error());
+        }
+        Ok(ReadTarget(buffer))
+    }
+
+    /// The buffer's memory.
+    ///
+    /// # Safety
+    ///
+    /// No other slice of the buffer may be alive at the same time. Python code
+    /// writing to the buffer during a read races with it, as with the
+    /// built-in `readinto()`, which also releases the GIL.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn slice(&self) -> &mut [u8] {
+        std::slice::from_raw_parts_mut(self.0.buf_ptr().cast::<u8>(), self.0.len_bytes())
+    }
+}
+
+/// Read from `reader` into `buf`; with `fill`, until it is full or at EOF.
+fn read_into_slice(
+    reader: &mut impl std::io::Read,
+    buf: &mut [u8],
+    fill: bool,
+) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break, // EOF
+            Ok(n) => {
+                filled += n;
+                if !fill {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Append the contents of a Python `bytes` or `str` object (as UTF-8) to
+/// `buffer`; returns false if `data` is neither.
+fn extend_with_data(buffer: &mut Vec<u8>, data: &Bound<'_, PyAny>) -> PyResult<bool> {
+    if let Ok(py_bytes) = data.cast::<PyBytes>() {
+        buffer.extend_from_slice(py_bytes.as_bytes());
+    } else if let Ok(py_str) = data.cast::<PyString>() {
+        buffer.extend_from_slice(py_str.to_cow()?.as_bytes());
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Async file handle for true async I/O operations.
+///
+/// Provides file handle operations with true async I/O backed by Tokio.
+/// All operations execute outside the Python GIL, ensuring event loops
+/// never stall. Supports both text and binary modes, and can be used
+/// as an async context manager.
+///
+/// # Example
+///
+/// ```python
+/// async with rapfiles.open("file.txt", "r") as f:
+///     content = await f.read()
+/// ```
+#[pyclass]
+struct AsyncFile {
+    /// `None` once closed, which releases the descriptor
+    file: Arc<Mutex<Option<OpenHandle>>>,
+    /// Completes once the last operation called holds `file`, see `HandleTurn`
+    last_turn: std::sync::Mutex<Option<tokio::sync::oneshot::Receiver<()>>>,
+    path: String,
+    mode: String,
+    access: AccessMode,
+    /// Same file as `file`, for `pread`/`pwrite` without taking its lock
+    /// (only with `AccessMode::Positional`); replaced when the file is reopened
+    positional: Arc<std::sync::Mutex<Option<Arc<std::fs::File>>>>,
+    /// Python `opener(path, flags)` the file was opened with, reused by `reopen`
+    opener: Option<Arc<Py<PyAny>>>,
+    /// Descriptor passed to `open()` in place of a path; such handles have
+    /// no path to reopen
+    fd: Option<i32>,
+    /// Flush after each write containing a newline (text mode, `buffering=1`)
+    line_buffering: bool,
+    /// Key of this handle in `OPEN_HANDLES`
+    handle_id: u64,
+    /// Process that opened the handle; a child forked from it may not use it
+    pid: u32,
+    /// Set by `close()` and `__aexit__`; I/O on a closed handle raises
+    closed: AtomicBool,
+    /// Whether the file opened supports seeking
+    seekable: bool,
+    /// Read-ahead buffer size for line reads, from the `buffering` argument
+    buffer_size: usize,
+}
+
+/// `AsyncFile` handle that has not been closed, as listed by `open_files`.
+///
+/// # Properties
+///
+/// * `path` - Path the file was opened with
+/// * `mode` - Mode the file was opened with
+/// * `opened_at` - Unix timestamp of the open call
+/// * `stack` - Python stack of the open call, if stack capture was enabled
+#[pyclass]
+#[derive(Clone)]
+struct OpenFileInfo {
+    path: String,
+    mode: String,
+    opened_at: f64,
+    stack: Option<String>,
+}
+
+#[pymethods]
+impl OpenFileInfo {
+    #[getter]
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    #[getter]
+    fn mode(&self) -> String {
+        self.mode.clone()
+    }
+
+    #[getter]
+    fn opened_at(&self) -> f64 {
+        self.opened_at
+    }
+
+    #[getter]
+    fn stack(&self) -> Option<String> {
+        self.stack.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "OpenFileInfo(path={:?}, mode={:?}, opened_at={})",
+            self.path, self.mode, self.opened_at
+        )
+    }
+}
+
+/// Handles neither closed nor dropped, keyed by handle id (i.e. open order).
+static OPEN_HANDLES: std::sync::Mutex<std::collections::BTreeMap<u64, OpenFileInfo>> =
+    std::sync::Mutex::new(std::collections::BTreeMap::new());
+static NEXT_HANDLE_ID: AtomicU64 = AtomicU64::new(1);
+static CAPTURE_OPEN_STACKS: AtomicBool = AtomicBool::new(false);
+
+impl AsyncFile {
+    /// Wrap an opened file, tracking it as open until closed or dropped.
+    #[allow(clippy::too_many_arguments)] // Everything a handle keeps from open()
+    fn new_open(
+        file: HandleFile,
+        path: String,
+        mode: String,
+        access: AccessMode,
+        opener: Option<Arc<Py<PyAny>>>,
+        line_buffering: bool,
+        buffer_size: usize,
+        stack: Option<String>,
+    ) -> Self {
+        let handle_id = NEXT_HANDLE_ID.fetch_add(1, Ordering::Relaxed);
+        let opened_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        let info = OpenFileInfo {
+            path: path.clone(),
+            mode: mode.clone(),
+            opened_at,
+            stack,
+        };
+        OPEN_HANDLES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(handle_id, info);
+        let positional = match (&file, access) {
+            (
+                HandleFile::Blocking(file) | HandleFile::Duplicate { file, .. },
+                AccessMode::Positional,
+            ) => Some(Arc::clone(file)),
+            _ => None,
+        };
+        let seekable = file.is_seekable();
+        // Data read ahead of a pipe could not be given back
+        let read_ahead = if seekable { buffer_size } else { 0 };
+        AsyncFile {
+            file: Arc::new(Mutex::new(Some(OpenHandle::new(file, read_ahead)))),
+            last_turn: std::sync::Mutex::new(None),
+            path,
+            mode,
+            access,
+            positional: Arc::new(std::sync::Mutex::new(positional)),
+            opener,
+            fd: None,
+            line_buffering,
+            handle_id,
+            pid: std::process::id(),
+            closed: AtomicBool::new(false),
+            seekable,
+            buffer_size,
+        }
+    }
+
+    /// Take the next place in the handle's queue for an operation being called.
+    fn enqueue(&self) -> HandleTurn {
+        let (next, last) = tokio::sync::oneshot::channel();
+        let mut last_turn = self.last_turn.lock().unwrap_or_else(|e| e.into_inner());
+        HandleTurn {
+            file: Arc::clone(&self.file),
+            previous: last_turn.replace(last),
+            next: Some(next),
+        }
+    }
+
+    /// Check that the handle has not been closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` after `close()` or the end of the `async with`
+    /// block, like Python's own file objects, and `PyRuntimeError` in a
+    /// child process forked after the handle was opened: the child shares
+    /// the file position with the parent, and an operation the parent had
+    /// in progress would keep the handle locked forever.
+    fn check_open(&self) -> PyResult<()> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "I/O operation on closed file {}",
+                self.path
+            )));
+        }
+        if self.pid != std::process::id() {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "File {} was opened in process {} before fork(); open it again in this process",
+                self.path, self.pid
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that the handle was opened from a path, for `op_name` to open again.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` for handles opened from a file descriptor.
+    fn check_has_path(&self, op_name: &str) -> PyResult<()> {
+        if self.fd.is_some() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Cannot {op_name} {}: it was opened from a file descriptor",
+                self.path
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read into `buffer` as operation `name`, see `HandleFile::read_into`.
+    fn read_into_op<'a>(
+        &self,
+        py: Python<'a>,
+        name: &'static str,
+        buffer: &Bound<'a, PyAny>,
+        fill: bool,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let target = Arc::new(ReadTarget::new(buffer, name)?);
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+
+        let op = OpContext::new(name, [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+            let n = file_guard
+                .read_into(target, fill)
+                .await
+                .map_err(|e| map_io_error(e, &path, "read file"))?;
+            record_transfer(n);
+            Ok(n)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Write `bytes` at the handle's position, flushing them on line-buffered
+    /// files if they contain a newline; yields the number of bytes written.
+    fn write_future(
+        &self,
+        bytes: Vec<u8>,
+    ) -> impl std::future::Future<Output = PyResult<i64>> + Send + 'static {
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+        let line_buffering = self.line_buffering;
+
+        async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+            record_transfer(bytes.len());
+            let len = bytes.len() as i64;
+            let flush = line_buffering && bytes.contains(&b'\n');
+            file_guard.write_all(bytes).await.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to write file {path}: {e}"
+                ))
+            })?;
+            if flush {
+                file_guard.flush().await.map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                        "Failed to flush file {path}: {e}"
+                    ))
+                })?;
+            }
+            Ok(len)
+        }
+    }
+
+    /// Read a line as a `readline` operation; with `stop_at_eof`, end of file
+    /// raises `StopAsyncIteration` instead of yielding an empty line.
+    fn read_line_op<'a>(
+        &self,
+        py: Python<'a>,
+        limit: Option<usize>,
+        stop_at_eof: bool,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+
+        let op = OpContext::new("readline", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_open_handle(&mut turn, access, &path).await?;
+            let buffer = file_guard
+                .read_line(limit)
+                .await
+                .map_err(|e| map_io_error(e, &path, "read file"))?;
+
+            record_transfer(buffer.len());
+            // For now, always return bytes - Python will handle text decoding
+            Ok(buffer)
+        };
+
+        if !stop_at_eof {
+            return run_op(py, op, future);
+        }
+        // End of file is a successful read for recordings and audit logs;
+        // only the awaitable raises StopAsyncIteration
+        let mut future = Some(future);
+        let line = prepare_op(py, op, false, move || {
+            future.take().expect("operation future started twice")
+        })?;
+        future_into_py(py, async move {
+            let line = line.await?;
+            if line.is_empty() {
+                return Err(PyErr::new::<pyo3::exceptions::PyStopAsyncIteration, _>(()));
+            }
+            Ok(line)
+        })
+    }
+
+    /// Run a flush or sync step on the handle's file as operation `name`.
+    fn sync_op<'a, S>(
+        &self,
+        py: Python<'a>,
+        name: &'static str,
+        step: S,
+    ) -> PyResult<Bound<'a, PyAny>>
+    where
+        S: for<'f> FnOnce(
+                &'f mut HandleFile,
+            ) -> std::pin::Pin<
+                Box<dyn std::future::Future<Output = std::io::Result<()>> + Send + 'f>,
+            > + Send
+            + 'static,
+    {
+        self.check_open()?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+
+        let op = OpContext::new(name, [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+            step(&mut file_guard).await.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to {name} file {path}: {e}"
+                ))
+            })
+        };
+
+        run_unit_op(py, op, future)
+    }
+
+    /// Return the file used by positional I/O.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the handle was not opened with `access="positional"`.
+    fn positional_file(&self, method: &str) -> PyResult<Arc<std::fs::File>> {
+        let positional = self.positional.lock().unwrap_or_else(|e| e.into_inner());
+        positional.clone().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "{method}() requires a file opened with access='positional' (opened with access='{}')",
+                self.access.name()
+            ))
+        })
+    }
+
+    /// Mark the handle as closed and return a future that flushes (and with
+    /// `sync`, syncs) a writable file and then drops it, releasing the
+    /// descriptor. The future does nothing if the handle was closed before.
+    ///
+    /// Closing takes its place in the handle's queue like any operation, so
+    /// operations called before `close()` finish first, even when awaited
+    /// concurrently with it (e.g. in `asyncio.gather`).
+    fn close_future(
+        &self,
+        sync: bool,
+    ) -> impl std::future::Future<Output = PyResult<()>> + Send + 'static {
+        self.closed.store(true, Ordering::Release);
+        self.untrack();
+        *self.positional.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        // Only flush/sync for write-enabled modes to avoid Windows permission errors
+        let is_write_mode = parse_mode(&self.mode).is_ok_and(|mode| mode.write);
+        let forked = self.pid != std::process::id();
+
+        async move {
+            if forked {
+                // Inherited across fork(): release the descriptor without
+                // flushing the parent's buffered writes a second time, or
+                // waiting for a lock an operation of the parent holds
+                if let Ok(mut handle) = turn.file.try_lock() {
+                    handle.take();
+                }
+                return Ok(());
+            }
+            // Operations called earlier finish first
+            turn.wait().await;
+            let Some(OpenHandle {
+                file: mut handle, ..
+            }) = turn.file.lock().await.take()
+            else {
+                return Ok(());
+            };
+            if !is_write_mode {
+                return Ok(());
+            }
+            handle.flush().await.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to flush file {path}: {e}"
+                ))
+            })?;
+            if sync {
+                handle.sync_all().await.map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                        "Failed to sync file {path}: {e}"
+                    ))
+                })?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Stop tracking the handle, returning its entry unless it was already untracked.
+    fn untrack(&self) -> Option<OpenFileInfo> {
+        OPEN_HANDLES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.handle_id)
+    }
+}
+
+impl Drop for AsyncFile {
+    fn drop(&mut self) {
+        let Some(info) = self.untrack() else {
+            return;
+        };
+        // Garbage collected without close(): flush writes the runtime still
+        // buffers and release the descriptor, best-effort, after the
+        // operations already called; a child forked since only drops its copy
+        if self.pid == std::process::id() {
+            let closing = self.close_future(false);
+            if tokio::runtime::Handle::try_current().is_ok() {
+                // Dropped by a task on the runtime, which can't block on it
+                runtime().spawn(closing);
+            } else {
+                Python::try_attach(|py| py.detach(|| runtime().block_on(closing)));
+            }
+        }
+        // Then warn like Python's own file objects
+        let mut message = format!("unclosed file {:?} (mode {:?})", self.path, self.mode);
+        if let Some(stack) = info.stack {
+            message.push_str(", opened at:\n");
+            message.push_str(stack.trim_end());
+        }
+        let Ok(message) = std::ffi::CString::new(message) else {
+            return;
+        };
+        Python::try_attach(|py| {
+            let category = py.get_type::<pyo3::exceptions::PyResourceWarning>();
+            if let Err(err) = PyErr::warn(py, &category, &message, 1) {
+                err.write_unraisable(py, None);
+            }
+        });
+    }
+}
+
+/// List the `AsyncFile` handles that are still open.
+///
+/// A handle counts as open from the moment `open()` returns it until
+/// `close()` or the end of its `async with` block, so long-running servers
+/// can find handles that were never closed.
+///
+/// # Returns
+///
+/// A list of `OpenFileInfo` objects, oldest first.
+#[pyfunction]
+fn open_files() -> Vec<OpenFileInfo> {
+    OPEN_HANDLES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// Enable or disable capturing the Python stack of each `open()` call.
+///
+/// Captured stacks are reported by `open_files` and make leaks easy to trace
+/// back to their origin, at the cost of formatting a traceback per open.
+///
+/// # Arguments
+///
+/// * `enabled` - Whether to capture stacks for handles opened from now on
+#[pyfunction]
+#[pyo3(signature = (enabled = true))]
+fn capture_open_stacks(enabled: bool) {
+    CAPTURE_OPEN_STACKS.store(enabled, Ordering::Relaxed);
+}
+
+/// Format the calling Python stack for a new handle, if stack capture is enabled.
+fn capture_open_stack(py: Python<'_>) -> PyResult<Option<String>> {
+    if !CAPTURE_OPEN_STACKS.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    let frames = py.import("traceback")?.call_method0("format_stack")?;
+    Ok(Some(frames.extract::<Vec<String>>()?.concat()))
+}
+
+#[pymethods]
+impl AsyncFile {
+    /// Default constructor - use open_file() or rapfiles.open() instead.
+    #[new]
+    fn new() -> PyResult<Self> {
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "AsyncFile cannot be instantiated directly. Use rapfiles.open() or open_file() instead."
+        ))
+    }
+
+    /// Wrap an open file descriptor, e.g. one from `os.pipe()` or a socket.
+    ///
+    /// Same as `rapfiles.open(fd, mode, closefd=closefd)`, so text modes
+    /// decode as they do for paths. Unix only.
+    ///
+    /// # Arguments
+    ///
+    /// * `fd` - Open file descriptor
+    /// * `mode` - Mode matching how the descriptor was opened (default "r")
+    /// * `closefd` - Close the descriptor with the handle; if false, the
+    ///   handle uses its own duplicate and `fd` stays open
+    ///
+    /// # Returns
+    ///
+    /// An awaitable that yields the handle, also usable with `async with`.
+    ///
+    /// # Errors
+    ///
+    /// Same as `rapfiles.open()` with a file descriptor.
+    #[staticmethod]
+    #[pyo3(signature = (fd, mode = "r", closefd = true))]
+    fn from_fd<'py>(
+        py: Python<'py>,
+        fd: i32,
+        mode: &str,
+        closefd: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let kwargs = pyo3::types::PyDict::new(py);
+        kwargs.set_item("closefd", closefd)?;
+        py.import("rapfiles")?
+            .getattr("open")?
+            .call((fd, mode), Some(&kwargs))
+    }
+
+    /// Pickle the handle with a duplicate of its descriptor, for
+    /// `multiprocessing` queues, pipes and process arguments.
+    ///
+    /// The descriptor is transferred with `multiprocessing.reduction.DupFd`,
+    /// so the unpickled handle shares the open file, including its position,
+    /// with this one. Binary mode only; call `flush()` first so the other
+    /// process sees pending writes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyTypeError` for text-mode handles, or the errors of
+    /// `fileno()`.
+    #[allow(clippy::type_complexity)]
+    fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(
+        Bound<'py, PyAny>,
+        (Bound<'py, PyAny>, String, String, &'static str, bool, usize),
+    )> {
+        let transferred = TransferredFile::of(self)?;
+        let fd = self.fileno(py)?;
+        let dup_fd = py
+            .import("multiprocessing.reduction")?
+            .getattr("DupFd")?
+            .call1((fd,))?;
+        let rebuild = py.import("rapfiles")?.getattr("_rebuild_file")?;
+        Ok((
+            rebuild,
+            (
+                dup_fd,
+                transferred.mode,
+                transferred.path,
+                transferred.access.name(),
+                transferred.line_buffering,
+                transferred.buffer_size,
+            ),
+        ))
+    }
+
+    /// Create a handle owning `fd`, as pickled by `__reduce__`.
+    #[staticmethod]
+    fn _adopt_fd(
+        py: Python<'_>,
+        fd: i32,
+        mode: String,
+        path: String,
+        access: &str,
+        line_buffering: bool,
+        buffer_size: usize,
+    ) -> PyResult<Self> {
+        // Owned from here on, so it is closed if the checks below fail
+        let file = file_from_fd(fd, true, false, &path)?;
+        let access = AccessMode::parse(access)?;
+        let flags = parse_mode(&mode)?;
+        if flags.write || flags.append {
+            check_writable("open", &path)?;
+        }
+        let transferred = TransferredFile {
+            mode,
+            path,
+            access,
+            line_buffering,
+            buffer_size,
+        };
+        Ok(transferred.into_handle(file, capture_open_stack(py)?))
+    }
+
+    /// Read from file.
+    ///
+    /// Reads data from the file. In binary mode, returns bytes. In text mode,
+    /// returns bytes that are decoded to strings by the Python wrapper.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Number of bytes to read. If -1 (default), reads the entire file.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields bytes (or str in text mode via wrapper).
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be read.
+    #[pyo3(signature = (size = -1))]
+    fn read<'a>(&self, py: Python<'a>, size: i64) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+        let _mode = self.mode.clone();
+
+        let op = OpContext::new("read", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+
+            let buffer = if size < 0 {
+                // Read all
+                file_guard.read_to_end().await
+            } else {
+                file_guard.read_up_to(size as usize).await
+            }
+            .map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to read file {path}: {e}"
+                ))
+            })?;
+
+            record_transfer(buffer.len());
+            // Return bytes - Python wrapper will decode for text mode
+            Ok(buffer)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Read into a pre-allocated buffer.
+    ///
+    /// Reads until the buffer is full or the end of the file is reached,
+    /// directly into the buffer's memory, without creating a bytes object.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - Writable bytes-like object, e.g. a `bytearray`, a
+    ///   `memoryview` or a numpy array (filled as raw bytes)
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the number of bytes read (0 at end of file).
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyTypeError` if the buffer is read-only or not a bytes-like
+    /// object, or `PyIOError` if the file cannot be read.
+    fn readinto<'a>(
+        &self,
+        py: Python<'a>,
+        buffer: &Bound<'a, PyAny>,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        self.read_into_op(py, "readinto", buffer, true)
+    }
+
+    /// Read into a pre-allocated buffer with at most one underlying read.
+    ///
+    /// Like `readinto()`, but may return fewer bytes than fit in the buffer
+    /// before the end of the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - Writable bytes-like object
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the number of bytes read (0 at end of file).
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyTypeError` if the buffer is read-only or not a bytes-like
+    /// object, or `PyIOError` if the file cannot be read.
+    fn readinto1<'a>(
+        &self,
+        py: Python<'a>,
+        buffer: &Bound<'a, PyAny>,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        self.read_into_op(py, "readinto1", buffer, false)
+    }
+
+    /// Return buffered data without advancing the position.
+    ///
+    /// Like `io.BufferedReader.peek()`, if nothing is buffered a single read
+    /// fills the buffer first, and the result may be longer or shorter than
+    /// `size`; it is empty only at end of file. The data is consumed by
+    /// `read1()` and line reads, and given back before any other operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Minimum number of bytes to fetch if the buffer is empty
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if `size` is negative, or `PyIOError` if the
+    /// file cannot be read.
+    #[pyo3(signature = (size = 0))]
+    fn peek<'a>(&self, py: Python<'a>, size: i64) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let size = usize::try_from(size).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid size: {size}. Must be non-negative"
+            ))
+        })?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+
+        let op = OpContext::new("peek", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_open_handle(&mut turn, access, &path).await?;
+            let buffer = file_guard
+                .peek(size)
+                .await
+                .map_err(|e| map_io_error(e, &path, "read file"))?
+                .to_vec();
+            Ok(buffer)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Read with at most one underlying read.
+    ///
+    /// Like `io.BufferedReader.read1()`, returns data buffered by `peek()` or
+    /// line reads if there is any, and otherwise performs a single read. The
+    /// result may be shorter than `size` before the end of the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Maximum number of bytes to read. If -1 (default), returns
+    ///   everything buffered, or the result of one buffer-sized read.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields bytes (empty at end of file).
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be read.
+    #[pyo3(signature = (size = -1))]
+    fn read1<'a>(&self, py: Python<'a>, size: i64) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let size = usize::try_from(size).ok();
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+
+        let op = OpContext::new("read1", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_open_handle(&mut turn, access, &path).await?;
+            let buffer = file_guard
+                .read1(size)
+                .await
+                .map_err(|e| map_io_error(e, &path, "read file"))?;
+            record_transfer(buffer.len());
+            Ok(buffer)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Read exactly `size` bytes.
+    ///
+    /// Repeats partial reads until `size` bytes have arrived, for
+    /// length-prefixed protocols on pipes, sockets and character devices.
+    /// Always returns bytes, also in text mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Number of bytes to read
+    /// * `timeout` - Seconds to wait for all of them, or `None` to wait
+    ///   forever. On timeout the bytes already read stay buffered in the
+    ///   handle for the next read.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields exactly `size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Raises `asyncio.IncompleteReadError` (with the bytes read in
+    /// `partial`) if the file ends first, `TimeoutError` on timeout,
+    /// `PyValueError` for a negative timeout, or `PyIOError` if the read
+    /// fails.
+    #[pyo3(signature = (size, timeout = None))]
+    fn read_exact<'a>(
+        &self,
+        py: Python<'a>,
+        size: usize,
+        timeout: Option<f64>,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let deadline = read_exact_deadline(timeout)?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+
+        let op = OpContext::new("read_exact", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_open_handle(&mut turn, access, &path).await?;
+            let buffer = read_exact_from(&mut file_guard, size, deadline)
+                .await
+                .map_err(|e| e.into_py_err(&path, size))?;
+            record_transfer(buffer.len());
+            Ok(buffer)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Write to file.
+    ///
+    /// Writes data to the file. Accepts both strings and bytes. On
+    /// line-buffered files the data is flushed if it contains a newline.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Data to write (str or bytes)
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyTypeError` if data is not str or bytes,
+    /// or `PyIOError` if the file cannot be written.
+    fn write<'a>(&self, py: Python<'a>, data: &Bound<'a, PyAny>) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        // Convert Python bytes/string to Vec<u8>
+        let mut bytes = Vec::new();
+        if !extend_with_data(&mut bytes, data)? {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "write() argument must be bytes or str",
+            ));
+        }
+
+        let op = OpContext::new("write", [self.path.as_str()]);
+        run_op(py, op, self.write_future(bytes))
+    }
+
+    /// Write a sequence of strings or bytes to file.
+    ///
+    /// The items are joined and written in a single pass, as one `write()`
+    /// call would. Like `io` file objects, no line separators are added.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - Iterable of str or bytes
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyTypeError` if an item is not str or bytes,
+    /// or `PyIOError` if the file cannot be written.
+    fn writelines<'a>(
+        &self,
+        py: Python<'a>,
+        lines: &Bound<'a, PyAny>,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let mut bytes = Vec::new();
+        for line in lines.try_iter()? {
+            if !extend_with_data(&mut bytes, &line?)? {
+                return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                    "writelines() argument must be an iterable of bytes or str",
+                ));
+            }
+        }
+
+        let op = OpContext::new("writelines", [self.path.as_str()]);
+        let future = self.write_future(bytes);
+        run_unit_op(py, op, async move { future.await.map(drop) })
+    }
+
+    /// Read a line from file.
+    ///
+    /// Reads a single line from the file, up to and including the newline character.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Maximum number of bytes to read. If -1 (default), reads until newline.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields bytes (or str in text mode via wrapper).
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be read.
+    #[pyo3(signature = (size = -1))]
+    fn readline<'a>(&self, py: Python<'a>, size: i64) -> PyResult<Bound<'a, PyAny>> {
+        self.read_line_op(py, (size > 0).then_some(size as usize), false)
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Read the next line for `async for line in f`.
+    ///
+    /// Lines are read in chunks, like `readline()`, and the file position
+    /// ends up right after the returned line.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the next line as bytes (or str in text mode via
+    /// wrapper), and raises `StopAsyncIteration` at end of file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be read.
+    fn __anext__<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.read_line_op(py, None, true)
+    }
+
+    /// Read all lines from file.
+    ///
+    /// Reads all lines from the file and returns them as a list.
+    ///
+    /// # Arguments
+    ///
+    /// * `hint` - Approximate number of lines to read. If -1 (default), reads all lines.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields a list of bytes (or list of str in text mode via wrapper).
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be read.
+    #[pyo3(signature = (hint = -1))]
+    fn readlines<'a>(&self, py: Python<'a>, hint: i64) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+        let _mode = self.mode.clone();
+
+        let op = OpContext::new("readlines", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_open_handle(&mut turn, access, &path).await?;
+            let hint = (hint > 0).then_some(hint as usize);
+            let lines = file_guard
+                .read_lines(hint)
+                .await
+                .map_err(|e| map_io_error(e, &path, "read file"))?;
+
+            record_transfer(lines.iter().map(Vec::len).sum());
+            // For now, always return list of bytes - Python will handle text decoding
+            Ok(lines)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Seek to a position in the file.
+    ///
+    /// Changes the file position to the given offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte offset
+    /// * `whence` - Reference point: 0=start (SEEK_SET), 1=current (SEEK_CUR), 2=end (SEEK_END)
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the new absolute position.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if whence is invalid, or `PyIOError` if seek fails.
+    #[pyo3(signature = (offset, whence = 0))]
+    fn seek<'a>(&self, py: Python<'a>, offset: i64, whence: i32) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+
+        let op = OpContext::new("seek", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+
+            let pos = match whence {
+                0 => std::io::SeekFrom::Start(offset as u64),
+                1 => std::io::SeekFrom::Current(offset),
+                2 => std::io::SeekFrom::End(offset),
+                _ => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                        format!("Invalid whence value: {whence}. Must be 0 (SEEK_SET), 1 (SEEK_CUR), or 2 (SEEK_END)"),
+                    ));
+                }
+            };
+
+            let new_pos = file_guard.seek(pos).await.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to seek in file {path}: {e}"
+                ))
+            })?;
+
+            Ok(new_pos as i64)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Get current position in file.
+    ///
+    /// Returns the current file position (byte offset from start).
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the current position as an integer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the position cannot be determined.
+    fn tell<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+
+        let op = OpContext::new("tell", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+            let pos = file_guard.stream_position().await.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to get position in file {path}: {e}"
+                ))
+            })?;
+            Ok(pos as i64)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Flush buffered writes to the operating system.
+    ///
+    /// Data is handed to the OS, not forced to disk; use `fsync()` for that.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the buffered data cannot be written.
+    fn flush<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.sync_op(py, "flush", |file| Box::pin(file.flush()))
+    }
+
+    /// Flush buffered writes and force the file's data and metadata to disk.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` once the data is on stable storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the data cannot be written or synced.
+    fn fsync<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.sync_op(py, "fsync", |file| {
+            Box::pin(async move {
+                file.flush().await?;
+                file.sync_all().await
+            })
+        })
+    }
+
+    /// Like `fsync()`, but skip metadata not needed to read the data back
+    /// (e.g. the modification time), which is faster on most filesystems.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` once the data is on stable storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the data cannot be written or synced.
+    fn fdatasync<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.sync_op(py, "fdatasync", |file| {
+            Box::pin(async move {
+                file.flush().await?;
+                file.sync_data().await
+            })
+        })
+    }
+
+    /// Resize the file.
+    ///
+    /// Shrinking discards the data past `size`; growing fills the new space
+    /// with zero bytes. The file position is not changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - New size in bytes. If None (default), the current position.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the new size.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if size is negative, or `PyIOError` if the file
+    /// cannot be resized (e.g. it is not open for writing).
+    #[pyo3(signature = (size = None))]
+    fn truncate<'a>(&self, py: Python<'a>, size: Option<i64>) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let size = size
+            .map(|size| {
+                u64::try_from(size).map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Negative size value {size}"
+                    ))
+                })
+            })
+            .transpose()?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+
+        let op = OpContext::new("truncate", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+            let new_size = file_guard.set_len(size).await.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to truncate file {path}: {e}"
+                ))
+            })?;
+            Ok(new_size as i64)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Whether writes containing a newline are flushed immediately.
+    ///
+    /// True for text-mode files opened with `buffering=1`.
+    #[getter]
+    fn line_buffering(&self) -> bool {
+        self.line_buffering
+    }
+
+    /// Concurrent-access mode the file was opened with.
+    ///
+    /// One of "exclusive", "serialized" or "positional".
+    #[getter]
+    fn access(&self) -> &'static str {
+        self.access.name()
+    }
+
+    /// Path the file was opened with, or the descriptor it was opened from.
+    #[getter]
+    fn name<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        Ok(match self.fd {
+            Some(fd) => fd.into_pyobject(py)?.into_any(),
+            None => PyString::new(py, &self.path).into_any(),
+        })
+    }
+
+    /// Mode the file was opened with.
+    #[getter]
+    fn mode(&self) -> &str {
+        &self.mode
+    }
+
+    /// Whether the handle has been closed.
+    #[getter]
+    fn closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Whether the file was opened for reading.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the handle is closed.
+    fn readable(&self) -> PyResult<bool> {
+        self.check_open()?;
+        Ok(parse_mode(&self.mode)?.read)
+    }
+
+    /// Whether the file was opened for writing or appending.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the handle is closed.
+    fn writable(&self) -> PyResult<bool> {
+        self.check_open()?;
+        Ok(parse_mode(&self.mode)?.write)
+    }
+
+    /// Whether `seek()` and `tell()` are supported: false for pipes, sockets
+    /// and terminals, whose position cannot be set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the handle is closed.
+    fn seekable(&self) -> PyResult<bool> {
+        self.check_open()?;
+        Ok(self.seekable)
+    }
+
+    /// Underlying OS file descriptor, for `os.sendfile`, `select` and other
+    /// descriptor-based APIs.
+    ///
+    /// Like the descriptor of a buffered Python file, its position may be
+    /// ahead of `tell()` after line reads or `peek()`; call `flush()` before
+    /// other code writes through it. Small files loaded whole when they were
+    /// opened are read from the descriptor from then on.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the handle is closed, `PyIOError` if the
+    /// descriptor of a loaded file cannot be positioned, or
+    /// `PyNotImplementedError` on platforms without file descriptors.
+    fn fileno(&self, py: Python<'_>) -> PyResult<i32> {
+        self.check_open()?;
+        #[cfg(unix)]
+        {
+            // Waits for an operation in progress, which may replace the file
+            let mut guard = py.detach(|| self.file.blocking_lock());
+            let handle = guard.as_mut().ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "I/O operation on closed file {}",
+                    self.path
+                ))
+            })?;
+            handle
+                .file
+                .raw_fd()
+                .map_err(|e| map_io_error(e, &self.path, "get the descriptor of"))
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = py;
+            Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+                "file descriptors are only supported on Unix",
+            ))
+        }
+    }
+
+    /// Close the handle but keep its file open, and return the descriptor.
+    ///
+    /// Like `socket.detach()`: pending writes are flushed and the descriptor
+    /// is positioned at `tell()`, then the caller owns it, e.g. to hand it
+    /// to `os.fdopen()`, another library or `AsyncFile.from_fd()` in a
+    /// worker process. The handle is closed afterwards, even if the
+    /// descriptor cannot be detached.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the descriptor.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if pending writes cannot be flushed or the
+    /// descriptor cannot be duplicated, or `PyNotImplementedError` on
+    /// platforms without file descriptors.
+    fn detach_fd<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        if cfg!(not(unix)) {
+            return Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+                "file descriptors are only supported on Unix",
+            ));
+        }
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let access = self.access;
+        let close = self.close_future(false);
+
+        let op = OpContext::new("detach_fd", [self.path.as_str()]);
+        let future = async move {
+            let detached = detach_handle_fd(&mut turn, access, &path).await;
+            close.await?;
+            detached
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Read from a byte offset without using or moving the file cursor.
+    ///
+    /// Only available with `access="positional"`. Calls never wait for other
+    /// operations on the handle, so any number of them can run concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Number of bytes to read
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields bytes, shorter than `size` only at end of file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the handle was not opened with
+    /// `access="positional"`, or `PyIOError` if the file cannot be read.
+    fn pread<'a>(&self, py: Python<'a>, size: usize, offset: u64) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let file = self.positional_file("pread")?;
+        let path = self.path.clone();
+
+        let op = OpContext::new("pread", [self.path.as_str()]);
+        let future = async move {
+            let buffer = run_blocking(move || {
+                let mut buffer = vec![0u8; size];
+                let n = read_full_at(&file, &mut buffer, offset)?;
+                buffer.truncate(n);
+                Ok(buffer)
+            })
+            .await
+            .map_err(|e| map_io_error(e, &path, "read file"))?;
+
+            record_transfer(buffer.len());
+            Ok(buffer)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Write at a byte offset without using or moving the file cursor.
+    ///
+    /// Only available with `access="positional"`. Calls never wait for other
+    /// operations on the handle; concurrent writes to overlapping ranges are
+    /// not ordered. In append modes the OS may append regardless of `offset`
+    /// (e.g. on Linux).
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Data to write (str or bytes)
+    /// * `offset` - Byte offset to write at
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the handle was not opened with
+    /// `access="positional"`, `PyTypeError` if data is not str or bytes, or
+    /// `PyIOError` if the file cannot be written.
+    fn pwrite<'a>(
+        &self,
+        py: Python<'a>,
+        data: &Bound<'a, PyAny>,
+        offset: u64,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let file = self.positional_file("pwrite")?;
+        let path = self.path.clone();
+
+        let bytes: Vec<u8> = if let Ok(py_bytes) = data.cast::<PyBytes>() {
+            py_bytes.as_bytes().to_vec()
+        } else if let Ok(py_str) = data.cast::<PyString>() {
+            py_str.to_string().into_bytes()
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "pwrite() argument must be bytes or str",
+            ));
+        };
+
+        let op = OpContext::new("pwrite", [self.path.as_str()]);
+        let future = async move {
+            let len = bytes.len();
+            run_blocking(move || write_full_at(&file, &bytes, offset))
+                .await
+                .map_err(|e| map_io_error(e, &path, "write file"))?;
+
+            record_transfer(len);
+            Ok(len as i64)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Create an independent handle on the same open file.
+    ///
+    /// The new handle shares the underlying file (it keeps working if the
+    /// path is renamed or removed) but has its own cursor, starting at this
+    /// handle's current position, so e.g. a reader task and a writer task
+    /// can use one file without moving each other's position. It has the
+    /// same mode and access mode and must be closed separately.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields a new `AsyncFile`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be duplicated, or
+    /// `PyRuntimeError` for concurrent use with `access="exclusive"`.
+    fn dup<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let mut turn = self.enqueue();
+        let path = self.path.clone();
+        let mode = self.mode.clone();
+        let access = self.access;
+        let append = parse_mode(&self.mode)?.append;
+        let opener = self.opener.clone();
+        let fd = self.fd;
+        let line_buffering = self.line_buffering;
+        let buffer_size = self.buffer_size;
+        let stack = capture_open_stack(py)?;
+
+        let op = OpContext::new("dup", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+            let duplicate = file_guard
+                .duplicate(append)
+                .await
+                .map_err(|e| map_io_error(e, &path, "duplicate handle of"))?;
+            let mut handle = AsyncFile::new_open(
+                duplicate,
+                path,
+                mode,
+                access,
+                opener,
+                line_buffering,
+                buffer_size,
+                stack,
+            );
+            handle.fd = fd;
+            Ok(handle)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Open the file's path again, replacing the underlying file.
+    ///
+    /// The handle object stays the same, so code holding a reference to it
+    /// keeps working, e.g. after the file was rotated by a log rotation tool.
+    /// The original mode is used again ("w" modes truncate, "x" modes fail
+    /// unless the path is free, append modes continue at the end) and the
+    /// position starts over.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` once the file has been reopened.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyFileNotFoundError` if the path no longer exists (read
+    /// modes), `PyIOError` if it cannot be opened, `PyValueError` for
+    /// handles opened from a file descriptor, or `PyRuntimeError` for
+    /// concurrent use with `access="exclusive"`. On failure the handle keeps
+    /// its current file.
+    fn reopen<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        self.check_has_path("reopen")?;
+        let mut turn = self.enqueue();
+        let positional = Arc::clone(&self.positional);
+        let opener = self.opener.clone();
+        let path = self.path.clone();
+        let access = self.access;
+        let flags = parse_mode(&self.mode)?;
+        if flags.write || flags.append {
+            check_writable("reopen", &path)?;
+        }
+
+        let op = OpContext::new("reopen", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+            reopen_handle(
+                &mut file_guard,
+                &positional,
+                &path,
+                flags,
+                access,
+                opener.as_deref(),
+            )
+            .await?;
+            Ok(())
+        };
+
+        run_unit_op(py, op, future)
+    }
+
+    /// Reopen the file if its path now refers to a different file.
+    ///
+    /// Compares the open file with the one currently at the path (device and
+    /// inode on Unix), so a rotated log can be followed cheaply by calling
+    /// this periodically. While the path is missing, "r" and "r+" handles
+    /// keep their current file; "w" and "a" handles recreate it. Small files loaded
+    /// whole when opened are also reopened once modified, and platforms
+    /// without file identities always reopen.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields True if the file was reopened, False otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the path cannot be checked or reopened,
+    /// `PyValueError` for handles opened from a file descriptor, or
+    /// `PyRuntimeError` for concurrent use with `access="exclusive"`.
+    fn refresh<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        self.check_has_path("refresh")?;
+        let mut turn = self.enqueue();
+        let positional = Arc::clone(&self.positional);
+        let opener = self.opener.clone();
+        let path = self.path.clone();
+        let access = self.access;
+        let flags = parse_mode(&self.mode)?;
+
+        let op = OpContext::new("refresh", [self.path.as_str()]);
+        let future = async move {
+            let mut file_guard = lock_handle(&mut turn, access, &path).await?;
+            let OpenMode {
+                write,
+                append,
+                truncate,
+                ..
+            } = flags;
+            let current = match tokio::fs::metadata(&path).await {
+                Ok(metadata) => Some(metadata),
+                // Not recreated yet; keep reading the old file
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound && !(truncate || append) => {
+                    return Ok(false);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(map_io_error(e, &path, "refresh file")),
+            };
+            let open = file_guard
+                .identity()
+                .await
+                .map_err(|e| map_io_error(e, &path, "refresh file"))?;
+            if current.is_some_and(|metadata| is_same_file(open, &metadata)) {
+                return Ok(false);
+            }
+            if write || append {
+                check_writable("refresh", &path)?;
+            }
+            reopen_handle(
+                &mut file_guard,
+                &positional,
+                &path,
+                flags,
+                access,
+                opener.as_deref(),
+            )
+            .await?;
+            Ok(true)
+        };
+
+        run_op(py, op, future)
+    }
+
+    /// Close the file.
+    ///
+    /// Flushes pending writes and releases the descriptor once operations
+    /// called before it have finished, even if they are awaited concurrently
+    /// with `close()`. Further I/O on the handle raises `ValueError`; closing
+    /// it again has no effect. Handles from `dup()` stay open.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if pending writes cannot be flushed; the handle is
+    /// closed regardless.
+    fn close<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let closing = self.close_future(false);
+        let op = OpContext::new("close", [self.path.as_str()]);
+        let future = async move {
+            closing.await?;
+            Ok(())
+        };
+        run_unit_op(py, op, future)
+    }
+
+    /// Async context manager entry.
+    ///
+    /// Returns an awaitable yielding the handle itself, so handles from
+    /// `dup()` can be used with `async with` directly.
+    fn __aenter__<'a>(slf: PyRef<'a, Self>) -> PyResult<Bound<'a, PyAny>> {
+        let py = slf.py();
+        let handle: Py<Self> = slf.into();
+        future_into_py(py, async move { Ok(handle) })
+    }
+
+    /// Async context manager exit.
+    fn __aexit__(
+        &self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_val: Option<&Bound<'_, PyAny>>,
+        _exc_tb: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        // Flush and sync file on exit to ensure all writes are persisted
+        let closing = self.close_future(true);
+        Python::attach(|py| {
+            let op = OpContext::new("close", [self.path.as_str()]);
+            let future = async move {
+                closing.await?;
+                Ok(false) // Return False to not suppress exceptions
+            };
+            run_op(py, op, future).map(|bound| bound.unbind())
+        })
+    }
+}
+
+/// File metadata structure (aiofiles.stat_result compatible).
+///
+/// Provides file metadata including size, timestamps, and type information.
+/// Compatible with `aiofiles.stat_result` for drop-in replacement scenarios.
+///
+/// # Properties
+///
+/// * `size` - File size in bytes
+/// * `is_file` - True if path is a file
+/// * `is_dir` - True if path is a directory
+/// * `modified` - Modification time as Unix timestamp (float)
+/// * `accessed` - Access time as Unix timestamp (float)
+/// * `created` - Creation time as Unix timestamp (float)
+#[pyclass]
+#[derive(Clone)]
+struct FileMetadata {
+    size: u64,
+    is_file: bool,
+    is_dir: bool,
+    modified: f64, // Unix timestamp
+    accessed: f64, // Unix timestamp
+    created: f64,  // Unix timestamp (creation time on Windows, birth time on Unix)
+}
+
+#[pymethods]
+impl FileMetadata {
+    #[new]
+    fn new(
+        size: u64,
+        is_file: bool,
+        is_dir: bool,
+        modified: f64,
+        accessed: f64,
+        created: f64,
+    ) -> Self {
+        FileMetadata {
+            size,
+            is_file,
+            is_dir,
+            modified,
+            accessed,
+            created,
+        }
+    }
+
+    #[getter]
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[getter]
+    fn is_file(&self) -> bool {
+        self.is_file
+    }
+
+    #[getter]
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    #[getter]
+    fn modified(&self) -> f64 {
+        self.modified
+    }
+
+    #[getter]
+    fn accessed(&self) -> f64 {
+        self.accessed
+    }
+
+    #[getter]
+    fn created(&self) -> f64 {
+        self.created
+    }
+}
+
+impl FileMetadata {
+    /// Build from the metadata of an open file or a path.
+    fn from_std(metadata: &std::fs::Metadata) -> Self {
+        let modified = metadata
+            .modified()
+            .map(system_time_to_timestamp)
+            .unwrap_or(0.0);
+        let accessed = metadata
+            .accessed()
+            .map(system_time_to_timestamp)
+            .unwrap_or(0.0);
+
+        // Creation time (available on Windows, birth time on Unix requires platform-specific code)
+        let created = metadata
+            .created()
+            .map(system_time_to_timestamp)
+            .unwrap_or(modified); // Fallback to modified time if creation time not available
+
+        FileMetadata {
+            size: metadata.len(),
+            is_file: metadata.is_file(),
+            is_dir: metadata.is_dir(),
+            modified,
+            accessed,
+            created,
+        }
+    }
+}
+
+/// Convert SystemTime to Unix timestamp.
+///
+/// Converts a Rust SystemTime to a Unix timestamp (seconds since epoch as float).
+/// Used for file metadata timestamps (modified, accessed, created).
+///
+/// # Arguments
+///
+/// * `time` - SystemTime to convert
+///
+/// # Returns
+///
+/// Unix timestamp as f64 (seconds since epoch)
+fn system_time_to_timestamp(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Get file statistics asynchronously.
+///
+/// Returns file metadata including size, timestamps, and type information.
+/// All I/O operations execute outside the Python GIL using native Tokio.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file or directory
+///
+/// # Returns
+///
+/// A coroutine that yields a `FileMetadata` object.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the path does not exist,
+/// `PyIOError` if metadata cannot be retrieved, or `PyValueError` if the path is invalid.
+#[pyfunction]
+fn stat_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("stat", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let metadata = tokio::fs::metadata(&path).await.map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to get metadata for {path_clone}: {e}"
+            ))
+        })?;
+
+        Ok(FileMetadata::from_std(&metadata))
+    };
+    run_op(py, op, future)
+}
+
+/// Get file metadata asynchronously (alias for stat).
+#[pyfunction]
+fn metadata_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    stat_async(py, path)
+}
+
+// Directory traversal
+
+/// Recursively walk a directory asynchronously.
+///
+/// Traverses a directory tree recursively and returns a list of all files
+/// and directories found. The whole traversal runs as a single job on Tokio's
+/// blocking pool, outside the Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Directory path to walk
+/// * `sorted` - `None`/`False` for filesystem order, `True` or `"bytes"` for a
+///   deterministic pre-order traversal with siblings sorted by name, or
+///   `"natural"` for the same traversal in numeric-aware order
+///
+/// # Returns
+///
+/// A coroutine that yields a list of (path, is_file) tuples where:
+/// - `path`: Full path to the file or directory
+/// - `is_file`: True if the path is a file, False if it's a directory
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the directory does not exist,
+/// `PyIOError` if the directory cannot be read, or `PyValueError` if the path
+/// or sort order is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, sorted = None))]
+fn walk_dir_async<'a>(
+    py: Python<'a>,
+    path: String,
+    sorted: Option<&Bound<'a, PyAny>>,
+) -> PyResult<Bound<'a, PyAny>> {
+    validate_path(&path)?;
+    let order = SortOrder::from_py(sorted)?;
+    let op = OpContext::new("walk_dir", [path.as_str()]);
+    let future = async move {
+        let entries = rapfiles_core::walk_dir(&path)
+            .await
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        let results = run_blocking(move || {
+            // Convert the paths in place rather than copying them
+            let mut results: Vec<(String, bool)> = entries
+                .into_iter()
+                .map(|(path, is_file)| {
+                    let path = path
+                        .into_os_string()
+                        .into_string()
+                        .unwrap_or_else(|path| path.to_string_lossy().into_owned());
+                    (path, is_file)
+                })
+                .collect();
+            if order != SortOrder::Unsorted {
+                results.sort_by(|a, b| order.compare_paths(&a.0, &b.0));
+            }
+            Ok(results)
+        })
+        .await
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(results)
+    };
+    run_op(py, op, future)
+}
+
+// Permission auditing
+
+/// Permission audit finding.
+///
+/// Describes a single policy violation reported by `audit_permissions_async`.
+///
+/// # Properties
+///
+/// * `path` - Path of the offending entry
+/// * `issue` - Violation kind: "world_writable", "setuid", "setgid", "owner", or "group"
+/// * `mode` - Full permission bits of the entry (e.g. 0o4755)
+/// * `uid` - Owning user ID
+/// * `gid` - Owning group ID
+#[pyclass]
+#[derive(Clone)]
+struct PermissionFinding {
+    path: String,
+    issue: String,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+}
+
+#[pymethods]
+impl PermissionFinding {
+    #[getter]
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    #[getter]
+    fn issue(&self) -> String {
+        self.issue.clone()
+    }
+
+    #[getter]
+    fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    #[getter]
+    fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    #[getter]
+    fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PermissionFinding(path={:?}, issue={:?}, mode={:#o})",
+            self.path, self.issue, self.mode
+        )
+    }
+}
+
+/// Audit permissions of a directory tree asynchronously.
+///
+/// Walks the tree rooted at `path` (including the root itself) without
+/// following symbolic links and reports every entry that violates the
+/// given policy. All I/O operations execute outside the Python GIL using
+/// native Tokio.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Root of the tree to audit
+/// * `world_writable` - Report entries writable by others. Directories with
+///   the sticky bit set (like `/tmp`) are not reported.
+/// * `setuid` - Report setuid files/directories and setgid regular files
+/// * `owner` - If set, report entries not owned by this user ID
+/// * `group` - If set, report entries not owned by this group ID
+///
+/// # Returns
+///
+/// A coroutine that yields a list of `PermissionFinding` objects. An entry
+/// violating several rules produces one finding per rule.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the root does not exist, `PyValueError` if
+/// the path is invalid, or `PyNotImplementedError` on platforms without Unix
+/// permission bits.
+#[pyfunction]
+#[pyo3(signature = (path, world_writable = true, setuid = true, owner = None, group = None))]
+fn audit_permissions_async(
+    py: Python<'_>,
+    path: String,
+    world_writable: bool,
+    setuid: bool,
+    owner: Option<u32>,
+    group: Option<u32>,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("audit_permissions", [path.as_str()]);
+    let future = async move {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let root_metadata = tokio::fs::symlink_metadata(&path)
+                .await
+                .map_err(|e| map_io_error(e, &path, "audit permissions of"))?;
+
+            let mut findings = Vec::new();
+            let mut pending = vec![(path.clone(), root_metadata)];
+            let mut stack = Vec::new();
+
+            loop {
+                for (entry_path, metadata) in pending.drain(..) {
+                    let file_type = metadata.file_type();
+                    if file_type.is_symlink() {
+                        // Symlink permission bits are meaningless; the target is audited on its own
+                        continue;
+                    }
+
+                    let mode = metadata.mode() & 0o7777;
+                    let uid = metadata.uid();
+                    let gid = metadata.gid();
+                    let mut report = |issue: &str| {
+                        findings.push(PermissionFinding {
+                            path: entry_path.clone(),
+                            issue: issue.to_string(),
+                            mode,
+                            uid,
+                            gid,
+                        });
+                    };
+
+                    let sticky_dir = file_type.is_dir() && mode & 0o1000 != 0;
+                    if world_writable && mode & 0o002 != 0 && !sticky_dir {
+                        report("world_writable");
+                    }
+                    if setuid && mode & 0o4000 != 0 {
+                        report("setuid");
+                    }
+                    if setuid && file_type.is_file() && mode & 0o2000 != 0 {
+                        report("setgid");
+                    }
+                    if owner.is_some_and(|expected| expected != uid) {
+                        report("owner");
+                    }
+                    if group.is_some_and(|expected| expected != gid) {
+                        report("group");
+                    }
+
+                    if file_type.is_dir() {
+                        stack.push(entry_path);
+                    }
+                }
+
+                let Some(current_path) = stack.pop() else {
+                    break;
+                };
+
+                let mut entries = match tokio::fs::read_dir(&current_path).await {
+                    Ok(entries) => entries,
+                    Err(_) => continue, // Skip directories we can't read
+                };
+
+                while let Some(entry) = entries.next_entry().await.map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                        "Failed to read directory entry in {current_path}: {e}"
+                    ))
+                })? {
+                    // DirEntry::metadata does not traverse symlinks
+                    if let Ok(metadata) = entry.metadata().await {
+                        pending.push((entry.path().to_string_lossy().to_string(), metadata));
+                    }
+                }
+            }
+
+            Ok(findings)
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = (world_writable, setuid, owner, group);
+            Err::<Vec<PermissionFinding>, _>(
+                PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(format!(
+                    "Permission auditing of {path} is only supported on Unix platforms"
+                )),
+            )
+        }
+    };
+    run_op(py, op, future)
+}
+
+// Portability checks
+
+/// Device names Windows reserves in every directory, with any extension.
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Longest name component Windows accepts, in UTF-16 code units.
+const WINDOWS_MAX_NAME_LENGTH: usize = 255;
+
+/// Portability problem.
+///
+/// Describes a name reported by `check_portability_async` that cannot be
+/// extracted unchanged on every platform.
+///
+/// # Properties
+///
+/// * `path` - Path of the offending entry
+/// * `issue` - Problem kind: "case_conflict", "reserved_name",
+///   "invalid_character", "trailing_dot_or_space", "name_too_long", or
+///   "path_too_long"
+/// * `detail` - Explanation, e.g. the entry a name collides with
+#[pyclass]
+#[derive(Clone)]
+struct PortabilityIssue {
+    path: String,
+    issue: String,
+    detail: String,
+}
+
+#[pymethods]
+impl PortabilityIssue {
+    #[getter]
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    #[getter]
+    fn issue(&self) -> String {
+        self.issue.clone()
+    }
+
+    #[getter]
+    fn detail(&self) -> String {
+        self.detail.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PortabilityIssue(path={:?}, issue={:?}, detail={:?})",
+            self.path, self.issue, self.detail
+        )
+    }
+}
+
+/// Reserved device name a name refers to on Windows, if any.
+fn windows_reserved_stem(name: &str) -> Option<&str> {
+    // "nul.txt" and "COM1 .tar.gz" name the devices too
+    let stem = name.split('.').next().unwrap_or(name).trim_end_matches(' ');
+    WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+        .then_some(stem)
+}
+
+/// Whether Windows rejects `c` in file names.
+fn is_windows_invalid_char(c: char) -> bool {
+    matches!(c, '<' | '>' | ':' | '"' | '\\' | '|' | '?' | '*') || c < ' '
+}
+
+/// Problems Windows has with a single name component, as (issue, detail).
+fn name_portability_issues(name: &str) -> Vec<(&'static str, String)> {
+    let mut issues = Vec::new();
+    if let Some(stem) = windows_reserved_stem(name) {
+        issues.push((
+            "reserved_name",
+            format!("{stem} is a reserved device name on Windows"),
+        ));
+    }
+    if let Some(c) = name.chars().find(|&c| is_windows_invalid_char(c)) {
+        issues.push((
+            "invalid_character",
+            format!("{c:?} is not allowed in Windows file names"),
+        ));
+    }
+    if name.ends_with(['.', ' ']) {
+        issues.push((
+            "trailing_dot_or_space",
+            "Windows strips trailing dots and spaces from names".to_string(),
+        ));
+    }
+    let length = name.encode_utf16().count();
+    if length > WINDOWS_MAX_NAME_LENGTH {
+        issues.push((
+            "name_too_long",
+            format!("{length} characters, Windows allows {WINDOWS_MAX_NAME_LENGTH}"),
+        ));
+    }
+    issues
+}
+
+/// How `copytree_async` handles names that are not valid on Windows.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SanitizeMode {
+    /// Replace each offending byte with "_"
+    Replace,
+    /// Percent-encode offending bytes, and "%" itself, as "%XX"
+    PercentEncode,
+    /// Fail on the first offending name
+    Error,
+}
+
+impl SanitizeMode {
+    /// Parse the `sanitize` argument of `copytree_async`.
+    fn parse(mode: &str) -> PyResult<Self> {
+        match mode {
+            "replace" => Ok(SanitizeMode::Replace),
+            "percent-encode" => Ok(SanitizeMode::PercentEncode),
+            "error" => Ok(SanitizeMode::Error),
+            other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid sanitize mode: {other}. Must be 'replace', 'percent-encode' or 'error'"
+            ))),
+        }
+    }
+
+    /// Name to use for `name` on Windows, or `None` if it is valid as is:
+    /// bytes that are not valid Unicode, characters Windows rejects, trailing
+    /// dots and spaces, and the last character of a reserved device name are
+    /// escaped. In `Error` mode only whether the result is `None` matters.
+    fn sanitize(self, name: &std::ffi::OsStr) -> Option<String> {
+        let escape = |out: &mut String, bytes: &[u8]| {
+            for byte in bytes {
+                if self == SanitizeMode::PercentEncode {
+                    out.push_str(&format!("%{byte:02X}"));
+                } else {
+                    out.push('_');
+                }
+            }
+        };
+        let transform = |escape_percent: bool| {
+            let mut out = String::new();
+            for chunk in name.as_encoded_bytes().utf8_chunks() {
+                for c in chunk.valid().chars() {
+                    if is_windows_invalid_char(c) || (escape_percent && c == '%') {
+                        escape(&mut out, c.to_string().as_bytes());
+                    } else {
+                        out.push(c);
+                    }
+                }
+                escape(&mut out, chunk.invalid());
+            }
+            let trailing = out.split_off(out.trim_end_matches(['.', ' ']).len());
+            escape(&mut out, trailing.as_bytes());
+            if let Some(stem) = windows_reserved_stem(&out) {
+                // Device names are ASCII
+                let last = stem.len() - 1;
+                let tail = out.split_off(last);
+                escape(&mut out, &tail.as_bytes()[..1]);
+                out.push_str(&tail[1..]);
+            }
+            out
+        };
+        let sanitized = transform(false);
+        if sanitized.as_bytes() == name.as_encoded_bytes() {
+            return None;
+        }
+        // Escaping "%" too keeps encoded names reversible
+        Some(match self {
+            SanitizeMode::PercentEncode => transform(true),
+            _ => sanitized,
+        })
+    }
+}
+
+/// Walk the tree below `root` without following symbolic links and report
+/// the names that are not portable, sorted by path.
+fn scan_portability(
+    root: &std::path::Path,
+    max_path_length: usize,
+) -> std::io::Result<Vec<PortabilityIssue>> {
+    let mut issues = Vec::new();
+    let mut report = |path: &std::path::Path, issue: &str, detail: String| {
+        issues.push(PortabilityIssue {
+            path: path.to_string_lossy().to_string(),
+            issue: issue.to_string(),
+            detail,
+        });
+    };
+
+    // Directories with the length of their path relative to the root
+    let mut stack = vec![(root.to_path_buf(), 0)];
+    let mut root_entries = Some(std::fs::read_dir(root)?);
+    while let Some((dir, dir_length)) = stack.pop() {
+        let entries = match root_entries.take() {
+            Some(entries) => entries,
+            None => match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue, // Skip directories we can't read
+            },
+        };
+        let mut entries = entries
+            .map(|entry| {
+                let entry = entry?;
+                Ok((entry.file_name(), entry.file_type()?))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut folded: std::collections::HashMap<String, std::path::PathBuf> =
+            std::collections::HashMap::new();
+        for (name, file_type) in entries {
+            let path = dir.join(&name);
+            let Some(name) = name.to_str() else {
+                report(
+                    &path,
+                    "invalid_character",
+                    "name is not valid Unicode".to_string(),
+                );
+                continue;
+            };
+            for (issue, detail) in name_portability_issues(name) {
+                report(&path, issue, detail);
+            }
+            match folded.entry(name.to_lowercase()) {
+                std::collections::hash_map::Entry::Occupied(other) => report(
+                    &path,
+                    "case_conflict",
+                    format!("conflicts with {}", other.get().display()),
+                ),
+                std::collections::hash_map::Entry::Vacant(slot) => {
+                    slot.insert(path.clone());
+                }
+            }
+
+            // Only the shallowest entry past the limit is reported
+            let separator = usize::from(dir_length > 0);
+            let length = dir_length + separator + name.encode_utf16().count();
+            if length > max_path_length && dir_length <= max_path_length {
+                report(
+                    &path,
+                    "path_too_long",
+                    format!("{length} characters below the root, the limit is {max_path_length}"),
+                );
+            }
+            if file_type.is_dir() {
+                stack.push((path, length));
+            }
+        }
+    }
+
+    issues.sort_by(|a, b| (&a.path, &a.issue).cmp(&(&b.path, &b.issue)));
+    Ok(issues)
+}
+
+/// Check a directory tree for names that break on other platforms.
+///
+/// Walks the tree below `root` without following symbolic links and reports
+/// names that collide case-insensitively within a directory (as on Windows
+/// and macOS), Windows reserved device names such as `CON` and `NUL`,
+/// characters Windows rejects, trailing dots and spaces, name components
+/// over 255 characters, and paths longer than `max_path_length` characters
+/// relative to the root. Directories that cannot be read are skipped.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `root` - Root of the tree to check; its own name is not checked
+/// * `max_path_length` - Longest allowed path below the root. Defaults to
+///   Windows' `MAX_PATH` of 260; lower it to leave room for the directory
+///   the tree is extracted into.
+///
+/// # Returns
+///
+/// A coroutine that yields a list of `PortabilityIssue` objects sorted by
+/// path. A name with several problems produces one issue per problem.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if the path or `max_path_length` is invalid,
+/// `PyFileNotFoundError` if the root does not exist, or `PyIOError` if it
+/// cannot be read.
+#[pyfunction]
+#[pyo3(signature = (root, max_path_length = 260))]
+fn check_portability_async(
+    py: Python<'_>,
+    root: String,
+    max_path_length: usize,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&root)?;
+    if max_path_length == 0 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Invalid max_path_length: 0. Must be at least 1",
+        ));
+    }
+    let op = OpContext::new("check_portability", [root.as_str()]);
+    let future = async move {
+        let scan_root = std::path::PathBuf::from(&root);
+        run_blocking(move || scan_portability(&scan_root, max_path_length))
+            .await
+            .map_err(|e| map_io_error(e, &root, "check portability of"))
+    };
+    run_op(py, op, future)
+}
+
+// Directory comparison
+
+/// Difference between two directory trees.
+///
+/// Yielded by the `DirCompare` stream returned from `dircmp_async`.
+///
+/// # Properties
+///
+/// * `path` - Path relative to both roots
+/// * `kind` - "only_in_a", "only_in_b", or "different"
+/// * `reason` - For "different" entries: "type", "size", "mtime", "content",
+///   or "target" (symlinks pointing elsewhere); `None` otherwise
+#[pyclass]
+#[derive(Clone)]
+struct DirDiff {
+    path: String,
+    kind: String,
+    reason: Option<String>,
+}
+
+#[pymethods]
+impl DirDiff {
+    #[getter]
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    #[getter]
+    fn kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    #[getter]
+    fn reason(&self) -> Option<String> {
+        self.reason.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.reason {
+            Some(reason) => format!(
+                "DirDiff(path={:?}, kind={:?}, reason={:?})",
+                self.path, self.kind, reason
+            ),
+            None => format!("DirDiff(path={:?}, kind={:?})", self.path, self.kind),
+        }
+    }
+}
+
+/// Async iterator over the differences between two directory trees.
+///
+/// The comparison runs on the Tokio runtime and feeds a bounded channel, so
+/// only a small window of differences is buffered at any time. Dropping the
+/// iterator stops the comparison.
+#[pyclass]
+struct DirCompare {
+    a: String,
+    b: String,
+    receiver: Arc<Mutex<tokio::sync::mpsc::Receiver<PyResult<DirDiff>>>>,
+}
+
+#[pymethods]
+impl DirCompare {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let receiver = Arc::clone(&self.receiver);
+        let op = OpContext::new("dircmp", [self.a.as_str(), self.b.as_str()]);
+        let future = async move {
+            match receiver.lock().await.recv().await {
+                Some(result) => result,
+                None => Err(PyErr::new::<pyo3::exceptions::PyStopAsyncIteration, _>(
+                    "Directory comparison complete",
+                )),
+            }
+        };
+        run_op(py, op, future)
+    }
+}
+
+/// Number of differences buffered ahead of the consumer.
+const DIRCMP_BUFFER: usize = 64;
+
+/// Chunk size used when comparing file contents.
+const DIRCMP_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Read the entry names of a directory in byte order.
+async fn sorted_entry_names(path: &std::path::Path) -> std::io::Result<Vec<std::ffi::OsString>> {
+    let mut entries = tokio::fs::read_dir(path).await?;
+    let mut names = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        names.push(entry.file_name());
+    }
+    names.sort_by(|a, b| SortOrder::Bytes.compare(a, b));
+    Ok(names)
+}
+
+/// Compare the contents of two files of equal size chunk by chunk.
+async fn same_contents(a: &std::path::Path, b: &std::path::Path) -> std::io::Result<bool> {
+    let mut file_a = File::open(a).await?;
+    let mut file_b = File::open(b).await?;
+    let mut buf_a = vec![0u8; DIRCMP_CHUNK_SIZE];
+    let mut buf_b = vec![0u8; DIRCMP_CHUNK_SIZE];
+    loop {
+        let n = file_a.read(&mut buf_a).await?;
+        if n == 0 {
+            return Ok(file_b.read(&mut buf_b[..1]).await? == 0);
+        }
+        match file_b.read_exact(&mut buf_b[..n]).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        if buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Outcome of comparing an entry present in both trees.
+enum EntryMatch {
+    /// Both entries match
+    Same,
+    /// Both entries are directories whose contents still need comparing
+    Directory,
+    /// The entries differ for the given reason
+    Different(&'static str),
+}
+
+/// Classify an entry present in both trees.
+async fn entry_difference(
+    a: &std::path::Path,
+    b: &std::path::Path,
+    compare_content: bool,
+) -> std::io::Result<EntryMatch> {
+    let meta_a = tokio::fs::symlink_metadata(a).await?;
+    let meta_b = tokio::fs::symlink_metadata(b).await?;
+    let (type_a, type_b) = (meta_a.file_type(), meta_b.file_type());
+    if type_a.is_dir() != type_b.is_dir()
+        || type_a.is_file() != type_b.is_file()
+        || type_a.is_symlink() != type_b.is_symlink()
+    {
+        return Ok(EntryMatch::Different("type"));
+    }
+    if type_a.is_dir() {
+        return Ok(EntryMatch::Directory);
+    }
+    let differs = if type_a.is_symlink() {
+        let target_a = tokio::fs::read_link(a).await?;
+        let target_b = tokio::fs::read_link(b).await?;
+        (target_a != target_b).then_some("target")
+    } else if !type_a.is_file() {
+        None
+    } else if meta_a.len() != meta_b.len() {
+        Some("size")
+    } else if compare_content {
+        (!same_contents(a, b).await?).then_some("content")
+    } else {
+        (meta_a.modified().ok() != meta_b.modified().ok()).then_some("mtime")
+    };
+    Ok(differs.map_or(EntryMatch::Same, EntryMatch::Different))
+}
+
+/// Walk both trees in lockstep and send every difference to `sender`.
+///
+/// Returns early once the receiving `DirCompare` has been dropped.
+async fn compare_trees(
+    root_a: String,
+    root_b: String,
+    compare_content: bool,
+    sender: tokio::sync::mpsc::Sender<PyResult<DirDiff>>,
+) {
+    let mut stack = vec![std::path::PathBuf::new()];
+    while let Some(rel_dir) = stack.pop() {
+        let dir_a = std::path::Path::new(&root_a).join(&rel_dir);
+        let dir_b = std::path::Path::new(&root_b).join(&rel_dir);
+        let names = match tokio::try_join!(sorted_entry_names(&dir_a), sorted_entry_names(&dir_b)) {
+            Ok(names) => names,
+            Err(e) => {
+                let path = format!("{} and {}", dir_a.display(), dir_b.display());
+                let _ = sender.send(Err(map_io_error(e, &path, "compare"))).await;
+                return;
+            }
+        };
+        let (names_a, names_b) = names;
+        let mut names_a = names_a.into_iter().peekable();
+        let mut names_b = names_b.into_iter().peekable();
+        let mut subdirs = Vec::new();
+
+        loop {
+            let ordering = match (names_a.peek(), names_b.peek()) {
+                (Some(a), Some(b)) => SortOrder::Bytes.compare(a, b),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => break,
+            };
+            let (name, kind) = match ordering {
+                std::cmp::Ordering::Less => (names_a.next().unwrap(), "only_in_a"),
+                std::cmp::Ordering::Greater => (names_b.next().unwrap(), "only_in_b"),
+                std::cmp::Ordering::Equal => {
+                    names_b.next();
+                    (names_a.next().unwrap(), "different")
+                }
+            };
+            let rel_path = rel_dir.join(&name);
+            let reason = if kind == "different" {
+                let path_a = dir_a.join(&name);
+                let path_b = dir_b.join(&name);
+                match entry_difference(&path_a, &path_b, compare_content).await {
+                    Ok(EntryMatch::Same) => continue,
+                    Ok(EntryMatch::Directory) => {
+                        subdirs.push(rel_path);
+                        continue;
+                    }
+                    Ok(EntryMatch::Different(reason)) => Some(reason),
+                    Err(e) => {
+                        let path = path_a.to_string_lossy();
+                        let _ = sender.send(Err(map_io_error(e, &path, "compare"))).await;
+                        return;
+                    }
+                }
+            } else {
+                None
+            };
+            let diff = DirDiff {
+                path: rel_path.to_string_lossy().to_string(),
+                kind: kind.to_string(),
+                reason: reason.map(str::to_string),
+            };
+            if sender.send(Ok(diff)).await.is_err() {
+                return;
+            }
+        }
+
+        // Descend in byte order once this directory's own entries are reported
+        stack.extend(subdirs.into_iter().rev());
+    }
+}
+
+/// Compare two directory trees as a stream of differences.
+///
+/// Walks both trees in lockstep without following symbolic links and yields
+/// a `DirDiff` for every entry that exists on only one side or differs
+/// between them. Differences are produced directory by directory in byte
+/// order while the caller consumes them, so neither tree is snapshotted into
+/// memory. All I/O operations execute outside the Python GIL using native
+/// Tokio.
+///
+/// # Arguments
+///
+/// * `a` - First directory tree
+/// * `b` - Second directory tree
+/// * `compare_content` - If true, files of equal size are compared byte by
+///   byte instead of by modification time
+///
+/// # Returns
+///
+/// A `DirCompare` async iterator yielding `DirDiff` objects.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if either path is invalid. I/O errors encountered
+/// during the walk (e.g. `PyFileNotFoundError` for a missing root) are raised
+/// from the iterator and end the comparison.
+#[pyfunction]
+#[pyo3(signature = (a, b, compare_content = false))]
+fn dircmp_async(a: String, b: String, compare_content: bool) -> PyResult<DirCompare> {
+    validate_path(&a)?;
+    validate_path(&b)?;
+    let (sender, receiver) = tokio::sync::mpsc::channel(DIRCMP_BUFFER);
+    runtime().spawn(compare_trees(a.clone(), b.clone(), compare_content, sender));
+    Ok(DirCompare {
+        a,
+        b,
+        receiver: Arc::new(Mutex::new(receiver)),
+    })
+}
+
+// File manipulation operations
+
+/// Copy a file asynchronously.
+///
+/// Copies a file from source to destination. If the destination file exists,
+/// it will be overwritten. The data is streamed in Rust (with `copy_file_range`
+/// or a reflink where the OS offers one) and never enters Python memory. All
+/// I/O operations execute outside the Python GIL using native Tokio, ensuring
+/// true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path to the source file
+/// * `dst` - Path to the destination file
+/// * `preserve_owner` - If true, give the destination the source's owning user
+///   and group (Unix only; changing ownership usually requires root)
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` or
+///   `"fsync+dir"`; see `Durability`
+///
+/// # Returns
+///
+/// A coroutine that yields the number of bytes copied.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the source file does not exist,
+/// `PyPermissionError` if ownership cannot be preserved,
+/// `PyIOError` if the file cannot be copied or synced, or `PyValueError` if
+/// the path or durability is invalid.
+#[pyfunction]
+#[pyo3(signature = (src, dst, preserve_owner = false, durability = "flush"))]
+fn copy_file_async<'py>(
+    py: Python<'py>,
+    src: String,
+    dst: String,
+    preserve_owner: bool,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    let durability = Durability::parse(durability)?;
+    let op = OpContext::new("copy_file", [src.as_str(), dst.as_str()]);
+    let future = async move {
+        let src_clone = src.clone();
+        let dst_clone = dst.clone();
+        let copied = tokio::fs::copy(&src, &dst)
+            .await
+            .map_err(|e| map_io_error(e, &format!("{src_clone} -> {dst_clone}"), "copy file"))?;
+        record_transfer(copied as usize);
+        if preserve_owner {
+            preserve_ownership(&src, &dst)
+                .await
+                .map_err(|e| map_io_error(e, &dst_clone, "preserve owner of"))?;
+        }
+        let dst_path = std::path::Path::new(&dst);
+        durability
+            .sync_file(dst_path)
+            .await
+            .map_err(|e| map_io_error(e, &dst_clone, "sync"))?;
+        durability
+            .sync_parent(dst_path)
+            .await
+            .map_err(|e| map_io_error(e, &dst_clone, "sync directory of"))?;
+        Ok(copied)
+    };
+    run_op(py, op, future)
+}
+
+/// Give `dst` the owning user and group of `src`.
+///
+/// Used by copy operations with `preserve_owner=True`. Changing ownership to
+/// another user generally requires root privileges. This is a no-op on
+/// non-Unix platforms, where ownership is not expressed as uid/gid.
+async fn preserve_ownership(src: &str, dst: &str) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = tokio::fs::metadata(src).await?;
+        let (uid, gid) = (metadata.uid(), metadata.gid());
+        let dst = dst.to_string();
+        tokio::task::spawn_blocking(move || std::os::unix::fs::chown(dst, Some(uid), Some(gid)))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (src, dst);
+        Ok(())
+    }
+}
+
+/// Default number of files copied or linked at once by `copytree_async`.
+const COPYTREE_CONCURRENCY: usize = 64;
+
+/// How `copytree_async` materializes the files of the tree.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CopyMode {
+    /// Copy file contents
+    Copy,
+    /// Hard link files, copying only when source and destination are on
+    /// different devices
+    Hardlink,
+}
+
+impl CopyMode {
+    /// Parse the `mode` argument of `copytree_async`.
+    fn parse(mode: &str) -> PyResult<Self> {
+        match mode {
+            "copy" => Ok(CopyMode::Copy),
+            "hardlink" => Ok(CopyMode::Hardlink),
+            other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid copy mode: {other}. Must be 'copy' or 'hardlink'"
+            ))),
+        }
+    }
+}
+
+pyo3::import_exception!(shutil, SpecialFileError);
+
+/// What kind of special file `file_type` is, if it is one that cannot be
+/// copied: reading a FIFO blocks until a writer shows up, and sockets and
+/// devices have no contents to copy.
+fn special_file_kind(file_type: std::fs::FileType) -> Option<&'static str> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        if file_type.is_fifo() {
+            return Some("a named pipe");
+        }
+        if file_type.is_socket() {
+            return Some("a socket");
+        }
+        if file_type.is_block_device() || file_type.is_char_device() {
+            return Some("a device");
+        }
+    }
+    let _ = file_type;
+    None
+}
+
+/// Materialize one file of a tree at `dst` according to `mode`.
+///
+/// Fails with `shutil.SpecialFileError` if `file_type` is a FIFO, socket or
+/// device, like `shutil.copytree`.
+async fn copy_tree_file(
+    src: std::path::PathBuf,
+    dst: std::path::PathBuf,
+    file_type: std::fs::FileType,
+    mode: CopyMode,
+    preserve_owner: bool,
+) -> PyResult<()> {
+    if let Some(kind) = special_file_kind(file_type) {
+        return Err(SpecialFileError::new_err(format!(
+            "Failed to copy file {}: it is {kind}",
+            src.display()
+        )));
+    }
+    let describe = || format!("{} -> {}", src.display(), dst.display());
+    if mode == CopyMode::Hardlink {
+        match tokio::fs::hard_link(&src, &dst).await {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {}
+            Err(e) => return Err(map_io_error(e, &describe(), "hard link")),
+        }
+    }
+    let copied = tokio::fs::copy(&src, &dst)
+        .await
+        .map_err(|e| map_io_error(e, &describe(), "copy file"))?;
+    record_transfer(copied as usize);
+    if preserve_owner {
+        preserve_ownership(&src.to_string_lossy(), &dst.to_string_lossy())
+            .await
+            .map_err(|e| map_io_error(e, &dst.to_string_lossy(), "preserve owner of"))?;
+    }
+    Ok(())
+}
+
+/// Format marker on the first line of a `copytree_async` checkpoint file.
+const CHECKPOINT_HEADER: &str = "rapfiles copytree checkpoint 1";
+
+/// How a resumed `copytree_async` decides that a recorded file is intact.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ResumeCheck {
+    /// The source size and mtime are unchanged and the copy has that size
+    Stat,
+    /// As `Stat`, and the contents of source and copy are equal
+    Content,
+}
+
+impl ResumeCheck {
+    /// Parse the `verify` argument of `copytree_async`.
+    fn parse(verify: &str) -> PyResult<Self> {
+        match verify {
+            "stat" => Ok(ResumeCheck::Stat),
+            "content" => Ok(ResumeCheck::Content),
+            other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid verify mode: {other}. Must be 'stat' or 'content'"
+            ))),
+        }
+    }
+}
+
+/// Size and modification time of a file, to tell whether it has changed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    size: u64,
+    mtime_ns: i128,
+}
+
+impl FileStamp {
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        let mtime_ns = metadata
+            .modified()
+            .map(|time| match time.duration_since(UNIX_EPOCH) {
+                Ok(after) => after.as_nanos() as i128,
+                Err(before) => -(before.duration().as_nanos() as i128),
+            })
+            .unwrap_or(0);
+        FileStamp {
+            size: metadata.len(),
+            mtime_ns,
+        }
+    }
+}
+
+/// Percent-encode a path so it fits on one line of a checkpoint file.
+fn encode_checkpoint_path(path: &std::ffi::OsStr) -> String {
+    use std::fmt::Write;
+
+    let mut encoded = String::new();
+    for &byte in path.as_encoded_bytes() {
+        if byte == b'%' || byte == b' ' || byte.is_ascii_control() || !byte.is_ascii() {
+            let _ = write!(encoded, "%{byte:02X}");
+        } else {
+            encoded.push(byte as char);
+        }
+    }
+    encoded
+}
+
+/// Reverse `encode_checkpoint_path`.
+fn decode_checkpoint_path(encoded: &str) -> Option<std::path::PathBuf> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Some(std::ffi::OsStr::from_bytes(&bytes).into())
+    }
+    #[cfg(not(unix))]
+    {
+        Some(String::from_utf8_lossy(&bytes).into_owned().into())
+    }
+}
+
+/// Progress of a `copytree_async` run, kept on disk so an interrupted copy
+/// can resume instead of starting over.
+///
+/// The file holds a header, the source and destination roots, and then one
+/// line per completed file: the size and mtime (in nanoseconds) the source
+/// had when it was copied, and its path relative to the tree root. A line
+/// cut short by a crash is ignored, so that file is copied again.
+struct CopyCheckpoint {
+    path: String,
+    /// Files recorded by an earlier run, by path relative to the tree root
+    completed: std::collections::HashMap<std::path::PathBuf, FileStamp>,
+    check: ResumeCheck,
+    writer: Mutex<File>,
+}
+
+impl CopyCheckpoint {
+    /// Resume from the checkpoint at `path`, or create `dst` and then a new
+    /// checkpoint if there is none yet.
+    async fn open(path: String, src: &str, dst: &str, check: ResumeCheck) -> PyResult<Self> {
+        let roots = format!(
+            "src {}\ndst {}\n",
+            encode_checkpoint_path(src.as_ref()),
+            encode_checkpoint_path(dst.as_ref())
+        );
+        let completed = match tokio::fs::read(&path).await {
+            Ok(contents) => Self::parse(&path, &contents, &roots, src, dst)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tokio::fs::create_dir(dst)
+                    .await
+                    .map_err(|e| map_io_error(e, dst, "create directory"))?;
+                tokio::fs::write(&path, format!("{CHECKPOINT_HEADER}\n{roots}"))
+                    .await
+                    .map_err(|e| map_io_error(e, &path, "write checkpoint"))?;
+                std::collections::HashMap::new()
+            }
+            Err(e) => return Err(map_io_error(e, &path, "read checkpoint")),
+        };
+        let writer = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await
+            .map_err(|e| map_io_error(e, &path, "open checkpoint"))?;
+        Ok(CopyCheckpoint {
+            path,
+            completed,
+            check,
+            writer: Mutex::new(writer),
+        })
+    }
+
+    /// Parse the completed files of an existing checkpoint.
+    fn parse(
+        path: &str,
+        contents: &[u8],
+        roots: &str,
+        src: &str,
+        dst: &str,
+    ) -> PyResult<std::collections::HashMap<std::path::PathBuf, FileStamp>> {
+        let invalid = |detail: &str| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid copytree checkpoint {path}: {detail}"
+            ))
+        };
+        let text = std::str::from_utf8(contents).map_err(|_| invalid("not UTF-8"))?;
+        let body = text
+            .strip_prefix(CHECKPOINT_HEADER)
+            .and_then(|rest| rest.strip_prefix('\n'))
+            .ok_or_else(|| invalid("missing header"))?;
+        let entries = body.strip_prefix(roots).ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Checkpoint {path} does not belong to a copy of {src} to {dst}"
+            ))
+        })?;
+        let mut completed = std::collections::HashMap::new();
+        // The last piece has no newline yet: it was cut short or is empty
+        let mut lines: Vec<&str> = entries.split('\n').collect();
+        lines.pop();
+        for line in lines {
+            let mut fields = line.splitn(3, ' ');
+            let entry = (|| {
+                let size = fields.next()?.parse().ok()?;
+                let mtime_ns = fields.next()?.parse().ok()?;
+                let rel = decode_checkpoint_path(fields.next()?)?;
+                Some((rel, FileStamp { size, mtime_ns }))
+            })();
+            let (rel, stamp) = entry.ok_or_else(|| invalid(&format!("bad entry {line:?}")))?;
+            completed.insert(rel, stamp);
+        }
+        Ok(completed)
+    }
+
+    /// Whether an earlier run already copied the file at `rel` intact.
+    async fn is_intact(
+        &self,
+        rel: &std::path::Path,
+        stamp: FileStamp,
+        src: &std::path::Path,
+        dst: &std::path::Path,
+    ) -> std::io::Result<bool> {
+        if self.completed.get(rel) != Some(&stamp) {
+            return Ok(false);
+        }
+        match tokio::fs::symlink_metadata(dst).await {
+            Ok(metadata) if metadata.is_file() && metadata.len() == stamp.size => {}
+            Ok(_) => return Ok(false),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        match self.check {
+            ResumeCheck::Stat => Ok(true),
+            ResumeCheck::Content => same_contents(src, dst).await,
+        }
+    }
+
+    /// Append a completed file to the checkpoint.
+    async fn record(&self, rel: &std::path::Path, stamp: FileStamp) -> PyResult<()> {
+        let line = format!(
+            "{} {} {}\n",
+            stamp.size,
+            stamp.mtime_ns,
+            encode_checkpoint_path(rel.as_os_str())
+        );
+        let mut writer = self.writer.lock().await;
+        async {
+            writer.write_all(line.as_bytes()).await?;
+            writer.flush().await
+        }
+        .await
+        .map_err(|e| map_io_error(e, &self.path, "write checkpoint"))
+    }
+}
+
+/// Copy one file of a checkpointed tree, unless an earlier run already did.
+async fn resume_tree_file(
+    rel: std::path::PathBuf,
+    src: std::path::PathBuf,
+    dst: std::path::PathBuf,
+    file_type: std::fs::FileType,
+    mode: CopyMode,
+    preserve_owner: bool,
+    checkpoint: Arc<CopyCheckpoint>,
+) -> PyResult<()> {
+    let metadata = tokio::fs::metadata(&src)
+        .await
+        .map_err(|e| map_io_error(e, &src.to_string_lossy(), "stat"))?;
+    let stamp = FileStamp::of(&metadata);
+    if checkpoint
+        .is_intact(&rel, stamp, &src, &dst)
+        .await
+        .map_err(|e| map_io_error(e, &dst.to_string_lossy(), "verify"))?
+    {
+        return Ok(());
+    }
+    // Left behind half-copied, or stale after the source changed
+    match tokio::fs::remove_file(&dst).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(map_io_error(e, &dst.to_string_lossy(), "remove"));
+        }
+        _ => {}
+    }
+    copy_tree_file(src, dst, file_type, mode, preserve_owner).await?;
+    checkpoint.record(&rel, stamp).await
+}
+
+/// Copy a directory tree asynchronously.
+///
+/// Recreates the directory structure of `src` at `dst` and then copies (or,
+/// with `mode="hardlink"`, hard links) up to `COPYTREE_CONCURRENCY` files
+/// (or the matching route's `concurrency`) at a time. Hard linking makes
+/// snapshot-style deployments of large trees nearly instant; files on a
+/// different device than `dst` fall back to a copy. Symlinks are recreated as symlinks on Unix rather than followed.
+/// With a `checkpoint` file, every copied file is recorded so that running
+/// the same copy again after an interruption resumes it: files recorded as
+/// complete are verified (see `ResumeCheck`) and skipped, everything else is
+/// copied again. The checkpoint is removed once the copy succeeds.
+/// With `sanitize`, names that are not valid on Windows (see
+/// `SanitizeMode::sanitize`) are escaped in the copy, or fail it.
+/// All I/O operations execute outside the Python GIL using native Tokio,
+/// ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path to the directory to copy
+/// * `dst` - Path of the new tree (must not exist, unless resuming)
+/// * `mode` - `"copy"` (default) or `"hardlink"`
+/// * `preserve_owner` - If true, give copied files and created directories
+///   the uid/gid of their source (Unix only, usually requires root)
+/// * `checkpoint` - Path of the checkpoint file to record progress in and
+///   resume from
+/// * `verify` - `"stat"` (default) or `"content"`: how files recorded in the
+///   checkpoint are verified before being skipped
+/// * `sanitize` - `"replace"`, `"percent-encode"` or `"error"`: how names
+///   that are not valid on Windows are handled; by default they are copied
+///   unchanged
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success, or with `sanitize`, a list of
+/// the `(source, destination)` paths of renamed entries.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if `src` does not exist,
+/// `PyFileExistsError` if `dst` already exists without a checkpoint or two
+/// entries are sanitized to the same name, `SpecialFileError` if an entry is
+/// a FIFO, socket or device, `PyIOError` if an entry cannot be copied, or
+/// `PyValueError` if a path, the mode, the sanitize mode or the checkpoint
+/// is invalid, `dst` is inside `src`, or a name is invalid with
+/// `sanitize="error"`.
+#[pyfunction]
+#[pyo3(signature = (src, dst, mode = "copy", preserve_owner = false, checkpoint = None, verify = "stat", sanitize = None))]
+#[allow(clippy::too_many_arguments)] // Mirrors the Python signature
+fn copytree_async<'py>(
+    py: Python<'py>,
+    src: String,
+    dst: String,
+    mode: &str,
+    preserve_owner: bool,
+    checkpoint: Option<String>,
+    verify: &str,
+    sanitize: Option<&str>,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    if let Some(checkpoint) = &checkpoint {
+        validate_path(checkpoint)?;
+    }
+    let mode = CopyMode::parse(mode)?;
+    let check = ResumeCheck::parse(verify)?;
+    let sanitize = sanitize.map(SanitizeMode::parse).transpose()?;
+    let op = OpContext::new(
+        "copytree",
+        [src.as_str(), dst.as_str()]
+            .into_iter()
+            .chain(checkpoint.as_deref()),
+    );
+    let future = async move {
+        use futures::stream::{self, StreamExt, TryStreamExt};
+
+        let metadata = tokio::fs::metadata(&src)
+            .await
+            .map_err(|e| map_io_error(e, &src, "copy tree"))?;
+        if !metadata.is_dir() {
+            return Err(PyErr::new::<pyo3::exceptions::PyNotADirectoryError, _>(
+                format!("Failed to copy tree {src}: not a directory"),
+            ));
+        }
+        // A copy inside its own source would keep copying itself
+        let roots = (src.clone(), dst.clone());
+        let (src_real, dst_real) = run_blocking(move || {
+            Ok((
+                real_path(roots.0.as_ref(), false)?,
+                real_path(roots.1.as_ref(), false)?,
+            ))
+        })
+        .await
+        .map_err(|e| map_io_error(e, &dst, "resolve path"))?;
+        if dst_real.starts_with(&src_real) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Failed to copy tree {src}: destination {dst} is inside the source"
+            )));
+        }
+
+        // Plan the copy before creating anything, so a name `sanitize`
+        // rejects leaves no partial tree behind
+        let mut dirs = Vec::new();
+        #[cfg(unix)]
+        let mut links = Vec::new();
+        let mut files = Vec::new();
+        let mut renamed = Vec::new();
+        let mut pending = vec![(
+            std::path::PathBuf::from(&src),
+            std::path::PathBuf::from(&dst),
+            std::path::PathBuf::new(),
+        )];
+        while let Some((src_dir, dst_dir, rel_dir)) = pending.pop() {
+            let mut entries = tokio::fs::read_dir(&src_dir)
+                .await
+                .map_err(|e| map_io_error(e, &src_dir.to_string_lossy(), "read directory"))?;
+            let mut target_names = std::collections::HashSet::new();
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| map_io_error(e, &src_dir.to_string_lossy(), "read directory"))?
+            {
+                let file_type = entry
+                    .file_type()
+                    .await
+                    .map_err(|e| map_io_error(e, &entry.path().to_string_lossy(), "stat"))?;
+                let mut name = entry.file_name();
+                if let Some(sanitize) = sanitize {
+                    if let Some(sanitized) = sanitize.sanitize(&name) {
+                        if sanitize == SanitizeMode::Error {
+                            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                                "Failed to copy tree {src}: {} is not a valid name on Windows",
+                                entry.path().display()
+                            )));
+                        }
+                        name = sanitized.into();
+                        renamed.push((
+                            entry.path().to_string_lossy().to_string(),
+                            dst_dir.join(&name).to_string_lossy().to_string(),
+                        ));
+                    }
+                    if !target_names.insert(name.clone()) {
+                        return Err(PyErr::new::<pyo3::exceptions::PyFileExistsError, _>(
+                            format!(
+                                "Failed to copy tree {src}: {} would be copied to {}, like another entry",
+                                entry.path().display(),
+                                dst_dir.join(&name).display()
+                            ),
+                        ));
+                    }
+                }
+                let target = dst_dir.join(&name);
+                let rel = rel_dir.join(entry.file_name());
+                if file_type.is_dir() {
+                    pending.push((entry.path(), target, rel));
+                    continue;
+                }
+                #[cfg(unix)]
+                if file_type.is_symlink() {
+                    let link = tokio::fs::read_link(entry.path()).await.map_err(|e| {
+                        map_io_error(e, &entry.path().to_string_lossy(), "read link")
+                    })?;
+                    links.push((link, target));
+                    continue;
+                }
+                files.push((rel, entry.path(), target, file_type));
+            }
+            // Parents are listed before their subdirectories
+            dirs.push((src_dir, dst_dir));
+        }
+
+        let checkpoint = match checkpoint {
+            Some(path) => Some(Arc::new(
+                CopyCheckpoint::open(path, &src, &dst, check).await?,
+            )),
+            None => None,
+        };
+
+        // Create every directory and symlink first.
+        // A checkpointed copy has created `dst` already, or is resuming.
+        for (src_dir, dst_dir) in dirs {
+            match tokio::fs::create_dir(&dst_dir).await {
+                Err(e)
+                    if e.kind() == std::io::ErrorKind::AlreadyExists
+                        && checkpoint.is_some()
+                        && tokio::fs::metadata(&dst_dir)
+                            .await
+                            .is_ok_and(|metadata| metadata.is_dir()) => {}
+                Err(e) => {
+                    return Err(map_io_error(
+                        e,
+                        &dst_dir.to_string_lossy(),
+                        "create directory",
+                    ))
+                }
+                Ok(()) => {}
+            }
+            if preserve_owner {
+                preserve_ownership(&src_dir.to_string_lossy(), &dst_dir.to_string_lossy())
+                    .await
+                    .map_err(|e| {
+                        map_io_error(e, &dst_dir.to_string_lossy(), "preserve owner of")
+                    })?;
+            }
+        }
+        #[cfg(unix)]
+        for (link, target) in links {
+            if checkpoint.is_some() {
+                match tokio::fs::remove_file(&target).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(map_io_error(e, &target.to_string_lossy(), "remove"));
+                    }
+                    _ => {}
+                }
+            }
+            tokio::fs::symlink(&link, &target)
+                .await
+                .map_err(|e| map_io_error(e, &target.to_string_lossy(), "create symlink"))?;
+        }
+
+        let concurrency = op_concurrency(COPYTREE_CONCURRENCY);
+        match checkpoint {
+            None => {
+                stream::iter(files)
+                    .map(|(_, src, dst, file_type)| {
+                        copy_tree_file(src, dst, file_type, mode, preserve_owner)
+                    })
+                    .buffer_unordered(concurrency)
+                    .try_collect::<()>()
+                    .await?
+            }
+            Some(checkpoint) => {
+                stream::iter(files)
+                    .map(|(rel, src, dst, file_type)| {
+                        let checkpoint = Arc::clone(&checkpoint);
+                        resume_tree_file(rel, src, dst, file_type, mode, preserve_owner, checkpoint)
+                    })
+                    .buffer_unordered(concurrency)
+                    .try_collect::<()>()
+                    .await?;
+                tokio::fs::remove_file(&checkpoint.path)
+                    .await
+                    .map_err(|e| map_io_error(e, &checkpoint.path, "remove checkpoint"))?;
+            }
+        }
+        renamed.sort();
+        Ok(sanitize.map(|_| renamed))
+    };
+    run_op(py, op, future)
+}
+
+/// Move or rename a file asynchronously.
+///
+/// Moves a file from source to destination. This is an atomic operation when
+/// moving within the same filesystem. For cross-device moves, it will copy
+/// and then remove the source file. All I/O operations execute outside the
+/// Python GIL using native Tokio, ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path to the source file
+/// * `dst` - Path to the destination file
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the source file does not exist,
+/// `PyIOError` if the file cannot be moved, or `PyValueError` if the path is invalid.
+#[pyfunction]
+fn move_file_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    let op = OpContext::new("move_file", [src.as_str(), dst.as_str()]);
+    let future = async move {
+        let src_clone = src.clone();
+        let dst_clone = dst.clone();
+
+        // Try rename first (atomic on same filesystem)
+        match tokio::fs::rename(&src, &dst).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                // Cross-device move: copy then remove
+                tokio::fs::copy(&src, &dst).await.map_err(|e| {
+                    map_io_error(e, &format!("{src_clone} -> {dst_clone}"), "copy file")
+                })?;
+                tokio::fs::remove_file(&src)
+                    .await
+                    .map_err(|e| map_io_error(e, &src_clone, "remove file"))?;
+                Ok(())
+            }
+            Err(e) => Err(map_io_error(
+                e,
+                &format!("{src_clone} -> {dst_clone}"),
+                "move file",
+            )),
+        }
+    };
+    run_op(py, op, future)
+}
+
+/// Copy `src` to `dst` for a move across filesystems, returning the number
+/// of bytes copied.
+///
+/// Permissions and access/modification times are kept, and file contents
+/// are synced to disk. Directories are copied recursively, with symlinks
+/// copied as links on Unix, and get their metadata after their contents.
+fn copy_for_move(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<u64> {
+    let metadata = std::fs::symlink_metadata(src)?;
+    let file_type = metadata.file_type();
+    #[cfg(unix)]
+    if file_type.is_symlink() {
+        std::os::unix::fs::symlink(std::fs::read_link(src)?, dst)?;
+        return Ok(0);
+    }
+    let times = std::fs::FileTimes::new()
+        .set_accessed(metadata.accessed()?)
+        .set_modified(metadata.modified()?);
+
+    if file_type.is_dir() {
+        std::fs::create_dir(dst)?;
+        let mut copied = 0;
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            copied += copy_for_move(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        #[cfg(unix)]
+        std::fs::File::open(dst)?.set_times(times)?;
+        std::fs::set_permissions(dst, metadata.permissions())?;
+        return Ok(copied);
+    }
+    if !file_type.is_file() && !file_type.is_symlink() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("{} is not a regular file or directory", src.display()),
+        ));
+    }
+
+    let mut reader = std::fs::File::open(src)?;
+    let mut writer = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dst)?;
+    let copied = std::io::copy(&mut reader, &mut writer)?;
+    writer.set_times(times)?;
+    writer.set_permissions(metadata.permissions())?;
+    writer.sync_all()?;
+    Ok(copied)
+}
+
+/// Move `src` to `dst` on another filesystem, returning the number of bytes
+/// copied.
+///
+/// The copy is built under a temporary name next to `dst` and renamed into
+/// place, so `dst` never holds a partial copy; the source is removed by the
+/// caller once this succeeds.
+fn move_across_devices(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<u64> {
+    let (Some(dir), Some(file_name)) = (dst.parent(), dst.file_name()) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "destination has no parent directory or file name",
+        ));
+    };
+    let temp = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    let copied = copy_for_move(src, &temp).and_then(|copied| {
+        std::fs::rename(&temp, dst)?;
+        // Persist the new directory entry too
+        sync_parent_dir(dst)?;
+        Ok(copied)
+    });
+    if copied.is_err() {
+        let _ = match std::fs::symlink_metadata(&temp) {
+            Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(&temp),
+            _ => std::fs::remove_file(&temp),
+        };
+    }
+    copied
+}
+
+/// Move a file or directory asynchronously, like `shutil.move`.
+///
+/// If `dst` is an existing directory, `src` is moved inside it. The move is
+/// a rename where possible; across filesystems, `src` is copied with its
+/// permissions and timestamps, synced to disk, renamed into place and only
+/// then removed. All I/O operations execute outside the Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path of the file or directory to move
+/// * `dst` - Destination path, or an existing directory to move into
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` (a renamed
+///   entry is synced too) or `"fsync+dir"` (the directories of source and
+///   destination are synced as well); see `Durability`
+///
+/// # Returns
+///
+/// A coroutine that yields the path `src` was moved to.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the source does not exist,
+/// `PyFileExistsError` if `dst` is a directory that already contains an
+/// entry of that name, `PyIOError` if the move fails, or `PyValueError` if
+/// a path or the durability is invalid.
+#[pyfunction]
+#[pyo3(signature = (src, dst, durability = "flush"))]
+fn move_async<'py>(
+    py: Python<'py>,
+    src: String,
+    dst: String,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    let durability = Durability::parse(durability)?;
+    let op = OpContext::new("move", [src.as_str(), dst.as_str()]);
+    let future = async move {
+        let describe = format!("{src} -> {dst}");
+        let mut target = std::path::PathBuf::from(&dst);
+        if tokio::fs::metadata(&dst)
+            .await
+            .is_ok_and(|metadata| metadata.is_dir())
+        {
+            let name = std::path::Path::new(src.trim_end_matches(std::path::is_separator))
+                .file_name()
+                .ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Failed to move {describe}: source has no file name"
+                    ))
+                })?;
+            target.push(name);
+            if tokio::fs::symlink_metadata(&target).await.is_ok() {
+                return Err(PyErr::new::<pyo3::exceptions::PyFileExistsError, _>(
+                    format!(
+                        "Failed to move {describe}: {} already exists",
+                        target.display()
+                    ),
+                ));
+            }
+        }
+        let target_str = target.to_string_lossy().into_owned();
+        let sync_dirs = || async {
+            durability
+                .sync_parent(std::path::Path::new(&target_str))
+                .await
+                .map_err(|e| map_io_error(e, &target_str, "sync directory of"))?;
+            durability
+                .sync_parent(std::path::Path::new(&src))
+                .await
+                .map_err(|e| map_io_error(e, &src, "sync directory of"))
+        };
+
+        match tokio::fs::rename(&src, &target).await {
+            Ok(()) => {
+                durability
+                    .sync_file(&target)
+                    .await
+                    .map_err(|e| map_io_error(e, &target_str, "sync"))?;
+                sync_dirs().await?;
+                return Ok(target_str);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {}
+            Err(e) => return Err(map_io_error(e, &describe, "move")),
+        }
+
+        // The copy is always synced, as the source is removed afterwards
+        let src_path = std::path::PathBuf::from(&src);
+        let copied = run_blocking(move || move_across_devices(&src_path, &target))
+            .await
+            .map_err(|e| map_io_error(e, &describe, "move"))?;
+        record_transfer(copied as usize);
+
+        let src_path = std::path::PathBuf::from(&src);
+        run_blocking(move || match std::fs::symlink_metadata(&src_path) {
+            Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(&src_path),
+            _ => std::fs::remove_file(&src_path),
+        })
+        .await
+        .map_err(|e| map_io_error(e, &src, "remove moved source"))?;
+        sync_dirs().await?;
+        Ok(target_str)
+    };
+    run_op(py, op, future)
+}
+
+/// Rename `src` to `dst`, failing if `dst` exists.
+///
+/// `std::fs::rename` replaces an existing file on Windows, while
+/// `os.rename` raises there; `MoveFileExW` without `MOVEFILE_REPLACE_EXISTING`
+/// keeps Python's behavior.
+#[cfg(windows)]
+fn rename_no_replace(src: &str, dst: &str) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn MoveFileExW(existing: *const u16, new: *const u16, flags: u32) -> i32;
+    }
+
+    let wide = |path: &str| -> Vec<u16> {
+        std::ffi::OsStr::new(path)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    };
+    let (src, dst) = (wide(src), wide(dst));
+    // SAFETY: both paths are NUL-terminated and outlive the call
+    if unsafe { MoveFileExW(src.as_ptr(), dst.as_ptr(), 0) } == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Rename a file or directory asynchronously, like `os.rename`.
+///
+/// A single `rename(2)` call: there is no copy fallback, so moving across
+/// filesystems fails. On POSIX an existing destination file is replaced
+/// atomically; on Windows an existing destination is an error, as with
+/// `os.rename`. All I/O operations execute outside the Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path to rename
+/// * `dst` - New path
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if `src` does not exist,
+/// `PyFileExistsError` if `dst` exists on Windows, `PyIOError` if the
+/// rename fails (e.g., across filesystems), or `PyValueError` if a path is
+/// invalid.
+#[pyfunction]
+fn rename_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    let op = OpContext::new("rename", [src.as_str(), dst.as_str()]);
+    let future = async move {
+        let label = format!("{src} -> {dst}");
+        #[cfg(windows)]
+        let result = run_blocking(move || rename_no_replace(&src, &dst)).await;
+        #[cfg(not(windows))]
+        let result = tokio::fs::rename(&src, &dst).await;
+        result.map_err(|e| map_io_error(e, &label, "rename"))
+    };
+    run_op(py, op, future)
+}
+
+/// Rename a file or directory, replacing the destination, like `os.replace`.
+///
+/// An existing destination file (or, on POSIX, empty directory) is replaced
+/// atomically: other processes see either the old or the new file at `dst`,
+/// never neither. On Windows the replacement uses `MoveFileExW` with
+/// `MOVEFILE_REPLACE_EXISTING` (or POSIX rename semantics where the
+/// filesystem supports them). There is no copy fallback, so moving across
+/// filesystems fails. All I/O operations execute outside the Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path to rename
+/// * `dst` - New path, replaced if it exists
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if `src` does not exist, `PyIOError` if the
+/// rename fails (e.g., across filesystems, or `dst` is a non-empty
+/// directory), or `PyValueError` if a path is invalid.
+#[pyfunction]
+fn replace_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    let op = OpContext::new("replace", [src.as_str(), dst.as_str()]);
+    let future = async move {
+        tokio::fs::rename(&src, &dst)
+            .await
+            .map_err(|e| map_io_error(e, &format!("{src} -> {dst}"), "replace"))
+    };
+    run_op(py, op, future)
+}
+
+/// Remove the file (or symlink) at `path`, refusing directories.
+///
+/// With `missing_ok`, a path that does not exist is not an error.
+async fn remove_file_at(path: &str, missing_ok: bool) -> PyResult<()> {
+    // Check for a directory first to provide a better error message; a
+    // symlink to one is removed like any other link
+    match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) if metadata.is_dir() => {
+            return Err(PyErr::new::<pyo3::exceptions::PyIsADirectoryError, _>(
+                format!(
+                    "Failed to remove file {path}: path is a directory. Use remove_dir() instead."
+                ),
+            ));
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && missing_ok => return Ok(()),
+        _ => {}
+    }
+
+    match tokio::fs::remove_file(path).await {
+        // Removed concurrently since the check
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && missing_ok => Ok(()),
+        result => result.map_err(|e| map_io_error(e, path, "remove file")),
+    }
+}
+
+/// Remove a file asynchronously.
+///
+/// Deletes a file from the filesystem. This will not remove directories.
+/// All I/O operations execute outside the Python GIL using native Tokio,
+/// ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to remove
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the file does not exist,
+/// `PyIsADirectoryError` if the path is a directory, `PyIOError` if the
+/// file cannot be removed, or `PyValueError` if the path is invalid.
+#[pyfunction]
+fn remove_file_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("remove_file", [path.as_str()]);
+    let future = async move { remove_file_at(&path, false).await };
+    run_op(py, op, future)
+}
+
+/// Remove a file asynchronously, like `pathlib.Path.unlink()`.
+///
+/// Same as `remove_file_async`, except that with `missing_ok` a path that
+/// does not exist is silently ignored.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to remove
+/// * `missing_ok` - Whether a missing path is not an error
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the file does not exist and `missing_ok`
+/// is false, `PyIsADirectoryError` if the path is a directory, `PyIOError`
+/// if the file cannot be removed, or `PyValueError` if the path is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, missing_ok=false))]
+fn unlink_async(py: Python<'_>, path: String, missing_ok: bool) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("unlink", [path.as_str()]);
+    let future = async move { remove_file_at(&path, missing_ok).await };
+    run_op(py, op, future)
+}
+
+/// Hard link `dst` to `src`.
+///
+/// Linking across filesystems gets its own message, as the generic
+/// "Invalid cross-device link" doesn't say what to do instead.
+async fn hard_link_at(src: &str, dst: &str) -> PyResult<()> {
+    tokio::fs::hard_link(src, dst).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::CrossesDevices {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to create hard link {src} -> {dst}: source and destination are on \
+                 different filesystems; copy the file or create a symlink instead ({e})"
+            ))
+        } else {
+            map_io_error(e, &format!("{src} -> {dst}"), "create hard link")
+        }
+    })
+}
+
+/// Create a hard link asynchronously.
+///
+/// Creates a hard link from source to destination. Both files will refer
+/// to the same underlying file data. All I/O operations execute outside
+/// the Python GIL using native Tokio, ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path to the source file
+/// * `dst` - Path to the destination link
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the source file does not exist,
+/// `PyFileExistsError` if the destination exists, `PyIOError` if the link
+/// cannot be created (including across filesystems), or `PyValueError` if
+/// the path is invalid.
+#[pyfunction]
+fn hard_link_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    let op = OpContext::new("hard_link", [src.as_str(), dst.as_str()]);
+    let future = async move { hard_link_at(&src, &dst).await };
+    run_op(py, op, future)
+}
+
+/// Create a hard link asynchronously, named after `os.link`.
+///
+/// Same as `hard_link_async`.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path to the source file
+/// * `dst` - Path to the destination link
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the source file does not exist,
+/// `PyFileExistsError` if the destination exists, `PyIOError` if the link
+/// cannot be created (including across filesystems), or `PyValueError` if
+/// the path is invalid.
+#[pyfunction]
+fn link_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    let op = OpContext::new("link", [src.as_str(), dst.as_str()]);
+    let future = async move { hard_link_at(&src, &dst).await };
+    run_op(py, op, future)
+}
+
+/// Create a symbolic link asynchronously.
+///
+/// Creates a symbolic link from source to destination. The destination
+/// will point to the source path. All I/O operations execute outside
+/// the Python GIL using native Tokio, ensuring true async behavior.
+///
+/// Windows distinguishes file and directory symlinks: a directory symlink
+/// is created when `target_is_directory` is set or the target (resolved
+/// relative to the link's directory) is an existing directory. Elsewhere
+/// the flag is ignored, as with `os.symlink`.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path that the symlink will point to
+/// * `dst` - Path to the symbolic link to create
+/// * `target_is_directory` - Create a directory symlink on Windows
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileExistsError` if `dst` already exists, `PyIOError` if the
+/// symlink cannot be created, or `PyValueError` if the path is invalid.
+#[pyfunction]
+#[pyo3(signature = (src, dst, target_is_directory = false))]
+fn symlink_async(
+    py: Python<'_>,
+    src: String,
+    dst: String,
+    target_is_directory: bool,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    let op = OpContext::new("symlink", [src.as_str(), dst.as_str()]);
+    let future = async move {
+        let describe = format!("{src} -> {dst}");
+
+        #[cfg(unix)]
+        {
+            let _ = target_is_directory;
+            tokio::fs::symlink(&src, &dst)
+                .await
+                .map_err(|e| map_io_error(e, &describe, "create symlink"))
+        }
+
+        #[cfg(windows)]
+        {
+            // A relative target is relative to the link, not the working directory
+            let link_dir = std::path::Path::new(&dst)
+                .parent()
+                .unwrap_or(std::path::Path::new(""));
+            let is_dir = target_is_directory
+                || tokio::fs::metadata(link_dir.join(&src))
+                    .await
+                    .is_ok_and(|metadata| metadata.is_dir());
+            let created = if is_dir {
+                tokio::fs::symlink_dir(&src, &dst).await
+            } else {
+                tokio::fs::symlink_file(&src, &dst).await
+            };
+            created.map_err(|e| map_io_error(e, &describe, "create symlink"))
+        }
+    };
+    run_op(py, op, future)
+}
+
+/// Canonicalize a path asynchronously.
+///
+/// Resolves all symbolic links and returns the absolute path. All I/O
+/// operations execute outside the Python GIL using native Tokio, ensuring
+/// true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to canonicalize
+///
+/// # Returns
+///
+/// A coroutine that yields the canonical path as a string.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the path does not exist,
+/// `PyIOError` if the path cannot be canonicalized, or `PyValueError` if the path is invalid.
+#[pyfunction]
+fn canonicalize_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("canonicalize", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let canonical = tokio::fs::canonicalize(&path)
+            .await
+            .map_err(|e| map_io_error(e, &path_clone, "canonicalize path"))?;
+
+        canonical
+            .to_str()
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyUnicodeDecodeError, _>(
+                    "Canonicalized path contains invalid UTF-8",
+                )
+            })
+            .map(|s| s.to_string())
+    };
+    run_op(py, op, future)
+}
+
+/// Component of a path being resolved by `real_path`.
+enum PathPart {
+    /// Root directory, with the drive or share prefix on Windows
+    Root(std::path::PathBuf),
+    Parent,
+    Name(std::ffi::OsString),
+}
+
+/// Split `path` into the parts `real_path` resolves, dropping `.`.
+fn path_parts(path: &std::path::Path) -> Vec<PathPart> {
+    use std::path::Component;
+
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => {
+                // A prefix and the root directory after it form one root
+                if let Some(PathPart::Root(root)) = parts.last_mut() {
+                    root.push(component);
+                } else {
+                    parts.push(PathPart::Root(component.as_os_str().into()));
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir => parts.push(PathPart::Parent),
+            Component::Normal(name) => parts.push(PathPart::Name(name.to_owned())),
+        }
+    }
+    parts
+}
+
+/// Symbolic links followed by `real_path` before it reports a loop (the
+/// usual `SYMLOOP_MAX`).
+const MAX_SYMLINKS: usize = 40;
+
+/// Resolve symbolic links, `.` and `..` in `path`, like `os.path.realpath`.
+///
+/// Components are resolved one at a time from the root, so `..` applies to
+/// the directory a symlink led to. Unless `strict`, a component that can't
+/// be examined (usually because it doesn't exist) is kept as it is and
+/// resolution continues with the next one, as is a symlink loop.
+fn real_path(path: &std::path::Path, strict: bool) -> std::io::Result<std::path::PathBuf> {
+    let start = match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => std::env::current_dir()?.join(path),
+    };
+    let mut pending = path_parts(&start);
+    pending.reverse();
+    let mut resolved = std::path::PathBuf::new();
+    let mut links = 0;
+    while let Some(part) = pending.pop() {
+        let name = match part {
+            PathPart::Root(root) => {
+                resolved = root;
+                continue;
+            }
+            PathPart::Parent => {
+                resolved.pop();
+                continue;
+            }
+            PathPart::Name(name) => name,
+        };
+        let candidate = resolved.join(name);
+        let target = match std::fs::symlink_metadata(&candidate) {
+            Ok(metadata) if metadata.file_type().is_symlink() && links < MAX_SYMLINKS => {
+                links += 1;
+                std::fs::read_link(&candidate)
+            }
+            Ok(metadata) if metadata.file_type().is_symlink() && strict => {
+                return Err(std::io::Error::other("too many levels of symbolic links"));
+            }
+            Ok(_) => {
+                resolved = candidate;
+                continue;
+            }
+            Err(e) => Err(e),
+        };
+        match target {
+            Ok(target) => pending.extend(path_parts(&target).into_iter().rev()),
+            Err(e) if strict => return Err(e),
+            Err(_) => resolved = candidate,
+        }
+    }
+    Ok(resolved)
+}
+
+/// Resolve a path like `os.path.realpath` asynchronously.
+///
+/// Makes the path absolute and resolves symbolic links, `.` and `..`. With
+/// `strict`, every component must exist; otherwise the path is resolved as
+/// far as possible and the rest is kept, so paths of files not created yet
+/// can be resolved too. Unlike `canonicalize_async`, the result has no
+/// `\\?\` prefix on Windows, and non-UTF-8 paths are decoded like
+/// `os.fsdecode()`. The whole resolution runs as one blocking job.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to resolve
+/// * `strict` - Raise if a component does not exist or a symlink loops
+///
+/// # Returns
+///
+/// A coroutine that yields the resolved absolute path.
+///
+/// # Errors
+///
+/// With `strict`, returns `PyFileNotFoundError` if a component does not
+/// exist or `PyIOError` for a symlink loop or another error examining the
+/// path. Returns `PyValueError` if the path is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, strict = false))]
+fn realpath_async(py: Python<'_>, path: String, strict: bool) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("realpath", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let resolved = run_blocking(move || real_path(std::path::Path::new(&path_clone), strict))
+            .await
+            .map_err(|e| map_io_error(e, &path, "resolve path"))?;
+        Ok(resolved.into_os_string())
+    };
+    run_op(py, op, future)
+}
+
+/// Read the target of a symbolic link asynchronously.
+///
+/// Returns the target exactly as stored in the link, without resolving it:
+/// a relative target stays relative to the link's directory, and the target
+/// need not exist. Targets that are not valid UTF-8 are decoded like
+/// `os.fsdecode()` (undecodable bytes become surrogate escapes), so they
+/// round-trip through `os.fsencode()`.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path of the symbolic link
+///
+/// # Returns
+///
+/// A coroutine that yields the link target as a string.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the path does not exist, `PyIOError` if
+/// it is not a symbolic link or cannot be read, or `PyValueError` if the
+/// path is invalid.
+#[pyfunction]
+fn readlink_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("readlink", [path.as_str()]);
+    let future = async move {
+        let target = tokio::fs::read_link(&path).await.map_err(|e| {
+            // EINVAL, which would otherwise become a ValueError
+            if e.kind() == std::io::ErrorKind::InvalidInput {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to read link {path}: not a symbolic link ({e})"
+                ))
+            } else {
+                map_io_error(e, &path, "read link")
+            }
+        })?;
+        Ok(target.into_os_string())
+    };
+    run_op(py, op, future)
+}
+
+/// Set the access and modification times of `path` to now.
+fn touch_times(path: &std::path::Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: the path is NUL-terminated; null times mean "now", which
+        // (unlike explicit times) only needs write access, not ownership
+        if unsafe { libc::utimensat(libc::AT_FDCWD, c_path.as_ptr(), std::ptr::null(), 0) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let now = SystemTime::now();
+        let times = std::fs::FileTimes::new()
+            .set_accessed(now)
+            .set_modified(now);
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)?
+            .set_times(times)
+    }
+}
+
+/// Create a file or update its timestamps asynchronously, like
+/// `pathlib.Path.touch()`.
+///
+/// An existing file (or directory) gets its access and modification times
+/// set to now; a missing file is created empty. Symbolic links are
+/// followed.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path of the file
+/// * `exist_ok` - If false, fail instead of touching an existing file
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileExistsError` if the file exists and `exist_ok` is false,
+/// `PyFileNotFoundError` if the parent directory does not exist,
+/// `PyPermissionError` if access is denied, or `PyValueError` if the path is
+/// invalid.
+#[pyfunction]
+#[pyo3(signature = (path, exist_ok = true))]
+fn touch_async(py: Python<'_>, path: String, exist_ok: bool) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("touch", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        run_blocking(move || {
+            let path = std::path::Path::new(&path_clone);
+            if exist_ok {
+                match touch_times(path) {
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    touched => return touched,
+                }
+            }
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create(exist_ok)
+                .create_new(!exist_ok)
+                .open(path)
+                .map(drop)
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path, "touch"))?;
+        Ok(())
+    };
+    run_unit_op(py, op, future)
+}
+
+/// Truncate or extend a file to `size` bytes asynchronously, like
+/// `os.truncate()`.
+///
+/// A shorter file is extended with zero bytes (sparsely, where the
+/// filesystem supports it). The file must exist; it is opened for writing
+/// and closed again in Rust, so Python never holds a handle.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path of the file
+/// * `size` - New size in bytes
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if size is negative or the path is invalid,
+/// `PyFileNotFoundError` if the file does not exist, `PyPermissionError` if
+/// access is denied, or `PyIOError` if the file cannot be resized.
+#[pyfunction]
+fn truncate_async(py: Python<'_>, path: String, size: i64) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let size = u64::try_from(size).map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Negative size value {size}"))
+    })?;
+    let op = OpContext::new("truncate", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        run_blocking(move || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(&path_clone)?
+                .set_len(size)
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path, "truncate"))?;
+        Ok(())
+    };
+    run_unit_op(py, op, future)
+}
+
+/// Change the permission bits of a file or directory asynchronously, like
+/// `os.chmod()`.
+///
+/// On Unix, `mode` replaces the permission bits (including setuid, setgid
+/// and sticky). Elsewhere only the owner write bit (`0o200`) is used: the
+/// read-only attribute is set when it is clear and removed when it is set.
+/// Symbolic links are followed.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path of the file or directory
+/// * `mode` - Permission bits, e.g. `0o644`
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if mode has bits above `0o7777` or the path is
+/// invalid, `PyFileNotFoundError` if the path does not exist,
+/// `PyPermissionError` if access is denied (e.g. the file belongs to another
+/// user), or `PyIOError` for other errors.
+#[pyfunction]
+fn chmod_async(py: Python<'_>, path: String, mode: u32) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    if mode > 0o7777 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid mode: {mode:#o}. Must be at most 0o7777"
+        )));
+    }
+    let op = OpContext::new("chmod", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        run_blocking(move || {
+            #[cfg(unix)]
+            let permissions = {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::Permissions::from_mode(mode)
+            };
+            #[cfg(not(unix))]
+            let permissions = {
+                let mut permissions = std::fs::metadata(&path_clone)?.permissions();
+                permissions.set_readonly(mode & 0o200 == 0);
+                permissions
+            };
+            std::fs::set_permissions(&path_clone, permissions)
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path, "change permissions of"))?;
+        Ok(())
+    };
+    run_unit_op(py, op, future)
+}
+
+/// Convert a `chown` user or group ID, where -1 leaves it unchanged.
+fn owner_id(id: i64, name: &str) -> PyResult<Option<u32>> {
+    match id {
+        -1 => Ok(None),
+        id => u32::try_from(id)
+            .ok()
+            .filter(|&id| id != u32::MAX)
+            .map(Some)
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid {name}: {id}. Must be -1 or between 0 and {}",
+                    u32::MAX - 1
+                ))
+            }),
+    }
+}
+
+/// Change the owning user and group of a file asynchronously, like
+/// `os.chown()`.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path of the file or directory
+/// * `uid` - New owning user ID, or -1 to leave it unchanged
+/// * `gid` - New owning group ID, or -1 to leave it unchanged
+/// * `follow_symlinks` - If false, change a symbolic link itself (`lchown`)
+///   instead of the file it points to
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if an ID or the path is invalid,
+/// `PyFileNotFoundError` if the path does not exist, `PyPermissionError` if
+/// the process may not give the file away, `PyIOError` for other errors, or
+/// `PyNotImplementedError` on platforms other than Unix.
+#[pyfunction]
+#[pyo3(signature = (path, uid, gid, follow_symlinks = true))]
+fn chown_async(
+    py: Python<'_>,
+    path: String,
+    uid: i64,
+    gid: i64,
+    follow_symlinks: bool,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let uid = owner_id(uid, "uid")?;
+    let gid = owner_id(gid, "gid")?;
+    if cfg!(not(unix)) {
+        return Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "changing ownership is only supported on Unix",
+        ));
+    }
+    let op = OpContext::new("chown", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        run_blocking(move || {
+            #[cfg(unix)]
+            {
+                if follow_symlinks {
+                    std::os::unix::fs::chown(&path_clone, uid, gid)
+                } else {
+                    std::os::unix::fs::lchown(&path_clone, uid, gid)
+                }
+            }
+            #[cfg(not(unix))]
+            {
+                let _ = (path_clone, uid, gid, follow_symlinks);
+                Err(std::io::ErrorKind::Unsupported.into())
+            }
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path, "change the owner of"))?;
+        Ok(())
+    };
+    run_unit_op(py, op, future)
+}
+
+// Deduplication
+
+/// How `dedupe_tree_async` replaces duplicate files.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DedupeMethod {
+    /// Copy-on-write clone sharing the original's data blocks
+    Reflink,
+    /// Hard link to the original
+    Hardlink,
+}
+
+impl DedupeMethod {
+    /// Parse the `method` argument of `dedupe_tree_async`.
+    fn parse(method: &str) -> PyResult<Self> {
+        match method {
+            "reflink" => Ok(DedupeMethod::Reflink),
+            "hardlink" => Ok(DedupeMethod::Hardlink),
+            other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid dedupe method: {other}. Must be 'reflink' or 'hardlink'"
+            ))),
+        }
+    }
+}
+
+/// File found by `dedupe_tree_async`, with every path it has in the tree.
+struct DedupeFile {
+    paths: Vec<std::path::PathBuf>,
+    size: u64,
+}
+
+/// Result of `dedupe_tree_async`.
+///
+/// # Properties
+///
+/// * `groups` - Sets of identical files, sorted by path. Each set starts
+///   with the path that is kept; the others are (or, in a dry run, would be)
+///   replaced. Hard links of one file are listed together.
+/// * `duplicates` - Number of replaced paths
+/// * `reclaimed` - Bytes freed by replacing them
+/// * `dry_run` - Whether the files were left unchanged
+#[pyclass]
+struct DedupeReport {
+    groups: Vec<Vec<String>>,
+    duplicates: usize,
+    reclaimed: u64,
+    dry_run: bool,
+}
+
+#[pymethods]
+impl DedupeReport {
+    #[getter]
+    fn groups(&self) -> Vec<Vec<String>> {
+        self.groups.clone()
+    }
+
+    #[getter]
+    fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+
+    #[getter]
+    fn reclaimed(&self) -> u64 {
+        self.reclaimed
+    }
+
+    #[getter]
+    fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DedupeReport(groups={}, duplicates={}, reclaimed={}, dry_run={})",
+            self.groups.len(),
+            self.duplicates,
+            self.reclaimed,
+            if self.dry_run { "True" } else { "False" }
+        )
+    }
+}
+
+/// Walk the tree below `root` without following symbolic links and group
+/// its non-empty regular files by device and size, sorted by path.
+///
+/// Hard links of one file become a single `DedupeFile`; groups of one file
+/// are dropped.
+fn collect_dedupe_candidates(root: &std::path::Path) -> std::io::Result<Vec<Vec<DedupeFile>>> {
+    let mut groups: std::collections::HashMap<(u64, u64), Vec<DedupeFile>> =
+        std::collections::HashMap::new();
+    let mut linked: std::collections::HashMap<FileId, (u64, u64, usize)> =
+        std::collections::HashMap::new();
+
+    let mut stack = vec![root.to_path_buf()];
+    let mut root_entries = Some(std::fs::read_dir(root)?);
+    while let Some(dir) = stack.pop() {
+        let entries = match root_entries.take() {
+            Some(entries) => entries,
+            None => match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue, // Skip directories we can't read
+            },
+        };
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            if !metadata.is_file() || metadata.len() == 0 {
+                continue;
+            }
+            let id = file_id(&metadata, true);
+            let key = (id.map_or(0, |id| id.dev), metadata.len());
+            if let Some(&(dev, size, index)) = id.as_ref().and_then(|id| linked.get(id)) {
+                groups.get_mut(&(dev, size)).expect("group of linked file")[index]
+                    .paths
+                    .push(path);
+                continue;
+            }
+            let group = groups.entry(key).or_default();
+            if let Some(id) = id {
+                linked.insert(id, (key.0, key.1, group.len()));
+            }
+            group.push(DedupeFile {
+                paths: vec![path],
+                size: metadata.len(),
+            });
+        }
+    }
+
+    let mut groups: Vec<Vec<DedupeFile>> = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            for file in &mut group {
+                file.paths.sort();
+            }
+            group.sort_by(|a, b| a.paths[0].cmp(&b.paths[0]));
+            group
+        })
+        .collect();
+    groups.sort_by(|a, b| a[0].paths[0].cmp(&b[0].paths[0]));
+    Ok(groups)
+}
+
+/// Hash the contents of a file, to find candidates for a byte comparison.
+fn hash_file_contents(path: &std::path::Path) -> std::io::Result<u64> {
+    use std::hash::Hasher;
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let mut chunk = vec![0u8; DIRCMP_CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut chunk)?;
+        if n == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.write(&chunk[..n]);
+    }
+}
+
+/// Create `dst` as a copy-on-write clone of `src`.
+#[cfg(target_os = "linux")]
+fn reflink_file(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let source = std::fs::File::open(src)?;
+    let target = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dst)?;
+    // SAFETY: both descriptors stay open for the duration of the call
+    if unsafe { libc::ioctl(target.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) } == -1 {
+        let error = std::io::Error::last_os_error();
+        drop(target);
+        let _ = std::fs::remove_file(dst);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Create `dst` as a copy-on-write clone of `src`.
+#[cfg(target_os = "macos")]
+fn reflink_file(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let to_c = |path: &std::path::Path| {
+        std::ffi::CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+    };
+    let (src, dst) = (to_c(src)?, to_c(dst)?);
+    // SAFETY: both paths are NUL-terminated strings that outlive the call
+    if unsafe { libc::clonefile(src.as_ptr(), dst.as_ptr(), 0) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Create `dst` as a copy-on-write clone of `src`.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink_file(_src: &std::path::Path, _dst: &std::path::Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "reflinks are not supported on this platform",
+    ))
+}
+
+/// Replace `duplicate` with a clone of or link to `original`.
+///
+/// The clone or link is created next to `duplicate` and renamed over it, so
+/// `duplicate` is never missing. Clones keep the permissions of the file
+/// they replace.
+fn replace_duplicate(
+    original: &std::path::Path,
+    duplicate: &std::path::Path,
+    method: DedupeMethod,
+) -> std::io::Result<()> {
+    let temp = transaction_sibling(duplicate, "dedupe");
+    let result = match method {
+        DedupeMethod::Hardlink => std::fs::hard_link(original, &temp),
+        DedupeMethod::Reflink => reflink_file(original, &temp).and_then(|()| {
+            std::fs::set_permissions(&temp, std::fs::metadata(duplicate)?.permissions())
+        }),
+    }
+    .and_then(|()| std::fs::rename(&temp, duplicate));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Find identical files in a tree and replace duplicates with clones or
+/// hard links asynchronously.
+///
+/// Non-empty regular files of equal size on the same device are hashed, and
+/// files with equal hashes are compared byte by byte before they are
+/// grouped. In every group the first path (in sorted order) is kept and
+/// the others are replaced by a reflink (a copy-on-write clone, on file
+/// systems such as Btrfs, XFS and APFS) or a hard link to it. Symbolic
+/// links are not followed. Run it on trees nothing is writing to: a file
+/// changed after it was compared loses the change.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `root` - Root of the tree to deduplicate
+/// * `method` - `"reflink"` (default) or `"hardlink"`. Hard linked files
+///   share their metadata, and writing to one in place changes all of them.
+/// * `dry_run` - If true (default), only report what would be replaced
+///
+/// # Returns
+///
+/// A coroutine that yields a `DedupeReport`.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the root does not exist, `PyValueError`
+/// if the path or method is invalid, or `PyIOError` if a file cannot be
+/// read or replaced, including when the file system does not support
+/// reflinks. Files replaced before an error stay replaced.
+#[pyfunction]
+#[pyo3(signature = (root, *, method = "reflink", dry_run = true))]
+fn dedupe_tree_async<'py>(
+    py: Python<'py>,
+    root: String,
+    method: &str,
+    dry_run: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&root)?;
+    let method = DedupeMethod::parse(method)?;
+    // A dry run only reads, so it is allowed in read-only mode
+    let name = if dry_run {
+        "dedupe_tree_dry_run"
+    } else {
+        "dedupe_tree"
+    };
+    let op = OpContext::new(name, [root.as_str()]);
+    let future = async move {
+        let scan_root = std::path::PathBuf::from(&root);
+        let candidates = run_blocking(move || collect_dedupe_candidates(&scan_root))
+            .await
+            .map_err(|e| map_io_error(e, &root, "deduplicate"))?;
+
+        let mut groups: Vec<Vec<DedupeFile>> = Vec::new();
+        for files in candidates {
+            let hashes = join_limited(
+                files
+                    .iter()
+                    .map(|file| {
+                        let path = file.paths[0].clone();
+                        run_blocking(move || hash_file_contents(&path))
+                    })
+                    .collect(),
+            )
+            .await;
+            let mut by_hash: Vec<(u64, Vec<DedupeFile>)> = Vec::new();
+            for (file, hash) in files.into_iter().zip(hashes) {
+                let hash = hash
+                    .map_err(|e| map_io_error(e, &file.paths[0].to_string_lossy(), "read file"))?;
+                match by_hash.iter_mut().find(|(other, _)| *other == hash) {
+                    Some((_, same)) => same.push(file),
+                    None => by_hash.push((hash, vec![file])),
+                }
+            }
+            for (_, mut same) in by_hash {
+                if same.len() < 2 {
+                    continue;
+                }
+                // Files whose hash collides with the first file's without
+                // matching it are left alone
+                let mut group = vec![same.remove(0)];
+                for file in same {
+                    let (a, b) = (&group[0].paths[0], &file.paths[0]);
+                    if same_contents(a, b)
+                        .await
+                        .map_err(|e| map_io_error(e, &b.to_string_lossy(), "read file"))?
+                    {
+                        group.push(file);
+                    }
+                }
+                if group.len() > 1 {
+                    groups.push(group);
+                }
+            }
+        }
+        groups.sort_by(|a, b| a[0].paths[0].cmp(&b[0].paths[0]));
+
+        let mut duplicates = 0;
+        let mut reclaimed = 0;
+        for group in &groups {
+            let original = &group[0].paths[0];
+            for file in group.iter().skip(1) {
+                if !dry_run {
+                    for path in &file.paths {
+                        let (source, target) = (original.clone(), path.clone());
+                        run_blocking(move || replace_duplicate(&source, &target, method))
+                            .await
+                            .map_err(|e| map_io_error(e, &path.to_string_lossy(), "deduplicate"))?;
+                    }
+                }
+                duplicates += file.paths.len();
+                reclaimed += file.size;
+            }
+        }
+
+        Ok(DedupeReport {
+            groups: groups
+                .iter()
+                .map(|group| {
+                    group
+                        .iter()
+                        .flat_map(|file| &file.paths)
+                        .map(|path| path.to_string_lossy().to_string())
+                        .collect()
+                })
+                .collect(),
+            duplicates,
+            reclaimed,
+            dry_run,
+        })
+    };
+    run_op(py, op, future)
+}
+
+// Atomic file operations
+
+/// Write a file atomically using a temporary file.
+///
+/// Writes content to a temporary file first, then atomically replaces
+/// the target file by renaming. This ensures the target file is never
+/// in a partially-written state. All I/O operations execute outside
+/// the Python GIL using native Tokio, ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to write
+/// * `contents` - Content to write to the file (UTF-8 string)
+/// * `finalize_read_only` - If true, drop all write permission bits before the
+///   file is moved into place, so it never appears writable at `path`
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` (the temporary
+///   file is synced before it is renamed, so `path` never holds a file whose
+///   data was lost) or `"fsync+dir"` (the rename is synced too)
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
+/// if write permission is denied, or `PyValueError` if the path or
+/// durability is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, contents, finalize_read_only = false, durability = "flush"))]
+fn atomic_write_file_async<'py>(
+    py: Python<'py>,
+    path: String,
+    contents: String,
+    finalize_read_only: bool,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&path)?;
+    let durability = Durability::parse(durability)?;
+    let op = OpContext::new("atomic_write_file", [path.as_str()]);
+    let future = async move {
+        use std::path::Path;
+        let path_clone = path.clone();
+
+        let file_path = Path::new(&path);
+        let dir = file_path.parent().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("Path has no parent directory")
+        })?;
+
+        // Create temporary file in same directory
+        let file_name = file_path.file_name().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("Path has no file name")
+        })?;
+
+        let temp_path = dir.join(format!(".{}.tmp", file_name.to_string_lossy()));
+        let temp_path_str = temp_path.to_string_lossy().to_string();
+
+        // Write to temporary file
+        record_transfer(contents.len());
+        durability
+            .write_file(&temp_path, contents.into_bytes(), tokio::fs::write)
+            .await
+            .map_err(|e| map_io_error(e, &temp_path_str, "write temporary file"))?;
+
+        if finalize_read_only {
+            if let Err(e) = make_read_only(&temp_path_str).await {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(map_io_error(e, &temp_path_str, "make read-only"));
+            }
+        }
+
+        // Atomically replace target file
+        tokio::fs::rename(&temp_path, &path).await.map_err(|e| {
+            // Clean up temp file on error (spawn cleanup task)
+            let temp_cleanup = temp_path.clone();
+            tokio::spawn(async move {
+                let _ = tokio::fs::remove_file(&temp_cleanup).await;
+            });
+            map_io_error(e, &path_clone, "atomically write file")
+        })?;
+        durability
+            .sync_parent(file_path)
+            .await
+            .map_err(|e| map_io_error(e, &path_clone, "sync directory of"))
+    };
+    run_op(py, op, future)
+}
+
+/// Drop all write permission bits from a file.
+///
+/// Clears the user, group and other write bits on Unix and sets the
+/// read-only attribute elsewhere.
+async fn make_read_only(path: &str) -> std::io::Result<()> {
+    let mut permissions = tokio::fs::metadata(path).await?.permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() & !0o222);
+    }
+    #[cfg(not(unix))]
+    permissions.set_readonly(true);
+    tokio::fs::set_permissions(path, permissions).await
+}
+
+/// Write bytes to a file atomically using a temporary file.
+///
+/// Writes bytes to a temporary file first, then atomically replaces
+/// the target file by renaming. This ensures the target file is never
+/// in a partially-written state. All I/O operations execute outside
+/// the Python GIL using native Tokio, ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to write
+/// * `contents` - Bytes to write to the file
+/// * `finalize_read_only` - If true, drop all write permission bits before the
+///   file is moved into place, so it never appears writable at `path`
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` (the temporary
+///   file is synced before it is renamed, so `path` never holds a file whose
+///   data was lost) or `"fsync+dir"` (the rename is synced too)
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyIOError` if the file cannot be written, `PyPermissionError`
+/// if write permission is denied, or `PyValueError` if the path or
+/// durability is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, contents, finalize_read_only = false, durability = "flush"))]
+fn atomic_write_file_bytes_async<'a>(
+    py: Python<'a>,
+    path: String,
+    contents: &'a Bound<'a, PyBytes>,
+    finalize_read_only: bool,
+    durability: &str,
+) -> PyResult<Bound<'a, PyAny>> {
+    validate_path(&path)?;
+    let durability = Durability::parse(durability)?;
+    let bytes = contents.as_bytes().to_vec();
+    let op = OpContext::new("atomic_write_file_bytes", [path.as_str()]);
+    let future = async move {
+        use std::path::Path;
+        let path_clone = path.clone();
+
+        let file_path = Path::new(&path);
+        let dir = file_path.parent().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("Path has no parent directory")
+        })?;
+
+        // Create temporary file in same directory
+        let file_name = file_path.file_name().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("Path has no file name")
+        })?;
+
+        let temp_path = dir.join(format!(".{}.tmp", file_name.to_string_lossy()));
+        let temp_path_str = temp_path.to_string_lossy().to_string();
+
+        // Write to temporary file
+        record_transfer(bytes.len());
+        durability
+            .write_file(&temp_path, bytes, tokio::fs::write)
+            .await
+            .map_err(|e| map_io_error(e, &temp_path_str, "write temporary file"))?;
+
+        if finalize_read_only {
+            if let Err(e) = make_read_only(&temp_path_str).await {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(map_io_error(e, &temp_path_str, "make read-only"));
+            }
+        }
+
+        // Atomically replace target file
+        tokio::fs::rename(&temp_path, &path).await.map_err(|e| {
+            // Clean up temp file on error (spawn cleanup task)
+            let temp_cleanup = temp_path.clone();
+            tokio::spawn(async move {
+                let _ = tokio::fs::remove_file(&temp_cleanup).await;
+            });
+            map_io_error(e, &path_clone, "atomically write file")
+        })?;
+        durability
+            .sync_parent(file_path)
+            .await
+            .map_err(|e| map_io_error(e, &path_clone, "sync directory of"))
+    };
+    run_op(py, op, future)
+}
+
+pyo3::create_exception!(
+    _rapfiles,
+    FileChangedError,
+    pyo3::exceptions::PyException,
+    "Raised when write_if_unchanged() finds that the file changed since it was read."
+);
+
+/// Counter making the temporary files of concurrent writers unique.
+static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(1);
+
+/// State a file must still be in for `write_if_unchanged_async` to replace it.
+enum ExpectedState {
+    /// The file must not exist
+    Missing,
+    /// Modification time, as reported by `stat().modified`
+    Modified(f64),
+    /// Lowercase hex SHA-256 digest of the contents
+    Sha256(String),
+}
+
+impl ExpectedState {
+    /// Parse the `expected` argument of `write_if_unchanged_async`.
+    fn extract(expected: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if expected.is_none() {
+            return Ok(ExpectedState::Missing);
+        }
+        if let Ok(digest) = expected.cast::<PyString>() {
+            let digest = digest.to_cow()?.to_ascii_lowercase();
+            if digest.len() != 64 || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid expected digest: {digest:?}. Must be a hex SHA-256 digest"
+                )));
+            }
+            return Ok(ExpectedState::Sha256(digest));
+        }
+        expected
+            .extract::<f64>()
+            .map(ExpectedState::Modified)
+            .map_err(|_| {
+                PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                    "expected must be None, a modification time or a SHA-256 hex digest",
+                )
+            })
+    }
+
+    /// Describe how the file at `path` no longer matches, or `None` if it does.
+    async fn mismatch(&self, path: &str) -> PyResult<Option<String>> {
+        let metadata = match tokio::fs::metadata(path).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(match self {
+                    ExpectedState::Missing => None,
+                    _ => Some("it was removed".to_string()),
+                });
+            }
+            Err(e) => return Err(map_io_error(e, path, "check file")),
+        };
+        match self {
+            ExpectedState::Missing => Ok(Some("it was created".to_string())),
+            ExpectedState::Modified(expected) => {
+                let modified = metadata
+                    .modified()
+                    .map(system_time_to_timestamp)
+                    .map_err(|e| map_io_error(e, path, "check file"))?;
+                Ok((modified != *expected)
+                    .then(|| format!("its modification time is {modified}, expected {expected}")))
+            }
+            ExpectedState::Sha256(expected) => {
+                let contents = tokio::fs::read(path)
+                    .await
+                    .map_err(|e| map_io_error(e, path, "check file"))?;
+                let digest = Python::attach(|py| {
+                    py.import("hashlib")?
+                        .call_method1("sha256", (PyBytes::new(py, &contents),))?
+                        .call_method0("hexdigest")?
+                        .extract::<String>()
+                })?;
+                Ok((digest != *expected).then(|| "its contents changed".to_string()))
+            }
+        }
+    }
+}
+
+/// Replace a file only if it has not changed since the caller read it.
+///
+/// Compare-and-swap for files: `data` is written to a temporary file next
+/// to `path`, the file at `path` is checked against `expected`, and only
+/// if it still matches is the temporary file renamed over it. When
+/// `expected` is `None` the file must not exist, and the new file is
+/// linked into place so a file created concurrently is never replaced; on
+/// filesystems without hard links this fails instead. Otherwise the check and the rename are separate steps; writers that must
+/// never race should also hold `lock_file`.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to replace
+/// * `data` - New contents (`str` is encoded as UTF-8)
+/// * `expected` - `None` (the file must not exist), the modification time
+///   the caller saw (`stat().modified`), or the hex SHA-256 digest of the
+///   contents the caller read
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` or
+///   `"fsync+dir"`, as for `atomic_write_file_async`
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `FileChangedError` if the file no longer matches `expected`,
+/// `PyIOError` if the file cannot be written (or, with `expected=None`,
+/// linked into place), `PyTypeError` if `data` or
+/// `expected` has an unsupported type, or `PyValueError` if the path, the
+/// digest or the durability is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, data, expected, durability = "flush"))]
+fn write_if_unchanged_async<'py>(
+    py: Python<'py>,
+    path: String,
+    data: &Bound<'py, PyAny>,
+    expected: &Bound<'py, PyAny>,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&path)?;
+    let durability = Durability::parse(durability)?;
+    let mut bytes = Vec::new();
+    if !extend_with_data(&mut bytes, data)? {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "write_if_unchanged() data must be str or bytes",
+        ));
+    }
+    let expected = ExpectedState::extract(expected)?;
+    let op = OpContext::new("write_if_unchanged", [path.as_str()]);
+    let future = async move {
+        let file_path = std::path::Path::new(&path);
+        let (Some(dir), Some(file_name)) = (file_path.parent(), file_path.file_name()) else {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Path has no parent directory or file name",
+            ));
+        };
+        let temp_path = dir.join(format!(
+            ".{}.{}.{}.tmp",
+            file_name.to_string_lossy(),
+            std::process::id(),
+            NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path_str = temp_path.to_string_lossy().to_string();
+
+        record_transfer(bytes.len());
+        durability
+            .write_file(&temp_path, bytes, tokio::fs::write)
+            .await
+            .map_err(|e| map_io_error(e, &temp_path_str, "write temporary file"))?;
+
+        let replaced = async {
+            if let Some(reason) = expected.mismatch(&path).await? {
+                return Err(FileChangedError::new_err(format!(
+                    "Refusing to overwrite {path}: {reason} since it was read"
+                )));
+            }
+            if let ExpectedState::Missing = expected {
+                // Fails instead of replacing a file created since the check
+                // (a rename would), so there is no fallback where linking fails
+                return match tokio::fs::hard_link(&temp_path, &path).await {
+                    Ok(()) => Ok(()),
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                        Err(FileChangedError::new_err(format!(
+                            "Refusing to overwrite {path}: it was created since it was read"
+                        )))
+                    }
+                    Err(e) => Err(map_io_error(e, &path, "atomically write file")),
+                };
+            }
+            tokio::fs::rename(&temp_path, &path)
+                .await
+                .map_err(|e| map_io_error(e, &path, "atomically write file"))
+        }
+        .await;
+        // Gone already if it was renamed into place
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        replaced?;
+        durability
+            .sync_parent(file_path)
+            .await
+            .map_err(|e| map_io_error(e, &path, "sync directory of"))
+    };
+    run_op(py, op, future)
+}
+
+/// Move a file atomically.
+///
+/// Moves a file from source to destination atomically. For cross-device
+/// moves, it will copy atomically and then remove the source. All I/O
+/// operations execute outside the Python GIL using native Tokio, ensuring
+/// true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `src` - Path to the source file
+/// * `dst` - Path to the destination file
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the source file does not exist,
+/// `PyIOError` if the file cannot be moved, or `PyValueError` if the path is invalid.
+#[pyfunction]
+fn atomic_move_file_async(py: Python<'_>, src: String, dst: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&src)?;
+    validate_path(&dst)?;
+    let op = OpContext::new("atomic_move_file", [src.as_str(), dst.as_str()]);
+    let future = async move {
+        let src_clone = src.clone();
+        let dst_clone = dst.clone();
+
+        // Try rename first (atomic on same filesystem)
+        match tokio::fs::rename(&src, &dst).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                // Cross-device move: copy atomically then remove
+                use std::path::Path;
+                let dst_path = Path::new(&dst);
+                let dir = dst_path.parent().ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                        "Destination path has no parent directory",
+                    )
+                })?;
+
+                let file_name = dst_path.file_name().ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                        "Destination path has no file name",
+                    )
+                })?;
+
+                let temp_path = dir.join(format!(".{}.tmp", file_name.to_string_lossy()));
+
+                // Copy to temp file
+                tokio::fs::copy(&src, &temp_path).await.map_err(|e| {
+                    map_io_error(e, &format!("{src_clone} -> {dst_clone}"), "copy file")
+                })?;
+
+                // Atomically replace destination
+                tokio::fs::rename(&temp_path, &dst).await.map_err(|e| {
+                    // Clean up temp file on error (spawn cleanup task)
+                    let temp_cleanup = temp_path.clone();
+                    tokio::spawn(async move {
+                        let _ = tokio::fs::remove_file(&temp_cleanup).await;
+                    });
+                    map_io_error(
+                        e,
+                        &format!("{src_clone} -> {dst_clone}"),
+                        "atomically move file",
+                    )
+                })?;
+
+                // Remove source file (best effort - move already succeeded)
+                if let Err(e) = tokio::fs::remove_file(&src).await {
+                    // Log warning but don't fail - the move was successful
+                    // The source file removal failure is logged but doesn't affect the operation
+                    eprintln!("Warning: Failed to remove source file after atomic move {src_clone} -> {dst_clone}: {e}");
+                }
+                Ok(())
+            }
+            Err(e) => Err(map_io_error(
+                e,
+                &format!("{src_clone} -> {dst_clone}"),
+                "atomically move file",
+            )),
+        }
+    };
+    run_op(py, op, future)
+}
+
+// Config file parsing
+
+/// Read and parse a config file off the GIL.
+///
+/// The file is read and parsed by `parse` on the blocking pool; only the
+/// conversion of the parsed document to Python objects needs the GIL.
+///
+/// # Errors
+///
+/// Returns `PyIOError` if the file cannot be read, or `PyValueError` if it
+/// is not valid UTF-8 or `parse` rejects it.
+fn read_config<'py, T, P>(
+    py: Python<'py>,
+    path: String,
+    op_name: &'static str,
+    format: &'static str,
+    parse: P,
+) -> PyResult<Bound<'py, PyAny>>
+where
+    T: for<'a> IntoPyObject<'a> + Send + 'static,
+    P: FnOnce(&str) -> Result<T, String> + Copy + Send + 'static,
+{
+    validate_path(&path)?;
+    let op = OpContext::new(op_name, [path.as_str()]);
+    run_retryable_op(py, op, move || {
+        let path = path.clone();
+        async move {
+            let path_clone = path.clone();
+            let (parsed, len) = run_blocking(move || {
+                let bytes = std::fs::read(&path)?;
+                let parsed = std::str::from_utf8(&bytes)
+                    .map_err(|e| e.to_string())
+                    .and_then(parse);
+                Ok((parsed, bytes.len()))
+            })
+            .await
+            .map_err(|e| map_io_error(e, &path_clone, "read file"))?;
+            record_transfer(len);
+            parsed.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Failed to parse {format} file {path_clone}: {e}"
+                ))
+            })
+        }
+    })
+}
+
+/// A parsed TOML document, converted to a `dict` when returned to Python.
+struct TomlDocument(toml::Table);
+
+impl<'py> IntoPyObject<'py> for TomlDocument {
+    type Target = pyo3::types::PyDict;
+    type Output = Bound<'py, pyo3::types::PyDict>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+        toml_table_to_py(py, &self.0)
+    }
+}
+
+fn toml_table_to_py<'py>(
+    py: Python<'py>,
+    table: &toml::Table,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let dict = pyo3::types::PyDict::new(py);
+    for (key, value) in table {
+        dict.set_item(key, toml_value_to_py(py, value)?)?;
+    }
+    Ok(dict)
+}
+
+/// Convert a TOML value to the Python object `tomllib` would return.
+fn toml_value_to_py<'py>(py: Python<'py>, value: &toml::Value) -> PyResult<Bound<'py, PyAny>> {
+    use pyo3::types::PyList;
+    Ok(match value {
+        toml::Value::String(s) => s.into_pyobject(py)?.into_any(),
+        toml::Value::Integer(i) => i.into_pyobject(py)?.into_any(),
+        toml::Value::Float(f) => f.into_pyobject(py)?.into_any(),
+        toml::Value::Boolean(b) => b.into_pyobject(py)?.to_owned().into_any(),
+        toml::Value::Datetime(datetime) => toml_datetime_to_py(py, datetime)?,
+        toml::Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(toml_value_to_py(py, item)?)?;
+            }
+            list.into_any()
+        }
+        toml::Value::Table(table) => toml_table_to_py(py, table)?.into_any(),
+    })
+}
+
+/// Convert a TOML date and/or time to `datetime`, `date` or `time`.
+///
+/// Offset date-times get a fixed-offset `tzinfo` (`timezone.utc` for `Z`);
+/// local date-times are naive, like `tomllib`.
+fn toml_datetime_to_py<'py>(
+    py: Python<'py>,
+    datetime: &toml::value::Datetime,
+) -> PyResult<Bound<'py, PyAny>> {
+    use pyo3::types::{PyDate, PyDateTime, PyDelta, PyTime, PyTzInfo};
+    let tzinfo = match datetime.offset {
+        None => None,
+        Some(toml::value::Offset::Z) => Some(PyTzInfo::utc(py)?.to_owned()),
+        Some(toml::value::Offset::Custom { minutes }) => {
+            let delta = PyDelta::new(py, 0, i32::from(minutes) * 60, 0, true)?;
+            Some(PyTzInfo::fixed_offset(py, delta)?)
+        }
+    };
+    Ok(match (datetime.date, datetime.time) {
+        (Some(date), Some(time)) => PyDateTime::new(
+            py,
+            i32::from(date.year),
+            date.month,
+            date.day,
+            time.hour,
+            time.minute,
+            time.second,
+            time.nanosecond / 1000,
+            tzinfo.as_ref(),
+        )?
+        .into_any(),
+        (Some(date), None) => {
+            PyDate::new(py, i32::from(date.year), date.month, date.day)?.into_any()
+        }
+        (None, Some(time)) => PyTime::new(
+            py,
+            time.hour,
+            time.minute,
+            time.second,
+            time.nanosecond / 1000,
+            None,
+        )?
+        .into_any(),
+        (None, None) => py.None().into_bound(py),
+    })
+}
+
+/// Async TOML file read and parse (GIL-independent).
+///
+/// Reads and parses the file on a worker thread, so large config files do
+/// not stall the event loop the way `tomllib.loads` does.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the TOML file
+///
+/// # Returns
+///
+/// A coroutine that yields the document as a `dict`, with the same value
+/// types as `tomllib` (including `datetime`, `date` and `time` values).
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the file does not exist, `PyIOError` if
+/// it cannot be read, or `PyValueError` if the path is invalid or the file
+/// is not valid TOML.
+#[pyfunction]
+fn read_toml_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    read_config(py, path, "read_toml", "TOML", |text| {
+        toml::from_str::<toml::Table>(text)
+            .map(TomlDocument)
+            .map_err(|e| e.to_string().trim_end().to_string())
+    })
+}
+
+/// Parse TOML text into a `dict`, with the same value types as `tomllib.loads`.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if the text is not valid TOML.
+#[pyfunction]
+fn toml_loads(text: &str) -> PyResult<TomlDocument> {
+    toml::from_str::<toml::Table>(text)
+        .map(TomlDocument)
+        .map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid TOML: {}",
+                e.to_string().trim_end()
+            ))
+        })
+}
+
+/// Serialize a `dict` to TOML text, like `tomli_w.dumps`.
+///
+/// Keys must be strings; values may be `str`, `int`, `float`, `bool`,
+/// `datetime`, `date`, `time`, lists, tuples and dicts.
+///
+/// # Errors
+///
+/// Returns `PyTypeError` if `data` is not a dict or holds a value TOML has
+/// no type for (e.g. `None`), `PyOverflowError` if an integer doesn't fit in
+/// 64 bits, or `PyValueError` if a date is out of range or the document
+/// can't be written.
+#[pyfunction]
+fn toml_dumps(data: &Bound<'_, PyAny>) -> PyResult<String> {
+    if !data.is_instance_of::<pyo3::types::PyDict>() {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "A TOML document must be a dict, not {}",
+            data.get_type().name()?
+        )));
+    }
+    let toml::Value::Table(table) = py_to_toml_value(data)? else {
+        unreachable!("dicts convert to tables")
+    };
+    toml::to_string(&table)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid TOML: {e}")))
+}
+
+/// Convert a Python object to the TOML value `tomli_w` would write.
+fn py_to_toml_value(value: &Bound<'_, PyAny>) -> PyResult<toml::Value> {
+    use pyo3::types::{
+        PyBool, PyDate, PyDateAccess, PyDateTime, PyDict, PyFloat, PyInt, PyList, PyTime,
+        PyTimeAccess, PyTuple,
+    };
+
+    let not_serializable = || {
+        Ok::<_, PyErr>(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "Object of type {} is not TOML serializable",
+            value.get_type().name()?
+        )))
+    };
+    if let Ok(b) = value.cast::<PyBool>() {
+        return Ok(b.is_true().into());
+    }
+    if value.is_instance_of::<PyInt>() {
+        return value.extract::<i64>().map(toml::Value::from).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyOverflowError, _>(format!(
+                "Integer {value} is out of range for TOML"
+            ))
+        });
+    }
+    if let Ok(f) = value.cast::<PyFloat>() {
+        return Ok(f.value().into());
+    }
+    if let Ok(s) = value.cast::<PyString>() {
+        return Ok(s.to_cow()?.into_owned().into());
+    }
+    if let Ok(dict) = value.cast::<PyDict>() {
+        let mut table = toml::Table::new();
+        for (key, item) in dict.iter() {
+            let Ok(key) = key.cast::<PyString>() else {
+                return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                    "TOML keys must be str, not {}",
+                    key.get_type().name()?
+                )));
+            };
+            table.insert(key.to_cow()?.into_owned(), py_to_toml_value(&item)?);
+        }
+        return Ok(toml::Value::Table(table));
+    }
+    if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        return value
+            .try_iter()?
+            .map(|item| py_to_toml_value(&item?))
+            .collect::<PyResult<Vec<_>>>()
+            .map(toml::Value::Array);
+    }
+
+    let date = |date: &dyn PyDateAccess| {
+        u16::try_from(date.get_year())
+            .ok()
+            .filter(|year| *year <= 9999)
+            .map(|year| toml::value::Date {
+                year,
+                month: date.get_month(),
+                day: date.get_day(),
+            })
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Date {value} is out of range for TOML"
+                ))
+            })
+    };
+    let time = |time: &dyn PyTimeAccess| toml::value::Time {
+        hour: time.get_hour(),
+        minute: time.get_minute(),
+        second: time.get_second(),
+        nanosecond: time.get_microsecond() * 1000,
+    };
+    // Checked before `date`, which `datetime` subclasses
+    if let Ok(datetime) = value.cast::<PyDateTime>() {
+        let offset = datetime.call_method0("utcoffset")?;
+        let offset = if offset.is_none() {
+            None
+        } else {
+            let seconds: f64 = offset.call_method0("total_seconds")?.extract()?;
+            // TOML offsets are whole minutes
+            let minutes = (seconds / 60.0).round() as i16;
+            Some(toml::value::Offset::Custom { minutes })
+        };
+        return Ok(toml::Value::Datetime(toml::value::Datetime {
+            date: Some(date(datetime)?),
+            time: Some(time(datetime)),
+            offset,
+        }));
+    }
+    if let Ok(d) = value.cast::<PyDate>() {
+        return Ok(toml::Value::Datetime(toml::value::Datetime {
+            date: Some(date(d)?),
+            time: None,
+            offset: None,
+        }));
+    }
+    if let Ok(t) = value.cast::<PyTime>() {
+        if !t.call_method0("utcoffset")?.is_none() {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "TOML local times can't have a timezone",
+            ));
+        }
+        return Ok(toml::Value::Datetime(toml::value::Datetime {
+            date: None,
+            time: Some(time(t)),
+            offset: None,
+        }));
+    }
+    Err(not_serializable()?)
+}
+
+/// A parsed YAML document, converted to Python objects when returned.
+#[cfg(feature = "yaml")]
+struct YamlDocument(serde_yaml_ng::Value);
+
+#[cfg(feature = "yaml")]
+impl<'py> IntoPyObject<'py> for YamlDocument {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+        yaml_value_to_py(py, &self.0)
+    }
+}
+
+/// Convert a YAML value to the Python object `yaml.safe_load` would return.
+///
+/// Custom tags are dropped and their value is converted as if untagged.
+#[cfg(feature = "yaml")]
+fn yaml_value_to_py<'py>(
+    py: Python<'py>,
+    value: &serde_yaml_ng::Value,
+) -> PyResult<Bound<'py, PyAny>> {
+    use pyo3::types::{PyDict, PyList};
+    use serde_yaml_ng::Value;
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => b.into_pyobject(py)?.to_owned().into_any(),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.into_pyobject(py)?.into_any()
+            } else if let Some(u) = n.as_u64() {
+                u.into_pyobject(py)?.into_any()
+            } else {
+                n.as_f64().unwrap_or(f64::NAN).into_pyobject(py)?.into_any()
+            }
+        }
+        Value::String(s) => s.into_pyobject(py)?.into_any(),
+        Value::Sequence(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(yaml_value_to_py(py, item)?)?;
+            }
+            list.into_any()
+        }
+        Value::Mapping(mapping) => {
+            let dict = PyDict::new(py);
+            for (key, value) in mapping {
+                dict.set_item(yaml_value_to_py(py, key)?, yaml_value_to_py(py, value)?)?;
+            }
+            dict.into_any()
+        }
+        Value::Tagged(tagged) => yaml_value_to_py(py, &tagged.value)?,
+    })
+}
+
+/// Async YAML file read and parse (GIL-independent).
+///
+/// Reads and parses the file on a worker thread. Only the first document of
+/// a multi-document stream is accepted; merge keys (`<<`) are applied.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the YAML file
+///
+/// # Returns
+///
+/// A coroutine that yields the document as plain Python objects (`dict`,
+/// `list`, `str`, `int`, `float`, `bool` or `None`).
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the file does not exist, `PyIOError` if
+/// it cannot be read, `PyValueError` if the path is invalid or the file is
+/// not valid YAML, or `PyNotImplementedError` if rapfiles was built without
+/// the `yaml` feature.
+#[pyfunction]
+fn read_yaml_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    #[cfg(feature = "yaml")]
+    {
+        read_config(py, path, "read_yaml", "YAML", |text| {
+            let mut value: serde_yaml_ng::Value =
+                serde_yaml_ng::from_str(text).map_err(|e| e.to_string())?;
+            value.apply_merge().map_err(|e| e.to_string())?;
+            Ok(YamlDocument(value))
+        })
+    }
+    #[cfg(not(feature = "yaml"))]
+    {
+        let _ = (py, path);
+        Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "rapfiles was built without YAML support (the 'yaml' feature)",
+        ))
+    }
+}
+
+/// Replace a config file, keeping the previous version as `backup`.
+///
+/// The previous file is hard linked to the backup name (copied if hard
+/// links are not supported), as replacing `path` leaves its data intact.
+/// The new contents go to a temporary file created with `mode`, which is
+/// renamed over `path`. With the default `fsync+dir` durability the file is
+/// synced before the rename and the directory last, persisting both renames.
+fn save_config_file(
+    path: &std::path::Path,
+    contents: &[u8],
+    backup: Option<&std::path::Path>,
+    mode: Option<u32>,
+    durability: Durability,
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "path has no parent directory or file name",
+        ));
+    };
+    let temp_path = || {
+        dir.join(format!(
+            ".{}.{}.{}.tmp",
+            file_name.to_string_lossy(),
+            std::process::id(),
+            NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+        ))
+    };
+
+    if let Some(backup) = backup {
+        let temp = temp_path();
+        let kept = match std::fs::hard_link(path, &temp) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                std::fs::copy(path, &temp).map(drop)
+            }
+            linked => linked,
+        };
+        match kept.and_then(|()| std::fs::rename(&temp, backup)) {
+            Ok(()) => {}
+            // Nothing to back up yet
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                let _ = std::fs::remove_file(&temp);
+                return Err(e);
+            }
+        }
+    }
+
+    let temp = temp_path();
+    let saved = (|| {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(mode);
+        }
+        let mut file = options.open(&temp)?;
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            // The umask may have cleared bits of `mode`
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        file.write_all(contents)?;
+        if durability >= Durability::Fsync {
+            file.sync_all()?;
+        }
+        std::fs::rename(&temp, path)?;
+        if durability >= Durability::FsyncDir {
+            sync_parent_dir(path)?;
+        }
+        Ok(())
+    })();
+    if saved.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    saved
+}
+
+/// Save a config file with a backup, permissions and a durable atomic replace.
+///
+/// The previous version is kept as `<path>.bak`, the new contents are
+/// written to a temporary file created with `mode` (so they are never
+/// readable with looser permissions), synced to disk and renamed over
+/// `path`, and the directory is synced (less with a lower `durability`).
+/// Serialization happens in Python.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path of the config file
+/// * `contents` - Serialized contents
+/// * `backup` - Keep the previous version as `<path>.bak`
+/// * `mode` - Permission bits of the new file (Unix only), or `None` for
+///   the default permissions
+/// * `durability` - `"none"`, `"flush"`, `"fsync"` or `"fsync+dir"`
+///   (default); see `Durability`
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyPermissionError` if the directory is not writable,
+/// `PyIOError` if the file cannot be written, or `PyValueError` if the path
+/// or durability is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, contents, backup = true, mode = Some(0o600), durability = "fsync+dir"))]
+fn save_config_async<'py>(
+    py: Python<'py>,
+    path: String,
+    contents: &Bound<'py, PyBytes>,
+    backup: bool,
+    mode: Option<u32>,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&path)?;
+    let durability = Durability::parse(durability)?;
+    let contents = contents.as_bytes().to_vec();
+    let backup_path = format!("{path}.bak");
+    let op = OpContext::new("save_config", [path.as_str(), backup_path.as_str()]);
+    let future = async move {
+        let len = contents.len();
+        let path_clone = path.clone();
+        run_blocking(move || {
+            let backup = backup.then(|| std::path::Path::new(&backup_path));
+            save_config_file(
+                std::path::Path::new(&path_clone),
+                &contents,
+                backup,
+                mode,
+                durability,
+            )
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path, "save config"))?;
+        record_transfer(len);
+        Ok(())
+    };
+    run_op(py, op, future)
+}
+
+// File locking operations
+
+use std::fs::File as StdFile;
+
+/// File lock for advisory file locking.
+///
+/// Provides advisory file locks for coordinating access to files across
+/// processes. Supports both shared (read) and exclusive (write) locks.
+/// The lock is automatically released when the object is dropped or when
+/// `release()` is called.
+///
+/// # Example
+///
+/// ```python
+/// async with rapfiles.lock_file("file.txt", exclusive=True) as lock:
+///     # File is locked here
+///     await rapfiles.write_file("file.txt", "content")
 /// # Lock is automatically released
 /// ```
 #[pyclass]
-struct FileLock {
-    file: Arc<StdFile>,
+struct FileLock {
+    file: Arc<StdFile>,
+    path: String,
+    #[allow(dead_code)]
+    exclusive: bool,
+}
+
+#[pymethods]
+impl FileLock {
+    /// Default constructor - use lock_file() instead.
+    #[new]
+    fn new() -> PyResult<Self> {
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "FileLock cannot be instantiated directly. Use rapfiles.lock_file() instead.",
+        ))
+    }
+
+    /// Release the file lock.
+    ///
+    /// Releases the advisory file lock. The lock is also automatically
+    /// released when the object is dropped.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the lock cannot be released.
+    fn release<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let file = Arc::clone(&self.file);
+        let path = self.path.clone();
+
+        let op = OpContext::new("release_lock", [self.path.as_str()]);
+        let future = async move {
+            // Unlock the file (blocking operation)
+            tokio::task::spawn_blocking(move || {
+                use fs2::FileExt;
+                match FileExt::unlock(&*file) {
+                    Ok(()) => Ok(()),
+                    Err(e) => {
+                        // On Windows, error code 158 (ERROR_NOT_LOCKED) means already unlocked
+                        // Make release() idempotent by ignoring this error
+                        #[cfg(windows)]
+                        if e.raw_os_error() == Some(158) {
+                            return Ok(());
+                        }
+                        Err(PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                            "Failed to release lock on {path}: {e}"
+                        )))
+                    }
+                }
+            })
+            .await
+            .map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to release lock: {e}"))
+            })?
+        };
+        run_op(py, op, future)
+    }
+
+    /// Async context manager entry.
+    fn __aenter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        // Return self directly - Python's async context manager will handle it
+        slf
+    }
+
+    /// Async context manager exit.
+    fn __aexit__(
+        &self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_val: Option<&Bound<'_, PyAny>>,
+        _exc_tb: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        // Release lock on exit
+        Python::attach(|py| {
+            let release_future = self.release(py)?;
+            // Release is already a coroutine, return it wrapped
+            Ok(release_future.unbind())
+        })
+    }
+}
+
+/// Lock a file asynchronously.
+///
+/// Acquires an advisory file lock on the specified file. The lock can be
+/// shared (read) or exclusive (write). The file is created if it doesn't
+/// exist. All I/O operations execute outside the Python GIL using native
+/// Tokio, ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to lock
+/// * `exclusive` - If true, acquire exclusive (write) lock; if false, acquire shared (read) lock
+///
+/// # Returns
+///
+/// A coroutine that yields a `FileLock` object that can be used as an async context manager.
+///
+/// # Errors
+///
+/// Returns `PyIOError` if the file cannot be locked, or `PyValueError` if the path is invalid.
+#[pyfunction]
+fn lock_file_async(py: Python<'_>, path: String, exclusive: bool) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("lock_file", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+
+        // Open or create the file
+        let file = tokio::task::spawn_blocking({
+            let path = path_clone.clone();
+            let path_clone_for_error = path_clone.clone();
+            move || {
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .truncate(false)
+                    .read(true)
+                    .write(true)
+                    .open(&path)
+                    .map_err(|e| map_io_error(e, &path_clone_for_error, "open file for locking"))
+            }
+        })
+        .await
+        .map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to open file: {e}"))
+        })??;
+
+        // Acquire the lock (blocking operation)
+        {
+            let file_clone = file.try_clone().map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to clone file handle: {e}"
+                ))
+            })?;
+            tokio::task::spawn_blocking({
+                let path_clone2 = path_clone.clone();
+                move || {
+                    if exclusive {
+                        fs2::FileExt::lock_exclusive(&file_clone)
+                    } else {
+                        fs2::FileExt::lock_shared(&file_clone)
+                    }
+                    .map_err(|e| {
+                        PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                            "Failed to acquire lock on {path_clone2}: {e}"
+                        ))
+                    })
+                }
+            })
+            .await
+            .map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to acquire lock: {e}"))
+            })??;
+        }
+
+        Ok(FileLock {
+            file: Arc::new(file),
+            path: path_clone,
+            exclusive,
+        })
+    };
+    run_op(py, op, future)
+}
+
+// Leases
+//
+// A lease is an exclusive lock that expires unless its holder keeps renewing
+// it, so a crashed process cannot hold it forever. It lives in a sidecar file
+// `<path>.lease` (TOML with the owner, a unique token and the expiry as Unix
+// time) rather than in an OS lock, which shared storage often does not
+// honour. The sidecar is created exclusively, so one contender wins; an
+// expired sidecar is renamed away before it is replaced, so only one
+// contender can break it. While the lease is held a background task moves
+// the expiry forward every third of the TTL.
+
+/// Contents of a lease sidecar file.
+struct LeaseRecord {
+    owner: String,
+    token: String,
+    /// Unix time after which the lease may be taken over
+    expires: f64,
+}
+
+impl LeaseRecord {
+    fn to_toml(&self) -> String {
+        let mut table = toml::Table::new();
+        table.insert("owner".into(), self.owner.clone().into());
+        table.insert("token".into(), self.token.clone().into());
+        table.insert("pid".into(), i64::from(std::process::id()).into());
+        table.insert("expires".into(), self.expires.into());
+        table.to_string()
+    }
+
+    /// Read the sidecar at `path`, or `None` if there is none.
+    ///
+    /// A sidecar that cannot be parsed (e.g. one still being created)
+    /// expires `ttl` seconds after it was last modified.
+    fn read(path: &std::path::Path, ttl: f64) -> std::io::Result<Option<Self>> {
+        let not_found = |e: std::io::Error| match e.kind() {
+            std::io::ErrorKind::NotFound => Ok(None),
+            _ => Err(e),
+        };
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => return not_found(e),
+        };
+        let table = text.parse::<toml::Table>().ok();
+        let field = |key: &str| table.as_ref().and_then(|table| table.get(key));
+        let expires = match field("expires").and_then(toml::Value::as_float) {
+            Some(expires) => expires,
+            None => match std::fs::metadata(path).and_then(|metadata| metadata.modified()) {
+                Ok(modified) => system_time_to_timestamp(modified) + ttl,
+                Err(e) => return not_found(e),
+            },
+        };
+        let text_field = |key: &str| field(key).and_then(toml::Value::as_str).unwrap_or("");
+        Ok(Some(LeaseRecord {
+            owner: text_field("owner").to_string(),
+            token: text_field("token").to_string(),
+            expires,
+        }))
+    }
+}
+
+/// Name of this host, for default lease owners.
+fn host_name() -> String {
+    #[cfg(unix)]
+    {
+        let mut buffer = [0u8; 256];
+        // SAFETY: the buffer is valid for its whole length
+        if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } == 0 {
+            let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+            return String::from_utf8_lossy(&buffer[..end]).into_owned();
+        }
+    }
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "localhost".to_string())
+}
+
+/// Current time as Unix time.
+fn unix_now() -> f64 {
+    system_time_to_timestamp(SystemTime::now())
+}
+
+/// Create the sidecar for `record` unless a live lease holds it.
+///
+/// Returns the holder if the lease is held by someone else.
+fn try_acquire_lease(
+    sidecar: &std::path::Path,
+    record: &LeaseRecord,
+    ttl: f64,
+) -> std::io::Result<Option<LeaseRecord>> {
+    use std::io::Write;
+    loop {
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(sidecar)
+        {
+            Ok(mut file) => {
+                file.write_all(record.to_toml().as_bytes())?;
+                file.sync_all()?;
+                return Ok(None);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+        let Some(holder) = LeaseRecord::read(sidecar, ttl)? else {
+            continue; // Released in the meantime
+        };
+        if holder.expires > unix_now() {
+            return Ok(Some(holder));
+        }
+        // Move the expired sidecar aside first: of several contenders that
+        // saw it expire, only one can rename it
+        let stale = sidecar.with_file_name(format!(
+            ".{}.{}.stale",
+            sidecar.file_name().unwrap_or_default().to_string_lossy(),
+            record.token
+        ));
+        match std::fs::rename(sidecar, &stale) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+        if let Some(broken) = LeaseRecord::read(&stale, ttl)? {
+            if broken.token != holder.token {
+                // Renewed or taken over since it was read: put it back
+                let _ = std::fs::hard_link(&stale, sidecar);
+            }
+        }
+        let _ = std::fs::remove_file(&stale);
+    }
+}
+
+/// Move the expiry of the lease with `record.token` forward.
+///
+/// Returns the new expiry, or `None` if the lease is no longer ours.
+fn renew_lease(
+    sidecar: &std::path::Path,
+    record: &LeaseRecord,
+    ttl: f64,
+) -> std::io::Result<Option<f64>> {
+    match LeaseRecord::read(sidecar, ttl)? {
+        Some(current) if current.token == record.token => {}
+        _ => return Ok(None),
+    }
+    let renewed = LeaseRecord {
+        owner: record.owner.clone(),
+        token: record.token.clone(),
+        expires: unix_now() + ttl,
+    };
+    let temp = sidecar.with_file_name(format!(
+        ".{}.{}.tmp",
+        sidecar.file_name().unwrap_or_default().to_string_lossy(),
+        record.token
+    ));
+    std::fs::write(&temp, renewed.to_toml())?;
+    if let Err(e) = std::fs::rename(&temp, sidecar) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e);
+    }
+    Ok(Some(renewed.expires))
+}
+
+/// Renewal state shared between a `FileLease` and its background task.
+struct LeaseState {
+    expires: f64,
+    /// Cleared on release, or when the lease was lost or could not be
+    /// renewed before it expired
+    held: bool,
+}
+
+/// Renew the lease every third of its TTL until it is released or lost.
+async fn keep_lease(
+    sidecar: std::path::PathBuf,
+    record: LeaseRecord,
+    ttl: f64,
+    state: Arc<std::sync::Mutex<LeaseState>>,
+) {
+    let record = Arc::new(record);
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs_f64(ttl / 3.0)).await;
+        let (sidecar, record) = (sidecar.clone(), Arc::clone(&record));
+        let renewed = run_blocking(move || renew_lease(&sidecar, &record, ttl)).await;
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        match renewed {
+            Ok(Some(expires)) => state.expires = expires,
+            // Retry failed renewals until the lease runs out
+            Err(_) if unix_now() < state.expires => {}
+            _ => {
+                state.held = false;
+                return;
+            }
+        }
+    }
+}
+
+/// Advisory lease on a file, renewed in the background while held.
+///
+/// Created with `rapfiles.lease_file()`. Unlike a `FileLock`, a lease
+/// expires `ttl` seconds after its last renewal, so the lease of a crashed
+/// process can be taken over once it runs out.
+///
+/// # Example
+///
+/// ```python
+/// async with rapfiles.lease_file("/shared/jobs/nightly", ttl=60) as lease:
+///     await do_work()
+///     if not lease.held:
+///         raise RuntimeError("lease lost")
+/// ```
+#[pyclass]
+struct FileLease {
+    path: String,
+    sidecar: std::path::PathBuf,
+    owner: String,
+    token: String,
+    ttl: f64,
+    state: Arc<std::sync::Mutex<LeaseState>>,
+    renewal: tokio::task::AbortHandle,
+}
+
+impl Drop for FileLease {
+    fn drop(&mut self) {
+        // An abandoned lease stops being renewed and expires
+        self.renewal.abort();
+    }
+}
+
+#[pymethods]
+impl FileLease {
+    /// Default constructor - use lease_file() instead.
+    #[new]
+    fn new() -> PyResult<Self> {
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "FileLease cannot be instantiated directly. Use rapfiles.lease_file() instead.",
+        ))
+    }
+
+    /// Path the lease protects.
+    #[getter]
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Owner recorded in the sidecar file.
+    #[getter]
+    fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Seconds the lease lasts after each renewal.
+    #[getter]
+    fn ttl(&self) -> f64 {
+        self.ttl
+    }
+
+    /// Unix time at which the lease expires unless it is renewed.
+    #[getter]
+    fn expires(&self) -> f64 {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).expires
+    }
+
+    /// Whether the lease is still held: not released, not taken over and
+    /// not expired.
+    #[getter]
+    fn held(&self) -> bool {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.held && unix_now() < state.expires
+    }
+
+    /// Stop renewing the lease and remove its sidecar file.
+    ///
+    /// The sidecar is left alone if another process has taken the lease
+    /// over. Releasing twice is harmless.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the sidecar file cannot be removed.
+    fn release<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.renewal.abort();
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).held = false;
+        let (sidecar, token, ttl) = (self.sidecar.clone(), self.token.clone(), self.ttl);
+
+        let op = OpContext::new("release_lease", [self.path.as_str()]);
+        let future = async move {
+            let sidecar_str = sidecar.to_string_lossy().to_string();
+            run_blocking(move || match LeaseRecord::read(&sidecar, ttl)? {
+                Some(current) if current.token == token => match std::fs::remove_file(&sidecar) {
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                    other => other,
+                },
+                _ => Ok(()),
+            })
+            .await
+            .map_err(|e| map_io_error(e, &sidecar_str, "release lease"))?;
+            Ok(())
+        };
+        run_unit_op(py, op, future)
+    }
+
+    /// Async context manager entry.
+    fn __aenter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Async context manager exit; releases the lease.
+    fn __aexit__<'a>(
+        &self,
+        py: Python<'a>,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_val: Option<&Bound<'_, PyAny>>,
+        _exc_tb: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        self.release(py)
+    }
+}
+
+/// Acquire a lease on a file asynchronously.
+///
+/// Waits until no live lease is recorded in the sidecar file
+/// `path + ".lease"`, records a new one and starts renewing it in the
+/// background every third of `ttl`.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path the lease protects; the file itself is not touched
+/// * `ttl` - Seconds the lease lasts after each renewal
+/// * `owner` - Owner recorded in the sidecar; defaults to `<host>:<pid>`
+/// * `timeout` - Seconds to wait at most; `None` waits indefinitely
+/// * `poll_interval` - Seconds between attempts while the lease is held
+///
+/// # Returns
+///
+/// A coroutine that yields a `FileLease` usable as an async context manager.
+///
+/// # Errors
+///
+/// Returns `PyTimeoutError` if the lease is still held when the timeout
+/// expires, `PyIOError` if the sidecar cannot be written, or `PyValueError`
+/// if the path or a duration is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, ttl = 30.0, owner = None, timeout = None, poll_interval = 1.0))]
+fn lease_file_async(
+    py: Python<'_>,
+    path: String,
+    ttl: f64,
+    owner: Option<String>,
+    timeout: Option<f64>,
+    poll_interval: f64,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let positive = |name: &str, seconds: f64| {
+        std::time::Duration::try_from_secs_f64(seconds)
+            .ok()
+            .filter(|duration| !duration.is_zero())
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid {name}: {seconds}. Must be a positive number of seconds"
+                ))
+            })
+    };
+    positive("ttl", ttl)?;
+    let poll_interval = positive("poll_interval", poll_interval)?;
+    let deadline = timeout
+        .map(|timeout| {
+            std::time::Duration::try_from_secs_f64(timeout.max(0.0)).map_err(|_| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid timeout: {timeout}"
+                ))
+            })
+        })
+        .transpose()?
+        .map(|timeout| tokio::time::Instant::now() + timeout);
+    let owner = owner.unwrap_or_else(|| format!("{}:{}", host_name(), std::process::id()));
+
+    let op = OpContext::new("lease_file", [path.as_str()]);
+    let future = async move {
+        let sidecar = std::path::PathBuf::from(format!("{path}.lease"));
+        let sidecar_str = sidecar.to_string_lossy().to_string();
+        let token = format!(
+            "{}-{}-{}",
+            std::process::id(),
+            NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        );
+        let expires = loop {
+            let (sidecar, owner, token) = (sidecar.clone(), owner.clone(), token.clone());
+            let attempt = run_blocking(move || {
+                let record = LeaseRecord {
+                    owner,
+                    token,
+                    expires: unix_now() + ttl,
+                };
+                Ok(try_acquire_lease(&sidecar, &record, ttl)?.ok_or(record.expires))
+            })
+            .await
+            .map_err(|e| map_io_error(e, &sidecar_str, "acquire lease"))?;
+            let holder = match attempt {
+                Err(expires) => break expires,
+                Ok(holder) => holder,
+            };
+            let now = tokio::time::Instant::now();
+            match deadline {
+                Some(deadline) if deadline <= now => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyTimeoutError, _>(format!(
+                        "Timed out waiting for lease on {path} held by {}",
+                        holder.owner
+                    )));
+                }
+                Some(deadline) => tokio::time::sleep_until(deadline.min(now + poll_interval)).await,
+                None => tokio::time::sleep(poll_interval).await,
+            }
+        };
+
+        let state = Arc::new(std::sync::Mutex::new(LeaseState {
+            expires,
+            held: true,
+        }));
+        let renewal = tokio::spawn(keep_lease(
+            sidecar.clone(),
+            LeaseRecord {
+                owner: owner.clone(),
+                token: token.clone(),
+                expires,
+            },
+            ttl,
+            Arc::clone(&state),
+        ))
+        .abort_handle();
+        Ok(FileLease {
+            path,
+            sidecar,
+            owner,
+            token,
+            ttl,
+            state,
+            renewal,
+        })
+    };
+    run_op(py, op, future)
+}
+
+// Log reading
+//
+// A `LogReader` follows a file that another process keeps appending to. It
+// remembers how far it has read and reads the rest with positional reads, so
+// a concurrent writer never sees a moved cursor. The path is checked on every
+// read: a file truncated in place is read again from the start, and after a
+// rotation (a new file at the path) the old file is drained before the
+// reader switches to the new one.
+
+/// Progress of a `LogReader`.
+#[derive(Default)]
+struct LogState {
+    /// File being followed and its identity; `None` while the path is missing
+    file: Option<(Arc<std::fs::File>, Option<FileId>)>,
+    offset: u64,
+    /// Bytes of an incomplete last line, kept until its newline arrives
+    partial: Vec<u8>,
+    /// Complete lines read ahead by `__anext__`
+    lines: std::collections::VecDeque<Vec<u8>>,
+    closed: bool,
+}
+
+impl LogState {
+    /// Open the file at `path` if it exists, starting at its end with `from_end`.
+    fn open(&mut self, path: &str, from_end: bool) -> std::io::Result<bool> {
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        let metadata = file.metadata()?;
+        self.offset = if from_end { metadata.len() } else { 0 };
+        self.file = Some((Arc::new(file), file_id(&metadata, true)));
+        Ok(true)
+    }
+
+    /// Read every byte appended since the last call, following truncation
+    /// and rotation of the file.
+    fn read_appended(&mut self, path: &str) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        if self.file.is_none() && !self.open(path, false)? {
+            return Ok(data);
+        }
+        loop {
+            let (file, id) = self.file.clone().expect("log file is open");
+            if file.metadata()?.len() < self.offset {
+                // Truncated in place: the partial line is gone with the old contents
+                self.offset = 0;
+                self.partial.clear();
+            }
+            let mut chunk = vec![0u8; 64 * 1024];
+            loop {
+                let n = read_full_at(&file, &mut chunk, self.offset)?;
+                data.extend_from_slice(&chunk[..n]);
+                self.offset += n as u64;
+                if n < chunk.len() {
+                    break; // EOF
+                }
+            }
+            let rotated = match std::fs::metadata(path) {
+                Ok(current) => id.is_some() && !is_same_file(id, &current),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+                Err(e) => return Err(e),
+            };
+            if !rotated || !self.open(path, false)? {
+                return Ok(data);
+            }
+        }
+    }
+
+    /// Whether a read would return new data (or a rotated file).
+    fn has_new_data(&self, path: &str) -> std::io::Result<bool> {
+        if !self.lines.is_empty() {
+            return Ok(true);
+        }
+        let current = match std::fs::metadata(path) {
+            Ok(current) => current,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        Ok(match &self.file {
+            None => true,
+            Some((file, id)) => {
+                file.metadata()?.len() != self.offset
+                    || (id.is_some() && !is_same_file(*id, &current))
+            }
+        })
+    }
+
+    /// Split `data`, preceded by the pending partial line, into complete
+    /// lines; the rest becomes the new partial line.
+    fn complete_lines(&mut self, data: Vec<u8>) -> Vec<Vec<u8>> {
+        let mut data = if self.partial.is_empty() {
+            data
+        } else {
+            let mut joined = std::mem::take(&mut self.partial);
+            joined.extend_from_slice(&data);
+            joined
+        };
+        let Some(end) = data.iter().rposition(|&b| b == b'\n') else {
+            self.partial = data;
+            return Vec::new();
+        };
+        self.partial = data.split_off(end + 1);
+        data.split_inclusive(|&b| b == b'\n')
+            .map(<[u8]>::to_vec)
+            .collect()
+    }
+}
+
+/// Reader following a log file while other processes append to it.
+///
+/// Created with `rapfiles.open_log()`. Reads never move the writer's file
+/// position, survive truncation and rotation of the file, and keep an
+/// incomplete last line until the writer finishes it.
+///
+/// # Example
+///
+/// ```python
+/// reader = await rapfiles.open_log("app.log", from_end=True)
+/// async for line in reader:
+///     handle(line)
+/// ```
+#[pyclass]
+struct LogReader {
+    path: String,
+    state: Arc<std::sync::Mutex<LogState>>,
+    poll_interval: std::time::Duration,
+}
+
+impl LogReader {
+    fn check_open(&self) -> PyResult<()> {
+        if self.state.lock().unwrap_or_else(|e| e.into_inner()).closed {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "I/O operation on closed log reader for {}",
+                self.path
+            )));
+        }
+        Ok(())
+    }
+
+    /// Run `f` on the reader state in a blocking job as operation `name`.
+    fn state_op<'a, T, F>(
+        &self,
+        py: Python<'a>,
+        name: &'static str,
+        f: F,
+    ) -> PyResult<Bound<'a, PyAny>>
+    where
+        F: FnOnce(&mut LogState, &str) -> std::io::Result<T> + Send + 'static,
+        T: for<'py> IntoPyObject<'py> + Send + 'static,
+    {
+        self.check_open()?;
+        let state = Arc::clone(&self.state);
+        let path = self.path.clone();
+        let op = OpContext::new(name, [self.path.as_str()]);
+        let future = async move {
+            let path_clone = path.clone();
+            run_blocking(move || {
+                let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                f(&mut state, &path_clone)
+            })
+            .await
+            .map_err(|e| map_io_error(e, &path, "read log"))
+        };
+        run_op(py, op, future)
+    }
+}
+
+#[pymethods]
+impl LogReader {
+    /// Default constructor - use open_log() instead.
+    #[new]
+    fn new() -> PyResult<Self> {
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "LogReader cannot be instantiated directly. Use rapfiles.open_log() instead.",
+        ))
+    }
+
+    /// Path of the followed file.
+    #[getter]
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Offset in the current file up to which data has been read.
+    #[getter]
+    fn offset(&self) -> u64 {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).offset
+    }
+
+    /// Read the bytes appended since the last read.
+    ///
+    /// A partial line held back by `read_lines()` is returned first.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the new bytes (empty if there are none).
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be read.
+    fn read_new<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.state_op(py, "read_log", |state, path| {
+            let data = state.read_appended(path)?;
+            let mut buffer: Vec<u8> = state.lines.drain(..).flatten().collect();
+            buffer.append(&mut state.partial);
+            buffer.extend_from_slice(&data);
+            record_transfer(data.len());
+            Ok(buffer)
+        })
+    }
+
+    /// Read the complete lines appended since the last read.
+    ///
+    /// An incomplete last line is held back until the writer finishes it.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields a list of lines as bytes, each ending with a
+    /// newline.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be read.
+    fn read_lines<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        self.state_op(py, "read_log", |state, path| {
+            let data = state.read_appended(path)?;
+            record_transfer(data.len());
+            let mut lines: Vec<Vec<u8>> = state.lines.drain(..).collect();
+            lines.extend(state.complete_lines(data));
+            Ok(lines)
+        })
+    }
+
+    /// Wait until new data has been appended (or the file was replaced).
+    ///
+    /// The file is polled every `poll_interval` seconds given to `open_log()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - Seconds to wait at most; `None` waits indefinitely
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `True` once data is available, or `False`
+    /// if the timeout expired first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be inspected.
+    #[pyo3(signature = (timeout = None))]
+    fn wait<'a>(&self, py: Python<'a>, timeout: Option<f64>) -> PyResult<Bound<'a, PyAny>> {
+        self.check_open()?;
+        let deadline = timeout
+            .map(|timeout| {
+                std::time::Duration::try_from_secs_f64(timeout.max(0.0)).map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid timeout: {timeout}"
+                    ))
+                })
+            })
+            .transpose()?
+            .map(|timeout| tokio::time::Instant::now() + timeout);
+        let state = Arc::clone(&self.state);
+        let path = self.path.clone();
+        let poll_interval = self.poll_interval;
+
+        let op = OpContext::new("wait_log", [self.path.as_str()]);
+        let future = async move {
+            loop {
+                let (state, path_clone) = (Arc::clone(&state), path.clone());
+                let ready = run_blocking(move || {
+                    let state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    state.has_new_data(&path_clone)
+                })
+                .await
+                .map_err(|e| map_io_error(e, &path, "read log"))?;
+                if ready {
+                    return Ok(true);
+                }
+                let next_poll = tokio::time::Instant::now() + poll_interval;
+                match deadline {
+                    Some(deadline) if deadline <= next_poll => {
+                        tokio::time::sleep_until(deadline).await;
+                        return Ok(false);
+                    }
+                    _ => tokio::time::sleep_until(next_poll).await,
+                }
+            }
+        };
+        run_op(py, op, future)
+    }
+
+    /// Stop following the file; `async for` loops over the reader end.
+    fn close(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.closed = true;
+        state.file = None;
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Wait for and return the next complete line, for `async for`.
+    ///
+    /// The iteration follows the file indefinitely, polling every
+    /// `poll_interval` seconds, until `close()` is called.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the next line as bytes, or raises
+    /// `StopAsyncIteration` once the reader is closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the file cannot be read.
+    fn __anext__<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let state = Arc::clone(&self.state);
+        let path = self.path.clone();
+        let poll_interval = self.poll_interval;
+
+        let op = OpContext::new("read_log", [self.path.as_str()]);
+        let future = async move {
+            loop {
+                let (state, path_clone) = (Arc::clone(&state), path.clone());
+                let line = run_blocking(move || {
+                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    if state.closed {
+                        return Ok(None);
+                    }
+                    if state.lines.is_empty() {
+                        let data = state.read_appended(&path_clone)?;
+                        record_transfer(data.len());
+                        let lines = state.complete_lines(data);
+                        state.lines.extend(lines);
+                    }
+                    Ok(Some(state.lines.pop_front()))
+                })
+                .await
+                .map_err(|e| map_io_error(e, &path, "read log"))?;
+                match line {
+                    None => {
+                        return Err(PyErr::new::<pyo3::exceptions::PyStopAsyncIteration, _>(()))
+                    }
+                    Some(Some(line)) => return Ok(line),
+                    Some(None) => tokio::time::sleep(poll_interval).await,
+                }
+            }
+        };
+        run_op(py, op, future)
+    }
+}
+
+/// Start following a log file that other processes append to.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path of the log file; it may not exist yet
+/// * `from_end` - If true, skip the data already in the file
+/// * `poll_interval` - Seconds between checks for new data while waiting
+///
+/// # Returns
+///
+/// A coroutine that yields a `LogReader`.
+///
+/// # Errors
+///
+/// Returns `PyIOError` if the file exists but cannot be opened, or
+/// `PyValueError` if the path or the poll interval is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, from_end = false, poll_interval = 0.1))]
+fn open_log_async(
+    py: Python<'_>,
+    path: String,
+    from_end: bool,
+    poll_interval: f64,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let poll_interval = std::time::Duration::try_from_secs_f64(poll_interval)
+        .ok()
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid poll_interval: {poll_interval}. Must be a positive number of seconds"
+            ))
+        })?;
+    let op = OpContext::new("open_log", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let state = run_blocking(move || {
+            let mut state = LogState::default();
+            state.open(&path_clone, from_end)?;
+            Ok(state)
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path, "open log"))?;
+        Ok(LogReader {
+            path,
+            state: Arc::new(std::sync::Mutex::new(state)),
+            poll_interval,
+        })
+    };
+    run_op(py, op, future)
+}
+
+// Directory watching
+//
+// A `DirWatcher` polls a directory tree: each scan records the type, size
+// and modification time of every entry and is compared with the previous
+// scan. Polling needs no platform support and sees changes made over
+// network filesystems too, at the cost of missing changes that keep both
+// the size and the modification time. With a journal, events are written to
+// disk before they are returned, so a watcher started again after a restart
+// can replay them and report what changed while it was down.
+
+/// Change to an entry of a watched directory.
+///
+/// Yielded by the `DirWatcher` returned from `watch_async`.
+///
+/// # Properties
+///
+/// * `kind` - "created", "modified" or "deleted"
+/// * `path` - Path of the entry (the watched path joined with its location)
+/// * `is_dir` - Whether the entry is (or was) a directory
+/// * `existing` - True for the "created" events synthesized for entries that
+///   existed when watching started
+/// * `token` - Position of the event in the watcher's journal, to pass as
+///   `resume_token` once the event is processed (None without a journal)
+#[pyclass]
+#[derive(Clone)]
+struct WatchEvent {
+    kind: &'static str,
+    path: String,
+    is_dir: bool,
+    existing: bool,
+    token: Option<String>,
+}
+
+#[pymethods]
+impl WatchEvent {
+    #[getter]
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    #[getter]
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    #[getter]
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    #[getter]
+    fn existing(&self) -> bool {
+        self.existing
+    }
+
+    #[getter]
+    fn token(&self) -> Option<String> {
+        self.token.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "WatchEvent(kind={:?}, path={:?}, is_dir={})",
+            self.kind,
+            self.path,
+            if self.is_dir { "True" } else { "False" }
+        )
+    }
+}
+
+/// Entries of a watched tree by path relative to its root, ordered so that
+/// directories come before their contents.
+type WatchSnapshot = std::collections::BTreeMap<std::path::PathBuf, (bool, FileStamp)>;
+
+/// Record every entry below `root` (only its direct entries unless
+/// `recursive`), without following symbolic links.
+///
+/// Entries removed during the scan are skipped.
+fn scan_watched_tree(root: &std::path::Path, recursive: bool) -> std::io::Result<WatchSnapshot> {
+    let mut snapshot = WatchSnapshot::new();
+    let mut pending = vec![std::path::PathBuf::new()];
+    while let Some(rel_dir) = pending.pop() {
+        let entries = match std::fs::read_dir(root.join(&rel_dir)) {
+            Ok(entries) => entries,
+            Err(e) if rel_dir.as_os_str().is_empty() => return Err(e),
+            // Removed or replaced by a file since it was listed; the next
+            // scan sees that
+            Err(_) => continue,
+        };
+        for entry in entries {
+            let entry = entry?;
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            let rel_path = rel_dir.join(entry.file_name());
+            let is_dir = metadata.is_dir();
+            if is_dir && recursive {
+                pending.push(rel_path.clone());
+            }
+            snapshot.insert(rel_path, (is_dir, FileStamp::of(&metadata)));
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Change to a watched tree, as queued and recorded in a watch journal.
+///
+/// The kind "existing" stands for an entry that existed when watching
+/// started; it is reported as a "created" event.
+struct WatchChange {
+    /// Sequence number in the journal, or 0 if not recorded as an event
+    seq: u64,
+    kind: &'static str,
+    rel_path: std::path::PathBuf,
+    is_dir: bool,
+    stamp: FileStamp,
+}
+
+impl WatchChange {
+    /// Format the change as a line of a watch journal.
+    fn line(&self) -> String {
+        format!(
+            "{} {} {} {} {} {}\n",
+            self.seq,
+            self.kind,
+            if self.is_dir { "d" } else { "f" },
+            self.stamp.size,
+            self.stamp.mtime_ns,
+            encode_checkpoint_path(self.rel_path.as_os_str())
+        )
+    }
+
+    /// Reverse `line`, without the newline.
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(6, ' ');
+        let seq = fields.next()?.parse().ok()?;
+        let kind = match fields.next()? {
+            "existing" => "existing",
+            "created" => "created",
+            "modified" => "modified",
+            "deleted" => "deleted",
+            _ => return None,
+        };
+        let is_dir = match fields.next()? {
+            "d" => true,
+            "f" => false,
+            _ => return None,
+        };
+        let size = fields.next()?.parse().ok()?;
+        let mtime_ns = fields.next()?.parse().ok()?;
+        let rel_path = decode_checkpoint_path(fields.next()?)?;
+        Some(WatchChange {
+            seq,
+            kind,
+            rel_path,
+            is_dir,
+            stamp: FileStamp { size, mtime_ns },
+        })
+    }
+
+    /// Bring a snapshot taken before the change up to date.
+    fn apply(&self, snapshot: &mut WatchSnapshot) {
+        if self.kind == "deleted" {
+            snapshot.remove(&self.rel_path);
+        } else {
+            snapshot.insert(self.rel_path.clone(), (self.is_dir, self.stamp));
+        }
+    }
+
+    fn event(&self, root: &std::path::Path) -> WatchEvent {
+        let existing = self.kind == "existing";
+        WatchEvent {
+            kind: if existing { "created" } else { self.kind },
+            path: root.join(&self.rel_path).to_string_lossy().into_owned(),
+            is_dir: self.is_dir,
+            existing,
+            token: (self.seq != 0).then(|| self.seq.to_string()),
+        }
+    }
+}
+
+/// Format marker on the first line of a `watch_async` journal file.
+const WATCH_JOURNAL_HEADER: &str = "rapfiles watch journal 1";
+
+/// Events of a `DirWatcher`, kept on disk so that watching can resume after
+/// a restart.
+///
+/// The file holds a header, the watched root, a cursor, and then one line
+/// per `WatchChange`: its sequence number, kind, "d" or "f", the size and
+/// mtime (in nanoseconds), and the path relative to the root. Lines
+/// numbered 0 describe the tree as of the cursor, the sequence number of
+/// the last event left out; events follow in order. A resumed watcher
+/// rewrites the file without the events its consumer has processed.
+struct WatchJournal {
+    file: std::fs::File,
+    next_seq: u64,
+}
+
+/// The part of a journal kept when a watcher resumes.
+struct ResumedJournal {
+    /// Sequence number of the last processed event
+    cursor: u64,
+    /// The tree after the last processed event
+    base: WatchSnapshot,
+    /// Events recorded after the cursor
+    replay: Vec<WatchChange>,
+    next_seq: u64,
+}
+
+impl WatchJournal {
+    /// Read the journal at `path`, if there is one, and split it after the
+    /// event `resume_token` (after the cursor if None).
+    async fn load(
+        path: &str,
+        root: &str,
+        resume_token: Option<&str>,
+    ) -> PyResult<Option<ResumedJournal>> {
+        let contents = match tokio::fs::read(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && resume_token.is_none() => {
+                return Ok(None)
+            }
+            Err(e) => return Err(map_io_error(e, path, "read journal")),
+        };
+        let invalid = |detail: &str| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid watch journal {path}: {detail}"
+            ))
+        };
+        let text = std::str::from_utf8(&contents).map_err(|_| invalid("not UTF-8"))?;
+        let body = text
+            .strip_prefix(WATCH_JOURNAL_HEADER)
+            .and_then(|rest| rest.strip_prefix('\n'))
+            .ok_or_else(|| invalid("missing header"))?;
+        let body = body
+            .strip_prefix(&format!("root {}\n", encode_checkpoint_path(root.as_ref())))
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Journal {path} does not belong to a watch of {root}"
+                ))
+            })?;
+        let (cursor, entries) = body
+            .split_once('\n')
+            .and_then(|(line, entries)| {
+                Some((line.strip_prefix("cursor ")?.parse().ok()?, entries))
+            })
+            .ok_or_else(|| invalid("missing cursor"))?;
+        // The last piece has no newline yet: it was cut short or is empty
+        let mut lines: Vec<&str> = entries.split('\n').collect();
+        lines.pop();
+        let changes = lines
+            .into_iter()
+            .map(|line| {
+                WatchChange::parse(line).ok_or_else(|| invalid(&format!("bad entry {line:?}")))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let last = changes
+            .iter()
+            .map(|change| change.seq)
+            .fold(cursor, u64::max);
+        let resume_from = match resume_token {
+            None => cursor,
+            Some(token) => token
+                .parse()
+                .ok()
+                .filter(|seq| (cursor..=last).contains(seq))
+                .ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid resume_token: {token}. Journal {path} holds tokens {cursor} to {last}"
+                    ))
+                })?,
+        };
+        let mut base = WatchSnapshot::new();
+        let mut replay = Vec::new();
+        for change in changes {
+            if change.seq <= resume_from {
+                change.apply(&mut base);
+            } else {
+                replay.push(change);
+            }
+        }
+        Ok(Some(ResumedJournal {
+            cursor: resume_from,
+            base,
+            replay,
+            next_seq: last + 1,
+        }))
+    }
+
+    /// Replace the journal at `path` with `base` as of `cursor` followed by
+    /// `events`, and open it for appending further events.
+    fn create(
+        path: &str,
+        root: &str,
+        cursor: u64,
+        base: &WatchSnapshot,
+        events: &[WatchChange],
+        next_seq: u64,
+    ) -> std::io::Result<Self> {
+        let mut contents = format!(
+            "{WATCH_JOURNAL_HEADER}\nroot {}\ncursor {cursor}\n",
+            encode_checkpoint_path(root.as_ref())
+        );
+        for (rel_path, &(is_dir, stamp)) in base {
+            let change = WatchChange {
+                seq: 0,
+                kind: "existing",
+                rel_path: rel_path.clone(),
+                is_dir,
+                stamp,
+            };
+            contents.push_str(&change.line());
+        }
+        for event in events {
+            contents.push_str(&event.line());
+        }
+        let path = std::path::Path::new(path);
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp_path = path.with_file_name(format!(".{file_name}.tmp"));
+        std::fs::write(&temp_path, contents)?;
+        std::fs::rename(&temp_path, path)?;
+        let file = std::fs::OpenOptions::new().append(true).open(path)?;
+        Ok(WatchJournal { file, next_seq })
+    }
+}
+
+/// Progress of a `DirWatcher`.
+struct WatchState {
+    snapshot: WatchSnapshot,
+    /// Events found by the last scan and not yet returned
+    events: std::collections::VecDeque<WatchEvent>,
+    /// When the tree may be scanned again
+    next_scan: tokio::time::Instant,
+    closed: bool,
+    journal: Option<WatchJournal>,
+    /// How long a file must stay unchanged before it is reported
+    stable_after: Option<std::time::Duration>,
+    /// Creations and modifications of files held back until they settle,
+    /// with the time the file was last seen to change
+    unstable: std::collections::BTreeMap<std::path::PathBuf, (WatchChange, tokio::time::Instant)>,
+}
+
+impl WatchState {
+    /// Hold back the creation or modification of a file until it has not
+    /// changed for `stable_after`, returning the changes to report now.
+    fn settle(&mut self, changes: Vec<WatchChange>) -> Vec<WatchChange> {
+        let Some(stable_after) = self.stable_after else {
+            return changes;
+        };
+        let now = tokio::time::Instant::now();
+        let mut ready = Vec::new();
+        for change in changes {
+            if change.is_dir {
+                ready.push(change);
+                continue;
+            }
+            let held = self.unstable.remove(&change.rel_path);
+            if change.kind == "deleted" {
+                // A file removed before it settled was never reported
+                match held {
+                    Some((held, _)) if held.kind != "modified" => {}
+                    _ => ready.push(change),
+                }
+            } else {
+                // A new file that is still changing is reported as created
+                let kind = held.map_or(change.kind, |(held, _)| held.kind);
+                let rel_path = change.rel_path.clone();
+                self.unstable
+                    .insert(rel_path, (WatchChange { kind, ..change }, now));
+            }
+        }
+        let settled: Vec<std::path::PathBuf> = self
+            .unstable
+            .iter()
+            .filter(|(_, (_, since))| now.duration_since(*since) >= stable_after)
+            .map(|(rel_path, _)| rel_path.clone())
+            .collect();
+        for rel_path in settled {
+            if let Some((change, _)) = self.unstable.remove(&rel_path) {
+                ready.push(change);
+            }
+        }
+        ready
+    }
+
+    /// Queue an event for each change, recording them in the journal first.
+    fn queue(
+        &mut self,
+        root: &std::path::Path,
+        mut changes: Vec<WatchChange>,
+    ) -> std::io::Result<()> {
+        if let Some(journal) = &mut self.journal {
+            let mut lines = String::new();
+            for change in &mut changes {
+                change.seq = journal.next_seq;
+                journal.next_seq += 1;
+                lines.push_str(&change.line());
+            }
+            std::io::Write::write_all(&mut journal.file, lines.as_bytes())?;
+        }
+        self.events
+            .extend(changes.iter().map(|change| change.event(root)));
+        Ok(())
+    }
+
+    /// Replace the snapshot with `current`, queueing an event for every
+    /// difference: deletions (contents before their directory), then
+    /// creations (directories before their contents), then modifications.
+    fn update(&mut self, root: &std::path::Path, current: WatchSnapshot) -> std::io::Result<()> {
+        let change = |kind, rel_path: &std::path::Path, is_dir, stamp| WatchChange {
+            seq: 0,
+            kind,
+            rel_path: rel_path.to_path_buf(),
+            is_dir,
+            stamp,
+        };
+        let mut changes = Vec::new();
+        // An entry that changed between file and directory is replaced
+        for (rel_path, &(was_dir, stamp)) in self.snapshot.iter().rev() {
+            if current.get(rel_path).map(|&(is_dir, _)| is_dir) != Some(was_dir) {
+                changes.push(change("deleted", rel_path, was_dir, stamp));
+            }
+        }
+        let mut modified = Vec::new();
+        for (rel_path, &(is_dir, stamp)) in &current {
+            match self.snapshot.get(rel_path) {
+                Some(&(was_dir, old)) if was_dir == is_dir => {
+                    // A directory's own timestamps change with its contents
+                    if !is_dir && old != stamp {
+                        modified.push(change("modified", rel_path, is_dir, stamp));
+                    }
+                }
+                _ => changes.push(change("created", rel_path, is_dir, stamp)),
+            }
+        }
+        changes.extend(modified);
+        let changes = self.settle(changes);
+        self.queue(root, changes)?;
+        self.snapshot = current;
+        Ok(())
+    }
+}
+
+/// Async iterator over changes to a directory tree.
+///
+/// Created with `rapfiles.watch()`. The tree is scanned every
+/// `poll_interval` seconds while the iterator waits for events; iteration
+/// continues until `close()` is called.
+///
+/// # Example
+///
+/// ```python
+/// watcher = await rapfiles.watch("inbox")
+/// async for event in watcher:
+///     print(event.kind, event.path)
+/// ```
+#[pyclass]
+struct DirWatcher {
+    path: String,
+    recursive: bool,
+    poll_interval: std::time::Duration,
+    state: Arc<std::sync::Mutex<WatchState>>,
+}
+
+#[pymethods]
+impl DirWatcher {
+    /// Default constructor - use watch() instead.
+    #[new]
+    fn new() -> PyResult<Self> {
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "DirWatcher cannot be instantiated directly. Use rapfiles.watch() instead.",
+        ))
+    }
+
+    /// Path of the watched directory.
+    #[getter]
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Stop watching; `async for` loops over the watcher end.
+    fn close(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.closed = true;
+        state.events.clear();
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Wait for and return the next change, for `async for`.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the next `WatchEvent`, or raises
+    /// `StopAsyncIteration` once the watcher is closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the directory cannot be scanned.
+    fn __anext__<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let state = Arc::clone(&self.state);
+        let path = self.path.clone();
+        let recursive = self.recursive;
+        let poll_interval = self.poll_interval;
+
+        let op = OpContext::new("watch", [self.path.as_str()]);
+        let future = async move {
+            loop {
+                let next_scan = {
+                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    if state.closed {
+                        return Err(PyErr::new::<pyo3::exceptions::PyStopAsyncIteration, _>(()));
+                    }
+                    if let Some(event) = state.events.pop_front() {
+                        return Ok(event);
+                    }
+                    state.next_scan
+                };
+                tokio::time::sleep_until(next_scan).await;
+                let (state, path_clone) = (Arc::clone(&state), path.clone());
+                run_blocking(move || {
+                    let root = std::path::Path::new(&path_clone);
+                    let current = match scan_watched_tree(root, recursive) {
+                        // Everything below a removed root is gone
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => WatchSnapshot::new(),
+                        current => current?,
+                    };
+                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    if !state.closed {
+                        state.update(root, current)?;
+                    }
+                    state.next_scan = tokio::time::Instant::now() + poll_interval;
+                    Ok(())
+                })
+                .await
+                .map_err(|e| map_io_error(e, &path, "watch directory"))?;
+            }
+        };
+        run_op(py, op, future)
+    }
+}
+
+/// Start watching a directory tree for changes.
+///
+/// The tree is scanned once before the coroutine completes, so changes made
+/// after it are reported even if iteration starts later.
+///
+/// With a `journal`, every event is appended to the journal file before it
+/// is returned and carries a `token`. Watching the same path with the same
+/// journal again, e.g. after a restart, first replays the events recorded
+/// after `resume_token` and then reports the changes made to the tree since
+/// the last scan of the previous watcher.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Directory to watch
+/// * `emit_existing` - If true, the first events are "created" events for
+///   the entries already in the tree (directories before their contents);
+///   ignored when resuming from an existing journal
+/// * `recursive` - If false, only direct entries of `path` are watched
+/// * `poll_interval` - Seconds between scans while waiting for changes; each
+///   scan reads the metadata of the whole tree
+/// * `journal` - Path of the file to record events in and resume from
+/// * `resume_token` - `token` of the last event processed from the journal;
+///   if None, every event recorded since it was last resumed is replayed
+/// * `emit_when_stable` - If given, files are only reported as created or
+///   modified once their size and modification time have not changed for
+///   this many seconds, so files still being copied in are not picked up;
+///   a file removed before then is not reported at all
+///
+/// # Returns
+///
+/// A coroutine that yields a `DirWatcher`.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the directory does not exist,
+/// `PyIOError` if it or the journal cannot be read or written, or
+/// `PyValueError` if the path, the poll interval, the journal or the resume
+/// token is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, emit_existing = true, recursive = true, poll_interval = 1.0, journal = None, resume_token = None, emit_when_stable = None))]
+#[allow(clippy::too_many_arguments)] // Mirrors the Python signature
+fn watch_async(
+    py: Python<'_>,
+    path: String,
+    emit_existing: bool,
+    recursive: bool,
+    poll_interval: f64,
+    journal: Option<String>,
+    resume_token: Option<String>,
+    emit_when_stable: Option<f64>,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    if let Some(journal) = &journal {
+        validate_path(journal)?;
+    } else if resume_token.is_some() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "resume_token requires a journal",
+        ));
+    }
+    let poll_interval = std::time::Duration::try_from_secs_f64(poll_interval)
+        .ok()
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid poll_interval: {poll_interval}. Must be a positive number of seconds"
+            ))
+        })?;
+    let stable_after = emit_when_stable
+        .map(|seconds| {
+            std::time::Duration::try_from_secs_f64(seconds)
+                .ok()
+                .filter(|duration| !duration.is_zero())
+                .ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid emit_when_stable: {seconds}. Must be a positive number of seconds"
+                    ))
+                })
+        })
+        .transpose()?;
+    let op = OpContext::new(
+        "watch",
+        [path.as_str()].into_iter().chain(journal.as_deref()),
+    );
+    let future = async move {
+        let resumed = match &journal {
+            Some(journal) => WatchJournal::load(journal, &path, resume_token.as_deref()).await?,
+            None => None,
+        };
+        let path_clone = path.clone();
+        let state = run_blocking(move || {
+            let root = std::path::Path::new(&path_clone);
+            let current = scan_watched_tree(root, recursive)?;
+            let mut state = WatchState {
+                snapshot: WatchSnapshot::new(),
+                events: std::collections::VecDeque::new(),
+                next_scan: tokio::time::Instant::now() + poll_interval,
+                closed: false,
+                journal: None,
+                stable_after,
+                unstable: std::collections::BTreeMap::new(),
+            };
+            match (journal, resumed) {
+                (Some(journal), Some(resumed)) => {
+                    state.journal = Some(WatchJournal::create(
+                        &journal,
+                        &path_clone,
+                        resumed.cursor,
+                        &resumed.base,
+                        &resumed.replay,
+                        resumed.next_seq,
+                    )?);
+                    state.snapshot = resumed.base;
+                    for change in &resumed.replay {
+                        change.apply(&mut state.snapshot);
+                        state.events.push_back(change.event(root));
+                    }
+                    state.update(root, current)?;
+                }
+                (journal, _) => {
+                    if let Some(journal) = journal {
+                        // Without "existing" events the initial tree is
+                        // recorded as of cursor 0
+                        let base = if emit_existing {
+                            WatchSnapshot::new()
+                        } else {
+                            current.clone()
+                        };
+                        state.journal = Some(WatchJournal::create(
+                            &journal,
+                            &path_clone,
+                            0,
+                            &base,
+                            &[],
+                            1,
+                        )?);
+                    }
+                    if emit_existing {
+                        let existing = current
+                            .iter()
+                            .map(|(rel_path, &(is_dir, stamp))| WatchChange {
+                                seq: 0,
+                                kind: "existing",
+                                rel_path: rel_path.clone(),
+                                is_dir,
+                                stamp,
+                            })
+                            .collect();
+                        let existing = state.settle(existing);
+                        state.queue(root, existing)?;
+                    }
+                    state.snapshot = current;
+                }
+            }
+            Ok(state)
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path, "watch directory"))?;
+        Ok(DirWatcher {
+            path,
+            recursive,
+            poll_interval,
+            state: Arc::new(std::sync::Mutex::new(state)),
+        })
+    };
+    run_op(py, op, future)
+}
+
+// Cleanup registry
+
+/// Paths registered with `register_cleanup`, with the scope they belong to.
+///
+/// Scope 0 is the process; the Python layer uses other scopes for paths
+/// that must also go when a particular event loop shuts down.
+static CLEANUP_PATHS: std::sync::Mutex<Vec<(String, u64)>> = std::sync::Mutex::new(Vec::new());
+
+/// Process the entries of `CLEANUP_PATHS` were registered in.
+static CLEANUP_PID: AtomicU32 = AtomicU32::new(0);
+
+/// Lock the cleanup registry.
+///
+/// A child process forked after paths were registered starts with an empty
+/// registry: the paths belong to the parent, which removes them itself, so
+/// a worker exiting must not remove them under it.
+fn cleanup_registry() -> std::sync::MutexGuard<'static, Vec<(String, u64)>> {
+    let mut registry = CLEANUP_PATHS.lock().unwrap_or_else(|e| e.into_inner());
+    let pid = std::process::id();
+    if CLEANUP_PID.swap(pid, Ordering::Relaxed) != pid {
+        registry.clear();
+    }
+    registry
+}
+
+/// Remove a registered path, whether it is a file, a symlink or a tree.
+///
+/// A path that no longer exists counts as removed.
+fn remove_cleanup_path(path: &str) -> std::io::Result<()> {
+    let path = std::path::Path::new(path);
+    let result = match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => remove_tree_parallel(path, REMOVE_TREE_MAX_WORKERS),
+        Ok(_) => std::fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+/// List the registered paths of `scope` (every scope if None).
+fn registered_paths(scope: Option<u64>) -> Vec<String> {
+    cleanup_registry()
+        .iter()
+        .filter(|(_, path_scope)| scope.is_none_or(|scope| *path_scope == scope))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+/// Remove and unregister the paths of `scope` (every scope if None).
+///
+/// Returns an error message for each path that could not be removed; those
+/// paths are unregistered as well so a failure is reported only once.
+/// Paths read-only mode does not allow removing are reported but stay
+/// registered, so they are removed once it is disabled.
+fn remove_registered(scope: Option<u64>) -> Vec<String> {
+    let (paths, blocked): (Vec<(String, u64)>, Vec<_>) = {
+        let mut registry = cleanup_registry();
+        let (selected, kept): (Vec<_>, Vec<_>) = registry
+            .drain(..)
+            .partition(|(_, path_scope)| scope.is_none_or(|scope| *path_scope == scope));
+        *registry = kept;
+        let (paths, blocked): (Vec<_>, Vec<_>) = selected
+            .into_iter()
+            .partition(|(path, _)| is_writable(path));
+        registry.extend(blocked.iter().cloned());
+        (paths, blocked)
+    };
+    let blocked = blocked
+        .into_iter()
+        .map(|(path, _)| format!("Failed to remove {path}: rapfiles is in read-only mode"));
+    let failed = paths.into_iter().filter_map(|(path, _)| {
+        remove_cleanup_path(&path)
+            .err()
+            .map(|e| format!("Failed to remove {path}: {e}"))
+    });
+    blocked.chain(failed).collect()
+}
+
+/// Register a file or directory to be removed by `run_cleanup`.
+///
+/// Relative paths are made absolute against the current working directory,
+/// so a later `chdir` does not change what gets removed. Registering a path
+/// twice has no further effect.
+///
+/// # Arguments
+///
+/// * `path` - File or directory to remove at cleanup time
+/// * `scope` - Cleanup scope (0 for the process)
+///
+/// # Returns
+///
+/// The absolute path that was registered.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if the path is invalid, or `PyIOError` if the
+/// current working directory cannot be determined.
+#[pyfunction]
+#[pyo3(signature = (path, scope = 0))]
+fn register_cleanup(path: String, scope: u64) -> PyResult<String> {
+    validate_path(&path)?;
+    let absolute = std::path::absolute(&path)
+        .map_err(|e| map_io_error(e, &path, "register cleanup for"))?
+        .to_string_lossy()
+        .into_owned();
+    let mut registry = cleanup_registry();
+    if !registry
+        .iter()
+        .any(|(registered, _)| *registered == absolute)
+    {
+        registry.push((absolute.clone(), scope));
+    }
+    Ok(absolute)
+}
+
+/// Stop tracking a path registered with `register_cleanup`.
+///
+/// Returns true if the path was registered.
+#[pyfunction]
+fn unregister_cleanup(path: String) -> PyResult<bool> {
+    let absolute = std::path::absolute(&path)
+        .map_err(|e| map_io_error(e, &path, "unregister cleanup for"))?
+        .to_string_lossy()
+        .into_owned();
+    let mut registry = cleanup_registry();
+    let before = registry.len();
+    registry.retain(|(registered, _)| *registered != absolute);
+    Ok(registry.len() != before)
+}
+
+/// List the registered cleanup paths in registration order.
+#[pyfunction]
+fn registered_cleanup() -> Vec<String> {
+    registered_paths(None)
+}
+
+/// Remove registered paths synchronously.
+///
+/// Used from `atexit`, when no event loop is available; the GIL is released
+/// while the paths are removed.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `scope` - Only remove paths of this scope; every path if None
+///
+/// # Returns
+///
+/// An error message for each path that could not be removed, including
+/// paths read-only mode protects.
+#[pyfunction]
+#[pyo3(signature = (scope = None))]
+fn run_cleanup(py: Python<'_>, scope: Option<u64>) -> Vec<String> {
+    py.detach(|| remove_registered(scope))
+}
+
+/// Remove registered paths asynchronously.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `scope` - Only remove paths of this scope; every path if None
+///
+/// # Returns
+///
+/// A coroutine that yields an error message for each path that could not
+/// be removed.
+///
+/// # Errors
+///
+/// Returns `ReadOnlyError` if read-only mode protects a registered path.
+#[pyfunction]
+#[pyo3(signature = (scope = None))]
+fn run_cleanup_async(py: Python<'_>, scope: Option<u64>) -> PyResult<Bound<'_, PyAny>> {
+    let paths = registered_paths(scope);
+    let op = OpContext::new("run_cleanup", paths.iter().map(String::as_str));
+    let future = async move {
+        tokio::task::spawn_blocking(move || remove_registered(scope))
+            .await
+            .map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to run cleanup: {e}"))
+            })
+    };
+    run_op(py, op, future)
+}
+
+// Scratch space
+
+/// Directories `best_scratch_dir_async` considers when given no candidates,
+/// in order of preference among equally fast ones.
+fn default_scratch_candidates() -> Vec<String> {
+    let mut candidates = vec![std::env::temp_dir().to_string_lossy().into_owned()];
+    #[cfg(unix)]
+    candidates.extend(["/dev/shm", "/tmp", "/var/tmp"].map(String::from));
+    candidates
+}
+
+/// Whether `path` lives on a memory-backed filesystem (tmpfs or ramfs).
+///
+/// Only detected on Linux; other platforms report `false`.
+fn is_memory_backed(path: &std::path::Path) -> std::io::Result<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+        let mut st: libc::statfs = unsafe { std::mem::zeroed() };
+        // SAFETY: statfs only writes into the buffer we pass
+        if unsafe { libc::statfs(c_path.as_ptr(), &mut st) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // Filesystem magic numbers are 32 bits wide; RAMFS_MAGIC is missing from libc
+        let fs_type = st.f_type as u32;
+        Ok(fs_type == libc::TMPFS_MAGIC as u32 || fs_type == 0x8584_58f6)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        Ok(false)
+    }
+}
+
+/// Whether the current user can create files in the directory `path`.
+fn is_writable_dir(path: &std::path::Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    if !metadata.is_dir() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        // SAFETY: access only reads the path
+        unsafe { libc::access(c_path.as_ptr(), libc::W_OK | libc::X_OK) == 0 }
+    }
+    #[cfg(not(unix))]
+    {
+        !metadata.permissions().readonly()
+    }
+}
+
+/// Pick the fastest suitable scratch directory asynchronously.
+///
+/// Each candidate must be an existing directory the current user can write
+/// to, with at least `min_free` bytes available. Memory-backed filesystems
+/// (tmpfs, ramfs) are preferred over disks; among equally fast candidates
+/// the earlier one wins. Filesystem types are only detected on Linux, so
+/// elsewhere the first suitable candidate is returned. All I/O operations
+/// execute outside the Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `min_free` - Bytes that must be available to the current user
+/// * `candidates` - Directories to consider, in order of preference; if
+///   `None`, the system temporary directory (`TMPDIR`), then `/dev/shm`,
+///   `/tmp` and `/var/tmp` on Unix
+///
+/// # Returns
+///
+/// A coroutine that yields the chosen directory, or `None` if no candidate
+/// is suitable.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if a candidate path is invalid.
+#[pyfunction]
+#[pyo3(signature = (min_free = 0, candidates = None))]
+fn best_scratch_dir_async(
+    py: Python<'_>,
+    min_free: u64,
+    candidates: Option<Vec<String>>,
+) -> PyResult<Bound<'_, PyAny>> {
+    let candidates = candidates.unwrap_or_else(default_scratch_candidates);
+    for candidate in &candidates {
+        validate_path(candidate)?;
+    }
+    let op = OpContext::new("best_scratch_dir", candidates.iter().map(String::as_str));
+    let future = async move {
+        run_blocking(move || {
+            let mut on_disk = None;
+            for candidate in candidates {
+                let path = std::path::Path::new(&candidate);
+                if !is_writable_dir(path) {
+                    continue;
+                }
+                match fs2::available_space(path) {
+                    Ok(free) if free >= min_free => {}
+                    _ => continue,
+                }
+                if is_memory_backed(path).unwrap_or(false) {
+                    return Ok(Some(candidate));
+                }
+                on_disk.get_or_insert(candidate);
+            }
+            Ok(on_disk)
+        })
+        .await
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+    };
+    run_op(py, op, future)
+}
+
+// Runtime introspection
+
+/// Describe the state of the rapfiles runtime.
+///
+/// Reports the Tokio worker threads and task counts together with the
+/// in-memory tables rapfiles keeps (open handles, testing hooks, the cleanup
+/// registry and the active recording), so services can expose library state
+/// on a debug endpoint. Reading these values is cheap and does not block on
+/// in-flight I/O.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+///
+/// # Returns
+///
+/// A dict with `runtime` ("tokio"), `worker_threads`, `alive_tasks`,
+/// `queued_tasks` (tasks waiting in the runtime's global queue),
+/// `open_files` (`AsyncFile` handles not yet closed) and `caches`, a dict of table
+/// sizes: `faults`, `slowdowns`, `cleanup_paths` and `recorded_ops`.
+#[pyfunction]
+fn runtime_info(py: Python<'_>) -> PyResult<Bound<'_, pyo3::types::PyDict>> {
+    let metrics = runtime().metrics();
+    let recorded_ops = RECORDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map_or(0, |recording| recording.records.len());
+
+    let caches = pyo3::types::PyDict::new(py);
+    caches.set_item(
+        "faults",
+        FAULTS.lock().unwrap_or_else(|e| e.into_inner()).len(),
+    )?;
+    caches.set_item(
+        "slowdowns",
+        SLOWDOWNS.lock().unwrap_or_else(|e| e.into_inner()).len(),
+    )?;
+    caches.set_item("cleanup_paths", cleanup_registry().len())?;
+    caches.set_item("recorded_ops", recorded_ops)?;
+
+    let info = pyo3::types::PyDict::new(py);
+    info.set_item("runtime", "tokio")?;
+    info.set_item("worker_threads", metrics.num_workers())?;
+    info.set_item("alive_tasks", metrics.num_alive_tasks())?;
+    info.set_item("queued_tasks", metrics.global_queue_depth())?;
+    info.set_item(
+        "open_files",
+        OPEN_HANDLES.lock().unwrap_or_else(|e| e.into_inner()).len(),
+    )?;
+    info.set_item("caches", caches)?;
+    Ok(info)
+}
+
+// Positional I/O
+
+/// Read up to `buf.len()` bytes at `offset` without moving a file cursor.
+///
+/// Stops early only at end of file, returning the number of bytes read.
+fn read_full_at(file: &std::fs::File, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        #[cfg(unix)]
+        let n =
+            std::os::unix::fs::FileExt::read_at(file, &mut buf[filled..], offset + filled as u64);
+        #[cfg(windows)]
+        let n = std::os::windows::fs::FileExt::seek_read(
+            file,
+            &mut buf[filled..],
+            offset + filled as u64,
+        );
+        match n {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Write all of `buf` at `offset` without moving a file cursor.
+fn write_full_at(file: &std::fs::File, buf: &[u8], offset: u64) -> std::io::Result<()> {
+    let mut written = 0;
+    while written < buf.len() {
+        #[cfg(unix)]
+        let n =
+            std::os::unix::fs::FileExt::write_at(file, &buf[written..], offset + written as u64);
+        #[cfg(windows)]
+        let n = std::os::windows::fs::FileExt::seek_write(
+            file,
+            &buf[written..],
+            offset + written as u64,
+        );
+        match n {
+            Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+            Ok(n) => written += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Group requested ranges into the spans that are actually read.
+///
+/// Ranges are visited in offset order and merged into the current span when
+/// they start at most `gap` bytes after its end (overlapping and adjacent
+/// ranges always qualify). Without a gap every range is read on its own.
+///
+/// Returns `(span_offset, span_len, range_indices)` tuples.
+fn plan_range_reads(ranges: &[(u64, usize)], gap: Option<u64>) -> Vec<(u64, usize, Vec<usize>)> {
+    let Some(gap) = gap else {
+        return ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &(offset, len))| (offset, len, vec![i]))
+            .collect();
+    };
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by_key(|&i| ranges[i].0);
+
+    let mut spans: Vec<(u64, usize, Vec<usize>)> = Vec::new();
+    for i in order {
+        let (offset, len) = ranges[i];
+        let end = offset.saturating_add(len as u64);
+        if let Some((span_offset, span_len, members)) = spans.last_mut() {
+            let span_end = *span_offset + *span_len as u64;
+            if offset <= span_end.saturating_add(gap) {
+                *span_len = (span_end.max(end) - *span_offset) as usize;
+                members.push(i);
+                continue;
+            }
+        }
+        spans.push((offset, len, vec![i]));
+    }
+    spans
+}
+
+/// Read several byte ranges of a file asynchronously.
+///
+/// Each range is read with positional reads (`pread` on Unix), so no file
+/// cursor is shared and the ranges can be requested in any order. With
+/// `coalesce_gap`, nearby ranges are merged into fewer, larger reads in Rust
+/// and the results are sliced back out, which suits footer-then-pages access
+/// patterns such as Parquet or ZIP. All I/O operations execute outside the
+/// Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to read
+/// * `ranges` - List of `(offset, length)` pairs
+/// * `coalesce_gap` - If set, merge ranges separated by at most this many
+///   bytes (0 merges only overlapping or adjacent ranges)
+///
+/// # Returns
+///
+/// A coroutine that yields a list of bytes, one per requested range in the
+/// original order. Ranges extending past end of file are truncated.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the file does not exist, `PyIOError` if
+/// the file cannot be read, or `PyValueError` if the path is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, ranges, coalesce_gap = None))]
+fn read_ranges_async(
+    py: Python<'_>,
+    path: String,
+    ranges: Vec<(u64, usize)>,
+    coalesce_gap: Option<u64>,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("read_ranges", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let (results, bytes_read) = tokio::task::spawn_blocking(move || {
+            let file = std::fs::File::open(&path)?;
+            let file_len = file.metadata()?.len();
+            let mut results = vec![Vec::new(); ranges.len()];
+            let mut bytes_read = 0;
+            for (span_offset, span_len, members) in plan_range_reads(&ranges, coalesce_gap) {
+                // Never allocate past end of file, whatever length was requested
+                let span_len = span_len.min(file_len.saturating_sub(span_offset) as usize);
+                let mut span = vec![0u8; span_len];
+                let n = read_full_at(&file, &mut span, span_offset)?;
+                span.truncate(n);
+                bytes_read += n;
+                for i in members {
+                    let (offset, len) = ranges[i];
+                    let start = ((offset - span_offset) as usize).min(span.len());
+                    let end = start.saturating_add(len).min(span.len());
+                    results[i] = span[start..end].to_vec();
+                }
+            }
+            Ok::<_, std::io::Error>((results, bytes_read))
+        })
+        .await
+        .map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to read ranges of {path_clone}: {e}"
+            ))
+        })?
+        .map_err(|e| map_io_error(e, &path_clone, "read ranges of"))?;
+        record_transfer(bytes_read);
+        Ok(results)
+    };
+    run_op(py, op, future)
+}
+
+/// Merge consecutive segments that continue exactly where the previous one
+/// ended, so a manifest of small contiguous patches becomes one write.
+///
+/// Segments that are not contiguous stay separate and keep their order, so
+/// a later segment still overwrites an earlier overlapping one.
+fn merge_contiguous_segments(segments: Vec<(u64, Vec<u8>)>) -> Vec<(u64, Vec<u8>)> {
+    let mut merged: Vec<(u64, Vec<u8>)> = Vec::with_capacity(segments.len());
+    for (offset, data) in segments {
+        if let Some((last_offset, last_data)) = merged.last_mut() {
+            if *last_offset + last_data.len() as u64 == offset {
+                last_data.extend_from_slice(&data);
+                continue;
+            }
+        }
+        merged.push((offset, data));
+    }
+    merged
+}
+
+/// Write several `(offset, data)` segments into a file asynchronously.
+///
+/// Segments are written with positional writes (`pwrite` on Unix) in the
+/// given order, with consecutive contiguous segments merged into a single
+/// write, and by default the file is flushed to disk with one `fsync` at the
+/// end. The file is created if missing and never truncated; writing past its
+/// end extends it (leaving a hole where nothing was written). All I/O
+/// operations execute outside the Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to patch
+/// * `segments` - List of `(offset, data)` pairs; later segments win where
+///   they overlap earlier ones
+/// * `durability` - `"none"`, `"flush"`, `"fsync"` (default) or
+///   `"fsync+dir"`; see `Durability`
+///
+/// # Returns
+///
+/// A coroutine that yields `None` once every segment is written (and synced).
+///
+/// # Errors
+///
+/// Returns `PyIOError` if the file cannot be opened, written or synced
+/// (e.g., permission denied, disk full), or `PyValueError` if the path or
+/// durability is invalid.
+#[pyfunction]
+#[pyo3(signature = (path, segments, durability = "fsync"))]
+fn write_segments_async<'py>(
+    py: Python<'py>,
+    path: String,
+    segments: Vec<(u64, Vec<u8>)>,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&path)?;
+    let durability = Durability::parse(durability)?;
+    let op = OpContext::new("write_segments", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let bytes_written = tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)?;
+            let mut bytes_written = 0;
+            for (offset, data) in merge_contiguous_segments(segments) {
+                write_full_at(&file, &data, offset)?;
+                bytes_written += data.len();
+            }
+            if durability >= Durability::Fsync {
+                file.sync_all()?;
+            }
+            if durability >= Durability::FsyncDir {
+                sync_parent_dir(std::path::Path::new(&path))?;
+            }
+            Ok::<_, std::io::Error>(bytes_written)
+        })
+        .await
+        .map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to write segments to {path_clone}: {e}"
+            ))
+        })?
+        .map_err(|e| map_io_error(e, &path_clone, "write segments to"))?;
+        record_transfer(bytes_written);
+        Ok(())
+    };
+    run_op(py, op, future)
+}
+
+/// Find the allocated data regions of an open file as `(offset, length)`.
+///
+/// Uses `SEEK_DATA`/`SEEK_HOLE` where the platform has them. Filesystems
+/// without hole tracking report the whole file as one extent, which is also
+/// the fallback on other platforms.
+fn data_extents(file: &std::fs::File) -> std::io::Result<Vec<(u64, u64)>> {
+    let len = file.metadata()?.len();
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "illumos",
+        target_os = "solaris"
+    ))]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let fd = file.as_raw_fd();
+        let seek = |offset: u64, whence: libc::c_int| -> std::io::Result<Option<u64>> {
+            // SAFETY: lseek only moves the offset of a descriptor we own
+            let pos = unsafe { libc::lseek(fd, offset as libc::off_t, whence) };
+            if pos >= 0 {
+                return Ok(Some(pos as u64));
+            }
+            let err = std::io::Error::last_os_error();
+            match err.raw_os_error() {
+                // No data at or after `offset`
+                Some(libc::ENXIO) => Ok(None),
+                _ => Err(err),
+            }
+        };
+
+        let mut extents = Vec::new();
+        let mut pos = 0;
+        while pos < len {
+            let start = match seek(pos, libc::SEEK_DATA) {
+                Ok(Some(start)) => start,
+                Ok(None) => break,
+                // Hole queries unsupported: treat the file as fully allocated
+                Err(e) if e.raw_os_error() == Some(libc::EINVAL) => return Ok(vec![(0, len)]),
+                Err(e) => return Err(e),
+            };
+            let end = seek(start, libc::SEEK_HOLE)?.unwrap_or(len).min(len);
+            if end > start {
+                extents.push((start, end - start));
+            }
+            pos = end.max(start + 1);
+        }
+        Ok(extents)
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "illumos",
+        target_os = "solaris"
+    )))]
+    {
+        Ok(if len > 0 { vec![(0, len)] } else { Vec::new() })
+    }
+}
+
+/// List the data extents of a file asynchronously.
+///
+/// Returns the allocated regions of a sparse file so backup and copy tools
+/// can skip holes and transfer only the data. Extents are found with
+/// `SEEK_DATA`/`SEEK_HOLE` where available; on filesystems or platforms
+/// without hole support the whole file is reported as a single extent. All
+/// I/O operations execute outside the Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to inspect
+///
+/// # Returns
+///
+/// A coroutine that yields a list of `(offset, length)` tuples in ascending
+/// offset order. Holes are the gaps between extents; an empty or fully
+/// sparse file yields an empty list.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if the file does not exist, `PyIOError` if
+/// the file cannot be inspected, or `PyValueError` if the path is invalid.
+#[pyfunction]
+fn list_extents_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let op = OpContext::new("list_extents", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        tokio::task::spawn_blocking(move || data_extents(&std::fs::File::open(&path)?))
+            .await
+            .map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to list extents of {path_clone}: {e}"
+                ))
+            })?
+            .map_err(|e| map_io_error(e, &path_clone, "list extents of"))
+    };
+    run_op(py, op, future)
+}
+
+/// Size of the buffer `create_filled_async` writes random data from.
+const FILL_CHUNK_SIZE: usize = 1 << 20;
+
+/// Contents written by `create_filled_async`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FillPattern {
+    /// Zero bytes, allocated on disk where the platform allows it
+    Zero,
+    /// Pseudo-random bytes from a SplitMix64 stream
+    Random,
+}
+
+impl FillPattern {
+    /// Parse the `fill` argument of `create_filled_async`.
+    fn parse(fill: &str) -> PyResult<Self> {
+        match fill {
+            "zero" => Ok(FillPattern::Zero),
+            "random" => Ok(FillPattern::Random),
+            other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid fill: {other}. Must be 'zero' or 'random'"
+            ))),
+        }
+    }
+}
+
+/// Allocate `size` zero bytes for an empty file.
+///
+/// Uses `fallocate` on Linux so the blocks are reserved on disk instead of
+/// left as a hole; filesystems without it, and other platforms, fall back to
+/// extending the file, which may leave it sparse.
+fn allocate_zeroed(file: &std::fs::File, size: u64) -> std::io::Result<()> {
+    if size == 0 {
+        return Ok(());
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        // SAFETY: fallocate only changes the allocation of a descriptor we own
+        let result = unsafe { libc::fallocate(file.as_raw_fd(), 0, 0, size as libc::off_t) };
+        if result == 0 {
+            return Ok(());
+        }
+        let err = std::io::Error::last_os_error();
+        if !matches!(
+            err.raw_os_error(),
+            Some(libc::EOPNOTSUPP) | Some(libc::ENOSYS)
+        ) {
+            return Err(err);
+        }
+    }
+    file.set_len(size)
+}
+
+/// Create a file of `size` bytes filled with zeros or random data asynchronously.
+///
+/// Meant for generating test data and for disk burn-in. Zero fill reserves
+/// the space with `fallocate` where available, so no data is written; random
+/// fill streams SplitMix64 output to the file. An existing file is replaced,
+/// and by default the file is flushed to disk with `fsync` before the
+/// coroutine completes. All I/O operations execute outside the Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Path to the file to create
+/// * `size` - Size of the file in bytes
+/// * `fill` - `"zero"` (default) or `"random"`
+/// * `seed` - Seed for random fill, for reproducible contents (random if `None`)
+/// * `durability` - `"none"`, `"flush"`, `"fsync"` (default) or
+///   `"fsync+dir"`; see `Durability`
+///
+/// # Returns
+///
+/// A coroutine that yields `None` once the file is written (and synced).
+///
+/// # Errors
+///
+/// Returns `PyValueError` if `fill` or the durability is unknown or the path
+/// is invalid, or `PyIOError` if the file cannot be created or written
+/// (e.g., disk full).
+#[pyfunction]
+#[pyo3(signature = (path, size, fill = "zero", *, seed = None, durability = "fsync"))]
+fn create_filled_async<'py>(
+    py: Python<'py>,
+    path: String,
+    size: u64,
+    fill: &str,
+    seed: Option<u64>,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    validate_path(&path)?;
+    let fill = FillPattern::parse(fill)?;
+    let durability = Durability::parse(durability)?;
+    let mut state = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+            ^ u64::from(std::process::id())
+    });
+    let op = OpContext::new("create_filled", [path.as_str()]);
+    let future = async move {
+        let path_clone = path.clone();
+        let bytes_written = run_blocking(move || {
+            let mut file = std::fs::File::create(&path)?;
+            let mut bytes_written = 0;
+            match fill {
+                FillPattern::Zero => allocate_zeroed(&file, size)?,
+                FillPattern::Random => {
+                    let mut buffer = vec![0u8; FILL_CHUNK_SIZE];
+                    let mut remaining = size;
+                    while remaining > 0 {
+                        let n = remaining.min(FILL_CHUNK_SIZE as u64) as usize;
+                        fill_random(&mut state, &mut buffer[..n]);
+                        std::io::Write::write_all(&mut file, &buffer[..n])?;
+                        remaining -= n as u64;
+                    }
+                    bytes_written = size as usize;
+                }
+            }
+            if durability >= Durability::Fsync {
+                file.sync_all()?;
+            }
+            if durability >= Durability::FsyncDir {
+                sync_parent_dir(std::path::Path::new(&path))?;
+            }
+            Ok(bytes_written)
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path_clone, "create"))?;
+        record_transfer(bytes_written);
+        Ok(())
+    };
+    run_op(py, op, future)
+}
+
+// Batch operations
+
+/// Read multiple files concurrently.
+///
+/// Reads all specified files concurrently and returns their contents.
+/// All I/O operations execute outside the Python GIL using native Tokio,
+/// ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `paths` - Vector of file paths to read
+///
+/// # Returns
+///
+/// A coroutine that yields a list of (path, result) tuples where:
+/// - `path`: The file path
+/// - `result`: Either the file contents as bytes, or an error message string
+#[pyfunction]
+fn read_files_async(py: Python<'_>, paths: Vec<String>) -> PyResult<Bound<'_, PyAny>> {
+    // Validate all paths
+    for path in &paths {
+        validate_path(path)?;
+    }
+    // Each path is allocated once and shared by the hooks, the per-file
+    // futures and the results
+    let paths: Vec<Arc<str>> = paths.into_iter().map(Arc::from).collect();
+
+    let op = OpContext::shared("read_files", &paths);
+    let future = async move {
+        let read_futures: Vec<_> = paths
+            .into_iter()
+            .map(|path| async move {
+                let result = match tokio::fs::read(&*path).await {
+                    Ok(bytes) => {
+                        record_transfer(bytes.len());
+                        Ok(bytes)
+                    }
+                    Err(e) => Err(format!("Failed to read file {path}: {e}")),
+                };
+                (path, result)
+            })
+            .collect();
+
+        let results = join_limited(read_futures).await;
+        // Convert to tuples with bytes (Ok) or error strings (Err), all under
+        // a single GIL acquisition
+        let python_results: Vec<(Py<PyString>, Py<PyAny>)> = Python::attach(|py| {
+            results
+                .into_iter()
+                .map(|(path, result)| {
+                    let py_obj: Py<PyAny> = match result {
+                        Ok(bytes) => PyBytes::new(py, &bytes).into(),
+                        Err(err_str) => PyString::new(py, &err_str).into(),
+                    };
+                    (PyString::new(py, &path).unbind(), py_obj)
+                })
+                .collect()
+        });
+        Ok(python_results)
+    };
+    run_op(py, op, future)
+}
+
+/// Write multiple files concurrently.
+///
+/// Writes contents to all specified files concurrently. All I/O operations
+/// execute outside the Python GIL using native Tokio, ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `files` - Vector of (path, contents) tuples to write
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` or
+///   `"fsync+dir"`, applied to each file
+///
+/// # Returns
+///
+/// A coroutine that yields a list of (path, result) tuples where:
+/// - `path`: The file path
+/// - `result`: Either Ok(()) on success, or an error message string
+#[pyfunction]
+#[pyo3(signature = (files, durability = "flush"))]
+fn write_files_async<'py>(
+    py: Python<'py>,
+    files: Vec<(String, Vec<u8>)>,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    // Validate all paths
+    for (path, _) in &files {
+        validate_path(path)?;
+    }
+    let durability = Durability::parse(durability)?;
+    let (paths, contents): (Vec<Arc<str>>, Vec<Vec<u8>>) = files
+        .into_iter()
+        .map(|(path, bytes)| (Arc::from(path), bytes))
+        .unzip();
+
+    let op = OpContext::shared("write_files", &paths);
+    let future = async move {
+        let write_futures: Vec<_> = paths
+            .into_iter()
+            .zip(contents)
+            .map(|(path, bytes)| async move {
+                let len = bytes.len();
+                let file_path = std::path::Path::new(&*path);
+                let written = durability
+                    .write_file(file_path, bytes, tokio::fs::write)
+                    .await;
+                let result = match written {
+                    Ok(()) => {
+                        record_transfer(len);
+                        durability
+                            .sync_parent(file_path)
+                            .await
+                            .map_err(|e| format!("Failed to sync directory of {path}: {e}"))
+                    }
+                    Err(e) => Err(format!("Failed to write file {path}: {e}")),
+                };
+                (path, result)
+            })
+            .collect();
+
+        let results = join_limited(write_futures).await;
+        // Convert Result<(), String> to Python-compatible values
+        let python_results: Vec<(Py<PyString>, Py<PyAny>)> = Python::attach(|py| {
+            results
+                .into_iter()
+                .map(|(path, result)| {
+                    let py_obj: Py<PyAny> = match result {
+                        Ok(()) => py.None(),
+                        Err(err_str) => PyString::new(py, &err_str).into(),
+                    };
+                    (PyString::new(py, &path).unbind(), py_obj)
+                })
+                .collect()
+        });
+        Ok(python_results)
+    };
+    run_op(py, op, future)
+}
+
+/// Copy multiple files concurrently.
+///
+/// Copies all specified files concurrently. All I/O operations execute
+/// outside the Python GIL using native Tokio, ensuring true async behavior.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `files` - Vector of (src, dst) tuples to copy
+/// * `preserve_owner` - If true, give each destination its source's owning
+///   user and group (Unix only)
+/// * `durability` - `"none"`, `"flush"` (default), `"fsync"` or
+///   `"fsync+dir"`, applied to each copy; see `Durability`
+///
+/// # Returns
+///
+/// A coroutine that yields a list of (src, dst, result) tuples where:
+/// - `src`: The source file path
+/// - `dst`: The destination file path
+/// - `result`: Either Ok(()) on success, or an error message string
+///
+/// # Errors
+///
+/// Returns `PyValueError` if a path or the durability is invalid.
+#[pyfunction]
+#[pyo3(signature = (files, preserve_owner = false, durability = "flush"))]
+fn copy_files_async<'py>(
+    py: Python<'py>,
+    files: Vec<(String, String)>,
+    preserve_owner: bool,
+    durability: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    // Validate all paths
+    for (src, dst) in &files {
+        validate_path(src)?;
+        validate_path(dst)?;
+    }
+    let durability = Durability::parse(durability)?;
+    // Sources and destinations alternate, matching the recorded operation paths
+    let paths: Vec<Arc<str>> = files
+        .into_iter()
+        .flat_map(|(src, dst)| [Arc::from(src), Arc::from(dst)])
+        .collect();
+
+    let op = OpContext::shared("copy_files", &paths);
+    let future = async move {
+        let copy_futures: Vec<_> = paths
+            .chunks_exact(2)
+            .map(|pair| {
+                let (src, dst) = (Arc::clone(&pair[0]), Arc::clone(&pair[1]));
+                async move {
+                    let result = async {
+                        let copied = tokio::fs::copy(&*src, &*dst)
+                            .await
+                            .map_err(|e| format!("Failed to copy file {src} -> {dst}: {e}"))?;
+                        record_transfer(copied as usize);
+                        if preserve_owner {
+                            preserve_ownership(&src, &dst)
+                                .await
+                                .map_err(|e| format!("Failed to preserve owner of {dst}: {e}"))?;
+                        }
+                        let dst_path = std::path::Path::new(&*dst);
+                        durability
+                            .sync_file(dst_path)
+                            .await
+                            .map_err(|e| format!("Failed to sync {dst}: {e}"))?;
+                        durability
+                            .sync_parent(dst_path)
+                            .await
+                            .map_err(|e| format!("Failed to sync directory of {dst}: {e}"))
+                    }
+                    .await;
+                    (src, dst, result)
+                }
+            })
+            .collect();
+
+        let results = join_limited(copy_futures).await;
+        // Convert Result<(), String> to Python-compatible values
+        let python_results: Vec<(Py<PyString>, Py<PyString>, Py<PyAny>)> = Python::attach(|py| {
+            results
+                .into_iter()
+                .map(|(src, dst, result)| {
+                    let py_obj: Py<PyAny> = match result {
+                        Ok(()) => py.None(),
+                        Err(err_str) => PyString::new(py, &err_str).into(),
+                    };
+                    (
+                        PyString::new(py, &src).unbind(),
+                        PyString::new(py, &dst).unbind(),
+                        py_obj,
+                    )
+                })
+                .collect()
+        });
+        Ok(python_results)
+    };
+    run_op(py, op, future)
+}
+
+/// Rename `from` to `to` for every `(from, to, label)` concurrently.
+///
+/// Returns the renames that succeeded and the first failure (in input
+/// order) with the label of its pair.
+async fn rename_all(
+    renames: Vec<(String, String, String)>,
+) -> (Vec<(String, String)>, Option<(std::io::Error, String)>) {
+    let futures: Vec<_> = renames
+        .into_iter()
+        .map(|(from, to, label)| async move {
+            let result = tokio::fs::rename(&from, &to).await;
+            (from, to, label, result)
+        })
+        .collect();
+    let mut done = Vec::new();
+    let mut failure = None;
+    for (from, to, label, result) in join_limited(futures).await {
+        match result {
+            Ok(()) => done.push((from, to)),
+            Err(e) => {
+                failure.get_or_insert((e, label));
+            }
+        }
+    }
+    (done, failure)
+}
+
+/// Rename every `(src, dst)` pair as one batch.
+///
+/// All pairs are checked before anything is renamed: every source must
+/// exist, no two pairs may share a source or a destination, and a
+/// destination may only exist if the batch renames it away. Sources that
+/// are also destinations (chains such as a -> b, b -> c, and swaps) are
+/// first moved to temporary names next to them. If a rename fails, the
+/// renames already done are undone, the last phase first.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if two pairs share a source or a destination,
+/// `PyFileExistsError` if a destination is taken, `PyFileNotFoundError` if
+/// a source does not exist, or the error of the failed rename (`PyIOError`
+/// naming the renames that could not be undone, if any).
+async fn rename_batch(pairs: Vec<(String, String)>) -> PyResult<()> {
+    use std::collections::{HashMap, HashSet};
+    use std::path::{Path, PathBuf};
+
+    let pairs: Vec<(String, String)> = pairs
+        .into_iter()
+        .filter(|(src, dst)| Path::new(src) != Path::new(dst))
+        .collect();
+    let mut sources = HashSet::new();
+    let mut destinations: HashMap<PathBuf, &str> = HashMap::new();
+    for (src, dst) in &pairs {
+        if !sources.insert(PathBuf::from(src)) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Duplicate source in rename batch: {src}"
+            )));
+        }
+        if let Some(other) = destinations.insert(PathBuf::from(dst), src) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Rename collision: {other} and {src} both rename to {dst}"
+            )));
+        }
+    }
+
+    let checks: Vec<_> = pairs
+        .iter()
+        .map(|(src, dst)| {
+            let replaced = sources.contains(Path::new(dst));
+            async move {
+                tokio::fs::symlink_metadata(src)
+                    .await
+                    .map_err(|e| map_io_error(e, src, "rename"))?;
+                if replaced {
+                    return Ok(());
+                }
+                match tokio::fs::symlink_metadata(dst).await {
+                    Ok(_) => Err(PyErr::new::<pyo3::exceptions::PyFileExistsError, _>(
+                        format!("Rename collision: {dst} already exists"),
+                    )),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                    Err(e) => Err(map_io_error(e, dst, "check destination")),
+                }
+            }
+        })
+        .collect();
+    join_limited(checks)
+        .await
+        .into_iter()
+        .collect::<PyResult<()>>()?;
+
+    // Sources that other pairs rename onto are moved out of the way first
+    let mut aside = Vec::new();
+    let mut renames = Vec::with_capacity(pairs.len());
+    for (src, dst) in &pairs {
+        let label = format!("{src} -> {dst}");
+        let from = if destinations.contains_key(Path::new(src)) {
+            let path = Path::new(src);
+            let temp = path
+                .with_file_name(format!(
+                    ".{}.{}.{}.rename",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    std::process::id(),
+                    NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+                ))
+                .to_string_lossy()
+                .into_owned();
+            aside.push((src.clone(), temp.clone(), label.clone()));
+            temp
+        } else {
+            src.clone()
+        };
+        renames.push((from, dst.clone(), label));
+    }
+    let (moved_aside, mut failure) = rename_all(aside).await;
+    let mut phases = vec![moved_aside];
+    if failure.is_none() {
+        let (renamed, renamed_failure) = rename_all(renames).await;
+        phases.push(renamed);
+        failure = renamed_failure;
+    }
+    let Some((error, label)) = failure else {
+        return Ok(());
+    };
+
+    let mut not_undone = Vec::new();
+    for done in phases.into_iter().rev() {
+        let undo = done
+            .into_iter()
+            .map(|(from, to)| {
+                let label = format!("{to} -> {from}");
+                (to, from, label)
+            })
+            .collect();
+        let (_, undo_failure) = rename_all(undo).await;
+        not_undone.extend(undo_failure.map(|(e, label)| format!("{label} ({e})")));
+    }
+    if not_undone.is_empty() {
+        Err(map_io_error(error, &label, "rename"))
+    } else {
+        Err(PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+            "Failed to rename {label}: {error}; could not undo {}",
+            not_undone.join(", ")
+        )))
+    }
+}
+
+/// Rename many files concurrently, all or nothing.
+///
+/// The batch is checked for collisions before anything is renamed, and
+/// rolled back if a rename fails (see `rename_batch`). Chains and swaps of
+/// names are allowed.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `pairs` - Vector of (src, dst) tuples to rename
+///
+/// # Returns
+///
+/// A coroutine that yields `None` on success.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if a path is invalid or two pairs share a source
+/// or destination, `PyFileExistsError` if a destination already exists,
+/// `PyFileNotFoundError` if a source does not exist, or `PyIOError` if a
+/// rename fails.
+#[pyfunction]
+fn rename_many_async(py: Python<'_>, pairs: Vec<(String, String)>) -> PyResult<Bound<'_, PyAny>> {
+    for (src, dst) in &pairs {
+        validate_path(src)?;
+        validate_path(dst)?;
+    }
+    let paths: Vec<Arc<str>> = pairs
+        .iter()
+        .flat_map(|(src, dst)| [Arc::from(src.as_str()), Arc::from(dst.as_str())])
+        .collect();
+    let op = OpContext::shared("rename_many", &paths);
+    let future = async move {
+        rename_batch(pairs).await?;
+        Ok(())
+    };
+    run_unit_op(py, op, future)
+}
+
+/// Piece of a `rename_pattern_async` template.
+enum TemplatePart {
+    Literal(String),
+    /// `{0}` for the whole name, `{1}`, `{2}`, ... for the wildcards
+    Capture(usize),
+    /// `{n}` or `{n:0W}`: position of the file among the matches, from 1
+    Counter {
+        width: usize,
+    },
+}
+
+/// Parse a `rename_pattern_async` template.
+fn parse_rename_template(template: &str) -> PyResult<Vec<TemplatePart>> {
+    let invalid = |detail: &str| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid template: {template}. {detail}"
+        ))
+    };
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let (field, rest) = chars
+                    .as_str()
+                    .split_once('}')
+                    .ok_or_else(|| invalid("Unclosed '{'"))?;
+                let part = match field.split_once(':') {
+                    None if field == "n" => TemplatePart::Counter { width: 0 },
+                    Some(("n", width)) if width.starts_with('0') => TemplatePart::Counter {
+                        width: width
+                            .parse()
+                            .map_err(|_| invalid(&format!("Bad width in {{{field}}}")))?,
+                    },
+                    None => TemplatePart::Capture(field.parse().map_err(|_| {
+                        invalid(&format!(
+                            "Unknown field {{{field}}}; use {{0}}, {{1}}, ..., {{n}} or {{n:0W}}"
+                        ))
+                    })?),
+                    Some(_) => return Err(invalid(&format!("Bad field {{{field}}}"))),
+                };
+                parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                parts.push(part);
+                chars = rest.chars();
+            }
+            '}' => return Err(invalid("Single '}' (use '}}')")),
+            c => literal.push(c),
+        }
+    }
+    parts.push(TemplatePart::Literal(literal));
+    Ok(parts)
+}
+
+/// Build the new name of the `position`-th match `name` with `captures`.
+fn render_rename_template(
+    template: &[TemplatePart],
+    name: &str,
+    captures: &[&[u8]],
+    position: usize,
+) -> PyResult<String> {
+    let mut rendered = String::new();
+    for part in template {
+        match part {
+            TemplatePart::Literal(text) => rendered.push_str(text),
+            TemplatePart::Capture(0) => rendered.push_str(name),
+            TemplatePart::Capture(index) => {
+                let capture = captures.get(index - 1).ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid template field {{{index}}}: the pattern has {} wildcards",
+                        captures.len()
+                    ))
+                })?;
+                rendered.push_str(&String::from_utf8_lossy(capture));
+            }
+            TemplatePart::Counter { width } => {
+                rendered.push_str(&format!("{position:0width$}"));
+            }
+        }
+    }
+    Ok(rendered)
+}
+
+/// Rename the files of a directory whose names match a pattern.
+///
+/// Every entry of `path` whose name matches the fnmatch-style `pattern` is
+/// renamed to `template` filled in with the match; the new names are
+/// relative to `path`. The renames run as one batch (see `rename_batch`):
+/// they are checked for collisions first and rolled back on failure.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `path` - Directory whose entries are renamed
+/// * `pattern` - Pattern the names must match, with `*`, `?` and `[...]`
+/// * `template` - New name: `{0}` is the old name, `{1}`, `{2}`, ... the
+///   text matched by each wildcard, `{n}` the position of the entry among
+///   the matches in name order (from 1, `{n:04}` pads it with zeros), and
+///   `{{`/`}}` are literal braces
+///
+/// # Returns
+///
+/// A coroutine that yields the list of (src, dst) pairs renamed, in name
+/// order.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if the path or template is invalid or two names
+/// map to the same new name, `PyFileExistsError` if a new name is taken,
+/// `PyFileNotFoundError` if the directory does not exist, or `PyIOError` if
+/// a rename fails.
+#[pyfunction]
+fn rename_pattern_async(
+    py: Python<'_>,
     path: String,
-    #[allow(dead_code)]
-    exclusive: bool,
+    pattern: String,
+    template: String,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&path)?;
+    let template = parse_rename_template(&template)?;
+    let op = OpContext::new("rename_pattern", [path.as_str()]);
+    let future = async move {
+        let dir = path.clone();
+        let mut names = run_blocking(move || {
+            std::fs::read_dir(&dir)?
+                .map(|entry| Ok(entry?.file_name()))
+                .collect::<std::io::Result<Vec<_>>>()
+        })
+        .await
+        .map_err(|e| map_io_error(e, &path, "list directory"))?;
+        names.sort();
+        let root = std::path::Path::new(&path);
+        let mut pairs = Vec::new();
+        // Names that are not valid UTF-8 cannot be matched by a str pattern
+        for name in names.iter().filter_map(|name| name.to_str()) {
+            if let Some(captures) = glob_captures(pattern.as_bytes(), name.as_bytes()) {
+                let new_name = render_rename_template(&template, name, &captures, pairs.len() + 1)?;
+                pairs.push((
+                    root.join(name).to_string_lossy().into_owned(),
+                    root.join(new_name).to_string_lossy().into_owned(),
+                ));
+            }
+        }
+        rename_batch(pairs.clone()).await?;
+        Ok(pairs)
+    };
+    run_op(py, op, future)
+}
+
+/// Extensions given to the files created by `generate_tree_async`.
+const GENERATED_EXTENSIONS: [&str; 6] = ["txt", "bin", "json", "log", "csv", "dat"];
+
+/// Make a pseudo-random lowercase name, made unique by the index `i`.
+fn generated_name(state: &mut u64, i: usize) -> String {
+    let len = 4 + (splitmix64(state) % 7) as usize;
+    let word: String = (0..len)
+        .map(|_| char::from(b'a' + (splitmix64(state) % 26) as u8))
+        .collect();
+    format!("{word}_{i}")
+}
+
+/// Generate a reproducible tree of synthetic directories and files asynchronously.
+///
+/// Builds fixtures for benchmarks and stress tests. Each directory is placed
+/// in the root or in a directory created before it, and each file in one of
+/// these, so the tree has a random shape. Names, sizes and contents all come
+/// from a SplitMix64 stream seeded with `name_seed`: the same arguments
+/// always produce the same tree. Directories are created first, then the
+/// files are written concurrently. All I/O operations execute outside the
+/// Python GIL.
+///
+/// # Arguments
+///
+/// * `py` - Python GIL token
+/// * `root` - Directory to generate into (created if missing); files that
+///   already exist at generated paths are replaced
+/// * `n_dirs` - Number of directories to create below `root`
+/// * `n_files` - Number of files to create
+/// * `size_distribution` - `(min, max)` file size in bytes, inclusive; sizes
+///   are drawn uniformly from this range
+/// * `name_seed` - Seed for the names, layout and contents
+///
+/// # Returns
+///
+/// A coroutine that yields the paths of the created files, in creation order.
+///
+/// # Errors
+///
+/// Returns `PyValueError` if the path is invalid or `min` exceeds `max`, or
+/// `PyIOError` if a directory or file cannot be created (e.g., disk full).
+#[pyfunction]
+#[pyo3(signature = (root, n_dirs, n_files, size_distribution = (0, 4096), name_seed = 0))]
+fn generate_tree_async(
+    py: Python<'_>,
+    root: String,
+    n_dirs: usize,
+    n_files: usize,
+    size_distribution: (u64, u64),
+    name_seed: u64,
+) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&root)?;
+    let (min_size, max_size) = size_distribution;
+    if min_size > max_size {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid size_distribution: ({min_size}, {max_size}). Must be (min, max) with min <= max"
+        )));
+    }
+    let op = OpContext::new("generate_tree", [root.as_str()]);
+    let future = async move {
+        // Plan the whole tree up front so the concurrent writes cannot
+        // change which stream values go where
+        let mut state = name_seed;
+        let mut dirs = vec![std::path::PathBuf::from(&root)];
+        for i in 0..n_dirs {
+            let parent = &dirs[(splitmix64(&mut state) % dirs.len() as u64) as usize];
+            let dir = parent.join(generated_name(&mut state, i));
+            dirs.push(dir);
+        }
+        let span = max_size - min_size;
+        let files: Vec<_> = (0..n_files)
+            .map(|i| {
+                let parent = &dirs[(splitmix64(&mut state) % dirs.len() as u64) as usize];
+                let ext = GENERATED_EXTENSIONS[i % GENERATED_EXTENSIONS.len()];
+                let path = parent.join(format!("{}.{ext}", generated_name(&mut state, i)));
+                let draw = splitmix64(&mut state);
+                let size = min_size + span.checked_add(1).map_or(draw, |n| draw % n);
+                (path, size, splitmix64(&mut state))
+            })
+            .collect();
+
+        // Parents come before their children, so one pass creates them all
+        let failed = run_blocking(move || {
+            for dir in dirs {
+                if let Err(e) = std::fs::create_dir_all(&dir) {
+                    return Ok(Some((e, dir)));
+                }
+            }
+            Ok(None)
+        })
+        .await
+        .map_err(|e| map_io_error(e, &root, "generate"))?;
+        if let Some((e, dir)) = failed {
+            return Err(map_io_error(e, &dir.to_string_lossy(), "generate"));
+        }
+
+        let writes: Vec<_> = files
+            .iter()
+            .map(|(path, size, seed)| {
+                let (path, size, mut seed) = (path.clone(), *size, *seed);
+                run_blocking(move || {
+                    let mut contents = vec![0u8; size as usize];
+                    fill_random(&mut seed, &mut contents);
+                    std::fs::write(&path, &contents)?;
+                    record_transfer(contents.len());
+                    Ok(())
+                })
+            })
+            .collect();
+        for ((path, _, _), result) in files.iter().zip(join_limited(writes).await) {
+            result.map_err(|e| map_io_error(e, &path.to_string_lossy(), "generate"))?;
+        }
+        Ok(files
+            .into_iter()
+            .map(|(path, _, _)| path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>())
+    };
+    run_op(py, op, future)
+}
+
+// Transactions
+//
+// A `Transaction` stages writes, renames and removals and applies them on
+// commit. Staged writes go to temporary files next to their targets right
+// away, so a commit only renames: files that are replaced or removed are
+// renamed to backup names instead of being deleted. Every step can thus be
+// undone by renaming back, which is how a failed commit is rolled back, and
+// the backups are deleted once all steps succeeded. With a journal the plan
+// is written to disk before the first rename and each finished step is
+// noted, so `recover_transaction()` can complete or roll back a commit that
+// was interrupted by a crash.
+
+/// First line of a transaction journal.
+const TRANSACTION_JOURNAL_HEADER: &str = "rapfiles transaction journal 1";
+
+/// Change staged in a `Transaction`.
+enum StagedChange {
+    /// `temp` holds the new contents of `path`
+    Write {
+        path: String,
+        temp: std::path::PathBuf,
+    },
+    Rename {
+        src: String,
+        dst: String,
+    },
+    Remove {
+        path: String,
+    },
+}
+
+/// Rename performed by a commit; `backup` marks renames that move a
+/// replaced or removed file aside.
+struct CommitStep {
+    from: std::path::PathBuf,
+    to: std::path::PathBuf,
+    backup: bool,
+}
+
+impl CommitStep {
+    fn label(&self) -> String {
+        format!("{} -> {}", self.from.display(), self.to.display())
+    }
+}
+
+/// Fresh hidden path next to `path` for a temporary file or backup.
+fn transaction_sibling(path: &std::path::Path, kind: &str) -> std::path::PathBuf {
+    path.with_file_name(format!(
+        ".{}.{}.{}.{kind}",
+        path.file_name().unwrap_or_default().to_string_lossy(),
+        std::process::id(),
+        NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Whether `path` exists once the steps planned so far have run.
+async fn planned_exists(
+    known: &mut std::collections::HashMap<std::path::PathBuf, bool>,
+    path: &std::path::Path,
+) -> PyResult<bool> {
+    if let Some(&exists) = known.get(path) {
+        return Ok(exists);
+    }
+    let exists = match tokio::fs::symlink_metadata(path).await {
+        Ok(_) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => return Err(map_io_error(e, &path.to_string_lossy(), "check")),
+    };
+    known.insert(path.to_path_buf(), exists);
+    Ok(exists)
+}
+
+/// Turn staged changes into the renames of a commit.
+///
+/// Each change is checked against the files as the earlier steps will
+/// leave them, so nothing is renamed if a source is missing.
+///
+/// # Errors
+///
+/// Returns `PyFileNotFoundError` if a renamed or removed path will not
+/// exist, or `PyIOError` if a path cannot be checked.
+async fn plan_commit(changes: &[StagedChange]) -> PyResult<Vec<CommitStep>> {
+    use std::path::Path;
+
+    let mut known = std::collections::HashMap::new();
+    let mut steps = Vec::new();
+    let mut add = |known: &mut std::collections::HashMap<_, _>, from: &Path, to: &Path, backup| {
+        known.insert(from.to_path_buf(), false);
+        known.insert(to.to_path_buf(), true);
+        steps.push(CommitStep {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+            backup,
+        });
+    };
+    let missing = |path: &str, verb: &str| {
+        PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!(
+            "Failed to {verb} {path}: No such file or directory"
+        ))
+    };
+    for change in changes {
+        let (source, target) = match change {
+            StagedChange::Write { path, temp } => (Some(temp.as_path()), Path::new(path)),
+            StagedChange::Rename { src, dst } => {
+                if !planned_exists(&mut known, Path::new(src)).await? {
+                    return Err(missing(src, "rename"));
+                }
+                if Path::new(src) == Path::new(dst) {
+                    continue;
+                }
+                (Some(Path::new(src)), Path::new(dst))
+            }
+            StagedChange::Remove { path } => {
+                if !planned_exists(&mut known, Path::new(path)).await? {
+                    return Err(missing(path, "remove"));
+                }
+                (None, Path::new(path))
+            }
+        };
+        if planned_exists(&mut known, target).await? {
+            add(
+                &mut known,
+                target,
+                &transaction_sibling(target, "bak"),
+                true,
+            );
+        }
+        if let Some(source) = source {
+            add(&mut known, source, target, false);
+        }
+    }
+    Ok(steps)
+}
+
+/// Journal of a commit in progress.
+struct TransactionJournal {
+    path: String,
+    file: tokio::fs::File,
+}
+
+impl TransactionJournal {
+    /// Create the journal at `path` with the temporary files and steps of a
+    /// commit, and flush it to disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyFileExistsError` if a journal is already there, which
+    /// must be recovered first, or `PyIOError` if it cannot be written.
+    async fn create(
+        path: &str,
+        temps: &[&std::path::Path],
+        steps: &[CommitStep],
+    ) -> PyResult<Self> {
+        let mut contents = format!("{TRANSACTION_JOURNAL_HEADER}\n");
+        for temp in temps {
+            contents.push_str(&format!(
+                "temp {}\n",
+                encode_checkpoint_path(temp.as_os_str())
+            ));
+        }
+        for step in steps {
+            contents.push_str(&format!(
+                "{} {} {}\n",
+                if step.backup { "backup" } else { "rename" },
+                encode_checkpoint_path(step.from.as_os_str()),
+                encode_checkpoint_path(step.to.as_os_str())
+            ));
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::AlreadyExists => {
+                    PyErr::new::<pyo3::exceptions::PyFileExistsError, _>(format!(
+                        "Transaction journal {path} already exists; recover it with recover_transaction() first"
+                    ))
+                }
+                _ => map_io_error(e, path, "create journal"),
+            })?;
+        let written = async {
+            file.write_all(contents.as_bytes()).await?;
+            file.sync_all().await
+        };
+        if let Err(e) = written.await {
+            let _ = tokio::fs::remove_file(path).await;
+            return Err(map_io_error(e, path, "write journal"));
+        }
+        Ok(TransactionJournal {
+            path: path.to_string(),
+            file,
+        })
+    }
+
+    /// Append `line`, flushing it to disk if `sync`.
+    async fn note(&mut self, line: &str, sync: bool) -> std::io::Result<()> {
+        self.file.write_all(line.as_bytes()).await?;
+        if sync {
+            self.file.sync_all().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Rename `steps` in order, noting each in the journal, and finally note
+/// the commit as complete.
+///
+/// If a step fails, the steps done are renamed back, last first, and the
+/// journal is removed.
+///
+/// # Errors
+///
+/// Returns the error of the failed step, or `PyIOError` naming the steps
+/// that could not be undone, if any (the journal is then kept).
+async fn apply_commit(
+    steps: &[CommitStep],
+    journal: &mut Option<TransactionJournal>,
+) -> PyResult<()> {
+    // Steps done, error, and the path and verb it is reported with
+    let mut failure = None;
+    for (index, step) in steps.iter().enumerate() {
+        if let Err(e) = tokio::fs::rename(&step.from, &step.to).await {
+            failure = Some((index, e, step.label(), "rename"));
+            break;
+        }
+        if let Some(journal) = journal.as_mut() {
+            if let Err(e) = journal.note(&format!("done {index}\n"), false).await {
+                failure = Some((index + 1, e, journal.path.clone(), "write journal"));
+                break;
+            }
+        }
+    }
+    if let (None, Some(journal)) = (&failure, journal.as_mut()) {
+        // From here on recovery completes the commit
+        if let Err(e) = journal.note("committed\n", true).await {
+            failure = Some((steps.len(), e, journal.path.clone(), "write journal"));
+        }
+    }
+    let Some((done, error, label, verb)) = failure else {
+        return Ok(());
+    };
+
+    let mut not_undone = Vec::new();
+    for step in steps[..done].iter().rev() {
+        if let Err(e) = tokio::fs::rename(&step.to, &step.from).await {
+            not_undone.push(format!(
+                "{} -> {} ({e})",
+                step.to.display(),
+                step.from.display()
+            ));
+        }
+    }
+    if !not_undone.is_empty() {
+        return Err(PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+            "Failed to {verb} {label}: {error}; could not undo {}",
+            not_undone.join(", ")
+        )));
+    }
+    if let Some(journal) = journal.take() {
+        let _ = tokio::fs::remove_file(&journal.path).await;
+    }
+    Err(map_io_error(error, &label, verb))
+}
+
+/// Remove a file, or a directory with its contents, ignoring missing paths.
+async fn remove_any(path: &std::path::Path) -> std::io::Result<()> {
+    let result = match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) if metadata.is_dir() => tokio::fs::remove_dir_all(path).await,
+        Ok(_) => tokio::fs::remove_file(path).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+/// Remove all `paths` concurrently; true if every removal succeeded.
+async fn remove_all_quietly(paths: Vec<std::path::PathBuf>) -> bool {
+    let removals: Vec<_> = paths
+        .into_iter()
+        .map(|path| async move { remove_any(&path).await })
+        .collect();
+    join_limited(removals).await.iter().all(Result::is_ok)
+}
+
+/// Where a `Transaction` stands.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TransactionStatus {
+    Active,
+    Committed,
+    RolledBack,
+}
+
+impl TransactionStatus {
+    fn name(self) -> &'static str {
+        match self {
+            TransactionStatus::Active => "active",
+            TransactionStatus::Committed => "committed",
+            TransactionStatus::RolledBack => "rolled back",
+        }
+    }
+}
+
+struct TransactionState {
+    changes: Vec<StagedChange>,
+    status: TransactionStatus,
+}
+
+impl TransactionState {
+    /// Check that changes can still be staged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` once the transaction is committed or rolled back.
+    fn check_active(&self) -> PyResult<()> {
+        match self.status {
+            TransactionStatus::Active => Ok(()),
+            status => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Transaction is already {}",
+                status.name()
+            ))),
+        }
+    }
+}
+
+/// Writes, renames and removals applied together on commit.
+///
+/// Created by `transaction()`. Changes are applied in the order they were
+/// staged, as renames that are undone if one of them fails. As an async
+/// context manager the transaction commits when the block exits normally
+/// and rolls back when it raises. A transaction garbage collected while
+/// still active is rolled back with a `ResourceWarning`.
+#[pyclass]
+struct Transaction {
+    journal: Option<String>,
+    state: Arc<std::sync::Mutex<TransactionState>>,
+}
+
+impl Transaction {
+    fn lock(&self) -> std::sync::MutexGuard<'_, TransactionState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        let changes = {
+            let mut state = self.lock();
+            if state.status != TransactionStatus::Active {
+                return;
+            }
+            // Writes still staging remove their own temporary files
+            state.status = TransactionStatus::RolledBack;
+            std::mem::take(&mut state.changes)
+        };
+        if changes.is_empty() {
+            return;
+        }
+        for change in &changes {
+            if let StagedChange::Write { temp, .. } = change {
+                let _ = std::fs::remove_file(temp);
+            }
+        }
+        // Garbage collected without commit() or rollback(): warn like an unclosed file
+        let message = format!(
+            "unfinished transaction discarded {} staged change(s) without commit() or rollback()",
+            changes.len()
+        );
+        let Ok(message) = std::ffi::CString::new(message) else {
+            return;
+        };
+        Python::try_attach(|py| {
+            let category = py.get_type::<pyo3::exceptions::PyResourceWarning>();
+            if let Err(err) = PyErr::warn(py, &category, &message, 1) {
+                err.write_unraisable(py, None);
+            }
+        });
+    }
 }
 
 #[pymethods]
-impl FileLock {
-    /// Default constructor - use lock_file() instead.
+impl Transaction {
+    /// Default constructor - use transaction() instead.
     #[new]
     fn new() -> PyResult<Self> {
         Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-            "FileLock cannot be instantiated directly. Use rapfiles.lock_file() instead.",
+            "Transaction cannot be instantiated directly. Use rapfiles.transaction() instead.",
         ))
     }
 
-    /// Release the file lock.
+    /// Journal path given to `transaction()`, if any.
+    #[getter]
+    fn journal(&self) -> Option<&str> {
+        self.journal.as_deref()
+    }
+
+    /// "active", "committed" or "rolled back".
+    #[getter]
+    fn status(&self) -> &'static str {
+        self.lock().status.name()
+    }
+
+    /// Number of changes staged and not yet committed.
+    #[getter]
+    fn pending(&self) -> usize {
+        self.lock().changes.len()
+    }
+
+    /// Stage new contents for a file.
+    ///
+    /// The data is written to a temporary file next to `path` right away
+    /// and flushed to disk; `path` itself is replaced on commit.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - File to write
+    /// * `data` - Contents as `bytes` or `str` (encoded as UTF-8)
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` once the change is staged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the path is invalid or the transaction is
+    /// no longer active, `PyTypeError` if `data` is neither bytes nor str,
+    /// or `PyIOError` if the temporary file cannot be written.
+    fn write<'a>(
+        &self,
+        py: Python<'a>,
+        path: String,
+        data: &Bound<'_, PyAny>,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        validate_path(&path)?;
+        self.lock().check_active()?;
+        let mut contents = Vec::new();
+        if !extend_with_data(&mut contents, data)? {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "data must be bytes or str",
+            ));
+        }
+        let state = Arc::clone(&self.state);
+
+        let op = OpContext::new("transaction_write", [path.as_str()]);
+        let future = async move {
+            let temp = transaction_sibling(std::path::Path::new(&path), "txn");
+            record_transfer(contents.len());
+            let written = async {
+                let mut file = tokio::fs::File::create(&temp).await?;
+                file.write_all(&contents).await?;
+                file.sync_all().await
+            };
+            if let Err(e) = written.await {
+                let _ = tokio::fs::remove_file(&temp).await;
+                return Err(map_io_error(e, &path, "stage write to"));
+            }
+            let staged = {
+                let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                state.check_active().map(|()| {
+                    state.changes.push(StagedChange::Write {
+                        path,
+                        temp: temp.clone(),
+                    })
+                })
+            };
+            if let Err(e) = staged {
+                // Committed or rolled back while the data was written
+                let _ = tokio::fs::remove_file(&temp).await;
+                return Err(e);
+            }
+            Ok(())
+        };
+        run_unit_op(py, op, future)
+    }
+
+    /// Stage renaming `src` to `dst`, replacing `dst` if it exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if a path is invalid or the transaction is no
+    /// longer active.
+    fn rename(&self, src: String, dst: String) -> PyResult<()> {
+        validate_path(&src)?;
+        validate_path(&dst)?;
+        let mut state = self.lock();
+        state.check_active()?;
+        state.changes.push(StagedChange::Rename { src, dst });
+        Ok(())
+    }
+
+    /// Stage removing a file or directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the path is invalid or the transaction is
+    /// no longer active.
+    fn remove(&self, path: String) -> PyResult<()> {
+        validate_path(&path)?;
+        let mut state = self.lock();
+        state.check_active()?;
+        state.changes.push(StagedChange::Remove { path });
+        Ok(())
+    }
+
+    /// Apply the staged changes.
+    ///
+    /// All changes are checked first, then applied in order as renames.
+    /// If one fails, the renames done are undone and the staged temporary
+    /// files removed, leaving the files as they were. Replaced and removed
+    /// files are deleted only after every change was applied.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` once the changes are applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the transaction is no longer active,
+    /// `PyFileNotFoundError` if a renamed or removed path does not exist,
+    /// `PyFileExistsError` if the journal of an earlier commit was not
+    /// recovered, or `PyIOError` if a change fails (naming the changes that
+    /// could not be undone, if any). The transaction is rolled back in
+    /// every case.
+    fn commit<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let changes = {
+            let mut state = self.lock();
+            state.check_active()?;
+            state.status = TransactionStatus::Committed;
+            std::mem::take(&mut state.changes)
+        };
+        let state = Arc::clone(&self.state);
+        let journal_path = self.journal.clone();
+        let mut paths = Vec::new();
+        for change in &changes {
+            match change {
+                StagedChange::Write { path, .. } | StagedChange::Remove { path } => {
+                    paths.push(path.as_str())
+                }
+                StagedChange::Rename { src, dst } => paths.extend([src.as_str(), dst.as_str()]),
+            }
+        }
+        paths.extend(self.journal.as_deref());
+
+        let op = OpContext::new("commit_transaction", paths);
+        let future = async move {
+            let temps: Vec<_> = changes
+                .iter()
+                .filter_map(|change| match change {
+                    StagedChange::Write { temp, .. } => Some(temp.clone()),
+                    _ => None,
+                })
+                .collect();
+            let rolled_back = |error: PyErr| async {
+                remove_all_quietly(temps.clone()).await;
+                state.lock().unwrap_or_else(|e| e.into_inner()).status =
+                    TransactionStatus::RolledBack;
+                Err(error)
+            };
+            let steps = match plan_commit(&changes).await {
+                Ok(steps) => steps,
+                Err(e) => return rolled_back(e).await,
+            };
+            let mut journal = match &journal_path {
+                Some(path) => {
+                    let temp_paths: Vec<_> = temps.iter().map(|temp| temp.as_path()).collect();
+                    match TransactionJournal::create(path, &temp_paths, &steps).await {
+                        Ok(journal) => Some(journal),
+                        Err(e) => return rolled_back(e).await,
+                    }
+                }
+                None => None,
+            };
+            if let Err(e) = apply_commit(&steps, &mut journal).await {
+                return rolled_back(e).await;
+            }
+            let backups = steps
+                .into_iter()
+                .filter(|step| step.backup)
+                .map(|step| step.to)
+                .collect();
+            // Left for recover_transaction() to retry if a backup remains
+            if remove_all_quietly(backups).await {
+                if let Some(journal) = journal {
+                    let _ = tokio::fs::remove_file(&journal.path).await;
+                }
+            }
+            Ok(())
+        };
+        run_unit_op(py, op, future)
+    }
+
+    /// Discard the staged changes and remove their temporary files.
     ///
-    /// Releases the advisory file lock. The lock is also automatically
-    /// released when the object is dropped.
+    /// Rolling back again has no effect.
     ///
     /// # Returns
     ///
-    /// A coroutine that yields `None` on success.
+    /// A coroutine that yields `None` once the temporary files are removed.
     ///
     /// # Errors
     ///
-    /// Returns `PyIOError` if the lock cannot be released.
-    fn release<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
-        let file = Arc::clone(&self.file);
-        let path = self.path.clone();
+    /// Returns `PyValueError` if the transaction was committed.
+    fn rollback<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let changes = {
+            let mut state = self.lock();
+            if state.status == TransactionStatus::Committed {
+                state.check_active()?;
+            }
+            state.status = TransactionStatus::RolledBack;
+            std::mem::take(&mut state.changes)
+        };
+        let mut paths = Vec::new();
+        let mut temps = Vec::new();
+        for change in changes {
+            if let StagedChange::Write { path, temp } = change {
+                paths.push(path);
+                temps.push(temp);
+            }
+        }
 
+        let op = OpContext::new("rollback_transaction", paths.iter().map(String::as_str));
         let future = async move {
-            // Unlock the file (blocking operation)
-            tokio::task::spawn_blocking(move || {
-                use fs2::FileExt;
-                match FileExt::unlock(&*file) {
-                    Ok(()) => Ok(()),
-                    Err(e) => {
-                        // On Windows, error code 158 (ERROR_NOT_LOCKED) means already unlocked
-                        // Make release() idempotent by ignoring this error
-                        #[cfg(windows)]
-                        if e.raw_os_error() == Some(158) {
-                            return Ok(());
-                        }
-                        Err(PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                            "Failed to release lock on {path}: {e}"
-                        )))
-                    }
-                }
-            })
-            .await
-            .map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to release lock: {e}"))
-            })?
+            remove_all_quietly(temps).await;
+            Ok(())
         };
-        future_into_py(py, future)
+        run_unit_op(py, op, future)
     }
 
-    /// Async context manager entry.
-    fn __aenter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
-        // Return self directly - Python's async context manager will handle it
-        slf
+    /// Async context manager entry; yields the transaction itself.
+    fn __aenter__<'a>(slf: PyRef<'a, Self>) -> PyResult<Bound<'a, PyAny>> {
+        let py = slf.py();
+        let transaction: Py<Self> = slf.into();
+        future_into_py(py, async move { Ok(transaction) })
     }
 
-    /// Async context manager exit.
-    fn __aexit__(
+    /// Async context manager exit; commits if the block succeeded and rolls
+    /// back if it raised, unless the transaction was already finished.
+    fn __aexit__<'a>(
         &self,
-        _exc_type: Option<&Bound<'_, PyAny>>,
+        py: Python<'a>,
+        exc_type: Option<&Bound<'_, PyAny>>,
         _exc_val: Option<&Bound<'_, PyAny>>,
         _exc_tb: Option<&Bound<'_, PyAny>>,
-    ) -> PyResult<Py<PyAny>> {
-        // Release lock on exit
-        Python::attach(|py| {
-            let release_future = self.release(py)?;
-            // Release is already a coroutine, return it wrapped
-            Ok(release_future.unbind())
-        })
+    ) -> PyResult<Bound<'a, PyAny>> {
+        if self.lock().status != TransactionStatus::Active {
+            let none = py.None();
+            return future_into_py(py, async move { Ok(none) });
+        }
+        if exc_type.is_some_and(|exc_type| !exc_type.is_none()) {
+            self.rollback(py)
+        } else {
+            self.commit(py)
+        }
     }
 }
 
-/// Lock a file asynchronously.
+/// Start a transaction.
 ///
-/// Acquires an advisory file lock on the specified file. The lock can be
-/// shared (read) or exclusive (write). The file is created if it doesn't
-/// exist. All I/O operations execute outside the Python GIL using native
-/// Tokio, ensuring true async behavior.
+/// # Arguments
+///
+/// * `journal` - Path of a journal file written during the commit, for
+///   `recover_transaction_async` after a crash
+///
+/// # Errors
+///
+/// Returns `PyValueError` if the journal path is invalid.
+#[pyfunction]
+#[pyo3(signature = (journal = None))]
+fn transaction(journal: Option<String>) -> PyResult<Transaction> {
+    if let Some(journal) = &journal {
+        validate_path(journal)?;
+    }
+    Ok(Transaction {
+        journal,
+        state: Arc::new(std::sync::Mutex::new(TransactionState {
+            changes: Vec::new(),
+            status: TransactionStatus::Active,
+        })),
+    })
+}
+
+/// Complete or roll back a commit interrupted by a crash.
+///
+/// If the journal records the commit as complete, the backups left behind
+/// are removed. Otherwise the steps done are renamed back, last first, and
+/// the staged temporary files are removed. The journal is removed
+/// afterwards.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `path` - Path to the file to lock
-/// * `exclusive` - If true, acquire exclusive (write) lock; if false, acquire shared (read) lock
+/// * `journal` - Journal path given to `transaction()`
 ///
 /// # Returns
 ///
-/// A coroutine that yields a `FileLock` object that can be used as an async context manager.
+/// A coroutine that yields True if the commit was completed, False if it
+/// was rolled back, or None if there is no journal.
 ///
 /// # Errors
 ///
-/// Returns `PyIOError` if the file cannot be locked, or `PyValueError` if the path is invalid.
+/// Returns `PyValueError` if the path is invalid or the file is not a
+/// transaction journal, or `PyIOError` if a file cannot be restored or
+/// removed (the journal is then kept, so recovery can be retried).
 #[pyfunction]
-fn lock_file_async(py: Python<'_>, path: String, exclusive: bool) -> PyResult<Bound<'_, PyAny>> {
-    validate_path(&path)?;
+fn recover_transaction_async(py: Python<'_>, journal: String) -> PyResult<Bound<'_, PyAny>> {
+    validate_path(&journal)?;
+    let op = OpContext::new("recover_transaction", [journal.as_str()]);
     let future = async move {
-        let path_clone = path.clone();
+        let contents = match tokio::fs::read(&journal).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(map_io_error(e, &journal, "read journal")),
+        };
+        let invalid = |detail: &str| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid transaction journal {journal}: {detail}"
+            ))
+        };
+        let text = std::str::from_utf8(&contents).map_err(|_| invalid("not UTF-8"))?;
+        let body = text
+            .strip_prefix(TRANSACTION_JOURNAL_HEADER)
+            .and_then(|rest| rest.strip_prefix('\n'))
+            .ok_or_else(|| invalid("missing header"))?;
+        let mut temps = Vec::new();
+        let mut steps = Vec::new();
+        let mut done = 0;
+        let mut committed = false;
+        // The last piece has no newline yet: it was cut short or is empty
+        let mut lines: Vec<&str> = body.split('\n').collect();
+        lines.pop();
+        for line in lines {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let path = |field: &str| {
+                decode_checkpoint_path(field).ok_or_else(|| invalid(&format!("bad entry {line:?}")))
+            };
+            match fields[..] {
+                ["temp", temp] => temps.push(path(temp)?),
+                [kind @ ("rename" | "backup"), from, to] => steps.push(CommitStep {
+                    from: path(from)?,
+                    to: path(to)?,
+                    backup: kind == "backup",
+                }),
+                ["done", index] => {
+                    let index: usize = index
+                        .parse()
+                        .map_err(|_| invalid(&format!("bad entry {line:?}")))?;
+                    done = done.max(index + 1);
+                }
+                ["committed"] => committed = true,
+                _ => return Err(invalid(&format!("bad entry {line:?}"))),
+            }
+        }
 
-        // Open or create the file
-        let file = tokio::task::spawn_blocking({
-            let path = path_clone.clone();
-            let path_clone_for_error = path_clone.clone();
-            move || {
-                std::fs::OpenOptions::new()
-                    .create(true)
-                    .truncate(false)
-                    .read(true)
-                    .write(true)
-                    .open(&path)
-                    .map_err(|e| map_io_error(e, &path_clone_for_error, "open file for locking"))
+        let mut failures = Vec::new();
+        if committed {
+            let backups = steps.iter().filter(|step| step.backup).map(|step| &step.to);
+            for path in backups.chain(&temps) {
+                if let Err(e) = remove_any(path).await {
+                    failures.push(format!("remove {} ({e})", path.display()));
+                }
+            }
+        } else {
+            // The step after the last one noted may have been done before
+            // the crash; its source is then gone
+            if let Some(step) = steps.get(done) {
+                let source_gone = !tokio::fs::try_exists(&step.from).await.unwrap_or(true);
+                if source_gone && tokio::fs::try_exists(&step.to).await.unwrap_or(false) {
+                    done += 1;
+                }
+            }
+            // A step whose destination is missing was already undone
+            for step in steps[..done.min(steps.len())].iter().rev() {
+                if !tokio::fs::try_exists(&step.to).await.unwrap_or(true) {
+                    continue;
+                }
+                if let Err(e) = tokio::fs::rename(&step.to, &step.from).await {
+                    failures.push(format!(
+                        "{} -> {} ({e})",
+                        step.to.display(),
+                        step.from.display()
+                    ));
+                }
+            }
+            for temp in &temps {
+                if let Err(e) = remove_any(temp).await {
+                    failures.push(format!("remove {} ({e})", temp.display()));
+                }
+            }
+        }
+        if !failures.is_empty() {
+            return Err(PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to recover transaction {journal}: could not {}",
+                failures.join(", ")
+            )));
+        }
+        tokio::fs::remove_file(&journal)
+            .await
+            .map_err(|e| map_io_error(e, &journal, "remove journal"))?;
+        Ok(Some(committed))
+    };
+    run_op(py, op, future)
+}
+
+// Disk cache
+//
+// A `Cache` keeps values in files under its root, one file per key. Keys are
+// hashed with 64-bit FNV-1a, which is stable across processes and versions,
+// and the files are spread over 256 shard directories named after the first
+// byte of the hash, so no directory grows huge. Each file starts with a
+// header holding the expiry and the key, so a hash collision reads as a
+// miss. Entries are written to temporary files and renamed into place, so a
+// reader in another process sees a whole entry or none. The modification
+// time of an entry records its last use: hits move it forward, and once the
+// cache grows past `max_bytes` a background task removes expired entries and
+// then the least recently used ones until it is back under 90% of the limit.
+
+const CACHE_MAGIC: &[u8; 4] = b"RFC1";
+/// Magic, expiry (f64, NaN for none) and key length (u32)
+const CACHE_HEADER_LEN: usize = 16;
+/// Fraction of `max_bytes` background eviction shrinks the cache to
+const CACHE_LOW_WATER: f64 = 0.9;
+/// Age after which eviction removes temporary files left by crashed writers
+const CACHE_STALE_TEMP: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// 64-bit FNV-1a hash of `data`.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// File holding the entry for `key`: `<root>/<first byte>/<rest of hash>`.
+fn cache_entry_path(root: &std::path::Path, key: &[u8]) -> std::path::PathBuf {
+    let hash = format!("{:016x}", fnv1a(key));
+    root.join(&hash[..2]).join(&hash[2..])
+}
+
+/// Expiry (Unix time) and key length from the start of an entry file, or
+/// `None` if it is not a cache entry.
+fn parse_cache_header(header: &[u8]) -> Option<(Option<f64>, usize)> {
+    if header.len() < CACHE_HEADER_LEN || &header[..4] != CACHE_MAGIC {
+        return None;
+    }
+    let expires = f64::from_le_bytes(header[4..12].try_into().ok()?);
+    let key_len = u32::from_le_bytes(header[12..16].try_into().ok()?) as usize;
+    Some(((!expires.is_nan()).then_some(expires), key_len))
+}
+
+/// Expiry and key of the entry file at `path`, or `None` if it is not a
+/// cache entry.
+fn read_cache_header(path: &std::path::Path) -> std::io::Result<Option<(Option<f64>, Vec<u8>)>> {
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)?;
+    let mut header = [0u8; CACHE_HEADER_LEN];
+    if file.read_exact(&mut header).is_err() {
+        return Ok(None);
+    }
+    let Some((expires, key_len)) = parse_cache_header(&header) else {
+        return Ok(None);
+    };
+    let mut key = vec![0u8; key_len];
+    if file.read_exact(&mut key).is_err() {
+        return Ok(None);
+    }
+    Ok(Some((expires, key)))
+}
+
+/// Value stored for `key` in the entry file at `path`, or `None` on a miss.
+///
+/// An expired entry is removed and a hit is marked as used for eviction,
+/// unless read-only mode protects the entry.
+fn read_cache_entry(path: &std::path::Path, key: &[u8]) -> std::io::Result<Option<Vec<u8>>> {
+    let mut data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let Some((expires, key_len)) = parse_cache_header(&data) else {
+        return Ok(None);
+    };
+    let body = CACHE_HEADER_LEN + key_len;
+    if data.get(CACHE_HEADER_LEN..body) != Some(key) {
+        return Ok(None); // Truncated, or another key with the same hash
+    }
+    let writable = is_writable(&path.to_string_lossy());
+    if expires.is_some_and(|expires| expires <= unix_now()) {
+        if writable {
+            let _ = std::fs::remove_file(path);
+        }
+        return Ok(None);
+    }
+    // Best effort: a hit that cannot be marked is merely evicted earlier
+    if writable {
+        if let Ok(file) = std::fs::OpenOptions::new().write(true).open(path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+    }
+    Ok(Some(data.split_off(body)))
+}
+
+/// Write the entry for `key` to `path` through a temporary file.
+///
+/// Returns the size of the entry it replaced (0 if there was none).
+fn write_cache_entry(
+    path: &std::path::Path,
+    key: &[u8],
+    value: &[u8],
+    expires: Option<f64>,
+) -> std::io::Result<u64> {
+    let mut data = Vec::with_capacity(CACHE_HEADER_LEN + key.len() + value.len());
+    data.extend_from_slice(CACHE_MAGIC);
+    data.extend_from_slice(&expires.unwrap_or(f64::NAN).to_le_bytes());
+    data.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    data.extend_from_slice(key);
+    data.extend_from_slice(value);
+    let temp = transaction_sibling(path, "tmp");
+    if let Err(e) = std::fs::write(&temp, &data) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            return Err(e);
+        }
+        // First entry of this shard
+        std::fs::create_dir_all(temp.parent().unwrap_or(path))?;
+        std::fs::write(&temp, &data)?;
+    }
+    let replaced = std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+    if let Err(e) = std::fs::rename(&temp, path) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e);
+    }
+    Ok(replaced)
+}
+
+/// Whether `name` is one of the 256 shard directories of a cache root.
+fn is_cache_shard(name: &std::ffi::OsStr) -> bool {
+    name.to_str()
+        .is_some_and(|name| name.len() == 2 && name.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Remove expired entries, then the least recently used ones until the
+/// entries take at most `target` bytes.
+///
+/// Returns the number of entries removed and the bytes left.
+fn evict_cache(root: &std::path::Path, target: u64) -> std::io::Result<(usize, u64)> {
+    let skip_missing = |e: std::io::Error| match e.kind() {
+        // Removed by a concurrent `clear()` or eviction
+        std::io::ErrorKind::NotFound => Ok(()),
+        _ => Err(e),
+    };
+    let shards = match std::fs::read_dir(root) {
+        Ok(shards) => shards,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+        Err(e) => return Err(e),
+    };
+    let now = SystemTime::now();
+    let mut entries = Vec::new();
+    let (mut removed, mut total) = (0, 0);
+    for shard in shards {
+        let shard = shard?;
+        if !is_cache_shard(&shard.file_name()) || !shard.file_type()?.is_dir() {
+            continue;
+        }
+        let files = match std::fs::read_dir(shard.path()) {
+            Ok(files) => files,
+            Err(e) => {
+                skip_missing(e)?;
+                continue;
+            }
+        };
+        for file in files {
+            let file = file?;
+            let path = file.path();
+            let metadata = match file.metadata() {
+                Ok(metadata) if metadata.is_file() => metadata,
+                Ok(_) => continue,
+                Err(e) => {
+                    skip_missing(e)?;
+                    continue;
+                }
+            };
+            let used = metadata.modified()?;
+            if file.file_name().to_string_lossy().starts_with('.') {
+                // Temporary file, in use unless its writer crashed
+                if now.duration_since(used).unwrap_or_default() > CACHE_STALE_TEMP {
+                    std::fs::remove_file(&path).or_else(skip_missing)?;
+                }
+                continue;
+            }
+            let expired = match read_cache_header(&path) {
+                Ok(header) => header
+                    .and_then(|(expires, _)| expires)
+                    .is_some_and(|expires| expires <= system_time_to_timestamp(now)),
+                Err(e) => {
+                    skip_missing(e)?;
+                    continue;
+                }
+            };
+            if expired {
+                std::fs::remove_file(&path).or_else(skip_missing)?;
+                removed += 1;
+                continue;
             }
+            total += metadata.len();
+            entries.push((used, metadata.len(), path));
+        }
+    }
+    entries.sort_unstable_by_key(|&(used, ..)| used);
+    for (_, size, path) in entries {
+        if total <= target {
+            break;
+        }
+        std::fs::remove_file(&path).or_else(skip_missing)?;
+        total -= size;
+        removed += 1;
+    }
+    Ok((removed, total))
+}
+
+/// Size bookkeeping shared between a `Cache` and its eviction tasks.
+#[derive(Default)]
+struct CacheState {
+    /// Bytes of entries as of the last eviction plus those written since;
+    /// `None` until the cache has been scanned
+    size: Option<u64>,
+    /// Whether a background eviction is scheduled or running
+    evicting: bool,
+}
+
+/// Remove entries in the background until the cache is under its low-water
+/// mark, then record the size found.
+async fn evict_in_background(
+    root: std::path::PathBuf,
+    max_bytes: u64,
+    state: Arc<std::sync::Mutex<CacheState>>,
+    eviction: Arc<Mutex<()>>,
+) {
+    let _running = eviction.lock().await;
+    let target = (max_bytes as f64 * CACHE_LOW_WATER) as u64;
+    let evicted = run_blocking(move || evict_cache(&root, target)).await;
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    // After a failure the next write scans again
+    state.size = evicted.ok().map(|(_, size)| size);
+    state.evicting = false;
+}
+
+/// Validate a time-to-live in seconds.
+fn cache_ttl(name: &str, ttl: Option<f64>) -> PyResult<Option<f64>> {
+    match ttl {
+        Some(ttl) if !(ttl > 0.0 && ttl.is_finite()) => {
+            Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid {name}: {ttl}. Must be a positive number of seconds"
+            )))
+        }
+        ttl => Ok(ttl),
+    }
+}
+
+/// Cache key as bytes; `str` keys are encoded as UTF-8.
+fn cache_key(key: &Bound<'_, PyAny>) -> PyResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    if !extend_with_data(&mut bytes, key)? {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "key must be bytes or str",
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Disk cache of byte values with expiry and a size limit.
+///
+/// Entries are files under `root`, so the cache survives restarts and can be
+/// shared by several processes. Writes are atomic, and when the cache grows
+/// past `max_bytes` the least recently used entries are evicted in the
+/// background.
+///
+/// # Example
+///
+/// ```python
+/// cache = rapfiles.Cache("/var/cache/app", max_bytes=256 * 2**20, default_ttl=3600)
+/// page = await cache.get(url)
+/// if page is None:
+///     page = await fetch(url)
+///     await cache.set(url, page)
+/// ```
+#[pyclass]
+struct Cache {
+    root: String,
+    max_bytes: u64,
+    default_ttl: Option<f64>,
+    state: Arc<std::sync::Mutex<CacheState>>,
+    /// Held while entries are evicted or cleared
+    eviction: Arc<Mutex<()>>,
+}
+
+impl Cache {
+    /// Run `f` on the entry file for `key` in a blocking job as operation `name`.
+    ///
+    /// `f` returns its result and the number of value bytes it transferred.
+    fn entry_op<'a, T, F>(
+        &self,
+        py: Python<'a>,
+        name: &'static str,
+        key: &Bound<'_, PyAny>,
+        verb: &'static str,
+        f: F,
+    ) -> PyResult<Bound<'a, PyAny>>
+    where
+        F: FnOnce(&std::path::Path, &[u8]) -> std::io::Result<(T, usize)> + Send + 'static,
+        T: for<'py> IntoPyObject<'py> + Send + 'static,
+    {
+        let key = cache_key(key)?;
+        let path = cache_entry_path(std::path::Path::new(&self.root), &key);
+        let op = OpContext::new(name, [self.root.as_str()]);
+        let future = async move {
+            let path_str = path.to_string_lossy().to_string();
+            let (result, transferred) = run_blocking(move || f(&path, &key))
+                .await
+                .map_err(|e| map_io_error(e, &path_str, verb))?;
+            record_transfer(transferred);
+            Ok(result)
+        };
+        run_op(py, op, future)
+    }
+}
+
+#[pymethods]
+impl Cache {
+    /// Create a cache storing its entries under `root`.
+    ///
+    /// The directory is created with the first entry. Caches with the same
+    /// root share their entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Directory holding the entries
+    /// * `max_bytes` - Size above which entries are evicted (default 1 GiB)
+    /// * `default_ttl` - Seconds entries live unless `set()` is given a
+    ///   `ttl`; `None` keeps them until they are evicted
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyValueError` if the root, `max_bytes` or `default_ttl` is
+    /// invalid.
+    #[new]
+    #[pyo3(signature = (root, max_bytes = 1 << 30, default_ttl = None))]
+    fn new(root: String, max_bytes: u64, default_ttl: Option<f64>) -> PyResult<Self> {
+        validate_path(&root)?;
+        if max_bytes == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Invalid max_bytes: 0. Must be at least 1",
+            ));
+        }
+        Ok(Cache {
+            root,
+            max_bytes,
+            default_ttl: cache_ttl("default_ttl", default_ttl)?,
+            state: Arc::default(),
+            eviction: Arc::default(),
         })
-        .await
-        .map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to open file: {e}"))
-        })??;
+    }
+
+    /// Directory holding the entries.
+    #[getter]
+    fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Size in bytes above which entries are evicted.
+    #[getter]
+    fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Seconds entries live unless `set()` is given a `ttl`.
+    #[getter]
+    fn default_ttl(&self) -> Option<f64> {
+        self.default_ttl
+    }
+
+    /// Get the value stored for a key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key as `bytes` or `str` (encoded as UTF-8, so `"a"` and
+    ///   `b"a"` are the same key)
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the value as bytes, or `None` if the key is
+    /// missing or expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyTypeError` if the key is neither bytes nor str, or
+    /// `PyIOError` if the entry cannot be read.
+    fn get<'a>(&self, py: Python<'a>, key: &Bound<'_, PyAny>) -> PyResult<Bound<'a, PyAny>> {
+        self.entry_op(py, "cache_get", key, "read cache entry", |path, key| {
+            let value = read_cache_entry(path, key)?;
+            let transferred = value.as_ref().map_or(0, Vec::len);
+            Ok((value, transferred))
+        })
+    }
+
+    /// Store a value, replacing the one stored for the key.
+    ///
+    /// If the cache then exceeds `max_bytes`, eviction starts in the
+    /// background; `set()` does not wait for it.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key as `bytes` or `str`
+    /// * `value` - Value as `bytes` or `str` (encoded as UTF-8)
+    /// * `ttl` - Seconds the entry lives; defaults to the cache's `default_ttl`
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` once the entry is written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyTypeError` if the key or value is neither bytes nor str,
+    /// `PyValueError` if the ttl is invalid or the entry is larger than
+    /// `max_bytes`, or `PyIOError` if the entry cannot be written.
+    #[pyo3(signature = (key, value, ttl = None))]
+    fn set<'a>(
+        &self,
+        py: Python<'a>,
+        key: &Bound<'_, PyAny>,
+        value: &Bound<'_, PyAny>,
+        ttl: Option<f64>,
+    ) -> PyResult<Bound<'a, PyAny>> {
+        let mut contents = Vec::new();
+        if !extend_with_data(&mut contents, value)? {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "value must be bytes or str",
+            ));
+        }
+        let ttl = cache_ttl("ttl", ttl)?.or(self.default_ttl);
+        let size = (CACHE_HEADER_LEN + cache_key(key)?.len() + contents.len()) as u64;
+        if size > self.max_bytes {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid cache entry: {size} bytes. Must be at most max_bytes ({})",
+                self.max_bytes
+            )));
+        }
+        let (root, max_bytes) = (std::path::PathBuf::from(&self.root), self.max_bytes);
+        let (state, eviction) = (Arc::clone(&self.state), Arc::clone(&self.eviction));
+        let none = py.None();
+        self.entry_op(
+            py,
+            "cache_set",
+            key,
+            "write cache entry",
+            move |path, key| {
+                let replaced =
+                    write_cache_entry(path, key, &contents, ttl.map(|ttl| unix_now() + ttl))?;
+                let start_eviction = {
+                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    state.size = state
+                        .size
+                        .map(|total| (total + size).saturating_sub(replaced));
+                    let start = !state.evicting && state.size.is_none_or(|total| total > max_bytes);
+                    state.evicting |= start;
+                    start
+                };
+                if start_eviction {
+                    tokio::spawn(evict_in_background(root, max_bytes, state, eviction));
+                }
+                Ok((none, contents.len()))
+            },
+        )
+    }
 
-        // Acquire the lock (blocking operation)
-        {
-            let file_clone = file.try_clone().map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                    "Failed to clone file handle: {e}"
-                ))
-            })?;
-            tokio::task::spawn_blocking({
-                let path_clone2 = path_clone.clone();
-                move || {
-                    if exclusive {
-                        fs2::FileExt::lock_exclusive(&file_clone)
-                    } else {
-                        fs2::FileExt::lock_shared(&file_clone)
+    /// Remove the entry for a key.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `True` if an entry was removed, `False` if
+    /// there was none.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyTypeError` if the key is neither bytes nor str, or
+    /// `PyIOError` if the entry cannot be removed.
+    fn delete<'a>(&self, py: Python<'a>, key: &Bound<'_, PyAny>) -> PyResult<Bound<'a, PyAny>> {
+        let state = Arc::clone(&self.state);
+        self.entry_op(
+            py,
+            "cache_delete",
+            key,
+            "remove cache entry",
+            move |path, key| {
+                let size = match std::fs::metadata(path) {
+                    Ok(metadata) => metadata.len(),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((false, 0)),
+                    Err(e) => return Err(e),
+                };
+                if !matches!(read_cache_header(path), Ok(Some((_, stored))) if stored == key) {
+                    return Ok((false, 0));
+                }
+                match std::fs::remove_file(path) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((false, 0)),
+                    Err(e) => return Err(e),
+                }
+                let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                state.size = state.size.map(|total| total.saturating_sub(size));
+                Ok((true, 0))
+            },
+        )
+    }
+
+    /// Remove every entry.
+    ///
+    /// Only the shard directories are removed; other files under the root
+    /// are left alone.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields `None` once the cache is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if an entry cannot be removed.
+    fn clear<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let (root, state, eviction) = (
+            self.root.clone(),
+            Arc::clone(&self.state),
+            Arc::clone(&self.eviction),
+        );
+        let op = OpContext::new("cache_clear", [self.root.as_str()]);
+        let future = async move {
+            let _evicting = eviction.lock().await;
+            let root_clone = root.clone();
+            run_blocking(move || {
+                let shards = match std::fs::read_dir(&root_clone) {
+                    Ok(shards) => shards,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+                    Err(e) => return Err(e),
+                };
+                for shard in shards {
+                    let shard = shard?;
+                    if is_cache_shard(&shard.file_name()) && shard.file_type()?.is_dir() {
+                        match std::fs::remove_dir_all(shard.path()) {
+                            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                            _ => {}
+                        }
                     }
-                    .map_err(|e| {
-                        PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                            "Failed to acquire lock on {path_clone2}: {e}"
-                        ))
-                    })
                 }
+                Ok(())
             })
             .await
-            .map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to acquire lock: {e}"))
-            })??;
+            .map_err(|e| map_io_error(e, &root, "clear cache"))?;
+            state.lock().unwrap_or_else(|e| e.into_inner()).size = Some(0);
+            Ok(())
+        };
+        run_unit_op(py, op, future)
+    }
+
+    /// Remove expired entries, then the least recently used ones until the
+    /// cache fits in `max_bytes`, and wait for it.
+    ///
+    /// Eviction normally runs in the background after `set()`; this runs it
+    /// now, e.g. from a periodic maintenance task to drop expired entries.
+    ///
+    /// # Returns
+    ///
+    /// A coroutine that yields the number of entries removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyIOError` if the cache cannot be scanned or an entry cannot
+    /// be removed.
+    fn evict<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let (root, max_bytes) = (self.root.clone(), self.max_bytes);
+        let (state, eviction) = (Arc::clone(&self.state), Arc::clone(&self.eviction));
+        let op = OpContext::new("cache_evict", [self.root.as_str()]);
+        let future = async move {
+            let _evicting = eviction.lock().await;
+            let root_clone = root.clone();
+            let (removed, size) =
+                run_blocking(move || evict_cache(std::path::Path::new(&root_clone), max_bytes))
+                    .await
+                    .map_err(|e| map_io_error(e, &root, "evict cache entries in"))?;
+            state.lock().unwrap_or_else(|e| e.into_inner()).size = Some(size);
+            Ok(removed)
+        };
+        run_op(py, op, future)
+    }
+}
+
+// Descriptor passing
+
+/// State of an `AsyncFile` besides its descriptor, carried along with the
+/// descriptor when the handle is pickled or sent to another process.
+struct TransferredFile {
+    mode: String,
+    path: String,
+    access: AccessMode,
+    line_buffering: bool,
+    buffer_size: usize,
+}
+
+impl TransferredFile {
+    /// Capture the state of a binary-mode handle.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PyTypeError` for text mode, whose decoder lives in the
+    /// Python wrapper and cannot be transferred.
+    fn of(file: &AsyncFile) -> PyResult<Self> {
+        if !file.mode.contains('b') {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                "Cannot transfer text-mode file {}; open it in binary mode",
+                file.path
+            )));
         }
+        Ok(TransferredFile {
+            mode: file.mode.clone(),
+            path: file.path.clone(),
+            access: file.access,
+            line_buffering: file.line_buffering,
+            buffer_size: file.buffer_size,
+        })
+    }
 
-        Ok(FileLock {
-            file: Arc::new(file),
-            path: path_clone,
-            exclusive,
+    /// NUL-separated fields, with the path last since it is the only one
+    /// that may contain other separators.
+    fn encode(&self) -> Vec<u8> {
+        format!(
+            "{}\0{}\0{}\0{}\0{}",
+            self.mode,
+            self.access.name(),
+            u8::from(self.line_buffering),
+            self.buffer_size,
+            self.path
+        )
+        .into_bytes()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(bytes).ok()?;
+        let mut fields = text.splitn(5, '\0');
+        let mode = fields.next()?.to_string();
+        let access = AccessMode::parse(fields.next()?).ok()?;
+        let line_buffering = fields.next()? == "1";
+        let buffer_size = fields.next()?.parse().ok()?;
+        let path = fields.next()?.to_string();
+        parse_mode(&mode).ok()?;
+        Some(TransferredFile {
+            mode,
+            path,
+            access,
+            line_buffering,
+            buffer_size,
         })
-    };
-    future_into_py(py, future)
+    }
+
+    /// Create a handle that owns `file`, continuing at its current position.
+    fn into_handle(self, file: std::fs::File, stack: Option<String>) -> AsyncFile {
+        AsyncFile::new_open(
+            handle_from_std(file, self.access),
+            self.path,
+            self.mode,
+            self.access,
+            None,
+            self.line_buffering,
+            self.buffer_size,
+            stack,
+        )
+    }
 }
 
-// Batch operations
+/// Send `data` with `fd` attached as `SCM_RIGHTS` ancillary data, without
+/// blocking.
+#[cfg(unix)]
+fn send_with_fd(sock: i32, data: &[u8], fd: i32) -> std::io::Result<usize> {
+    let fd_len = std::mem::size_of::<i32>() as u32;
+    // SAFETY: CMSG_SPACE only computes a size
+    let space = unsafe { libc::CMSG_SPACE(fd_len) } as usize;
+    // u64 words keep the control buffer aligned for `cmsghdr`
+    let mut control = vec![0u64; space.div_ceil(8)];
+    let mut iov = libc::iovec {
+        iov_base: data.as_ptr() as *mut libc::c_void,
+        iov_len: data.len(),
+    };
+    // SAFETY: an all-zero msghdr is valid (no name, no buffers)
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+    // SAFETY: the control buffer has room for one header carrying one
+    // descriptor, and `msg` only points to live buffers during sendmsg
+    let sent = unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(fd_len) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<i32>(), fd);
+        libc::sendmsg(sock, &msg, libc::MSG_DONTWAIT)
+    };
+    if sent < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(sent as usize)
+}
 
-/// Read multiple files concurrently.
-///
-/// Reads all specified files concurrently and returns their contents.
-/// All I/O operations execute outside the Python GIL using native Tokio,
-/// ensuring true async behavior.
-///
-/// # Arguments
-///
-/// * `py` - Python GIL token
-/// * `paths` - Vector of file paths to read
+/// Receive into `buf` without blocking, returning the number of bytes and
+/// the descriptors that arrived with them.
 ///
-/// # Returns
+/// # Errors
 ///
-/// A coroutine that yields a list of (path, result) tuples where:
-/// - `path`: The file path
-/// - `result`: Either the file contents as bytes, or an error message string
-#[pyfunction]
-fn read_files_async(py: Python<'_>, paths: Vec<String>) -> PyResult<Bound<'_, PyAny>> {
-    // Validate all paths
-    for path in &paths {
-        validate_path(path)?;
+/// Fails if the socket was closed before anything was received, or if
+/// descriptors were discarded because more arrived than expected.
+#[cfg(unix)]
+fn recv_with_fds(
+    sock: i32,
+    buf: &mut [u8],
+) -> std::io::Result<(usize, Vec<std::os::unix::io::OwnedFd>)> {
+    use std::os::unix::io::FromRawFd;
+
+    // Room for a few descriptors, so unexpected extras are closed rather
+    // than leaked
+    let fds_len = (4 * std::mem::size_of::<i32>()) as u32;
+    // SAFETY: CMSG_SPACE only computes a size
+    let space = unsafe { libc::CMSG_SPACE(fds_len) } as usize;
+    let mut control = vec![0u64; space.div_ceil(8)];
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    // SAFETY: an all-zero msghdr is valid (no name, no buffers)
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let flags = libc::MSG_DONTWAIT | libc::MSG_CMSG_CLOEXEC;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    let flags = libc::MSG_DONTWAIT;
+    // SAFETY: `msg` points to live buffers of the given sizes
+    let received = unsafe { libc::recvmsg(sock, &mut msg, flags) };
+    if received < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut fds = Vec::new();
+    // SAFETY: recvmsg filled in the control buffer; headers are walked with
+    // the CMSG macros and their descriptors are owned by us from here on
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cmsg);
+                let len = (*cmsg).cmsg_len as usize - (data as usize - cmsg as usize);
+                for i in 0..len / std::mem::size_of::<i32>() {
+                    let fd = std::ptr::read_unaligned(data.cast::<i32>().add(i));
+                    fds.push(std::os::unix::io::OwnedFd::from_raw_fd(fd));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
     }
+    if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+        return Err(std::io::Error::other("too many descriptors were sent"));
+    }
+    if received == 0 {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok((received as usize, fds))
+}
 
-    let future = async move {
-        use futures::future;
+/// Wait until `op` on the socket stops returning `WouldBlock`.
+#[cfg(unix)]
+async fn socket_io<T>(
+    sock: &tokio::io::unix::AsyncFd<std::os::unix::io::OwnedFd>,
+    interest: tokio::io::Interest,
+    mut op: impl FnMut(i32) -> std::io::Result<T>,
+) -> std::io::Result<T> {
+    use std::os::unix::io::AsRawFd;
+
+    loop {
+        let mut guard = sock.ready(interest).await?;
+        match guard.try_io(|sock| op(sock.get_ref().as_raw_fd())) {
+            Ok(result) => return result,
+            Err(_would_block) => continue,
+        }
+    }
+}
 
-        let read_futures: Vec<_> = paths
-            .iter()
-            .map(|path| {
-                let path_clone = path.clone();
-                async move {
-                    let path_for_result = path_clone.clone();
-                    match tokio::fs::read(&path_clone).await {
-                        Ok(bytes) => (path_clone, Ok(bytes)),
-                        Err(e) => (
-                            path_for_result.clone(),
-                            Err(format!("Failed to read file {path_for_result}: {e}")),
-                        ),
-                    }
-                }
-            })
-            .collect();
+/// Register a duplicate of the caller's socket with the runtime, so its
+/// blocking mode is left alone.
+#[cfg(unix)]
+fn async_socket(
+    sock: i32,
+) -> std::io::Result<tokio::io::unix::AsyncFd<std::os::unix::io::OwnedFd>> {
+    use std::os::unix::io::BorrowedFd;
 
-        let results = future::join_all(read_futures).await;
-        // Convert to tuples with bytes (Ok) or error strings (Err)
-        // PyO3 can convert both bytes and String to Python objects
-        let python_results: Vec<(String, Py<PyAny>)> = results
-            .into_iter()
-            .map(|(path, result)| {
-                Python::attach(|py| {
-                    let py_obj: Py<PyAny> = match result {
-                        Ok(bytes) => PyBytes::new(py, &bytes).into(),
-                        Err(err_str) => PyString::new(py, &err_str).into(),
-                    };
-                    (path, py_obj)
-                })
-            })
-            .collect();
-        Ok(python_results)
-    };
-    future_into_py(py, future)
+    if sock < 0 {
+        return Err(std::io::Error::from_raw_os_error(libc::EBADF));
+    }
+    // SAFETY: the caller keeps the socket open until the operation is done
+    let owned = unsafe { BorrowedFd::borrow_raw(sock) }.try_clone_to_owned()?;
+    tokio::io::unix::AsyncFd::new(owned)
 }
 
-/// Write multiple files concurrently.
+/// Send an open `AsyncFile` to another process over a Unix socket.
 ///
-/// Writes contents to all specified files concurrently. All I/O operations
-/// execute outside the Python GIL using native Tokio, ensuring true async behavior.
+/// The descriptor travels as `SCM_RIGHTS` ancillary data with a small
+/// header describing the handle, so `recv_file_async` on the other end gets
+/// an equivalent handle sharing the same open file (and file position).
+/// The sending handle stays open; close it when it is no longer needed.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `files` - Vector of (path, contents) tuples to write
+/// * `sock` - Descriptor of a connected Unix stream socket
+/// * `file` - Binary-mode handle to send
 ///
 /// # Returns
 ///
-/// A coroutine that yields a list of (path, result) tuples where:
-/// - `path`: The file path
-/// - `result`: Either Ok(()) on success, or an error message string
+/// A coroutine that yields `None` once the descriptor has been sent.
+///
+/// # Errors
+///
+/// Returns `PyTypeError` for text-mode handles, `PyValueError` if the
+/// handle is closed, `PyOSError` if sending fails (e.g. `sock` is not a Unix
+/// socket), or `PyNotImplementedError` on platforms other than Unix.
 #[pyfunction]
-fn write_files_async(py: Python<'_>, files: Vec<(String, Vec<u8>)>) -> PyResult<Bound<'_, PyAny>> {
-    // Validate all paths
-    for (path, _) in &files {
-        validate_path(path)?;
-    }
-    let files_data = files;
-
-    let future = async move {
-        use futures::future;
-
-        let write_futures: Vec<_> = files_data
-            .iter()
-            .map(|(path, bytes)| {
-                let path_clone = path.clone();
-                let bytes_clone = bytes.clone();
-                async move {
-                    let path_for_result = path_clone.clone();
-                    match tokio::fs::write(&path_clone, bytes_clone).await {
-                        Ok(_) => (path_clone, Ok(())),
-                        Err(e) => (
-                            path_for_result.clone(),
-                            Err(format!("Failed to write file {path_for_result}: {e}")),
-                        ),
-                    }
-                }
-            })
-            .collect();
-
-        let results = future::join_all(write_futures).await;
-        // Convert Result<(), String> to Python-compatible values
-        let python_results: Vec<(String, Py<PyAny>)> = results
-            .into_iter()
-            .map(|(path, result)| {
-                Python::attach(|py| {
-                    let py_obj: Py<PyAny> = match result {
-                        Ok(_) => py.None(),
-                        Err(err_str) => PyString::new(py, &err_str).into(),
-                    };
-                    (path, py_obj)
+fn send_file_async<'py>(
+    py: Python<'py>,
+    sock: i32,
+    file: PyRef<'py, AsyncFile>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let transferred = TransferredFile::of(&file)?;
+    let fd = file.fileno(py)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::{AsRawFd, BorrowedFd};
+
+        // SAFETY: the handle's descriptor is open; the duplicate keeps the
+        // file open even if the handle is closed before the send
+        let fd = unsafe { BorrowedFd::borrow_raw(fd) }
+            .try_clone_to_owned()
+            .map_err(|e| map_io_error(e, &transferred.path, "duplicate"))?;
+        let header = transferred.encode();
+        let mut message = (header.len() as u32).to_le_bytes().to_vec();
+        message.extend_from_slice(&header);
+
+        let op = OpContext::new("send_file", [transferred.path.as_str()]);
+        let future = async move {
+            let send = async {
+                let sock = async_socket(sock)?;
+                let interest = tokio::io::Interest::WRITABLE;
+                // The descriptor goes with the first byte; the rest of the
+                // message follows as plain data
+                let mut sent = socket_io(&sock, interest, |sock| {
+                    send_with_fd(sock, &message, fd.as_raw_fd())
                 })
-            })
-            .collect();
-        Ok(python_results)
-    };
-    future_into_py(py, future)
+                .await?;
+                while sent < message.len() {
+                    sent += socket_io(&sock, interest, |sock| {
+                        let rest = &message[sent..];
+                        // SAFETY: `rest` is a live buffer of the given length
+                        let n = unsafe {
+                            libc::send(sock, rest.as_ptr().cast(), rest.len(), libc::MSG_DONTWAIT)
+                        };
+                        if n < 0 {
+                            return Err(std::io::Error::last_os_error());
+                        }
+                        Ok(n as usize)
+                    })
+                    .await?;
+                }
+                Ok::<_, std::io::Error>(())
+            };
+            send.await.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
+                    "Failed to send file {}: {e}",
+                    transferred.path
+                ))
+            })?;
+            Ok(())
+        };
+        run_unit_op(py, op, future)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (sock, transferred, fd);
+        Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "passing file descriptors is only supported on Unix",
+        ))
+    }
 }
 
-/// Copy multiple files concurrently.
-///
-/// Copies all specified files concurrently. All I/O operations execute
-/// outside the Python GIL using native Tokio, ensuring true async behavior.
+/// Receive an `AsyncFile` sent by `send_file_async` over a Unix socket.
 ///
 /// # Arguments
 ///
 /// * `py` - Python GIL token
-/// * `files` - Vector of (src, dst) tuples to copy
+/// * `sock` - Descriptor of a connected Unix stream socket
 ///
 /// # Returns
 ///
-/// A coroutine that yields a list of (src, dst, result) tuples where:
-/// - `src`: The source file path
-/// - `dst`: The destination file path
-/// - `result`: Either Ok(()) on success, or an error message string
+/// A coroutine that yields an `AsyncFile` with the sender's path, mode and
+/// access mode, owning the received descriptor.
+///
+/// # Errors
+///
+/// Returns `PyOSError` if receiving fails, the socket is closed, or the
+/// message was not sent by `send_file_async`, or `PyNotImplementedError`
+/// on platforms other than Unix.
 #[pyfunction]
-fn copy_files_async(py: Python<'_>, files: Vec<(String, String)>) -> PyResult<Bound<'_, PyAny>> {
-    // Validate all paths
-    for (src, dst) in &files {
-        validate_path(src)?;
-        validate_path(dst)?;
-    }
-
-    let future = async move {
-        use futures::future;
-
-        let copy_futures: Vec<_> = files
-            .iter()
-            .map(|(src, dst)| {
-                let src_clone = src.clone();
-                let dst_clone = dst.clone();
-                async move {
-                    let src_for_result = src_clone.clone();
-                    let dst_for_result = dst_clone.clone();
-                    match tokio::fs::copy(&src_clone, &dst_clone).await {
-                        Ok(_) => (src_clone, dst_clone, Ok(())),
-                        Err(e) => (
-                            src_for_result.clone(),
-                            dst_for_result.clone(),
-                            Err(format!(
-                                "Failed to copy file {src_for_result} -> {dst_for_result}: {e}"
-                            )),
-                        ),
-                    }
+fn recv_file_async(py: Python<'_>, sock: i32) -> PyResult<Bound<'_, PyAny>> {
+    #[cfg(unix)]
+    {
+        let stack = capture_open_stack(py)?;
+        let op = OpContext::new("recv_file", std::iter::empty::<&str>());
+        let future = async move {
+            let receive = async {
+                let sock = async_socket(sock)?;
+                let interest = tokio::io::Interest::READABLE;
+                // Read exactly one message, leaving any later ones (and
+                // their descriptors) on the socket
+                let mut length = [0u8; 4];
+                let (mut received, mut fds) =
+                    socket_io(&sock, interest, |sock| recv_with_fds(sock, &mut length)).await?;
+                while received < length.len() {
+                    let (n, more) = socket_io(&sock, interest, |sock| {
+                        recv_with_fds(sock, &mut length[received..])
+                    })
+                    .await?;
+                    received += n;
+                    fds.extend(more);
                 }
-            })
-            .collect();
-
-        let results = future::join_all(copy_futures).await;
-        // Convert Result<(), String> to Python-compatible values
-        let python_results: Vec<(String, String, Py<PyAny>)> = results
-            .into_iter()
-            .map(|(src, dst, result)| {
-                Python::attach(|py| {
-                    let py_obj: Py<PyAny> = match result {
-                        Ok(_) => py.None(),
-                        Err(err_str) => PyString::new(py, &err_str).into(),
-                    };
-                    (src, dst, py_obj)
-                })
-            })
-            .collect();
-        Ok(python_results)
-    };
-    future_into_py(py, future)
+                let mut header = vec![0u8; u32::from_le_bytes(length) as usize];
+                let mut received = 0;
+                while received < header.len() {
+                    let (n, more) = socket_io(&sock, interest, |sock| {
+                        recv_with_fds(sock, &mut header[received..])
+                    })
+                    .await?;
+                    received += n;
+                    fds.extend(more);
+                }
+                let invalid = || std::io::Error::other("it was not sent by send_file()");
+                let transferred = TransferredFile::decode(&header).ok_or_else(invalid)?;
+                let mut fds = fds.into_iter();
+                match (fds.next(), fds.next()) {
+                    (Some(fd), None) => Ok((transferred, std::fs::File::from(fd))),
+                    _ => Err(invalid()),
+                }
+            };
+            let (transferred, file) = receive.await.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyOSError, _>(format!("Failed to receive file: {e}"))
+            })?;
+            let flags = parse_mode(&transferred.mode)?;
+            if flags.write || flags.append {
+                check_writable("open", &transferred.path)?;
+            }
+            Ok(transferred.into_handle(file, stack))
+        };
+        run_op(py, op, future)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (py, sock);
+        Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "passing file descriptors is only supported on Unix",
+        ))
+    }
 }
 
 /// Open a file asynchronously (aiofiles.open() compatible).
+///
+/// `access` selects how the handle treats concurrently awaited operations:
+/// "exclusive" raises, "serialized" (default) queues them, and "positional"
+/// additionally allows lock-free `pread`/`pwrite` calls.
+///
+/// An `opener(path, flags)` callable is called right away, on the calling
+/// thread with the GIL held, and the file descriptor it returns is owned by
+/// the new handle (Unix only).
+///
+/// `path` may also be an open file descriptor (Unix only), which the handle
+/// closes unless `closefd` is false; `opener` is not called then.
+///
+/// The handle reads and writes bytes; `rapfiles.open()` decodes text mode
+/// (`encoding`, `errors` and `newline`) on top of it.
 #[pyfunction]
-#[allow(clippy::too_many_arguments)] // Matches Python's open() signature for aiofiles compatibility
-fn open_file(
-    py: Python<'_>,
-    path: String,
+#[pyo3(signature = (path, mode, buffering, closefd, opener, access = "serialized"))]
+fn open_file<'py>(
+    py: Python<'py>,
+    path: FileArg,
     mode: String,
     buffering: i32,
-    encoding: Option<String>,
-    errors: Option<String>,
-    newline: Option<String>,
     closefd: bool,
     opener: Option<Py<PyAny>>,
-) -> PyResult<Bound<'_, PyAny>> {
+    access: &str,
+) -> PyResult<Bound<'py, PyAny>> {
     // Validate parameters
-    validate_path(&path)?;
-
-    // Note: encoding, errors, newline, buffering, closefd, opener are accepted for API compatibility
-    // but not fully implemented yet (will be added in later phases)
-    let _ = (buffering, encoding, errors, newline, closefd, opener);
+    let (path, fd) = match path {
+        FileArg::Path(path) => {
+            validate_path(&path)?;
+            if !closefd {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "Cannot use closefd=False with file name",
+                ));
+            }
+            (path, None)
+        }
+        FileArg::Fd(fd) if fd < 0 => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "negative file descriptor",
+            ));
+        }
+        // Named like the descriptor in messages and `open_files()`
+        FileArg::Fd(fd) => (format!("<fd {fd}>"), Some(fd)),
+    };
+    let access = AccessMode::parse(access)?;
 
-    let (read, write, append) = parse_mode(&mode)?;
+    let flags = parse_mode(&mode)?;
+    if flags.write || flags.append {
+        check_writable("open", &path)?;
+    }
+    let line_buffering = buffering == 1 && !mode.contains('b');
+    let buffer_size = match buffering {
+        0 => 0,
+        size if size > 1 => size as usize,
+        _ => DEFAULT_BUFFER_SIZE,
+    };
+    if buffering == 1 && !line_buffering {
+        // Same warning as the built-in open()
+        let category = py.get_type::<pyo3::exceptions::PyRuntimeWarning>();
+        PyErr::warn(
+            py,
+            &category,
+            c"line buffering (buffering=1) isn't supported in binary mode, the default buffer size will be used",
+            1,
+        )?;
+    }
+    let opened = match (fd, &opener) {
+        (Some(fd), _) => Some(file_from_fd(fd, closefd, flags.append, &path)?),
+        (None, Some(opener)) => Some(open_with_opener(opener.bind(py), &path, flags)?),
+        (None, None) => None,
+    };
+    let opener = opener.filter(|_| fd.is_none()).map(Arc::new);
+    let stack = capture_open_stack(py)?;
     let path_clone = path.clone();
     let mode_clone = mode.clone();
 
+    let op = OpContext::new("open", [path.as_str()]);
     let future = async move {
-        let mut open_options = tokio::fs::OpenOptions::new();
-        open_options.read(read);
-        open_options.write(write || append);
-        open_options.create(write || append);
-        open_options.truncate(write && !append);
-        open_options.append(append);
-
-        let file = open_options
-            .open(&path_clone)
-            .await
-            .map_err(|e| map_io_error(e, &path_clone, "open file"))?;
+        let file = match opened {
+            Some(opened) => handle_from_std(opened, access),
+            None => open_for_mode(&path_clone, flags, access)
+                .await
+                .map_err(|e| map_io_error(e, &path_clone, "open file"))?,
+        };
 
-        Ok(AsyncFile {
-            file: Arc::new(Mutex::new(file)),
-            path: path_clone,
-            mode: mode_clone,
-        })
+        let mut handle = AsyncFile::new_open(
+            file,
+            path_clone,
+            mode_clone,
+            access,
+            opener,
+            line_buffering,
+            buffer_size,
+            stack,
+        );
+        handle.fd = fd;
+        Ok(handle)
     };
 
-    future_into_py(py, future)
+    run_op(py, op, future)
 }
@@ -0,0 +1,4 @@
+"""Shared pytest configuration."""
+
+# The rapfiles_fs fixture
+pytest_plugins = ["rapfiles.testing"]
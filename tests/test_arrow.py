@@ -0,0 +1,81 @@
+"""Test the pyarrow random access adapter."""
+
+import os
+import tempfile
+from concurrent.futures import ThreadPoolExecutor
+
+import pytest
+
+from rapfiles.arrow import RandomAccessFile, open_input_file
+
+try:
+    import pyarrow  # noqa: F401
+
+    HAS_PYARROW = True
+except ImportError:
+    HAS_PYARROW = False
+
+
+def test_random_access_file():
+    """Test positional, sequential and concurrent reads."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        data = bytes(range(256)) * 64
+        with open(path, "wb") as f:
+            f.write(data)
+
+        with RandomAccessFile(path) as f:
+            assert f.size() == len(data)
+            assert f.read_at(4, 10) == data[10:14]
+            assert f.read_at(100, len(data) - 5) == data[-5:]
+            assert f.read_at(10, len(data) + 1) == b""
+            assert f.tell() == 0
+
+            assert f.read(3) == data[:3]
+            assert f.seek(-2, 2) == len(data) - 2
+            assert f.read() == data[-2:]
+            assert f.seek(5) == 5
+            assert f.seek(5, 1) == 10
+            assert f.read(2) == data[10:12]
+            with pytest.raises(ValueError):
+                f.seek(-1)
+
+            offsets = range(0, len(data), 512)
+            with ThreadPoolExecutor(max_workers=8) as pool:
+                chunks = list(pool.map(lambda o: f.read_at(512, o), offsets))
+            assert b"".join(chunks) == data
+
+        assert f.closed
+        with pytest.raises(ValueError, match="closed file"):
+            f.read_at(1, 0)
+        f.close()
+
+
+@pytest.mark.asyncio
+async def test_random_access_file_inside_event_loop():
+    """Test that the synchronous interface works from a running event loop."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        with open(path, "wb") as f:
+            f.write(b"0123456789")
+
+        with RandomAccessFile(path) as f:
+            assert f.read_at(3, 7) == b"789"
+
+    with pytest.raises(FileNotFoundError):
+        RandomAccessFile(os.path.join(tmpdir, "missing.bin"))
+
+
+@pytest.mark.skipif(not HAS_PYARROW, reason="pyarrow not installed")
+def test_open_input_file_parquet():
+    """Test reading a Parquet file through pyarrow."""
+    import pyarrow as pa
+    import pyarrow.parquet as pq
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "table.parquet")
+        table = pa.table({"id": list(range(1000)), "name": ["x"] * 1000})
+        pq.write_table(table, path, row_group_size=100)
+
+        with open_input_file(path) as source:
+            assert pq.read_table(source).equals(table)
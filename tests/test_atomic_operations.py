@@ -4,6 +4,8 @@ import pytest
 import tempfile
 import os
 import asyncio
+import hashlib
+import json
 import uuid
 import sys
 
@@ -11,12 +13,25 @@ from rapfiles import (
     atomic_write_file,
     atomic_write_file_bytes,
     atomic_move_file,
+    write_if_unchanged,
+    FileChangedError,
+    stat,
     lock_file,
     lock_file_shared,
     write_file,
+    write_file_bytes,
+    write_files,
+    append_file,
     read_file,
     read_file_bytes,
     exists,
+    copy_file,
+    copy_files,
+    move,
+    write_segments,
+    create_filled,
+    update_json,
+    save_config,
 )
 
 
@@ -70,6 +85,134 @@ async def test_atomic_write_file_overwrites():
         assert content == "New content"
 
 
+@pytest.mark.asyncio
+async def test_atomic_write_file_finalize_read_only():
+    """Test that finalize_read_only publishes a file without write permission."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        test_file = os.path.join(tmpdir, _unique_name("artifact.txt"))
+
+        await atomic_write_file(test_file, "Artifact v1", finalize_read_only=True)
+
+        assert await read_file(test_file) == "Artifact v1"
+        assert not os.stat(test_file).st_mode & 0o222
+        assert os.listdir(tmpdir) == [os.path.basename(test_file)]
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(
+    sys.platform == "win32", reason="Cannot replace read-only files on Windows"
+)
+async def test_atomic_write_file_bytes_finalize_read_only_replaces():
+    """Test that a read-only artifact can be replaced by a new atomic write."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        test_file = os.path.join(tmpdir, _unique_name("artifact.bin"))
+
+        await atomic_write_file_bytes(test_file, b"v1", finalize_read_only=True)
+        await atomic_write_file_bytes(test_file, b"v2", finalize_read_only=True)
+
+        assert await read_file_bytes(test_file) == b"v2"
+        assert not os.stat(test_file).st_mode & 0o222
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("durability", ["none", "flush", "fsync", "fsync+dir"])
+async def test_write_durability(durability):
+    """Test that every durability preset writes the same contents."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, _unique_name("data.txt"))
+
+        await write_file(path, "one", durability=durability)
+        await append_file(path, " two", durability=durability)
+        assert await read_file(path) == "one two"
+        await write_file_bytes(path, b"three", durability=durability)
+        assert await read_file_bytes(path) == b"three"
+
+        await atomic_write_file(path, "four", durability=durability)
+        assert await read_file(path) == "four"
+        await atomic_write_file_bytes(
+            path, b"five", finalize_read_only=True, durability=durability
+        )
+        assert await read_file_bytes(path) == b"five"
+        assert not os.stat(path).st_mode & 0o222
+
+        other = os.path.join(tmpdir, _unique_name("other.txt"))
+        await write_if_unchanged(other, "six", None, durability=durability)
+        await write_files({other: b"seven"}, durability=durability)
+        assert await read_file(other) == "seven"
+        assert sorted(os.listdir(tmpdir)) == sorted(
+            [os.path.basename(path), os.path.basename(other)]
+        )
+
+
+@pytest.mark.asyncio
+async def test_write_durability_invalid():
+    """Test that unknown durability presets are rejected before writing."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, _unique_name("data.txt"))
+        with pytest.raises(ValueError, match="Invalid durability"):
+            await write_file(path, "data", durability="sync")
+        with pytest.raises(ValueError, match="Invalid durability"):
+            await atomic_write_file_bytes(path, b"data", durability="fsync+file")
+        assert not os.path.exists(path)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("durability", ["none", "flush", "fsync", "fsync+dir"])
+async def test_copy_move_config_durability(durability):
+    """Test that copies, moves, segment and config writes accept every preset."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src.bin")
+        await write_file_bytes(src, b"payload")
+
+        copied = os.path.join(tmpdir, "copied.bin")
+        assert await copy_file(src, copied, durability=durability) == 7
+        many = os.path.join(tmpdir, "many.bin")
+        await copy_files([(src, many)], durability=durability)
+        moved = os.path.join(tmpdir, "moved.bin")
+        assert await move(copied, moved, durability=durability) == moved
+        assert await read_file_bytes(moved) == b"payload"
+        assert await read_file_bytes(many) == b"payload"
+        assert not os.path.exists(copied)
+
+        segmented = os.path.join(tmpdir, "segments.bin")
+        await write_segments(
+            segmented, [(0, b"ab"), (4, b"ef")], durability=durability
+        )
+        assert await read_file_bytes(segmented) == b"ab\0\0ef"
+        filled = os.path.join(tmpdir, "filled.bin")
+        await create_filled(filled, 16, durability=durability)
+        assert await read_file_bytes(filled) == bytes(16)
+
+        config = os.path.join(tmpdir, "config.json")
+        await update_json(
+            config, lambda data: {"n": 1}, default={}, durability=durability
+        )
+        await save_config(config, {"n": 2}, durability=durability)
+        assert json.loads(await read_file(config)) == {"n": 2}
+
+
+@pytest.mark.asyncio
+async def test_copy_move_config_durability_invalid():
+    """Test that unknown presets are rejected before copying, moving or writing."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src.bin")
+        await write_file_bytes(src, b"payload")
+        dst = os.path.join(tmpdir, "dst.bin")
+        with pytest.raises(ValueError, match="Invalid durability"):
+            await copy_file(src, dst, durability="sync")
+        with pytest.raises(ValueError, match="Invalid durability"):
+            await copy_files([(src, dst)], durability="sync")
+        with pytest.raises(ValueError, match="Invalid durability"):
+            await move(src, dst, durability="sync")
+        with pytest.raises(ValueError, match="Invalid durability"):
+            await write_segments(dst, [(0, b"x")], durability="sync")
+        with pytest.raises(ValueError, match="Invalid durability"):
+            await create_filled(dst, 4, durability="sync")
+        with pytest.raises(ValueError, match="Invalid durability"):
+            await save_config(dst, {"n": 1}, durability="sync")
+        assert os.listdir(tmpdir) == ["src.bin"]
+
+
 @pytest.mark.asyncio
 async def test_atomic_write_concurrent_read():
     """Test that atomic write doesn't interfere with concurrent reads."""
@@ -494,3 +637,60 @@ async def test_lock_file_with_file_operations():
         # After lock release, file should have final content
         final_content = await read_file(file_path)
         assert final_content == "New content"
+
+
+@pytest.mark.asyncio
+async def test_write_if_unchanged():
+    """Test that writes are refused once the file changed since it was read."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "notes.md")
+
+        await write_if_unchanged(path, "v1", None)
+        with pytest.raises(FileChangedError, match="it was created"):
+            await write_if_unchanged(path, "other v1", None)
+
+        digest = hashlib.sha256(b"v1").hexdigest()
+        await write_if_unchanged(path, b"v2", digest.upper())
+        assert await read_file(path) == "v2"
+        # A second editor still holding the old digest loses
+        with pytest.raises(FileChangedError, match="contents changed"):
+            await write_if_unchanged(path, "stale", digest)
+
+        modified = (await stat(path)).modified
+        await write_if_unchanged(path, "v3", modified)
+        os.utime(path, (modified - 10, modified - 10))
+        with pytest.raises(FileChangedError, match="modification time"):
+            await write_if_unchanged(path, "stale", modified)
+        assert await read_file(path) == "v3"
+
+        os.remove(path)
+        with pytest.raises(FileChangedError, match="removed"):
+            await write_if_unchanged(path, "stale", modified)
+        assert not os.path.exists(path)
+        assert os.listdir(tmpdir) == []
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(sys.platform == "win32", reason="Unix path semantics")
+async def test_write_if_unchanged_link_fails():
+    """Test that expected=None raises instead of renaming when linking fails."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        # The check finds nothing, but a file can't be linked to a path
+        # ending in a slash; the error comes from the link, not a rename
+        path = os.path.join(tmpdir, "notes.md") + "/"
+        with pytest.raises(OSError, match="No such file or directory"):
+            await write_if_unchanged(path, "data", None)
+        assert os.listdir(tmpdir) == []
+
+
+@pytest.mark.asyncio
+async def test_write_if_unchanged_invalid():
+    """Test argument validation of write_if_unchanged."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "notes.md")
+        with pytest.raises(ValueError, match="SHA-256"):
+            await write_if_unchanged(path, "data", "abc")
+        with pytest.raises(TypeError):
+            await write_if_unchanged(path, "data", [])
+        with pytest.raises(TypeError):
+            await write_if_unchanged(path, 42, None)
@@ -0,0 +1,113 @@
+"""Test the file access audit log."""
+
+import asyncio
+import json
+import os
+import sys
+import tempfile
+import time
+
+import pytest
+
+import rapfiles
+from rapfiles.audit import AuditLog, actor, redact_paths
+
+
+@pytest.mark.asyncio
+async def test_callback_sink():
+    """Test that every finished operation is passed to a callable sink."""
+    events = []
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "a.txt")
+        before = time.time()
+        with AuditLog(events.append):
+            await rapfiles.write_file(path, "data")
+            with pytest.raises(FileNotFoundError):
+                await rapfiles.read_file(path + ".missing")
+        await rapfiles.read_file(path)
+
+    assert [event["op"] for event in events] == ["write_file", "read_file"]
+    written, failed = events
+    assert written["paths"] == [path]
+    assert written["bytes"] == 4
+    assert written["ok"] is True and written["error"] is None
+    assert before <= written["timestamp"] <= time.time()
+    assert written["duration"] >= 0
+    assert written["actor"] is None
+    assert written["pid"] == os.getpid()
+    if hasattr(os, "getuid"):
+        assert written["uid"] == os.getuid()
+    assert failed["ok"] is False
+    assert "No such file" in failed["error"]
+
+
+@pytest.mark.asyncio
+async def test_file_sink_and_actor():
+    """Test JSON Lines output and the per-task actor."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        log_path = os.path.join(tmpdir, "audit.jsonl")
+
+        async def handler(user, name):
+            with actor(user):
+                await rapfiles.write_file(os.path.join(tmpdir, name), user)
+
+        with AuditLog(log_path):
+            await asyncio.gather(handler("alice", "a.txt"), handler("bob", "b.txt"))
+
+        with open(log_path) as f:
+            events = [json.loads(line) for line in f]
+    actors = {os.path.basename(event["paths"][0]): event["actor"] for event in events}
+    assert actors == {"a.txt": "alice", "b.txt": "bob"}
+
+
+@pytest.mark.asyncio
+async def test_redaction():
+    """Test that redaction hooks rewrite or drop events."""
+    events = []
+
+    def redact(event):
+        if event["op"] == "exists":
+            return None
+        return redact_paths(lambda path: "<redacted>")(event)
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        secret = os.path.join(tmpdir, "secret.txt")
+        with AuditLog(events.append, redact=redact):
+            await rapfiles.exists(secret)
+            with pytest.raises(FileNotFoundError):
+                await rapfiles.read_file(secret)
+
+    (event,) = events
+    assert event["paths"] == ["<redacted>"]
+    assert secret not in event["error"]
+    assert "<redacted>" in event["error"]
+
+
+@pytest.mark.asyncio
+async def test_failing_sink_does_not_break_operations():
+    """Test that sink errors go to sys.unraisablehook."""
+    unraisable = []
+    previous_hook = sys.unraisablehook
+    sys.unraisablehook = unraisable.append
+
+    def sink(event):
+        raise RuntimeError("sink down")
+
+    try:
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "a.txt")
+            with AuditLog(sink):
+                await rapfiles.write_file(path, "data")
+            assert await rapfiles.read_file(path) == "data"
+    finally:
+        sys.unraisablehook = previous_hook
+    assert [str(hook.exc_value) for hook in unraisable] == ["sink down"]
+
+
+def test_single_active_log():
+    """Test that only one audit log can be active."""
+    with AuditLog(lambda event: None):
+        with pytest.raises(RuntimeError, match="already active"):
+            AuditLog(lambda event: None).start()
+    with AuditLog(lambda event: None):
+        pass
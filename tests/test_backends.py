@@ -0,0 +1,151 @@
+"""Test the selectable I/O backends."""
+
+import asyncio
+import os
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+def test_backend_selection():
+    """Test setting, reading and validating the global backend."""
+    assert rapfiles.available_backends() == ["tokio", "threadpool"]
+    assert rapfiles.get_backend() == "tokio"
+
+    rapfiles.set_backend("threadpool")
+    try:
+        assert rapfiles.get_backend() == "threadpool"
+        with pytest.raises(ValueError, match="Invalid backend"):
+            rapfiles.set_backend("io_uring")
+        with pytest.raises(ValueError, match="Invalid backend"):
+            with rapfiles.use_backend("io_uring"):
+                pass
+        assert rapfiles.get_backend() == "threadpool"
+    finally:
+        rapfiles.set_backend("tokio")
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_file_handle_operations(backend):
+    """Test that AsyncFile behaves identically with each backend."""
+    with rapfiles.use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "lines.txt")
+
+        async with rapfiles.open(path, "w") as f:
+            assert await f.write("first\nsecond\n") == 13
+            await f.write("third")
+
+        async with rapfiles.open(path, "r") as f:
+            assert await f.readline() == "first\n"
+            assert await f.tell() == 6
+            assert await f.readline(3) == "sec"
+            assert await f.readlines() == ["ond\n", "third"]
+            assert await f.seek(0) == 0
+            assert await f.read(5) == "first"
+            assert await f.read() == "\nsecond\nthird"
+
+        async with rapfiles.open(path, "rb") as f:
+            assert await f.readlines(1) == [b"first\n"]
+            assert await f.seek(-5, 2) == 13
+            assert await f.read() == b"third"
+
+        await rapfiles.append_file(path, "\nfourth")
+        assert (await rapfiles.read_file(path)).endswith("third\nfourth")
+
+
+@pytest.mark.asyncio
+async def test_use_backend_is_scoped_to_context():
+    """Test that use_backend overrides the backend only inside its block."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+
+        async def other_task():
+            await asyncio.sleep(0)
+            return rapfiles.get_backend()
+
+        with rapfiles.use_backend("threadpool"):
+            assert rapfiles.get_backend() == "threadpool"
+            await rapfiles.write_file_bytes(path, b"x" * 100_000)
+            async with rapfiles.open(path, "rb") as f:
+                assert len(await f.read()) == 100_000
+            concurrent = asyncio.create_task(other_task())
+            with rapfiles.use_backend("tokio"):
+                assert rapfiles.get_backend() == "tokio"
+            assert rapfiles.get_backend() == "threadpool"
+        assert rapfiles.get_backend() == "tokio"
+
+        # Tasks copy the context they were created in
+        assert await concurrent == "threadpool"
+        assert await asyncio.create_task(other_task()) == "tokio"
+
+
+def test_routes_select_backend():
+    """Test that the most specific route decides the backend of a path."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        fuse = os.path.join(tmpdir, "fuse")
+        try:
+            rapfiles.add_route(fuse, backend="threadpool")
+            rapfiles.add_route(os.path.join(fuse, "fast"), backend="tokio")
+
+            assert rapfiles.get_backend(os.path.join(fuse, "a.txt")) == "threadpool"
+            assert rapfiles.get_backend(os.path.join(fuse, "fast", "b")) == "tokio"
+            # Prefixes match whole components
+            assert rapfiles.get_backend(fuse + "2") == "tokio"
+            with rapfiles.use_backend("tokio"):
+                assert rapfiles.get_backend(os.path.join(fuse, "a.txt")) == "tokio"
+
+            # Re-adding a prefix replaces its route
+            rapfiles.add_route(fuse, concurrency=2)
+            listed = [(r.prefix, r.backend, r.concurrency) for r in rapfiles.routes()]
+            assert listed == [
+                (os.path.join(fuse, "fast"), "tokio", None),
+                (fuse, None, 2),
+            ]
+            assert rapfiles.get_backend(os.path.join(fuse, "a.txt")) == "tokio"
+
+            assert rapfiles.remove_route(fuse) is True
+            assert rapfiles.remove_route(fuse) is False
+        finally:
+            rapfiles.clear_routes()
+        assert rapfiles.routes() == []
+
+
+def test_add_route_validation():
+    """Test that invalid routes are rejected."""
+    with pytest.raises(ValueError, match="Invalid backend"):
+        rapfiles.add_route("/mnt/data", backend="io_uring")
+    with pytest.raises(ValueError, match="concurrency"):
+        rapfiles.add_route("/mnt/data", concurrency=0)
+    with pytest.raises(ValueError):
+        rapfiles.add_route("/mnt/data")
+    assert rapfiles.routes() == []
+
+
+@pytest.mark.asyncio
+async def test_routes_apply_to_operations():
+    """Test that routed backends and concurrency limits keep results intact."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        dst = os.path.join(tmpdir, "dst")
+        os.makedirs(src)
+        paths = [os.path.join(src, f"f{i}.txt") for i in range(20)]
+        try:
+            rapfiles.add_route(tmpdir, backend="threadpool", concurrency=1)
+
+            await rapfiles.write_files({p: p.encode() for p in paths})
+            results = await rapfiles.read_files(paths)
+            assert results == [(p, p.encode()) for p in paths]
+
+            async with rapfiles.open(paths[0], "a") as f:
+                await f.write("!")
+            assert await rapfiles.read_file(paths[0]) == paths[0] + "!"
+
+            await rapfiles.copytree(src, dst)
+            assert sorted(os.listdir(dst)) == sorted(os.listdir(src))
+            await rapfiles.remove_dir_all(dst)
+            assert not os.path.exists(dst)
+        finally:
+            rapfiles.clear_routes()
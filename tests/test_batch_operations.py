@@ -16,6 +16,7 @@ from rapfiles import (
     read_file_bytes,
     write_file_bytes,
     exists,
+    generate_tree,
 )
 
 
@@ -401,3 +402,39 @@ async def test_batch_operations_mixed_types():
         for path, expected_content in files.items():
             assert path in result_dict
             assert result_dict[path] == expected_content
+
+
+def _snapshot(root):
+    """Return the relative paths and contents of every file under root."""
+    tree = {}
+    for dirpath, _, filenames in os.walk(root):
+        for name in filenames:
+            path = os.path.join(dirpath, name)
+            with open(path, "rb") as f:
+                tree[os.path.relpath(path, root)] = f.read()
+    return tree
+
+
+@pytest.mark.asyncio
+async def test_generate_tree():
+    """Test that generated trees are reproducible and follow the arguments."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        first, second, other = (os.path.join(tmpdir, n) for n in "abc")
+        files = await generate_tree(first, 20, 200, (10, 100), name_seed=1)
+        await generate_tree(second, 20, 200, (10, 100), name_seed=1)
+        await generate_tree(other, 20, 200, (10, 100), name_seed=2)
+
+        tree = _snapshot(first)
+        assert len(files) == len(tree) == 200
+        assert sorted(os.path.relpath(path, first) for path in files) == sorted(tree)
+        assert all(10 <= len(data) <= 100 for data in tree.values())
+        dirs = [d for _, names, _ in os.walk(first) for d in names]
+        assert len(dirs) == 20
+        assert _snapshot(second) == tree
+        assert _snapshot(other) != tree
+
+        fixed = await generate_tree(os.path.join(tmpdir, "fixed"), 0, 5, 7)
+        assert [os.path.getsize(path) for path in fixed] == [7] * 5
+
+        with pytest.raises(ValueError, match="size_distribution"):
+            await generate_tree(first, 1, 1, (10, 1))
@@ -0,0 +1,68 @@
+"""Test the benchmark harness."""
+
+import pytest
+import os
+import tempfile
+
+from rapfiles import bench
+
+
+@pytest.mark.asyncio
+async def test_bench_run_custom_profile():
+    """Test running all workloads with a small custom profile."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        results = await bench.run(
+            {"file_size": 1024, "file_count": 8, "concurrency": 2}, root=tmpdir
+        )
+
+        assert [r.workload for r in results] == list(bench.WORKLOADS)
+        for result in results:
+            assert result.backend == "tokio"
+            assert result.file_count == 8
+            assert result.ops_per_second > 0
+            assert 0 <= result.latency_p50 <= result.latency_p99
+            assert result.latency_p99 <= result.latency_max
+        assert results[2].bytes_per_second == 0.0
+        assert results[1].as_dict()["file_size"] == 1024
+        # The scratch directory is removed afterwards
+        assert os.listdir(tmpdir) == []
+
+
+@pytest.mark.asyncio
+async def test_bench_run_selected_workloads():
+    """Test that read-only workloads prepare their input files."""
+    results = await bench.run(
+        {"base": "small-files", "file_count": 4, "workloads": ["stat", "read"]}
+    )
+
+    assert [r.workload for r in results] == ["read", "stat"]
+    assert results[0].file_size == bench.PROFILES["small-files"]["file_size"]
+
+
+@pytest.mark.asyncio
+async def test_bench_run_directory_workloads():
+    """Test that whole-directory workloads count every entry as an operation."""
+    results = await bench.run(
+        {"file_size": 16, "file_count": 50, "workloads": ["walk", "list", "batch-read"]}
+    )
+
+    assert [r.workload for r in results] == ["list", "walk", "batch-read"]
+    for result in results:
+        assert result.file_count == 50
+        expected = bench.SCAN_ROUNDS * 50 / result.seconds
+        assert result.ops_per_second == pytest.approx(expected)
+    assert results[0].bytes_per_second == 0.0
+    assert results[2].bytes_per_second == pytest.approx(
+        bench.SCAN_ROUNDS * 50 * 16 / results[2].seconds
+    )
+
+
+@pytest.mark.asyncio
+async def test_bench_run_invalid_profile():
+    """Test validation of profile names, workloads and settings."""
+    with pytest.raises(ValueError):
+        await bench.run("nonexistent")
+    with pytest.raises(ValueError):
+        await bench.run({"workloads": ["delete"]})
+    with pytest.raises(ValueError):
+        await bench.run({"concurrency": 0})
@@ -30,11 +30,10 @@ async def test_simple_iteration(mode):
 
         counter = 1
         await file.seek(0)
-        # Note: rapfiles doesn't support async iteration yet
-        # The new iteration pattern would be:
-        # async for line in file:
-        #     assert line.strip() == b"line " + str(counter).encode()
-        #     counter += 1
+        # The new iteration pattern:
+        async for line in file:
+            assert line.strip() == b"line " + str(counter).encode()
+            counter += 1
 
 
 @pytest.mark.parametrize("mode", ["rb", "rb+", "ab+"])
@@ -56,8 +55,15 @@ async def test_simple_readlines(mode):
 
 @pytest.mark.parametrize("mode", ["rb+", "wb", "ab"])
 async def test_simple_flush(mode, tmp_path):
-    """Test flushing to a file - flush not yet implemented in rapfiles."""
-    pytest.skip("flush() not yet implemented in rapfiles")
+    """Test flushing to a file."""
+    filename = tmp_path / "file.bin"
+    filename.write_bytes(b"")
+
+    async with rapfiles.open(str(filename), mode=mode) as file:
+        await file.write(b"0")
+        await file.flush()
+        # The data is visible to other readers before the file is closed
+        assert filename.read_bytes() == b"0"
 
 
 @pytest.mark.parametrize("mode", ["rb", "rb+", "ab+"])
@@ -105,10 +111,6 @@ async def test_staggered_read(mode):
 @pytest.mark.parametrize("mode", ["rb", "rb+", "ab+"])
 async def test_simple_seek(mode, tmp_path):
     """Test seeking and then reading."""
-    # Skip rb+ mode due to known issue with read operations in rb+ mode
-    if mode == "rb+":
-        pytest.skip("rb+ mode read operations have known issues in rapfiles")
-
     filename = "bigfile.bin"
     content = b"0123456789" * 4 * io.DEFAULT_BUFFER_SIZE
 
@@ -138,8 +140,15 @@ async def test_simple_close_ctx_mgr(mode, tmp_path):
 
 @pytest.mark.parametrize("mode", ["rb+", "wb", "ab+"])
 async def test_simple_truncate(mode, tmp_path):
-    """Test truncating files - not yet implemented in rapfiles."""
-    pytest.skip("truncate not yet implemented in rapfiles")
+    """Test truncating files."""
+    full_file = tmp_path / "file.txt"
+    full_file.write_bytes(b"line 1\n")
+
+    async with rapfiles.open(str(full_file), mode=mode) as file:
+        await file.seek(0)  # Append mode starts at the end.
+        await file.truncate()
+
+    assert full_file.read_bytes() == b""
 
 
 @pytest.mark.parametrize("mode", ["wb", "rb+", "wb+", "ab", "ab+"])
@@ -161,9 +170,20 @@ async def test_simple_write(mode, tmp_path):
 
 
 @pytest.mark.parametrize("mode", ["rb", "rb+", "ab+"])
-async def test_simple_readinto(mode):
-    """Test the readinto functionality - not yet implemented in rapfiles."""
-    pytest.skip("readinto not yet implemented in rapfiles")
+async def test_simple_readinto(mode, tmp_path):
+    """Test the readinto functionality."""
+    filename = tmp_path / "test_file1.txt"
+    filename.write_bytes(b"0123456789")
+
+    async with rapfiles.open(str(filename), mode=mode) as file:
+        await file.seek(0)  # Needed for the append mode.
+
+        array = bytearray(4)
+        bytes_read = await file.readinto(array)
+
+        expected = filename.read_bytes()[:4]
+        assert bytes_read == len(expected)
+        assert array[:bytes_read] == expected
 
 
 @pytest.mark.parametrize("mode", ["rb", "rb+", "ab+"])
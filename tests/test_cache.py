@@ -0,0 +1,136 @@
+"""Test the disk cache."""
+
+import asyncio
+import os
+import tempfile
+import time
+
+import pytest
+
+from rapfiles import Cache, set_read_only
+
+
+def count_entries(root):
+    return sum(
+        not entry.name.startswith(".")
+        for shard in os.scandir(root)
+        for entry in os.scandir(shard.path)
+    )
+
+
+@pytest.mark.asyncio
+async def test_get_set_delete_clear():
+    """Test storing, reading and removing entries."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        root = os.path.join(tmpdir, "cache")
+        cache = Cache(root)
+        assert await cache.get("missing") is None
+
+        await cache.set("a", b"alpha")
+        await cache.set(b"b", "beta")
+        assert await cache.get(b"a") == b"alpha"
+        assert await cache.get("b") == b"beta"
+
+        # Another cache on the same root sees the entries
+        assert await Cache(root).get("a") == b"alpha"
+
+        # Entries live in shard directories, without temporary files
+        shards = os.listdir(root)
+        assert all(len(shard) == 2 for shard in shards)
+        files = [name for shard in shards for name in os.listdir(f"{root}/{shard}")]
+        assert len(files) == 2 and not any(name.startswith(".") for name in files)
+
+        await cache.set("a", b"replaced")
+        assert await cache.get("a") == b"replaced"
+        assert await cache.delete("a") is True
+        assert await cache.delete("a") is False
+        assert await cache.get("a") is None
+
+        other = os.path.join(root, "keep.txt")
+        with open(other, "w") as f:
+            f.write("not an entry")
+        await cache.clear()
+        assert await cache.get("b") is None
+        assert os.listdir(root) == ["keep.txt"]
+
+        with pytest.raises(TypeError):
+            await cache.set(1, b"x")
+
+
+@pytest.mark.asyncio
+async def test_ttl():
+    """Test that entries expire after their TTL."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        cache = Cache(tmpdir, default_ttl=0.2)
+        assert cache.default_ttl == 0.2
+        await cache.set("short", b"1")
+        await cache.set("long", b"2", ttl=60)
+        await asyncio.sleep(0.3)
+        assert await cache.get("short") is None
+        assert await cache.get("long") == b"2"
+
+        await cache.set("expired", b"3", ttl=0.01)
+        await asyncio.sleep(0.05)
+        assert await cache.evict() == 1
+
+        with pytest.raises(ValueError, match="Invalid ttl"):
+            await cache.set("x", b"x", ttl=0)
+        with pytest.raises(ValueError, match="Invalid default_ttl"):
+            Cache(tmpdir, default_ttl=-1)
+        with pytest.raises(ValueError, match="Invalid max_bytes"):
+            Cache(tmpdir, max_bytes=0)
+
+
+@pytest.mark.asyncio
+async def test_get_read_only():
+    """Test that get() leaves entries untouched in read-only mode."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        cache = Cache(tmpdir)
+        await cache.set("expired", b"1", ttl=0.01)
+        await cache.set("hit", b"2")
+        for shard in os.scandir(tmpdir):
+            for entry in os.scandir(shard.path):
+                os.utime(entry.path, (1_000_000, 1_000_000))
+        await asyncio.sleep(0.05)
+
+        set_read_only()
+        try:
+            assert await cache.get("expired") is None
+            assert await cache.get("hit") == b"2"
+        finally:
+            set_read_only(False)
+        assert count_entries(tmpdir) == 2
+        for shard in os.scandir(tmpdir):
+            for entry in os.scandir(shard.path):
+                assert entry.stat().st_mtime == 1_000_000
+
+
+@pytest.mark.asyncio
+async def test_eviction():
+    """Test that the least recently used entries are evicted in the background."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        cache = Cache(tmpdir, max_bytes=4000)
+        for i in range(3):
+            await cache.set(f"k{i}", bytes(1000))
+            # Entry modification times must differ for the LRU order
+            await asyncio.sleep(0.02)
+        assert await cache.get("k0") == bytes(1000)
+
+        await cache.set("k3", bytes(1000))
+        # Over the limit: evicted down to 90% of it, i.e. three entries
+        deadline = time.monotonic() + 5
+        while count_entries(tmpdir) > 3 and time.monotonic() < deadline:
+            await asyncio.sleep(0.01)
+        assert count_entries(tmpdir) == 3
+        # k1 was the least recently used; k0 was read after it was written
+        assert await cache.get("k1") is None
+        assert await cache.get("k0") == bytes(1000)
+        assert await cache.get("k3") == bytes(1000)
+
+        await cache.set("k4", b"small", ttl=0.01)
+        await asyncio.sleep(0.05)
+        assert await cache.evict() == 1
+        assert count_entries(tmpdir) == 3
+
+        with pytest.raises(ValueError, match="Invalid cache entry"):
+            await cache.set("huge", bytes(5000))
@@ -0,0 +1,95 @@
+"""Test the cleanup registry."""
+
+import asyncio
+import os
+import subprocess
+import sys
+import tempfile
+
+import pytest
+
+from rapfiles import (
+    register_cleanup,
+    registered_cleanup,
+    run_cleanup,
+    unregister_cleanup,
+)
+
+
+def _make(root):
+    path = os.path.join(root, "scratch")
+    os.makedirs(os.path.join(path, "nested"))
+    with open(os.path.join(path, "nested", "data.bin"), "wb") as f:
+        f.write(b"data")
+    single = os.path.join(root, "single.tmp")
+    with open(single, "w") as f:
+        f.write("tmp")
+    return path, single
+
+
+@pytest.mark.asyncio
+async def test_run_cleanup_removes_registered_paths():
+    """Test that registered files and trees are removed and unregistered."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        tree, single = _make(tmpdir)
+        missing = os.path.join(tmpdir, "never-created")
+
+        assert register_cleanup(tree) == os.path.abspath(tree)
+        register_cleanup(single)
+        register_cleanup(missing)
+        assert set(registered_cleanup()) >= {tree, single, missing}
+
+        assert await run_cleanup() == []
+
+        assert not os.path.exists(tree)
+        assert not os.path.exists(single)
+        assert not set(registered_cleanup()) & {tree, single, missing}
+
+
+@pytest.mark.asyncio
+async def test_unregister_cleanup_keeps_path():
+    """Test that unregistered paths survive cleanup."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        tree, _ = _make(tmpdir)
+
+        register_cleanup(tree)
+        assert unregister_cleanup(tree) is True
+        assert unregister_cleanup(tree) is False
+
+        await run_cleanup()
+        assert os.path.isdir(tree)
+
+
+@pytest.mark.asyncio
+async def test_cleanup_on_loop_shutdown():
+    """Test that paths registered in a loop go when the loop shuts down."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        tree, single = _make(tmpdir)
+
+        async def job():
+            register_cleanup(tree)
+            register_cleanup(single, on_loop_shutdown=False)
+            await asyncio.sleep(3600)
+
+        async def main():
+            task = asyncio.ensure_future(job())
+            await asyncio.sleep(0.01)
+            task.cancel()
+
+        # A separate thread gets its own loop, like a worker running asyncio.run()
+        await asyncio.get_running_loop().run_in_executor(None, asyncio.run, main())
+
+        assert not os.path.exists(tree)
+        assert os.path.exists(single)
+        assert unregister_cleanup(single) is True
+
+
+def test_cleanup_at_interpreter_exit():
+    """Test that registered paths are removed when the interpreter exits."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        tree, _ = _make(tmpdir)
+        script = "import rapfiles, sys; rapfiles.register_cleanup(sys.argv[1])"
+
+        subprocess.run([sys.executable, "-c", script, tree], check=True)
+
+        assert not os.path.exists(tree)
@@ -0,0 +1,112 @@
+"""Test the native TOML and YAML read helpers."""
+
+import datetime
+import os
+import sys
+import tempfile
+
+import pytest
+
+import rapfiles
+
+TOML_DOCUMENT = """\
+title = "app"
+ratio = 0.5
+enabled = true
+ports = [8000, 8001]
+released = 1979-05-27T07:32:00Z
+shifted = 1979-05-27T00:32:00.5-07:00
+local = 1979-05-27T07:32:00
+day = 1979-05-27
+alarm = 07:32:00
+
+[server]
+host = "localhost"
+
+[[workers]]
+name = "a"
+
+[[workers]]
+name = "b"
+"""
+
+
+@pytest.mark.asyncio
+async def test_read_toml():
+    """Test that TOML files parse to the types tomllib returns."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "config.toml")
+        with open(path, "w") as f:
+            f.write(TOML_DOCUMENT)
+
+        data = await rapfiles.read_toml(path)
+        assert list(data)[:4] == ["title", "ratio", "enabled", "ports"]
+        assert data["ports"] == [8000, 8001]
+        assert data["server"] == {"host": "localhost"}
+        assert data["workers"] == [{"name": "a"}, {"name": "b"}]
+        utc = datetime.timezone.utc
+        assert data["released"] == datetime.datetime(1979, 5, 27, 7, 32, tzinfo=utc)
+        assert data["shifted"].utcoffset() == datetime.timedelta(hours=-7)
+        assert data["shifted"].microsecond == 500000
+        assert data["local"].tzinfo is None
+        assert data["day"] == datetime.date(1979, 5, 27)
+        assert data["alarm"] == datetime.time(7, 32)
+
+        if sys.version_info >= (3, 11):
+            import tomllib
+
+            assert data == tomllib.loads(TOML_DOCUMENT)
+
+
+@pytest.mark.asyncio
+async def test_read_toml_errors():
+    """Test that missing and malformed files raise the usual errors."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "broken.toml")
+        with pytest.raises(FileNotFoundError):
+            await rapfiles.read_toml(path)
+
+        with open(path, "w") as f:
+            f.write("key = \n")
+        with pytest.raises(ValueError, match="Failed to parse TOML file"):
+            await rapfiles.read_toml(path)
+
+
+@pytest.mark.asyncio
+async def test_read_yaml():
+    """Test that YAML files parse to plain Python objects."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "config.yaml")
+        with open(path, "w") as f:
+            f.write(
+                "defaults: &defaults\n"
+                "  retries: 3\n"
+                "  timeout: 1.5\n"
+                "server:\n"
+                "  <<: *defaults\n"
+                "  host: localhost\n"
+                "  debug: false\n"
+                "  tags: [a, b]\n"
+                "  missing: ~\n"
+                "1: one\n"
+            )
+
+        data = await rapfiles.read_yaml(path)
+        assert data["server"] == {
+            "retries": 3,
+            "timeout": 1.5,
+            "host": "localhost",
+            "debug": False,
+            "tags": ["a", "b"],
+            "missing": None,
+        }
+        assert data[1] == "one"
+
+        with open(path, "w") as f:
+            f.write("- 1\n- two\n")
+        assert await rapfiles.read_yaml(path) == [1, "two"]
+
+        with open(path, "w") as f:
+            f.write("key: [unclosed\n")
+        with pytest.raises(ValueError, match="Failed to parse YAML file"):
+            await rapfiles.read_yaml(path)
@@ -0,0 +1,188 @@
+"""Test the locked read-modify-write config helpers."""
+
+import asyncio
+import datetime
+import json
+import os
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+@pytest.mark.asyncio
+async def test_update_json():
+    """Test updating a JSON file in place and by returning new data."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "config.json")
+        with open(path, "w") as f:
+            json.dump({"name": "app", "workers": 2}, f)
+
+        def scale(config):
+            config["workers"] *= 2
+
+        result = await rapfiles.update_json(path, scale)
+        assert result == {"name": "app", "workers": 4}
+
+        async def rename(config):
+            return {**config, "name": "service"}
+
+        await rapfiles.update_json(path, rename, indent=None)
+        with open(path) as f:
+            assert f.read() == '{"name": "service", "workers": 4}\n'
+
+
+@pytest.mark.asyncio
+async def test_update_json_missing_file():
+    """Test that a missing file raises unless a default is given."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "state.json")
+        with pytest.raises(FileNotFoundError):
+            await rapfiles.update_json(path, lambda data: None)
+
+        default = {"runs": []}
+        await rapfiles.update_json(path, lambda data: data["runs"].append(1), default)
+        assert default == {"runs": []}
+        with open(path) as f:
+            assert json.load(f) == {"runs": [1]}
+
+
+@pytest.mark.asyncio
+async def test_update_json_concurrent_updates():
+    """Test that concurrent updates are serialized and none is lost."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "counter.json")
+
+        async def increment(data):
+            value = data["count"]
+            await asyncio.sleep(0)
+            data["count"] = value + 1
+
+        await asyncio.gather(
+            *(rapfiles.update_json(path, increment, {"count": 0}) for _ in range(20))
+        )
+        with open(path) as f:
+            assert json.load(f) == {"count": 20}
+
+
+@pytest.mark.asyncio
+async def test_update_toml():
+    """Test updating a TOML file."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "config.toml")
+        with open(path, "w") as f:
+            f.write('[server]\nport = 8000\n')
+
+        def move(config):
+            config["server"]["port"] = 9000
+
+        result = await rapfiles.update_toml(path, move)
+        assert result == {"server": {"port": 9000}}
+        await rapfiles.update_toml(path, lambda config: None)
+        assert (await rapfiles.read_file(path)).strip() == "[server]\nport = 9000"
+
+
+@pytest.mark.asyncio
+async def test_update_toml_value_types():
+    """Test that TOML values keep their types through an update."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "config.toml")
+        with open(path, "w") as f:
+            f.write(
+                'title = "app"\nratio = 0.5\nenabled = true\n'
+                "released = 2024-05-01T12:30:00+02:00\nday = 2024-05-01\n"
+                "at = 07:15:00\nports = [80, 443]\n"
+                '[[users]]\nname = "a"\n[[users]]\nname = "b"\n'
+            )
+
+        def add(config):
+            config["users"].append({"name": "c"})
+            config["local"] = datetime.datetime(2024, 1, 2, 3, 4, 5)
+
+        result = await rapfiles.update_toml(path, add)
+        assert await rapfiles.read_toml(path) == result
+        offset = datetime.timezone(datetime.timedelta(hours=2))
+        assert result["released"] == datetime.datetime(
+            2024, 5, 1, 12, 30, tzinfo=offset
+        )
+        assert result["day"] == datetime.date(2024, 5, 1)
+        assert result["at"] == datetime.time(7, 15)
+        assert [user["name"] for user in result["users"]] == ["a", "b", "c"]
+        assert (result["ratio"], result["enabled"]) == (0.5, True)
+
+        before = await rapfiles.read_file(path)
+        with pytest.raises(TypeError, match="NoneType"):
+            await rapfiles.update_toml(path, lambda config: {"missing": None})
+        with pytest.raises(TypeError, match="keys must be str"):
+            await rapfiles.update_toml(path, lambda config: {1: "one"})
+        assert await rapfiles.read_file(path) == before
+
+        with open(path, "w") as f:
+            f.write("not = [valid")
+        with pytest.raises(ValueError, match="Invalid TOML"):
+            await rapfiles.update_toml(path, lambda config: None)
+
+
+@pytest.mark.asyncio
+async def test_save_config():
+    """Test saving a config file with a backup and restricted permissions."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "credentials.json")
+
+        await rapfiles.save_config(path, {"token": "one"})
+        with open(path) as f:
+            assert f.read() == '{\n  "token": "one"\n}\n'
+        assert os.listdir(tmpdir) == ["credentials.json"]
+
+        await rapfiles.save_config(path, {"token": "two"})
+        with open(path) as f:
+            assert json.load(f) == {"token": "two"}
+        with open(path + ".bak") as f:
+            assert json.load(f) == {"token": "one"}
+        assert not os.path.samefile(path, path + ".bak")
+
+        await rapfiles.save_config(path, {"token": "three"}, backup=False)
+        with open(path + ".bak") as f:
+            assert json.load(f) == {"token": "one"}
+        assert sorted(os.listdir(tmpdir)) == [
+            "credentials.json",
+            "credentials.json.bak",
+        ]
+
+        with pytest.raises(TypeError):
+            await rapfiles.save_config(path, {"token": object()})
+        with pytest.raises(ValueError, match="Invalid format"):
+            await rapfiles.save_config(path, {}, format="ini")
+        with open(path) as f:
+            assert json.load(f) == {"token": "three"}
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="POSIX permissions")
+async def test_save_config_mode():
+    """Test that the mode is applied regardless of the umask."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "config.json")
+        await rapfiles.save_config(path, {})
+        assert os.stat(path).st_mode & 0o777 == 0o600
+
+        old_umask = os.umask(0o077)
+        try:
+            await rapfiles.save_config(path, {}, mode=0o644)
+        finally:
+            os.umask(old_umask)
+        assert os.stat(path).st_mode & 0o777 == 0o644
+        assert os.stat(path + ".bak").st_mode & 0o777 == 0o600
+
+
+@pytest.mark.asyncio
+async def test_save_config_toml():
+    """Test saving a TOML config file."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "app.toml")
+        await rapfiles.save_config(path, {"server": {"port": 9000}}, format="toml")
+        assert await rapfiles.read_toml(path) == {"server": {"port": 9000}}
+        with pytest.raises(TypeError, match="must be a dict"):
+            await rapfiles.save_config(path, [1, 2], format="toml")
+        assert await rapfiles.read_toml(path) == {"server": {"port": 9000}}
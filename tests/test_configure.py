@@ -0,0 +1,220 @@
+"""Test global configuration with configure() and RAPFILES_* variables."""
+
+import json
+import os
+import subprocess
+import sys
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+def run_python(code, **env):
+    """Run code in a fresh interpreter with extra environment variables."""
+    result = subprocess.run(
+        [sys.executable, "-W", "always", "-c", code],
+        env={**os.environ, **env},
+        capture_output=True,
+        text=True,
+        check=True,
+        timeout=60,
+    )
+    return result
+
+
+def test_get_config_defaults():
+    """Test that get_config() reports every option."""
+    config = rapfiles.get_config()
+    assert set(config) == {
+        "backend",
+        "worker_threads",
+        "max_blocking_threads",
+        "small_file_threshold",
+        "capture_open_stacks",
+        "concurrency",
+    }
+    assert config["backend"] == rapfiles.get_backend()
+    assert config["small_file_threshold"] == rapfiles.get_small_file_threshold()
+    rapfiles.configure(**config)
+    assert rapfiles.get_config() == config
+
+
+def test_configure_updates_settings():
+    """Test that configure() changes the same settings as the setters."""
+    before = rapfiles.get_config()
+    try:
+        rapfiles.configure(
+            backend="threadpool",
+            small_file_threshold=1024,
+            capture_open_stacks=True,
+            concurrency=3,
+        )
+        assert rapfiles.get_backend() == "threadpool"
+        assert rapfiles.get_small_file_threshold() == 1024
+        config = rapfiles.get_config()
+        assert config["capture_open_stacks"] is True
+        assert config["concurrency"] == 3
+        rapfiles.configure(concurrency=None)
+        assert rapfiles.get_config()["concurrency"] is None
+    finally:
+        rapfiles.configure(**before)
+    assert rapfiles.get_config() == before
+
+
+def test_configure_validates_before_applying():
+    """Test that an invalid option leaves the configuration unchanged."""
+    before = rapfiles.get_config()
+    with pytest.raises(TypeError, match="Unknown configuration option"):
+        rapfiles.configure(backend="threadpool", buffer_size=1)
+    with pytest.raises(ValueError, match="Invalid backend"):
+        rapfiles.configure(concurrency=2, backend="io_uring")
+    with pytest.raises(ValueError, match="Must be at least 1"):
+        rapfiles.configure(concurrency=0)
+    with pytest.raises(ValueError, match="Must be at least 0"):
+        rapfiles.configure(small_file_threshold=-1)
+    with pytest.raises(TypeError):
+        rapfiles.configure(worker_threads="four")
+    assert rapfiles.get_config() == before
+
+
+@pytest.mark.asyncio
+async def test_thread_settings_fixed_once_started():
+    """Test that thread counts cannot change after the runtime started."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        await rapfiles.write_file(os.path.join(tmpdir, "a.txt"), "a")
+    config = rapfiles.get_config()
+    with pytest.raises(RuntimeError, match="cannot be changed"):
+        rapfiles.configure(worker_threads=(config["worker_threads"] or 1) + 1)
+    rapfiles.configure(worker_threads=config["worker_threads"])
+    assert rapfiles.get_config() == config
+
+
+@pytest.mark.asyncio
+async def test_concurrency_applies_to_batches():
+    """Test that batch operations work under a global concurrency limit."""
+    before = rapfiles.get_config()
+    rapfiles.configure(concurrency=1)
+    try:
+        with tempfile.TemporaryDirectory() as tmpdir:
+            files = [
+                (os.path.join(tmpdir, f"{i}.bin"), bytes([i])) for i in range(5)
+            ]
+            await rapfiles.write_files(dict(files))
+            assert await rapfiles.read_files([path for path, _ in files]) == files
+    finally:
+        rapfiles.configure(**before)
+
+
+def test_environment_variables():
+    """Test that RAPFILES_* variables configure the library at import."""
+    code = "import json, rapfiles\nprint(json.dumps(rapfiles.get_config()))\n"
+    result = run_python(
+        code,
+        RAPFILES_BACKEND="threadpool",
+        RAPFILES_WORKER_THREADS="2",
+        RAPFILES_MAX_BLOCKING_THREADS="none",
+        RAPFILES_SMALL_FILE_THRESHOLD="1024",
+        RAPFILES_CAPTURE_OPEN_STACKS="yes",
+        RAPFILES_CONCURRENCY="4",
+    )
+    config = json.loads(result.stdout)
+    assert config == {
+        "backend": "threadpool",
+        "worker_threads": 2,
+        "max_blocking_threads": None,
+        "small_file_threshold": 1024,
+        "capture_open_stacks": True,
+        "concurrency": 4,
+    }
+
+
+def test_invalid_environment_variable_warns():
+    """Test that an invalid variable is ignored with a RuntimeWarning."""
+    code = "import json, rapfiles\nprint(json.dumps(rapfiles.get_config()))\n"
+    result = run_python(code, RAPFILES_WORKER_THREADS="0", RAPFILES_CONCURRENCY="lots")
+    config = json.loads(result.stdout)
+    assert config["worker_threads"] is None
+    assert config["concurrency"] is None
+    assert "RuntimeWarning: Ignoring RAPFILES_WORKER_THREADS" in result.stderr
+    assert "Ignoring RAPFILES_CONCURRENCY" in result.stderr
+
+
+@pytest.mark.skipif(
+    not sys.platform.startswith("linux"), reason="counts threads in /proc"
+)
+def test_import_is_lazy_and_preload_starts_runtime():
+    """Test that importing starts no threads and preload() starts the runtime."""
+    code = (
+        "import os, rapfiles\n"
+        "threads = lambda: len(os.listdir('/proc/self/task'))\n"
+        "print(threads())\n"
+        "rapfiles.configure(worker_threads=2)\n"
+        "rapfiles.preload()\n"
+        "rapfiles.preload()\n"
+        "print(threads(), rapfiles.runtime_info()['worker_threads'])\n"
+        "try:\n"
+        "    rapfiles.configure(worker_threads=3)\n"
+        "except RuntimeError:\n"
+        "    print('fixed')\n"
+    )
+    before, after, fixed = run_python(code).stdout.splitlines()
+    assert before == "1"
+    threads, workers = map(int, after.split())
+    # The main thread, two workers and the warm-up job's blocking threads
+    assert workers == 2 and threads >= 4
+    assert fixed == "fixed"
+
+
+def test_import_loads_no_heavy_modules():
+    """Test that importing rapfiles leaves asyncio, json and friends unloaded."""
+    code = (
+        "import sys\n"
+        "heavy = {'asyncio', 'socket', 'inspect', 'json', 'copy'}\n"
+        "preloaded = heavy & set(sys.modules)\n"
+        "import rapfiles\n"
+        "print(sorted((heavy & set(sys.modules)) - preloaded))\n"
+    )
+    assert run_python(code).stdout.strip() == "[]"
+
+
+@pytest.mark.skipif(not hasattr(os, "fork"), reason="needs os.fork()")
+def test_forked_child_gets_its_own_runtime():
+    """Test that a child forked after rapfiles was used does not hang."""
+    code = (
+        "import asyncio, os, tempfile, warnings, rapfiles\n"
+        "warnings.simplefilter('ignore', DeprecationWarning)\n"
+        "path = os.path.join(tempfile.mkdtemp(), 'f.txt')\n"
+        "async def parent():\n"
+        "    await rapfiles.write_file(path, 'parent')\n"
+        "    rapfiles.register_cleanup(path + '.tmp', on_loop_shutdown=False)\n"
+        "    return await rapfiles.open(path)\n"
+        "handle = asyncio.run(parent())\n"
+        "async def child():\n"
+        "    assert rapfiles.registered_cleanup() == []\n"
+        "    assert await rapfiles.read_file(path) == 'parent'\n"
+        "    try:\n"
+        "        await handle.read()\n"
+        "    except RuntimeError as e:\n"
+        "        assert 'before fork()' in str(e)\n"
+        "    else:\n"
+        "        raise AssertionError('inherited handle was usable')\n"
+        "    await handle.close()\n"
+        "pid = os.fork()\n"
+        "if pid == 0:\n"
+        "    try:\n"
+        "        rapfiles.reinit_after_fork()\n"
+        "        asyncio.run(child())\n"
+        "        os._exit(0)\n"
+        "    except BaseException as e:\n"
+        "        print('child failed:', repr(e), flush=True)\n"
+        "        os._exit(1)\n"
+        "_, status = os.waitpid(pid, 0)\n"
+        "print(os.waitstatus_to_exitcode(status))\n"
+        "print(rapfiles.registered_cleanup() == [path + '.tmp'])\n"
+        "async def close():\n"
+        "    await handle.close()\n"
+        "asyncio.run(close())\n"
+    )
+    assert run_python(code).stdout.splitlines() == ["0", "True"]
@@ -0,0 +1,95 @@
+"""Test deduplication of directory trees."""
+
+import os
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+def make_tree(root):
+    os.makedirs(os.path.join(root, "sub"))
+    files = {
+        "a.bin": b"same" * 1000,
+        os.path.join("sub", "b.bin"): b"same" * 1000,
+        "c.bin": b"diff" * 1000,
+        "empty1": b"",
+        "empty2": b"",
+    }
+    for name, data in files.items():
+        with open(os.path.join(root, name), "wb") as f:
+            f.write(data)
+    # Already a hard link of sub/b.bin, so replaced together with it
+    os.link(os.path.join(root, "sub", "b.bin"), os.path.join(root, "d.bin"))
+    return [os.path.join(root, name) for name in ("a.bin", "d.bin", "sub/b.bin")]
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="hard link identity is Unix only")
+async def test_dry_run_and_hardlink():
+    """Test that identical files are reported, then hard linked."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        group = make_tree(tmpdir)
+
+        report = await rapfiles.dedupe_tree(tmpdir, method="hardlink")
+        assert report.dry_run is True
+        assert report.groups == [group]
+        assert (report.duplicates, report.reclaimed) == (2, 4000)
+        assert not os.path.samefile(group[0], group[2])
+
+        rapfiles.set_read_only()
+        try:
+            # Dry runs only read
+            assert (await rapfiles.dedupe_tree(tmpdir)).groups == [group]
+            with pytest.raises(rapfiles.ReadOnlyError):
+                await rapfiles.dedupe_tree(tmpdir, dry_run=False)
+        finally:
+            rapfiles.set_read_only(False)
+
+        report = await rapfiles.dedupe_tree(tmpdir, method="hardlink", dry_run=False)
+        assert (report.dry_run, report.duplicates) == (False, 2)
+        assert all(os.path.samefile(group[0], path) for path in group)
+        with open(group[2], "rb") as f:
+            assert f.read() == b"same" * 1000
+        assert sorted(os.listdir(tmpdir)) == [
+            "a.bin",
+            "c.bin",
+            "d.bin",
+            "empty1",
+            "empty2",
+            "sub",
+        ]
+        # Nothing is left to deduplicate
+        assert (await rapfiles.dedupe_tree(tmpdir)).groups == []
+
+
+@pytest.mark.asyncio
+async def test_reflink():
+    """Test that reflinked duplicates stay independent files."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        for name in ("a.bin", "b.bin"):
+            with open(os.path.join(tmpdir, name), "wb") as f:
+                f.write(b"x" * 8192)
+        os.chmod(os.path.join(tmpdir, "b.bin"), 0o600)
+        try:
+            report = await rapfiles.dedupe_tree(tmpdir, dry_run=False)
+        except OSError as e:
+            pytest.skip(f"file system without reflinks: {e}")
+        assert report.duplicates == 1
+        a, b = (os.path.join(tmpdir, name) for name in ("a.bin", "b.bin"))
+        assert not os.path.samefile(a, b)
+        assert os.stat(b).st_mode & 0o777 == 0o600
+        with open(b, "rb") as f:
+            assert f.read() == b"x" * 8192
+        assert sorted(os.listdir(tmpdir)) == ["a.bin", "b.bin"]
+
+
+@pytest.mark.asyncio
+async def test_invalid_arguments():
+    """Test argument validation."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        with pytest.raises(ValueError, match="Invalid dedupe method"):
+            await rapfiles.dedupe_tree(tmpdir, method="symlink")
+        with pytest.raises(FileNotFoundError):
+            await rapfiles.dedupe_tree(os.path.join(tmpdir, "missing"))
@@ -0,0 +1,96 @@
+"""Test streaming directory comparison."""
+
+import pytest
+import tempfile
+import os
+
+from rapfiles import dircmp
+
+
+def _write(root, rel, content):
+    path = os.path.join(root, rel)
+    os.makedirs(os.path.dirname(path), exist_ok=True)
+    with open(path, "w") as f:
+        f.write(content)
+
+
+async def _collect(a, b, **kwargs):
+    return [(d.path, d.kind, d.reason) async for d in dircmp(a, b, **kwargs)]
+
+
+@pytest.mark.asyncio
+async def test_dircmp_identical_trees():
+    """Test that identical trees yield no differences."""
+    with tempfile.TemporaryDirectory() as a, tempfile.TemporaryDirectory() as b:
+        for root in (a, b):
+            _write(root, os.path.join("sub", "file.txt"), "same")
+
+        assert await _collect(a, b, compare_content=True) == []
+
+
+@pytest.mark.asyncio
+async def test_dircmp_only_in_one_side():
+    """Test that entries missing from one side are reported in order."""
+    with tempfile.TemporaryDirectory() as a, tempfile.TemporaryDirectory() as b:
+        _write(a, "a_only.txt", "x")
+        _write(a, os.path.join("a_dir", "nested.txt"), "x")
+        _write(b, "b_only.txt", "x")
+        _write(a, os.path.join("shared", "left.txt"), "x")
+        _write(b, os.path.join("shared", "right.txt"), "x")
+
+        assert await _collect(a, b) == [
+            ("a_dir", "only_in_a", None),
+            ("a_only.txt", "only_in_a", None),
+            ("b_only.txt", "only_in_b", None),
+            (os.path.join("shared", "left.txt"), "only_in_a", None),
+            (os.path.join("shared", "right.txt"), "only_in_b", None),
+        ]
+
+
+@pytest.mark.asyncio
+async def test_dircmp_different_entries():
+    """Test size, content and type differences."""
+    with tempfile.TemporaryDirectory() as a, tempfile.TemporaryDirectory() as b:
+        _write(a, "size.txt", "short")
+        _write(b, "size.txt", "much longer")
+        _write(a, "content.txt", "aaaa")
+        _write(b, "content.txt", "bbbb")
+        _write(a, "kind", "file")
+        os.mkdir(os.path.join(b, "kind"))
+
+        assert await _collect(a, b, compare_content=True) == [
+            ("content.txt", "different", "content"),
+            ("kind", "different", "type"),
+            ("size.txt", "different", "size"),
+        ]
+
+
+@pytest.mark.asyncio
+async def test_dircmp_mtime_without_content():
+    """Test that shallow comparison falls back to modification times."""
+    with tempfile.TemporaryDirectory() as a, tempfile.TemporaryDirectory() as b:
+        _write(a, "file.txt", "same")
+        _write(b, "file.txt", "same")
+        os.utime(os.path.join(a, "file.txt"), (1_000_000, 1_000_000))
+        os.utime(os.path.join(b, "file.txt"), (2_000_000, 2_000_000))
+
+        assert await _collect(a, b) == [("file.txt", "different", "mtime")]
+        assert await _collect(a, b, compare_content=True) == []
+
+
+@pytest.mark.asyncio
+async def test_dircmp_early_exit_and_errors():
+    """Test breaking out of the stream and missing roots."""
+    with tempfile.TemporaryDirectory() as a, tempfile.TemporaryDirectory() as b:
+        for i in range(200):
+            _write(a, f"file{i:03}.txt", "x")
+
+        async for diff in dircmp(a, b):
+            assert diff.path == "file000.txt"
+            break
+
+        with pytest.raises(FileNotFoundError):
+            await _collect(a, os.path.join(b, "missing"))
+
+        with pytest.raises(ValueError):
+            dircmp("", b)
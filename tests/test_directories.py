@@ -1,8 +1,12 @@
 """Test directory operations."""
 
+import asyncio
 import pytest
 import tempfile
 import os
+import shutil
+import threading
+import time
 
 from rapfiles import (
     create_dir,
@@ -10,6 +14,7 @@ from rapfiles import (
     remove_dir,
     remove_dir_all,
     list_dir,
+    list_dir_stat,
     exists,
     is_file,
     is_dir,
@@ -61,6 +66,117 @@ async def test_remove_dir_all():
         assert not os.path.exists(test_dir)
 
 
+@pytest.mark.asyncio
+async def test_remove_dir_all_large_tree():
+    """Test removing a wide and deep tree with files at every level."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        test_dir = os.path.join(tmpdir, "cache")
+        for i in range(20):
+            leaf = os.path.join(test_dir, f"d{i}", "a", "b", "c")
+            os.makedirs(leaf)
+            for depth_dir in (os.path.join(test_dir, f"d{i}"), leaf):
+                for j in range(25):
+                    with open(os.path.join(depth_dir, f"f{j}"), "w") as f:
+                        f.write("x")
+        os.mkdir(os.path.join(test_dir, "empty"))
+
+        await remove_dir_all(test_dir)
+        assert not os.path.exists(test_dir)
+
+
+@pytest.mark.asyncio
+async def test_remove_dir_all_does_not_follow_symlinks():
+    """Test that symlinks inside the tree (and a symlink root) are not followed."""
+    if not hasattr(os, "symlink"):
+        pytest.skip("symlinks not supported")
+    with tempfile.TemporaryDirectory() as tmpdir:
+        outside = os.path.join(tmpdir, "outside")
+        os.makedirs(os.path.join(outside, "keep"))
+        with open(os.path.join(outside, "keep", "file.txt"), "w") as f:
+            f.write("keep")
+        test_dir = os.path.join(tmpdir, "test_dir")
+        os.makedirs(os.path.join(test_dir, "sub"))
+        os.symlink(outside, os.path.join(test_dir, "sub", "link"))
+        root_link = os.path.join(tmpdir, "root_link")
+        os.symlink(outside, root_link)
+
+        await remove_dir_all(test_dir)
+        await remove_dir_all(root_link)
+
+        assert not os.path.exists(test_dir)
+        assert not os.path.lexists(root_link)
+        assert os.path.exists(os.path.join(outside, "keep", "file.txt"))
+
+
+@pytest.mark.asyncio
+async def test_remove_dir_all_concurrent_deletion():
+    """Test that entries deleted by someone else meanwhile are not errors."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        for attempt in range(5):
+            test_dir = os.path.join(tmpdir, f"shared{attempt}")
+            for i in range(20):
+                leaf = os.path.join(test_dir, f"d{i}", "a", "b")
+                os.makedirs(leaf)
+                for j in range(50):
+                    with open(os.path.join(leaf, f"f{j}"), "w") as f:
+                        f.write("x")
+
+            # Let the removal start before shutil races it through the tree
+            removal = asyncio.ensure_future(remove_dir_all(test_dir))
+            await asyncio.sleep(0)
+            rmtree = threading.Thread(
+                target=shutil.rmtree, args=(test_dir,), kwargs={"ignore_errors": True}
+            )
+            rmtree.start()
+            try:
+                await removal
+            finally:
+                rmtree.join()
+            assert not os.path.exists(test_dir)
+
+
+@pytest.mark.asyncio
+async def test_remove_dir_all_missing():
+    """Test that removing a missing tree raises an OSError."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        with pytest.raises(OSError):
+            await remove_dir_all(os.path.join(tmpdir, "missing"))
+
+
+@pytest.mark.asyncio
+async def test_remove_dir_all_throttled():
+    """Test that rate_limit_ops paces removals and nice still removes all."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        test_dir = os.path.join(tmpdir, "cleanup")
+        os.makedirs(os.path.join(test_dir, "sub"))
+        for name in ("a", "b", os.path.join("sub", "c")):
+            with open(os.path.join(test_dir, name), "w") as f:
+                f.write("x")
+        outside = os.path.join(tmpdir, "outside")
+        os.mkdir(outside)
+        if hasattr(os, "symlink"):
+            os.symlink(outside, os.path.join(test_dir, "link"))
+
+        # 5 or 6 entries (with the root) paced 0.05 seconds apart
+        start = time.monotonic()
+        await remove_dir_all(test_dir, rate_limit_ops=20)
+        assert time.monotonic() - start >= 0.2
+        assert not os.path.exists(test_dir)
+        assert os.path.isdir(outside)
+
+        os.makedirs(os.path.join(test_dir, "sub"))
+        await remove_dir_all(test_dir, nice=True)
+        assert not os.path.exists(test_dir)
+
+        file_path = os.path.join(tmpdir, "file")
+        open(file_path, "w").close()
+        with pytest.raises(OSError):
+            await remove_dir_all(file_path, nice=True)
+        assert os.path.exists(file_path)
+        with pytest.raises(ValueError, match="rate_limit_ops"):
+            await remove_dir_all(tmpdir, rate_limit_ops=0)
+
+
 @pytest.mark.asyncio
 async def test_list_dir():
     """Test listing directory contents."""
@@ -79,6 +195,33 @@ async def test_list_dir():
         assert len(entries) == 3
 
 
+@pytest.mark.asyncio
+async def test_list_dir_stat():
+    """Test listing entries with their metadata."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        with open(os.path.join(tmpdir, "b.txt"), "w") as f:
+            f.write("content")
+        os.mkdir(os.path.join(tmpdir, "a"))
+        os.utime(os.path.join(tmpdir, "b.txt"), (1_000_000, 2_000_000))
+        if hasattr(os, "symlink") and os.name != "nt":
+            os.symlink("b.txt", os.path.join(tmpdir, "link"))
+            os.symlink("missing", os.path.join(tmpdir, "broken"))
+
+        entries = dict(await list_dir_stat(tmpdir))
+        assert entries["a"].is_dir and not entries["a"].is_file
+        assert entries["b.txt"].is_file and entries["b.txt"].size == 7
+        assert entries["b.txt"].modified == 2_000_000
+        if "link" in entries:
+            # Symlinks are followed, broken ones report the link
+            assert entries["link"].is_file and entries["link"].size == 7
+            assert not entries["broken"].is_file and not entries["broken"].is_dir
+
+        names = [name for name, _ in await list_dir_stat(tmpdir, sorted=True)]
+        assert names == sorted(entries)
+        with pytest.raises(FileNotFoundError):
+            await list_dir_stat(os.path.join(tmpdir, "missing"))
+
+
 @pytest.mark.asyncio
 async def test_exists():
     """Test checking if a path exists."""
@@ -134,3 +277,89 @@ async def test_walk_dir():
         assert any("file2.txt" in p for p in paths)
         assert any("subdir1" in p for p in paths)
         assert any("subdir2" in p for p in paths)
+
+
+@pytest.mark.asyncio
+async def test_list_dir_sorted():
+    """Test that sorted=True returns names in byte order."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        for name in ["b.txt", "a.txt", "C.txt", "a10.txt", "a2.txt"]:
+            with open(os.path.join(tmpdir, name), "w") as f:
+                f.write("x")
+
+        expected = ["C.txt", "a.txt", "a10.txt", "a2.txt", "b.txt"]
+        assert await list_dir(tmpdir, sorted=True) == expected
+        assert await list_dir(tmpdir, sorted="bytes") == expected
+
+
+@pytest.mark.asyncio
+async def test_walk_dir_sorted():
+    """Test that sorted walks are deterministic pre-order traversals."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        os.makedirs(os.path.join(tmpdir, "b", "c"))
+        os.makedirs(os.path.join(tmpdir, "a"))
+        for rel in ["z.txt", os.path.join("a", "y.txt"), os.path.join("b", "x.txt")]:
+            with open(os.path.join(tmpdir, rel), "w") as f:
+                f.write("x")
+
+        results = await walk_dir(tmpdir, sorted=True)
+
+        rel_paths = [os.path.relpath(path, tmpdir) for path, _ in results]
+        assert rel_paths == [
+            "a",
+            os.path.join("a", "y.txt"),
+            "b",
+            os.path.join("b", "c"),
+            os.path.join("b", "x.txt"),
+            "z.txt",
+        ]
+        assert results == await walk_dir(tmpdir, sorted=True)
+
+
+@pytest.mark.asyncio
+async def test_list_dir_sorted_invalid():
+    """Test that unknown sort orders raise ValueError."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        with pytest.raises(ValueError):
+            await list_dir(tmpdir, sorted="random")
+
+
+@pytest.mark.asyncio
+async def test_list_dir_sorted_natural():
+    """Test that natural sort compares digit runs numerically."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        names = ["file10.txt", "file2.txt", "file1.txt", "file02.txt", "file.txt"]
+        for name in names:
+            with open(os.path.join(tmpdir, name), "w") as f:
+                f.write("x")
+
+        assert await list_dir(tmpdir, sorted="natural") == [
+            "file.txt",
+            "file1.txt",
+            "file02.txt",
+            "file2.txt",
+            "file10.txt",
+        ]
+
+
+@pytest.mark.asyncio
+async def test_walk_dir_sorted_natural():
+    """Test that natural sort applies to every level of a walk."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        for part in ["part10", "part9"]:
+            os.makedirs(os.path.join(tmpdir, part))
+            for shard in ["shard11", "shard3"]:
+                with open(os.path.join(tmpdir, part, shard), "w") as f:
+                    f.write("x")
+
+        results = await walk_dir(tmpdir, sorted="natural")
+
+        rel_paths = [os.path.relpath(path, tmpdir) for path, _ in results]
+        assert rel_paths == [
+            "part9",
+            os.path.join("part9", "shard3"),
+            os.path.join("part9", "shard11"),
+            "part10",
+            os.path.join("part10", "shard3"),
+            os.path.join("part10", "shard11"),
+        ]
@@ -0,0 +1,143 @@
+"""Test rapfiles with event loops other than the one running at call time."""
+
+import asyncio
+import os
+import tempfile
+import threading
+
+import pytest
+
+import rapfiles
+
+try:
+    import uvloop
+
+    HAS_UVLOOP = True
+except ImportError:
+    HAS_UVLOOP = False
+
+
+async def _read_and_lock(path):
+    async with rapfiles.open(path) as f:
+        content = await f.read()
+    async with rapfiles.lock_file(path):
+        await rapfiles.append_file(path, "!")
+    return content
+
+
+def test_context_managers_created_outside_a_loop():
+    """Test that open() and lock_file() can be called before a loop runs."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        with open(path, "w") as f:
+            f.write("hello")
+
+        opened = rapfiles.open(path)
+        locked = rapfiles.lock_file_shared(path)
+
+        async def use():
+            async with opened as f:
+                content = await f.read()
+            async with locked:
+                pass
+            return content
+
+        assert asyncio.run(use()) == "hello"
+
+
+def test_context_managers_moved_between_loops():
+    """Test that an open() call made in one loop can be awaited in another."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        with open(path, "w") as f:
+            f.write("hello")
+
+        async def create():
+            return rapfiles.open(path, "rb")
+
+        opened = asyncio.run(create())
+
+        async def use():
+            f = await opened
+            try:
+                return await f.read()
+            finally:
+                await f.close()
+
+        assert asyncio.run(use()) == b"hello"
+
+        async def exit_unentered():
+            # Nothing was opened, so there is nothing to close
+            return await rapfiles.open(path).__aexit__(None, None, None)
+
+        assert asyncio.run(exit_unentered()) is None
+
+
+def test_open_awaited_twice():
+    """Test that awaiting one open() call twice raises instead of leaking a handle."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        with open(path, "w") as f:
+            f.write("hello")
+
+        async def main():
+            opened = rapfiles.open(path)
+            f = await opened
+            try:
+                with pytest.raises(RuntimeError, match="already awaited"):
+                    await opened
+                with pytest.raises(RuntimeError, match="already awaited"):
+                    async with opened:
+                        pass
+            finally:
+                await f.close()
+
+        asyncio.run(main())
+
+
+def test_custom_loop_in_another_thread():
+    """Test a custom loop subclass running in an embedding thread."""
+
+    class CustomLoop(asyncio.SelectorEventLoop):
+        pass
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        with open(path, "w") as f:
+            f.write("hello")
+
+        loop = CustomLoop()
+        thread = threading.Thread(target=loop.run_forever)
+        thread.start()
+        try:
+            future = asyncio.run_coroutine_threadsafe(_read_and_lock(path), loop)
+            assert future.result(timeout=30) == "hello"
+        finally:
+            loop.call_soon_threadsafe(loop.stop)
+            thread.join()
+            loop.close()
+        with open(path) as f:
+            assert f.read() == "hello!"
+
+
+@pytest.mark.skipif(not HAS_UVLOOP, reason="uvloop not installed")
+def test_uvloop():
+    """Test file handles, locks and cleanup scopes under uvloop."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        scratch = os.path.join(tmpdir, "scratch")
+        with open(path, "w") as f:
+            f.write("hello")
+
+        async def main():
+            os.mkdir(scratch)
+            rapfiles.register_cleanup(scratch)
+            return await _read_and_lock(path)
+
+        loop = uvloop.new_event_loop()
+        try:
+            assert loop.run_until_complete(main()) == "hello"
+            loop.run_until_complete(loop.shutdown_asyncgens())
+        finally:
+            loop.close()
+        assert not os.path.exists(scratch)
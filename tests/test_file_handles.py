@@ -1,10 +1,30 @@
 """Test file handle operations."""
 
+import array
+import asyncio
+import builtins
 import pytest
+import select
 import tempfile
 import os
+import gc
+import pickle
+import socket
+import warnings
 
-from rapfiles import open
+from rapfiles import (
+    AsyncFile,
+    capture_open_stacks,
+    get_small_file_threshold,
+    open,
+    open_files,
+    read_exact,
+    recv_file,
+    send_file,
+    set_small_file_threshold,
+    use_backend,
+)
+from rapfiles.testing import inject_latency
 
 
 @pytest.mark.asyncio
@@ -42,6 +62,25 @@ async def test_open_write():
             os.unlink(test_file)
 
 
+@pytest.mark.asyncio
+async def test_writelines():
+    """Test writing an iterable of lines in one call."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "out.txt")
+
+        async with open(path, "w") as file:
+            assert await file.writelines(f"line {i}\n" for i in range(3)) is None
+            await file.writelines([])
+            await file.writelines(["é", b"!"])
+            with pytest.raises(TypeError, match="iterable of bytes or str"):
+                await file.writelines(["ok", 1])
+            with pytest.raises(TypeError):
+                await file.writelines(None)
+
+        with builtins.open(path, encoding="utf-8") as f:
+            assert f.read() == "line 0\nline 1\nline 2\né!"
+
+
 @pytest.mark.asyncio
 async def test_open_binary():
     """Test opening a file in binary mode."""
@@ -59,6 +98,80 @@ async def test_open_binary():
             os.unlink(test_file)
 
 
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_readinto(backend):
+    """Test reading into caller-provided buffers."""
+    data = bytes(range(256)) * 400  # Above the small-file threshold
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        with builtins.open(path, "wb") as f:
+            f.write(data)
+
+        async with open(path, "rb") as f:
+            buffer = bytearray(1000)
+            assert await f.readinto(buffer) == 1000
+            assert buffer == data[:1000]
+            view = memoryview(buffer)[10:20]
+            assert await f.readinto(view) == 10
+            assert buffer[10:20] == data[1000:1010]
+            n = await f.readinto1(buffer)
+            assert 0 < n <= 1000
+            assert buffer[:n] == data[1010 : 1010 + n]
+            assert await f.tell() == 1010 + n
+
+            await f.seek(len(data) - 5)
+            assert await f.readinto(buffer) == 5
+            assert buffer[:5] == data[-5:]
+            assert await f.readinto(buffer) == 0
+
+            for readonly in (b"abc", memoryview(b"abc"), "abc"):
+                with pytest.raises(TypeError, match="read-write bytes-like"):
+                    await f.readinto(readonly)
+
+        async with open(path, "rb") as f:
+            # Buffers of other item types are filled as raw bytes
+            numbers = array.array("I", [0] * 4)
+            assert await f.readinto(numbers) == 16
+            assert numbers.tobytes() == data[:16]
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("size", [100, 200_000])  # Preloaded and streamed
+@pytest.mark.parametrize("buffering", [0, 64])
+async def test_peek_and_read1(size, buffering):
+    """Test looking ahead with peek() and single reads with read1()."""
+    data = b"header\n" + bytes(range(256)) * (size // 256 + 1)
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        with builtins.open(path, "wb") as f:
+            f.write(data)
+
+        async with open(path, "rb", buffering=buffering) as f:
+            ahead = await f.peek(4)
+            assert len(ahead) >= 4 and data.startswith(ahead)
+            assert await f.peek() == ahead
+            assert await f.read1(2) == b"he"
+            assert await f.readline() == b"ader\n"
+            assert await f.tell() == 7
+
+            chunk = await f.read1(50)
+            assert 0 < len(chunk) <= 50 and data[7:].startswith(chunk)
+            assert await f.read1(0) == b""
+            ahead = await f.peek(1)
+            assert ahead and data[7 + len(chunk) :].startswith(ahead)
+            assert await f.read(3) == data[7 + len(chunk) : 10 + len(chunk)]
+
+            await f.seek(-2, os.SEEK_END)
+            assert await f.peek(10) == data[-2:]
+            assert await f.read1() == data[-2:]
+            assert await f.peek() == b""
+            assert await f.read1() == b""
+
+            with pytest.raises(ValueError, match="Invalid size"):
+                await f.peek(-1)
+
+
 @pytest.mark.asyncio
 async def test_readline():
     """Test reading a line from a file."""
@@ -101,6 +214,34 @@ async def test_readlines():
             os.unlink(test_file)
 
 
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_async_iteration(backend):
+    """Test iterating over lines with async for in text and binary mode."""
+    lines = [f"línea {i}\n" for i in range(10_000)]
+    lines[5] = "x" * 20_000 + "\n"  # Longer than one read chunk
+    lines[-1] = "no newline at end"
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "app.log")
+        with builtins.open(path, "w", encoding="utf-8") as f:
+            f.writelines(lines)
+
+        async with open(path, "r") as f:
+            assert [line async for line in f] == lines
+
+        async with open(path, "rb") as f:
+            async for line in f:
+                if line.startswith(b"l\xc3\xadnea 2"):
+                    break
+            # The position is right after the last line returned
+            offset = sum(len(line.encode()) for line in lines[:3])
+            assert await f.tell() == offset
+            assert await f.readline(3) == b"l\xc3\xad"
+            assert [line async for line in f][-1] == b"no newline at end"
+            with pytest.raises(StopAsyncIteration):
+                await f.__anext__()
+
+
 @pytest.mark.asyncio
 async def test_seek_tell():
     """Test seeking and telling position in a file."""
@@ -164,3 +305,1047 @@ async def test_read_size():
     finally:
         if os.path.exists(test_file):
             os.unlink(test_file)
+
+
+@pytest.mark.asyncio
+async def test_open_files_tracks_handles():
+    """Test that open handles are listed until closed."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "tracked.txt")
+
+        async with open(path, "w") as f:
+            info = [h for h in open_files() if h.path == path]
+            assert len(info) == 1
+            assert info[0].mode == "w"
+            assert info[0].opened_at > 0
+            assert info[0].stack is None
+            await f.write("data")
+
+        assert not [h for h in open_files() if h.path == path]
+
+        handle = await open(path, "r")
+        assert [h.path for h in open_files()].count(path) == 1
+        await handle.close()
+        assert path not in [h.path for h in open_files()]
+
+
+@pytest.mark.asyncio
+async def test_open_files_captures_stack():
+    """Test that capture_open_stacks records where a handle was opened."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "stack.txt")
+        capture_open_stacks()
+        try:
+            async with open(path, "w"):
+                (info,) = [h for h in open_files() if h.path == path]
+        finally:
+            capture_open_stacks(False)
+
+        assert "test_open_files_captures_stack" in info.stack
+
+
+@pytest.mark.asyncio
+async def test_unclosed_handle_warns():
+    """Test that collecting an unclosed handle emits a ResourceWarning."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "leak.txt")
+
+        with warnings.catch_warnings(record=True) as caught:
+            warnings.simplefilter("always")
+            handle = await open(path, "w")
+            del handle
+            # The runtime thread may release its last reference to the finished
+            # open() future a little later; the next call into the extension
+            # drops references it deferred
+            for _ in range(100):
+                await asyncio.sleep(0.01)
+                gc.collect()
+                open_files()
+                leaks = [w for w in caught if issubclass(w.category, ResourceWarning)]
+                if any(path in str(w.message) for w in leaks):
+                    break
+
+        assert any(path in str(w.message) for w in leaks)
+        assert path not in [h.path for h in open_files()]
+
+        # With stack capture the warning shows where the handle was opened
+        capture_open_stacks()
+        try:
+            with warnings.catch_warnings(record=True) as caught:
+                warnings.simplefilter("always")
+                handle = await open(path, "r")
+                del handle
+                for _ in range(100):
+                    await asyncio.sleep(0.01)
+                    gc.collect()
+                    open_files()
+                    if any(path in str(w.message) for w in caught):
+                        break
+        finally:
+            capture_open_stacks(False)
+        message = next(str(w.message) for w in caught if path in str(w.message))
+        assert "opened at:" in message
+        assert "test_unclosed_handle_warns" in message
+
+
+@pytest.mark.asyncio
+async def test_unclosed_handle_flushes_writes():
+    """Test that collecting an unclosed handle keeps its buffered writes."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "leak.bin")
+
+        with warnings.catch_warnings(record=True) as caught:
+            warnings.simplefilter("always")
+            handle = await open(path, "wb")
+            await handle.write(b"pending data")
+            del handle
+            for _ in range(100):
+                await asyncio.sleep(0.01)
+                gc.collect()
+                open_files()
+                if any(path in str(w.message) for w in caught):
+                    break
+
+        assert any(issubclass(w.category, ResourceWarning) for w in caught)
+        # The handle is flushed and closed before the warning is emitted
+        with builtins.open(path, "rb") as f:
+            assert f.read() == b"pending data"
+
+
+@pytest.mark.asyncio
+async def test_small_file_fast_path():
+    """Test that small read-only files are loaded whole when opened."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "small.txt")
+        with builtins.open(path, "wb") as f:
+            f.write(b"line one\nline two\n")
+
+        assert get_small_file_threshold() == 64 * 1024
+        async with open(path, "r") as f:
+            # Served from the contents loaded by open()
+            with builtins.open(path, "wb") as g:
+                g.write(b"replaced")
+            assert await f.readline() == "line one\n"
+            assert await f.tell() == 9
+            assert await f.readline(4) == "line"
+            assert await f.read() == " two\n"
+            assert await f.seek(-4, 2) == 14
+            assert await f.readlines() == ["two\n"]
+
+        set_small_file_threshold(0)
+        try:
+            async with open(path, "rb") as f:
+                assert await f.read() == b"replaced"
+        finally:
+            set_small_file_threshold(64 * 1024)
+
+
+@pytest.mark.asyncio
+async def test_read_sees_data_appended_after_open():
+    """Test that a read-only handle reads data appended after it was opened."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "app.log")
+        with builtins.open(path, "wb") as f:
+            f.write(b"first\n")
+
+        # Small enough to be loaded whole by open()
+        async with open(path, "rb") as f:
+            assert await f.read() == b"first\n"
+            with builtins.open(path, "ab") as g:
+                g.write(b"second\n")
+            assert await f.read() == b"second\n"
+            with builtins.open(path, "ab") as g:
+                g.write(b"third\n")
+            assert await f.readline() == b"third\n"
+            assert await f.readline() == b""
+
+        # Handles from dup() keep their own position once reading the file
+        async with open(path, "rb") as f:
+            assert await f.readline() == b"first\n"
+            async with await f.dup() as g:
+                assert await f.read() == b"second\nthird\n"
+                with builtins.open(path, "ab") as h:
+                    h.write(b"fourth\n")
+                assert await f.read() == b"fourth\n"
+                assert await g.read() == b"second\nthird\n"
+                assert await g.read() == b"fourth\n"
+
+
+@pytest.mark.asyncio
+async def test_small_file_threshold_boundary():
+    """Test that files above the threshold use a regular handle."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        with builtins.open(path, "wb") as f:
+            f.write(b"0123456789")
+
+        set_small_file_threshold(4)
+        try:
+            async with open(path, "rb") as f:
+                assert await f.read(3) == b"012"
+                assert await f.seek(8) == 8
+                assert await f.read() == b"89"
+            async with open(path, "rb") as f:
+                assert await f.readline(4) == b"0123"
+                assert await f.tell() == 4
+        finally:
+            set_small_file_threshold(64 * 1024)
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(not hasattr(os, "mkfifo"), reason="requires named pipes")
+async def test_access_modes_concurrent_reads():
+    """Test that exclusive handles reject, and serialized ones queue, overlap."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        fifo = os.path.join(tmpdir, "pipe")
+        os.mkfifo(fifo)
+        # Opening read-write never blocks and keeps a writer attached
+        writer = os.open(fifo, os.O_RDWR)
+        try:
+            async with open(fifo, "rb", access="exclusive") as f:
+                assert f.access == "exclusive"
+                pending = asyncio.ensure_future(f.read(5))
+                await asyncio.sleep(0.1)
+                with pytest.raises(RuntimeError, match="access='exclusive'"):
+                    await f.read(1)
+                os.write(writer, b"hello")
+                assert await pending == b"hello"
+
+            async with open(fifo, "rb") as f:
+                assert f.access == "serialized"
+                first = asyncio.ensure_future(f.read(3))
+                await asyncio.sleep(0.1)
+                second = asyncio.ensure_future(f.read(3))
+                os.write(writer, b"abc")
+                assert await first == b"abc"
+                os.write(writer, b"def")
+                assert await second == b"def"
+        finally:
+            os.close(writer)
+
+
+@pytest.mark.asyncio
+async def test_positional_access():
+    """Test concurrent pread/pwrite on a positional handle."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        with builtins.open(path, "wb") as f:
+            f.write(b"\0" * 4096)
+
+        async with open(path, "rb+", access="positional") as f:
+            assert f.access == "positional"
+            written = await asyncio.gather(
+                *(f.pwrite(bytes([i]) * 256, i * 256) for i in range(16))
+            )
+            assert written == [256] * 16
+            chunks = await asyncio.gather(*(f.pread(256, i * 256) for i in range(16)))
+            assert chunks == [bytes([i]) * 256 for i in range(16)]
+            # Positional calls neither use nor move the cursor
+            assert await f.tell() == 0
+            assert await f.read(2) == b"\0\0"
+            assert await f.pread(10, 4090) == b"\x0f" * 6
+
+        async with open(path, "rb") as f:
+            with pytest.raises(ValueError, match="access='positional'"):
+                await f.pread(1, 0)
+            with pytest.raises(ValueError, match="access='positional'"):
+                await f.pwrite(b"x", 0)
+
+        with pytest.raises(ValueError, match="Invalid access mode"):
+            await open(path, "rb", access="shared")
+
+
+@pytest.mark.asyncio
+async def test_reopen_and_refresh():
+    """Test that a handle follows its path after the file is rotated."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "app.log")
+        rotated = path + ".1"
+
+        async with open(path, "a") as log:
+            await log.write("old\n")
+            assert await log.refresh() is False
+
+            os.rename(path, rotated)
+            await log.write("still old\n")
+            assert await log.refresh() is True
+            assert await log.refresh() is False
+            await log.write("new\n")
+
+            await log.reopen()
+            await log.write("more\n")
+
+        with builtins.open(rotated) as f:
+            assert f.read() == "old\nstill old\n"
+        with builtins.open(path) as f:
+            assert f.read() == "new\nmore\n"
+
+
+@pytest.mark.asyncio
+async def test_refresh_read_handle():
+    """Test that read handles keep their file until a new one appears."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        with builtins.open(path, "w") as f:
+            f.write("first")
+
+        async with open(path, "r") as f:
+            assert await f.read() == "first"
+            os.remove(path)
+            assert await f.refresh() is False
+            with pytest.raises(FileNotFoundError):
+                await f.reopen()
+
+            with builtins.open(path, "w") as new:
+                new.write("second")
+            assert await f.refresh() is True
+            assert await f.read() == "second"
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_dup_has_independent_cursor(backend):
+    """Test that a duplicated handle shares the file but not the position."""
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "shared.txt")
+
+        async with open(path, "w+") as writer:
+            await writer.write("one\n")
+            async with await writer.dup() as reader:
+                assert await reader.tell() == 4
+                await reader.seek(0)
+                await writer.write("two\nthree")
+                # Renaming the path doesn't affect either handle
+                os.rename(path, path + ".moved")
+                assert await reader.readline() == "one\n"
+                assert await reader.read(3) == "two"
+                assert await writer.tell() == 13
+                assert await reader.readlines() == ["\n", "three"]
+                await reader.seek(-5, 2)
+                assert await reader.read() == "three"
+            assert len(open_files()) == 1
+
+        async with open(path + ".moved", "ab") as appender:
+            async with await appender.dup() as other:
+                await other.write(b"!")
+                await appender.write(b"?")
+        with builtins.open(path + ".moved", "rb") as f:
+            assert f.read() == b"one\ntwo\nthree!?"
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="opener is Unix only")
+async def test_opener():
+    """Test that an opener callable provides the file descriptor."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        dir_fd = os.open(tmpdir, os.O_RDONLY)
+        calls = []
+
+        def opener(path, flags):
+            calls.append((path, flags))
+            return os.open(path, flags, dir_fd=dir_fd)
+
+        try:
+            async with open("data.txt", "w", opener=opener) as f:
+                await f.write("via opener")
+                assert await f.reopen() is None
+                await f.write("again")
+            assert len(calls) == 2
+            flags = calls[0][1]
+            assert flags & os.O_CREAT and flags & os.O_TRUNC
+            assert flags & os.O_ACCMODE == os.O_WRONLY
+
+            async with open("data.txt", "r", opener=opener) as f:
+                assert await f.read() == "again"
+            assert calls[2][1] & os.O_ACCMODE == os.O_RDONLY
+
+            def failing(path, flags):
+                raise PermissionError("denied by opener")
+
+            with pytest.raises(PermissionError, match="denied by opener"):
+                await open("data.txt", "r", opener=failing)
+            with pytest.raises(ValueError, match="opener returned -1"):
+                await open("data.txt", "r", opener=lambda path, flags: -1)
+        finally:
+            os.close(dir_fd)
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="file descriptors are Unix only")
+async def test_file_descriptor():
+    """Test wrapping a file descriptor, with and without closefd."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        fd = os.open(path, os.O_RDWR | os.O_CREAT)
+        try:
+            async with open(fd, "w", closefd=False) as f:
+                assert f.name == fd
+                await f.write("kept open")
+                with pytest.raises(ValueError, match="file descriptor"):
+                    await f.reopen()
+            # The descriptor still works and shares the file position
+            assert os.write(fd, b"!") == 1
+            os.lseek(fd, 0, os.SEEK_SET)
+            async with open(fd, "r", closefd=False) as f:
+                assert await f.read() == "kept open!"
+        finally:
+            os.close(fd)
+
+        fd = os.open(path, os.O_RDWR)
+        async with open(fd, "a") as f:
+            await f.write("?")
+        with pytest.raises(OSError):
+            os.fstat(fd)
+        with builtins.open(path) as f:
+            assert f.read() == "kept open!?"
+
+        with pytest.raises(ValueError, match="closefd=False with file name"):
+            await open(path, "r", closefd=False)
+        with pytest.raises(ValueError, match="negative file descriptor"):
+            await open(-1, "r")
+        with pytest.raises(OSError, match="Bad file descriptor"):
+            await open(2**20, "r")
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="file descriptors are Unix only")
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_fileno_and_from_fd(backend):
+    """Test passing descriptors between rapfiles and descriptor-based APIs."""
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "small.txt")
+        with builtins.open(path, "w") as f:
+            f.write("small file")
+
+        # Loaded whole at open, then opened again at the same position
+        async with open(path, "rb") as f:
+            assert await f.read(6) == b"small "
+            fd = f.fileno()
+            assert f.fileno() == fd
+            assert os.fstat(fd).st_size == 10
+            assert os.lseek(fd, 0, os.SEEK_CUR) == 6
+            assert await f.read() == b"file"
+        with pytest.raises(ValueError, match="closed"):
+            f.fileno()
+
+        copy = os.path.join(tmpdir, "copy.txt")
+        async with open(copy, "wb") as out, open(path, "rb") as src:
+            assert os.sendfile(out.fileno(), src.fileno(), 0, 10) == 10
+        with builtins.open(copy) as f:
+            assert f.read() == "small file"
+
+        read_fd, write_fd = os.pipe()
+        async with AsyncFile.from_fd(read_fd, "r") as reader:
+            assert reader.fileno() == read_fd
+            assert select.select([read_fd], [], [], 0)[0] == []
+            os.write(write_fd, "héllo".encode())
+            os.close(write_fd)
+            assert select.select([reader.fileno()], [], [], 0)[0] == [read_fd]
+            assert await reader.read() == "héllo"
+        with pytest.raises(OSError):
+            os.fstat(read_fd)
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="file descriptors are Unix only")
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_detach_fd(backend):
+    """Test taking the descriptor out of a handle."""
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "file.txt")
+        f = await open(path, "wb")
+        await f.write(b"abc")
+        fd = await f.detach_fd()
+        assert f.closed
+        assert os.lseek(fd, 0, os.SEEK_CUR) == 3
+        os.write(fd, b"d")
+        os.close(fd)
+        with builtins.open(path, "rb") as check:
+            assert check.read() == b"abcd"
+
+        with builtins.open(path, "wb") as out:
+            out.write(b"line 1\nline 2\n" * 10_000)
+        f = await open(path, "rb")
+        assert await f.readline() == b"line 1\n"
+        # The read-ahead of readline() is given back
+        fd = await f.detach_fd()
+        assert os.read(fd, 7) == b"line 2\n"
+        os.close(fd)
+        with pytest.raises(ValueError, match="closed"):
+            await f.detach_fd()
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="file descriptors are Unix only")
+async def test_send_and_recv_file():
+    """Test passing handles over a Unix socket."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "file.txt")
+        with builtins.open(path, "w") as out:
+            out.write("hello world")
+        sender, receiver = socket.socketpair()
+        sender.setblocking(False)
+        with sender, receiver:
+            async with open(path, "rb") as f, open(path, "ab") as g:
+                assert await f.read(6) == b"hello "
+                # Back to back, as one stream of data
+                await send_file(sender, f)
+                await send_file(sender.fileno(), g)
+                async with await recv_file(receiver) as received:
+                    assert received.name == path
+                    assert received.mode == "rb"
+                    assert await received.read() == b"world"
+                async with await recv_file(receiver.fileno()) as received:
+                    await received.write(b"!")
+            with builtins.open(path) as check:
+                assert check.read() == "hello world!"
+
+            async with open(path, "r") as text:
+                with pytest.raises(TypeError):
+                    await send_file(sender, text)
+            sender.send(b"\x03\0\0\0abc")
+            with pytest.raises(OSError, match="send_file"):
+                await recv_file(receiver)
+            sender.close()
+            with pytest.raises(OSError, match="Failed to receive file"):
+                await recv_file(receiver)
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="file descriptors are Unix only")
+async def test_pickle_file():
+    """Test that pickled handles share the open file."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "file.bin")
+        with builtins.open(path, "wb") as out:
+            out.write(b"0123456789")
+        async with open(path, "rb", access="positional") as f:
+            assert await f.read(4) == b"0123"
+            async with pickle.loads(pickle.dumps(f)) as copy:
+                assert copy.access == "positional"
+                assert copy.fileno() != f.fileno()
+                assert await copy.pread(2, 8) == b"89"
+                assert await copy.read() == b"456789"
+
+
+@pytest.mark.asyncio
+async def test_line_buffering():
+    """Test that buffering=1 flushes text writes at newlines."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "app.log")
+
+        async with open(path, "w", buffering=1) as f:
+            assert f.line_buffering is True
+            for i in range(20):
+                await f.write(f"line {i}\n")
+                with builtins.open(path) as reader:
+                    assert reader.read().endswith(f"line {i}\n")
+
+        async with open(path, "r") as f:
+            assert f.line_buffering is False
+
+        with warnings.catch_warnings(record=True) as caught:
+            warnings.simplefilter("always")
+            f = await open(path, "rb", buffering=1)
+        assert [w.category for w in caught] == [RuntimeWarning]
+        assert "line buffering" in str(caught[0].message)
+        assert f.line_buffering is False
+        await f.close()
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_truncate(backend):
+    """Test shrinking and extending an open file without moving the cursor."""
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+
+        async with open(path, "wb+") as f:
+            await f.write(b"0123456789")
+            await f.seek(4)
+            assert await f.truncate() == 4
+            assert await f.tell() == 4
+            assert await f.truncate(8) == 8
+            await f.seek(0)
+            assert await f.read() == b"0123\0\0\0\0"
+            with pytest.raises(ValueError, match="Negative size"):
+                await f.truncate(-1)
+
+            async with await f.dup() as other:
+                await other.seek(2)
+                assert await other.truncate() == 2
+        assert os.path.getsize(path) == 2
+
+        async with open(path, "r") as f:
+            with pytest.raises(IOError, match="Failed to truncate"):
+                await f.truncate(0)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_flush_and_sync(backend):
+    """Test flushing and syncing an open file."""
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "wal.log")
+
+        async with open(path, "w") as f:
+            await f.write("entry 1\n")
+            assert await f.flush() is None
+            with builtins.open(path) as reader:
+                assert reader.read() == "entry 1\n"
+            await f.write("entry 2\n")
+            assert await f.fsync() is None
+            await f.write("entry 3\n")
+            assert await f.fdatasync() is None
+        with builtins.open(path) as reader:
+            assert reader.read() == "entry 1\nentry 2\nentry 3\n"
+
+        async with open(path, "r") as f:
+            await f.flush()
+            await f.fsync()
+
+
+@pytest.mark.asyncio
+async def test_file_attributes():
+    """Test the name, mode and closed attributes."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+
+        async with open(path, "w") as f:
+            assert (f.name, f.mode, f.closed) == (path, "w", False)
+            await f.write("data")
+        assert f.closed
+        with pytest.raises(ValueError, match="closed file"):
+            await f.write("more")
+
+        f = await open(path, "rb")
+        assert (f.name, f.mode) == (path, "rb")
+        copy = await f.dup()
+        await f.close()
+        assert f.closed
+        with pytest.raises(ValueError, match="closed file"):
+            await f.read()
+        with pytest.raises(ValueError, match="closed file"):
+            async for _ in f:
+                pass
+        # Closing again is allowed, and duplicates stay open
+        await f.close()
+        assert not copy.closed
+        assert await copy.read() == b"data"
+        await copy.close()
+
+
+@pytest.mark.asyncio
+async def test_capability_probes():
+    """Test readable(), writable() and seekable()."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        expected = {
+            "wb": (False, True),
+            "rb": (True, False),
+            "ab+": (True, True),
+            "r+": (True, True),
+        }
+        for mode, (readable, writable) in expected.items():
+            async with open(path, mode) as f:
+                assert (f.readable(), f.writable(), f.seekable()) == (
+                    readable,
+                    writable,
+                    True,
+                )
+        with pytest.raises(ValueError, match="closed file"):
+            f.readable()
+
+        if hasattr(os, "mkfifo"):
+            fifo = os.path.join(tmpdir, "pipe")
+            os.mkfifo(fifo)
+            writer = os.open(fifo, os.O_RDWR)
+            try:
+                async with open(fifo, "rb") as f:
+                    assert f.readable() and not f.seekable()
+            finally:
+                os.close(writer)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("access", ["serialized", "positional"])
+async def test_update_mode_keeps_contents(access):
+    """Test that "r+" modes neither truncate nor create the file."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        with pytest.raises(FileNotFoundError):
+            await open(path, "rb+", access=access)
+        assert not os.path.exists(path)
+
+        with builtins.open(path, "wb") as f:
+            f.write(b"0123456789")
+        async with open(path, "rb+", access=access) as f:
+            if access == "positional":
+                await f.pwrite(b"ab", 4)
+            else:
+                assert await f.read(4) == b"0123"
+                await f.write(b"ab")
+        with builtins.open(path, "rb") as f:
+            assert f.read() == b"0123ab6789"
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_close_releases_file(backend):
+    """Test that close() flushes and releases the descriptor."""
+    fd_dir = "/proc/self/fd"
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "out.bin")
+        f = await open(path, "wb")
+        if os.path.isdir(fd_dir):
+            before = len(os.listdir(fd_dir))
+        # A write started before close() completes first
+        written, closed = await asyncio.gather(f.write(b"x" * 100_000), f.close())
+        assert (written, closed) == (100_000, None)
+        assert os.path.getsize(path) == 100_000
+        if os.path.isdir(fd_dir):
+            assert len(os.listdir(fd_dir)) == before - 1
+        with pytest.raises(ValueError, match="closed file"):
+            await f.write(b"late")
+
+        async with open(path, "rb") as f:
+            pass
+        assert await f.close() is None
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_operations_run_in_call_order(backend):
+    """Test that concurrent operations on a handle run in the order they were called."""
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "out.bin")
+        f = await open(path, "wb+")
+        # The writes start last but were called first, so they still go first
+        with inject_latency(path, 0.2, ops=["write"]):
+            results = await asyncio.gather(
+                f.write(b"abc"), f.write(b"def"), f.seek(1), f.read(2), f.close()
+            )
+        assert results == [3, 3, 1, b"bc", None]
+        with builtins.open(path, "rb") as g:
+            assert g.read() == b"abcdef"
+
+        # A cancelled operation gives up its place instead of blocking the queue
+        f = await open(path, "rb")
+        with inject_latency(path, 0.2, ops=["read"]):
+            read = asyncio.ensure_future(f.read())
+            seek = f.seek(2)
+            await asyncio.sleep(0.05)
+            read.cancel()
+            assert await asyncio.wait_for(seek, 5) == 2
+        assert await f.read() == b"cdef"
+        await f.close()
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+@pytest.mark.parametrize("buffering", [-1, 0, 100])
+async def test_line_reads_keep_position(backend, buffering):
+    """Test that data read ahead by line reads is not lost to other operations."""
+    lines = [f"{i:05d} ".encode() + b"x" * (i % 300) + b"\n" for i in range(1000)]
+    data = b"".join(lines)
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "big.txt")
+        async with open(path, "wb+", buffering=buffering) as f:
+            await f.write(data)
+            await f.seek(0)
+            assert await f.readline() == lines[0]
+            assert await f.tell() == len(lines[0])
+            assert await f.read(3) == lines[1][:3]
+            assert await f.readline() == lines[1][3:]
+            assert await f.readline(4) == lines[2][:4]
+            assert await f.readlines(2) == [lines[2][4:], lines[3]]
+            async with await f.dup() as other:
+                assert await other.readline() == lines[4]
+            assert await f.readline() == lines[4]
+            await f.write(b"!")
+            await f.seek(0)
+            assert await f.readlines() == lines[:5] + [b"!" + lines[5][1:]] + lines[6:]
+            assert await f.readline() == b""
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="Timeouts need poll()")
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_read_exact(backend):
+    """Test reading exactly n bytes from pipes and files."""
+    read_fd, write_fd = os.pipe()
+    with use_backend(backend):
+        async with AsyncFile.from_fd(read_fd, "rb") as pipe:
+            os.write(write_fd, b"abc")
+            reader = asyncio.ensure_future(pipe.read_exact(6))
+            await asyncio.sleep(0.05)
+            assert not reader.done()
+            os.write(write_fd, b"def")
+            assert await reader == b"abcdef"
+
+            # Bytes read before a timeout are kept for the next read
+            os.write(write_fd, b"gh")
+            with pytest.raises(TimeoutError):
+                await read_exact(pipe, 4, timeout=0.05)
+            os.write(write_fd, b"ij")
+            assert await read_exact(pipe, 4, timeout=1) == b"ghij"
+
+            os.write(write_fd, b"kl")
+            os.close(write_fd)
+            with pytest.raises(asyncio.IncompleteReadError) as excinfo:
+                await pipe.read_exact(3, timeout=1)
+            assert excinfo.value.partial == b"kl"
+            assert excinfo.value.expected == 3
+            assert await pipe.read_exact(0) == b""
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        with builtins.open(path, "wb") as f:
+            f.write(b"0123456789")
+        assert await read_exact(path, 4) == b"0123"
+        async with open(path, "r") as f:
+            assert await f.read(2) == "01"
+            assert await read_exact(f, 3, timeout=1) == b"234"
+        with pytest.raises(asyncio.IncompleteReadError):
+            await read_exact(path, 11)
+        with pytest.raises(FileNotFoundError):
+            await read_exact(os.path.join(tmpdir, "missing"), 1)
+        with pytest.raises(ValueError, match="Invalid timeout"):
+            await read_exact(path, 1, timeout=-1)
+        with pytest.raises(TypeError):
+            await read_exact(42, 1)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("backend", ["tokio", "threadpool"])
+async def test_exclusive_creation(backend):
+    """Test that "x" modes create the file and refuse existing ones."""
+    with use_backend(backend), tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "new.txt")
+        async with open(path, "x") as f:
+            await f.write("created")
+        with builtins.open(path) as existing:
+            assert existing.read() == "created"
+        for mode in ("x", "x+", "xb", "xb+"):
+            with pytest.raises(FileExistsError):
+                await open(path, mode)
+        with builtins.open(path) as existing:
+            assert existing.read() == "created"
+
+        other = os.path.join(tmpdir, "new.bin")
+        async with open(other, "xb+") as f:
+            await f.write(b"data")
+            await f.seek(0)
+            assert await f.read() == b"data"
+        if os.name == "posix":
+            flags = []
+
+            def opener(path, mode):
+                flags.append(mode)
+                return os.open(path, mode)
+
+            with pytest.raises(FileExistsError):
+                await open(other, "xb", opener=opener)
+            assert flags[0] & os.O_EXCL and not flags[0] & os.O_TRUNC
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("encoding", ["utf-8", "utf-16", "latin-1", "ascii", "cp1252"])
+async def test_text_encoding(encoding):
+    """Test that text mode reads and writes with the given encoding."""
+    text = "café crème\nnaïve\nend"
+    if encoding == "ascii":
+        text = "cafe\nnaive\nend"
+    elif encoding == "cp1252":
+        text += " €"
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "text.txt")
+        async with open(path, "w", encoding=encoding) as f:
+            assert await f.write(text[:5]) == 5
+            await f.writelines([text[5:]])
+        with builtins.open(path, encoding=encoding, newline="") as f:
+            assert f.read() == text
+
+        lines = text.splitlines(keepends=True)
+        async with open(path, "r", encoding=encoding) as f:
+            assert await f.read(3) == text[:3]
+            position = await f.tell()
+            assert await f.readline() == lines[0][3:]
+            assert [line async for line in f] == lines[1:]
+            await f.seek(position)
+            assert await f.read() == text[3:]
+        async with open(path, "r", encoding=encoding) as f:
+            assert await f.readlines() == lines
+
+
+@pytest.mark.asyncio
+async def test_text_encoding_errors():
+    """Test decoding errors, error handlers and argument validation."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        with builtins.open(path, "wb") as f:
+            f.write(b"abc\xffdef\n")
+        async with open(path, "r") as f:
+            with pytest.raises(UnicodeDecodeError) as info:
+                await f.read()
+        assert (info.value.encoding, info.value.start) == ("utf-8", 3)
+        async with open(path, "r", errors="replace") as f:
+            assert await f.readline() == "abc�def\n"
+        async with open(path, "r", encoding="latin-1") as f:
+            assert await f.read() == "abcÿdef\n"
+        # A read never splits a character
+        async with open(path, "w") as f:
+            await f.write("éé")
+        async with open(path, "r") as f:
+            assert await f.read(1) == "é"
+            assert await f.tell() == 2
+
+        with pytest.raises(LookupError):
+            open(path, "r", encoding="no-such-codec")
+        with pytest.raises(ValueError, match="binary mode"):
+            open(path, "rb", encoding="utf-8")
+
+
+@pytest.mark.asyncio
+async def test_awaited_open_decodes_text():
+    """Test that awaiting open() applies the text options like async with."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        with builtins.open(path, "wb") as f:
+            f.write(b"caf\xe9\r\nok\n")
+
+        f = await open(path, "r", encoding="latin-1")
+        try:
+            assert await f.readline() == "café\n"
+            assert await f.read() == "ok\n"
+        finally:
+            await f.close()
+
+        f = await open(path, "w", encoding="utf-16", newline="\r\n")
+        try:
+            assert await f.write("a\nb") == 3
+        finally:
+            await f.close()
+        with builtins.open(path, "rb") as f:
+            assert f.read() == "a\r\nb".encode("utf-16")
+
+        f = await open(path, "rb")
+        try:
+            assert await f.read() == "a\r\nb".encode("utf-16")
+        finally:
+            await f.close()
+
+
+@pytest.mark.asyncio
+async def test_text_error_handlers():
+    """Test that error handlers apply when decoding and encoding."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        raw = b"caf\xe9\nok\n"
+        with builtins.open(path, "wb") as f:
+            f.write(raw)
+        expected = {
+            "replace": "caf\ufffd\n",
+            "ignore": "caf\n",
+            "backslashreplace": "caf\\xe9\n",
+            "surrogateescape": "caf\udce9\n",
+        }
+        for errors, line in expected.items():
+            async with open(path, "r", errors=errors) as f:
+                assert await f.readline() == line
+                assert await f.read() == "ok\n"
+
+        # Undecodable bytes are written back unchanged
+        async with open(path, "r", errors="surrogateescape") as f:
+            text = await f.read()
+        async with open(path, "w", errors="surrogateescape") as f:
+            await f.write(text)
+        with builtins.open(path, "rb") as f:
+            assert f.read() == raw
+
+        async with open(path, "w", encoding="ascii") as f:
+            with pytest.raises(UnicodeEncodeError):
+                await f.write("é")
+        async with open(path, "w", encoding="ascii", errors="backslashreplace") as f:
+            await f.write("é\n")
+        with builtins.open(path, "rb") as f:
+            assert f.read() == b"\\xe9\n"
+
+        # Lines found in decoded UTF-16 text stay aligned despite replacements
+        with builtins.open(path, "wb") as f:
+            f.write("a\n".encode("utf-16") + b"\x00\xdc" + "b\nc\n".encode("utf-16-le"))
+        async with open(path, "r", encoding="utf-16", errors="replace") as f:
+            assert [line async for line in f] == ["a\n", "\ufffdb\n", "c\n"]
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize("encoding", ["utf-8", "utf-16"])
+@pytest.mark.parametrize("newline", [None, "", "\n", "\r", "\r\n"])
+async def test_text_newline(encoding, newline):
+    """Test that newline= splits and translates lines like the built-in open()."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.txt")
+        with builtins.open(path, "w", encoding=encoding, newline="") as f:
+            f.write("a\r\nb\rc\nd\r\r\ne\r")
+
+        def expected(read):
+            with builtins.open(path, encoding=encoding, newline=newline) as f:
+                return read(f)
+
+        async with open(path, "r", encoding=encoding, newline=newline) as f:
+            assert await f.read() == expected(lambda f: f.read())
+        async with open(path, "r", encoding=encoding, newline=newline) as f:
+            assert await f.readlines() == expected(lambda f: f.readlines())
+        async with open(path, "r", encoding=encoding, newline=newline) as f:
+            assert [line async for line in f] == expected(list)
+        async with open(path, "r", encoding=encoding, newline=newline) as f:
+            # "\r\n" split between reads is still one line end
+            assert await f.readline(1) == "a"
+            assert await f.read(1) == expected(lambda f: f.read(2))[1]
+            assert await f.readline() == expected(lambda f: [f.read(2), *f][1])
+
+        async with open(path, "w", encoding=encoding, newline=newline) as f:
+            assert await f.write("x\ny\n") == 4
+        with builtins.open(path, encoding=encoding, newline="") as f:
+            written = f.read()
+        line_end = {None: os.linesep, "": "\n"}.get(newline, newline)
+        assert written == "x\ny\n".replace("\n", line_end)
+
+    with pytest.raises(ValueError, match="illegal newline"):
+        open("data.txt", newline="\n\n")
+    with pytest.raises(ValueError, match="binary mode"):
+        open("data.txt", "rb", newline="")
+
+
+@pytest.mark.asyncio
+async def test_text_detach():
+    """Test switching from text to binary reads at the text position."""
+    body = bytes(range(256))
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "response")
+        with builtins.open(path, "wb") as f:
+            f.write(b"HTTP/1.1 200 OK\r\nLength: 256\r\n\r\n" + body)
+
+        async with open(path, "r", encoding="latin-1") as f:
+            assert [await f.readline() for _ in range(3)][2] == "\n"
+            raw = await f.detach()
+            assert await raw.read() == body
+            with pytest.raises(ValueError, match="detached"):
+                await f.readline()
+            with pytest.raises(ValueError, match="detached"):
+                await f.detach()
+        assert raw.closed
+
+        with builtins.open(path, "wb") as f:
+            f.write(b"head\r\nx\xc3\xa9\n")
+        async with open(path, "r") as f:
+            # The "\n" of a "\r\n" split between reads is skipped
+            assert await f.read(5) == "head\n"
+            raw = await f.detach()
+            assert await raw.read() == b"x\xc3\xa9\n"
+        async with open(path, "r") as f:
+            # So is the start of a character split by a size limit
+            await f.readline()
+            assert await f.readline(2) == "x"
+            raw = await f.detach()
+            assert await raw.read() == b"\xc3\xa9\n"
@@ -3,16 +3,33 @@
 import pytest
 import tempfile
 import os
+import shutil
+import socket
+import stat
+import sys
 import uuid
 
 from rapfiles import (
     copy_file,
+    copy_files,
+    copytree,
     move_file,
+    move,
     rename,
+    replace,
     remove_file,
+    unlink,
+    touch,
+    truncate,
+    chmod,
+    chown,
+    lchown,
     hard_link,
+    link,
     symlink,
     canonicalize,
+    readlink,
+    realpath,
     write_file,
     write_file_bytes,
     read_file,
@@ -55,7 +72,7 @@ async def test_copy_file_binary():
         original_data = b"\x00\x01\x02\x03\xff\xfe\xfd"
         await write_file_bytes(src, original_data)
 
-        await copy_file(src, dst)
+        assert await copy_file(src, dst) == len(original_data)
 
         assert await exists(src)
         assert await exists(dst)
@@ -109,7 +126,7 @@ async def test_move_file():
 
 @pytest.mark.asyncio
 async def test_rename():
-    """Test renaming a file (alias for move_file)."""
+    """Test renaming a file."""
     with tempfile.TemporaryDirectory() as tmpdir:
         src = os.path.join(tmpdir, _unique_name("old_name.txt"))
         dst = os.path.join(tmpdir, _unique_name("new_name.txt"))
@@ -124,6 +141,29 @@ async def test_rename():
         assert content == "Test content"
 
 
+@pytest.mark.asyncio
+async def test_rename_and_replace_errors():
+    """Test rename/replace errors and that replace overwrites directories."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        missing = os.path.join(tmpdir, "missing")
+        with pytest.raises(FileNotFoundError):
+            await rename(missing, os.path.join(tmpdir, "dst"))
+        with pytest.raises(FileNotFoundError):
+            await replace(missing, os.path.join(tmpdir, "dst"))
+
+        src = os.path.join(tmpdir, "src")
+        dst = os.path.join(tmpdir, "dst")
+        os.makedirs(os.path.join(src, "inner"))
+        os.mkdir(dst)
+        if sys.platform != "win32":
+            # An empty directory is replaced, a non-empty one is not
+            await replace(src, dst)
+            assert os.path.isdir(os.path.join(dst, "inner"))
+            os.mkdir(src)
+            with pytest.raises(OSError):
+                await replace(src, dst)
+
+
 @pytest.mark.asyncio
 async def test_move_file_overwrites():
     """Test that move_file overwrites existing destination."""
@@ -152,6 +192,73 @@ async def test_move_file_nonexistent_source():
             await move_file(src, dst)
 
 
+@pytest.mark.asyncio
+async def test_move():
+    """Test shutil.move() semantics within a filesystem."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src.txt")
+        target_dir = os.path.join(tmpdir, "target")
+        os.mkdir(target_dir)
+        await write_file(src, "Content")
+
+        # Into an existing directory
+        moved = await move(src, target_dir)
+        assert moved == os.path.join(target_dir, "src.txt")
+        assert not os.path.exists(src)
+        assert await read_file(moved) == "Content"
+
+        # To an explicit path, including a directory
+        assert await move(target_dir, os.path.join(tmpdir, "renamed")) == (
+            os.path.join(tmpdir, "renamed")
+        )
+        assert os.listdir(os.path.join(tmpdir, "renamed")) == ["src.txt"]
+
+        await write_file(src, "Again")
+        os.mkdir(target_dir)
+        await write_file(os.path.join(target_dir, "src.txt"), "Existing")
+        with pytest.raises(FileExistsError):
+            await move(src, target_dir)
+        with pytest.raises(FileNotFoundError):
+            await move(os.path.join(tmpdir, "missing"), target_dir)
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="uses /dev/shm")
+async def test_move_across_filesystems():
+    """Test the copy fallback when moving between filesystems."""
+    if not os.path.isdir("/dev/shm") or not os.access("/dev/shm", os.W_OK):
+        pytest.skip("no writable /dev/shm")
+    with tempfile.TemporaryDirectory(dir="/dev/shm") as shm, (
+        tempfile.TemporaryDirectory()
+    ) as disk:
+        if os.stat(shm).st_dev == os.stat(disk).st_dev:
+            pytest.skip("/dev/shm is on the same filesystem")
+        tree = os.path.join(shm, "tree")
+        os.makedirs(os.path.join(tree, "sub"))
+        data = os.path.join(tree, "sub", "data.bin")
+        await write_file_bytes(data, b"x" * 100_000)
+        os.chmod(data, 0o640)
+        os.utime(data, (1_000_000, 2_000_000))
+        os.symlink("sub/data.bin", os.path.join(tree, "link"))
+
+        moved = await move(tree, disk)
+        assert moved == os.path.join(disk, "tree") and not os.path.exists(tree)
+        copied = os.path.join(moved, "sub", "data.bin")
+        assert await read_file_bytes(copied) == b"x" * 100_000
+        assert os.stat(copied).st_mode & 0o777 == 0o640
+        assert os.stat(copied).st_mtime == 2_000_000
+        assert os.readlink(os.path.join(moved, "link")) == "sub/data.bin"
+
+        single = os.path.join(shm, "single.txt")
+        await write_file(single, "Content")
+        target = os.path.join(disk, "single.txt")
+        assert await move(single, target) == target
+        assert await read_file(target) == "Content"
+        assert not os.path.exists(single)
+        # No temporary copies are left behind
+        assert sorted(os.listdir(disk)) == ["single.txt", "tree"]
+
+
 @pytest.mark.asyncio
 async def test_remove_file():
     """Test removing a file."""
@@ -177,13 +284,132 @@ async def test_remove_file_nonexistent():
 
 @pytest.mark.asyncio
 async def test_remove_file_directory():
-    """Test that remove_file raises IOError when trying to remove a directory."""
+    """Test that remove_file raises IsADirectoryError for a directory."""
     with tempfile.TemporaryDirectory() as tmpdir:
         dir_path = os.path.join(tmpdir, _unique_name("directory"))
         os.mkdir(dir_path)
 
-        with pytest.raises(IOError):
+        with pytest.raises(IsADirectoryError):
             await remove_file(dir_path)
+        with pytest.raises(IsADirectoryError):
+            await unlink(dir_path, missing_ok=True)
+        assert os.path.isdir(dir_path)
+
+
+@pytest.mark.asyncio
+async def test_unlink():
+    """Test unlink() with and without missing_ok."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        file_path = os.path.join(tmpdir, "file.txt")
+        await write_file(file_path, "Content")
+
+        await unlink(file_path)
+        assert not os.path.exists(file_path)
+        with pytest.raises(FileNotFoundError):
+            await unlink(file_path)
+        await unlink(file_path, missing_ok=True)
+
+        if hasattr(os, "symlink") and os.name != "nt":
+            # The link is removed, not the directory it points to
+            target = os.path.join(tmpdir, "target")
+            os.mkdir(target)
+            link = os.path.join(tmpdir, "link")
+            os.symlink(target, link)
+            await unlink(link)
+            assert not os.path.lexists(link) and os.path.isdir(target)
+
+
+@pytest.mark.asyncio
+async def test_touch():
+    """Test creating files and bumping timestamps with touch()."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        file_path = os.path.join(tmpdir, "file.txt")
+        await touch(file_path)
+        assert os.path.getsize(file_path) == 0
+
+        await write_file(file_path, "Content")
+        os.utime(file_path, (1_000_000, 1_000_000))
+        await touch(file_path)
+        stat = os.stat(file_path)
+        assert stat.st_mtime > 1_000_000 and stat.st_atime > 1_000_000
+        assert await read_file(file_path) == "Content"
+
+        with pytest.raises(FileExistsError):
+            await touch(file_path, exist_ok=False)
+        new_path = os.path.join(tmpdir, "new.txt")
+        await touch(new_path, exist_ok=False)
+        assert os.path.isfile(new_path)
+
+        with pytest.raises(FileNotFoundError):
+            await touch(os.path.join(tmpdir, "missing", "file.txt"))
+
+
+@pytest.mark.asyncio
+async def test_truncate():
+    """Test truncating and extending a file by path."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        file_path = os.path.join(tmpdir, "file.txt")
+        await write_file(file_path, "Hello, World!")
+        await truncate(file_path, 5)
+        assert await read_file(file_path) == "Hello"
+
+        await truncate(file_path, 8)
+        assert await read_file_bytes(file_path) == b"Hello\0\0\0"
+        await truncate(file_path, 0)
+        assert os.path.getsize(file_path) == 0
+
+        with pytest.raises(ValueError, match="Negative size"):
+            await truncate(file_path, -1)
+        with pytest.raises(FileNotFoundError):
+            await truncate(os.path.join(tmpdir, "missing.txt"), 0)
+
+
+@pytest.mark.asyncio
+async def test_chmod():
+    """Test changing permission bits."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        file_path = os.path.join(tmpdir, "file.txt")
+        await write_file(file_path, "Content")
+        await chmod(file_path, 0o444)
+        assert not os.access(file_path, os.W_OK) or os.geteuid() == 0
+        if os.name != "nt":
+            assert stat.S_IMODE(os.stat(file_path).st_mode) == 0o444
+            await chmod(file_path, 0o4751)
+            assert stat.S_IMODE(os.stat(file_path).st_mode) == 0o4751
+        await chmod(file_path, 0o644)
+        assert os.access(file_path, os.W_OK)
+
+        with pytest.raises(ValueError, match="Invalid mode"):
+            await chmod(file_path, 0o10000)
+        with pytest.raises(FileNotFoundError):
+            await chmod(os.path.join(tmpdir, "missing.txt"), 0o644)
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name == "nt", reason="ownership is Unix only")
+async def test_chown():
+    """Test changing ownership to IDs the process may use."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        file_path = os.path.join(tmpdir, "file.txt")
+        link_path = os.path.join(tmpdir, "link")
+        await write_file(file_path, "Content")
+        os.symlink(file_path, link_path)
+        uid, gid = os.getuid(), os.getgid()
+
+        await chown(file_path, -1, -1)
+        await chown(link_path, uid, gid)
+        await lchown(link_path, -1, gid)
+        await chown(link_path, uid, -1, follow_symlinks=False)
+        stat_result = os.stat(file_path)
+        assert (stat_result.st_uid, stat_result.st_gid) == (uid, gid)
+
+        with pytest.raises(ValueError, match="Invalid uid"):
+            await chown(file_path, -2, -1)
+        with pytest.raises(FileNotFoundError):
+            await chown(os.path.join(tmpdir, "missing.txt"), uid, gid)
+        if uid != 0:
+            with pytest.raises(PermissionError):
+                await chown(file_path, uid + 1, -1)
 
 
 @pytest.mark.asyncio
@@ -229,6 +455,35 @@ async def test_hard_link_nonexistent_source():
             await hard_link(src, dst)
 
 
+@pytest.mark.asyncio
+@pytest.mark.skipif(
+    os.name == "nt", reason="Hard links may not be supported on Windows"
+)
+async def test_link():
+    """Test link() and its errors for existing and cross-device destinations."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, _unique_name("original.txt"))
+        dst = os.path.join(tmpdir, _unique_name("link.txt"))
+        await write_file(src, "Original content")
+
+        await link(src, dst)
+        assert os.path.samefile(src, dst)
+        assert os.stat(src).st_nlink == 2
+
+        with pytest.raises(FileExistsError):
+            await link(src, dst)
+        with pytest.raises(FileExistsError):
+            await hard_link(src, dst)
+
+        if os.path.isdir("/dev/shm") and os.access("/dev/shm", os.W_OK):
+            with tempfile.TemporaryDirectory(dir="/dev/shm") as shm:
+                if os.stat(shm).st_dev != os.stat(tmpdir).st_dev:
+                    other = os.path.join(shm, "link.txt")
+                    with pytest.raises(IOError, match="different filesystems"):
+                        await link(src, other)
+                    assert not os.path.exists(other)
+
+
 @pytest.mark.asyncio
 async def test_symlink():
     """Test creating a symbolic link."""
@@ -249,6 +504,24 @@ async def test_symlink():
             assert dst_content == "Original content"
 
 
+@pytest.mark.asyncio
+async def test_symlink_to_directory():
+    """Test directory symlinks, including to a directory created later."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        link = os.path.join(tmpdir, "link")
+        # Relative to the link, created before its target
+        await symlink("target", link, target_is_directory=True)
+        assert os.readlink(link) == "target"
+        os.mkdir(os.path.join(tmpdir, "target"))
+        await write_file(os.path.join(tmpdir, "target", "file.txt"), "Content")
+        assert await read_file(os.path.join(link, "file.txt")) == "Content"
+
+        await symlink("target", os.path.join(tmpdir, "detected"))
+        assert os.path.isdir(os.path.join(tmpdir, "detected"))
+        with pytest.raises(FileExistsError):
+            await symlink("target", link)
+
+
 @pytest.mark.asyncio
 async def test_symlink_resolves():
     """Test that symlink can be resolved through canonicalize."""
@@ -268,6 +541,30 @@ async def test_symlink_resolves():
         assert os.path.normpath(canonical_dst) == os.path.normpath(canonical_src)
 
 
+@pytest.mark.asyncio
+async def test_readlink():
+    """Test reading symlink targets without resolving them."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        link = os.path.join(tmpdir, _unique_name("link"))
+        await symlink(os.path.join("sub", "..", "missing.txt"), link)
+        assert await readlink(link) == os.path.join("sub", "..", "missing.txt")
+
+        regular = os.path.join(tmpdir, _unique_name("file.txt"))
+        await write_file(regular, "data")
+        with pytest.raises(IOError, match="not a symbolic link"):
+            await readlink(regular)
+        with pytest.raises(FileNotFoundError):
+            await readlink(os.path.join(tmpdir, "missing"))
+
+        if sys.platform.startswith("linux"):
+            # Undecodable bytes round-trip like os.readlink()
+            raw = os.path.join(tmpdir, _unique_name("raw"))
+            os.symlink(b"caf\xe9", raw.encode())
+            target = await readlink(raw)
+            assert target == os.readlink(raw)
+            assert os.fsencode(target) == b"caf\xe9"
+
+
 @pytest.mark.asyncio
 async def test_canonicalize():
     """Test canonicalizing a path."""
@@ -302,6 +599,42 @@ async def test_canonicalize_nonexistent():
             await canonicalize(nonexistent_path)
 
 
+@pytest.mark.asyncio
+@pytest.mark.skipif(sys.platform == "win32", reason="symlinks need privileges")
+async def test_realpath():
+    """Test resolving paths like os.path.realpath()."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        real = os.path.join(tmpdir, "releases", "v2")
+        os.makedirs(real)
+        os.symlink(os.path.join("releases", "v2"), os.path.join(tmpdir, "v"))
+        os.symlink("v", os.path.join(tmpdir, "current"))
+
+        # A chain of links, '..' after a link and a missing leaf
+        for rel in ("current", "current/../v2/x.txt", "current/new.log"):
+            path = os.path.join(tmpdir, rel)
+            assert await realpath(path) == os.path.realpath(path)
+        assert await realpath(os.path.join(tmpdir, "current", "new.log")) == (
+            os.path.join(os.path.realpath(real), "new.log")
+        )
+
+        cwd = os.getcwd()
+        os.chdir(tmpdir)
+        try:
+            assert await realpath("current") == os.path.realpath("current")
+        finally:
+            os.chdir(cwd)
+
+        missing = os.path.join(tmpdir, "current", "missing", "file.txt")
+        assert await realpath(missing) == os.path.realpath(missing)
+        with pytest.raises(FileNotFoundError):
+            await realpath(missing, strict=True)
+
+        loop = os.path.join(tmpdir, "loop")
+        os.symlink("loop", loop)
+        with pytest.raises(IOError, match="symbolic links"):
+            await realpath(loop, strict=True)
+
+
 @pytest.mark.asyncio
 async def test_concurrent_file_operations():
     """Test concurrent file operations."""
@@ -506,3 +839,328 @@ async def test_file_manipulation_unicode():
         dst2 = os.path.join(tmpdir, _unique_name("dest2_unicode.txt"))
         await move_file(dst, dst2)
         assert await read_file(dst2) == unicode_content
+
+
+_IS_ROOT = hasattr(os, "geteuid") and os.geteuid() == 0
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(not _IS_ROOT, reason="Changing ownership requires root")
+async def test_copy_file_preserve_owner():
+    """Test that preserve_owner keeps the source's uid/gid."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, _unique_name("owned.txt"))
+        dst = os.path.join(tmpdir, _unique_name("copy.txt"))
+        plain = os.path.join(tmpdir, _unique_name("plain.txt"))
+
+        await write_file(src, "Owned content")
+        os.chown(src, 12345, 12346)
+
+        await copy_file(src, dst, preserve_owner=True)
+        await copy_file(src, plain)
+
+        st = os.stat(dst)
+        assert (st.st_uid, st.st_gid) == (12345, 12346)
+        assert os.stat(plain).st_uid == os.geteuid()
+        assert await read_file(dst) == "Owned content"
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(not _IS_ROOT, reason="Changing ownership requires root")
+async def test_copy_files_preserve_owner():
+    """Test that copy_files forwards preserve_owner to every copy."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        pairs = []
+        for i in range(3):
+            src = os.path.join(tmpdir, _unique_name(f"src{i}.txt"))
+            dst = os.path.join(tmpdir, _unique_name(f"dst{i}.txt"))
+            await write_file(src, f"content {i}")
+            os.chown(src, 20000 + i, 20000 + i)
+            pairs.append((src, dst))
+
+        await copy_files(pairs, preserve_owner=True)
+
+        for i, (_, dst) in enumerate(pairs):
+            assert os.stat(dst).st_uid == 20000 + i
+
+
+_TREE_FILES = (
+    "top.txt",
+    os.path.join("sub", "a.txt"),
+    os.path.join("sub", "deep", "b"),
+)
+
+
+def _make_tree(root):
+    os.makedirs(os.path.join(root, "sub", "deep"))
+    os.makedirs(os.path.join(root, "empty"))
+    for rel in _TREE_FILES:
+        with open(os.path.join(root, rel), "w") as f:
+            f.write(f"content of {rel}")
+
+
+@pytest.mark.asyncio
+async def test_copytree_copy():
+    """Test that copytree copies files and directories."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        dst = os.path.join(tmpdir, "dst")
+        _make_tree(src)
+
+        await copytree(src, dst)
+
+        assert os.path.isdir(os.path.join(dst, "empty"))
+        b = os.path.join("sub", "deep", "b")
+        assert await read_file(os.path.join(dst, b)) == f"content of {b}"
+        src_ino = os.stat(os.path.join(src, "top.txt")).st_ino
+        assert os.stat(os.path.join(dst, "top.txt")).st_ino != src_ino
+
+
+@pytest.mark.asyncio
+async def test_copytree_hardlink():
+    """Test that hardlink mode links files instead of copying them."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        dst = os.path.join(tmpdir, "dst")
+        _make_tree(src)
+
+        await copytree(src, dst, mode="hardlink")
+
+        for rel in ("top.txt", os.path.join("sub", "a.txt")):
+            assert os.path.samefile(os.path.join(src, rel), os.path.join(dst, rel))
+        assert os.stat(os.path.join(dst, "top.txt")).st_nlink == 2
+        assert os.path.isdir(os.path.join(dst, "empty"))
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(not hasattr(os, "symlink"), reason="symlinks not supported")
+async def test_copytree_keeps_symlinks():
+    """Test that symlinks are recreated rather than followed."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        dst = os.path.join(tmpdir, "dst")
+        _make_tree(src)
+        os.symlink("top.txt", os.path.join(src, "link"))
+
+        await copytree(src, dst, mode="hardlink")
+
+        assert os.readlink(os.path.join(dst, "link")) == "top.txt"
+
+
+@pytest.mark.asyncio
+async def test_copytree_errors():
+    """Test copytree error handling."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        _make_tree(src)
+
+        os.mkdir(os.path.join(tmpdir, "taken"))
+        with pytest.raises(FileExistsError):
+            await copytree(src, os.path.join(tmpdir, "taken"))
+        with pytest.raises(FileNotFoundError):
+            await copytree(os.path.join(tmpdir, "missing"), os.path.join(tmpdir, "x"))
+        with pytest.raises(NotADirectoryError):
+            await copytree(os.path.join(src, "top.txt"), os.path.join(tmpdir, "y"))
+        with pytest.raises(ValueError):
+            await copytree(src, os.path.join(tmpdir, "z"), mode="reflink")
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(not hasattr(os, "mkfifo"), reason="requires FIFOs")
+async def test_copytree_rejects_special_files():
+    """Test that FIFOs fail the copy instead of blocking it forever."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        _make_tree(src)
+        os.mkfifo(os.path.join(src, "sub", "fifo"))
+
+        for mode in ("copy", "hardlink"):
+            with pytest.raises(shutil.SpecialFileError, match="named pipe"):
+                await copytree(src, os.path.join(tmpdir, mode), mode=mode)
+
+
+@pytest.mark.asyncio
+async def test_copytree_rejects_dst_inside_src():
+    """Test that a copy into its own source fails before creating anything."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        _make_tree(src)
+
+        for dst in (
+            os.path.join(src, "copy"),
+            os.path.join(src, "sub", "deep", "copy"),
+            os.path.join(tmpdir, "src", "..", "src", "copy"),
+        ):
+            with pytest.raises(ValueError, match="inside the source"):
+                await copytree(src, dst)
+            assert not os.path.exists(dst)
+        if hasattr(os, "symlink"):
+            os.symlink(src, os.path.join(tmpdir, "alias"))
+            with pytest.raises(ValueError, match="inside the source"):
+                await copytree(src, os.path.join(tmpdir, "alias", "copy"))
+        assert sorted(os.listdir(src)) == ["empty", "sub", "top.txt"]
+
+        # A sibling whose name merely starts with the source's is fine
+        await copytree(src, src + "-copy")
+        assert os.path.isfile(os.path.join(src + "-copy", "top.txt"))
+
+
+def _interrupted_copytree(src, dst, checkpoint):
+    """Fail a checkpointed copy on a socket, which cannot be copied."""
+    sock = socket.socket(socket.AF_UNIX)
+    sock.bind(os.path.join(src, "sub", "sock"))
+    sock.close()
+    return copytree(src, dst, checkpoint=checkpoint)
+
+
+def _recorded_files(checkpoint):
+    with open(checkpoint) as f:
+        # Header, source and destination lines precede the completed files
+        return [line.split(" ", 2)[2] for line in f.read().splitlines()[3:]]
+
+
+@pytest.mark.skipif(not hasattr(socket, "AF_UNIX"), reason="requires Unix sockets")
+@pytest.mark.asyncio
+@pytest.mark.parametrize("verify", ["stat", "content"])
+async def test_copytree_checkpoint_resume(verify):
+    """Test that a checkpointed copy resumes, skipping verified files."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        dst = os.path.join(tmpdir, "dst")
+        checkpoint = os.path.join(tmpdir, "copy.ckpt")
+        _make_tree(src)
+        with pytest.raises(OSError):
+            await _interrupted_copytree(src, dst, checkpoint)
+        os.remove(os.path.join(src, "sub", "sock"))
+
+        # Same-size edits are only noticed when comparing contents
+        recorded = _recorded_files(checkpoint)
+        for rel in recorded:
+            with open(os.path.join(dst, rel), "r+") as f:
+                f.write("X")
+        await copytree(src, dst, checkpoint=checkpoint, verify=verify)
+
+        assert not os.path.exists(checkpoint)
+        assert os.path.isdir(os.path.join(dst, "empty"))
+        for rel in _TREE_FILES:
+            with open(os.path.join(dst, rel)) as f:
+                content = f.read()
+            if verify == "stat" and rel in recorded:
+                assert content == "X" + f"content of {rel}"[1:]
+            else:
+                assert content == f"content of {rel}"
+
+
+@pytest.mark.skipif(not hasattr(socket, "AF_UNIX"), reason="requires Unix sockets")
+@pytest.mark.asyncio
+async def test_copytree_checkpoint_recopies_changed_files():
+    """Test that truncated copies and changed sources are copied again."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        dst = os.path.join(tmpdir, "dst")
+        checkpoint = os.path.join(tmpdir, "copy.ckpt")
+        _make_tree(src)
+        with pytest.raises(OSError):
+            await _interrupted_copytree(src, dst, checkpoint)
+        os.remove(os.path.join(src, "sub", "sock"))
+
+        for rel in _recorded_files(checkpoint):
+            open(os.path.join(dst, rel), "w").close()
+        with open(os.path.join(src, "top.txt"), "a") as f:
+            f.write(" v2")
+        await copytree(src, dst, checkpoint=checkpoint)
+
+        for rel in _TREE_FILES:
+            with open(os.path.join(src, rel)) as f, open(os.path.join(dst, rel)) as g:
+                assert f.read() == g.read()
+
+
+@pytest.mark.asyncio
+async def test_copytree_checkpoint_errors():
+    """Test that checkpoints only resume the copy they were written for."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        dst = os.path.join(tmpdir, "dst")
+        checkpoint = os.path.join(tmpdir, "copy.ckpt")
+        _make_tree(src)
+
+        # Without a checkpoint to resume from, dst must not exist
+        os.mkdir(dst)
+        with pytest.raises(FileExistsError):
+            await copytree(src, dst, checkpoint=checkpoint)
+        assert not os.path.exists(checkpoint)
+        os.rmdir(dst)
+
+        # A completed copy leaves no checkpoint behind
+        await copytree(src, dst, checkpoint=checkpoint)
+        assert not os.path.exists(checkpoint)
+
+        with open(checkpoint, "w") as f:
+            f.write("rapfiles copytree checkpoint 1\nsrc /elsewhere\ndst /other\n")
+        with pytest.raises(ValueError, match="does not belong"):
+            await copytree(src, dst, checkpoint=checkpoint)
+        with open(checkpoint, "w") as f:
+            f.write("not a checkpoint\n")
+        with pytest.raises(ValueError, match="Invalid copytree checkpoint"):
+            await copytree(src, dst, checkpoint=checkpoint)
+        with pytest.raises(ValueError, match="verify"):
+            await copytree(src, os.path.join(tmpdir, "z"), verify="hash")
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name != "posix", reason="names are invalid on Windows")
+async def test_copytree_sanitize():
+    """Test that names invalid on Windows are escaped or rejected."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        src = os.path.join(tmpdir, "src")
+        os.makedirs(os.path.join(src, "aux"))
+        names = ["a:b", "100%?", "notes. ", "nul.txt", "ok.txt"]
+        for name in names + [os.path.join("aux", "con")]:
+            with open(os.path.join(src, name), "w") as f:
+                f.write(name)
+        with open(os.path.join(src.encode(), b"bad\xff"), "w") as f:
+            f.write("bytes")
+
+        dst = os.path.join(tmpdir, "replaced")
+        renamed = await copytree(src, dst, sanitize="replace")
+        expected = {
+            "a:b": "a_b",
+            "100%?": "100%_",
+            "notes. ": "notes__",
+            "nul.txt": "nu_.txt",
+            "aux": "au_",
+            os.path.join("aux", "con"): os.path.join("au_", "co_"),
+        }
+        assert renamed[0] == (os.path.join(src, "100%?"), os.path.join(dst, "100%_"))
+        relative = {
+            os.path.relpath(s, src): os.path.relpath(d, dst)
+            for s, d in renamed
+            if not s.startswith(os.path.join(src, "bad"))
+        }
+        assert relative == expected
+        assert sorted(os.listdir(dst)) == sorted(
+            ["a_b", "100%_", "notes__", "nu_.txt", "au_", "ok.txt", "bad_"]
+        )
+        assert await read_file(os.path.join(dst, "au_", "co_")) == "aux/con"
+
+        dst = os.path.join(tmpdir, "encoded")
+        renamed = await copytree(src, dst, sanitize="percent-encode")
+        assert sorted(os.listdir(dst)) == sorted(
+            ["a%3Ab", "100%25%3F", "notes%2E%20", "nu%6C.txt", "au%78"]
+            + ["ok.txt", "bad%FF"]
+        )
+        assert await copytree(src, os.path.join(tmpdir, "plain")) is None
+
+        with pytest.raises(ValueError, match="not a valid name on Windows"):
+            await copytree(src, os.path.join(tmpdir, "strict"), sanitize="error")
+        # Names are checked before anything is created
+        assert not os.path.exists(os.path.join(tmpdir, "strict"))
+        with pytest.raises(ValueError, match="Invalid sanitize mode"):
+            await copytree(src, os.path.join(tmpdir, "x"), sanitize="drop")
+
+        # Two names that end up the same
+        with open(os.path.join(src, "a_b"), "w"):
+            pass
+        with pytest.raises(FileExistsError, match="like another entry"):
+            await copytree(src, os.path.join(tmpdir, "clash"), sanitize="replace")
+        assert not os.path.exists(os.path.join(tmpdir, "clash"))
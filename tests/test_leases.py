@@ -0,0 +1,96 @@
+"""Test file leases with lease_file()."""
+
+import asyncio
+import os
+import tempfile
+import time
+
+import pytest
+
+import rapfiles
+
+
+def write_sidecar(path, owner, expires):
+    with open(path + ".lease", "w") as f:
+        f.write(f'owner = "{owner}"\ntoken = "{owner}-token"\nexpires = {expires}\n')
+
+
+async def acquire(path, **kwargs):
+    async with rapfiles.lease_file(path, **kwargs) as lease:
+        return lease.owner
+
+
+@pytest.mark.asyncio
+async def test_exclusive_until_released():
+    """Test that a held lease blocks others until it is released."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "job")
+        async with rapfiles.lease_file(path, ttl=10, owner="first") as lease:
+            assert isinstance(lease, rapfiles.FileLease)
+            assert lease.held and lease.owner == "first" and lease.ttl == 10
+            assert time.time() < lease.expires <= time.time() + 10
+            assert not os.path.exists(path)
+            with open(path + ".lease") as f:
+                assert 'owner = "first"' in f.read()
+
+            with pytest.raises(TimeoutError, match="held by first"):
+                await acquire(path, timeout=0)
+            waiter = asyncio.ensure_future(
+                acquire(path, owner="second", poll_interval=0.02)
+            )
+            await asyncio.sleep(0.1)
+            assert not waiter.done()
+        assert not lease.held
+        assert await asyncio.wait_for(waiter, 5) == "second"
+        await lease.release()
+        assert os.listdir(tmpdir) == []
+
+
+@pytest.mark.asyncio
+async def test_expired_lease_taken_over():
+    """Test that the lease of a crashed holder can be taken once expired."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "job")
+        write_sidecar(path, "crashed", time.time() + 0.2)
+        with pytest.raises(TimeoutError, match="held by crashed"):
+            await acquire(path, timeout=0)
+        assert await acquire(path, owner="next", timeout=5, poll_interval=0.05)
+
+        # A sidecar that cannot be parsed expires ttl after its last change
+        with open(path + ".lease", "w") as f:
+            f.write("garbage")
+        with pytest.raises(TimeoutError, match="held by"):
+            await acquire(path, ttl=60, timeout=0)
+        await acquire(path, ttl=0.2, timeout=5, poll_interval=0.05)
+        assert os.listdir(tmpdir) == []
+
+
+@pytest.mark.asyncio
+async def test_renewal_and_loss():
+    """Test that leases are renewed in the background and notice takeovers."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "job")
+        async with rapfiles.lease_file(path, ttl=0.3) as lease:
+            first_expiry = lease.expires
+            await asyncio.sleep(0.6)
+            assert lease.held
+            assert lease.expires > first_expiry
+            with pytest.raises(TimeoutError):
+                await acquire(path, timeout=0)
+
+            write_sidecar(path, "intruder", time.time() + 60)
+            await asyncio.sleep(0.3)
+            assert not lease.held
+        with open(path + ".lease") as f:
+            assert "intruder" in f.read()
+
+
+@pytest.mark.asyncio
+async def test_invalid_arguments():
+    """Test argument validation."""
+    with pytest.raises(ValueError, match="Invalid ttl"):
+        await acquire("job", ttl=0)
+    with pytest.raises(ValueError, match="Invalid poll_interval"):
+        await acquire("job", poll_interval=-1)
+    with pytest.raises(ValueError, match="cannot be instantiated"):
+        rapfiles.FileLease()
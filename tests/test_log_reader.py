@@ -0,0 +1,95 @@
+"""Test following appended files with open_log()."""
+
+import asyncio
+import os
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+def append(path, data):
+    with open(path, "ab") as f:
+        f.write(data)
+
+
+@pytest.mark.asyncio
+async def test_read_new_and_partial_lines():
+    """Test that only appended data is returned and partial lines wait."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "app.log")
+        append(path, b"old\n")
+
+        reader = await rapfiles.open_log(path)
+        assert await reader.read_lines() == [b"old\n"]
+        assert await reader.read_new() == b""
+
+        append(path, b"first\nsecond, part")
+        assert await reader.read_lines() == [b"first\n"]
+        append(path, b"ial\nthird")
+        assert await reader.read_lines() == [b"second, partial\n"]
+        assert await reader.read_new() == b"third"
+        assert reader.offset == os.path.getsize(path)
+
+        tail = await rapfiles.open_log(path, from_end=True)
+        append(path, b"\n")
+        assert await tail.read_new() == b"\n"
+
+
+@pytest.mark.asyncio
+async def test_truncation_and_rotation():
+    """Test that truncated and rotated files are followed."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "app.log")
+        reader = await rapfiles.open_log(path)
+        assert await reader.read_new() == b""
+
+        append(path, b"created later\n")
+        assert await reader.read_lines() == [b"created later\n"]
+
+        with open(path, "wb") as f:
+            f.write(b"x\n")
+        assert await reader.read_lines() == [b"x\n"]
+
+        append(path, b"last of old\n")
+        os.rename(path, path + ".1")
+        append(path, b"new file\n")
+        assert await reader.read_lines() == [b"last of old\n", b"new file\n"]
+
+
+@pytest.mark.asyncio
+async def test_wait_and_follow():
+    """Test waiting for appended data and following with async for."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "app.log")
+        append(path, b"")
+        reader = await rapfiles.open_log(path, poll_interval=0.01)
+        assert await reader.wait(timeout=0.05) is False
+
+        async def writer():
+            for i in range(3):
+                await asyncio.sleep(0.02)
+                append(path, f"line {i}\n".encode())
+
+        task = asyncio.ensure_future(writer())
+        assert await reader.wait(timeout=5) is True
+        lines = []
+        async for line in reader:
+            lines.append(line)
+            if len(lines) == 3:
+                reader.close()
+        await task
+        assert lines == [b"line 0\n", b"line 1\n", b"line 2\n"]
+
+        with pytest.raises(ValueError, match="closed"):
+            await reader.read_new()
+
+
+@pytest.mark.asyncio
+async def test_invalid_arguments():
+    """Test argument validation."""
+    with pytest.raises(ValueError, match="poll_interval"):
+        await rapfiles.open_log("app.log", poll_interval=0)
+    with pytest.raises(ValueError, match="cannot be instantiated"):
+        rapfiles.LogReader()
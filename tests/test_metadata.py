@@ -4,7 +4,12 @@ import pytest
 import tempfile
 import os
 
-from rapfiles import stat, metadata, FileMetadata  # type: ignore[attr-defined]
+from rapfiles import (  # type: ignore[attr-defined]
+    FileMetadata,
+    metadata,
+    read_with_stat,
+    stat,
+)
 
 
 @pytest.mark.asyncio
@@ -84,3 +89,28 @@ async def test_stat_properties():
     finally:
         if os.path.exists(test_file):
             os.unlink(test_file)
+
+
+@pytest.mark.asyncio
+async def test_read_with_stat():
+    """Test reading a file together with the metadata of the same open."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "data.bin")
+        data = bytes(range(256)) * 100
+        with open(path, "wb") as f:
+            f.write(data)
+
+        file_metadata, contents = await read_with_stat(path)
+        assert isinstance(file_metadata, FileMetadata)
+        assert contents == data
+        assert file_metadata.size == len(data) and file_metadata.is_file
+        assert file_metadata.modified == (await stat(path)).modified
+
+        empty = os.path.join(tmpdir, "empty")
+        open(empty, "wb").close()
+        assert (await read_with_stat(empty))[1] == b""
+
+        with pytest.raises(FileNotFoundError):
+            await read_with_stat(os.path.join(tmpdir, "missing"))
+        with pytest.raises(IOError):
+            await read_with_stat(tmpdir)
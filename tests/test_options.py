@@ -0,0 +1,209 @@
+"""Test per-context operation options set with rapfiles.options()."""
+
+import asyncio
+import errno
+import os
+import tempfile
+import time
+
+import pytest
+
+import rapfiles
+from rapfiles.testing import inject_fault, inject_latency
+
+
+def test_options_nesting():
+    """Test that nested blocks inherit the options they don't set."""
+    assert rapfiles.get_options() == {
+        "timeout": None,
+        "retries": 0,
+        "rate_limit": None,
+        "priority": "normal",
+        "job": None,
+        "weight": 1.0,
+    }
+    with rapfiles.options(timeout=5, retries=2):
+        with rapfiles.options(rate_limit=1000):
+            assert rapfiles.get_options() == {
+                "timeout": 5.0,
+                "retries": 2,
+                "rate_limit": 1000.0,
+                "priority": "normal",
+                "job": None,
+                "weight": 1.0,
+            }
+        assert rapfiles.get_options()["rate_limit"] is None
+    assert rapfiles.get_options()["timeout"] is None
+
+
+def test_options_validation():
+    """Test that invalid options are rejected."""
+    with pytest.raises(ValueError, match="Invalid timeout"):
+        with rapfiles.options(timeout=0):
+            pass
+    with pytest.raises(ValueError, match="Invalid retries"):
+        with rapfiles.options(retries=-1):
+            pass
+    with pytest.raises(ValueError, match="Invalid rate_limit"):
+        with rapfiles.options(rate_limit=float("inf")):
+            pass
+    with pytest.raises(ValueError, match="Invalid priority"):
+        with rapfiles.options(priority="urgent"):
+            pass
+    with pytest.raises(ValueError, match="Invalid weight"):
+        with rapfiles.options(job="restore", weight=0):
+            pass
+    with pytest.raises(ValueError, match="Only named jobs"):
+        with rapfiles.options(weight=2):
+            pass
+    assert rapfiles.get_options()["timeout"] is None
+
+
+@pytest.mark.asyncio
+async def test_options_scoped_to_task():
+    """Test that options only apply to the task that set them."""
+
+    async def handler(timeout):
+        with rapfiles.options(timeout=timeout):
+            await asyncio.sleep(0.01)
+            return rapfiles.get_options()["timeout"]
+
+    assert await asyncio.gather(handler(1), handler(2)) == [1.0, 2.0]
+
+
+@pytest.mark.asyncio
+async def test_timeout():
+    """Test that an operation exceeding the timeout raises TimeoutError."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "slow.txt")
+        await rapfiles.write_file(path, "data")
+        with inject_latency(path, latency=1.0):
+            with rapfiles.options(timeout=0.05):
+                started = time.monotonic()
+                with pytest.raises(TimeoutError, match="timed out after 0.05s"):
+                    await rapfiles.read_file(path)
+                assert time.monotonic() - started < 0.5
+            with rapfiles.options(timeout=0.05):
+                with pytest.raises(TimeoutError):
+                    await rapfiles.stat(path)
+
+
+@pytest.mark.asyncio
+async def test_retries_transient_errors():
+    """Test that reads are retried until a transient fault clears."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "flaky.bin")
+        await rapfiles.write_file_bytes(path, b"payload")
+
+        with inject_fault(path, errno.EIO):
+            with pytest.raises(OSError):
+                await rapfiles.read_file_bytes(path)
+
+        fault = inject_fault(path, errno.EIO)
+
+        async def clear_fault():
+            await asyncio.sleep(0.02)
+            fault.remove()
+
+        with rapfiles.options(retries=3):
+            data, _ = await asyncio.gather(
+                rapfiles.read_file_bytes(path), clear_fault()
+            )
+        assert data == b"payload"
+
+
+@pytest.mark.asyncio
+async def test_permanent_errors_not_retried():
+    """Test that errors such as FileNotFoundError are raised at once."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "missing.txt")
+        with rapfiles.options(retries=5):
+            started = time.monotonic()
+            with pytest.raises(FileNotFoundError):
+                await rapfiles.read_file(path)
+            assert time.monotonic() - started < 0.05
+
+
+@pytest.mark.asyncio
+async def test_writes_not_retried():
+    """Test that operations that are unsafe to repeat run once."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "log.txt")
+        with inject_fault(path, errno.EIO, ops=["append_file"]):
+            with rapfiles.options(retries=5):
+                started = time.monotonic()
+                with pytest.raises(OSError):
+                    await rapfiles.append_file(path, "entry\n")
+                assert time.monotonic() - started < 0.05
+
+
+@pytest.mark.asyncio
+async def test_rate_limit_shared_by_block():
+    """Test that concurrent operations share the block's byte budget."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        paths = [os.path.join(tmpdir, f"{i}.bin") for i in range(3)]
+        with rapfiles.options(rate_limit=100_000):
+            started = time.monotonic()
+            await asyncio.gather(
+                *(rapfiles.write_file_bytes(path, b"x" * 10_000) for path in paths)
+            )
+            assert time.monotonic() - started >= 0.25
+        started = time.monotonic()
+        await asyncio.gather(*(rapfiles.read_file_bytes(path) for path in paths))
+        assert time.monotonic() - started < 0.25
+
+
+@pytest.mark.asyncio
+async def test_low_priority_yields_to_high():
+    """Test that low-priority operations queue while high-priority ones run."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        critical = os.path.join(tmpdir, "critical.txt")
+        scan = [os.path.join(tmpdir, f"scan{i}.txt") for i in range(4)]
+        for path in [critical, *scan]:
+            await rapfiles.write_file(path, "data")
+
+        async def low_batch():
+            with rapfiles.options(priority="low"):
+                assert rapfiles.get_options()["priority"] == "low"
+                started = time.monotonic()
+                await asyncio.gather(*(rapfiles.read_file(path) for path in scan))
+                return time.monotonic() - started
+
+        scan_glob = os.path.join(tmpdir, "scan*")
+        with inject_latency(scan_glob, 0.1), inject_latency(critical, 0.6):
+            # Alone, low-priority operations run concurrently
+            assert await low_batch() < 0.3
+
+            async def high():
+                with rapfiles.options(priority="high"):
+                    await rapfiles.read_file(critical)
+
+            high_task = asyncio.ensure_future(high())
+            await asyncio.sleep(0.05)
+            # Behind a high-priority operation they run one at a time
+            assert await low_batch() >= 0.35
+            await high_task
+
+
+@pytest.mark.asyncio
+async def test_rate_limit_weighted_jobs():
+    """Test that jobs sharing a rate limit split it by weight."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+
+        async def job(name, weight):
+            with rapfiles.options(job=name, weight=weight):
+                options = rapfiles.get_options()
+                assert (options["job"], options["weight"]) == (name, weight)
+                started = time.monotonic()
+                for i in range(5):
+                    path = os.path.join(tmpdir, f"{name}{i}.bin")
+                    await rapfiles.write_file_bytes(path, b"x" * 20_000)
+                return time.monotonic() - started
+
+        with rapfiles.options(rate_limit=200_000):
+            # Alone, a job gets the whole budget
+            assert await job("alone", 1) < 0.8
+            # First-come-first-served, both would take the full second
+            restore, mirror = await asyncio.gather(job("restore", 4), job("mirror", 1))
+        assert restore < 0.8
+        assert mirror >= 1.0
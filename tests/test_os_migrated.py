@@ -14,9 +14,9 @@ import rapfiles
 RESOURCES_DIR = Path(__file__).parent / "resources"
 
 
-async def test_stat():
+async def test_stat(tmp_path):
     """Test the stat call."""
-    filename = RESOURCES_DIR / "test_file1.txt"
+    filename = tmp_path / "test_file1.txt"
 
     # Ensure file exists and has content using rapfiles for async write
     await rapfiles.write_file(str(filename), "0123456789")
@@ -73,13 +73,11 @@ async def test_rename(tmp_path):
     new_filename = tmp_path / "test_file2.txt"
     old_filename.write_text("test content")
 
-    # rapfiles doesn't have rename() yet
-
-    os.rename(str(old_filename), str(new_filename))
+    await rapfiles.rename(str(old_filename), str(new_filename))
     assert not await rapfiles.exists(str(old_filename)) and await rapfiles.exists(
         str(new_filename)
     )
-    os.rename(str(new_filename), str(old_filename))
+    await rapfiles.rename(str(new_filename), str(old_filename))
     assert await rapfiles.exists(str(old_filename)) and not await rapfiles.exists(
         str(new_filename)
     )
@@ -112,13 +110,11 @@ async def test_replace(tmp_path):
     new_filename = tmp_path / "test_file2.txt"
     old_filename.write_text("test content")
 
-    # rapfiles doesn't have replace() yet
-
-    os.replace(str(old_filename), str(new_filename))
+    await rapfiles.replace(str(old_filename), str(new_filename))
     assert not await rapfiles.exists(str(old_filename)) and await rapfiles.exists(
         str(new_filename)
     )
-    os.replace(str(new_filename), str(old_filename))
+    await rapfiles.replace(str(new_filename), str(old_filename))
     assert await rapfiles.exists(str(old_filename)) and not await rapfiles.exists(
         str(new_filename)
     )
@@ -128,14 +124,15 @@ async def test_replace(tmp_path):
         str(new_filename)
     )
 
-    os.replace(str(old_filename), str(new_filename))
+    await rapfiles.replace(str(old_filename), str(new_filename))
     assert not await rapfiles.exists(str(old_filename)) and await rapfiles.exists(
         str(new_filename)
     )
-    os.replace(str(new_filename), str(old_filename))
+    await rapfiles.replace(str(new_filename), str(old_filename))
     assert await rapfiles.exists(str(old_filename)) and not await rapfiles.exists(
         str(new_filename)
     )
+    assert old_filename.read_text() == "test content"
 
 
 @pytest.mark.skipif(
@@ -204,9 +201,9 @@ async def test_ismount():
     assert os.path.ismount("/")
 
 
-async def test_getsize():
+async def test_getsize(tmp_path):
     """Test path.getsize call."""
-    filename = RESOURCES_DIR / "test_file1.txt"
+    filename = tmp_path / "test_file1.txt"
     # Ensure file exists and has content using rapfiles for async write
     await rapfiles.write_file(str(filename), "0123456789")
 
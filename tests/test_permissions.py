@@ -0,0 +1,87 @@
+"""Test permission auditing and permission-related operations."""
+
+import pytest
+import tempfile
+import os
+
+from rapfiles import audit_permissions
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name == "nt", reason="Unix permission bits required")
+async def test_audit_permissions_world_writable():
+    """Test that world-writable files are reported."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        safe = os.path.join(tmpdir, "safe.txt")
+        unsafe = os.path.join(tmpdir, "unsafe.txt")
+        for path in (safe, unsafe):
+            with open(path, "w") as f:
+                f.write("data")
+        os.chmod(tmpdir, 0o755)
+        os.chmod(safe, 0o644)
+        os.chmod(unsafe, 0o666)
+
+        findings = await audit_permissions(tmpdir)
+
+        assert [(f.path, f.issue) for f in findings] == [(unsafe, "world_writable")]
+        assert findings[0].mode == 0o666
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name == "nt", reason="Unix permission bits required")
+async def test_audit_permissions_setuid_nested():
+    """Test that setuid files are found in nested directories."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        nested = os.path.join(tmpdir, "a", "b")
+        os.makedirs(nested)
+        binary = os.path.join(nested, "tool")
+        with open(binary, "w") as f:
+            f.write("#!/bin/sh\n")
+        os.chmod(binary, 0o4755)
+
+        findings = await audit_permissions(tmpdir, {"world_writable": False})
+
+        assert [(f.path, f.issue) for f in findings] == [(binary, "setuid")]
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name == "nt", reason="Unix permission bits required")
+async def test_audit_permissions_sticky_dir_exempt():
+    """Test that sticky world-writable directories are not reported."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        shared = os.path.join(tmpdir, "shared")
+        os.mkdir(shared)
+        os.chmod(shared, 0o1777)
+        os.chmod(tmpdir, 0o755)
+
+        assert await audit_permissions(tmpdir) == []
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(os.name == "nt", reason="Unix ownership required")
+async def test_audit_permissions_owner():
+    """Test that entries with an unexpected owner are reported."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "file.txt")
+        with open(path, "w") as f:
+            f.write("data")
+
+        other_uid = os.getuid() + 1
+        findings = await audit_permissions(
+            tmpdir, {"world_writable": False, "setuid": False, "owner": other_uid}
+        )
+
+        assert sorted(f.path for f in findings) == sorted([tmpdir, path])
+        assert all(f.issue == "owner" and f.uid == os.getuid() for f in findings)
+
+
+@pytest.mark.asyncio
+async def test_audit_permissions_errors():
+    """Test error handling for missing roots and unknown policy keys."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        with pytest.raises(ValueError):
+            await audit_permissions(tmpdir, {"executable": True})
+
+        if os.name != "nt":
+            with pytest.raises(FileNotFoundError):
+                await audit_permissions(os.path.join(tmpdir, "missing"))
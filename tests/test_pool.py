@@ -0,0 +1,109 @@
+"""Test the file handle pool."""
+
+import asyncio
+import os
+import tempfile
+
+import pytest
+
+from rapfiles import pool
+from rapfiles.pool import HandlePool
+
+
+def make_files(root, count):
+    paths = []
+    for i in range(count):
+        path = os.path.join(root, f"{i}.bin")
+        with open(path, "wb") as f:
+            f.write(bytes([i]) * 100)
+        paths.append(path)
+    return paths
+
+
+@pytest.mark.asyncio
+async def test_reuse_and_lru_eviction():
+    """Test that handles are shared and idle ones are evicted LRU first."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        a, b, c = make_files(tmpdir, 3)
+        async with HandlePool(max_open=2) as handles:
+            async with handles.open(a) as fa:
+                assert await fa.pread(4, 10) == b"\0" * 4
+            async with handles.open(a) as again:
+                assert again is fa
+            async with handles.open(b) as fb:
+                assert await fb.pread(2, 0) == b"\1\1"
+            async with handles.open(a):
+                pass
+            # b is now least recently used
+            async with handles.open(c):
+                pass
+            assert fb.closed and not fa.closed
+            assert handles.stats() == {
+                "open": 2,
+                "in_use": 0,
+                "hits": 2,
+                "misses": 3,
+                "evictions": 1,
+            }
+        assert fa.closed
+
+
+@pytest.mark.asyncio
+async def test_borrowed_handles_are_not_evicted():
+    """Test that handles in use survive eviction and invalidation."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        a, b = make_files(tmpdir, 2)
+        async with HandlePool(max_open=1) as handles:
+            async with handles.open(a) as fa, handles.open(b) as fb:
+                # Concurrent reads through one shared handle
+                reads = await asyncio.gather(
+                    *(fa.pread(1, i) for i in range(10)), fb.pread(1, 0)
+                )
+                assert reads == [b"\0"] * 10 + [b"\1"]
+                assert handles.stats()["open"] == 2
+                assert await handles.invalidate(a) == 1
+                assert not fa.closed
+            assert fa.closed and not fb.closed
+            async with handles.open(a) as fresh:
+                assert fresh is not fa
+            assert handles.stats()["open"] == 1
+
+
+@pytest.mark.asyncio
+async def test_concurrent_first_open_shares_handle():
+    """Test that tasks borrowing a new file at once get one handle."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        (a,) = make_files(tmpdir, 1)
+        async with HandlePool() as handles:
+
+            async def borrow():
+                async with handles.open(a, "rb+") as f:
+                    await f.pwrite(b"x", 0)
+                    return f
+
+            files = await asyncio.gather(*(borrow() for _ in range(8)))
+            assert all(f is files[0] for f in files)
+            assert handles.stats()["misses"] == 1
+        with open(a, "rb") as f:
+            data = f.read()
+            assert data[:2] == b"x\0", data
+
+
+@pytest.mark.asyncio
+async def test_default_pool_and_errors():
+    """Test the module-level pool and argument validation."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        (a,) = make_files(tmpdir, 1)
+        async with pool.open(a) as f:
+            assert await f.pread(1, 99) == b"\0"
+        assert await pool.invalidate(a) == 1
+        assert f.closed
+        await pool.close()
+
+        with pytest.raises(FileNotFoundError):
+            async with pool.open(os.path.join(tmpdir, "missing")):
+                pass
+        with pytest.raises(ValueError, match="Invalid pool mode"):
+            pool.open(a, "w")
+        with pytest.raises(ValueError, match="Invalid max_open"):
+            HandlePool(max_open=0)
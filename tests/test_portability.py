@@ -0,0 +1,70 @@
+"""Test portability checks of directory trees."""
+
+import os
+import sys
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+def case_sensitive(directory):
+    probe = os.path.join(directory, "probe")
+    open(probe, "w").close()
+    try:
+        return not os.path.exists(probe.upper())
+    finally:
+        os.remove(probe)
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(sys.platform == "win32", reason="names are invalid on Windows")
+async def test_reports_non_portable_names():
+    """Test that every kind of non-portable name is reported."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        if not case_sensitive(tmpdir):
+            pytest.skip("file system is case-insensitive")
+        src = os.path.join(tmpdir, "src")
+        os.makedirs(os.path.join(src, "deep"))
+        names = ["README", "readme", "nul.txt", "Con", "a:b", "trailing.", "ok.txt"]
+        for name in names:
+            open(os.path.join(src, name), "w").close()
+        open(os.path.join(src, "deep", "console.txt"), "w").close()
+        long_dir = os.path.join(src, "d" * 100, "d" * 100)
+        os.makedirs(long_dir)
+        open(os.path.join(long_dir, "f" * 60), "w").close()
+
+        issues = await rapfiles.check_portability(tmpdir)
+        found = {(os.path.relpath(i.path, src), i.issue) for i in issues}
+        assert found == {
+            ("readme", "case_conflict"),
+            ("nul.txt", "reserved_name"),
+            ("Con", "reserved_name"),
+            ("a:b", "invalid_character"),
+            ("trailing.", "trailing_dot_or_space"),
+            (os.path.join("d" * 100, "d" * 100, "f" * 60), "path_too_long"),
+        }
+        assert [i.path for i in issues] == sorted(i.path for i in issues)
+        conflict = next(i for i in issues if i.issue == "case_conflict")
+        assert conflict.detail == f"conflicts with {os.path.join(src, 'README')}"
+
+        # Only the shallowest entry past the limit is reported
+        issues = await rapfiles.check_portability(tmpdir, max_path_length=5)
+        too_long = [i.path for i in issues if i.issue == "path_too_long"]
+        assert os.path.join(src, "deep") in too_long
+        assert os.path.join(src, "deep", "console.txt") not in too_long
+
+
+@pytest.mark.asyncio
+async def test_portable_tree_and_errors():
+    """Test a clean tree and argument validation."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        os.makedirs(os.path.join(tmpdir, "pkg", "data"))
+        open(os.path.join(tmpdir, "pkg", "data", "config.json"), "w").close()
+        assert await rapfiles.check_portability(tmpdir) == []
+
+        with pytest.raises(FileNotFoundError):
+            await rapfiles.check_portability(os.path.join(tmpdir, "missing"))
+        with pytest.raises(ValueError, match="max_path_length"):
+            await rapfiles.check_portability(tmpdir, max_path_length=0)
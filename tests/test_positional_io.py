@@ -0,0 +1,188 @@
+"""Test positional range reads and segment writes."""
+
+import pytest
+import tempfile
+import os
+
+from rapfiles import create_filled, list_extents, read_ranges, write_segments
+from rapfiles.recording import Recorder
+
+DATA = bytes(range(256)) * 4
+
+
+def _write(root, name, content=DATA):
+    path = os.path.join(root, name)
+    with open(path, "wb") as f:
+        f.write(content)
+    return path
+
+
+@pytest.mark.asyncio
+async def test_read_ranges_preserves_order():
+    """Test that results follow the order of the requested ranges."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = _write(tmpdir, "data.bin")
+        ranges = [(900, 10), (0, 4), (512, 0), (100, 50)]
+
+        result = await read_ranges(path, ranges)
+
+        assert result == [DATA[o : o + n] for o, n in ranges]
+
+
+@pytest.mark.asyncio
+async def test_read_ranges_coalesces_overlapping_and_adjacent():
+    """Test that merged reads are sliced back into the original ranges."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = _write(tmpdir, "data.bin")
+        ranges = [(20, 10), (0, 10), (10, 10), (5, 20), (700, 8)]
+
+        result = await read_ranges(path, ranges, coalesce_gap=0)
+
+        assert result == [DATA[o : o + n] for o, n in ranges]
+
+
+@pytest.mark.asyncio
+async def test_read_ranges_coalesce_gap_reduces_reads():
+    """Test that a gap merges nearby ranges into a single read."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = _write(tmpdir, "data.bin")
+        ranges = [(0, 4), (8, 4), (16, 4)]
+
+        with Recorder() as recorder:
+            separate = await read_ranges(path, ranges)
+            merged = await read_ranges(path, ranges, coalesce_gap=4)
+
+        assert separate == merged == [DATA[0:4], DATA[8:12], DATA[16:20]]
+        assert [r.bytes for r in recorder.records] == [12, 20]
+
+
+@pytest.mark.asyncio
+async def test_read_ranges_truncates_at_eof():
+    """Test that ranges past the end of the file are truncated."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = _write(tmpdir, "small.bin", b"0123456789")
+
+        for gap in (None, 0, 100):
+            result = await read_ranges(path, [(8, 10), (20, 5)], coalesce_gap=gap)
+            assert result == [b"89", b""]
+
+
+@pytest.mark.asyncio
+async def test_read_ranges_missing_file():
+    """Test that reading ranges of a missing file raises FileNotFoundError."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        with pytest.raises(FileNotFoundError):
+            await read_ranges(os.path.join(tmpdir, "missing.bin"), [(0, 1)])
+
+
+@pytest.mark.asyncio
+async def test_write_segments_patches_in_place():
+    """Test that segments overwrite only their own bytes."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = _write(tmpdir, "data.bin", b"." * 16)
+
+        await write_segments(path, [(2, b"ab"), (4, b"cd"), (10, b"XYZ")])
+
+        with open(path, "rb") as f:
+            assert f.read() == b"..abcd....XYZ..."
+
+
+@pytest.mark.asyncio
+async def test_write_segments_later_segment_wins():
+    """Test that overlapping segments are applied in order."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = _write(tmpdir, "data.bin", b"." * 8)
+
+        await write_segments(path, [(4, b"1111"), (2, b"22"), (3, b"3")])
+
+        with open(path, "rb") as f:
+            assert f.read() == b"..231111"
+
+
+@pytest.mark.asyncio
+async def test_write_segments_creates_and_extends():
+    """Test that a missing file is created and extended past its end."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "new.bin")
+
+        with Recorder() as recorder:
+            await write_segments(path, [(4, b"tail"), (0, b"head")])
+
+        with open(path, "rb") as f:
+            assert f.read() == b"headtail"
+        assert [(r.op, r.bytes) for r in recorder.records] == [
+            ("write_segments", 8)
+        ]
+
+
+@pytest.mark.asyncio
+async def test_write_segments_invalid_path():
+    """Test that an empty path is rejected."""
+    with pytest.raises(ValueError):
+        await write_segments("", [(0, b"x")])
+
+
+@pytest.mark.asyncio
+async def test_list_extents_dense_file():
+    """Test that a fully written file is covered by its extents."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = _write(tmpdir, "dense.bin")
+
+        extents = await list_extents(path)
+
+        assert sum(length for _, length in extents) == len(DATA)
+        assert extents[0][0] == 0
+
+
+@pytest.mark.asyncio
+async def test_list_extents_sparse_file():
+    """Test that extents cover the data of a sparse file and skip nothing."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "sparse.bin")
+        size = 64 * 1024 * 1024
+        await write_segments(path, [(0, b"head"), (size - 4, b"tail")])
+
+        extents = await list_extents(path)
+        chunks = await read_ranges(path, extents)
+
+        assert all(length > 0 for _, length in extents)
+        assert extents == sorted(extents)
+        covered = b"".join(chunks)
+        assert covered.startswith(b"head") and covered.endswith(b"tail")
+        assert extents[-1][0] + extents[-1][1] == size
+
+
+@pytest.mark.asyncio
+async def test_list_extents_empty_and_missing():
+    """Test extents of an empty file and of a missing file."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        assert await list_extents(_write(tmpdir, "empty.bin", b"")) == []
+        with pytest.raises(FileNotFoundError):
+            await list_extents(os.path.join(tmpdir, "missing.bin"))
+
+
+@pytest.mark.asyncio
+async def test_create_filled():
+    """Test zero and seeded random fills and argument validation."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        zeros = _write(tmpdir, "zeros.bin")
+        await create_filled(zeros, 100_000)
+        with open(zeros, "rb") as f:
+            assert f.read() == bytes(100_000)
+
+        paths = [os.path.join(tmpdir, name) for name in ("a.bin", "b.bin", "c.bin")]
+        size = (1 << 20) + 3
+        for path, seed in zip(paths, (7, 7, 8)):
+            await create_filled(path, size, "random", seed=seed)
+        contents = []
+        for path in paths:
+            with open(path, "rb") as f:
+                contents.append(f.read())
+        a, b, c = contents
+        assert len(a) == size and a == b and a != c
+        assert a.count(0) < size // 100
+
+        await create_filled(zeros, 0, "random")
+        assert os.path.getsize(zeros) == 0
+        with pytest.raises(ValueError, match="Invalid fill"):
+            await create_filled(zeros, 10, "ones")
@@ -61,3 +61,36 @@ async def test_read_write_roundtrip():
     finally:
         if os.path.exists(test_file):
             os.unlink(test_file)
+
+
+@pytest.mark.asyncio
+async def test_read_file_code_point_widths():
+    """Test that text of every code point width is decoded exactly."""
+    samples = [
+        "",
+        "plain ascii\n",
+        "café über",
+        "日本語 ☃",
+        "emoji \U0001f600 and é and 日",
+        "é" * (1 << 20),
+    ]
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "text.txt")
+        for sample in samples:
+            with open(path, "wb") as f:
+                f.write(sample.encode("utf-8"))
+            content = await read_file(path)
+            assert content == sample
+            assert len(content) == len(sample)
+
+
+@pytest.mark.asyncio
+async def test_read_file_invalid_utf8():
+    """Test that invalid UTF-8 raises UnicodeDecodeError."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "binary.bin")
+        with open(path, "wb") as f:
+            f.write(b"valid \xff\xfe invalid")
+
+        with pytest.raises(UnicodeDecodeError):
+            await read_file(path)
@@ -0,0 +1,160 @@
+"""Test read-only mode."""
+
+import os
+import tempfile
+
+import pytest
+
+import rapfiles
+from rapfiles import ReadOnlyError, _rapfiles
+
+
+@pytest.fixture
+def tree():
+    with tempfile.TemporaryDirectory() as tmpdir:
+        prod = os.path.join(tmpdir, "prod")
+        scratch = os.path.join(tmpdir, "scratch")
+        os.mkdir(prod)
+        os.mkdir(scratch)
+        with open(os.path.join(prod, "table.csv"), "w") as f:
+            f.write("a,b\n")
+        try:
+            yield prod, scratch
+        finally:
+            rapfiles.set_read_only(False)
+
+
+@pytest.mark.asyncio
+async def test_mutations_blocked(tree):
+    """Test that every kind of mutating operation raises ReadOnlyError."""
+    prod, scratch = tree
+    table = os.path.join(prod, "table.csv")
+    rapfiles.set_read_only()
+    assert rapfiles.is_read_only()
+    assert rapfiles.writable_roots() == []
+
+    assert await rapfiles.read_file(table) == "a,b\n"
+    assert await rapfiles.list_dir(prod) == ["table.csv"]
+    async with rapfiles.open(table, "r") as f:
+        assert await f.read() == "a,b\n"
+
+    blocked = [
+        rapfiles.write_file(table, "x"),
+        rapfiles.append_file(table, "x"),
+        rapfiles.remove_file(table),
+        rapfiles.create_dir(os.path.join(prod, "new")),
+        rapfiles.remove_dir_all(prod),
+        rapfiles.move_file(table, os.path.join(prod, "moved.csv")),
+        rapfiles.copy_file(table, os.path.join(prod, "copy.csv")),
+        rapfiles.atomic_write_file(table, "x"),
+        rapfiles.write_files({table: b"x"}),
+    ]
+    for operation in blocked:
+        with pytest.raises(ReadOnlyError, match="read-only mode"):
+            await operation
+    for mode in ("w", "a", "r+", "wb"):
+        with pytest.raises(ReadOnlyError):
+            await rapfiles.open(table, mode)
+    with pytest.raises(PermissionError):
+        await rapfiles.remove_file(table)
+
+    assert os.listdir(prod) == ["table.csv"]
+    with open(table) as f:
+        assert f.read() == "a,b\n"
+
+
+@pytest.mark.asyncio
+async def test_writable_roots(tree):
+    """Test that paths below writable roots stay writable."""
+    prod, scratch = tree
+    table = os.path.join(prod, "table.csv")
+    rapfiles.set_read_only(writable_roots=[scratch])
+    assert rapfiles.writable_roots() == [scratch]
+
+    copy = os.path.join(scratch, "copy.csv")
+    await rapfiles.copy_file(table, copy)
+    await rapfiles.copy_files([(table, os.path.join(scratch, "again.csv"))])
+    async with rapfiles.open(copy, "a") as f:
+        await f.write("c,d\n")
+    assert await rapfiles.read_file(copy) == "a,b\nc,d\n"
+
+    with pytest.raises(ReadOnlyError):
+        await rapfiles.copy_file(copy, os.path.join(prod, "back.csv"))
+    with pytest.raises(ReadOnlyError):
+        await rapfiles.copy_files([(copy, os.path.join(prod, "back.csv"))])
+    with pytest.raises(ReadOnlyError):
+        await rapfiles.move_file(table, os.path.join(scratch, "stolen.csv"))
+    # ".." is resolved before the check
+    escape = os.path.join(scratch, "..", "prod", "table.csv")
+    with pytest.raises(ReadOnlyError):
+        await rapfiles.write_file(escape, "x")
+
+
+
+@pytest.mark.asyncio
+async def test_writable_root_symlink_escape(tree):
+    """Test that a symlink inside a writable root doesn't make its target writable."""
+    prod, scratch = tree
+    os.symlink(prod, os.path.join(scratch, "esc"))
+    os.symlink(os.path.join(prod, "table.csv"), os.path.join(scratch, "table.csv"))
+    rapfiles.set_read_only(writable_roots=[scratch])
+
+    with pytest.raises(ReadOnlyError):
+        await rapfiles.write_file(os.path.join(scratch, "esc", "new.csv"), "x")
+    with pytest.raises(ReadOnlyError):
+        await rapfiles.write_file(os.path.join(scratch, "table.csv"), "x")
+    with pytest.raises(ReadOnlyError):
+        await rapfiles.create_dir(os.path.join(scratch, "esc", "sub"))
+    assert os.listdir(prod) == ["table.csv"]
+
+    # A root given through a symlink covers the directory it leads to
+    rapfiles.set_read_only(writable_roots=[os.path.join(scratch, "esc")])
+    await rapfiles.write_file(os.path.join(prod, "new.csv"), "x")
+    assert sorted(os.listdir(prod)) == ["new.csv", "table.csv"]
+
+@pytest.mark.asyncio
+async def test_open_handles_blocked(tree):
+    """Test that handles opened before enabling read-only mode stop writing."""
+    prod, scratch = tree
+    path = os.path.join(prod, "log.txt")
+    async with rapfiles.open(path, "w") as f:
+        await f.write("before\n")
+        rapfiles.set_read_only()
+        with pytest.raises(ReadOnlyError):
+            await f.write("after\n")
+        with pytest.raises(ReadOnlyError):
+            await f.truncate(0)
+        with pytest.raises(ReadOnlyError):
+            await f.reopen()
+        assert await f.refresh() is False
+        rapfiles.set_read_only(False)
+    assert not rapfiles.is_read_only()
+    with open(path) as f:
+        assert f.read() == "before\n"
+
+
+@pytest.mark.asyncio
+async def test_cleanup_blocked(tree):
+    """Test that the cleanup registry does not remove protected paths."""
+    prod, scratch = tree
+    table = os.path.join(prod, "table.csv")
+    temp = os.path.join(scratch, "temp.bin")
+    with open(temp, "wb") as f:
+        f.write(b"temp")
+    rapfiles.register_cleanup(table, on_loop_shutdown=False)
+    rapfiles.register_cleanup(temp, on_loop_shutdown=False)
+    rapfiles.set_read_only(writable_roots=[scratch])
+
+    with pytest.raises(ReadOnlyError):
+        await rapfiles.run_cleanup()
+    assert os.path.exists(temp)
+
+    # The atexit handler reports protected paths and removes the others
+    errors = _rapfiles.run_cleanup()
+    assert errors == [f"Failed to remove {table}: rapfiles is in read-only mode"]
+    assert not os.path.exists(temp)
+    assert os.path.exists(table)
+    assert table in rapfiles.registered_cleanup()
+
+    rapfiles.set_read_only(False)
+    assert rapfiles.unregister_cleanup(table) is True
@@ -0,0 +1,100 @@
+"""Test operation recording and replay."""
+
+import pytest
+import os
+
+from rapfiles import (
+    copy_file,
+    create_dir_all,
+    list_dir,
+    read_file,
+    read_file_bytes,
+    write_file,
+)
+from rapfiles.recording import Recorder, load_records, replay
+from rapfiles.testing import TestFs
+
+
+@pytest.mark.asyncio
+async def test_recorder_captures_operations(rapfiles_fs):
+    """Test that operations are recorded with paths, sizes and outcomes."""
+    path = rapfiles_fs.path("data.txt")
+    missing = rapfiles_fs.path("missing.txt")
+
+    with Recorder() as recorder:
+        await write_file(path, "hello")
+        assert await read_file(path) == "hello"
+        with pytest.raises(FileNotFoundError):
+            await read_file(missing)
+
+    # Operations after stop() are not recorded
+    await read_file(path)
+
+    ops = [(r.op, r.paths, r.bytes, r.ok) for r in recorder.records]
+    assert ops == [
+        ("write_file", [path], 5, True),
+        ("read_file", [path], 5, True),
+        ("read_file", [missing], 0, False),
+    ]
+    assert "missing.txt" in recorder.records[2].error
+    starts = [r.start for r in recorder.records]
+    assert starts == sorted(starts)
+    assert all(r.duration >= 0 for r in recorder.records)
+
+
+def test_recorder_single_active_recording():
+    """Test that only one recording can be active at a time."""
+    with Recorder():
+        with pytest.raises(RuntimeError):
+            Recorder().start()
+
+
+@pytest.mark.asyncio
+async def test_save_load_and_replay(rapfiles_fs):
+    """Test replaying a saved trace against another root."""
+    source = await rapfiles_fs.make_dir("source")
+    target = await rapfiles_fs.make_dir("target")
+    outside = rapfiles_fs.path("outside.txt")
+
+    with Recorder() as recorder:
+        await create_dir_all(os.path.join(source, "out"))
+        await write_file(os.path.join(source, "out", "a.txt"), "x" * 10)
+        await copy_file(
+            os.path.join(source, "out", "a.txt"), os.path.join(source, "b.txt")
+        )
+        await list_dir(source)
+        await write_file(outside, "not replayed")
+
+    trace = rapfiles_fs.path("trace.jsonl")
+    await recorder.save(trace)
+    records = await load_records(trace)
+    assert [r["op"] for r in records] == [
+        "create_dir_all",
+        "write_file",
+        "copy_file",
+        "list_dir",
+        "write_file",
+    ]
+
+    summary = await replay(records, source, target)
+
+    assert summary == {"replayed": 4, "skipped": 1, "errors": 0, "mismatches": 0}
+    assert await read_file_bytes(os.path.join(target, "b.txt")) == b"x" * 10
+    assert sorted(await list_dir(target)) == ["b.txt", "out"]
+
+
+@pytest.mark.asyncio
+async def test_replay_preserve_timing_and_mismatches():
+    """Test timed replay and reporting of outcome mismatches."""
+    async with TestFs() as fs:
+        source = await fs.make_dir("source")
+        target = await fs.make_dir("target")
+        with Recorder() as recorder:
+            await write_file(os.path.join(source, "a.txt"), "abc")
+            await read_file(os.path.join(source, "a.txt"))
+
+        # Reading before the write exists makes the replay diverge
+        records = [recorder.records[1]]
+        summary = await replay(records, source, target, preserve_timing=True)
+
+        assert summary == {"replayed": 1, "skipped": 0, "errors": 1, "mismatches": 1}
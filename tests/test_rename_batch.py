@@ -0,0 +1,110 @@
+"""Test batch renames with rename_many() and rename_pattern()."""
+
+import os
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+def make_files(directory, names):
+    for name in names:
+        with open(os.path.join(directory, name), "w") as f:
+            f.write(name)
+
+
+def contents(directory):
+    result = {}
+    for name in os.listdir(directory):
+        with open(os.path.join(directory, name)) as f:
+            result[name] = f.read()
+    return result
+
+
+@pytest.mark.asyncio
+async def test_rename_many_chains_and_swaps():
+    """Test that chains and swaps of names are renamed correctly."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        make_files(tmpdir, ["a", "b", "c", "x", "y"])
+
+        def path(name):
+            return os.path.join(tmpdir, name)
+
+        await rapfiles.rename_many(
+            [
+                (path("a"), path("b")),
+                (path("b"), path("c")),
+                (path("c"), path("d")),
+                (path("x"), path("y")),
+                (path("y"), path("x")),
+            ]
+        )
+        assert contents(tmpdir) == {"b": "a", "c": "b", "d": "c", "x": "y", "y": "x"}
+
+
+@pytest.mark.asyncio
+async def test_rename_many_collisions():
+    """Test that collisions are found before anything is renamed."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        make_files(tmpdir, ["a", "b", "taken"])
+
+        def path(name):
+            return os.path.join(tmpdir, name)
+
+        before = contents(tmpdir)
+        with pytest.raises(ValueError, match="both rename to"):
+            await rapfiles.rename_many([(path("a"), path("n")), (path("b"), path("n"))])
+        with pytest.raises(FileExistsError, match="taken"):
+            await rapfiles.rename_many(
+                [(path("a"), path("a2")), (path("b"), path("taken"))]
+            )
+        with pytest.raises(FileNotFoundError):
+            await rapfiles.rename_many(
+                [(path("a"), path("a2")), (path("missing"), path("m2"))]
+            )
+        assert contents(tmpdir) == before
+
+
+@pytest.mark.asyncio
+async def test_rename_many_rollback():
+    """Test that completed renames are undone when one fails."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        make_files(tmpdir, [f"{i}.txt" for i in range(50)])
+        before = contents(tmpdir)
+        pairs = [
+            (os.path.join(tmpdir, f"{i}.txt"), os.path.join(tmpdir, f"{i + 1}.txt"))
+            for i in range(50)
+        ]
+        # The parent directory of the last destination does not exist
+        pairs[-1] = (pairs[-1][0], os.path.join(tmpdir, "missing", "49.txt"))
+        with pytest.raises(FileNotFoundError, match="49.txt"):
+            await rapfiles.rename_many(pairs)
+        assert contents(tmpdir) == before
+
+
+@pytest.mark.asyncio
+async def test_rename_pattern():
+    """Test pattern matching and template fields."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        make_files(tmpdir, ["IMG_b.JPG", "IMG_a.JPG", "notes.txt", "scan-2024-01.tif"])
+        renamed = await rapfiles.rename_pattern(tmpdir, "IMG_*.JPG", "{n:03}_{1}.jpg")
+        assert renamed == [
+            (os.path.join(tmpdir, "IMG_a.JPG"), os.path.join(tmpdir, "001_a.jpg")),
+            (os.path.join(tmpdir, "IMG_b.JPG"), os.path.join(tmpdir, "002_b.jpg")),
+        ]
+        await rapfiles.rename_pattern(tmpdir, "scan-*-??.tif", "{2}{3}-{1} {{{0}}}")
+        assert contents(tmpdir) == {
+            "001_a.jpg": "IMG_a.JPG",
+            "002_b.jpg": "IMG_b.JPG",
+            "notes.txt": "notes.txt",
+            "01-2024 {scan-2024-01.tif}": "scan-2024-01.tif",
+        }
+        assert await rapfiles.rename_pattern(tmpdir, "*.png", "{0}.bak") == []
+
+        with pytest.raises(ValueError, match="both rename to"):
+            await rapfiles.rename_pattern(tmpdir, "*.jpg", "same.jpg")
+        with pytest.raises(ValueError, match="wildcards"):
+            await rapfiles.rename_pattern(tmpdir, "*.txt", "{2}")
+        with pytest.raises(ValueError, match="Invalid template"):
+            await rapfiles.rename_pattern(tmpdir, "*", "{name}")
@@ -0,0 +1,49 @@
+"""Test runtime introspection."""
+
+import os
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+def test_runtime_info_shape():
+    """Test that runtime_info reports the documented keys."""
+    info = rapfiles.runtime_info()
+
+    assert info["runtime"] == "tokio"
+    assert info["worker_threads"] >= 1
+    for key in ("alive_tasks", "queued_tasks", "open_files"):
+        assert isinstance(info[key], int) and info[key] >= 0
+    assert set(info["caches"]) == {
+        "faults",
+        "slowdowns",
+        "cleanup_paths",
+        "recorded_ops",
+    }
+
+
+@pytest.mark.asyncio
+async def test_runtime_info_counts_open_files():
+    """Test that open AsyncFile handles are counted until released."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "file.txt")
+        await rapfiles.write_file(path, "data")
+        before = rapfiles.runtime_info()["open_files"]
+
+        async with rapfiles.open(path, "r") as f:
+            assert rapfiles.runtime_info()["open_files"] == before + 1
+            await f.read()
+        assert rapfiles.runtime_info()["open_files"] == before
+
+
+def test_runtime_info_counts_cleanup_paths():
+    """Test that registered cleanup paths show up in the cache sizes."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        before = rapfiles.runtime_info()["caches"]["cleanup_paths"]
+        path = rapfiles.register_cleanup(os.path.join(tmpdir, "scratch"))
+        try:
+            assert rapfiles.runtime_info()["caches"]["cleanup_paths"] == before + 1
+        finally:
+            rapfiles.unregister_cleanup(path)
@@ -0,0 +1,58 @@
+"""Test scratch directory selection."""
+
+import os
+import sys
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+def is_tmpfs(path):
+    """Return whether path is on a tmpfs mount, from /proc/mounts."""
+    path = os.path.realpath(path)
+    best, fs_type = "", None
+    with open("/proc/mounts") as f:
+        for line in f:
+            mount, kind = line.split()[1:3]
+            inside = (path + "/").startswith(mount.rstrip("/") + "/")
+            if inside and len(mount) > len(best):
+                best, fs_type = mount, kind
+    return fs_type in ("tmpfs", "ramfs")
+
+
+@pytest.mark.asyncio
+async def test_candidate_order_and_free_space():
+    """Test that unsuitable candidates are skipped and order breaks ties."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        first, second = os.path.join(tmpdir, "a"), os.path.join(tmpdir, "b")
+        os.mkdir(first)
+        os.mkdir(second)
+        file_path = os.path.join(tmpdir, "file")
+        open(file_path, "w").close()
+        missing = os.path.join(tmpdir, "missing")
+
+        candidates = [missing, file_path, first, second]
+        assert await rapfiles.best_scratch_dir(candidates=candidates) == first
+        assert await rapfiles.best_scratch_dir(1 << 62, candidates) is None
+        assert await rapfiles.best_scratch_dir(candidates=[]) is None
+        with pytest.raises(ValueError):
+            await rapfiles.best_scratch_dir(candidates=["bad\0path"])
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(sys.platform != "linux", reason="tmpfs detection is Linux only")
+async def test_prefers_memory_backed():
+    """Test that a tmpfs candidate wins over an earlier disk candidate."""
+    if not os.path.isdir("/dev/shm") or not os.access("/dev/shm", os.W_OK):
+        pytest.skip("no writable /dev/shm")
+    if not is_tmpfs("/dev/shm"):
+        pytest.skip("/dev/shm is not a tmpfs")
+    disk = os.getcwd()
+    if is_tmpfs(disk) or not os.access(disk, os.W_OK):
+        pytest.skip("no writable disk-backed directory")
+
+    chosen = await rapfiles.best_scratch_dir(candidates=[disk, "/dev/shm"])
+    assert chosen == "/dev/shm"
+    assert await rapfiles.best_scratch_dir() is not None
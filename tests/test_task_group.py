@@ -0,0 +1,98 @@
+"""Test TaskGroup structured concurrency."""
+
+import asyncio
+import os
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+@pytest.mark.asyncio
+async def test_results_and_limit():
+    """Test that submitted operations complete within the concurrency limit."""
+    running = 0
+    peak = 0
+
+    async def tracked(path):
+        nonlocal running, peak
+        running += 1
+        peak = max(peak, running)
+        try:
+            await asyncio.sleep(0.01)
+            return await rapfiles.read_file(path)
+        finally:
+            running -= 1
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        paths = [os.path.join(tmpdir, f"{i}.txt") for i in range(10)]
+        async with rapfiles.TaskGroup() as group:
+            for i, path in enumerate(paths):
+                group.submit(rapfiles.write_file(path, str(i)))
+
+        async with rapfiles.TaskGroup(max_concurrency=3) as group:
+            tasks = [group.submit(tracked(path)) for path in paths]
+    assert [task.result() for task in tasks] == [str(i) for i in range(10)]
+    assert peak == 3
+
+
+@pytest.mark.asyncio
+async def test_failure_cancels_others():
+    """Test that a failing operation cancels the rest of the group."""
+    finished = []
+
+    async def slow(name):
+        await asyncio.sleep(1)
+        finished.append(name)
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        missing = os.path.join(tmpdir, "missing.txt")
+        with pytest.raises(FileNotFoundError):
+            async with rapfiles.TaskGroup(max_concurrency=2) as group:
+                failing = group.submit(rapfiles.read_file(missing))
+                tasks = [group.submit(slow(name)) for name in "abc"]
+                await asyncio.sleep(0)
+        with pytest.raises(RuntimeError, match="shutting down"):
+            group.submit(rapfiles.read_file(missing))
+    assert finished == []
+    assert all(task.cancelled() for task in tasks)
+    assert isinstance(failing.exception(), FileNotFoundError)
+
+
+@pytest.mark.asyncio
+async def test_body_error_cancels_tasks():
+    """Test that an error in the block cancels submitted operations."""
+    group = rapfiles.TaskGroup()
+    with pytest.raises(KeyError):
+        async with group:
+            task = group.submit(asyncio.sleep(1))
+            raise KeyError("body")
+    assert task.cancelled()
+    with pytest.raises(RuntimeError, match="not active"):
+        group.submit(asyncio.sleep(0))
+    with pytest.raises(RuntimeError, match="already been entered"):
+        async with group:
+            pass
+
+
+@pytest.mark.asyncio
+async def test_nested_submissions():
+    """Test that tasks can submit more tasks before the group exits."""
+    results = []
+
+    async with rapfiles.TaskGroup(max_concurrency=1) as group:
+
+        async def spawn(depth):
+            results.append(depth)
+            if depth < 3:
+                group.submit(spawn(depth + 1))
+
+        group.submit(spawn(0))
+    assert results == [0, 1, 2, 3]
+
+
+def test_invalid_max_concurrency():
+    """Test that max_concurrency must be positive."""
+    with pytest.raises(ValueError, match="Must be at least 1"):
+        rapfiles.TaskGroup(max_concurrency=0)
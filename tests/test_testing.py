@@ -0,0 +1,208 @@
+"""Test the TestFs helper and the rapfiles_fs pytest fixture."""
+
+import pytest
+import asyncio
+import errno
+import os
+import time
+
+from rapfiles import (
+    copy_file,
+    list_dir,
+    open as rap_open,
+    read_file,
+    read_file_bytes,
+    walk_dir,
+    write_file,
+)
+from rapfiles.testing import (
+    TestFs,
+    clear_faults,
+    clear_latency,
+    inject_fault,
+    inject_latency,
+)
+
+
+@pytest.mark.asyncio
+async def test_rapfiles_fs_fixture(rapfiles_fs):
+    """Test that the fixture provides an empty scratch directory."""
+    assert os.path.isdir(rapfiles_fs.root)
+    assert await list_dir(rapfiles_fs.root) == []
+
+    path = await rapfiles_fs.make_file(os.path.join("a", "b", "c.txt"), "hello")
+
+    assert path == rapfiles_fs.path("a", "b", "c.txt")
+    assert await read_file(path) == "hello"
+
+
+@pytest.mark.asyncio
+async def test_make_tree():
+    """Test building nested trees with text, bytes and empty directories."""
+    async with TestFs() as fs:
+        base = await fs.make_tree(
+            {
+                "README.md": "# Demo",
+                "src": {"main.py": "print('hi')", "empty": {}},
+                "blob.bin": b"\x00\x01",
+            },
+            "project",
+        )
+
+        assert base == fs.path("project")
+        results = await walk_dir(base, sorted=True)
+        assert [os.path.relpath(p, base) for p, _ in results] == [
+            "README.md",
+            "blob.bin",
+            "src",
+            os.path.join("src", "empty"),
+            os.path.join("src", "main.py"),
+        ]
+        assert await read_file_bytes(fs.path("project", "blob.bin")) == b"\x00\x01"
+
+
+@pytest.mark.asyncio
+async def test_testfs_teardown(tmp_path):
+    """Test that owned roots are removed and borrowed roots are kept."""
+    async with TestFs() as fs:
+        await fs.make_file("file.txt", "data")
+        root = fs.root
+    assert not os.path.exists(root)
+
+    fs = TestFs()
+    await fs.make_dir("nested")
+    fs.cleanup()
+    assert not os.path.exists(fs.root)
+
+    async with TestFs(str(tmp_path)) as fs:
+        await fs.make_file("kept.txt")
+    assert os.path.exists(tmp_path / "kept.txt")
+
+
+@pytest.mark.asyncio
+async def test_inject_fault_matching_paths(rapfiles_fs):
+    """Test that faults only hit operations on matching paths."""
+    target = await rapfiles_fs.make_file(os.path.join("data", "a.csv"), "1,2")
+    other = await rapfiles_fs.make_file("notes.txt", "hello")
+
+    with inject_fault("*/data/*.csv", errno.EACCES):
+        with pytest.raises(PermissionError) as exc_info:
+            await read_file(target)
+        assert exc_info.value.errno == errno.EACCES
+        assert await read_file(other) == "hello"
+
+        # Either side of a two-path operation can trigger the fault
+        with pytest.raises(PermissionError):
+            await copy_file(other, rapfiles_fs.path("data", "b.csv"))
+
+    assert await read_file(target) == "1,2"
+
+
+@pytest.mark.asyncio
+async def test_inject_fault_ops_filter(rapfiles_fs):
+    """Test restricting faults to specific operations, including handles."""
+    path = await rapfiles_fs.make_file("log.txt", "start")
+
+    fault = inject_fault("*log.txt", errno.ENOSPC, ops=["write_file", "write"])
+    try:
+        assert await read_file(path) == "start"
+        with pytest.raises(OSError) as exc_info:
+            await write_file(path, "more")
+        assert exc_info.value.errno == errno.ENOSPC
+
+        async with rap_open(path, "a") as f:
+            with pytest.raises(OSError):
+                await f.write("more")
+    finally:
+        assert fault.remove() is True
+    assert fault.remove() is False
+
+
+@pytest.mark.asyncio
+async def test_inject_fault_probability_is_seeded(rapfiles_fs):
+    """Test that probabilistic faults repeat for the same seed."""
+    path = await rapfiles_fs.make_file("flaky.txt", "x")
+
+    async def outcomes(seed):
+        results = []
+        with inject_fault(path, errno.EIO, probability=0.5, seed=seed):
+            for _ in range(32):
+                try:
+                    await read_file(path)
+                    results.append(True)
+                except OSError:
+                    results.append(False)
+        return results
+
+    first = await outcomes(7)
+    assert first == await outcomes(7)
+    assert True in first and False in first
+
+
+def test_inject_fault_validation():
+    """Test argument validation and clearing all faults."""
+    with pytest.raises(ValueError):
+        inject_fault("*", errno.EIO, probability=1.5)
+    with pytest.raises(ValueError):
+        inject_fault("*", 0)
+
+    fault = inject_fault("*never*")
+    clear_faults()
+    assert fault.remove() is False
+
+
+@pytest.mark.asyncio
+async def test_inject_latency(rapfiles_fs):
+    """Test that matching operations are delayed without blocking the loop."""
+    slow = await rapfiles_fs.make_file(os.path.join("nfs", "slow.txt"), "x")
+    fast = await rapfiles_fs.make_file("fast.txt", "x")
+
+    with inject_latency("*/nfs/*", latency=0.2):
+        start = time.monotonic()
+        assert await read_file(fast) == "x"
+        assert time.monotonic() - start < 0.2
+
+        with pytest.raises(asyncio.TimeoutError):
+            await asyncio.wait_for(read_file(slow), 0.05)
+
+        start = time.monotonic()
+        results = await asyncio.gather(*(read_file(slow) for _ in range(5)))
+        elapsed = time.monotonic() - start
+        assert results == ["x"] * 5
+        assert 0.2 <= elapsed < 0.9
+
+
+@pytest.mark.asyncio
+async def test_inject_latency_throughput(rapfiles_fs):
+    """Test that throughput caps scale with the bytes transferred."""
+    small = await rapfiles_fs.make_file("small.bin", b"x" * 100)
+    large = await rapfiles_fs.make_file("large.bin", b"x" * 30_000)
+
+    slowdown = inject_latency(bytes_per_second=100_000, ops=["read_file_bytes"])
+    try:
+        start = time.monotonic()
+        await read_file_bytes(small)
+        assert time.monotonic() - start < 0.2
+
+        start = time.monotonic()
+        await read_file_bytes(large)
+        assert time.monotonic() - start >= 0.3
+
+        # Other operations are not throttled
+        start = time.monotonic()
+        await read_file(large)
+        assert time.monotonic() - start < 0.2
+    finally:
+        slowdown.remove()
+
+
+def test_inject_latency_validation():
+    """Test argument validation and clearing all slowdowns."""
+    with pytest.raises(ValueError):
+        inject_latency(latency=-1)
+    with pytest.raises(ValueError):
+        inject_latency(bytes_per_second=0)
+
+    slowdown = inject_latency("*never*", latency=1.0)
+    clear_latency()
+    assert slowdown.remove() is False
@@ -32,11 +32,10 @@ async def test_simple_iteration(mode):
         await file.seek(0)
         counter = 1
 
-        # Note: rapfiles doesn't support async iteration yet
-        # The new iteration pattern would be:
-        # async for line in file:
-        #     assert line.strip() == "line " + str(counter)
-        #     counter += 1
+        # The new iteration pattern:
+        async for line in file:
+            assert line.strip() == "line " + str(counter)
+            counter += 1
 
 
 @pytest.mark.parametrize("mode", ["r", "r+", "a+"])
@@ -64,8 +63,15 @@ async def test_simple_readlines(mode):
 
 @pytest.mark.parametrize("mode", ["r+", "w", "a"])
 async def test_simple_flush(mode, tmp_path):
-    """Test flushing to a file - flush not yet implemented in rapfiles."""
-    pytest.skip("flush() not yet implemented in rapfiles")
+    """Test flushing to a file."""
+    filename = tmp_path / "file.txt"
+    filename.write_text("")
+
+    async with rapfiles.open(str(filename), mode=mode) as file:
+        await file.write("0")
+        await file.flush()
+        # The data is visible to other readers before the file is closed
+        assert filename.read_text() == "0"
 
 
 @pytest.mark.parametrize("mode", ["r", "r+", "a+"])
@@ -128,10 +134,6 @@ async def test_staggered_read(mode):
 @pytest.mark.parametrize("mode", ["r", "r+", "a+"])
 async def test_simple_seek(mode, tmp_path):
     """Test seeking and then reading."""
-    # Skip r+ mode due to known issue with read operations in r+ mode
-    if mode == "r+":
-        pytest.skip("r+ mode read operations have known issues in rapfiles")
-
     filename = "bigfile.bin"
     content = "0123456789" * 4 * io.DEFAULT_BUFFER_SIZE
 
@@ -161,8 +163,15 @@ async def test_simple_close(mode, tmp_path):
 
 @pytest.mark.parametrize("mode", ["r+", "w", "a+"])
 async def test_simple_truncate(mode, tmp_path):
-    """Test truncating files - not yet implemented in rapfiles."""
-    pytest.skip("truncate not yet implemented in rapfiles")
+    """Test truncating files."""
+    full_file = tmp_path / "file.txt"
+    full_file.write_text("line 1\n")
+
+    async with rapfiles.open(str(full_file), mode=mode) as file:
+        await file.seek(0)  # Append mode starts at the end.
+        await file.truncate()
+
+    assert full_file.read_text() == ""
 
 
 @pytest.mark.parametrize("mode", ["w", "r+", "w+", "a", "a+"])
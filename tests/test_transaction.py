@@ -0,0 +1,162 @@
+"""Test multi-file transactions."""
+
+import gc
+import os
+import tempfile
+import warnings
+
+import pytest
+
+import rapfiles
+
+
+def make_files(directory, files):
+    for name, text in files.items():
+        with open(os.path.join(directory, name), "w") as f:
+            f.write(text)
+
+
+def contents(directory):
+    result = {}
+    for name in os.listdir(directory):
+        with open(os.path.join(directory, name)) as f:
+            result[name] = f.read()
+    return result
+
+
+@pytest.mark.asyncio
+async def test_commit():
+    """Test that staged changes are applied in order on exit."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        make_files(tmpdir, {"a": "old a", "b": "old b", "c": "old c"})
+
+        def path(name):
+            return os.path.join(tmpdir, name)
+
+        async with rapfiles.transaction() as txn:
+            await txn.write(path("a"), "new a")
+            await txn.write(path("new"), b"created")
+            txn.rename(path("a"), path("b"))
+            txn.remove(path("c"))
+            assert txn.pending == 4
+            # Nothing changes before the commit
+            assert contents(tmpdir).items() >= {"a": "old a", "c": "old c"}.items()
+        assert txn.status == "committed"
+        assert contents(tmpdir) == {"b": "new a", "new": "created"}
+
+        with pytest.raises(ValueError, match="already committed"):
+            await txn.write(path("a"), "late")
+
+
+@pytest.mark.asyncio
+async def test_rollback():
+    """Test that a failing block or commit leaves the files unchanged."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        make_files(tmpdir, {"a": "old a", "b": "old b"})
+
+        def path(name):
+            return os.path.join(tmpdir, name)
+
+        with pytest.raises(KeyError):
+            async with rapfiles.transaction() as txn:
+                await txn.write(path("a"), "new a")
+                raise KeyError("abort")
+        assert txn.status == "rolled back"
+        assert contents(tmpdir) == {"a": "old a", "b": "old b"}
+
+        # The last rename fails after the others were applied
+        txn = rapfiles.transaction()
+        await txn.write(path("a"), "new a")
+        txn.remove(path("b"))
+        txn.rename(path("a"), path(os.path.join("missing", "a")))
+        with pytest.raises(FileNotFoundError):
+            await txn.commit()
+        assert txn.status == "rolled back"
+        assert contents(tmpdir) == {"a": "old a", "b": "old b"}
+
+        # Missing sources are found before anything is renamed
+        txn = rapfiles.transaction()
+        await txn.write(path("b"), "new b")
+        txn.remove(path("gone"))
+        with pytest.raises(FileNotFoundError, match="gone"):
+            await txn.commit()
+        assert contents(tmpdir) == {"a": "old a", "b": "old b"}
+
+
+@pytest.mark.asyncio
+async def test_journal_recovery():
+    """Test that an interrupted commit is completed or rolled back."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        data = os.path.join(tmpdir, "data")
+        os.mkdir(data)
+        journal = os.path.join(tmpdir, "journal")
+        assert await rapfiles.recover_transaction(journal) is None
+
+        async with rapfiles.transaction(journal=journal) as txn:
+            await txn.write(os.path.join(data, "a"), "committed")
+        assert not os.path.exists(journal)
+        assert contents(data) == {"a": "committed"}
+
+        # A crash after the old file was moved aside and the new one put in
+        # place, with only the first step noted
+        make_files(data, {".a.txn": "new", "a": "old"})
+        temp, target = os.path.join(data, ".a.txn"), os.path.join(data, "a")
+        backup = os.path.join(data, ".a.bak")
+        with open(journal, "w") as f:
+            f.write(
+                "rapfiles transaction journal 1\n"
+                f"temp {temp}\n"
+                f"backup {target} {backup}\n"
+                f"rename {temp} {target}\n"
+                "done 0\n"
+            )
+        os.rename(target, backup)
+        os.rename(temp, target)
+        with pytest.raises(FileExistsError, match="recover_transaction"):
+            async with rapfiles.transaction(journal=journal) as txn:
+                await txn.write(target, "blocked")
+        assert await rapfiles.recover_transaction(journal) is False
+        assert contents(data) == {"a": "old"}
+        assert not os.path.exists(journal)
+
+        # A crash after the commit was noted, before the cleanup
+        make_files(data, {".a.bak": "old"})
+        with open(journal, "w") as f:
+            f.write(
+                "rapfiles transaction journal 1\n"
+                f"backup {target} {backup}\n"
+                "done 0\n"
+                "committed\n"
+            )
+        assert await rapfiles.recover_transaction(journal) is True
+        assert contents(data) == {"a": "old"}
+
+        with open(journal, "w") as f:
+            f.write("not a journal\n")
+        with pytest.raises(ValueError, match="missing header"):
+            await rapfiles.recover_transaction(journal)
+
+
+@pytest.mark.asyncio
+async def test_dropped_transaction_removes_staged_files():
+    """Test that an unfinished transaction cleans up when collected."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        make_files(tmpdir, {"a": "old a"})
+        txn = rapfiles.transaction()
+        await txn.write(os.path.join(tmpdir, "a"), "new a")
+        await txn.write(os.path.join(tmpdir, "b"), "new b")
+        assert any(name.endswith(".txn") for name in os.listdir(tmpdir))
+
+        with warnings.catch_warnings(record=True) as caught:
+            warnings.simplefilter("always")
+            del txn
+            gc.collect()
+        leaks = [w for w in caught if issubclass(w.category, ResourceWarning)]
+        assert any("2 staged change" in str(w.message) for w in leaks)
+        assert contents(tmpdir) == {"a": "old a"}
+
+
+def test_direct_instantiation():
+    """Test that Transaction is created through transaction()."""
+    with pytest.raises(ValueError, match="cannot be instantiated"):
+        rapfiles.Transaction()
@@ -0,0 +1,199 @@
+"""Test directory watching with watch()."""
+
+import asyncio
+import os
+import tempfile
+
+import pytest
+
+import rapfiles
+
+
+async def next_events(watcher, count):
+    events = []
+    for _ in range(count):
+        event = await asyncio.wait_for(watcher.__anext__(), 5)
+        events.append((event.kind, event.path, event.is_dir))
+    return events
+
+
+@pytest.mark.asyncio
+async def test_existing_entries_first():
+    """Test that current contents are emitted as created events first."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        sub = os.path.join(tmpdir, "sub")
+        os.mkdir(sub)
+        for path in (os.path.join(tmpdir, "a.txt"), os.path.join(sub, "b.txt")):
+            with open(path, "w") as f:
+                f.write("x")
+
+        watcher = await rapfiles.watch(tmpdir, poll_interval=0.01)
+        assert watcher.path == tmpdir
+        # Created before iterating, still reported after the existing entries
+        new = os.path.join(tmpdir, "new.txt")
+        with open(new, "w") as f:
+            f.write("x")
+        existing = [await watcher.__anext__() for _ in range(3)]
+        assert all(event.existing for event in existing)
+        assert [(e.kind, e.path, e.is_dir) for e in existing] == [
+            ("created", os.path.join(tmpdir, "a.txt"), False),
+            ("created", sub, True),
+            ("created", os.path.join(sub, "b.txt"), False),
+        ]
+        event = await asyncio.wait_for(watcher.__anext__(), 5)
+        assert (event.kind, event.path, event.existing) == ("created", new, False)
+        watcher.close()
+        assert [event async for event in watcher] == []
+
+
+@pytest.mark.asyncio
+async def test_live_events():
+    """Test created, modified and deleted events without initial state."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        path = os.path.join(tmpdir, "a.txt")
+        with open(path, "w") as f:
+            f.write("x")
+        watcher = await rapfiles.watch(tmpdir, emit_existing=False, poll_interval=0.01)
+
+        with open(path, "a") as f:
+            f.write("more")
+        assert await next_events(watcher, 1) == [("modified", path, False)]
+
+        sub = os.path.join(tmpdir, "sub")
+        os.makedirs(os.path.join(sub, "deep"))
+        assert await next_events(watcher, 2) == [
+            ("created", sub, True),
+            ("created", os.path.join(sub, "deep"), True),
+        ]
+
+        os.rmdir(os.path.join(sub, "deep"))
+        os.rmdir(sub)
+        os.remove(path)
+        assert sorted(await next_events(watcher, 3)) == [
+            ("deleted", path, False),
+            ("deleted", sub, True),
+            ("deleted", os.path.join(sub, "deep"), True),
+        ]
+        watcher.close()
+
+
+@pytest.mark.asyncio
+async def test_not_recursive():
+    """Test that recursive=False ignores changes below subdirectories."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        sub = os.path.join(tmpdir, "sub")
+        os.mkdir(sub)
+        watcher = await rapfiles.watch(tmpdir, recursive=False, poll_interval=0.01)
+        assert await next_events(watcher, 1) == [("created", sub, True)]
+        with open(os.path.join(sub, "hidden.txt"), "w") as f:
+            f.write("x")
+        top = os.path.join(tmpdir, "top.txt")
+        with open(top, "w") as f:
+            f.write("x")
+        assert await next_events(watcher, 1) == [("created", top, False)]
+        watcher.close()
+
+
+@pytest.mark.asyncio
+async def test_emit_when_stable():
+    """Test that files are only reported once they stop changing."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        old = os.path.join(tmpdir, "old.txt")
+        sub = os.path.join(tmpdir, "sub")
+        upload = os.path.join(tmpdir, "upload.bin")
+        temp = os.path.join(tmpdir, "temp.bin")
+        with open(old, "w") as f:
+            f.write("x")
+        os.mkdir(sub)
+        watcher = await rapfiles.watch(tmpdir, emit_when_stable=0.2, poll_interval=0.01)
+        # Directories are not held back
+        assert await next_events(watcher, 1) == [("created", sub, True)]
+
+        async def copy_in():
+            # Removed before it settles
+            with open(temp, "wb") as f:
+                f.write(b"x")
+            await asyncio.sleep(0.05)
+            os.remove(temp)
+            for _ in range(5):
+                with open(upload, "ab") as f:
+                    f.write(b"x" * 1000)
+                await asyncio.sleep(0.05)
+
+        task = asyncio.ensure_future(copy_in())
+        event = await asyncio.wait_for(watcher.__anext__(), 5)
+        assert (event.kind, event.path, event.existing) == ("created", old, True)
+        event = await asyncio.wait_for(watcher.__anext__(), 5)
+        assert (event.kind, event.path, event.existing) == ("created", upload, False)
+        assert task.done()
+        assert os.path.getsize(upload) == 5000
+
+        os.remove(upload)
+        assert await next_events(watcher, 1) == [("deleted", upload, False)]
+        watcher.close()
+        with pytest.raises(ValueError, match="emit_when_stable"):
+            await rapfiles.watch(tmpdir, emit_when_stable=0)
+
+
+@pytest.mark.asyncio
+async def test_journal_resume():
+    """Test replaying unprocessed events and changes made while stopped."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        tree = os.path.join(tmpdir, "tree")
+        journal = os.path.join(tmpdir, "tree.journal")
+        os.mkdir(tree)
+        a, b, c = (os.path.join(tree, name) for name in ("a", "b", "c"))
+        with open(a, "w") as f:
+            f.write("x")
+
+        watcher = await rapfiles.watch(tree, journal=journal, poll_interval=0.01)
+        first = await watcher.__anext__()
+        assert (first.kind, first.path, first.existing) == ("created", a, True)
+        with open(b, "w") as f:
+            f.write("x")
+        second = await asyncio.wait_for(watcher.__anext__(), 5)
+        assert (second.kind, second.path) == ("created", b)
+        assert [first.token, second.token] == ["1", "2"]
+        # Stopped after processing only the first event
+        watcher.close()
+        os.remove(a)
+        with open(c, "w") as f:
+            f.write("x")
+
+        watcher = await rapfiles.watch(
+            tree, journal=journal, resume_token=first.token, poll_interval=0.01
+        )
+        events = [await watcher.__anext__() for _ in range(3)]
+        assert [(e.kind, e.path, e.token) for e in events] == [
+            ("created", b, "2"),
+            ("deleted", a, "3"),
+            ("created", c, "4"),
+        ]
+        watcher.close()
+
+        # Nothing left to replay, and older tokens were dropped from the journal
+        watcher = await rapfiles.watch(
+            tree, journal=journal, resume_token="4", poll_interval=0.01
+        )
+        with open(a, "w") as f:
+            f.write("x")
+        assert await next_events(watcher, 1) == [("created", a, False)]
+        watcher.close()
+        with pytest.raises(ValueError, match="resume_token"):
+            await rapfiles.watch(tree, journal=journal, resume_token="1")
+        with pytest.raises(ValueError, match="does not belong"):
+            await rapfiles.watch(tmpdir, journal=journal)
+
+
+@pytest.mark.asyncio
+async def test_invalid_arguments():
+    """Test argument validation."""
+    with tempfile.TemporaryDirectory() as tmpdir:
+        with pytest.raises(FileNotFoundError):
+            await rapfiles.watch(os.path.join(tmpdir, "missing"))
+        with pytest.raises(ValueError, match="poll_interval"):
+            await rapfiles.watch(tmpdir, poll_interval=0)
+        with pytest.raises(ValueError, match="requires a journal"):
+            await rapfiles.watch(tmpdir, resume_token="1")
+    with pytest.raises(ValueError, match="cannot be instantiated"):
+        rapfiles.DirWatcher()